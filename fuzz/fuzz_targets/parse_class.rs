@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// a malformed class file must be rejected with an error, never with a panic,
+// and a well-formed one must be written back unchanged
+fuzz_target!(|data: &[u8]| {
+  if jvmcraft::parse_class_file(data).is_ok() {
+    assert_eq!(jvmcraft::rewrite_class_file(data).unwrap(), data);
+  }
+});
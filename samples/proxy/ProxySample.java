@@ -0,0 +1,36 @@
+package proxy;
+
+import java.lang.annotation.Retention;
+import java.lang.annotation.RetentionPolicy;
+import java.lang.reflect.InvocationHandler;
+import java.lang.reflect.Proxy;
+
+public class ProxySample {
+  interface Greeter {
+    String greet(String name);
+  }
+
+  @Retention(RetentionPolicy.RUNTIME)
+  @interface Version {
+    String value();
+
+    int patch() default 0;
+  }
+
+  @Version("1.2")
+  static class Versioned {}
+
+  public static void main(String[] args) {
+    InvocationHandler handler = (proxy, method, arguments) -> method.getName() + " " + arguments[0];
+
+    Greeter greeter = (Greeter) Proxy.newProxyInstance(
+        ProxySample.class.getClassLoader(), new Class<?>[] {Greeter.class}, handler);
+
+    System.out.println(greeter.greet("world"));
+
+    // annotation instances are proxies too
+    Version version = Versioned.class.getAnnotation(Version.class);
+
+    System.out.println(version.value() + "." + version.patch());
+  }
+}
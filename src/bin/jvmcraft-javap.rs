@@ -0,0 +1,40 @@
+use clap::Parser;
+use color_eyre::eyre::{eyre, Result};
+use jvmcraft::ClassPath;
+
+use std::path::Path;
+
+/// Disassemble class files, like `javap -v -p`
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+  /// Directories, JAR files and jimage files (e.g. `$JAVA_HOME/lib/modules`) to search
+  #[arg(long, alias = "cp", default_value = ".")]
+  class_path: String,
+
+  /// Class files, or names of classes (e.g. java.lang.Object) to find in the class path
+  #[arg(required = true)]
+  classes: Vec<String>,
+}
+
+fn main() -> Result<()> {
+  color_eyre::install()?;
+
+  let args = Args::parse();
+
+  let mut class_path = ClassPath::new(&args.class_path)?;
+
+  for class in &args.classes {
+    let data = if class.ends_with(".class") && Path::new(class).is_file() {
+      std::fs::read(class)?
+    } else {
+      class_path
+        .find_class(class)?
+        .ok_or_else(|| eyre!("[!] Class {} not found", class))?
+    };
+
+    print!("{}", jvmcraft::disassemble_class_file(&data)?);
+  }
+
+  Ok(())
+}
@@ -0,0 +1,317 @@
+use color_eyre::eyre::{eyre, Result};
+use log::debug;
+
+use super::loader::ClassLoader;
+use crate::{runtime::errors, utils::*};
+
+// https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-5.html#jvms-5.4.4
+impl ClassLoader {
+  /// Run-time package of a class name (e.g. `java/lang` for `java/lang/String`)
+  pub(crate) fn get_package_name(classname: &str) -> String {
+    let binary_name = ClassLoader::get_binary_name(classname);
+    let name = ClassLoader::strip_hidden_suffix(&binary_name);
+
+    match name.rfind('/') {
+      Some(i) => name[..i].to_string(),
+      None => String::new(),
+    }
+  }
+
+  /// Are `left` and `right` in the same run-time package ?
+  pub(crate) fn is_same_package(left: &str, right: &str) -> bool {
+    // a run-time package is identified by its name and its defining loader
+    ClassLoader::get_defining_loader(left) == ClassLoader::get_defining_loader(right)
+      && ClassLoader::get_package_name(left) == ClassLoader::get_package_name(right)
+  }
+
+  /// Is `left` equal to `right` or one of its super classes ?
+  pub(crate) fn is_same_or_subclass(&mut self, left: &str, right: &str) -> Result<bool> {
+    let mut current = right.to_string();
+
+    loop {
+      if current == left {
+        return Ok(true);
+      }
+
+      let class = self.get(&current)?;
+
+      if !class.has_parent() {
+        return Ok(false);
+      }
+
+      current = class.get_parent_name().to_owned();
+    }
+  }
+
+  /// Get the nest host of a class
+  ///
+  /// A class without `NestHost` is the host of its own nest. A `NestHost` that
+  /// lives in a different package or that does not list the class in its
+  /// `NestMembers` is ignored, and the class becomes its own host.
+  pub(crate) fn get_nest_host_name(&mut self, classname: &str) -> Result<String> {
+    let host_name = {
+      let class = self.get(classname)?;
+
+      // hidden classes defined as nestmates join the nest of their lookup class
+      if let Some(nest_host) = class.get_dynamic_nest_host() {
+        return Ok(nest_host.to_string());
+      }
+
+      match class.get_nest_host() {
+        Some(nest_host) => class.resolve_class_name(nest_host.get_host_class_index())?,
+        None => return Ok(classname.to_string()),
+      }
+    };
+
+    let host_name = self.get_loaded_class_key(classname, &host_name);
+
+    if !ClassLoader::is_same_package(classname, &host_name) {
+      debug!(
+        "Nest host {} of {} in another package",
+        host_name, classname
+      );
+
+      return Ok(classname.to_string());
+    }
+
+    let members = self.get(&host_name)?.get_nest_members()?;
+
+    let binary_name = ClassLoader::get_binary_name(classname);
+
+    if members.contains(&binary_name) {
+      Ok(host_name)
+    } else {
+      debug!("{} is not a nest member of {}", classname, host_name);
+
+      Ok(classname.to_string())
+    }
+  }
+
+  /// Do `left` and `right` belong to the same nest ?
+  pub(crate) fn are_nest_mates(&mut self, left: &str, right: &str) -> Result<bool> {
+    if left == right {
+      return Ok(true);
+    }
+
+    Ok(self.get_nest_host_name(left)? == self.get_nest_host_name(right)?)
+  }
+
+  /// Is the class `target` accessible from the class `accessor` ?
+  pub(crate) fn is_class_accessible(&mut self, accessor: &str, target: &str) -> Result<bool> {
+    // an array class is accessible iff its element type is
+    if target.starts_with('[') {
+      let element = target.trim_start_matches('[');
+
+      if element.starts_with('L') && element.ends_with(';') {
+        return self.is_class_accessible(accessor, &element[1..element.len() - 1]);
+      }
+
+      // primitive element type
+      return Ok(true);
+    }
+
+    if accessor == target || ClassLoader::is_same_package(accessor, target) {
+      return Ok(true);
+    }
+
+    Ok(self.get(target)?.is_public())
+  }
+
+  /// Is a member declared in `declaring` with `access_flags` accessible from `accessor` ?
+  pub(crate) fn is_member_accessible(
+    &mut self,
+    accessor: &str,
+    declaring: &str,
+    access_flags: ju2,
+  ) -> Result<bool> {
+    // ACC_PUBLIC
+    if access_flags & 0x0001 != 0 {
+      return Ok(true);
+    }
+
+    // ACC_PRIVATE
+    if access_flags & 0x0002 != 0 {
+      return self.are_nest_mates(accessor, declaring);
+    }
+
+    // protected and package private members are accessible within the package
+    if ClassLoader::is_same_package(accessor, declaring) {
+      return Ok(true);
+    }
+
+    // ACC_PROTECTED
+    if access_flags & 0x0004 != 0 {
+      return self.is_same_or_subclass(declaring, accessor);
+    }
+
+    Ok(false)
+  }
+
+  /// Check the access to the class `target` from `accessor`
+  ///
+  /// # Returns
+  ///
+  /// `IllegalAccessError` if `target` is not accessible
+  pub(crate) fn check_class_access(&mut self, accessor: &str, target: &str) -> Result<()> {
+    if self.is_class_accessible(accessor, target)? {
+      return Ok(());
+    }
+
+    Err(eyre!(errors::JavaException::IllegalAccessError(format!(
+      "class {} cannot access class {}",
+      class_to_dotclass(accessor),
+      class_to_dotclass(target)
+    ))))
+  }
+
+  /// Check the access to the member `name` of `declaring` from `accessor`
+  ///
+  /// # Arguments
+  ///
+  /// * `accessor` - Class executing the instruction
+  /// * `referenced` - Class named in the symbolic reference
+  /// * `declaring` - Class where the member has been found
+  /// * `name` - Name of the member (only used for the error message)
+  /// * `access_flags` - Access flags of the member
+  ///
+  /// # Returns
+  ///
+  /// `IllegalAccessError` if the member is not accessible
+  pub(crate) fn check_member_access(
+    &mut self,
+    accessor: &str,
+    referenced: &str,
+    declaring: &str,
+    name: &str,
+    access_flags: ju2,
+  ) -> Result<()> {
+    self.check_class_access(accessor, referenced)?;
+
+    if self.is_member_accessible(accessor, declaring, access_flags)? {
+      return Ok(());
+    }
+
+    Err(eyre!(errors::JavaException::IllegalAccessError(format!(
+      "class {} tried to access {} {}.{}",
+      class_to_dotclass(accessor),
+      access_flags_to_str(access_flags),
+      class_to_dotclass(declaring),
+      name
+    ))))
+  }
+
+  /// Find the class declaring the field `field_name`, looking into super
+  /// interfaces and super classes (JVMS 5.4.3.2)
+  ///
+  /// # Returns
+  ///
+  /// Pair as (class: String, access_flags: ju2)
+  pub(crate) fn find_field_declaration(
+    &mut self,
+    classname: &str,
+    field_name: &str,
+    type_str: &str,
+  ) -> Result<Option<(String, ju2)>> {
+    let (interfaces, parent) = {
+      let class = self.get(classname)?;
+
+      for field in class.get_fields() {
+        if field.get_name() == field_name && field.get_descriptor() == type_str {
+          return Ok(Some((classname.to_string(), field.get_access_flags())));
+        }
+      }
+
+      let parent = if class.has_parent() {
+        Some(class.get_parent_name().to_owned())
+      } else {
+        None
+      };
+
+      (class.get_interfaces().clone(), parent)
+    };
+
+    for interface in interfaces {
+      if let Some(found) = self.find_field_declaration(&interface, field_name, type_str)? {
+        return Ok(Some(found));
+      }
+    }
+
+    match parent {
+      Some(parent) => self.find_field_declaration(&parent, field_name, type_str),
+      None => Ok(None),
+    }
+  }
+
+  /// Find the class declaring the method `method_name`, looking into super classes and
+  /// super interfaces (JVMS 5.4.3.3 and 5.4.3.4)
+  ///
+  /// The signature polymorphic methods of `MethodHandle` and `VarHandle` match any
+  /// descriptor.
+  ///
+  /// # Returns
+  ///
+  /// Pair as (class: String, access_flags: ju2)
+  pub(crate) fn find_method_declaration(
+    &mut self,
+    classname: &str,
+    method_name: &str,
+    type_str: &str,
+  ) -> Result<Option<(String, ju2)>> {
+    // the methods of an array class are those of java/lang/Object, except `clone`
+    // that is public
+    if classname.starts_with('[') {
+      return match self.find_method_declaration("java/lang/Object", method_name, type_str)? {
+        Some((declaring, _)) if method_name == "clone" => Ok(Some((declaring, 0x0001))),
+        found => Ok(found),
+      };
+    }
+
+    let mut interfaces = vec![];
+    let mut current = classname.to_string();
+
+    loop {
+      let class = self.get(&current)?;
+
+      let is_polymorphic_class =
+        current == "java/lang/invoke/MethodHandle" || current == "java/lang/invoke/VarHandle";
+
+      for method in class.get_methods().iter() {
+        if method.get_name() != method_name {
+          continue;
+        }
+
+        if method.get_descriptor() == type_str
+          || (is_polymorphic_class && method.has_polymorphic_signature())
+        {
+          return Ok(Some((current, method.get_access_flags())));
+        }
+      }
+
+      interfaces.extend(class.get_interfaces().clone());
+
+      if !class.has_parent() {
+        break;
+      }
+
+      current = class.get_parent_name().to_owned();
+    }
+
+    for interface in interfaces {
+      if let Some(found) = self.find_method_declaration(&interface, method_name, type_str)? {
+        return Ok(Some(found));
+      }
+    }
+
+    Ok(None)
+  }
+}
+
+fn access_flags_to_str(access_flags: ju2) -> &'static str {
+  if access_flags & 0x0002 != 0 {
+    "private"
+  } else if access_flags & 0x0004 != 0 {
+    "protected"
+  } else {
+    "package-private"
+  }
+}
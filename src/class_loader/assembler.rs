@@ -0,0 +1,1384 @@
+use std::{collections::HashMap, fmt::Display, vec::IntoIter};
+
+use color_eyre::eyre::{eyre, Report, Result};
+
+use super::{
+  attributes::{
+    AttributeInfo, AttributeInfoInfoEnum, BootstrapMethod, BootstrapMethods, Code,
+    ExceptionTableEntry, StackMapFrame, StackMapTable, VerificationTypeInfo,
+    VerificationTypeInfoEnum,
+  },
+  builder::ClassBuilder,
+  constant_pool::ConstantPool,
+};
+use crate::{runtime::opcode::OpCode, utils::*};
+
+// class files without StackMapTable, verified by type inference (not type checking)
+const DEFAULT_MAJOR_VERSION: ju2 = 49;
+
+// the interpreter does not bound the operand stack, only the verifier checks it
+const DEFAULT_MAX_STACK: ju2 = 16;
+
+const ACC_SUPER: ju2 = 0x0020;
+const ACC_INTERFACE: ju2 = 0x0200;
+const ACC_ABSTRACT: ju2 = 0x0400;
+const ACC_STATIC: ju2 = 0x0008;
+const ACC_NATIVE: ju2 = 0x0100;
+
+const CLASS_FLAGS: &[(&str, ju2)] = &[
+  ("public", 0x0001),
+  ("final", 0x0010),
+  ("super", 0x0020),
+  ("interface", 0x0200),
+  ("abstract", 0x0400),
+  ("synthetic", 0x1000),
+  ("annotation", 0x2000),
+  ("enum", 0x4000),
+];
+
+const FIELD_FLAGS: &[(&str, ju2)] = &[
+  ("public", 0x0001),
+  ("private", 0x0002),
+  ("protected", 0x0004),
+  ("static", 0x0008),
+  ("final", 0x0010),
+  ("volatile", 0x0040),
+  ("transient", 0x0080),
+  ("synthetic", 0x1000),
+  ("enum", 0x4000),
+];
+
+const METHOD_FLAGS: &[(&str, ju2)] = &[
+  ("public", 0x0001),
+  ("private", 0x0002),
+  ("protected", 0x0004),
+  ("static", 0x0008),
+  ("final", 0x0010),
+  ("synchronized", 0x0020),
+  ("bridge", 0x0040),
+  ("varargs", 0x0080),
+  ("native", 0x0100),
+  ("abstract", 0x0400),
+  ("strict", 0x0800),
+  ("synthetic", 0x1000),
+];
+
+// https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-6.html#jvms-6.5.newarray
+const ARRAY_TYPES: &[(&str, ju1)] = &[
+  ("boolean", 4),
+  ("char", 5),
+  ("float", 6),
+  ("double", 7),
+  ("byte", 8),
+  ("short", 9),
+  ("int", 10),
+  ("long", 11),
+];
+
+// https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-5.html#jvms-5.4.3.5
+const REFERENCE_KINDS: &[(&str, ju1)] = &[
+  ("getfield", 1),
+  ("getstatic", 2),
+  ("putfield", 3),
+  ("putstatic", 4),
+  ("invokevirtual", 5),
+  ("invokestatic", 6),
+  ("invokespecial", 7),
+  ("newinvokespecial", 8),
+  ("invokeinterface", 9),
+];
+
+const REF_INVOKE_STATIC: ju1 = 6;
+const REF_INVOKE_INTERFACE: ju1 = 9;
+
+fn error(line: usize, message: impl Display) -> Report {
+  eyre!("[!] Line {}: {}", line, message)
+}
+
+struct Line {
+  number: usize,
+  tokens: Vec<String>,
+}
+
+// tokens of a line separated by spaces, string literals are kept with their
+// quotes and a `;` after a space starts a comment (descriptors contain `;`)
+fn tokenize(number: usize, line: &str) -> Result<Vec<String>> {
+  let mut tokens = vec![];
+  let mut token = String::new();
+
+  let mut chars = line.chars();
+
+  while let Some(c) = chars.next() {
+    match c {
+      c if c.is_whitespace() => {
+        if !token.is_empty() {
+          tokens.push(std::mem::take(&mut token));
+        }
+      }
+      ';' if token.is_empty() => break,
+      '"' if token.is_empty() => {
+        token.push(c);
+
+        loop {
+          let c = chars
+            .next()
+            .ok_or_else(|| error(number, "Unterminated string"))?;
+
+          token.push(c);
+
+          match c {
+            '\\' => token.push(
+              chars
+                .next()
+                .ok_or_else(|| error(number, "Unterminated string"))?,
+            ),
+            '"' => break,
+            _ => {}
+          }
+        }
+      }
+      c => token.push(c),
+    }
+  }
+
+  if !token.is_empty() {
+    tokens.push(token);
+  }
+
+  Ok(tokens)
+}
+
+// value of a string literal
+fn unescape(line: usize, literal: &str) -> Result<String> {
+  let mut value = String::new();
+
+  let mut chars = literal[1..literal.len() - 1].chars();
+
+  while let Some(c) = chars.next() {
+    if c != '\\' {
+      value.push(c);
+      continue;
+    }
+
+    match chars.next() {
+      Some('n') => value.push('\n'),
+      Some('r') => value.push('\r'),
+      Some('t') => value.push('\t'),
+      Some('0') => value.push('\0'),
+      Some('\\') => value.push('\\'),
+      Some('"') => value.push('"'),
+      Some('u') => {
+        let code: String = chars.by_ref().take(4).collect();
+
+        let c = u32::from_str_radix(&code, 16)
+          .ok()
+          .and_then(char::from_u32)
+          .ok_or_else(|| error(line, format!("Invalid escape \\u{}", code)))?;
+
+        value.push(c);
+      }
+      c => {
+        return Err(error(
+          line,
+          format!("Invalid escape \\{}", c.unwrap_or(' ')),
+        ))
+      }
+    }
+  }
+
+  Ok(value)
+}
+
+fn parse_int<T: TryFrom<i64>>(line: usize, token: &str) -> Result<T> {
+  let value = match token.strip_prefix('-') {
+    Some(digits) => parse_unsigned(digits).map(|value| -value),
+    None => parse_unsigned(token),
+  };
+
+  value
+    .and_then(|value| T::try_from(value).ok())
+    .ok_or_else(|| error(line, format!("Invalid or out of range number {}", token)))
+}
+
+fn parse_unsigned(token: &str) -> Option<i64> {
+  match token.strip_prefix("0x") {
+    Some(hex) => i64::from_str_radix(hex, 16).ok(),
+    None => token.parse().ok(),
+  }
+}
+
+// access flags at the start of `tokens`, followed by the other tokens
+fn parse_flags<'a>(tokens: &'a [String], flags: &[(&str, ju2)]) -> (ju2, &'a [String]) {
+  let mut access_flags = 0;
+
+  for (i, token) in tokens.iter().enumerate() {
+    match flags.iter().find(|(name, _)| name == token) {
+      Some((_, flag)) => access_flags |= flag,
+      None => return (access_flags, &tokens[i..]),
+    }
+  }
+
+  (access_flags, &[])
+}
+
+// `owner/name` of a member
+fn split_member(line: usize, member: &str) -> Result<(&str, &str)> {
+  member
+    .rsplit_once('/')
+    .filter(|(owner, name)| !owner.is_empty() && !name.is_empty())
+    .ok_or_else(|| error(line, format!("Expected owner/name, got {}", member)))
+}
+
+// `owner/name(descriptor)` of a method
+fn split_method(line: usize, method: &str) -> Result<(&str, &str, &str)> {
+  let open = method
+    .find('(')
+    .filter(|open| method[*open..].contains(')'))
+    .ok_or_else(|| error(line, format!("Expected a method descriptor in {}", method)))?;
+
+  let (owner, name) = split_member(line, &method[..open])?;
+
+  Ok((owner, name, &method[open..]))
+}
+
+// `name(descriptor)` of a method or of a call site
+fn split_descriptor(line: usize, method: &str) -> Result<(&str, &str)> {
+  method
+    .find('(')
+    .filter(|open| *open > 0 && method[*open..].contains(')'))
+    .map(|open| method.split_at(open))
+    .ok_or_else(|| error(line, format!("Expected name(descriptor), got {}", method)))
+}
+
+// `key : label` of a switch, the spaces around `:` are optional
+fn split_case(line: &Line) -> Result<(String, String)> {
+  let case = line.tokens.join(" ");
+
+  case
+    .split_once(':')
+    .map(|(key, label)| (key.trim().to_string(), label.trim().to_string()))
+    .filter(|(key, label)| !key.is_empty() && !label.is_empty() && !label.contains(' '))
+    .ok_or_else(|| error(line.number, format!("Expected key : label, got {}", case)))
+}
+
+fn check_operands(line: usize, operands: &[String], count: usize) -> Result<()> {
+  if operands.len() != count {
+    return Err(error(
+      line,
+      format!("Expected {} operand(s), got {}", count, operands.len()),
+    ));
+  }
+
+  Ok(())
+}
+
+// number of local variable slots taken by the parameters of a method
+fn get_args_size(descriptor: &str) -> usize {
+  parse_parameter_types(get_parameters_type_descriptor(descriptor))
+    .iter()
+    .map(|param| if *param == "J" || *param == "D" { 2 } else { 1 })
+    .sum()
+}
+
+// branch offset to patch once the labels are known
+struct Jump {
+  line: usize,
+  // pc of the instruction, the offset is relative to it
+  pc: usize,
+  // position of the offset in the code
+  at: usize,
+  label: String,
+  wide: bool,
+}
+
+struct Handler {
+  line: usize,
+  catch_type: Option<String>,
+  from: String,
+  to: String,
+  using: String,
+}
+
+enum FrameType {
+  Resolved(VerificationTypeInfoEnum),
+  // object created by the `new` at a label
+  Uninitialized(String),
+}
+
+struct Frame {
+  line: usize,
+  pc: usize,
+  label: Option<String>,
+  locals: Vec<FrameType>,
+  stack: Vec<FrameType>,
+}
+
+/// Bootstrap methods and dynamic constants of a class, shared by its methods
+#[derive(Default)]
+struct Bootstraps {
+  methods: Vec<BootstrapMethod>,
+  // constant pool index of the dynamic constants, by label
+  dynamic_constants: HashMap<String, ju2>,
+}
+
+impl Bootstraps {
+  // `.dynamic <label> <name> <descriptor> <owner/name(descriptor)>` followed by the static
+  // arguments, the label can be used in the arguments to assemble cycles
+  fn dynamic(&mut self, cp: &mut ConstantPool, line: usize, operands: &[String]) -> Result<()> {
+    if operands.len() < 4 {
+      check_operands(line, operands, 4)?;
+    }
+
+    let label = &operands[0];
+
+    if self.dynamic_constants.contains_key(label) {
+      return Err(error(
+        line,
+        format!("Dynamic constant {} already defined", label),
+      ));
+    }
+
+    let index = cp.add_dynamic(self.methods.len() as ju2, &operands[1], &operands[2]);
+
+    self.dynamic_constants.insert(label.clone(), index);
+
+    self.bootstrap_method(cp, line, &operands[3..])?;
+
+    Ok(())
+  }
+
+  // index of the constant pool entry of the dynamic constant defined at `label`
+  fn dynamic_constant(&self, line: usize, label: &str) -> Result<ju2> {
+    self
+      .dynamic_constants
+      .get(label)
+      .copied()
+      .ok_or_else(|| error(line, format!("Undefined dynamic constant {}", label)))
+  }
+
+  // index of the constant of ldc and ldc_w: a number, a string, `class <name>` or
+  // `dynamic <label>`
+  fn constant(&self, cp: &mut ConstantPool, line: usize, operands: &[String]) -> Result<ju2> {
+    if operands.len() == 2 {
+      match operands[0].as_str() {
+        "class" => return Ok(cp.add_class(&operands[1])),
+        "dynamic" => return self.dynamic_constant(line, &operands[1]),
+        _ => {}
+      }
+    }
+
+    check_operands(line, operands, 1)?;
+
+    let operand = &operands[0];
+
+    if operand.starts_with('"') {
+      Ok(cp.add_string(&unescape(line, operand)?))
+    } else if let Ok(value) = parse_int(line, operand) {
+      Ok(cp.add_integer(value))
+    } else {
+      let value = operand
+        .parse()
+        .map_err(|_| error(line, format!("Invalid constant {}", operand)))?;
+
+      Ok(cp.add_float(value))
+    }
+  }
+
+  // index of the constant of ldc2_w: a long, a double or `dynamic <label>`
+  fn constant2(&self, cp: &mut ConstantPool, line: usize, operands: &[String]) -> Result<ju2> {
+    if operands.len() == 2 && operands[0] == "dynamic" {
+      return self.dynamic_constant(line, &operands[1]);
+    }
+
+    check_operands(line, operands, 1)?;
+
+    let operand = &operands[0];
+
+    if let Ok(value) = parse_int(line, operand) {
+      Ok(cp.add_long(value))
+    } else {
+      let value = operand
+        .parse()
+        .map_err(|_| error(line, format!("Invalid constant {}", operand)))?;
+
+      Ok(cp.add_double(value))
+    }
+  }
+
+  // index in the BootstrapMethods attribute of `owner/name(descriptor)`, a static method,
+  // followed by its static arguments
+  fn bootstrap_method(
+    &mut self,
+    cp: &mut ConstantPool,
+    line: usize,
+    operands: &[String],
+  ) -> Result<ju2> {
+    let (owner, name, descriptor) = split_method(line, &operands[0])?;
+
+    let method_ref = cp.add_method_ref(owner, name, descriptor);
+    let method_handle = cp.add_method_handle(REF_INVOKE_STATIC, method_ref);
+
+    let mut arguments = vec![];
+    let mut tokens = operands[1..].iter();
+
+    while let Some(token) = tokens.next() {
+      let mut next = || {
+        tokens
+          .next()
+          .map(String::as_str)
+          .ok_or_else(|| error(line, format!("Missing operand after {}", token)))
+      };
+
+      let index = match token.as_str() {
+        "class" => cp.add_class(next()?),
+        "type" => cp.add_method_type(next()?),
+        "dynamic" => self.dynamic_constant(line, next()?)?,
+        "handle" => {
+          let kind = next()?;
+
+          let (_, kind) = REFERENCE_KINDS
+            .iter()
+            .find(|(name, _)| *name == kind)
+            .ok_or_else(|| error(line, format!("Unknown reference kind {}", kind)))?;
+
+          let reference = if *kind <= 4 {
+            let (owner, name) = split_member(line, next()?)?;
+
+            cp.add_field_ref(owner, name, next()?)
+          } else {
+            let (owner, name, descriptor) = split_method(line, next()?)?;
+
+            if *kind == REF_INVOKE_INTERFACE {
+              cp.add_interface_method_ref(owner, name, descriptor)
+            } else {
+              cp.add_method_ref(owner, name, descriptor)
+            }
+          };
+
+          cp.add_method_handle(*kind, reference)
+        }
+        _ => self.constant(cp, line, std::slice::from_ref(token))?,
+      };
+
+      arguments.push(index);
+    }
+
+    self
+      .methods
+      .push(BootstrapMethod::define(method_handle, arguments));
+
+    Ok((self.methods.len() - 1) as ju2)
+  }
+}
+
+/// Assembler of the code of a method
+struct MethodAssembler<'a> {
+  cp: &'a mut ConstantPool,
+  // shared by the methods of the class
+  bootstraps: &'a mut Bootstraps,
+  code: Vec<ju1>,
+  labels: HashMap<String, usize>,
+  jumps: Vec<Jump>,
+  handlers: Vec<Handler>,
+  frames: Vec<Frame>,
+  max_stack: Option<ju2>,
+  max_locals: Option<ju2>,
+}
+
+impl<'a> MethodAssembler<'a> {
+  fn new(cp: &'a mut ConstantPool, bootstraps: &'a mut Bootstraps) -> MethodAssembler<'a> {
+    MethodAssembler {
+      cp,
+      bootstraps,
+      code: vec![],
+      labels: HashMap::new(),
+      jumps: vec![],
+      handlers: vec![],
+      frames: vec![],
+      max_stack: None,
+      max_locals: None,
+    }
+  }
+
+  /// Assemble the lines of the method until `.end method`
+  fn assemble(
+    mut self,
+    lines: &mut IntoIter<Line>,
+    access_flags: ju2,
+    descriptor: &str,
+  ) -> Result<Option<Code>> {
+    loop {
+      let line = lines
+        .next()
+        .ok_or_else(|| eyre!("[!] Missing .end method"))?;
+
+      let mut tokens = &line.tokens[..];
+
+      while let Some(label) = tokens.first().and_then(|token| token.strip_suffix(':')) {
+        if label.is_empty()
+          || self
+            .labels
+            .insert(label.to_string(), self.code.len())
+            .is_some()
+        {
+          return Err(error(line.number, format!("Invalid label {}", tokens[0])));
+        }
+
+        tokens = &tokens[1..];
+      }
+
+      let Some(first) = tokens.first() else {
+        continue;
+      };
+
+      match first.as_str() {
+        ".end" if tokens[1..] == ["method"] => break,
+        ".limit" => self.limit(line.number, &tokens[1..])?,
+        ".catch" => self.catch(line.number, &tokens[1..])?,
+        ".stack" => self.frame(line.number, lines)?,
+        directive if directive.starts_with('.') => {
+          return Err(error(
+            line.number,
+            format!("Unexpected {} in a method", directive),
+          ))
+        }
+        _ => self.instruction(line.number, tokens, lines)?,
+      }
+    }
+
+    if self.code.is_empty() && access_flags & (ACC_ABSTRACT | ACC_NATIVE) != 0 {
+      return Ok(None);
+    }
+
+    let max_locals = match self.max_locals {
+      Some(max_locals) => max_locals,
+      None if access_flags & ACC_STATIC != 0 => get_args_size(descriptor) as ju2,
+      None => get_args_size(descriptor) as ju2 + 1,
+    };
+
+    self.code(max_locals).map(Some)
+  }
+
+  fn label(&self, line: usize, label: &str) -> Result<usize> {
+    self
+      .labels
+      .get(label)
+      .copied()
+      .ok_or_else(|| error(line, format!("Undefined label {}", label)))
+  }
+
+  fn code(mut self, max_locals: ju2) -> Result<Code> {
+    for jump in &self.jumps {
+      let offset = self.label(jump.line, &jump.label)? as i64 - jump.pc as i64;
+
+      if jump.wide {
+        self.code[jump.at..jump.at + 4].copy_from_slice(&(offset as i32).to_be_bytes());
+      } else {
+        let offset = i16::try_from(offset)
+          .map_err(|_| error(jump.line, format!("Branch to {} is too far", jump.label)))?;
+
+        self.code[jump.at..jump.at + 2].copy_from_slice(&offset.to_be_bytes());
+      }
+    }
+
+    let mut exception_table = vec![];
+
+    for handler in &self.handlers {
+      let entry = ExceptionTableEntry::define(
+        self.cp,
+        self.label(handler.line, &handler.from)? as ju2,
+        self.label(handler.line, &handler.to)? as ju2,
+        self.label(handler.line, &handler.using)? as ju2,
+        handler.catch_type.as_deref(),
+      );
+
+      exception_table.push(entry);
+    }
+
+    let mut attributes = vec![];
+
+    if !self.frames.is_empty() {
+      let stack_map_table = self.stack_map_table()?;
+
+      attributes.push(AttributeInfo::define(
+        self.cp,
+        "StackMapTable",
+        AttributeInfoInfoEnum::StackMapTable(stack_map_table),
+      ));
+    }
+
+    Ok(Code::define(
+      self.max_stack.unwrap_or(DEFAULT_MAX_STACK),
+      max_locals,
+      self.code,
+      exception_table,
+      attributes,
+    ))
+  }
+
+  // every frame is a full frame, in the order of their offsets
+  fn stack_map_table(&self) -> Result<StackMapTable> {
+    let mut frames = vec![];
+
+    for frame in &self.frames {
+      let pc = match &frame.label {
+        Some(label) => self.label(frame.line, label)?,
+        None => frame.pc,
+      };
+
+      frames.push((pc, frame));
+    }
+
+    frames.sort_by_key(|(pc, _)| *pc);
+
+    let mut entries = vec![];
+    let mut previous: Option<usize> = None;
+
+    for (pc, frame) in frames {
+      let offset_delta = match previous {
+        Some(previous) if pc == previous => {
+          return Err(error(frame.line, format!("Two frames at {}", pc)))
+        }
+        Some(previous) => pc - previous - 1,
+        None => pc,
+      };
+
+      previous = Some(pc);
+
+      entries.push(StackMapFrame::full_frame(
+        offset_delta as ju2,
+        self.verification_types(frame.line, &frame.locals)?,
+        self.verification_types(frame.line, &frame.stack)?,
+      ));
+    }
+
+    Ok(StackMapTable::new(entries))
+  }
+
+  fn verification_types(
+    &self,
+    line: usize,
+    types: &[FrameType],
+  ) -> Result<Vec<VerificationTypeInfo>> {
+    types
+      .iter()
+      .map(|t| match t {
+        FrameType::Resolved(info) => Ok(VerificationTypeInfo::new(info.clone())),
+        FrameType::Uninitialized(label) => {
+          let offset = self.label(line, label)? as ju2;
+
+          Ok(VerificationTypeInfo::new(
+            VerificationTypeInfoEnum::Uninitialized { offset },
+          ))
+        }
+      })
+      .collect()
+  }
+
+  // .limit stack 2 or .limit locals 1
+  fn limit(&mut self, line: usize, operands: &[String]) -> Result<()> {
+    check_operands(line, operands, 2)?;
+
+    let value = parse_int(line, &operands[1])?;
+
+    match operands[0].as_str() {
+      "stack" => self.max_stack = Some(value),
+      "locals" => self.max_locals = Some(value),
+      limit => return Err(error(line, format!("Unknown limit {}", limit))),
+    }
+
+    Ok(())
+  }
+
+  // .catch java/lang/Exception from L1 to L2 using L3, `all` for any exception
+  fn catch(&mut self, line: usize, operands: &[String]) -> Result<()> {
+    check_operands(line, operands, 7)?;
+
+    if operands[1] != "from" || operands[3] != "to" || operands[5] != "using" {
+      return Err(error(
+        line,
+        "Expected .catch <class> from <label> to <label> using <label>",
+      ));
+    }
+
+    self.handlers.push(Handler {
+      line,
+      catch_type: (operands[0] != "all").then(|| operands[0].clone()),
+      from: operands[2].clone(),
+      to: operands[4].clone(),
+      using: operands[6].clone(),
+    });
+
+    Ok(())
+  }
+
+  // frame at the next instruction (or at `offset <label>`) until `.end stack`
+  fn frame(&mut self, line: usize, lines: &mut IntoIter<Line>) -> Result<()> {
+    let mut frame = Frame {
+      line,
+      pc: self.code.len(),
+      label: None,
+      locals: vec![],
+      stack: vec![],
+    };
+
+    loop {
+      let line = lines
+        .next()
+        .ok_or_else(|| error(line, "Missing .end stack"))?;
+
+      let tokens = &line.tokens;
+
+      match tokens[0].as_str() {
+        ".end" if tokens[1..] == ["stack"] => break,
+        "offset" => {
+          check_operands(line.number, &tokens[1..], 1)?;
+
+          frame.label = Some(tokens[1].clone());
+        }
+        "locals" => frame
+          .locals
+          .extend(self.frame_types(line.number, &tokens[1..])?),
+        "stack" => frame
+          .stack
+          .extend(self.frame_types(line.number, &tokens[1..])?),
+        token => {
+          return Err(error(
+            line.number,
+            format!("Unexpected {} in a frame", token),
+          ))
+        }
+      }
+    }
+
+    self.frames.push(frame);
+
+    Ok(())
+  }
+
+  fn frame_types(&mut self, line: usize, tokens: &[String]) -> Result<Vec<FrameType>> {
+    let mut types = vec![];
+
+    let mut tokens = tokens.iter();
+
+    while let Some(token) = tokens.next() {
+      let info = match token.as_str() {
+        "Top" => VerificationTypeInfoEnum::Top,
+        "Integer" => VerificationTypeInfoEnum::Integer,
+        "Float" => VerificationTypeInfoEnum::Float,
+        "Long" => VerificationTypeInfoEnum::Long,
+        "Double" => VerificationTypeInfoEnum::Double,
+        "Null" => VerificationTypeInfoEnum::Null,
+        "UninitializedThis" => VerificationTypeInfoEnum::UninitializedThis,
+        "Object" => {
+          let class_name = tokens
+            .next()
+            .ok_or_else(|| error(line, "Expected the class of Object"))?;
+
+          VerificationTypeInfoEnum::Object {
+            cpool_index: self.cp.add_class(class_name),
+          }
+        }
+        "Uninitialized" => {
+          let label = tokens
+            .next()
+            .ok_or_else(|| error(line, "Expected the label of Uninitialized"))?;
+
+          types.push(FrameType::Uninitialized(label.clone()));
+
+          continue;
+        }
+        token => return Err(error(line, format!("Unknown verification type {}", token))),
+      };
+
+      types.push(FrameType::Resolved(info));
+    }
+
+    Ok(types)
+  }
+
+  fn push_u2(&mut self, value: ju2) {
+    self.code.extend(value.to_be_bytes());
+  }
+
+  // offset of `label` from `pc`, patched at the end
+  fn push_jump(&mut self, line: usize, pc: usize, label: &str, wide: bool) {
+    self.jumps.push(Jump {
+      line,
+      pc,
+      at: self.code.len(),
+      label: label.to_string(),
+      wide,
+    });
+
+    self
+      .code
+      .extend(if wide { &[0; 4][..] } else { &[0; 2][..] });
+  }
+
+  fn instruction(
+    &mut self,
+    line: usize,
+    tokens: &[String],
+    lines: &mut IntoIter<Line>,
+  ) -> Result<()> {
+    // `wide` before an instruction forces its wide form
+    let (wide, tokens) = match tokens[0].as_str() {
+      "wide" if tokens.len() > 1 => (true, &tokens[1..]),
+      _ => (false, tokens),
+    };
+
+    let opcode = OpCode::from_mnemonic(&tokens[0])
+      .ok_or_else(|| error(line, format!("Unknown instruction {}", tokens[0])))?;
+
+    let operands = &tokens[1..];
+
+    let pc = self.code.len();
+
+    match opcode {
+      OpCode::BIPUSH => {
+        check_operands(line, operands, 1)?;
+
+        let value: i8 = parse_int(line, &operands[0])?;
+
+        self.code.extend([opcode as ju1, value as ju1]);
+      }
+      OpCode::SIPUSH => {
+        check_operands(line, operands, 1)?;
+
+        let value: i16 = parse_int(line, &operands[0])?;
+
+        self.code.push(opcode as ju1);
+        self.push_u2(value as ju2);
+      }
+      OpCode::LDC => {
+        let index = self.bootstraps.constant(self.cp, line, operands)?;
+
+        let index = ju1::try_from(index).map_err(|_| {
+          error(
+            line,
+            format!("Constant #{} too far for ldc, use ldc_w", index),
+          )
+        })?;
+
+        self.code.extend([opcode as ju1, index]);
+      }
+      OpCode::LDCW | OpCode::LDC2W => {
+        let index = if opcode == OpCode::LDCW {
+          self.bootstraps.constant(self.cp, line, operands)?
+        } else {
+          self.bootstraps.constant2(self.cp, line, operands)?
+        };
+
+        self.code.push(opcode as ju1);
+        self.push_u2(index);
+      }
+      OpCode::ILOAD
+      | OpCode::LLOAD
+      | OpCode::FLOAD
+      | OpCode::DLOAD
+      | OpCode::ALOAD
+      | OpCode::ISTORE
+      | OpCode::LSTORE
+      | OpCode::FSTORE
+      | OpCode::DSTORE
+      | OpCode::ASTORE
+      | OpCode::RET => {
+        check_operands(line, operands, 1)?;
+
+        let index: ju2 = parse_int(line, &operands[0])?;
+
+        if wide || index > 0xFF {
+          self.code.extend([OpCode::WIDE as ju1, opcode as ju1]);
+          self.push_u2(index);
+        } else {
+          self.code.extend([opcode as ju1, index as ju1]);
+        }
+      }
+      OpCode::IINC => {
+        check_operands(line, operands, 2)?;
+
+        let index: ju2 = parse_int(line, &operands[0])?;
+        let value: i16 = parse_int(line, &operands[1])?;
+
+        if wide || index > 0xFF || i8::try_from(value).is_err() {
+          self.code.extend([OpCode::WIDE as ju1, opcode as ju1]);
+          self.push_u2(index);
+          self.push_u2(value as ju2);
+        } else {
+          self
+            .code
+            .extend([opcode as ju1, index as ju1, value as ju1]);
+        }
+      }
+      OpCode::IFEQ
+      | OpCode::IFNE
+      | OpCode::IFLT
+      | OpCode::IFGE
+      | OpCode::IFGT
+      | OpCode::IFLE
+      | OpCode::IFICMPEQ
+      | OpCode::IFICMPNE
+      | OpCode::IFICMPLT
+      | OpCode::IFICMPGE
+      | OpCode::IFICMPGT
+      | OpCode::IFICMPLE
+      | OpCode::IFACMPEQ
+      | OpCode::IFACMPNE
+      | OpCode::GOTO
+      | OpCode::JSR
+      | OpCode::IFNULL
+      | OpCode::IFNONNULL
+      | OpCode::GOTOW
+      | OpCode::JSRW => {
+        check_operands(line, operands, 1)?;
+
+        self.code.push(opcode as ju1);
+        self.push_jump(
+          line,
+          pc,
+          &operands[0],
+          matches!(opcode, OpCode::GOTOW | OpCode::JSRW),
+        );
+      }
+      OpCode::GETSTATIC | OpCode::PUTSTATIC | OpCode::GETFIELD | OpCode::PUTFIELD => {
+        check_operands(line, operands, 2)?;
+
+        let (owner, name) = split_member(line, &operands[0])?;
+
+        let index = self.cp.add_field_ref(owner, name, &operands[1]);
+
+        self.code.push(opcode as ju1);
+        self.push_u2(index);
+      }
+      OpCode::INVOKEVIRTUAL | OpCode::INVOKESPECIAL | OpCode::INVOKESTATIC => {
+        // `interface` for a method of an interface
+        let (is_interface, operands) = match operands.first().map(String::as_str) {
+          Some("interface") if opcode != OpCode::INVOKEVIRTUAL => (true, &operands[1..]),
+          _ => (false, operands),
+        };
+
+        check_operands(line, operands, 1)?;
+
+        let (owner, name, descriptor) = split_method(line, &operands[0])?;
+
+        let index = if is_interface {
+          self.cp.add_interface_method_ref(owner, name, descriptor)
+        } else {
+          self.cp.add_method_ref(owner, name, descriptor)
+        };
+
+        self.code.push(opcode as ju1);
+        self.push_u2(index);
+      }
+      OpCode::INVOKEINTERFACE => {
+        if operands.is_empty() || operands.len() > 2 {
+          check_operands(line, operands, 1)?;
+        }
+
+        let (owner, name, descriptor) = split_method(line, &operands[0])?;
+
+        // the count is given by the descriptor unless it is explicit
+        let count = match operands.get(1) {
+          Some(count) => parse_int(line, count)?,
+          None => get_args_size(descriptor) as ju1 + 1,
+        };
+
+        let index = self.cp.add_interface_method_ref(owner, name, descriptor);
+
+        self.code.push(opcode as ju1);
+        self.push_u2(index);
+        self.code.extend([count, 0]);
+      }
+      OpCode::INVOKEDYNAMIC => {
+        if operands.len() < 2 {
+          check_operands(line, operands, 2)?;
+        }
+
+        let (name, descriptor) = split_descriptor(line, &operands[0])?;
+
+        let bootstrap_method = self
+          .bootstraps
+          .bootstrap_method(self.cp, line, &operands[1..])?;
+
+        let index = self
+          .cp
+          .add_invoke_dynamic(bootstrap_method, name, descriptor);
+
+        self.code.push(opcode as ju1);
+        self.push_u2(index);
+        self.push_u2(0);
+      }
+      OpCode::NEW | OpCode::ANEWARRAY | OpCode::CHECKCAST | OpCode::INSTANCEOF => {
+        check_operands(line, operands, 1)?;
+
+        let index = self.cp.add_class(&operands[0]);
+
+        self.code.push(opcode as ju1);
+        self.push_u2(index);
+      }
+      OpCode::MULTIANEWARRAY => {
+        check_operands(line, operands, 2)?;
+
+        let index = self.cp.add_class(&operands[0]);
+        let dimensions = parse_int(line, &operands[1])?;
+
+        self.code.push(opcode as ju1);
+        self.push_u2(index);
+        self.code.push(dimensions);
+      }
+      OpCode::NEWARRAY => {
+        check_operands(line, operands, 1)?;
+
+        let (_, atype) = ARRAY_TYPES
+          .iter()
+          .find(|(name, _)| *name == operands[0])
+          .ok_or_else(|| error(line, format!("Unknown array type {}", operands[0])))?;
+
+        self.code.extend([opcode as ju1, *atype]);
+      }
+      OpCode::TABLESWITCH => self.table_switch(line, operands, lines)?,
+      OpCode::LOOKUPSWITCH => self.lookup_switch(line, operands, lines)?,
+      _ => {
+        check_operands(line, operands, 0)?;
+
+        self.code.push(opcode as ju1);
+      }
+    }
+
+    Ok(())
+  }
+
+  // the operands of a switch are aligned on 4 bytes from the start of the code
+  fn align(&mut self) {
+    while !self.code.len().is_multiple_of(4) {
+      self.code.push(0);
+    }
+  }
+
+  // tableswitch <low> [<high>], then a label per line and `default : <label>`
+  fn table_switch(
+    &mut self,
+    line: usize,
+    operands: &[String],
+    lines: &mut IntoIter<Line>,
+  ) -> Result<()> {
+    if operands.is_empty() || operands.len() > 2 {
+      check_operands(line, operands, 1)?;
+    }
+
+    let low: i32 = parse_int(line, &operands[0])?;
+
+    let mut labels = vec![];
+
+    let default = loop {
+      let line = lines
+        .next()
+        .ok_or_else(|| error(line, "Missing default of tableswitch"))?;
+
+      if line.tokens[0].starts_with("default") {
+        break split_case(&line)?.1;
+      }
+
+      labels.extend(line.tokens);
+    };
+
+    let high = low + labels.len() as i32 - 1;
+
+    if let Some(expected) = operands.get(1) {
+      if parse_int::<i32>(line, expected)? != high {
+        return Err(error(
+          line,
+          format!("Expected labels from {} to {}", low, expected),
+        ));
+      }
+    }
+
+    let pc = self.code.len();
+
+    self.code.push(OpCode::TABLESWITCH as ju1);
+    self.align();
+    self.push_jump(line, pc, &default, true);
+    self.code.extend(low.to_be_bytes());
+    self.code.extend(high.to_be_bytes());
+
+    for label in labels {
+      self.push_jump(line, pc, &label, true);
+    }
+
+    Ok(())
+  }
+
+  // lookupswitch, then `<key> : <label>` per line and `default : <label>`
+  //
+  // The keys are kept in their order, even if they are not sorted.
+  fn lookup_switch(
+    &mut self,
+    line: usize,
+    operands: &[String],
+    lines: &mut IntoIter<Line>,
+  ) -> Result<()> {
+    check_operands(line, operands, 0)?;
+
+    let mut pairs = vec![];
+
+    let default = loop {
+      let line = lines
+        .next()
+        .ok_or_else(|| error(line, "Missing default of lookupswitch"))?;
+
+      let (key, label) = split_case(&line)?;
+
+      if key == "default" {
+        break label;
+      }
+
+      pairs.push((parse_int::<i32>(line.number, &key)?, label));
+    };
+
+    let pc = self.code.len();
+
+    self.code.push(OpCode::LOOKUPSWITCH as ju1);
+    self.align();
+    self.push_jump(line, pc, &default, true);
+    self.code.extend((pairs.len() as i32).to_be_bytes());
+
+    for (key, label) in pairs {
+      self.code.extend(key.to_be_bytes());
+      self.push_jump(line, pc, &label, true);
+    }
+
+    Ok(())
+  }
+}
+
+/// Declaration of the class, before its fields and methods
+struct Header {
+  access_flags: ju2,
+  name: Option<String>,
+  super_name: String,
+  interfaces: Vec<String>,
+  major_version: ju2,
+}
+
+fn builder<'a>(
+  builder: &'a mut Option<ClassBuilder>,
+  header: &Header,
+  line: usize,
+) -> Result<&'a mut ClassBuilder> {
+  if builder.is_none() {
+    let name = header
+      .name
+      .as_deref()
+      .ok_or_else(|| error(line, "Missing .class or .interface"))?;
+
+    let super_name = (name != "java/lang/Object").then_some(header.super_name.as_str());
+
+    let mut new = ClassBuilder::new(header.access_flags, name, super_name);
+
+    new.version(header.major_version);
+
+    for interface in &header.interfaces {
+      new.interface(interface);
+    }
+
+    *builder = Some(new);
+  }
+
+  Ok(builder.as_mut().unwrap())
+}
+
+/// Assemble a class from its text, in a syntax close to the one of Jasmin
+///
+/// A line holds a directive or an instruction, labels end with `:` and
+/// comments start with `;`. The header (`.class` or `.interface`, `.super`,
+/// `.implements`, `.version`) comes before the fields and the methods:
+///
+/// ```text
+/// .class public Answer
+/// .super java/lang/Object         ; java/lang/Object by default
+/// .version 61                     ; 49 by default (no StackMapTable needed)
+///
+/// .field private static counter I
+///
+/// .method public static answer(I)I
+///   .limit stack 2                ; 16 by default
+///   .limit locals 1               ; the size of the parameters by default
+///   .catch java/lang/Exception from Start to End using Handler
+///   Start:
+///   iload_0
+///   ifne Zero
+///   bipush 42
+///   ireturn
+///   .stack                        ; a full frame at the next instruction
+///     locals Integer
+///   .end stack
+///   Zero:
+///   ldc "string"                  ; or a number, or class <name>
+///   invokevirtual java/lang/String/length()I
+///   End:
+///   ireturn
+///   Handler:
+///   iconst_m1
+///   ireturn
+/// .end method
+/// ```
+///
+/// The operands are the ones of `javap` with the constants written in place:
+/// fields are `owner/name descriptor`, methods are `owner/name(descriptor)`
+/// (`invokestatic interface` for an interface method), branches take labels,
+/// `tableswitch <low>` and `lookupswitch` are followed by one label (or
+/// `<key> : <label>`) per line and end with `default : <label>`. `wide` is
+/// added when an index needs it, or explicitly before the instruction.
+///
+/// `invokedynamic <name(descriptor)> <owner/name(descriptor)>` is followed by
+/// the static arguments of the bootstrap method: numbers, strings,
+/// `class <name>`, `type <descriptor>` or `handle <kind> <member>` where the
+/// kind is the name of the instruction (`newinvokespecial` for a constructor)
+/// and the member is written like its operands.
+///
+/// `.dynamic <label> <name> <descriptor> <owner/name(descriptor)>` defines a
+/// dynamically-computed constant with the same static arguments, loaded by
+/// `ldc dynamic <label>` (`ldc2_w` for a long or a double) or passed as
+/// `dynamic <label>` to a bootstrap method.
+///
+/// # Returns
+///
+/// The content of the class file, whose format is not checked so that
+/// malformed classes can be assembled
+///
+/// ```
+/// let bytes = jvmcraft::assemble(
+///   "
+///   .class public Answer
+///   .method public static answer()I
+///     bipush 42
+///     ireturn
+///   .end method
+///   ",
+/// )
+/// .unwrap();
+///
+/// jvmcraft::parse_class_file(&bytes).unwrap();
+/// ```
+pub fn assemble(source: &str) -> Result<Vec<u8>> {
+  let lines = source
+    .lines()
+    .enumerate()
+    .map(|(i, line)| {
+      Ok(Line {
+        number: i + 1,
+        tokens: tokenize(i + 1, line)?,
+      })
+    })
+    .filter(|line| !matches!(line, Ok(Line { tokens, .. }) if tokens.is_empty()))
+    .collect::<Result<Vec<Line>>>()?;
+
+  let mut header = Header {
+    access_flags: 0,
+    name: None,
+    super_name: "java/lang/Object".to_string(),
+    interfaces: vec![],
+    major_version: DEFAULT_MAJOR_VERSION,
+  };
+
+  let mut class_builder: Option<ClassBuilder> = None;
+
+  let mut bootstraps = Bootstraps::default();
+
+  let mut lines = lines.into_iter();
+
+  while let Some(line) = lines.next() {
+    let number = line.number;
+    let operands = &line.tokens[1..];
+
+    let is_header = matches!(
+      line.tokens[0].as_str(),
+      ".class" | ".interface" | ".super" | ".implements" | ".version"
+    );
+
+    if is_header && class_builder.is_some() {
+      return Err(error(
+        number,
+        "The header must come before the fields and methods",
+      ));
+    }
+
+    match line.tokens[0].as_str() {
+      ".class" | ".interface" => {
+        let (access_flags, operands) = parse_flags(operands, CLASS_FLAGS);
+
+        check_operands(number, operands, 1)?;
+
+        header.access_flags = if line.tokens[0] == ".class" {
+          access_flags | ACC_SUPER
+        } else {
+          access_flags | ACC_INTERFACE | ACC_ABSTRACT
+        };
+        header.name = Some(operands[0].clone());
+      }
+      ".super" => {
+        check_operands(number, operands, 1)?;
+
+        header.super_name = operands[0].clone();
+      }
+      ".implements" => {
+        check_operands(number, operands, 1)?;
+
+        header.interfaces.push(operands[0].clone());
+      }
+      ".version" => {
+        check_operands(number, operands, 1)?;
+
+        header.major_version = parse_int(number, &operands[0])?;
+      }
+      ".field" => {
+        let (access_flags, operands) = parse_flags(operands, FIELD_FLAGS);
+
+        check_operands(number, operands, 2)?;
+
+        builder(&mut class_builder, &header, number)?.field(
+          access_flags,
+          &operands[0],
+          &operands[1],
+        );
+      }
+      ".method" => {
+        let (access_flags, operands) = parse_flags(operands, METHOD_FLAGS);
+
+        check_operands(number, operands, 1)?;
+
+        let (name, descriptor) = split_descriptor(number, &operands[0])?;
+
+        let builder = builder(&mut class_builder, &header, number)?;
+
+        let code = MethodAssembler::new(builder.constant_pool(), &mut bootstraps).assemble(
+          &mut lines,
+          access_flags,
+          descriptor,
+        )?;
+
+        builder.method(access_flags, name, descriptor, code);
+      }
+      ".dynamic" => {
+        let builder = builder(&mut class_builder, &header, number)?;
+
+        bootstraps.dynamic(builder.constant_pool(), number, operands)?;
+      }
+      token => return Err(error(number, format!("Unexpected {}", token))),
+    }
+  }
+
+  let line = source.lines().count();
+
+  let builder = builder(&mut class_builder, &header, line)?;
+
+  if !bootstraps.methods.is_empty() {
+    builder.attribute(
+      "BootstrapMethods",
+      AttributeInfoInfoEnum::BootstrapMethods(BootstrapMethods::define(bootstraps.methods)),
+    );
+  }
+
+  Ok(builder.to_bytes())
+}
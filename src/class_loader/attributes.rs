@@ -1,17 +1,21 @@
-use crate::{class_loader::constant_pool::*, notimpl, runtime::types, utils::*};
+use crate::{
+  class_loader::constant_pool::*,
+  runtime::{errors, types},
+  utils::*,
+};
 use color_eyre::eyre::{eyre, Result};
 
 use super::loader::ClassLoader;
 
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
-pub(crate) struct ConstantValue {
+pub struct ConstantValue {
   constantvalue_index: ju2,
 }
 
 impl ConstantValue {
   fn parse(bytes: &[u8]) -> Result<(ConstantValue, usize)> {
-    let constantvalue_index = ju2_from_bytes(&bytes[0..2])?;
+    let constantvalue_index = ju2_at(bytes, 0)?;
 
     Ok((
       ConstantValue {
@@ -20,20 +24,40 @@ impl ConstantValue {
       2,
     ))
   }
+
+  pub(crate) fn get_constantvalue_index(&self) -> ju2 {
+    self.constantvalue_index
+  }
+}
+
+impl WriteInfo for ConstantValue {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend(self.constantvalue_index.to_be_bytes());
+  }
 }
 
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
-pub(crate) struct SourceFile {
+pub struct SourceFile {
   sourcefile_index: ju2,
 }
 
 impl SourceFile {
   fn parse(bytes: &[u8]) -> Result<(SourceFile, usize)> {
-    let sourcefile_index = ju2_from_bytes(&bytes[0..2])?;
+    let sourcefile_index = ju2_at(bytes, 0)?;
 
     Ok((SourceFile { sourcefile_index }, 2))
   }
+
+  pub(crate) fn get_sourcefile_index(&self) -> ju2 {
+    self.sourcefile_index
+  }
+}
+
+impl WriteInfo for SourceFile {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend(self.sourcefile_index.to_be_bytes());
+  }
 }
 
 #[derive(Debug, Clone)]
@@ -42,18 +66,41 @@ pub(crate) struct ExceptionTableEntry {
   start_pc: ju2,
   end_pc: ju2,
   handler_pc: ju2,
+  catch_type_index: ju2,
   catch_type: String,
 }
 
 impl ExceptionTableEntry {
+  /// New handler of `catch_type` (any exception if `None`), added to the constant pool `cp`
+  pub(crate) fn define(
+    cp: &mut ConstantPool,
+    start_pc: ju2,
+    end_pc: ju2,
+    handler_pc: ju2,
+    catch_type: Option<&str>,
+  ) -> ExceptionTableEntry {
+    let (catch_type_index, catch_type) = match catch_type {
+      Some(catch_type) => (cp.add_class(catch_type), catch_type.to_string()),
+      None => (0, "finally".to_string()),
+    };
+
+    ExceptionTableEntry {
+      start_pc,
+      end_pc,
+      handler_pc,
+      catch_type_index,
+      catch_type,
+    }
+  }
+
   fn parse(bytes: &[u8], cp: &ConstantPool) -> Result<(ExceptionTableEntry, usize)> {
-    let start_pc = ju2_from_bytes(&bytes[0..2])?;
+    let start_pc = ju2_at(bytes, 0)?;
 
-    let end_pc = ju2_from_bytes(&bytes[2..4])?;
+    let end_pc = ju2_at(bytes, 2)?;
 
-    let handler_pc = ju2_from_bytes(&bytes[4..6])?;
+    let handler_pc = ju2_at(bytes, 4)?;
 
-    let catch_type_index = ju2_from_bytes(&bytes[6..8])?;
+    let catch_type_index = ju2_at(bytes, 6)?;
     let catch_type = if catch_type_index == 0 {
       "finally".to_string()
     } else {
@@ -65,6 +112,7 @@ impl ExceptionTableEntry {
         start_pc,
         end_pc,
         handler_pc,
+        catch_type_index,
         catch_type,
       },
       8,
@@ -79,14 +127,40 @@ impl ExceptionTableEntry {
   ) -> Result<bool> {
     Ok(
       pc >= self.start_pc
-        && pc <= self.end_pc
+        && pc < self.end_pc
         && types::Type::check_type(loader, &self.catch_type, exception_class)?,
     )
   }
 
+  pub(crate) fn get_start_pc(&self) -> ju2 {
+    self.start_pc
+  }
+
+  pub(crate) fn get_end_pc(&self) -> ju2 {
+    self.end_pc
+  }
+
   pub(crate) fn get_handler_pc(&self) -> ju2 {
     self.handler_pc
   }
+
+  // `finally` for a handler catching any exception
+  pub(crate) fn get_catch_type(&self) -> &str {
+    &self.catch_type
+  }
+
+  pub(crate) fn get_catch_type_index(&self) -> ju2 {
+    self.catch_type_index
+  }
+}
+
+impl WriteInfo for ExceptionTableEntry {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend(self.start_pc.to_be_bytes());
+    bytes.extend(self.end_pc.to_be_bytes());
+    bytes.extend(self.handler_pc.to_be_bytes());
+    bytes.extend(self.catch_type_index.to_be_bytes());
+  }
 }
 
 #[derive(Debug, Clone)]
@@ -101,33 +175,57 @@ pub struct Code {
 }
 
 impl Code {
-  fn parse(bytes: &[u8], cp: &ConstantPool) -> Result<(Code, usize)> {
-    let max_stack = ju2_from_bytes(&bytes[0..2])?;
+  /// Code of a method without exception handlers nor attributes
+  pub fn new(max_stack: ju2, max_locals: ju2, code: Vec<ju1>) -> Code {
+    Code {
+      max_stack,
+      max_locals,
+      code_length: code.len() as ju4,
+      code,
+      exception_table: vec![],
+      attributes: Attributes::new(vec![]),
+    }
+  }
 
-    let max_locals = ju2_from_bytes(&bytes[2..4])?;
+  /// Code of a method with its exception handlers and attributes (e.g. `StackMapTable`)
+  pub(crate) fn define(
+    max_stack: ju2,
+    max_locals: ju2,
+    code: Vec<ju1>,
+    exception_table: Vec<ExceptionTableEntry>,
+    attributes: Vec<AttributeInfo>,
+  ) -> Code {
+    Code {
+      max_stack,
+      max_locals,
+      code_length: code.len() as ju4,
+      code,
+      exception_table,
+      attributes: Attributes::new(attributes),
+    }
+  }
 
-    let code_length = ju4_from_bytes(&bytes[4..8])?;
+  fn parse(bytes: &[u8], cp: &ConstantPool) -> Result<(Code, usize)> {
+    let max_stack = ju2_at(bytes, 0)?;
 
-    let mut index = 8;
+    let max_locals = ju2_at(bytes, 2)?;
 
-    let code_slice = &bytes[index..];
+    let code_length = ju4_at(bytes, 4)?;
 
-    let mut code: Vec<ju1> = vec![];
+    let mut index = 8;
 
-    for code_item in code_slice.iter().take(code_length as usize) {
-      code.push(*code_item);
-    }
+    let code = get_slice(bytes, index, code_length as usize)?.to_vec();
 
     index += code_length as usize;
 
-    let exception_table_length = ju2_from_bytes(get_slice_arr(bytes, index, 2))?;
+    let exception_table_length = ju2_at(bytes, index)?;
 
     index += 2;
 
     let mut exception_table: Vec<ExceptionTableEntry> = vec![];
 
     for _ in 0..(exception_table_length as usize) {
-      let slice = &bytes[index..];
+      let slice = get_tail(bytes, index)?;
 
       let (excep, bytes_read) = ExceptionTableEntry::parse(slice, cp)?;
 
@@ -136,7 +234,7 @@ impl Code {
       exception_table.push(excep);
     }
 
-    let (attributes, attributes_size) = Attributes::parse(&bytes[index..], cp)?;
+    let (attributes, attributes_size) = Attributes::parse(get_tail(bytes, index)?, cp)?;
 
     index += attributes_size;
 
@@ -153,10 +251,22 @@ impl Code {
     ))
   }
 
+  pub(crate) fn get_max_stack(&self) -> ju2 {
+    self.max_stack
+  }
+
   pub(crate) fn get_max_locals(&self) -> ju2 {
     self.max_locals
   }
 
+  pub(crate) fn get_exception_table(&self) -> &Vec<ExceptionTableEntry> {
+    &self.exception_table
+  }
+
+  pub(crate) fn get_stack_map_table(&self) -> Option<&StackMapTable> {
+    self.attributes.get_stack_map_table()
+  }
+
   pub(crate) fn _get_attribute(&self, index: usize) -> &AttributeInfo {
     self.attributes.get(index)
   }
@@ -178,6 +288,27 @@ impl Code {
     }
     Ok(None)
   }
+
+  pub(crate) fn get_attributes(&self) -> &Attributes {
+    &self.attributes
+  }
+}
+
+impl WriteInfo for Code {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend(self.max_stack.to_be_bytes());
+    bytes.extend(self.max_locals.to_be_bytes());
+    bytes.extend((self.code.len() as ju4).to_be_bytes());
+    bytes.extend(&self.code);
+
+    bytes.extend((self.exception_table.len() as ju2).to_be_bytes());
+
+    for entry in &self.exception_table {
+      entry.write(bytes);
+    }
+
+    self.attributes.write(bytes);
+  }
 }
 
 #[derive(Debug, Clone)]
@@ -187,6 +318,16 @@ pub struct LineNumberTableEntry {
   line_number: ju2,
 }
 
+impl LineNumberTableEntry {
+  pub(crate) fn get_start_pc(&self) -> ju2 {
+    self.start_pc
+  }
+
+  pub(crate) fn get_line_number(&self) -> ju2 {
+    self.line_number
+  }
+}
+
 // https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.7.12
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -197,18 +338,18 @@ pub struct LineNumberTable {
 
 impl LineNumberTable {
   fn parse(bytes: &[u8]) -> Result<(LineNumberTable, usize)> {
-    let line_number_table_length = ju2_from_bytes(&bytes[0..2])?;
+    let line_number_table_length = ju2_at(bytes, 0)?;
 
     let mut line_number_table: Vec<LineNumberTableEntry> = vec![];
 
     let mut index = 2;
 
     for _ in 0..line_number_table_length {
-      let slice = &bytes[index..];
+      let slice = get_tail(bytes, index)?;
 
-      let start_pc = ju2_from_bytes(&slice[0..2])?;
+      let start_pc = ju2_at(slice, 0)?;
 
-      let line_number = ju2_from_bytes(&slice[2..4])?;
+      let line_number = ju2_at(slice, 2)?;
 
       index += 4;
 
@@ -223,9 +364,24 @@ impl LineNumberTable {
         line_number_table_length,
         line_number_table,
       },
-      (2 + line_number_table_length * 4) as usize,
+      index,
     ))
   }
+
+  pub(crate) fn get_entries(&self) -> &Vec<LineNumberTableEntry> {
+    &self.line_number_table
+  }
+}
+
+impl WriteInfo for LineNumberTable {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend((self.line_number_table.len() as ju2).to_be_bytes());
+
+    for entry in &self.line_number_table {
+      bytes.extend(entry.start_pc.to_be_bytes());
+      bytes.extend(entry.line_number.to_be_bytes());
+    }
+  }
 }
 
 // https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.7.28
@@ -237,7 +393,7 @@ pub struct NestHost {
 
 impl NestHost {
   fn parse(bytes: &[u8]) -> Result<(NestHost, usize)> {
-    let host_class_index = ju2_from_bytes(&bytes[0..2])?;
+    let host_class_index = ju2_at(bytes, 0)?;
 
     Ok((NestHost { host_class_index }, 2))
   }
@@ -247,6 +403,12 @@ impl NestHost {
   }
 }
 
+impl WriteInfo for NestHost {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend(self.host_class_index.to_be_bytes());
+  }
+}
+
 // https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.7.29
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -257,16 +419,16 @@ pub struct NestMembers {
 
 impl NestMembers {
   fn parse(bytes: &[u8]) -> Result<(NestMembers, usize)> {
-    let number_of_classes = ju2_from_bytes(&bytes[0..2])?;
+    let number_of_classes = ju2_at(bytes, 0)?;
 
     let mut classes: Vec<ju2> = vec![];
 
     let mut index = 2;
 
     for _ in 0..number_of_classes {
-      let slice = &bytes[index..];
+      let slice = get_tail(bytes, index)?;
 
-      let class = ju2_from_bytes(&slice[0..2])?;
+      let class = ju2_at(slice, 0)?;
 
       classes.push(class);
 
@@ -281,6 +443,20 @@ impl NestMembers {
       index,
     ))
   }
+
+  pub(crate) fn get_classes(&self) -> &Vec<ju2> {
+    &self.classes
+  }
+}
+
+impl WriteInfo for NestMembers {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend((self.classes.len() as ju2).to_be_bytes());
+
+    for class in &self.classes {
+      bytes.extend(class.to_be_bytes());
+    }
+  }
 }
 
 #[derive(Debug, Clone)]
@@ -288,17 +464,18 @@ impl NestMembers {
 pub struct InnerClassesClass {
   inner_class_info_index: ju2,
   outer_class_info_index: ju2,
+  inner_name_index: ju2,
   inner_name: String,
   inner_class_access_flags: ju2,
 }
 
 impl InnerClassesClass {
   fn parse(bytes: &[u8], cp: &ConstantPool) -> Result<(InnerClassesClass, usize)> {
-    let inner_class_info_index = ju2_from_bytes(&bytes[0..2])?;
+    let inner_class_info_index = ju2_at(bytes, 0)?;
 
-    let outer_class_info_index = ju2_from_bytes(&bytes[2..4])?;
+    let outer_class_info_index = ju2_at(bytes, 2)?;
 
-    let inner_name_index = ju2_from_bytes(&bytes[4..6])?;
+    let inner_name_index = ju2_at(bytes, 4)?;
     let inner_name = if inner_name_index == 0 {
       // anonymous
       "".to_string()
@@ -306,12 +483,13 @@ impl InnerClassesClass {
       cp.resolve_name(inner_name_index)?
     };
 
-    let inner_class_access_flags = ju2_from_bytes(&bytes[6..8])?;
+    let inner_class_access_flags = ju2_at(bytes, 6)?;
 
     Ok((
       InnerClassesClass {
         inner_class_info_index,
         outer_class_info_index,
+        inner_name_index,
         inner_name,
         inner_class_access_flags,
       },
@@ -322,26 +500,51 @@ impl InnerClassesClass {
   pub(crate) fn get_name(&self) -> &str {
     &self.inner_name
   }
+
+  pub(crate) fn get_inner_class_info_index(&self) -> ju2 {
+    self.inner_class_info_index
+  }
+
+  pub(crate) fn get_outer_class_info_index(&self) -> ju2 {
+    self.outer_class_info_index
+  }
+
+  pub(crate) fn get_inner_name_index(&self) -> ju2 {
+    self.inner_name_index
+  }
+
+  pub(crate) fn get_inner_class_access_flags(&self) -> ju2 {
+    self.inner_class_access_flags
+  }
+}
+
+impl WriteInfo for InnerClassesClass {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend(self.inner_class_info_index.to_be_bytes());
+    bytes.extend(self.outer_class_info_index.to_be_bytes());
+    bytes.extend(self.inner_name_index.to_be_bytes());
+    bytes.extend(self.inner_class_access_flags.to_be_bytes());
+  }
 }
 
 // https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.7.6
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
-pub(crate) struct InnerClasses {
+pub struct InnerClasses {
   number_of_classes: ju2,
   classes: Vec<InnerClassesClass>,
 }
 
 impl InnerClasses {
   fn parse(bytes: &[u8], cp: &ConstantPool) -> Result<(InnerClasses, usize)> {
-    let number_of_classes = ju2_from_bytes(&bytes[0..2])?;
+    let number_of_classes = ju2_at(bytes, 0)?;
 
     let mut classes: Vec<InnerClassesClass> = vec![];
 
     let mut index = 2;
 
     for _ in 0..number_of_classes {
-      let slice = &bytes[index..];
+      let slice = get_tail(bytes, index)?;
 
       let (class, bytes_read) = InnerClassesClass::parse(slice, cp)?;
 
@@ -364,6 +567,16 @@ impl InnerClasses {
   }
 }
 
+impl WriteInfo for InnerClasses {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend((self.classes.len() as ju2).to_be_bytes());
+
+    for class in &self.classes {
+      class.write(bytes);
+    }
+  }
+}
+
 // https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.7.9
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -373,10 +586,20 @@ pub struct Signature {
 
 impl Signature {
   fn parse(bytes: &[u8]) -> Result<(Signature, usize)> {
-    let signature_index = ju2_from_bytes(&bytes[0..2])?;
+    let signature_index = ju2_at(bytes, 0)?;
 
     Ok((Signature { signature_index }, 2))
   }
+
+  pub(crate) fn get_signature_index(&self) -> ju2 {
+    self.signature_index
+  }
+}
+
+impl WriteInfo for Signature {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend(self.signature_index.to_be_bytes());
+  }
 }
 
 // https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.7.5
@@ -388,16 +611,16 @@ pub struct Exceptions {
 
 impl Exceptions {
   fn parse(bytes: &[u8]) -> Result<(Exceptions, usize)> {
-    let number_of_exceptions = ju2_from_bytes(&bytes[0..2])?;
+    let number_of_exceptions = ju2_at(bytes, 0)?;
 
     let mut exception_index_table: Vec<ju2> = vec![];
 
     let mut index = 2;
 
     for _ in 0..number_of_exceptions {
-      let slice = &bytes[index..];
+      let slice = get_tail(bytes, index)?;
 
-      let excep = ju2_from_bytes(&slice[0..2])?;
+      let excep = ju2_at(slice, 0)?;
 
       exception_index_table.push(excep);
 
@@ -411,6 +634,20 @@ impl Exceptions {
       index,
     ))
   }
+
+  pub(crate) fn get_exception_index_table(&self) -> &Vec<ju2> {
+    &self.exception_index_table
+  }
+}
+
+impl WriteInfo for Exceptions {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend((self.exception_index_table.len() as ju2).to_be_bytes());
+
+    for exception in &self.exception_index_table {
+      bytes.extend(exception.to_be_bytes());
+    }
+  }
 }
 
 #[derive(Debug, Clone)]
@@ -440,18 +677,18 @@ pub struct ElementValue {
 
 impl ElementValue {
   fn parse(bytes: &[u8], cp: &ConstantPool) -> Result<(ElementValue, usize)> {
-    let tag = bytes[0];
+    let tag = ju1_at(bytes, 0)?;
 
     let (value, bytes_read) = match tag {
       b'B' | b'C' | b'D' | b'F' | b'I' | b'J' | b'S' | b'Z' | b's' => {
-        let const_value_index = ju2_from_bytes(&bytes[1..3])?;
+        let const_value_index = ju2_at(bytes, 1)?;
 
         (ElementValueEnum::ConstValue { const_value_index }, 1 + 2)
       }
       b'e' => {
-        let type_name_index = ju2_from_bytes(&bytes[1..3])?;
+        let type_name_index = ju2_at(bytes, 1)?;
 
-        let const_name_index = ju2_from_bytes(&bytes[3..5])?;
+        let const_name_index = ju2_at(bytes, 3)?;
 
         (
           ElementValueEnum::EnumConstValue {
@@ -462,12 +699,12 @@ impl ElementValue {
         )
       }
       b'c' => {
-        let class_info_index = ju2_from_bytes(&bytes[1..3])?;
+        let class_info_index = ju2_at(bytes, 1)?;
 
         (ElementValueEnum::ClassInfo { class_info_index }, 1 + 2)
       }
       b'@' => {
-        let (annotation, bytes_read) = Annotation::parse(&bytes[1..], cp)?;
+        let (annotation, bytes_read) = Annotation::parse(get_tail(bytes, 1)?, cp)?;
 
         (
           ElementValueEnum::AnnotationValue(annotation),
@@ -475,14 +712,14 @@ impl ElementValue {
         )
       }
       b'[' => {
-        let num_values = ju2_from_bytes(&bytes[1..3])?;
+        let num_values = ju2_at(bytes, 1)?;
 
         let mut values: Vec<ElementValue> = vec![];
 
         let mut index = 3;
 
         for _ in 0..num_values {
-          let (element, bytes_read) = ElementValue::parse(&bytes[index..], cp)?;
+          let (element, bytes_read) = ElementValue::parse(get_tail(bytes, index)?, cp)?;
 
           index += bytes_read;
 
@@ -501,6 +738,45 @@ impl ElementValue {
 
     Ok((ElementValue { tag, value }, bytes_read))
   }
+
+  pub(crate) fn get_tag(&self) -> ju1 {
+    self.tag
+  }
+
+  pub(crate) fn get_value(&self) -> &ElementValueEnum {
+    &self.value
+  }
+}
+
+impl WriteInfo for ElementValue {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.push(self.tag);
+
+    match &self.value {
+      ElementValueEnum::ConstValue { const_value_index } => {
+        bytes.extend(const_value_index.to_be_bytes());
+      }
+      ElementValueEnum::EnumConstValue {
+        type_name_index,
+        const_name_index,
+      } => {
+        bytes.extend(type_name_index.to_be_bytes());
+        bytes.extend(const_name_index.to_be_bytes());
+      }
+      ElementValueEnum::ClassInfo { class_info_index } => {
+        bytes.extend(class_info_index.to_be_bytes());
+      }
+      ElementValueEnum::AnnotationValue(annotation) => annotation.write(bytes),
+      ElementValueEnum::ArrayValue(values) => {
+        bytes.extend((values.len() as ju2).to_be_bytes());
+
+        for value in values {
+          value.write(bytes);
+        }
+      }
+      ElementValueEnum::NotRecognized => {}
+    }
+  }
 }
 
 #[derive(Debug, Clone)]
@@ -512,9 +788,9 @@ pub struct ElementValuePair {
 
 impl ElementValuePair {
   fn parse(bytes: &[u8], cp: &ConstantPool) -> Result<(ElementValuePair, usize)> {
-    let element_name_index = ju2_from_bytes(&bytes[0..2])?;
+    let element_name_index = ju2_at(bytes, 0)?;
 
-    let (value, bytes_read) = ElementValue::parse(&bytes[2..], cp)?;
+    let (value, bytes_read) = ElementValue::parse(get_tail(bytes, 2)?, cp)?;
 
     Ok((
       ElementValuePair {
@@ -524,11 +800,28 @@ impl ElementValuePair {
       2 + bytes_read,
     ))
   }
+
+  pub(crate) fn get_element_name_index(&self) -> ju2 {
+    self.element_name_index
+  }
+
+  pub(crate) fn get_value(&self) -> &ElementValue {
+    &self.value
+  }
+}
+
+impl WriteInfo for ElementValuePair {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend(self.element_name_index.to_be_bytes());
+
+    self.value.write(bytes);
+  }
 }
 
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub struct Annotation {
+  type_index: ju2,
   annotation_type: String,
   num_element_value_pairs: ju2,
   element_value_pairs: Vec<ElementValuePair>,
@@ -536,17 +829,17 @@ pub struct Annotation {
 
 impl Annotation {
   fn parse(bytes: &[u8], cp: &ConstantPool) -> Result<(Annotation, usize)> {
-    let type_index = ju2_from_bytes(&bytes[0..2])?;
+    let type_index = ju2_at(bytes, 0)?;
     let annotation_type = cp.resolve_name(type_index)?;
 
-    let num_element_value_pairs = ju2_from_bytes(&bytes[2..4])?;
+    let num_element_value_pairs = ju2_at(bytes, 2)?;
 
     let mut element_value_pairs: Vec<ElementValuePair> = vec![];
 
     let mut index = 4;
 
     for _ in 0..num_element_value_pairs {
-      let (element, bytes_read) = ElementValuePair::parse(&bytes[index..], cp)?;
+      let (element, bytes_read) = ElementValuePair::parse(get_tail(bytes, index)?, cp)?;
 
       index += bytes_read;
 
@@ -555,6 +848,7 @@ impl Annotation {
 
     Ok((
       Annotation {
+        type_index,
         annotation_type,
         num_element_value_pairs,
         element_value_pairs,
@@ -566,26 +860,484 @@ impl Annotation {
   pub(crate) fn get_annotation_type(&self) -> &str {
     &self.annotation_type
   }
+
+  pub(crate) fn get_type_index(&self) -> ju2 {
+    self.type_index
+  }
+
+  pub(crate) fn get_element_value_pairs(&self) -> &Vec<ElementValuePair> {
+    &self.element_value_pairs
+  }
+}
+
+impl WriteInfo for Annotation {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend(self.type_index.to_be_bytes());
+    bytes.extend((self.element_value_pairs.len() as ju2).to_be_bytes());
+
+    for pair in &self.element_value_pairs {
+      pair.write(bytes);
+    }
+  }
+}
+
+// https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.7.16
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct RuntimeVisibleAnnotations {
+  num_annotations: ju2,
+  annotations: Vec<Annotation>,
+  // content of the attribute, as read by `AnnotationParser`
+  raw: Vec<ju1>,
+}
+
+impl RuntimeVisibleAnnotations {
+  fn parse(bytes: &[u8], cp: &ConstantPool) -> Result<(RuntimeVisibleAnnotations, usize)> {
+    let num_annotations = ju2_at(bytes, 0)?;
+
+    let mut annotations: Vec<Annotation> = vec![];
+
+    let mut index = 2;
+
+    for _ in 0..num_annotations {
+      let (annotation, bytes_read) = Annotation::parse(get_tail(bytes, index)?, cp)?;
+
+      index += bytes_read;
+
+      annotations.push(annotation);
+    }
+
+    Ok((
+      RuntimeVisibleAnnotations {
+        num_annotations,
+        annotations,
+        raw: bytes[..index].to_vec(),
+      },
+      index,
+    ))
+  }
+
+  pub(crate) fn has_polymorphic_signature(&self) -> bool {
+    for ann in &self.annotations {
+      if ann.get_annotation_type() == "Ljava/lang/invoke/MethodHandle$PolymorphicSignature;" {
+        return true;
+      }
+    }
+    false
+  }
+
+  pub(crate) fn get_annotations(&self) -> &Vec<Annotation> {
+    &self.annotations
+  }
+
+  pub(crate) fn get_raw(&self) -> &[ju1] {
+    &self.raw
+  }
+}
+
+impl WriteInfo for RuntimeVisibleAnnotations {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend((self.annotations.len() as ju2).to_be_bytes());
+
+    for annotation in &self.annotations {
+      annotation.write(bytes);
+    }
+  }
+}
+
+// https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.7.17
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct RuntimeInvisibleAnnotations {
+  num_annotations: ju2,
+  annotations: Vec<Annotation>,
+  // content of the attribute, as read by `AnnotationParser`
+  raw: Vec<ju1>,
+}
+
+impl RuntimeInvisibleAnnotations {
+  fn parse(bytes: &[u8], cp: &ConstantPool) -> Result<(RuntimeInvisibleAnnotations, usize)> {
+    let num_annotations = ju2_at(bytes, 0)?;
+
+    let mut annotations: Vec<Annotation> = vec![];
+
+    let mut index = 2;
+
+    for _ in 0..num_annotations {
+      let (annotation, bytes_read) = Annotation::parse(get_tail(bytes, index)?, cp)?;
+
+      index += bytes_read;
+
+      annotations.push(annotation);
+    }
+
+    Ok((
+      RuntimeInvisibleAnnotations {
+        num_annotations,
+        annotations,
+        raw: bytes[..index].to_vec(),
+      },
+      index,
+    ))
+  }
+
+  pub(crate) fn get_annotations(&self) -> &Vec<Annotation> {
+    &self.annotations
+  }
+
+  pub(crate) fn get_raw(&self) -> &[ju1] {
+    &self.raw
+  }
+}
+
+impl WriteInfo for RuntimeInvisibleAnnotations {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend((self.annotations.len() as ju2).to_be_bytes());
+
+    for annotation in &self.annotations {
+      annotation.write(bytes);
+    }
+  }
+}
+
+// https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.7.18
+//
+// Content of both RuntimeVisibleParameterAnnotations and
+// RuntimeInvisibleParameterAnnotations
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct ParameterAnnotations {
+  num_parameters: ju1,
+  // annotations of each parameter
+  parameter_annotations: Vec<Vec<Annotation>>,
+  raw: Vec<ju1>,
+}
+
+impl ParameterAnnotations {
+  fn parse(bytes: &[u8], cp: &ConstantPool) -> Result<(ParameterAnnotations, usize)> {
+    let num_parameters = ju1_at(bytes, 0)?;
+
+    let mut parameter_annotations: Vec<Vec<Annotation>> = vec![];
+
+    let mut index = 1;
+
+    for _ in 0..num_parameters {
+      let num_annotations = ju2_at(bytes, index)?;
+
+      index += 2;
+
+      let mut annotations: Vec<Annotation> = vec![];
+
+      for _ in 0..num_annotations {
+        let (annotation, bytes_read) = Annotation::parse(get_tail(bytes, index)?, cp)?;
+
+        index += bytes_read;
+
+        annotations.push(annotation);
+      }
+
+      parameter_annotations.push(annotations);
+    }
+
+    Ok((
+      ParameterAnnotations {
+        num_parameters,
+        parameter_annotations,
+        raw: bytes[..index].to_vec(),
+      },
+      index,
+    ))
+  }
+
+  pub(crate) fn get_parameter_annotations(&self) -> &Vec<Vec<Annotation>> {
+    &self.parameter_annotations
+  }
+
+  pub(crate) fn get_raw(&self) -> &[ju1] {
+    &self.raw
+  }
+}
+
+impl WriteInfo for ParameterAnnotations {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.push(self.parameter_annotations.len() as ju1);
+
+    for annotations in &self.parameter_annotations {
+      bytes.extend((annotations.len() as ju2).to_be_bytes());
+
+      for annotation in annotations {
+        annotation.write(bytes);
+      }
+    }
+  }
+}
+
+// https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.7.20.1
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub enum TargetInfo {
+  TypeParameter {
+    /* 0x00-0x01 */ type_parameter_index: ju1,
+  },
+  Supertype {
+    /* 0x10, 65535 for the superclass */ supertype_index: ju2,
+  },
+  TypeParameterBound {
+    /* 0x11-0x12 */ type_parameter_index: ju1,
+    bound_index: ju1,
+  },
+  Empty, /* 0x13-0x15 */
+  FormalParameter {
+    /* 0x16 */ formal_parameter_index: ju1,
+  },
+  Throws {
+    /* 0x17 */ throws_type_index: ju2,
+  },
+  Localvar {
+    /* 0x40-0x41, (start_pc, length, index) of each range */
+    table: Vec<(ju2, ju2, ju2)>,
+  },
+  Catch {
+    /* 0x42 */ exception_table_index: ju2,
+  },
+  Offset {
+    /* 0x43-0x46 */ offset: ju2,
+  },
+  TypeArgument {
+    /* 0x47-0x4B */ offset: ju2,
+    type_argument_index: ju1,
+  },
+}
+
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct TypeAnnotation {
+  target_type: ju1,
+  target_info: TargetInfo,
+  // (type_path_kind, type_argument_index) of each step of the path
+  type_path: Vec<(ju1, ju1)>,
+  annotation: Annotation,
+}
+
+impl TypeAnnotation {
+  fn parse(bytes: &[u8], cp: &ConstantPool) -> Result<(TypeAnnotation, usize)> {
+    let target_type = ju1_at(bytes, 0)?;
+
+    let (target_info, mut index) = match target_type {
+      0x00 | 0x01 => {
+        let type_parameter_index = ju1_at(bytes, 1)?;
+
+        (
+          TargetInfo::TypeParameter {
+            type_parameter_index,
+          },
+          2,
+        )
+      }
+      0x10 => {
+        let supertype_index = ju2_at(bytes, 1)?;
+
+        (TargetInfo::Supertype { supertype_index }, 3)
+      }
+      0x11 | 0x12 => {
+        let type_parameter_index = ju1_at(bytes, 1)?;
+
+        let bound_index = ju1_at(bytes, 2)?;
+
+        (
+          TargetInfo::TypeParameterBound {
+            type_parameter_index,
+            bound_index,
+          },
+          3,
+        )
+      }
+      0x13..=0x15 => (TargetInfo::Empty, 1),
+      0x16 => {
+        let formal_parameter_index = ju1_at(bytes, 1)?;
+
+        (
+          TargetInfo::FormalParameter {
+            formal_parameter_index,
+          },
+          2,
+        )
+      }
+      0x17 => {
+        let throws_type_index = ju2_at(bytes, 1)?;
+
+        (TargetInfo::Throws { throws_type_index }, 3)
+      }
+      0x40 | 0x41 => {
+        let table_length = ju2_at(bytes, 1)?;
+
+        let mut table = vec![];
+
+        let mut index = 3;
+
+        for _ in 0..table_length {
+          table.push((
+            ju2_at(bytes, index)?,
+            ju2_at(bytes, index + 2)?,
+            ju2_at(bytes, index + 4)?,
+          ));
+
+          index += 6;
+        }
+
+        (TargetInfo::Localvar { table }, index)
+      }
+      0x42 => {
+        let exception_table_index = ju2_at(bytes, 1)?;
+
+        (
+          TargetInfo::Catch {
+            exception_table_index,
+          },
+          3,
+        )
+      }
+      0x43..=0x46 => {
+        let offset = ju2_at(bytes, 1)?;
+
+        (TargetInfo::Offset { offset }, 3)
+      }
+      0x47..=0x4B => {
+        let offset = ju2_at(bytes, 1)?;
+
+        let type_argument_index = ju1_at(bytes, 3)?;
+
+        (
+          TargetInfo::TypeArgument {
+            offset,
+            type_argument_index,
+          },
+          4,
+        )
+      }
+      _ => {
+        return Err(eyre!(errors::JavaException::ClassFormatError(format!(
+          "Invalid target type 0x{:02x} in type annotation",
+          target_type
+        ))))
+      }
+    };
+
+    let path_length = ju1_at(bytes, index)?;
+
+    index += 1;
+
+    let mut type_path = vec![];
+
+    for _ in 0..path_length {
+      type_path.push((ju1_at(bytes, index)?, ju1_at(bytes, index + 1)?));
+
+      index += 2;
+    }
+
+    let (annotation, bytes_read) = Annotation::parse(get_tail(bytes, index)?, cp)?;
+
+    index += bytes_read;
+
+    Ok((
+      TypeAnnotation {
+        target_type,
+        target_info,
+        type_path,
+        annotation,
+      },
+      index,
+    ))
+  }
+
+  pub(crate) fn get_target_type(&self) -> ju1 {
+    self.target_type
+  }
+
+  pub(crate) fn get_target_info(&self) -> &TargetInfo {
+    &self.target_info
+  }
+
+  pub(crate) fn get_type_path(&self) -> &Vec<(ju1, ju1)> {
+    &self.type_path
+  }
+
+  pub(crate) fn get_annotation(&self) -> &Annotation {
+    &self.annotation
+  }
+}
+
+impl WriteInfo for TypeAnnotation {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.push(self.target_type);
+
+    match &self.target_info {
+      TargetInfo::TypeParameter {
+        type_parameter_index,
+      } => bytes.push(*type_parameter_index),
+      TargetInfo::Supertype { supertype_index } => bytes.extend(supertype_index.to_be_bytes()),
+      TargetInfo::TypeParameterBound {
+        type_parameter_index,
+        bound_index,
+      } => bytes.extend([*type_parameter_index, *bound_index]),
+      TargetInfo::Empty => {}
+      TargetInfo::FormalParameter {
+        formal_parameter_index,
+      } => bytes.push(*formal_parameter_index),
+      TargetInfo::Throws { throws_type_index } => bytes.extend(throws_type_index.to_be_bytes()),
+      TargetInfo::Localvar { table } => {
+        bytes.extend((table.len() as ju2).to_be_bytes());
+
+        for (start_pc, length, index) in table {
+          bytes.extend(start_pc.to_be_bytes());
+          bytes.extend(length.to_be_bytes());
+          bytes.extend(index.to_be_bytes());
+        }
+      }
+      TargetInfo::Catch {
+        exception_table_index,
+      } => bytes.extend(exception_table_index.to_be_bytes()),
+      TargetInfo::Offset { offset } => bytes.extend(offset.to_be_bytes()),
+      TargetInfo::TypeArgument {
+        offset,
+        type_argument_index,
+      } => {
+        bytes.extend(offset.to_be_bytes());
+        bytes.push(*type_argument_index);
+      }
+    }
+
+    bytes.push(self.type_path.len() as ju1);
+
+    for (kind, index) in &self.type_path {
+      bytes.extend([*kind, *index]);
+    }
+
+    self.annotation.write(bytes);
+  }
 }
 
-// https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.7.16
+// https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.7.20
+//
+// Content of both RuntimeVisibleTypeAnnotations and RuntimeInvisibleTypeAnnotations
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
-pub struct RuntimeVisibleAnnotations {
+pub struct TypeAnnotations {
   num_annotations: ju2,
-  annotations: Vec<Annotation>,
+  annotations: Vec<TypeAnnotation>,
+  raw: Vec<ju1>,
 }
 
-impl RuntimeVisibleAnnotations {
-  fn parse(bytes: &[u8], cp: &ConstantPool) -> Result<(RuntimeVisibleAnnotations, usize)> {
-    let num_annotations = ju2_from_bytes(&bytes[0..2])?;
+impl TypeAnnotations {
+  fn parse(bytes: &[u8], cp: &ConstantPool) -> Result<(TypeAnnotations, usize)> {
+    let num_annotations = ju2_at(bytes, 0)?;
 
-    let mut annotations: Vec<Annotation> = vec![];
+    let mut annotations: Vec<TypeAnnotation> = vec![];
 
     let mut index = 2;
 
     for _ in 0..num_annotations {
-      let (annotation, bytes_read) = Annotation::parse(&bytes[index..], cp)?;
+      let (annotation, bytes_read) = TypeAnnotation::parse(get_tail(bytes, index)?, cp)?;
 
       index += bytes_read;
 
@@ -593,56 +1345,68 @@ impl RuntimeVisibleAnnotations {
     }
 
     Ok((
-      RuntimeVisibleAnnotations {
+      TypeAnnotations {
         num_annotations,
         annotations,
+        raw: bytes[..index].to_vec(),
       },
       index,
     ))
   }
 
-  pub(crate) fn has_polymorphic_signature(&self) -> bool {
-    for ann in &self.annotations {
-      if ann.get_annotation_type() == "Ljava/lang/invoke/MethodHandle$PolymorphicSignature;" {
-        return true;
-      }
+  pub(crate) fn get_annotations(&self) -> &Vec<TypeAnnotation> {
+    &self.annotations
+  }
+
+  pub(crate) fn get_raw(&self) -> &[ju1] {
+    &self.raw
+  }
+}
+
+impl WriteInfo for TypeAnnotations {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend((self.annotations.len() as ju2).to_be_bytes());
+
+    for annotation in &self.annotations {
+      annotation.write(bytes);
     }
-    false
   }
 }
 
-// https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.7.17
+// https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.7.22
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
-pub struct RuntimeInvisibleAnnotations {
-  num_annotations: ju2,
-  annotations: Vec<Annotation>,
+pub struct AnnotationDefault {
+  default_value: ElementValue,
+  raw: Vec<ju1>,
 }
 
-impl RuntimeInvisibleAnnotations {
-  fn parse(bytes: &[u8], cp: &ConstantPool) -> Result<(RuntimeInvisibleAnnotations, usize)> {
-    let num_annotations = ju2_from_bytes(&bytes[0..2])?;
-
-    let mut annotations: Vec<Annotation> = vec![];
-
-    let mut index = 2;
-
-    for _ in 0..num_annotations {
-      let (annotation, bytes_read) = Annotation::parse(&bytes[index..], cp)?;
-
-      index += bytes_read;
-
-      annotations.push(annotation);
-    }
+impl AnnotationDefault {
+  fn parse(bytes: &[u8], cp: &ConstantPool) -> Result<(AnnotationDefault, usize)> {
+    let (default_value, bytes_read) = ElementValue::parse(bytes, cp)?;
 
     Ok((
-      RuntimeInvisibleAnnotations {
-        num_annotations,
-        annotations,
+      AnnotationDefault {
+        default_value,
+        raw: bytes[..bytes_read].to_vec(),
       },
-      index,
+      bytes_read,
     ))
   }
+
+  pub(crate) fn get_default_value(&self) -> &ElementValue {
+    &self.default_value
+  }
+
+  pub(crate) fn get_raw(&self) -> &[ju1] {
+    &self.raw
+  }
+}
+
+impl WriteInfo for AnnotationDefault {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    self.default_value.write(bytes);
+  }
 }
 
 #[derive(Debug, Clone)]
@@ -667,8 +1431,24 @@ pub struct VerificationTypeInfo {
 }
 
 impl VerificationTypeInfo {
+  pub(crate) fn new(info: VerificationTypeInfoEnum) -> VerificationTypeInfo {
+    let tag = match info {
+      VerificationTypeInfoEnum::Top => 0,
+      VerificationTypeInfoEnum::Integer => 1,
+      VerificationTypeInfoEnum::Float => 2,
+      VerificationTypeInfoEnum::Double => 3,
+      VerificationTypeInfoEnum::Long => 4,
+      VerificationTypeInfoEnum::Null => 5,
+      VerificationTypeInfoEnum::UninitializedThis => 6,
+      VerificationTypeInfoEnum::Object { .. } => 7,
+      VerificationTypeInfoEnum::Uninitialized { .. } => 8,
+    };
+
+    VerificationTypeInfo { tag, info }
+  }
+
   fn parse(bytes: &[u8]) -> Result<(VerificationTypeInfo, usize)> {
-    let tag = bytes[0];
+    let tag = ju1_at(bytes, 0)?;
 
     let (info, bytes_read) = match tag {
       0 => (VerificationTypeInfoEnum::Top, 0),
@@ -679,12 +1459,12 @@ impl VerificationTypeInfo {
       5 => (VerificationTypeInfoEnum::Null, 0),
       6 => (VerificationTypeInfoEnum::UninitializedThis, 0),
       7 => {
-        let cpool_index = ju2_from_bytes(&bytes[1..3])?;
+        let cpool_index = ju2_at(bytes, 1)?;
 
         (VerificationTypeInfoEnum::Object { cpool_index }, 2)
       }
       8 => {
-        let offset = ju2_from_bytes(&bytes[1..3])?;
+        let offset = ju2_at(bytes, 1)?;
 
         (VerificationTypeInfoEnum::Uninitialized { offset }, 2)
       }
@@ -696,6 +1476,22 @@ impl VerificationTypeInfo {
       (bytes_read + 1) as usize,
     ))
   }
+
+  pub(crate) fn get_info(&self) -> &VerificationTypeInfoEnum {
+    &self.info
+  }
+}
+
+impl WriteInfo for VerificationTypeInfo {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.push(self.tag);
+
+    match self.info {
+      VerificationTypeInfoEnum::Object { cpool_index } => bytes.extend(cpool_index.to_be_bytes()),
+      VerificationTypeInfoEnum::Uninitialized { offset } => bytes.extend(offset.to_be_bytes()),
+      _ => {}
+    }
+  }
 }
 
 #[derive(Debug, Clone)]
@@ -737,15 +1533,33 @@ pub struct StackMapFrame {
 }
 
 impl StackMapFrame {
+  /// Frame giving all the locals and the operand stack
+  pub(crate) fn full_frame(
+    offset_delta: ju2,
+    locals: Vec<VerificationTypeInfo>,
+    stack: Vec<VerificationTypeInfo>,
+  ) -> StackMapFrame {
+    StackMapFrame {
+      frame_type: 255,
+      frame_enum: StackMapFrameEnum::FullFrame {
+        offset_delta,
+        number_of_locals: locals.len() as ju2,
+        locals,
+        number_of_stack_items: stack.len() as ju2,
+        stack,
+      },
+    }
+  }
+
   fn parse(bytes: &[u8]) -> Result<(StackMapFrame, usize)> {
-    let frame_type = bytes[0];
+    let frame_type = ju1_at(bytes, 0)?;
 
     let (frame_enum, bytes_read) = match frame_type {
       0..=63 => (StackMapFrameEnum::SameFrame, 0),
       64..=127 => {
         let mut stack: Vec<VerificationTypeInfo> = vec![];
 
-        let (verinfo, bytes_read) = VerificationTypeInfo::parse(&bytes[1..])?;
+        let (verinfo, bytes_read) = VerificationTypeInfo::parse(get_tail(bytes, 1)?)?;
 
         stack.push(verinfo);
 
@@ -755,11 +1569,11 @@ impl StackMapFrame {
         )
       }
       247 => {
-        let offset_delta = ju2_from_bytes(&bytes[1..3])?;
+        let offset_delta = ju2_at(bytes, 1)?;
 
         let mut stack: Vec<VerificationTypeInfo> = vec![];
 
-        let (verinfo, bytes_read) = VerificationTypeInfo::parse(&bytes[3..])?;
+        let (verinfo, bytes_read) = VerificationTypeInfo::parse(get_tail(bytes, 3)?)?;
 
         stack.push(verinfo);
 
@@ -772,17 +1586,17 @@ impl StackMapFrame {
         )
       }
       248..=250 => {
-        let offset_delta = ju2_from_bytes(&bytes[1..3])?;
+        let offset_delta = ju2_at(bytes, 1)?;
 
         (StackMapFrameEnum::ChopFrame { offset_delta }, 2)
       }
       251 => {
-        let offset_delta = ju2_from_bytes(&bytes[1..3])?;
+        let offset_delta = ju2_at(bytes, 1)?;
 
         (StackMapFrameEnum::SameFrameExtended { offset_delta }, 2)
       }
       252..=254 => {
-        let offset_delta = ju2_from_bytes(&bytes[1..3])?;
+        let offset_delta = ju2_at(bytes, 1)?;
 
         let mut locals: Vec<VerificationTypeInfo> = vec![];
 
@@ -791,7 +1605,7 @@ impl StackMapFrame {
         let mut index = 3;
 
         for _ in 0..num_locals {
-          let (verinfo, bytes_read) = VerificationTypeInfo::parse(&bytes[index..])?;
+          let (verinfo, bytes_read) = VerificationTypeInfo::parse(get_tail(bytes, index)?)?;
 
           index += bytes_read;
 
@@ -799,38 +1613,38 @@ impl StackMapFrame {
         }
 
         (
-          StackMapFrameEnum::SameLocals1StackItemFrameExtended {
+          StackMapFrameEnum::AppendFrame {
             offset_delta,
-            stack: locals,
+            locals,
           },
           index - 1, // not include the frame_type
         )
       }
       255 => {
-        let offset_delta = ju2_from_bytes(&bytes[1..3])?;
+        let offset_delta = ju2_at(bytes, 1)?;
 
-        let number_of_locals = ju2_from_bytes(&bytes[3..5])?;
+        let number_of_locals = ju2_at(bytes, 3)?;
 
         let mut locals: Vec<VerificationTypeInfo> = vec![];
 
         let mut index = 5;
 
         for _ in 0..number_of_locals {
-          let (verinfo, bytes_read) = VerificationTypeInfo::parse(&bytes[index..])?;
+          let (verinfo, bytes_read) = VerificationTypeInfo::parse(get_tail(bytes, index)?)?;
 
           index += bytes_read;
 
           locals.push(verinfo);
         }
 
-        let number_of_stack_items = ju2_from_bytes(&bytes[index..index + 2])?;
+        let number_of_stack_items = ju2_at(bytes, index)?;
 
         let mut stack: Vec<VerificationTypeInfo> = vec![];
 
         index += 2;
 
         for _ in 0..number_of_stack_items {
-          let (verinfo, bytes_read) = VerificationTypeInfo::parse(&bytes[index..])?;
+          let (verinfo, bytes_read) = VerificationTypeInfo::parse(get_tail(bytes, index)?)?;
 
           index += bytes_read;
 
@@ -859,6 +1673,57 @@ impl StackMapFrame {
       bytes_read + 1,
     ))
   }
+
+  pub(crate) fn get_frame_type(&self) -> ju1 {
+    self.frame_type
+  }
+
+  pub(crate) fn get_frame_enum(&self) -> &StackMapFrameEnum {
+    &self.frame_enum
+  }
+}
+
+impl WriteInfo for StackMapFrame {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.push(self.frame_type);
+
+    match &self.frame_enum {
+      StackMapFrameEnum::SameFrame => {}
+      StackMapFrameEnum::SameLocals1StackItemFrame { stack } => {
+        stack.iter().for_each(|info| info.write(bytes));
+      }
+      StackMapFrameEnum::SameLocals1StackItemFrameExtended {
+        offset_delta,
+        stack,
+      } => {
+        bytes.extend(offset_delta.to_be_bytes());
+        stack.iter().for_each(|info| info.write(bytes));
+      }
+      StackMapFrameEnum::ChopFrame { offset_delta }
+      | StackMapFrameEnum::SameFrameExtended { offset_delta } => {
+        bytes.extend(offset_delta.to_be_bytes());
+      }
+      StackMapFrameEnum::AppendFrame {
+        offset_delta,
+        locals,
+      } => {
+        bytes.extend(offset_delta.to_be_bytes());
+        locals.iter().for_each(|info| info.write(bytes));
+      }
+      StackMapFrameEnum::FullFrame {
+        offset_delta,
+        locals,
+        stack,
+        ..
+      } => {
+        bytes.extend(offset_delta.to_be_bytes());
+        bytes.extend((locals.len() as ju2).to_be_bytes());
+        locals.iter().for_each(|info| info.write(bytes));
+        bytes.extend((stack.len() as ju2).to_be_bytes());
+        stack.iter().for_each(|info| info.write(bytes));
+      }
+    }
+  }
 }
 
 // https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.7.4
@@ -870,15 +1735,22 @@ pub struct StackMapTable {
 }
 
 impl StackMapTable {
+  pub(crate) fn new(entries: Vec<StackMapFrame>) -> StackMapTable {
+    StackMapTable {
+      number_of_entries: entries.len() as ju2,
+      entries,
+    }
+  }
+
   fn parse(bytes: &[u8]) -> Result<(StackMapTable, usize)> {
-    let number_of_entries = ju2_from_bytes(&bytes[0..2])?;
+    let number_of_entries = ju2_at(bytes, 0)?;
 
     let mut entries: Vec<StackMapFrame> = vec![];
 
     let mut index = 2;
 
     for _ in 0..number_of_entries {
-      let (entry, bytes_read) = StackMapFrame::parse(&bytes[index..])?;
+      let (entry, bytes_read) = StackMapFrame::parse(get_tail(bytes, index)?)?;
 
       index += bytes_read;
 
@@ -893,6 +1765,20 @@ impl StackMapTable {
       index,
     ))
   }
+
+  pub(crate) fn get_entries(&self) -> &Vec<StackMapFrame> {
+    &self.entries
+  }
+}
+
+impl WriteInfo for StackMapTable {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend((self.entries.len() as ju2).to_be_bytes());
+
+    for entry in &self.entries {
+      entry.write(bytes);
+    }
+  }
 }
 
 #[derive(Debug, Clone)]
@@ -907,15 +1793,15 @@ pub struct LocalVariableTableEntry {
 
 impl LocalVariableTableEntry {
   fn parse(bytes: &[u8]) -> Result<(LocalVariableTableEntry, usize)> {
-    let start_pc = ju2_from_bytes(&bytes[0..2])?;
+    let start_pc = ju2_at(bytes, 0)?;
 
-    let length = ju2_from_bytes(&bytes[2..4])?;
+    let length = ju2_at(bytes, 2)?;
 
-    let name_index = ju2_from_bytes(&bytes[4..6])?;
+    let name_index = ju2_at(bytes, 4)?;
 
-    let descriptor_index = ju2_from_bytes(&bytes[6..8])?;
+    let descriptor_index = ju2_at(bytes, 6)?;
 
-    let index = ju2_from_bytes(&bytes[8..10])?;
+    let index = ju2_at(bytes, 8)?;
 
     Ok((
       LocalVariableTableEntry {
@@ -928,6 +1814,36 @@ impl LocalVariableTableEntry {
       10,
     ))
   }
+
+  pub(crate) fn get_start_pc(&self) -> ju2 {
+    self.start_pc
+  }
+
+  pub(crate) fn get_length(&self) -> ju2 {
+    self.length
+  }
+
+  pub(crate) fn get_name_index(&self) -> ju2 {
+    self.name_index
+  }
+
+  pub(crate) fn get_descriptor_index(&self) -> ju2 {
+    self.descriptor_index
+  }
+
+  pub(crate) fn get_index(&self) -> ju2 {
+    self.index
+  }
+}
+
+impl WriteInfo for LocalVariableTableEntry {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend(self.start_pc.to_be_bytes());
+    bytes.extend(self.length.to_be_bytes());
+    bytes.extend(self.name_index.to_be_bytes());
+    bytes.extend(self.descriptor_index.to_be_bytes());
+    bytes.extend(self.index.to_be_bytes());
+  }
 }
 
 // https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.7.13
@@ -940,14 +1856,14 @@ pub struct LocalVariableTable {
 
 impl LocalVariableTable {
   fn parse(bytes: &[u8]) -> Result<(LocalVariableTable, usize)> {
-    let local_variable_table_length = ju2_from_bytes(&bytes[0..2])?;
+    let local_variable_table_length = ju2_at(bytes, 0)?;
 
     let mut local_variable_table: Vec<LocalVariableTableEntry> = vec![];
 
     let mut index = 2;
 
     for _ in 0..local_variable_table_length {
-      let (entry, bytes_read) = LocalVariableTableEntry::parse(&bytes[index..])?;
+      let (entry, bytes_read) = LocalVariableTableEntry::parse(get_tail(bytes, index)?)?;
 
       index += bytes_read;
 
@@ -962,6 +1878,20 @@ impl LocalVariableTable {
       index,
     ))
   }
+
+  pub(crate) fn get_entries(&self) -> &Vec<LocalVariableTableEntry> {
+    &self.local_variable_table
+  }
+}
+
+impl WriteInfo for LocalVariableTable {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend((self.local_variable_table.len() as ju2).to_be_bytes());
+
+    for entry in &self.local_variable_table {
+      entry.write(bytes);
+    }
+  }
 }
 
 #[derive(Debug, Clone)]
@@ -972,17 +1902,28 @@ pub struct BootstrapMethod {
 }
 
 impl BootstrapMethod {
+  /// Bootstrap method handle and static arguments, indexes in the constant pool
+  pub(crate) fn define(
+    bootstrap_method_ref: ju2,
+    bootstrap_arguments: Vec<ju2>,
+  ) -> BootstrapMethod {
+    BootstrapMethod {
+      bootstrap_method_ref,
+      bootstrap_arguments,
+    }
+  }
+
   fn parse(bytes: &[u8]) -> Result<(BootstrapMethod, usize)> {
-    let bootstrap_method_ref = ju2_from_bytes(&bytes[0..2])?;
+    let bootstrap_method_ref = ju2_at(bytes, 0)?;
 
-    let num_bootstrap_arguments = ju2_from_bytes(&bytes[2..4])?;
+    let num_bootstrap_arguments = ju2_at(bytes, 2)?;
 
     let mut bootstrap_arguments: Vec<ju2> = vec![];
 
     let mut index = 4;
 
     for _ in 0..num_bootstrap_arguments {
-      let arg = ju2_from_bytes(get_slice_arr(bytes, index, 2))?;
+      let arg = ju2_at(bytes, index)?;
 
       index += 2;
 
@@ -1007,6 +1948,17 @@ impl BootstrapMethod {
   }
 }
 
+impl WriteInfo for BootstrapMethod {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend(self.bootstrap_method_ref.to_be_bytes());
+    bytes.extend((self.bootstrap_arguments.len() as ju2).to_be_bytes());
+
+    for argument in &self.bootstrap_arguments {
+      bytes.extend(argument.to_be_bytes());
+    }
+  }
+}
+
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub struct BootstrapMethods {
@@ -1015,15 +1967,22 @@ pub struct BootstrapMethods {
 }
 
 impl BootstrapMethods {
+  pub(crate) fn define(bootstrap_methods: Vec<BootstrapMethod>) -> BootstrapMethods {
+    BootstrapMethods {
+      num_bootstrap_methods: bootstrap_methods.len() as ju2,
+      bootstrap_methods,
+    }
+  }
+
   fn parse(bytes: &[u8]) -> Result<(BootstrapMethods, usize)> {
-    let num_bootstrap_methods = ju2_from_bytes(&bytes[0..2])?;
+    let num_bootstrap_methods = ju2_at(bytes, 0)?;
 
     let mut bootstrap_methods: Vec<BootstrapMethod> = vec![];
 
     let mut index = 2;
 
     for _ in 0..num_bootstrap_methods {
-      let (entry, bytes_read) = BootstrapMethod::parse(&bytes[index..])?;
+      let (entry, bytes_read) = BootstrapMethod::parse(get_tail(bytes, index)?)?;
 
       index += bytes_read;
 
@@ -1042,6 +2001,20 @@ impl BootstrapMethods {
   pub fn get(&self, index: usize) -> &BootstrapMethod {
     &self.bootstrap_methods[index]
   }
+
+  pub(crate) fn get_methods(&self) -> &Vec<BootstrapMethod> {
+    &self.bootstrap_methods
+  }
+}
+
+impl WriteInfo for BootstrapMethods {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend((self.bootstrap_methods.len() as ju2).to_be_bytes());
+
+    for method in &self.bootstrap_methods {
+      method.write(bytes);
+    }
+  }
 }
 
 #[derive(Debug, Clone)]
@@ -1053,9 +2026,9 @@ pub struct MethodParameter {
 
 impl MethodParameter {
   fn parse(bytes: &[u8]) -> Result<(MethodParameter, usize)> {
-    let name_index = ju2_from_bytes(&bytes[0..2])?;
+    let name_index = ju2_at(bytes, 0)?;
 
-    let access_flags = ju2_from_bytes(&bytes[2..4])?;
+    let access_flags = ju2_at(bytes, 2)?;
 
     Ok((
       MethodParameter {
@@ -1067,6 +2040,13 @@ impl MethodParameter {
   }
 }
 
+impl WriteInfo for MethodParameter {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend(self.name_index.to_be_bytes());
+    bytes.extend(self.access_flags.to_be_bytes());
+  }
+}
+
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub struct MethodParameters {
@@ -1076,14 +2056,14 @@ pub struct MethodParameters {
 
 impl MethodParameters {
   fn parse(bytes: &[u8]) -> Result<(MethodParameters, usize)> {
-    let parameters_count = bytes[0];
+    let parameters_count = ju1_at(bytes, 0)?;
 
     let mut parameters: Vec<MethodParameter> = vec![];
 
     let mut index = 1;
 
     for _ in 0..parameters_count {
-      let (entry, bytes_read) = MethodParameter::parse(&bytes[index..])?;
+      let (entry, bytes_read) = MethodParameter::parse(get_tail(bytes, index)?)?;
 
       index += bytes_read;
 
@@ -1100,9 +2080,21 @@ impl MethodParameters {
   }
 }
 
+impl WriteInfo for MethodParameters {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.push(self.parameters.len() as ju1);
+
+    for parameter in &self.parameters {
+      parameter.write(bytes);
+    }
+  }
+}
+
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
-pub(crate) struct EnclosingMethod {
+pub struct EnclosingMethod {
+  class_index: ju2,
+  method_index: ju2,
   class_name: String,
   method_name: String,
   method_descriptor: String,
@@ -1110,10 +2102,10 @@ pub(crate) struct EnclosingMethod {
 
 impl EnclosingMethod {
   fn parse(bytes: &[u8], cp: &ConstantPool) -> Result<(EnclosingMethod, usize)> {
-    let class_index = ju2_from_bytes(&bytes[0..2])?;
+    let class_index = ju2_at(bytes, 0)?;
     let class_name = cp.resolve_class_name(class_index)?;
 
-    let method_index = ju2_from_bytes(&bytes[2..4])?;
+    let method_index = ju2_at(bytes, 2)?;
     // no defined within a method
     let (method_name, method_descriptor) = if method_index == 0 {
       ("".to_string(), "".to_string())
@@ -1123,6 +2115,8 @@ impl EnclosingMethod {
 
     Ok((
       EnclosingMethod {
+        class_index,
+        method_index,
         class_name,
         method_name,
         method_descriptor,
@@ -1146,6 +2140,21 @@ impl EnclosingMethod {
   pub(crate) fn get_method_descriptor(&self) -> &str {
     &self.method_descriptor
   }
+
+  pub(crate) fn get_class_index(&self) -> ju2 {
+    self.class_index
+  }
+
+  pub(crate) fn get_method_index(&self) -> ju2 {
+    self.method_index
+  }
+}
+
+impl WriteInfo for EnclosingMethod {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend(self.class_index.to_be_bytes());
+    bytes.extend(self.method_index.to_be_bytes());
+  }
 }
 
 #[derive(Debug, Clone)]
@@ -1157,16 +2166,16 @@ pub struct PermittedSubclasses {
 
 impl PermittedSubclasses {
   fn parse(bytes: &[u8]) -> Result<(PermittedSubclasses, usize)> {
-    let number_of_classes = ju2_from_bytes(&bytes[0..2])?;
+    let number_of_classes = ju2_at(bytes, 0)?;
 
     let mut classes: Vec<ju2> = vec![];
 
     let mut index = 2;
 
     for _ in 0..number_of_classes {
-      let slice = &bytes[index..];
+      let slice = get_tail(bytes, index)?;
 
-      let class = ju2_from_bytes(&slice[0..2])?;
+      let class = ju2_at(slice, 0)?;
 
       classes.push(class);
 
@@ -1181,6 +2190,20 @@ impl PermittedSubclasses {
       index,
     ))
   }
+
+  pub(crate) fn get_classes(&self) -> &Vec<ju2> {
+    &self.classes
+  }
+}
+
+impl WriteInfo for PermittedSubclasses {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend((self.classes.len() as ju2).to_be_bytes());
+
+    for class in &self.classes {
+      bytes.extend(class.to_be_bytes());
+    }
+  }
 }
 
 #[derive(Debug, Clone)]
@@ -1193,11 +2216,11 @@ pub struct RecordComponent {
 
 impl RecordComponent {
   fn parse(bytes: &[u8], cp: &ConstantPool) -> Result<(RecordComponent, usize)> {
-    let name_index = ju2_from_bytes(&bytes[0..2])?;
+    let name_index = ju2_at(bytes, 0)?;
 
-    let descriptor_index = ju2_from_bytes(&bytes[2..4])?;
+    let descriptor_index = ju2_at(bytes, 2)?;
 
-    let (attributes, attributes_size) = Attributes::parse(&bytes[4..], cp)?;
+    let (attributes, attributes_size) = Attributes::parse(get_tail(bytes, 4)?, cp)?;
 
     let index = 4 + attributes_size;
 
@@ -1210,6 +2233,27 @@ impl RecordComponent {
       index,
     ))
   }
+
+  pub(crate) fn get_name_index(&self) -> ju2 {
+    self.name_index
+  }
+
+  pub(crate) fn get_descriptor_index(&self) -> ju2 {
+    self.descriptor_index
+  }
+
+  pub(crate) fn get_attributes(&self) -> &Attributes {
+    &self.attributes
+  }
+}
+
+impl WriteInfo for RecordComponent {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend(self.name_index.to_be_bytes());
+    bytes.extend(self.descriptor_index.to_be_bytes());
+
+    self.attributes.write(bytes);
+  }
 }
 
 #[derive(Debug, Clone)]
@@ -1221,14 +2265,14 @@ pub struct Record {
 
 impl Record {
   fn parse(bytes: &[u8], cp: &ConstantPool) -> Result<(Record, usize)> {
-    let components_count = ju2_from_bytes(&bytes[0..2])?;
+    let components_count = ju2_at(bytes, 0)?;
 
     let mut components = vec![];
 
     let mut index = 2;
 
     for _ in 0..components_count {
-      let slice = &bytes[index..];
+      let slice = get_tail(bytes, index)?;
 
       let (comp, size) = RecordComponent::parse(slice, cp)?;
 
@@ -1245,6 +2289,20 @@ impl Record {
       index,
     ))
   }
+
+  pub(crate) fn get_components(&self) -> &Vec<RecordComponent> {
+    &self.components
+  }
+}
+
+impl WriteInfo for Record {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend((self.components.len() as ju2).to_be_bytes());
+
+    for component in &self.components {
+      component.write(bytes);
+    }
+  }
 }
 
 // https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.6
@@ -1278,11 +2336,11 @@ pub enum AttributeInfoInfoEnum {
   Deprecated,
   RuntimeVisibleAnnotations(RuntimeVisibleAnnotations),
   RuntimeInvisibleAnnotations(RuntimeInvisibleAnnotations),
-  RuntimeVisibleParameterAnnotations,
-  RuntimeInvisibleParameterAnnotations,
-  RuntimeVisibleTypeAnnotations,
-  RuntimeInvisibleTypeAnnotations,
-  AnnotationDefault,
+  RuntimeVisibleParameterAnnotations(ParameterAnnotations),
+  RuntimeInvisibleParameterAnnotations(ParameterAnnotations),
+  RuntimeVisibleTypeAnnotations(TypeAnnotations),
+  RuntimeInvisibleTypeAnnotations(TypeAnnotations),
+  AnnotationDefault(AnnotationDefault),
   MethodParameters(MethodParameters),
   Module,
   ModulePackages,
@@ -1300,20 +2358,21 @@ pub struct AttributeInfo {
 }
 
 impl AttributeInfo {
-  pub fn parse(bytes: &[u8], cp: &ConstantPool) -> Result<(AttributeInfo, usize)> {
+  pub(crate) fn parse(bytes: &[u8], cp: &ConstantPool) -> Result<(AttributeInfo, usize)> {
     let mut index = 0;
 
-    let attribute_name_index: ju2 = ju2_from_bytes(get_slice_arr(bytes, index, 2))?;
+    let attribute_name_index: ju2 = ju2_at(bytes, index)?;
 
     index += 2;
 
-    let attribute_length: ju4 = ju4_from_bytes(get_slice_arr(bytes, index, 4))?;
+    let attribute_length: ju4 = ju4_at(bytes, index)?;
 
     index += 4;
 
     let attribute_name = cp.resolve_name(attribute_name_index)?;
 
-    let info_slice = &bytes[index..];
+    // an attribute can not read past its own length
+    let info_slice = get_slice(bytes, index, attribute_length as usize)?;
 
     //debug!("{:?}", attribute_name);
     let (info, bytes_read) = match attribute_name.as_ref() {
@@ -1379,6 +2438,46 @@ impl AttributeInfo {
           bytes_read,
         )
       }
+      "RuntimeVisibleParameterAnnotations" => {
+        let (annotations, bytes_read) = ParameterAnnotations::parse(info_slice, cp)?;
+
+        (
+          AttributeInfoInfoEnum::RuntimeVisibleParameterAnnotations(annotations),
+          bytes_read,
+        )
+      }
+      "RuntimeInvisibleParameterAnnotations" => {
+        let (annotations, bytes_read) = ParameterAnnotations::parse(info_slice, cp)?;
+
+        (
+          AttributeInfoInfoEnum::RuntimeInvisibleParameterAnnotations(annotations),
+          bytes_read,
+        )
+      }
+      "RuntimeVisibleTypeAnnotations" => {
+        let (annotations, bytes_read) = TypeAnnotations::parse(info_slice, cp)?;
+
+        (
+          AttributeInfoInfoEnum::RuntimeVisibleTypeAnnotations(annotations),
+          bytes_read,
+        )
+      }
+      "RuntimeInvisibleTypeAnnotations" => {
+        let (annotations, bytes_read) = TypeAnnotations::parse(info_slice, cp)?;
+
+        (
+          AttributeInfoInfoEnum::RuntimeInvisibleTypeAnnotations(annotations),
+          bytes_read,
+        )
+      }
+      "AnnotationDefault" => {
+        let (default, bytes_read) = AnnotationDefault::parse(info_slice, cp)?;
+
+        (
+          AttributeInfoInfoEnum::AnnotationDefault(default),
+          bytes_read,
+        )
+      }
       "StackMapTable" => {
         let (stackmap, bytes_read) = StackMapTable::parse(info_slice)?;
 
@@ -1431,11 +2530,20 @@ impl AttributeInfo {
 
         (AttributeInfoInfoEnum::Record(record), bytes_read)
       }
-      v => notimpl!(v),
+      // unknown attributes are silently ignored (JVMS 4.7.1)
+      _ => (
+        AttributeInfoInfoEnum::Raw(info_slice.to_vec()),
+        info_slice.len(),
+      ),
     };
 
     //debug!("    Attribute: {:?}", attribute_name);
-    assert_eq!(attribute_length as usize, bytes_read);
+    if attribute_length as usize != bytes_read {
+      return Err(eyre!(errors::JavaException::ClassFormatError(format!(
+        "Wrong length {} in {} attribute",
+        attribute_length, attribute_name
+      ))));
+    }
 
     let attr = AttributeInfo {
       attribute_name,
@@ -1447,6 +2555,28 @@ impl AttributeInfo {
     Ok((attr, 2 + 4 + bytes_read))
   }
 
+  /// New attribute `name`, with its name added to the constant pool `cp`
+  pub(crate) fn define(
+    cp: &mut ConstantPool,
+    name: &str,
+    info: AttributeInfoInfoEnum,
+  ) -> AttributeInfo {
+    let mut attr = AttributeInfo {
+      attribute_name: name.to_string(),
+      attribute_name_index: cp.add_utf8(name),
+      attribute_length: 0,
+      info,
+    };
+
+    let mut bytes = vec![];
+    attr.write(&mut bytes);
+
+    // name index and length
+    attr.attribute_length = (bytes.len() - 6) as ju4;
+
+    attr
+  }
+
   pub fn get_name(&self) -> &str {
     &self.attribute_name
   }
@@ -1454,6 +2584,49 @@ impl AttributeInfo {
   pub fn get_info(&self) -> &AttributeInfoInfoEnum {
     &self.info
   }
+
+  pub(crate) fn get_attribute_length(&self) -> ju4 {
+    self.attribute_length
+  }
+}
+
+impl WriteInfo for AttributeInfo {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    let mut info = vec![];
+
+    match &self.info {
+      AttributeInfoInfoEnum::Raw(raw) => info.extend(raw),
+      AttributeInfoInfoEnum::ConstantValue(attr) => attr.write(&mut info),
+      AttributeInfoInfoEnum::Code(attr) => attr.write(&mut info),
+      AttributeInfoInfoEnum::StackMapTable(attr) => attr.write(&mut info),
+      AttributeInfoInfoEnum::BootstrapMethods(attr) => attr.write(&mut info),
+      AttributeInfoInfoEnum::NestHost(attr) => attr.write(&mut info),
+      AttributeInfoInfoEnum::NestMembers(attr) => attr.write(&mut info),
+      AttributeInfoInfoEnum::PermittedSubclasses(attr) => attr.write(&mut info),
+      AttributeInfoInfoEnum::Exceptions(attr) => attr.write(&mut info),
+      AttributeInfoInfoEnum::InnerClasses(attr) => attr.write(&mut info),
+      AttributeInfoInfoEnum::EnclosingMethod(attr) => attr.write(&mut info),
+      AttributeInfoInfoEnum::Signature(attr) => attr.write(&mut info),
+      AttributeInfoInfoEnum::Record(attr) => attr.write(&mut info),
+      AttributeInfoInfoEnum::SourceFile(attr) => attr.write(&mut info),
+      AttributeInfoInfoEnum::LineNumberTable(attr) => attr.write(&mut info),
+      AttributeInfoInfoEnum::LocalVariableTable(attr) => attr.write(&mut info),
+      AttributeInfoInfoEnum::RuntimeVisibleAnnotations(attr) => attr.write(&mut info),
+      AttributeInfoInfoEnum::RuntimeInvisibleAnnotations(attr) => attr.write(&mut info),
+      AttributeInfoInfoEnum::RuntimeVisibleParameterAnnotations(attr)
+      | AttributeInfoInfoEnum::RuntimeInvisibleParameterAnnotations(attr) => attr.write(&mut info),
+      AttributeInfoInfoEnum::RuntimeVisibleTypeAnnotations(attr)
+      | AttributeInfoInfoEnum::RuntimeInvisibleTypeAnnotations(attr) => attr.write(&mut info),
+      AttributeInfoInfoEnum::AnnotationDefault(attr) => attr.write(&mut info),
+      AttributeInfoInfoEnum::MethodParameters(attr) => attr.write(&mut info),
+      // attributes without content
+      _ => {}
+    }
+
+    bytes.extend(self.attribute_name_index.to_be_bytes());
+    bytes.extend((info.len() as ju4).to_be_bytes());
+    bytes.extend(info);
+  }
 }
 
 #[derive(Debug, Clone)]
@@ -1466,19 +2639,19 @@ impl Attributes {
     Attributes { attributes }
   }
 
-  pub fn parse(bytes: &[u8], cp: &ConstantPool) -> Result<(Attributes, usize)> {
+  pub(crate) fn parse(bytes: &[u8], cp: &ConstantPool) -> Result<(Attributes, usize)> {
     //debug!("[-] Parsing Attributes");
     let mut attributes: Vec<AttributeInfo> = vec![];
 
     let mut index: usize = 0;
 
-    let attributes_count: ju2 = ju2_from_bytes(&bytes[0..2])?;
+    let attributes_count: ju2 = ju2_at(bytes, 0)?;
 
     //debug!("    Found {:?} attributes", attributes_count);
     index += 2;
 
     for _ in 0..attributes_count {
-      let slice = &bytes[index..];
+      let slice = get_tail(bytes, index)?;
 
       let (attribute, size) = AttributeInfo::parse(slice, cp)?;
 
@@ -1502,6 +2675,10 @@ impl Attributes {
     &self.attributes[index]
   }
 
+  pub(crate) fn iter(&self) -> impl Iterator<Item = &AttributeInfo> {
+    self.attributes.iter()
+  }
+
   pub fn _get_code(&self) -> Option<&Code> {
     for attr in &self.attributes {
       if let AttributeInfoInfoEnum::Code(c) = attr.get_info() {
@@ -1538,6 +2715,71 @@ impl Attributes {
     None
   }
 
+  pub fn get_stack_map_table(&self) -> Option<&StackMapTable> {
+    for attr in &self.attributes {
+      if let AttributeInfoInfoEnum::StackMapTable(c) = attr.get_info() {
+        return Some(c);
+      }
+    }
+    None
+  }
+
+  pub fn get_nest_members(&self) -> Option<&NestMembers> {
+    for attr in &self.attributes {
+      if let AttributeInfoInfoEnum::NestMembers(c) = attr.get_info() {
+        return Some(c);
+      }
+    }
+    None
+  }
+
+  pub fn get_signature(&self) -> Option<&Signature> {
+    for attr in &self.attributes {
+      if let AttributeInfoInfoEnum::Signature(c) = attr.get_info() {
+        return Some(c);
+      }
+    }
+    None
+  }
+
+  pub fn get_record(&self) -> Option<&Record> {
+    for attr in &self.attributes {
+      if let AttributeInfoInfoEnum::Record(c) = attr.get_info() {
+        return Some(c);
+      }
+    }
+    None
+  }
+
+  pub fn get_permitted_subclasses(&self) -> Option<&PermittedSubclasses> {
+    for attr in &self.attributes {
+      if let AttributeInfoInfoEnum::PermittedSubclasses(c) = attr.get_info() {
+        return Some(c);
+      }
+    }
+    None
+  }
+
+  /// Content of the annotations attribute `name` (e.g. `RuntimeVisibleAnnotations`),
+  /// as read from the class file
+  pub(crate) fn get_raw_annotations(&self, name: &str) -> Option<&[ju1]> {
+    let attr = self
+      .attributes
+      .iter()
+      .find(|attr| attr.get_name() == name)?;
+
+    match attr.get_info() {
+      AttributeInfoInfoEnum::RuntimeVisibleAnnotations(attr) => Some(attr.get_raw()),
+      AttributeInfoInfoEnum::RuntimeInvisibleAnnotations(attr) => Some(attr.get_raw()),
+      AttributeInfoInfoEnum::RuntimeVisibleParameterAnnotations(attr)
+      | AttributeInfoInfoEnum::RuntimeInvisibleParameterAnnotations(attr) => Some(attr.get_raw()),
+      AttributeInfoInfoEnum::RuntimeVisibleTypeAnnotations(attr)
+      | AttributeInfoInfoEnum::RuntimeInvisibleTypeAnnotations(attr) => Some(attr.get_raw()),
+      AttributeInfoInfoEnum::AnnotationDefault(attr) => Some(attr.get_raw()),
+      _ => None,
+    }
+  }
+
   pub fn get_by_name(&self, name: &str) -> &AttributeInfo {
     for a in &self.attributes {
       if a.get_name() == name {
@@ -1548,3 +2790,13 @@ impl Attributes {
     panic!();
   }
 }
+
+impl WriteInfo for Attributes {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend((self.attributes.len() as ju2).to_be_bytes());
+
+    for attribute in &self.attributes {
+      attribute.write(bytes);
+    }
+  }
+}
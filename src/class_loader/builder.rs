@@ -0,0 +1,193 @@
+use color_eyre::eyre::Result;
+
+use super::{
+  attributes::{AttributeInfo, AttributeInfoInfoEnum, Attributes, Code},
+  class_file::ClassFile,
+  constant_pool::ConstantPool,
+  fields::FieldInfo,
+  format_checker::MAX_MAJOR_VERSION,
+  methods::MethodInfo,
+};
+use crate::utils::*;
+
+const MAGIC: ju4 = 0xCAFEBABE;
+
+/// Build a class file programmatically
+///
+/// The constant pool entries are added as the class is built, and can also be
+/// added directly through `constant_pool` (e.g. the operands of the code).
+///
+/// ```
+/// use jvmcraft::{ClassBuilder, Code};
+///
+/// // ACC_PUBLIC | ACC_SUPER
+/// let mut builder = ClassBuilder::new(0x0021, "Answer", Some("java/lang/Object"));
+///
+/// // static int answer() { return 42; } with bipush 42, ireturn
+/// builder.method(0x0009, "answer", "()I", Some(Code::new(1, 0, vec![0x10, 42, 0xac])));
+///
+/// let bytes = builder.build().unwrap();
+/// ```
+pub struct ClassBuilder {
+  major_version: ju2,
+  constant_pool: ConstantPool,
+  access_flags: ju2,
+  this_class: ju2,
+  super_class: ju2,
+  interfaces: Vec<ju2>,
+  fields: Vec<FieldInfo>,
+  methods: Vec<MethodInfo>,
+  attributes: Vec<AttributeInfo>,
+}
+
+impl ClassBuilder {
+  /// New class `name` (in internal form), extending `super_name` if any
+  pub fn new(access_flags: ju2, name: &str, super_name: Option<&str>) -> ClassBuilder {
+    let mut constant_pool = ConstantPool::empty();
+
+    let this_class = constant_pool.add_class(name);
+
+    let super_class = match super_name {
+      Some(super_name) => constant_pool.add_class(super_name),
+      None => 0,
+    };
+
+    ClassBuilder {
+      major_version: MAX_MAJOR_VERSION,
+      constant_pool,
+      access_flags,
+      this_class,
+      super_class,
+      interfaces: vec![],
+      fields: vec![],
+      methods: vec![],
+      attributes: vec![],
+    }
+  }
+
+  /// Set the class file version (the newest supported one by default)
+  pub fn version(&mut self, major_version: ju2) -> &mut ClassBuilder {
+    self.major_version = major_version;
+
+    self
+  }
+
+  pub fn constant_pool(&mut self) -> &mut ConstantPool {
+    &mut self.constant_pool
+  }
+
+  pub fn interface(&mut self, name: &str) -> &mut ClassBuilder {
+    let index = self.constant_pool.add_class(name);
+
+    self.interfaces.push(index);
+
+    self
+  }
+
+  pub fn field(&mut self, access_flags: ju2, name: &str, descriptor: &str) -> &mut ClassBuilder {
+    let field = FieldInfo::define(
+      &mut self.constant_pool,
+      access_flags,
+      name,
+      descriptor,
+      Attributes::new(vec![]),
+    );
+
+    self.fields.push(field);
+
+    self
+  }
+
+  /// Add a method, with a `Code` attribute unless it is abstract or native
+  pub fn method(
+    &mut self,
+    access_flags: ju2,
+    name: &str,
+    descriptor: &str,
+    code: Option<Code>,
+  ) -> &mut ClassBuilder {
+    let attributes = match code {
+      Some(code) => vec![AttributeInfo::define(
+        &mut self.constant_pool,
+        "Code",
+        AttributeInfoInfoEnum::Code(code),
+      )],
+      None => vec![],
+    };
+
+    let method = MethodInfo::define(
+      &mut self.constant_pool,
+      access_flags,
+      name,
+      descriptor,
+      Attributes::new(attributes),
+    );
+
+    self.methods.push(method);
+
+    self
+  }
+
+  /// Add an attribute of the class
+  pub fn attribute(&mut self, name: &str, info: AttributeInfoInfoEnum) -> &mut ClassBuilder {
+    let attribute = AttributeInfo::define(&mut self.constant_pool, name, info);
+
+    self.attributes.push(attribute);
+
+    self
+  }
+
+  /// Content of the class file
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut bytes = vec![];
+
+    bytes.extend(MAGIC.to_be_bytes());
+    bytes.extend((0 as ju2).to_be_bytes());
+    bytes.extend(self.major_version.to_be_bytes());
+
+    self.constant_pool.write(&mut bytes);
+
+    bytes.extend(self.access_flags.to_be_bytes());
+    bytes.extend(self.this_class.to_be_bytes());
+    bytes.extend(self.super_class.to_be_bytes());
+
+    bytes.extend((self.interfaces.len() as ju2).to_be_bytes());
+
+    for interface in &self.interfaces {
+      bytes.extend(interface.to_be_bytes());
+    }
+
+    bytes.extend((self.fields.len() as ju2).to_be_bytes());
+
+    for field in &self.fields {
+      field.write(&mut bytes);
+    }
+
+    bytes.extend((self.methods.len() as ju2).to_be_bytes());
+
+    for method in &self.methods {
+      method.write(&mut bytes);
+    }
+
+    bytes.extend((self.attributes.len() as ju2).to_be_bytes());
+
+    for attribute in &self.attributes {
+      attribute.write(&mut bytes);
+    }
+
+    bytes
+  }
+
+  /// Content of the class file, after checking its format like any class file
+  ///
+  /// # Returns
+  ///
+  /// `ClassFormatError` if the built class is malformed
+  pub fn build(&self) -> Result<Vec<u8>> {
+    let bytes = self.to_bytes();
+
+    ClassFile::parse_from_bytes(&bytes)?;
+
+    Ok(bytes)
+  }
+}
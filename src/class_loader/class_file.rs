@@ -10,14 +10,14 @@ use color_eyre::eyre::{eyre, OptionExt, Result};
 use log::debug;
 
 use crate::{
-  class_loader::{attributes, constant_pool, fields, interfaces, methods},
+  class_loader::{attributes, constant_pool, fields, format_checker, interfaces, methods},
   runtime::{errors, types, types::Type},
   utils::*,
 };
 
 use super::fields::FieldInfo;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub(crate) struct MethodHandleResolved {
   ref_kind: ju1,
@@ -26,6 +26,13 @@ pub(crate) struct MethodHandleResolved {
   method_type: String, // metafactory method descriptor
 }
 
+/// Declaration of the member referenced by a field or method ref, found at resolution
+#[derive(Debug, Clone)]
+pub(crate) struct ResolvedMember {
+  pub(crate) declaring: String,
+  pub(crate) access_flags: ju2,
+}
+
 impl fmt::Display for MethodHandleResolved {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
     write!(f, "{:#?}", self)
@@ -113,11 +120,20 @@ pub struct ClassFile {
   attributes: attributes::Attributes,
 
   is_init: bool,
+  // verified and ready to run (JVMS 5.4)
+  is_linked: bool,
   // use to get field info given a index in the constan pool
   static_fields: HashMap<String, Type>,
 
   this_class_name: String,
   super_class_name: String,
+
+  // hidden classes are not found by name and may join the nest of their lookup class
+  hidden: bool,
+  dynamic_nest_host: Option<String>,
+
+  // members referenced by the field and method refs of the constant pool, once resolved
+  resolved_members: HashMap<ju2, ResolvedMember>,
 }
 
 impl fmt::Display for ClassFile {
@@ -143,49 +159,74 @@ impl ClassFile {
     ClassFile::parse_from_bytes(&data)
   }
 
+  /// Parse and check the format of a class file (JVMS 4.8)
+  ///
+  /// # Returns
+  ///
+  /// `ClassFormatError` or `UnsupportedClassVersionError` if the class file is malformed
   pub fn parse_from_bytes(data: &[u8]) -> Result<Arc<RwLock<Self>>> {
+    let class_file = ClassFile::parse_class_file(data).map_err(format_checker::as_format_error)?;
+
+    Ok(Arc::new(RwLock::new(class_file)))
+  }
+
+  fn parse_class_file(data: &[u8]) -> Result<ClassFile> {
     let mut index = 0;
-    let magic = ju4_from_bytes(get_slice(data, index, 4))?;
+    let magic = ju4_at(data, index)?;
 
     index += 4;
 
-    let minor_version = ju2_from_bytes(get_slice(data, index, 2))?;
+    let minor_version = ju2_at(data, index)?;
 
     index += 2;
 
-    let major_version = ju2_from_bytes(get_slice(data, index, 2))?;
+    let major_version = ju2_at(data, index)?;
 
     index += 2;
 
-    let (constant_pool, bytes_read) = constant_pool::ConstantPool::parse(&data[index..])?;
+    format_checker::check_header(magic, minor_version, major_version)?;
+
+    let (constant_pool, bytes_read) = constant_pool::ConstantPool::parse(get_tail(data, index)?)?;
+
+    constant_pool.check_format(major_version)?;
 
     index += bytes_read;
 
-    let access_flags = ju2_from_bytes(get_slice(data, index, 2))?;
+    let access_flags = ju2_at(data, index)?;
 
     index += 2;
 
-    let this_class = ju2_from_bytes(get_slice(data, index, 2))?;
+    let this_class = ju2_at(data, index)?;
 
     index += 2;
 
-    let super_class = ju2_from_bytes(get_slice(data, index, 2))?;
+    let super_class = ju2_at(data, index)?;
 
     index += 2;
 
-    let (interfaces, bytes_read) = interfaces::Interfaces::parse(&data[index..], &constant_pool)?;
+    let (interfaces, bytes_read) =
+      interfaces::Interfaces::parse(get_tail(data, index)?, &constant_pool)?;
 
     index += bytes_read;
 
-    let (fields, bytes_read) = fields::Fields::parse(&data[index..], &constant_pool)?;
+    let (fields, bytes_read) = fields::Fields::parse(get_tail(data, index)?, &constant_pool)?;
 
     index += bytes_read;
 
-    let (methods, bytes_read) = methods::Methods::parse(&data[index..], &constant_pool)?;
+    let (methods, bytes_read) = methods::Methods::parse(get_tail(data, index)?, &constant_pool)?;
 
     index += bytes_read;
 
-    let (attributes, _) = attributes::Attributes::parse(&data[index..], &constant_pool)?;
+    let (attributes, bytes_read) =
+      attributes::Attributes::parse(get_tail(data, index)?, &constant_pool)?;
+
+    index += bytes_read;
+
+    if index != data.len() {
+      return Err(format_checker::format_error(
+        "Extra bytes at the end of class file".to_string(),
+      ));
+    }
 
     let mut static_fields = HashMap::new();
 
@@ -207,7 +248,7 @@ impl ClassFile {
 
     debug!("=================================================================");
 
-    Ok(Arc::new(RwLock::new(ClassFile {
+    let class_file = ClassFile {
       magic,
       major_version,
       minor_version,
@@ -221,10 +262,44 @@ impl ClassFile {
       attributes,
 
       is_init: false,
+      is_linked: false,
       static_fields,
       this_class_name,
       super_class_name,
-    })))
+
+      hidden: false,
+      dynamic_nest_host: None,
+      resolved_members: HashMap::new(),
+    };
+
+    class_file.check_format()?;
+
+    Ok(class_file)
+  }
+
+  /// Write the class back to the bytes of a class file
+  ///
+  /// The fields added by the VM are left out, so `to_bytes` is the inverse of
+  /// `parse_from_bytes` for any class defined from a class file.
+  pub(crate) fn to_bytes(&self) -> Vec<u8> {
+    let mut bytes = vec![];
+
+    bytes.extend(self.magic.to_be_bytes());
+    bytes.extend(self.minor_version.to_be_bytes());
+    bytes.extend(self.major_version.to_be_bytes());
+
+    self.constant_pool.write(&mut bytes);
+
+    bytes.extend(self.access_flags.to_be_bytes());
+    bytes.extend(self.this_class.to_be_bytes());
+    bytes.extend(self.super_class.to_be_bytes());
+
+    self.interfaces.write(&mut bytes);
+    self.fields.write(&mut bytes);
+    self.methods.write(&mut bytes);
+    self.attributes.write(&mut bytes);
+
+    bytes
   }
 
   pub fn get_access_flags(&self) -> ju2 {
@@ -284,9 +359,14 @@ impl ClassFile {
       attributes,
 
       is_init: false,
+      is_linked: false,
       static_fields,
       this_class_name,
       super_class_name,
+
+      hidden: false,
+      dynamic_nest_host: None,
+      resolved_members: HashMap::new(),
     })))
   }
 
@@ -325,6 +405,38 @@ impl ClassFile {
     self.is_init = true;
   }
 
+  pub(crate) fn is_linked(&self) -> bool {
+    self.is_linked
+  }
+
+  pub(crate) fn link(&mut self) {
+    self.is_linked = true;
+  }
+
+  pub(crate) fn get_minor_version(&self) -> ju2 {
+    self.minor_version
+  }
+
+  pub(crate) fn get_this_class_index(&self) -> ju2 {
+    self.this_class
+  }
+
+  pub(crate) fn get_super_class_index(&self) -> ju2 {
+    self.super_class
+  }
+
+  pub(crate) fn get_constant_pool(&self) -> &constant_pool::ConstantPool {
+    &self.constant_pool
+  }
+
+  pub(crate) fn get_attributes(&self) -> &attributes::Attributes {
+    &self.attributes
+  }
+
+  pub(crate) fn get_major_version(&self) -> ju2 {
+    self.major_version
+  }
+
   pub fn get_interfaces(&self) -> &Vec<String> {
     self.interfaces.get_interfaces()
   }
@@ -382,7 +494,7 @@ impl ClassFile {
     self.methods._get_by_name_index(index)
   }
 
-  pub fn resolve_index(&self, index: ju2) -> Result<&constant_pool::CpInfo> {
+  pub(crate) fn resolve_index(&self, index: ju2) -> Result<&constant_pool::CpInfo> {
     self.constant_pool.resolve_index(index)
   }
 
@@ -396,6 +508,10 @@ impl ClassFile {
     self.constant_pool.resolve_name(class_index)
   }
 
+  pub fn resolve_name_and_type(&self, index: ju2) -> Result<(String, String)> {
+    self.constant_pool.resolve_name_and_type(index)
+  }
+
   pub fn resolve_field_ref(&self, index: ju2) -> Result<(String, String, String)> {
     self.constant_pool.resolve_field_ref(index)
   }
@@ -404,7 +520,7 @@ impl ClassFile {
     self.constant_pool.resolve_method_ref(index)
   }
 
-  pub fn resolve_method_handle(&self, index: ju2) -> Result<MethodHandleResolved> {
+  pub(crate) fn resolve_method_handle(&self, index: ju2) -> Result<MethodHandleResolved> {
     let (ref_kind, class_name, method_name, method_type) =
       self.constant_pool.resolve_method_handle(index)?;
 
@@ -420,7 +536,7 @@ impl ClassFile {
     self.constant_pool.resolve_method_type(index)
   }
 
-  pub fn resolve_invokedynamic(&self, index: ju2) -> Result<InvokeDynamicResolved> {
+  pub(crate) fn resolve_invokedynamic(&self, index: ju2) -> Result<InvokeDynamicResolved> {
     let (bootstrap_index, method_name, method_type) =
       self.constant_pool.resolve_invokedynamic(index)?;
 
@@ -440,6 +556,25 @@ impl ClassFile {
     })
   }
 
+  /// Bootstrap method of a dynamically-computed constant, its name and field descriptor are
+  /// the ones of the call site of an `invokedynamic`
+  pub(crate) fn resolve_dynamic(&self, index: ju2) -> Result<InvokeDynamicResolved> {
+    let (bootstrap_index, name, descriptor) = self.constant_pool.resolve_dynamic(index)?;
+
+    let bootstrap_method = self.get_bootstrap_method(bootstrap_index.into());
+
+    Ok(InvokeDynamicResolved {
+      bootstrap: self.resolve_method_handle(bootstrap_method.get_method_ref())?,
+      bootstrap_arguments: bootstrap_method.get_arguments().clone(),
+      method_name: name,
+      method_type: descriptor,
+    })
+  }
+
+  pub(crate) fn get_static_fields(&self) -> &HashMap<String, Type> {
+    &self.static_fields
+  }
+
   pub fn put_static_field(&mut self, name: &str, value: Type) -> Result<()> {
     // TODO: check that it is static !
     self.static_fields.insert(name.to_string(), value);
@@ -472,6 +607,18 @@ impl ClassFile {
     !self.super_class_name.is_empty()
   }
 
+  /// Rename the class, its super class and its interfaces with the keys
+  /// resolved by the defining loader
+  pub(crate) fn relink(&mut self, name: &str, parent: Option<String>, interfaces: Vec<String>) {
+    self.this_class_name = name.to_string();
+
+    if let Some(parent) = parent {
+      self.super_class_name = parent;
+    }
+
+    self.interfaces.relink(interfaces);
+  }
+
   pub fn get_bootstrap_methods(&self) -> &attributes::BootstrapMethods {
     match self.attributes.get_by_name("BootstrapMethods").get_info() {
       attributes::AttributeInfoInfoEnum::BootstrapMethods(b) => b,
@@ -487,6 +634,10 @@ impl ClassFile {
     self.access_flags & 0x0200 != 0
   }
 
+  pub fn is_abstract(&self) -> bool {
+    self.access_flags & 0x0400 != 0
+  }
+
   pub fn _is_enum(&self) -> bool {
     self.access_flags & 0x04000 != 0
   }
@@ -499,7 +650,131 @@ impl ClassFile {
     self.attributes.get_inner_classes()
   }
 
+  pub(crate) fn is_hidden(&self) -> bool {
+    self.hidden
+  }
+
+  /// Mark the class as hidden, joining the nest of `nest_host` if any
+  pub(crate) fn set_hidden(&mut self, nest_host: Option<String>) {
+    self.hidden = true;
+    self.dynamic_nest_host = nest_host;
+  }
+
+  /// Member resolved from the field or method ref at `index` of the constant pool
+  pub(crate) fn get_resolved_member(&self, index: ju2) -> Option<&ResolvedMember> {
+    self.resolved_members.get(&index)
+  }
+
+  pub(crate) fn set_resolved_member(&mut self, index: ju2, member: ResolvedMember) {
+    self.resolved_members.insert(index, member);
+  }
+
+  pub(crate) fn get_dynamic_nest_host(&self) -> Option<&str> {
+    self.dynamic_nest_host.as_deref()
+  }
+
   pub fn get_nest_host(&self) -> Option<&attributes::NestHost> {
     self.attributes.get_nest_host()
   }
+
+  /// Names of the classes listed in the `NestMembers` attribute (empty if none)
+  pub fn get_nest_members(&self) -> Result<Vec<String>> {
+    let mut members = vec![];
+
+    if let Some(nest_members) = self.attributes.get_nest_members() {
+      for class_index in nest_members.get_classes() {
+        members.push(self.constant_pool.resolve_class_name(*class_index)?);
+      }
+    }
+
+    Ok(members)
+  }
+
+  /// Generic signature of the class from the `Signature` attribute, if any
+  pub(crate) fn get_generic_signature(&self) -> Result<Option<String>> {
+    self
+      .attributes
+      .get_signature()
+      .map(|signature| {
+        self
+          .constant_pool
+          .resolve_name(signature.get_signature_index())
+      })
+      .transpose()
+  }
+
+  /// Names of the classes listed in the `PermittedSubclasses` attribute, `None` if the class
+  /// is not sealed
+  pub(crate) fn get_permitted_subclasses(&self) -> Result<Option<Vec<String>>> {
+    let Some(permitted) = self.attributes.get_permitted_subclasses() else {
+      return Ok(None);
+    };
+
+    let mut subclasses = vec![];
+
+    for class_index in permitted.get_classes() {
+      subclasses.push(self.constant_pool.resolve_class_name(*class_index)?);
+    }
+
+    Ok(Some(subclasses))
+  }
+
+  /// Names of the member classes declared by this class (`InnerClasses` entries whose outer
+  /// class is this class)
+  pub(crate) fn get_declared_classes(&self) -> Result<Vec<String>> {
+    let mut classes = vec![];
+
+    if let Some(inner_classes) = self.attributes.get_inner_classes() {
+      for entry in inner_classes.get_inner_classes() {
+        // local and anonymous classes have no outer class
+        if entry.get_outer_class_info_index() == 0 {
+          continue;
+        }
+
+        let outer = self
+          .constant_pool
+          .resolve_class_name(entry.get_outer_class_info_index())?;
+
+        let inner = self
+          .constant_pool
+          .resolve_class_name(entry.get_inner_class_info_index())?;
+
+        if outer == self.this_class_name && inner != self.this_class_name {
+          classes.push(inner);
+        }
+      }
+    }
+
+    Ok(classes)
+  }
+
+  /// Simple name of the class from its own `InnerClasses` entry, `None` for a top level or
+  /// an anonymous class
+  pub(crate) fn get_simple_binary_name(&self) -> Result<Option<String>> {
+    if let Some(inner_classes) = self.attributes.get_inner_classes() {
+      for entry in inner_classes.get_inner_classes() {
+        let inner = self
+          .constant_pool
+          .resolve_class_name(entry.get_inner_class_info_index())?;
+
+        if inner == self.this_class_name {
+          return Ok((entry.get_inner_name_index() != 0).then(|| entry.get_name().to_string()));
+        }
+      }
+    }
+
+    Ok(None)
+  }
+
+  pub(crate) fn get_record(&self) -> Option<&attributes::Record> {
+    self.attributes.get_record()
+  }
+
+  pub(crate) fn is_record(&self) -> bool {
+    self.super_class_name == "java/lang/Record" && self.get_record().is_some()
+  }
+
+  pub fn is_public(&self) -> bool {
+    self.access_flags & 0x0001 != 0
+  }
 }
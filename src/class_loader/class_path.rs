@@ -0,0 +1,313 @@
+use std::{
+  fs::{self, File},
+  io::Read,
+  path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{eyre, Result};
+use zip::{result::ZipError, ZipArchive};
+
+use crate::utils::*;
+
+const JIMAGE_MAGIC: ju4 = 0xCAFEDADA;
+
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+// header of a jimage: magic, version, flags, resource count, table length,
+// locations size and strings size
+const JIMAGE_HEADER_SIZE: usize = 7 * 4;
+
+const JIMAGE_HASH_MULTIPLIER: u32 = 0x01000193;
+
+// kinds of the attributes of a location in a jimage
+const ATTRIBUTE_END: usize = 0;
+const ATTRIBUTE_MODULE: usize = 1;
+const ATTRIBUTE_PARENT: usize = 2;
+const ATTRIBUTE_BASE: usize = 3;
+const ATTRIBUTE_EXTENSION: usize = 4;
+const ATTRIBUTE_OFFSET: usize = 5;
+const ATTRIBUTE_COMPRESSED: usize = 6;
+const ATTRIBUTE_UNCOMPRESSED: usize = 7;
+
+/// A jimage file, the container of the JDK modules (`lib/modules`)
+///
+/// The format is the one read by `jdk.internal.jimage.BasicImageReader`: a
+/// header, a hash table of the resource names, the locations of the resources
+/// and their names, then the content of the resources.
+struct JImage {
+  data: Vec<u8>,
+  table_length: usize,
+  locations_start: usize,
+  strings_start: usize,
+  index_size: usize,
+}
+
+impl JImage {
+  fn parse(data: Vec<u8>) -> Result<JImage> {
+    // the jimage is written in the native byte order
+    if data.len() < JIMAGE_HEADER_SIZE || Self::read_u32(&data, 0) != JIMAGE_MAGIC {
+      return Err(eyre!("[!] Not a jimage file"));
+    }
+
+    let table_length = Self::read_u32(&data, 16) as usize;
+    let locations_size = Self::read_u32(&data, 20) as usize;
+    let strings_size = Self::read_u32(&data, 24) as usize;
+
+    // redirect and offsets tables
+    let locations_start = JIMAGE_HEADER_SIZE + table_length * 8;
+    let strings_start = locations_start + locations_size;
+    let index_size = strings_start + strings_size;
+
+    if table_length == 0 || index_size > data.len() {
+      return Err(eyre!("[!] Truncated jimage file"));
+    }
+
+    Ok(JImage {
+      data,
+      table_length,
+      locations_start,
+      strings_start,
+      index_size,
+    })
+  }
+
+  fn read_u32(data: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(data[at..at + 4].try_into().unwrap())
+  }
+
+  fn hash(name: &str, seed: u32) -> usize {
+    let hash = name.bytes().fold(seed, |hash, byte| {
+      hash.wrapping_mul(JIMAGE_HASH_MULTIPLIER) ^ byte as u32
+    });
+
+    (hash & 0x7FFFFFFF) as usize
+  }
+
+  // nul-terminated string at `offset` of the strings table
+  fn get_string(&self, offset: u64) -> Result<&str> {
+    let start = self.strings_start + offset as usize;
+
+    let len = get_slice(&self.data, start, self.index_size.saturating_sub(start))?
+      .iter()
+      .position(|b| *b == 0)
+      .ok_or_else(|| eyre!("[!] Unterminated string in jimage"))?;
+
+    Ok(std::str::from_utf8(&self.data[start..start + len])?)
+  }
+
+  // attributes of the location `index`, indexed by their kind
+  fn get_attributes(&self, index: usize) -> Result<[u64; 8]> {
+    let offset = Self::read_u32(
+      &self.data,
+      JIMAGE_HEADER_SIZE + (self.table_length + index) * 4,
+    );
+
+    let mut at = self.locations_start + offset as usize;
+
+    let mut attributes = [0; 8];
+
+    loop {
+      let byte = ju1_at(&self.data, at)?;
+
+      let kind = (byte >> 3) as usize;
+
+      if kind == ATTRIBUTE_END {
+        break;
+      }
+
+      if kind >= attributes.len() {
+        return Err(eyre!("[!] Invalid attribute kind {} in jimage", kind));
+      }
+
+      let length = (byte & 0x7) as usize + 1;
+
+      attributes[kind] = get_slice(&self.data, at + 1, length)?
+        .iter()
+        .fold(0, |value, b| value << 8 | *b as u64);
+
+      at += 1 + length;
+    }
+
+    Ok(attributes)
+  }
+
+  // full name of a location: /module/parent/base.extension
+  fn get_name(&self, attributes: &[u64; 8]) -> Result<String> {
+    let mut name = String::new();
+
+    let module = self.get_string(attributes[ATTRIBUTE_MODULE])?;
+    let parent = self.get_string(attributes[ATTRIBUTE_PARENT])?;
+    let base = self.get_string(attributes[ATTRIBUTE_BASE])?;
+    let extension = self.get_string(attributes[ATTRIBUTE_EXTENSION])?;
+
+    if !module.is_empty() {
+      name += &format!("/{}/", module);
+    }
+
+    if !parent.is_empty() {
+      name += &format!("{}/", parent);
+    }
+
+    name += base;
+
+    if !extension.is_empty() {
+      name += &format!(".{}", extension);
+    }
+
+    Ok(name)
+  }
+
+  fn get_content(&self, attributes: &[u64; 8]) -> Result<Vec<u8>> {
+    if attributes[ATTRIBUTE_COMPRESSED] != 0 {
+      return Err(eyre!("[!] Compressed jimage resources are not supported"));
+    }
+
+    let start = self.index_size + attributes[ATTRIBUTE_OFFSET] as usize;
+
+    Ok(
+      get_slice(
+        &self.data,
+        start,
+        attributes[ATTRIBUTE_UNCOMPRESSED] as usize,
+      )?
+      .to_vec(),
+    )
+  }
+
+  /// Content of the resource `name` (e.g. `/java.base/java/lang/Object.class`)
+  fn find_resource(&self, name: &str) -> Result<Option<Vec<u8>>> {
+    let redirect = Self::read_u32(
+      &self.data,
+      JIMAGE_HEADER_SIZE + Self::hash(name, JIMAGE_HASH_MULTIPLIER) % self.table_length * 4,
+    ) as i32;
+
+    let index = match redirect {
+      0 => return Ok(None),
+      redirect if redirect < 0 => (-1 - redirect) as usize,
+      seed => Self::hash(name, seed as u32) % self.table_length,
+    };
+
+    if index >= self.table_length {
+      return Ok(None);
+    }
+
+    let attributes = self.get_attributes(index)?;
+
+    // the hash table only tells where the resource would be
+    if self.get_name(&attributes)? != name {
+      return Ok(None);
+    }
+
+    self.get_content(&attributes).map(Some)
+  }
+
+  /// Content of the class `class_name`, in any module
+  fn find_class(&self, class_name: &str) -> Result<Option<Vec<u8>>> {
+    let (parent, base) = class_name.rsplit_once('/').unwrap_or(("", class_name));
+
+    for index in 0..self.table_length {
+      let attributes = self.get_attributes(index)?;
+
+      if self.get_string(attributes[ATTRIBUTE_BASE])? == base
+        && self.get_string(attributes[ATTRIBUTE_PARENT])? == parent
+        && self.get_string(attributes[ATTRIBUTE_EXTENSION])? == "class"
+      {
+        return self.get_content(&attributes).map(Some);
+      }
+    }
+
+    Ok(None)
+  }
+}
+
+enum ClassPathEntry {
+  Directory(PathBuf),
+  Jar(ZipArchive<File>),
+  JImage(JImage),
+}
+
+impl ClassPathEntry {
+  fn open(path: &Path) -> Result<ClassPathEntry> {
+    if path.is_dir() {
+      return Ok(ClassPathEntry::Directory(path.to_path_buf()));
+    }
+
+    let mut magic = [0; 4];
+
+    File::open(path)?.read_exact(&mut magic)?;
+
+    if magic == ZIP_MAGIC {
+      Ok(ClassPathEntry::Jar(ZipArchive::new(File::open(path)?)?))
+    } else if u32::from_le_bytes(magic) == JIMAGE_MAGIC {
+      Ok(ClassPathEntry::JImage(JImage::parse(fs::read(path)?)?))
+    } else {
+      Err(eyre!(
+        "[!] {:?} is neither a directory, a JAR nor a jimage file",
+        path
+      ))
+    }
+  }
+
+  fn find_class(&mut self, class_name: &str) -> Result<Option<Vec<u8>>> {
+    match self {
+      ClassPathEntry::Directory(dir) => {
+        let path = dir.join(format!("{}.class", class_name));
+
+        if path.is_file() {
+          Ok(Some(fs::read(path)?))
+        } else {
+          Ok(None)
+        }
+      }
+      ClassPathEntry::Jar(jar) => match jar.by_name(&format!("{}.class", class_name)) {
+        Ok(mut file) => {
+          let mut data = vec![];
+
+          file.read_to_end(&mut data)?;
+
+          Ok(Some(data))
+        }
+        Err(ZipError::FileNotFound) => Ok(None),
+        Err(err) => Err(err.into()),
+      },
+      ClassPathEntry::JImage(jimage) => {
+        // most classes are in java.base
+        match jimage.find_resource(&format!("/java.base/{}.class", class_name))? {
+          Some(data) => Ok(Some(data)),
+          None => jimage.find_class(class_name),
+        }
+      }
+    }
+  }
+}
+
+/// Class path made of directories, JAR files and jimage files
+pub struct ClassPath {
+  entries: Vec<ClassPathEntry>,
+}
+
+impl ClassPath {
+  /// Class path from a list of paths separated by `:` (`;` on Windows)
+  pub fn new(paths: &str) -> Result<ClassPath> {
+    let entries = std::env::split_paths(paths)
+      .filter(|path| !path.as_os_str().is_empty())
+      .map(|path| ClassPathEntry::open(&path))
+      .collect::<Result<Vec<_>>>()?;
+
+    Ok(ClassPath { entries })
+  }
+
+  /// Content of the class file of `class_name` (e.g. `java/lang/Object` or
+  /// `java.lang.Object`), from the first entry that contains it
+  pub fn find_class(&mut self, class_name: &str) -> Result<Option<Vec<u8>>> {
+    let class_name = dotclass_to_class(class_name);
+
+    for entry in &mut self.entries {
+      if let Some(data) = entry.find_class(&class_name)? {
+        return Ok(Some(data));
+      }
+    }
+
+    Ok(None)
+  }
+}
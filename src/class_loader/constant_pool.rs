@@ -3,7 +3,8 @@ use core::fmt;
 use color_eyre::eyre::{eyre, Result};
 use log::debug;
 
-use crate::{notimpl, utils::*};
+use super::format_checker::*;
+use crate::{runtime::errors, utils::*};
 
 #[derive(Default, Debug, Clone)]
 
@@ -26,14 +27,13 @@ enum CpInfoTagEnum {
   Dynamic = 17,
   InvokeDynamic = 18,
   Module = 19,
-  Package = 2,
+  Package = 20,
 }
 
 impl CpInfoTagEnum {
   fn from_num(tag: ju1) -> Result<CpInfoTagEnum> {
     match tag {
       1 => Ok(CpInfoTagEnum::Utf8),
-      2 => Ok(CpInfoTagEnum::Package),
       3 => Ok(CpInfoTagEnum::Integer),
       4 => Ok(CpInfoTagEnum::Float),
       5 => Ok(CpInfoTagEnum::Long),
@@ -50,12 +50,16 @@ impl CpInfoTagEnum {
       17 => Ok(CpInfoTagEnum::Dynamic),
       18 => Ok(CpInfoTagEnum::InvokeDynamic),
       19 => Ok(CpInfoTagEnum::Module),
-      _ => Err(eyre!("cp info tag not handled: {:?}", tag)),
+      20 => Ok(CpInfoTagEnum::Package),
+      _ => Err(eyre!(errors::JavaException::ClassFormatError(format!(
+        "Unknown constant tag {} in class file",
+        tag
+      )))),
     }
   }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub(crate) struct RefInfo {
   class_index: ju2,
   name_and_type_index: ju2,
@@ -66,12 +70,12 @@ impl RefInfo {
     self.class_index
   }
 
-  pub(crate) fn _get_name_and_type_index(&self) -> ju2 {
+  pub(crate) fn get_name_and_type_index(&self) -> ju2 {
     self.name_and_type_index
   }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub(crate) struct StringInfo {
   string_index: ju2,
 }
@@ -82,7 +86,7 @@ impl StringInfo {
   }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub(crate) struct NumericInfo {
   bytes: ju4,
 }
@@ -97,7 +101,7 @@ impl NumericInfo {
   }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub(crate) struct BigNumericInfo {
   high_bytes: ju4,
   low_bytes: ju4,
@@ -109,57 +113,99 @@ impl BigNumericInfo {
   }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub(crate) struct NameAndTypeInfo {
   name_index: ju2,
   descriptor_index: ju2,
 }
 
-#[derive(Debug, Clone)]
+impl NameAndTypeInfo {
+  pub(crate) fn get_name_index(&self) -> ju2 {
+    self.name_index
+  }
+
+  pub(crate) fn get_descriptor_index(&self) -> ju2 {
+    self.descriptor_index
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub(crate) struct Utf8Info {
   length: ju2,
   bytes: Vec<ju1>, // of length `length`
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 #[allow(dead_code)]
 pub(crate) struct MethodHandleInfo {
   reference_kind: ju1,  // Ref kind (like Ref_invokeStatic)
   reference_index: ju2, // reference in ConstantPool to MethodRef
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 #[allow(dead_code)]
 pub(crate) struct MethodTypeInfo {
   descriptor_index: ju2,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 #[allow(dead_code)]
 pub(crate) struct DynamicInfo {
   bootstrap_method_attr_index: ju2,
   name_and_type_index: ju2,
 }
 
-#[derive(Debug, Clone)]
+impl DynamicInfo {
+  pub(crate) fn get_bootstrap_method_attr_index(&self) -> ju2 {
+    self.bootstrap_method_attr_index
+  }
+
+  pub(crate) fn get_name_and_type_index(&self) -> ju2 {
+    self.name_and_type_index
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub(crate) struct InvokeDynamicInfo {
   bootstrap_method_attr_index: ju2,
   name_and_type_index: ju2,
 }
 
-#[derive(Debug, Clone)]
+impl InvokeDynamicInfo {
+  pub(crate) fn get_bootstrap_method_attr_index(&self) -> ju2 {
+    self.bootstrap_method_attr_index
+  }
+
+  pub(crate) fn get_name_and_type_index(&self) -> ju2 {
+    self.name_and_type_index
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 #[allow(dead_code)]
 pub(crate) struct ModuleInfo {
   name_index: ju2,
 }
 
-#[derive(Debug, Clone)]
+impl ModuleInfo {
+  pub(crate) fn get_name_index(&self) -> ju2 {
+    self.name_index
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 #[allow(dead_code)]
 pub(crate) struct PackageInfo {
   name_index: ju2,
 }
 
-#[derive(Debug, Clone)]
+impl PackageInfo {
+  pub(crate) fn get_name_index(&self) -> ju2 {
+    self.name_index
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub(crate) struct ClassInfo {
   name_index: ju2,
 }
@@ -170,7 +216,7 @@ impl ClassInfo {
   }
 }
 
-#[derive(Default, Debug, Clone)]
+#[derive(Default, Debug, Clone, PartialEq)]
 #[allow(dead_code)]
 pub(crate) enum CpInfoInfoEnum {
   #[default]
@@ -198,7 +244,7 @@ pub(crate) enum CpInfoInfoEnum {
   Dynamic(DynamicInfo),
   InvokeDynamic(InvokeDynamicInfo),
   // ============================================== //
-  _Module(ModuleInfo),
+  Module(ModuleInfo),
   // ============================================== //
   Package(PackageInfo),
   // ============================================== //
@@ -220,24 +266,16 @@ impl Utf8Info {
     }
   }
 
-  fn name(&self) -> String {
-    // TODO: alternative to clone
-    match String::from_utf8(self.bytes.clone()) {
-      Ok(string) => string,
-      Err(_) => "".to_string(),
-    }
+  pub(crate) fn name(&self) -> String {
+    from_modified_utf8(&self.bytes)
   }
 }
 
 impl ParseInfo<Utf8Info> for Utf8Info {
   fn parse(bytes: &[u8]) -> Result<Utf8Info> {
-    let length: usize = u16::from_be_bytes(bytes[0..2].try_into()?) as usize;
+    let length: usize = ju2_at(bytes, 0)? as usize;
 
-    let mut data: Vec<ju1> = vec![];
-
-    for i in 0..length {
-      data.push(bytes[i + 2]);
-    }
+    let data = get_slice(bytes, 2, length)?.to_vec();
 
     Ok(Utf8Info {
       length: length as ju2,
@@ -248,7 +286,7 @@ impl ParseInfo<Utf8Info> for Utf8Info {
 
 impl ParseInfo<NumericInfo> for NumericInfo {
   fn parse(bytes: &[u8]) -> Result<NumericInfo> {
-    let bytes = u32::from_be_bytes(bytes[0..4].try_into()?);
+    let bytes = ju4_at(bytes, 0)?;
 
     Ok(NumericInfo { bytes })
   }
@@ -256,9 +294,9 @@ impl ParseInfo<NumericInfo> for NumericInfo {
 
 impl ParseInfo<BigNumericInfo> for BigNumericInfo {
   fn parse(bytes: &[u8]) -> Result<BigNumericInfo> {
-    let high_bytes = u32::from_be_bytes(bytes[0..4].try_into()?);
+    let high_bytes = ju4_at(bytes, 0)?;
 
-    let low_bytes = u32::from_be_bytes(bytes[4..8].try_into()?);
+    let low_bytes = ju4_at(bytes, 4)?;
 
     Ok(BigNumericInfo {
       high_bytes,
@@ -269,7 +307,7 @@ impl ParseInfo<BigNumericInfo> for BigNumericInfo {
 
 impl ParseInfo<StringInfo> for StringInfo {
   fn parse(bytes: &[u8]) -> Result<StringInfo> {
-    let string_index = u16::from_be_bytes(bytes[0..2].try_into()?);
+    let string_index = ju2_at(bytes, 0)?;
 
     Ok(StringInfo { string_index })
   }
@@ -277,9 +315,9 @@ impl ParseInfo<StringInfo> for StringInfo {
 
 impl ParseInfo<RefInfo> for RefInfo {
   fn parse(bytes: &[u8]) -> Result<RefInfo> {
-    let class_index = u16::from_be_bytes(bytes[0..2].try_into()?);
+    let class_index = ju2_at(bytes, 0)?;
 
-    let name_and_type_index = u16::from_be_bytes(bytes[2..4].try_into()?);
+    let name_and_type_index = ju2_at(bytes, 2)?;
 
     Ok(RefInfo {
       class_index,
@@ -290,9 +328,9 @@ impl ParseInfo<RefInfo> for RefInfo {
 
 impl ParseInfo<NameAndTypeInfo> for NameAndTypeInfo {
   fn parse(bytes: &[u8]) -> Result<NameAndTypeInfo> {
-    let name_index = u16::from_be_bytes(bytes[0..2].try_into()?);
+    let name_index = ju2_at(bytes, 0)?;
 
-    let descriptor_index = u16::from_be_bytes(bytes[2..4].try_into()?);
+    let descriptor_index = ju2_at(bytes, 2)?;
 
     Ok(NameAndTypeInfo {
       name_index,
@@ -301,9 +339,17 @@ impl ParseInfo<NameAndTypeInfo> for NameAndTypeInfo {
   }
 }
 
+impl ParseInfo<ModuleInfo> for ModuleInfo {
+  fn parse(bytes: &[u8]) -> Result<ModuleInfo> {
+    let name_index = ju2_at(bytes, 0)?;
+
+    Ok(ModuleInfo { name_index })
+  }
+}
+
 impl ParseInfo<PackageInfo> for PackageInfo {
   fn parse(bytes: &[u8]) -> Result<PackageInfo> {
-    let name_index = ju2_from_bytes(&bytes[0..2])?;
+    let name_index = ju2_at(bytes, 0)?;
 
     Ok(PackageInfo { name_index })
   }
@@ -311,7 +357,7 @@ impl ParseInfo<PackageInfo> for PackageInfo {
 
 impl ParseInfo<ClassInfo> for ClassInfo {
   fn parse(bytes: &[u8]) -> Result<ClassInfo> {
-    let name_index = ju2_from_bytes(&bytes[0..2])?;
+    let name_index = ju2_at(bytes, 0)?;
 
     Ok(ClassInfo { name_index })
   }
@@ -319,9 +365,9 @@ impl ParseInfo<ClassInfo> for ClassInfo {
 
 impl ParseInfo<DynamicInfo> for DynamicInfo {
   fn parse(bytes: &[u8]) -> Result<DynamicInfo> {
-    let bootstrap_method_attr_index = ju2_from_bytes(&bytes[0..2])?;
+    let bootstrap_method_attr_index = ju2_at(bytes, 0)?;
 
-    let name_and_type_index = ju2_from_bytes(&bytes[2..4])?;
+    let name_and_type_index = ju2_at(bytes, 2)?;
 
     Ok(DynamicInfo {
       bootstrap_method_attr_index,
@@ -332,9 +378,9 @@ impl ParseInfo<DynamicInfo> for DynamicInfo {
 
 impl ParseInfo<InvokeDynamicInfo> for InvokeDynamicInfo {
   fn parse(bytes: &[u8]) -> Result<InvokeDynamicInfo> {
-    let bootstrap_method_attr_index = ju2_from_bytes(&bytes[0..2])?;
+    let bootstrap_method_attr_index = ju2_at(bytes, 0)?;
 
-    let name_and_type_index = ju2_from_bytes(&bytes[2..4])?;
+    let name_and_type_index = ju2_at(bytes, 2)?;
 
     Ok(InvokeDynamicInfo {
       bootstrap_method_attr_index,
@@ -345,7 +391,7 @@ impl ParseInfo<InvokeDynamicInfo> for InvokeDynamicInfo {
 
 impl ParseInfo<MethodTypeInfo> for MethodTypeInfo {
   fn parse(bytes: &[u8]) -> Result<MethodTypeInfo> {
-    let descriptor_index = ju2_from_bytes(&bytes[0..2])?;
+    let descriptor_index = ju2_at(bytes, 0)?;
 
     Ok(MethodTypeInfo { descriptor_index })
   }
@@ -353,9 +399,9 @@ impl ParseInfo<MethodTypeInfo> for MethodTypeInfo {
 
 impl ParseInfo<MethodHandleInfo> for MethodHandleInfo {
   fn parse(bytes: &[u8]) -> Result<MethodHandleInfo> {
-    let reference_kind = bytes[0];
+    let reference_kind = ju1_at(bytes, 0)?;
 
-    let reference_index = ju2_from_bytes(&bytes[1..3])?;
+    let reference_index = ju2_at(bytes, 1)?;
 
     Ok(MethodHandleInfo {
       reference_kind,
@@ -393,6 +439,7 @@ impl CpInfoInfoEnum {
       CpInfoTagEnum::Methodref => CpInfoInfoEnum::Methodref(RefInfo::parse(bytes)?),
       CpInfoTagEnum::Interfaceref => CpInfoInfoEnum::Interfaceref(RefInfo::parse(bytes)?),
       CpInfoTagEnum::NameAndType => CpInfoInfoEnum::NameAndType(NameAndTypeInfo::parse(bytes)?),
+      CpInfoTagEnum::Module => CpInfoInfoEnum::Module(ModuleInfo::parse(bytes)?),
       CpInfoTagEnum::Package => CpInfoInfoEnum::Package(PackageInfo::parse(bytes)?),
       CpInfoTagEnum::Class => CpInfoInfoEnum::Class(ClassInfo::parse(bytes)?),
       CpInfoTagEnum::Dynamic => CpInfoInfoEnum::Dynamic(DynamicInfo::parse(bytes)?),
@@ -401,14 +448,42 @@ impl CpInfoInfoEnum {
       }
       CpInfoTagEnum::MethodType => CpInfoInfoEnum::MethodType(MethodTypeInfo::parse(bytes)?),
       CpInfoTagEnum::MethodHandle => CpInfoInfoEnum::MethodHandle(MethodHandleInfo::parse(bytes)?),
-      _ => {
-        notimpl!("CpInfo not recognized for tag: {:?}", tag)
+      CpInfoTagEnum::None => {
+        return Err(eyre!(errors::JavaException::ClassFormatError(
+          "Invalid constant pool entry".to_string()
+        )))
       }
     };
 
     Ok(res)
   }
 
+  /// Tag of the entry, 0 for the unusable entry after a long or a double
+  pub(crate) fn get_tag(&self) -> ju1 {
+    let tag = match self {
+      Self::None => CpInfoTagEnum::None,
+      Self::Fieldref(_) => CpInfoTagEnum::Fieldref,
+      Self::Methodref(_) => CpInfoTagEnum::Methodref,
+      Self::Interfaceref(_) => CpInfoTagEnum::Interfaceref,
+      Self::String(_) => CpInfoTagEnum::String,
+      Self::Integer(_) => CpInfoTagEnum::Integer,
+      Self::Float(_) => CpInfoTagEnum::Float,
+      Self::Long(_) => CpInfoTagEnum::Long,
+      Self::Double(_) => CpInfoTagEnum::Double,
+      Self::NameAndType(_) => CpInfoTagEnum::NameAndType,
+      Self::Utf8(_) => CpInfoTagEnum::Utf8,
+      Self::MethodHandle(_) => CpInfoTagEnum::MethodHandle,
+      Self::MethodType(_) => CpInfoTagEnum::MethodType,
+      Self::Dynamic(_) => CpInfoTagEnum::Dynamic,
+      Self::InvokeDynamic(_) => CpInfoTagEnum::InvokeDynamic,
+      Self::Module(_) => CpInfoTagEnum::Module,
+      Self::Package(_) => CpInfoTagEnum::Package,
+      Self::Class(_) => CpInfoTagEnum::Class,
+    };
+
+    tag as ju1
+  }
+
   // expressed in bytes
   fn get_size(&self) -> usize {
     // 1 to consider the tag
@@ -423,13 +498,14 @@ impl CpInfoInfoEnum {
       CpInfoInfoEnum::Methodref(_) => 4,
       CpInfoInfoEnum::Interfaceref(_) => 4,
       CpInfoInfoEnum::NameAndType(_) => 4,
+      CpInfoInfoEnum::Module(_) => 2,
       CpInfoInfoEnum::Package(_) => 2,
       CpInfoInfoEnum::Class(_) => 2,
       CpInfoInfoEnum::Dynamic(_) => 4,
       CpInfoInfoEnum::InvokeDynamic(_) => 4,
       CpInfoInfoEnum::MethodType(_) => 2,
       CpInfoInfoEnum::MethodHandle(_) => 3,
-      _ => notimpl!("get size not impl for {:?}", self),
+      CpInfoInfoEnum::None => 0,
     }
   }
 }
@@ -441,9 +517,9 @@ pub(crate) struct CpInfo {
 
 impl CpInfo {
   fn parse(bytes: &[u8]) -> Result<(CpInfo, usize)> {
-    let tag = CpInfoTagEnum::from_num(bytes[0])?;
+    let tag = CpInfoTagEnum::from_num(ju1_at(bytes, 0)?)?;
 
-    let info = CpInfoInfoEnum::from_tag(&tag, &bytes[1..])?;
+    let info = CpInfoInfoEnum::from_tag(&tag, get_tail(bytes, 1)?)?;
 
     let size = info.get_size();
 
@@ -457,8 +533,148 @@ impl CpInfo {
   }
 }
 
+impl WriteInfo for CpInfo {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    match &self.info {
+      // second slot of a long or a double
+      CpInfoInfoEnum::None => {}
+      CpInfoInfoEnum::Utf8(info) => {
+        bytes.push(CpInfoTagEnum::Utf8 as ju1);
+        bytes.extend(info.length.to_be_bytes());
+        bytes.extend(&info.bytes);
+      }
+      CpInfoInfoEnum::Integer(info) | CpInfoInfoEnum::Float(info) => {
+        bytes.push(match self.info {
+          CpInfoInfoEnum::Integer(_) => CpInfoTagEnum::Integer as ju1,
+          _ => CpInfoTagEnum::Float as ju1,
+        });
+        bytes.extend(info.bytes.to_be_bytes());
+      }
+      CpInfoInfoEnum::Long(info) | CpInfoInfoEnum::Double(info) => {
+        bytes.push(match self.info {
+          CpInfoInfoEnum::Long(_) => CpInfoTagEnum::Long as ju1,
+          _ => CpInfoTagEnum::Double as ju1,
+        });
+        bytes.extend(info.high_bytes.to_be_bytes());
+        bytes.extend(info.low_bytes.to_be_bytes());
+      }
+      CpInfoInfoEnum::Class(info) => {
+        bytes.push(CpInfoTagEnum::Class as ju1);
+        bytes.extend(info.name_index.to_be_bytes());
+      }
+      CpInfoInfoEnum::String(info) => {
+        bytes.push(CpInfoTagEnum::String as ju1);
+        bytes.extend(info.string_index.to_be_bytes());
+      }
+      CpInfoInfoEnum::Fieldref(info)
+      | CpInfoInfoEnum::Methodref(info)
+      | CpInfoInfoEnum::Interfaceref(info) => {
+        bytes.push(match self.info {
+          CpInfoInfoEnum::Fieldref(_) => CpInfoTagEnum::Fieldref as ju1,
+          CpInfoInfoEnum::Methodref(_) => CpInfoTagEnum::Methodref as ju1,
+          _ => CpInfoTagEnum::Interfaceref as ju1,
+        });
+        bytes.extend(info.class_index.to_be_bytes());
+        bytes.extend(info.name_and_type_index.to_be_bytes());
+      }
+      CpInfoInfoEnum::NameAndType(info) => {
+        bytes.push(CpInfoTagEnum::NameAndType as ju1);
+        bytes.extend(info.name_index.to_be_bytes());
+        bytes.extend(info.descriptor_index.to_be_bytes());
+      }
+      CpInfoInfoEnum::MethodHandle(info) => {
+        bytes.push(CpInfoTagEnum::MethodHandle as ju1);
+        bytes.push(info.reference_kind);
+        bytes.extend(info.reference_index.to_be_bytes());
+      }
+      CpInfoInfoEnum::MethodType(info) => {
+        bytes.push(CpInfoTagEnum::MethodType as ju1);
+        bytes.extend(info.descriptor_index.to_be_bytes());
+      }
+      CpInfoInfoEnum::Dynamic(info) => {
+        bytes.push(CpInfoTagEnum::Dynamic as ju1);
+        bytes.extend(info.bootstrap_method_attr_index.to_be_bytes());
+        bytes.extend(info.name_and_type_index.to_be_bytes());
+      }
+      CpInfoInfoEnum::InvokeDynamic(info) => {
+        bytes.push(CpInfoTagEnum::InvokeDynamic as ju1);
+        bytes.extend(info.bootstrap_method_attr_index.to_be_bytes());
+        bytes.extend(info.name_and_type_index.to_be_bytes());
+      }
+      CpInfoInfoEnum::Module(info) => {
+        bytes.push(CpInfoTagEnum::Module as ju1);
+        bytes.extend(info.name_index.to_be_bytes());
+      }
+      CpInfoInfoEnum::Package(info) => {
+        bytes.push(CpInfoTagEnum::Package as ju1);
+        bytes.extend(info.name_index.to_be_bytes());
+      }
+    }
+  }
+}
+
+// https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.4.7
+//
+// Modified UTF-8: the null character takes two bytes and a supplementary
+// character is encoded as a surrogate pair of three bytes each.
+fn to_modified_utf8(value: &str) -> Vec<ju1> {
+  let mut bytes = vec![];
+
+  for unit in value.encode_utf16() {
+    match unit {
+      0x0001..=0x007f => bytes.push(unit as ju1),
+      0x0000 | 0x0080..=0x07ff => {
+        bytes.push(0xc0 | (unit >> 6) as ju1);
+        bytes.push(0x80 | (unit & 0x3f) as ju1);
+      }
+      _ => {
+        bytes.push(0xe0 | (unit >> 12) as ju1);
+        bytes.push(0x80 | ((unit >> 6) & 0x3f) as ju1);
+        bytes.push(0x80 | (unit & 0x3f) as ju1);
+      }
+    }
+  }
+
+  bytes
+}
+
+// inverse of `to_modified_utf8`, an invalid sequence is replaced by U+FFFD
+fn from_modified_utf8(bytes: &[ju1]) -> String {
+  if bytes.is_ascii() {
+    return String::from_utf8_lossy(bytes).into_owned();
+  }
+
+  let mut units: Vec<ju2> = vec![];
+  let mut i = 0;
+
+  while i < bytes.len() {
+    let byte = bytes[i] as ju2;
+
+    match (bytes[i], bytes.get(i + 1), bytes.get(i + 2)) {
+      (0x00..=0x7f, _, _) => {
+        units.push(byte);
+        i += 1;
+      }
+      (0xc0..=0xdf, Some(b2), _) => {
+        units.push((byte & 0x1f) << 6 | (*b2 as ju2 & 0x3f));
+        i += 2;
+      }
+      (0xe0..=0xef, Some(b2), Some(b3)) => {
+        units.push((byte & 0x0f) << 12 | (*b2 as ju2 & 0x3f) << 6 | (*b3 as ju2 & 0x3f));
+        i += 3;
+      }
+      _ => {
+        units.push(0xfffd);
+        i += 1;
+      }
+    }
+  }
+
+  String::from_utf16_lossy(&units)
+}
+
 #[derive(Debug, Clone)]
-pub(crate) struct ConstantPool {
+pub struct ConstantPool {
   constant_pool_count: ju2,
   constant_pool: Vec<CpInfo>,
 }
@@ -480,6 +696,16 @@ impl fmt::Display for ConstantPool {
   }
 }
 
+impl WriteInfo for ConstantPool {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend(((self.constant_pool.len() + 1) as ju2).to_be_bytes());
+
+    for cpinfo in &self.constant_pool {
+      cpinfo.write(bytes);
+    }
+  }
+}
+
 impl ConstantPool {
   pub(crate) fn parse(bytes: &[u8]) -> Result<(ConstantPool, usize)> {
     debug!("[-] Parsing Constant Pools");
@@ -488,7 +714,13 @@ impl ConstantPool {
 
     let mut index: usize = 0;
 
-    let count: usize = u16::from_be_bytes(bytes[0..2].try_into()?) as usize;
+    let count: usize = ju2_at(bytes, 0)? as usize;
+
+    if count == 0 {
+      return Err(format_error(
+        "Illegal constant pool size 0 in class file".to_string(),
+      ));
+    }
 
     debug!("    Found {:?} constant pools", count - 1);
 
@@ -498,13 +730,13 @@ impl ConstantPool {
     let mut i = 1;
 
     while i < count {
-      let slice = &bytes[index..];
+      let slice = get_tail(bytes, index)?;
 
       let (cpinfo, bytes_read) = CpInfo::parse(slice)?;
 
       index += bytes_read;
 
-      // https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.4.5
+      // https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.4.5
       // ony valid for long and double
       let mut add_fake_entry = false;
 
@@ -528,6 +760,14 @@ impl ConstantPool {
       }
     }
 
+    // a long or a double can not be the last entry
+    if v.len() != count - 1 {
+      return Err(format_error(format!(
+        "Invalid constant pool entry {} in class file",
+        count - 1
+      )));
+    }
+
     Ok((
       ConstantPool {
         constant_pool_count: count as ju2,
@@ -537,6 +777,186 @@ impl ConstantPool {
     ))
   }
 
+  /// Check that the entries refer to entries of the expected kinds, and that
+  /// the names and descriptors are well formed (JVMS 4.4)
+  pub(crate) fn check_format(&self, major_version: ju2) -> Result<()> {
+    for (i, cpinfo) in self.constant_pool.iter().enumerate() {
+      let index = (i + 1) as ju2;
+
+      let valid = match &cpinfo.info {
+        CpInfoInfoEnum::None => true,
+        CpInfoInfoEnum::Utf8(info) => is_modified_utf8(&info.bytes),
+        CpInfoInfoEnum::Integer(_)
+        | CpInfoInfoEnum::Float(_)
+        | CpInfoInfoEnum::Long(_)
+        | CpInfoInfoEnum::Double(_) => true,
+        CpInfoInfoEnum::Class(info) => is_class_name(&self.check_utf8(index, info.name_index)?),
+        CpInfoInfoEnum::String(info) => self.check_utf8(index, info.string_index).is_ok(),
+        CpInfoInfoEnum::Module(info) => self.check_utf8(index, info.name_index).is_ok(),
+        CpInfoInfoEnum::Package(info) => self.check_utf8(index, info.name_index).is_ok(),
+        CpInfoInfoEnum::NameAndType(info) => {
+          self.check_utf8(index, info.name_index)?;
+          self.check_utf8(index, info.descriptor_index)?;
+
+          true
+        }
+        CpInfoInfoEnum::Fieldref(info) => {
+          self.check_kind(index, info.class_index, "Class")?;
+
+          let (name, descriptor) = self.check_name_and_type(index, info.name_and_type_index)?;
+
+          is_unqualified_name(&name) && is_field_descriptor(&descriptor)
+        }
+        CpInfoInfoEnum::Methodref(info) | CpInfoInfoEnum::Interfaceref(info) => {
+          self.check_kind(index, info.class_index, "Class")?;
+
+          let (name, descriptor) = self.check_name_and_type(index, info.name_and_type_index)?;
+
+          let is_method = matches!(cpinfo.info, CpInfoInfoEnum::Methodref(_));
+
+          // only a method can be a constructor, and no method is the class initializer
+          is_method_name(&name)
+            && is_method_descriptor(&descriptor)
+            && name != "<clinit>"
+            && (name != "<init>" || (is_method && descriptor.ends_with(")V")))
+        }
+        CpInfoInfoEnum::MethodHandle(info) => {
+          self.check_method_handle(index, info, major_version)?
+        }
+        CpInfoInfoEnum::MethodType(info) => {
+          is_method_descriptor(&self.check_utf8(index, info.descriptor_index)?)
+        }
+        CpInfoInfoEnum::Dynamic(info) => {
+          let (name, descriptor) = self.check_name_and_type(index, info.name_and_type_index)?;
+
+          is_unqualified_name(&name) && is_field_descriptor(&descriptor)
+        }
+        CpInfoInfoEnum::InvokeDynamic(info) => {
+          let (name, descriptor) = self.check_name_and_type(index, info.name_and_type_index)?;
+
+          is_method_name(&name) && is_method_descriptor(&descriptor)
+        }
+      };
+
+      if !valid {
+        return Err(format_error(format!(
+          "Illegal constant pool entry {} in class file",
+          index
+        )));
+      }
+    }
+
+    Ok(())
+  }
+
+  // https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.4.8
+  fn check_method_handle(
+    &self,
+    index: ju2,
+    info: &MethodHandleInfo,
+    major_version: ju2,
+  ) -> Result<bool> {
+    let reference = info.reference_index;
+
+    let name = match info.reference_kind {
+      // REF_getField, REF_getStatic, REF_putField, REF_putStatic
+      1..=4 => return Ok(self.check_kind(index, reference, "Fieldref").is_ok()),
+      // REF_invokeVirtual, REF_newInvokeSpecial
+      5 | 8 => self.check_kind(index, reference, "Methodref")?,
+      // REF_invokeStatic, REF_invokeSpecial (interface methods since version 52)
+      6 | 7 if major_version >= 52 => match self.check_kind(index, reference, "Methodref") {
+        Ok(name) => name,
+        Err(_) => self.check_kind(index, reference, "InterfaceMethodref")?,
+      },
+      6 | 7 => self.check_kind(index, reference, "Methodref")?,
+      // REF_invokeInterface
+      9 => self.check_kind(index, reference, "InterfaceMethodref")?,
+      _ => return Ok(false),
+    };
+
+    // only REF_newInvokeSpecial refers to a constructor
+    Ok((info.reference_kind == 8) == (name == "<init>"))
+  }
+
+  // name of the Utf8 entry `target` referenced by the entry `index`
+  fn check_utf8(&self, index: ju2, target: ju2) -> Result<String> {
+    match self.constant_pool.get((target as usize).wrapping_sub(1)) {
+      Some(CpInfo {
+        info: CpInfoInfoEnum::Utf8(info),
+      }) => Ok(info.name()),
+      _ => Err(format_error(format!(
+        "Invalid constant pool index {} in entry {} of class file",
+        target, index
+      ))),
+    }
+  }
+
+  // the entry `index` refers to the entry `target` of kind `kind`
+  //
+  // # Returns
+  //
+  // The name of the class or of the member referenced by `target`
+  fn check_kind(&self, index: ju2, target: ju2, kind: &str) -> Result<String> {
+    let name = match (
+      self.constant_pool.get((target as usize).wrapping_sub(1)),
+      kind,
+    ) {
+      (
+        Some(CpInfo {
+          info: CpInfoInfoEnum::Class(info),
+        }),
+        "Class",
+      ) => Some(self.check_utf8(index, info.name_index)?),
+      (
+        Some(CpInfo {
+          info: CpInfoInfoEnum::Fieldref(info),
+        }),
+        "Fieldref",
+      )
+      | (
+        Some(CpInfo {
+          info: CpInfoInfoEnum::Methodref(info),
+        }),
+        "Methodref",
+      )
+      | (
+        Some(CpInfo {
+          info: CpInfoInfoEnum::Interfaceref(info),
+        }),
+        "InterfaceMethodref",
+      ) => Some(self.check_name_and_type(index, info.name_and_type_index)?.0),
+      _ => None,
+    };
+
+    name.ok_or_else(|| {
+      format_error(format!(
+        "Invalid constant pool index {} in entry {} of class file (expected {})",
+        target, index, kind
+      ))
+    })
+  }
+
+  // name and descriptor of the NameAndType entry `target` referenced by the entry `index`
+  fn check_name_and_type(&self, index: ju2, target: ju2) -> Result<(String, String)> {
+    match self.constant_pool.get((target as usize).wrapping_sub(1)) {
+      Some(CpInfo {
+        info: CpInfoInfoEnum::NameAndType(info),
+      }) => Ok((
+        self.check_utf8(index, info.name_index)?,
+        self.check_utf8(index, info.descriptor_index)?,
+      )),
+      _ => Err(format_error(format!(
+        "Invalid constant pool index {} in entry {} of class file (expected NameAndType)",
+        target, index
+      ))),
+    }
+  }
+
+  /// Number of entries, including the unusable entries after longs and doubles
+  pub(crate) fn len(&self) -> usize {
+    self.constant_pool.len()
+  }
+
   pub(crate) fn empty() -> ConstantPool {
     ConstantPool {
       constant_pool_count: 0,
@@ -544,6 +964,160 @@ impl ConstantPool {
     }
   }
 
+  /// Index of the entry `info`, added at the end of the pool if it is not there yet
+  fn add(&mut self, info: CpInfoInfoEnum) -> ju2 {
+    if let Some(i) = self.constant_pool.iter().position(|cp| cp.info == info) {
+      return (i + 1) as ju2;
+    }
+
+    let wide = matches!(info, CpInfoInfoEnum::Long(_) | CpInfoInfoEnum::Double(_));
+
+    self.constant_pool.push(CpInfo { info });
+
+    let index = self.constant_pool.len() as ju2;
+
+    // a long or a double takes two entries
+    if wide {
+      self.constant_pool.push(CpInfo {
+        info: CpInfoInfoEnum::None,
+      });
+    }
+
+    self.constant_pool_count = (self.constant_pool.len() + 1) as ju2;
+
+    index
+  }
+
+  pub fn add_utf8(&mut self, value: &str) -> ju2 {
+    let bytes = to_modified_utf8(value);
+
+    self.add(CpInfoInfoEnum::Utf8(Utf8Info {
+      length: bytes.len() as ju2,
+      bytes,
+    }))
+  }
+
+  pub fn add_integer(&mut self, value: i32) -> ju2 {
+    self.add(CpInfoInfoEnum::Integer(NumericInfo {
+      bytes: value as ju4,
+    }))
+  }
+
+  pub fn add_float(&mut self, value: f32) -> ju2 {
+    self.add(CpInfoInfoEnum::Float(NumericInfo {
+      bytes: value.to_bits(),
+    }))
+  }
+
+  pub fn add_long(&mut self, value: i64) -> ju2 {
+    self.add(CpInfoInfoEnum::Long(BigNumericInfo {
+      high_bytes: (value as ju8 >> 32) as ju4,
+      low_bytes: value as ju4,
+    }))
+  }
+
+  pub fn add_double(&mut self, value: f64) -> ju2 {
+    self.add(CpInfoInfoEnum::Double(BigNumericInfo {
+      high_bytes: (value.to_bits() >> 32) as ju4,
+      low_bytes: value.to_bits() as ju4,
+    }))
+  }
+
+  pub fn add_class(&mut self, name: &str) -> ju2 {
+    let name_index = self.add_utf8(name);
+
+    self.add(CpInfoInfoEnum::Class(ClassInfo { name_index }))
+  }
+
+  pub fn add_string(&mut self, value: &str) -> ju2 {
+    let string_index = self.add_utf8(value);
+
+    self.add(CpInfoInfoEnum::String(StringInfo { string_index }))
+  }
+
+  pub fn add_name_and_type(&mut self, name: &str, descriptor: &str) -> ju2 {
+    let name_index = self.add_utf8(name);
+    let descriptor_index = self.add_utf8(descriptor);
+
+    self.add(CpInfoInfoEnum::NameAndType(NameAndTypeInfo {
+      name_index,
+      descriptor_index,
+    }))
+  }
+
+  pub fn add_field_ref(&mut self, class_name: &str, name: &str, descriptor: &str) -> ju2 {
+    let info = self.ref_info(class_name, name, descriptor);
+
+    self.add(CpInfoInfoEnum::Fieldref(info))
+  }
+
+  pub fn add_method_ref(&mut self, class_name: &str, name: &str, descriptor: &str) -> ju2 {
+    let info = self.ref_info(class_name, name, descriptor);
+
+    self.add(CpInfoInfoEnum::Methodref(info))
+  }
+
+  pub fn add_interface_method_ref(
+    &mut self,
+    class_name: &str,
+    name: &str,
+    descriptor: &str,
+  ) -> ju2 {
+    let info = self.ref_info(class_name, name, descriptor);
+
+    self.add(CpInfoInfoEnum::Interfaceref(info))
+  }
+
+  pub fn add_method_type(&mut self, descriptor: &str) -> ju2 {
+    let descriptor_index = self.add_utf8(descriptor);
+
+    self.add(CpInfoInfoEnum::MethodType(MethodTypeInfo {
+      descriptor_index,
+    }))
+  }
+
+  pub fn add_method_handle(&mut self, reference_kind: ju1, reference_index: ju2) -> ju2 {
+    self.add(CpInfoInfoEnum::MethodHandle(MethodHandleInfo {
+      reference_kind,
+      reference_index,
+    }))
+  }
+
+  pub fn add_invoke_dynamic(
+    &mut self,
+    bootstrap_method_attr_index: ju2,
+    name: &str,
+    descriptor: &str,
+  ) -> ju2 {
+    let name_and_type_index = self.add_name_and_type(name, descriptor);
+
+    self.add(CpInfoInfoEnum::InvokeDynamic(InvokeDynamicInfo {
+      bootstrap_method_attr_index,
+      name_and_type_index,
+    }))
+  }
+
+  pub fn add_dynamic(
+    &mut self,
+    bootstrap_method_attr_index: ju2,
+    name: &str,
+    descriptor: &str,
+  ) -> ju2 {
+    let name_and_type_index = self.add_name_and_type(name, descriptor);
+
+    self.add(CpInfoInfoEnum::Dynamic(DynamicInfo {
+      bootstrap_method_attr_index,
+      name_and_type_index,
+    }))
+  }
+
+  fn ref_info(&mut self, class_name: &str, name: &str, descriptor: &str) -> RefInfo {
+    RefInfo {
+      class_index: self.add_class(class_name),
+      name_and_type_index: self.add_name_and_type(name, descriptor),
+    }
+  }
+
   pub(crate) fn _print_name(&self, index: ju2) {
     match self.constant_pool.get((index - 1) as usize) {
       None => {
@@ -691,9 +1265,14 @@ impl ConstantPool {
     let reference_kind = metinfo.get_reference_kind();
     let reference_index = metinfo.get_reference_index();
 
-    let (method_class, method_name, method_type) = self.resolve_method_ref(reference_index)?;
+    // the kinds 1 to 4 (getField to putStatic) reference a field
+    let (member_class, member_name, member_type) = if reference_kind <= 4 {
+      self.resolve_field_ref(reference_index)?
+    } else {
+      self.resolve_method_ref(reference_index)?
+    };
 
-    Ok((reference_kind, method_class, method_name, method_type))
+    Ok((reference_kind, member_class, member_name, member_type))
   }
 
   pub(crate) fn resolve_method_type(&self, index: ju2) -> Result<String> {
@@ -735,6 +1314,25 @@ impl ConstantPool {
     Ok((refinfo.bootstrap_method_attr_index, name, _type))
   }
 
+  pub(crate) fn resolve_dynamic(&self, index: ju2) -> Result<(ju2, String, String)> {
+    let value: &CpInfo = self.resolve_index(index)?;
+
+    let refinfo = match &value.info {
+      CpInfoInfoEnum::Dynamic(refinfo) => refinfo,
+      _ => {
+        return Err(eyre!(
+          "[!] Index {:?} is not Dynamic but {:?}",
+          index,
+          value
+        ));
+      }
+    };
+
+    let (name, _type) = self.resolve_name_and_type(index)?;
+
+    Ok((refinfo.bootstrap_method_attr_index, name, _type))
+  }
+
   fn resolve_name_and_type_index(&self, index: ju2) -> Result<ju2> {
     let value: &CpInfo = self.resolve_index(index)?;
 
@@ -744,6 +1342,7 @@ impl ConstantPool {
       CpInfoInfoEnum::Methodref(refinfo) => Ok(refinfo.name_and_type_index),
       CpInfoInfoEnum::Interfaceref(refinfo) => Ok(refinfo.name_and_type_index),
       CpInfoInfoEnum::InvokeDynamic(refinfo) => Ok(refinfo.name_and_type_index),
+      CpInfoInfoEnum::Dynamic(refinfo) => Ok(refinfo.name_and_type_index),
       _ => Err(eyre!("[!] Index {:?} is not Ref but {:?}", index, value)),
     }
   }
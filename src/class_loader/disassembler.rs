@@ -0,0 +1,1401 @@
+use std::fmt::Write;
+
+use color_eyre::eyre::{eyre, Result};
+
+use super::{
+  attributes::*,
+  class_file::ClassFile,
+  constant_pool::{ConstantPool, CpInfoInfoEnum},
+  fields::FieldInfo,
+  methods::MethodInfo,
+};
+use crate::{runtime::opcode::OpCode, utils::*};
+
+// https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.1-200-E.1
+const CLASS_FLAGS: &[(ju2, &str)] = &[
+  (0x0001, "ACC_PUBLIC"),
+  (0x0010, "ACC_FINAL"),
+  (0x0020, "ACC_SUPER"),
+  (0x0200, "ACC_INTERFACE"),
+  (0x0400, "ACC_ABSTRACT"),
+  (0x1000, "ACC_SYNTHETIC"),
+  (0x2000, "ACC_ANNOTATION"),
+  (0x4000, "ACC_ENUM"),
+  (0x8000, "ACC_MODULE"),
+];
+
+// https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.5-200-A.1
+const FIELD_FLAGS: &[(ju2, &str)] = &[
+  (0x0001, "ACC_PUBLIC"),
+  (0x0002, "ACC_PRIVATE"),
+  (0x0004, "ACC_PROTECTED"),
+  (0x0008, "ACC_STATIC"),
+  (0x0010, "ACC_FINAL"),
+  (0x0040, "ACC_VOLATILE"),
+  (0x0080, "ACC_TRANSIENT"),
+  (0x1000, "ACC_SYNTHETIC"),
+  (0x4000, "ACC_ENUM"),
+];
+
+// https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.6-200-A.1
+const METHOD_FLAGS: &[(ju2, &str)] = &[
+  (0x0001, "ACC_PUBLIC"),
+  (0x0002, "ACC_PRIVATE"),
+  (0x0004, "ACC_PROTECTED"),
+  (0x0008, "ACC_STATIC"),
+  (0x0010, "ACC_FINAL"),
+  (0x0020, "ACC_SYNCHRONIZED"),
+  (0x0040, "ACC_BRIDGE"),
+  (0x0080, "ACC_VARARGS"),
+  (0x0100, "ACC_NATIVE"),
+  (0x0400, "ACC_ABSTRACT"),
+  (0x0800, "ACC_STRICT"),
+  (0x1000, "ACC_SYNTHETIC"),
+];
+
+// modifiers of the declarations, in the order of the Java language
+const FIELD_MODIFIERS: &[(ju2, &str)] = &[
+  (0x0001, "public"),
+  (0x0002, "private"),
+  (0x0004, "protected"),
+  (0x0008, "static"),
+  (0x0010, "final"),
+  (0x0040, "volatile"),
+  (0x0080, "transient"),
+];
+
+const METHOD_MODIFIERS: &[(ju2, &str)] = &[
+  (0x0001, "public"),
+  (0x0002, "private"),
+  (0x0004, "protected"),
+  (0x0400, "abstract"),
+  (0x0008, "static"),
+  (0x0010, "final"),
+  (0x0020, "synchronized"),
+  (0x0100, "native"),
+];
+
+// https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-5.html#jvms-5.4.3.5-220
+const REFERENCE_KINDS: [&str; 10] = [
+  "",
+  "REF_getField",
+  "REF_getStatic",
+  "REF_putField",
+  "REF_putStatic",
+  "REF_invokeVirtual",
+  "REF_invokeStatic",
+  "REF_invokeSpecial",
+  "REF_newInvokeSpecial",
+  "REF_invokeInterface",
+];
+
+// https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.7.20-400
+const TARGET_TYPES: &[(usize, &str)] = &[
+  (0x00, "CLASS_TYPE_PARAMETER"),
+  (0x01, "METHOD_TYPE_PARAMETER"),
+  (0x10, "CLASS_EXTENDS"),
+  (0x11, "CLASS_TYPE_PARAMETER_BOUND"),
+  (0x12, "METHOD_TYPE_PARAMETER_BOUND"),
+  (0x13, "FIELD"),
+  (0x14, "METHOD_RETURN"),
+  (0x15, "METHOD_RECEIVER"),
+  (0x16, "METHOD_FORMAL_PARAMETER"),
+  (0x17, "THROWS"),
+  (0x40, "LOCAL_VARIABLE"),
+  (0x41, "RESOURCE_VARIABLE"),
+  (0x42, "EXCEPTION_PARAMETER"),
+  (0x43, "INSTANCEOF"),
+  (0x44, "NEW"),
+  (0x45, "CONSTRUCTOR_REFERENCE"),
+  (0x46, "METHOD_REFERENCE"),
+  (0x47, "CAST"),
+  (0x48, "CONSTRUCTOR_INVOCATION_TYPE_ARGUMENT"),
+  (0x49, "METHOD_INVOCATION_TYPE_ARGUMENT"),
+  (0x4A, "CONSTRUCTOR_REFERENCE_TYPE_ARGUMENT"),
+  (0x4B, "METHOD_REFERENCE_TYPE_ARGUMENT"),
+];
+
+// column of the comments after the operands
+const COMMENT_COLUMN: usize = 40;
+
+fn flags_to_string(access_flags: ju2, names: &[(ju2, &str)]) -> String {
+  let names: Vec<&str> = names
+    .iter()
+    .filter(|(flag, _)| access_flags & flag != 0)
+    .map(|(_, name)| *name)
+    .collect();
+
+  format!("(0x{:04x}) {}", access_flags, names.join(", "))
+    .trim_end()
+    .to_string()
+}
+
+fn modifiers_to_string(access_flags: ju2, modifiers: &[(ju2, &str)]) -> String {
+  modifiers
+    .iter()
+    .filter(|(flag, _)| access_flags & flag != 0)
+    .map(|(_, modifier)| format!("{} ", modifier))
+    .collect()
+}
+
+/// Java type of a field descriptor (e.g. `[Ljava/lang/String;` is `java.lang.String[]`)
+pub(crate) fn descriptor_to_java_type(desc: &str) -> String {
+  let dimensions = desc.bytes().take_while(|b| *b == b'[').count();
+
+  let element = match &desc[dimensions..] {
+    "B" => "byte".to_string(),
+    "C" => "char".to_string(),
+    "D" => "double".to_string(),
+    "F" => "float".to_string(),
+    "I" => "int".to_string(),
+    "J" => "long".to_string(),
+    "S" => "short".to_string(),
+    "Z" => "boolean".to_string(),
+    "V" => "void".to_string(),
+    element => class_to_dotclass(&descriptor_to_classname(element)),
+  };
+
+  element + &"[]".repeat(dimensions)
+}
+
+// number of local variable slots taken by the parameters of a method
+fn get_args_size(descriptor: &str, is_static: bool) -> usize {
+  let parameters = parse_parameter_types(get_parameters_type_descriptor(descriptor));
+
+  let slots: usize = parameters
+    .iter()
+    .map(|param| if *param == "J" || *param == "D" { 2 } else { 1 })
+    .sum();
+
+  if is_static {
+    slots
+  } else {
+    slots + 1
+  }
+}
+
+// a member name is quoted when it is not a Java identifier (e.g. "<init>")
+fn quote_member_name(name: &str) -> String {
+  if name.starts_with('<') {
+    format!("\"{}\"", name)
+  } else {
+    name.to_string()
+  }
+}
+
+fn escape_string(value: &str) -> String {
+  let mut escaped = String::new();
+
+  for c in value.chars() {
+    match c {
+      '\n' => escaped.push_str("\\n"),
+      '\r' => escaped.push_str("\\r"),
+      '\t' => escaped.push_str("\\t"),
+      '\\' => escaped.push_str("\\\\"),
+      '"' => escaped.push_str("\\\""),
+      c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+      c => escaped.push(c),
+    }
+  }
+
+  escaped
+}
+
+fn with_comment(line: &str, comment: &str) -> String {
+  if comment.is_empty() {
+    line.trim_end().to_string()
+  } else {
+    format!("{:<width$}// {}", line, comment, width = COMMENT_COLUMN - 1)
+  }
+}
+
+/// Disassembler of a class file, printing it like `javap -v -p`
+struct Disassembler<'a> {
+  class_file: &'a ClassFile,
+  cp: &'a ConstantPool,
+  out: String,
+}
+
+impl Disassembler<'_> {
+  fn disassemble(mut self) -> Result<String> {
+    self.header()?;
+    self.constant_pool()?;
+
+    writeln!(self.out, "{{")?;
+
+    let fields = self.class_file.get_fields();
+    let methods = self.class_file.get_methods();
+
+    for (i, field) in fields.iter().enumerate() {
+      if i > 0 {
+        writeln!(self.out)?;
+      }
+
+      self.field(field)?;
+    }
+
+    for (i, method) in methods.iter().enumerate() {
+      if i > 0 || !fields.is_empty() {
+        writeln!(self.out)?;
+      }
+
+      self.method(method)?;
+    }
+
+    writeln!(self.out, "}}")?;
+
+    self.attributes(self.class_file.get_attributes(), "")?;
+
+    Ok(self.out)
+  }
+
+  fn header(&mut self) -> Result<()> {
+    let class_file = self.class_file;
+    let access_flags = class_file.get_access_flags();
+
+    let is_interface = access_flags & 0x0200 != 0;
+
+    let mut declaration = modifiers_to_string(access_flags & 0x0001, FIELD_MODIFIERS);
+
+    if !is_interface {
+      declaration += &modifiers_to_string(access_flags & 0x0410, METHOD_MODIFIERS);
+    }
+
+    declaration += if is_interface { "interface " } else { "class " };
+    declaration += &class_to_dotclass(class_file.get_name());
+
+    let interfaces: Vec<String> = class_file
+      .get_interfaces()
+      .iter()
+      .map(|interface| class_to_dotclass(interface))
+      .collect();
+
+    if is_interface {
+      if !interfaces.is_empty() {
+        declaration += &format!(" extends {}", interfaces.join(","));
+      }
+    } else {
+      if class_file.has_parent() {
+        declaration += &format!(
+          " extends {}",
+          class_to_dotclass(class_file.get_parent_name())
+        );
+      }
+
+      if !interfaces.is_empty() {
+        declaration += &format!(" implements {}", interfaces.join(","));
+      }
+    }
+
+    writeln!(self.out, "{}", declaration)?;
+    writeln!(
+      self.out,
+      "  minor version: {}",
+      class_file.get_minor_version()
+    )?;
+    writeln!(
+      self.out,
+      "  major version: {}",
+      class_file.get_major_version()
+    )?;
+    writeln!(
+      self.out,
+      "  flags: {}",
+      flags_to_string(access_flags, CLASS_FLAGS)
+    )?;
+
+    let this_class = class_file.get_this_class_index();
+    let super_class = class_file.get_super_class_index();
+
+    writeln!(
+      self.out,
+      "{}",
+      with_comment(
+        &format!("  this_class: #{}", this_class),
+        &self.cp.resolve_class_name(this_class)?
+      )
+    )?;
+    writeln!(
+      self.out,
+      "{}",
+      with_comment(
+        &format!("  super_class: #{}", super_class),
+        &self.cp.resolve_class_name(super_class)?
+      )
+    )?;
+    writeln!(
+      self.out,
+      "  interfaces: {}, fields: {}, methods: {}, attributes: {}",
+      class_file.get_interfaces().len(),
+      class_file.get_fields().len(),
+      class_file.get_methods().len(),
+      class_file.get_attributes().len()
+    )?;
+
+    Ok(())
+  }
+
+  fn constant_pool(&mut self) -> Result<()> {
+    writeln!(self.out, "Constant pool:")?;
+
+    for index in 1..=self.cp.len() as ju2 {
+      let info = self.cp.resolve_index(index)?.get_info();
+
+      let (tag, operands) = match info {
+        // second entry of a long or a double
+        CpInfoInfoEnum::None => continue,
+        CpInfoInfoEnum::Utf8(info) => ("Utf8", escape_string(&info.name())),
+        CpInfoInfoEnum::Integer(info) => ("Integer", info.int().to_string()),
+        CpInfoInfoEnum::Float(info) => ("Float", format!("{:?}f", info.float())),
+        CpInfoInfoEnum::Long(info) => ("Long", format!("{}l", info.value() as i64)),
+        CpInfoInfoEnum::Double(info) => ("Double", format!("{:?}d", f64::from_bits(info.value()))),
+        CpInfoInfoEnum::Class(info) => ("Class", format!("#{}", info.get_name_index())),
+        CpInfoInfoEnum::String(info) => ("String", format!("#{}", info.get_string_index())),
+        CpInfoInfoEnum::Fieldref(info) => (
+          "Fieldref",
+          format!(
+            "#{}.#{}",
+            info.get_class_index(),
+            info.get_name_and_type_index()
+          ),
+        ),
+        CpInfoInfoEnum::Methodref(info) => (
+          "Methodref",
+          format!(
+            "#{}.#{}",
+            info.get_class_index(),
+            info.get_name_and_type_index()
+          ),
+        ),
+        CpInfoInfoEnum::Interfaceref(info) => (
+          "InterfaceMethodref",
+          format!(
+            "#{}.#{}",
+            info.get_class_index(),
+            info.get_name_and_type_index()
+          ),
+        ),
+        CpInfoInfoEnum::NameAndType(info) => (
+          "NameAndType",
+          format!(
+            "#{}:#{}",
+            info.get_name_index(),
+            info.get_descriptor_index()
+          ),
+        ),
+        CpInfoInfoEnum::MethodHandle(info) => (
+          "MethodHandle",
+          format!(
+            "{}:#{}",
+            info.get_reference_kind(),
+            info.get_reference_index()
+          ),
+        ),
+        CpInfoInfoEnum::MethodType(info) => {
+          ("MethodType", format!("#{}", info.get_descriptor_index()))
+        }
+        CpInfoInfoEnum::Dynamic(info) => (
+          "Dynamic",
+          format!(
+            "#{}:#{}",
+            info.get_bootstrap_method_attr_index(),
+            info.get_name_and_type_index()
+          ),
+        ),
+        CpInfoInfoEnum::InvokeDynamic(info) => (
+          "InvokeDynamic",
+          format!(
+            "#{}:#{}",
+            info.get_bootstrap_method_attr_index(),
+            info.get_name_and_type_index()
+          ),
+        ),
+        CpInfoInfoEnum::Module(info) => ("Module", format!("#{}", info.get_name_index())),
+        CpInfoInfoEnum::Package(info) => ("Package", format!("#{}", info.get_name_index())),
+      };
+
+      let comment = match info {
+        CpInfoInfoEnum::Utf8(_)
+        | CpInfoInfoEnum::Integer(_)
+        | CpInfoInfoEnum::Float(_)
+        | CpInfoInfoEnum::Long(_)
+        | CpInfoInfoEnum::Double(_) => String::new(),
+        _ => self.describe_constant(index)?,
+      };
+
+      let line = format!(
+        "{:>6} = {:<18} {:<14}",
+        format!("#{}", index),
+        tag,
+        operands
+      );
+
+      writeln!(self.out, "{}", with_comment(&line, &comment))?;
+    }
+
+    Ok(())
+  }
+
+  // description of a constant, as in the comments of the constant pool
+  fn describe_constant(&self, index: ju2) -> Result<String> {
+    let description = match self.cp.resolve_index(index)?.get_info() {
+      CpInfoInfoEnum::None => String::new(),
+      CpInfoInfoEnum::Utf8(info) => escape_string(&info.name()),
+      CpInfoInfoEnum::Integer(info) => info.int().to_string(),
+      CpInfoInfoEnum::Float(info) => format!("{:?}f", info.float()),
+      CpInfoInfoEnum::Long(info) => format!("{}l", info.value() as i64),
+      CpInfoInfoEnum::Double(info) => format!("{:?}d", f64::from_bits(info.value())),
+      CpInfoInfoEnum::Class(_) => {
+        let name = self.cp.resolve_class_name(index)?;
+
+        // array classes are quoted
+        if name.starts_with('[') {
+          format!("\"{}\"", name)
+        } else {
+          name
+        }
+      }
+      CpInfoInfoEnum::String(info) => {
+        escape_string(&self.cp.resolve_name(info.get_string_index())?)
+      }
+      CpInfoInfoEnum::Fieldref(info)
+      | CpInfoInfoEnum::Methodref(info)
+      | CpInfoInfoEnum::Interfaceref(info) => format!(
+        "{}.{}",
+        self.describe_constant(info.get_class_index())?,
+        self.describe_constant(info.get_name_and_type_index())?
+      ),
+      CpInfoInfoEnum::NameAndType(_) => {
+        let (name, descriptor) = self.cp.resolve_name_and_type(index)?;
+
+        format!("{}:{}", quote_member_name(&name), descriptor)
+      }
+      CpInfoInfoEnum::MethodHandle(info) => format!(
+        "{} {}",
+        REFERENCE_KINDS
+          .get(info.get_reference_kind() as usize)
+          .unwrap_or(&"REF_???"),
+        self.describe_constant(info.get_reference_index())?
+      ),
+      CpInfoInfoEnum::MethodType(info) => self.cp.resolve_name(info.get_descriptor_index())?,
+      CpInfoInfoEnum::Dynamic(info) => format!(
+        "#{}:{}",
+        info.get_bootstrap_method_attr_index(),
+        self.describe_constant(info.get_name_and_type_index())?
+      ),
+      CpInfoInfoEnum::InvokeDynamic(info) => format!(
+        "#{}:{}",
+        info.get_bootstrap_method_attr_index(),
+        self.describe_constant(info.get_name_and_type_index())?
+      ),
+      CpInfoInfoEnum::Module(info) => self.cp.resolve_name(info.get_name_index())?,
+      CpInfoInfoEnum::Package(info) => self.cp.resolve_name(info.get_name_index())?,
+    };
+
+    Ok(description)
+  }
+
+  // description of a constant used by an instruction, prefixed by its kind
+  fn describe_operand(&self, index: ju2) -> Result<String> {
+    let kind = match self.cp.resolve_index(index)?.get_info() {
+      CpInfoInfoEnum::Utf8(_) => "Utf8",
+      CpInfoInfoEnum::Integer(_) => "int",
+      CpInfoInfoEnum::Float(_) => "float",
+      CpInfoInfoEnum::Long(_) => "long",
+      CpInfoInfoEnum::Double(_) => "double",
+      CpInfoInfoEnum::Class(_) => "class",
+      CpInfoInfoEnum::String(_) => "String",
+      CpInfoInfoEnum::Fieldref(_) => "Field",
+      CpInfoInfoEnum::Methodref(_) => "Method",
+      CpInfoInfoEnum::Interfaceref(_) => "InterfaceMethod",
+      CpInfoInfoEnum::MethodHandle(_) => "MethodHandle",
+      CpInfoInfoEnum::MethodType(_) => "MethodType",
+      CpInfoInfoEnum::Dynamic(_) => "Dynamic",
+      CpInfoInfoEnum::InvokeDynamic(_) => "InvokeDynamic",
+      _ => "",
+    };
+
+    Ok(format!("{} {}", kind, self.describe_constant(index)?))
+  }
+
+  fn field(&mut self, field: &FieldInfo) -> Result<()> {
+    let access_flags = field.get_access_flags();
+
+    writeln!(
+      self.out,
+      "  {}{} {};",
+      modifiers_to_string(access_flags, FIELD_MODIFIERS),
+      descriptor_to_java_type(field.get_descriptor()),
+      field.get_name()
+    )?;
+    writeln!(self.out, "    descriptor: {}", field.get_descriptor())?;
+    writeln!(
+      self.out,
+      "    flags: {}",
+      flags_to_string(access_flags, FIELD_FLAGS)
+    )?;
+
+    self.attributes(field.get_attributes(), "    ")
+  }
+
+  fn method(&mut self, method: &MethodInfo) -> Result<()> {
+    let access_flags = method.get_access_flags();
+    let descriptor = method.get_descriptor();
+
+    let parameters: Vec<String> = parse_parameter_types(get_parameters_type_descriptor(descriptor))
+      .iter()
+      .map(|param| descriptor_to_java_type(param))
+      .collect();
+
+    let return_type = descriptor_to_java_type(descriptor.split(')').nth(1).unwrap_or("V"));
+
+    let modifiers = modifiers_to_string(access_flags, METHOD_MODIFIERS);
+
+    let declaration = match method.get_name() {
+      "<clinit>" => "static {}".to_string(),
+      "<init>" => format!(
+        "{}{}({})",
+        modifiers,
+        class_to_dotclass(self.class_file.get_name()),
+        parameters.join(", ")
+      ),
+      name => format!(
+        "{}{} {}({})",
+        modifiers,
+        return_type,
+        name,
+        parameters.join(", ")
+      ),
+    };
+
+    writeln!(self.out, "  {};", declaration)?;
+    writeln!(self.out, "    descriptor: {}", descriptor)?;
+    writeln!(
+      self.out,
+      "    flags: {}",
+      flags_to_string(access_flags, METHOD_FLAGS)
+    )?;
+
+    for attr in method.get_attributes().iter() {
+      match attr.get_info() {
+        AttributeInfoInfoEnum::Code(code) => {
+          self.code(code, get_args_size(descriptor, method.is_static()))?
+        }
+        _ => self.attribute(attr, "    ")?,
+      }
+    }
+
+    Ok(())
+  }
+
+  fn attributes(&mut self, attributes: &Attributes, indent: &str) -> Result<()> {
+    for attr in attributes.iter() {
+      self.attribute(attr, indent)?;
+    }
+
+    Ok(())
+  }
+
+  fn attribute(&mut self, attr: &AttributeInfo, indent: &str) -> Result<()> {
+    let name = attr.get_name();
+
+    match attr.get_info() {
+      AttributeInfoInfoEnum::ConstantValue(value) => {
+        let index = value.get_constantvalue_index();
+
+        writeln!(
+          self.out,
+          "{}ConstantValue: {}",
+          indent,
+          self.describe_operand(index)?
+        )?;
+      }
+      AttributeInfoInfoEnum::SourceFile(source_file) => {
+        writeln!(
+          self.out,
+          "{}SourceFile: \"{}\"",
+          indent,
+          self.cp.resolve_name(source_file.get_sourcefile_index())?
+        )?;
+      }
+      AttributeInfoInfoEnum::Signature(signature) => {
+        let index = signature.get_signature_index();
+
+        let line = format!("{}Signature: #{}", indent, index);
+
+        writeln!(
+          self.out,
+          "{}",
+          with_comment(&line, &self.cp.resolve_name(index)?)
+        )?;
+      }
+      AttributeInfoInfoEnum::Exceptions(exceptions) => {
+        writeln!(self.out, "{}Exceptions:", indent)?;
+
+        let names = exceptions
+          .get_exception_index_table()
+          .iter()
+          .map(|index| Ok(class_to_dotclass(&self.cp.resolve_class_name(*index)?)))
+          .collect::<Result<Vec<String>>>()?;
+
+        writeln!(self.out, "{}  throws {}", indent, names.join(", "))?;
+      }
+      AttributeInfoInfoEnum::Deprecated => writeln!(self.out, "{}Deprecated: true", indent)?,
+      AttributeInfoInfoEnum::NestHost(nest_host) => {
+        writeln!(
+          self.out,
+          "{}NestHost: class {}",
+          indent,
+          self
+            .cp
+            .resolve_class_name(nest_host.get_host_class_index())?
+        )?;
+      }
+      AttributeInfoInfoEnum::NestMembers(nest_members) => {
+        self.class_list(indent, name, nest_members.get_classes())?;
+      }
+      AttributeInfoInfoEnum::PermittedSubclasses(subclasses) => {
+        self.class_list(indent, name, subclasses.get_classes())?;
+      }
+      AttributeInfoInfoEnum::InnerClasses(inner_classes) => {
+        writeln!(self.out, "{}InnerClasses:", indent)?;
+
+        for class in inner_classes.get_inner_classes() {
+          self.inner_class(class, indent)?;
+        }
+      }
+      AttributeInfoInfoEnum::EnclosingMethod(method) => {
+        let class_index = method.get_class_index();
+        let method_index = method.get_method_index();
+
+        let mut comment = self.cp.resolve_class_name(class_index)?;
+
+        if method_index != 0 {
+          comment += &format!(".{}", self.cp.resolve_name_and_type(method_index)?.0);
+        }
+
+        let line = format!(
+          "{}EnclosingMethod: #{}.#{}",
+          indent, class_index, method_index
+        );
+
+        writeln!(self.out, "{}", with_comment(&line, &comment))?;
+      }
+      AttributeInfoInfoEnum::RuntimeVisibleAnnotations(annotations) => {
+        self.annotations(indent, name, annotations.get_annotations())?;
+      }
+      AttributeInfoInfoEnum::RuntimeInvisibleAnnotations(annotations) => {
+        self.annotations(indent, name, annotations.get_annotations())?;
+      }
+      AttributeInfoInfoEnum::RuntimeVisibleParameterAnnotations(annotations)
+      | AttributeInfoInfoEnum::RuntimeInvisibleParameterAnnotations(annotations) => {
+        writeln!(self.out, "{}{}:", indent, name)?;
+
+        let parameter_indent = format!("{}  ", indent);
+
+        for (i, annotations) in annotations.get_parameter_annotations().iter().enumerate() {
+          self.annotations(&parameter_indent, &format!("parameter {}", i), annotations)?;
+        }
+      }
+      AttributeInfoInfoEnum::RuntimeVisibleTypeAnnotations(annotations)
+      | AttributeInfoInfoEnum::RuntimeInvisibleTypeAnnotations(annotations) => {
+        writeln!(self.out, "{}{}:", indent, name)?;
+
+        for (i, annotation) in annotations.get_annotations().iter().enumerate() {
+          writeln!(
+            self.out,
+            "{}  {}: {}: {}",
+            indent,
+            i,
+            Self::annotation_indices(annotation.get_annotation()),
+            Self::type_annotation_target(annotation)
+          )?;
+          writeln!(
+            self.out,
+            "{}    {}",
+            indent,
+            self.annotation(annotation.get_annotation())?
+          )?;
+        }
+      }
+      AttributeInfoInfoEnum::AnnotationDefault(default) => {
+        let value = default.get_default_value();
+
+        writeln!(self.out, "{}AnnotationDefault:", indent)?;
+        writeln!(
+          self.out,
+          "{}  default_value: {}",
+          indent,
+          Self::element_value_indices(value)
+        )?;
+        writeln!(self.out, "{}    {}", indent, self.element_value(value)?)?;
+      }
+      AttributeInfoInfoEnum::BootstrapMethods(methods) => {
+        self.bootstrap_methods(indent, methods)?;
+      }
+      _ => writeln!(
+        self.out,
+        "{}{}: length = 0x{:X}",
+        indent,
+        name,
+        attr.get_attribute_length()
+      )?,
+    }
+
+    Ok(())
+  }
+
+  fn class_list(&mut self, indent: &str, name: &str, classes: &[ju2]) -> Result<()> {
+    writeln!(self.out, "{}{}:", indent, name)?;
+
+    for class in classes {
+      writeln!(
+        self.out,
+        "{}  {}",
+        indent,
+        self.cp.resolve_class_name(*class)?
+      )?;
+    }
+
+    Ok(())
+  }
+
+  fn inner_class(&mut self, class: &InnerClassesClass, indent: &str) -> Result<()> {
+    let inner_index = class.get_inner_class_info_index();
+    let outer_index = class.get_outer_class_info_index();
+    let name_index = class.get_inner_name_index();
+
+    let modifiers = modifiers_to_string(class.get_inner_class_access_flags(), FIELD_MODIFIERS);
+
+    let mut line = format!("{}  {}", indent, modifiers);
+    let mut comment = String::new();
+
+    if name_index != 0 {
+      line += &format!("#{}= ", name_index);
+      comment += &format!("{}=", self.cp.resolve_name(name_index)?);
+    }
+
+    line += &format!("#{}", inner_index);
+    comment += &format!("class {}", self.cp.resolve_class_name(inner_index)?);
+
+    if outer_index != 0 {
+      line += &format!(" of #{}", outer_index);
+      comment += &format!(" of class {}", self.cp.resolve_class_name(outer_index)?);
+    }
+
+    writeln!(
+      self.out,
+      "{}",
+      with_comment(&format!("{};", line), &comment)
+    )?;
+
+    Ok(())
+  }
+
+  fn annotations(&mut self, indent: &str, name: &str, annotations: &[Annotation]) -> Result<()> {
+    writeln!(self.out, "{}{}:", indent, name)?;
+
+    for (i, annotation) in annotations.iter().enumerate() {
+      writeln!(
+        self.out,
+        "{}  {}: {}",
+        indent,
+        i,
+        Self::annotation_indices(annotation)
+      )?;
+      writeln!(self.out, "{}    {}", indent, self.annotation(annotation)?)?;
+    }
+
+    Ok(())
+  }
+
+  // annotation with its constant pool indices (e.g. #16(#17=s#18))
+  fn annotation_indices(annotation: &Annotation) -> String {
+    let pairs: Vec<String> = annotation
+      .get_element_value_pairs()
+      .iter()
+      .map(|pair| {
+        format!(
+          "#{}={}",
+          pair.get_element_name_index(),
+          Self::element_value_indices(pair.get_value())
+        )
+      })
+      .collect();
+
+    format!("#{}({})", annotation.get_type_index(), pairs.join(","))
+  }
+
+  // target of a type annotation (e.g. METHOD_FORMAL_PARAMETER, param_index=0)
+  fn type_annotation_target(annotation: &TypeAnnotation) -> String {
+    let target_type = annotation.get_target_type() as usize;
+
+    let mut target = TARGET_TYPES
+      .iter()
+      .find(|(value, _)| *value == target_type)
+      .map_or("UNKNOWN", |(_, name)| name)
+      .to_string();
+
+    target += &match annotation.get_target_info() {
+      TargetInfo::TypeParameter {
+        type_parameter_index,
+      } => format!(", param_index={}", type_parameter_index),
+      TargetInfo::Supertype { supertype_index } => {
+        format!(", type_index={}", *supertype_index as i16)
+      }
+      TargetInfo::TypeParameterBound {
+        type_parameter_index,
+        bound_index,
+      } => format!(
+        ", param_index={}, bound_index={}",
+        type_parameter_index, bound_index
+      ),
+      TargetInfo::Empty => String::new(),
+      TargetInfo::FormalParameter {
+        formal_parameter_index,
+      } => format!(", param_index={}", formal_parameter_index),
+      TargetInfo::Throws { throws_type_index } => format!(", type_index={}", throws_type_index),
+      TargetInfo::Localvar { table } => {
+        let ranges: Vec<String> = table
+          .iter()
+          .map(|(start_pc, length, index)| {
+            format!("start_pc={}, length={}, index={}", start_pc, length, index)
+          })
+          .collect();
+
+        format!(", {{{}}}", ranges.join("; "))
+      }
+      TargetInfo::Catch {
+        exception_table_index,
+      } => format!(", exception_index={}", exception_table_index),
+      TargetInfo::Offset { offset } => format!(", offset={}", offset),
+      TargetInfo::TypeArgument {
+        offset,
+        type_argument_index,
+      } => format!(", offset={}, type_index={}", offset, type_argument_index),
+    };
+
+    let path = annotation.get_type_path();
+
+    if !path.is_empty() {
+      let location: Vec<String> = path
+        .iter()
+        .map(|(kind, index)| match kind {
+          0 => "ARRAY".to_string(),
+          1 => "INNER_TYPE".to_string(),
+          2 => "WILDCARD".to_string(),
+          _ => format!("TYPE_ARGUMENT({})", index),
+        })
+        .collect();
+
+      target += &format!(", location=[{}]", location.join(", "));
+    }
+
+    target
+  }
+
+  fn element_value_indices(value: &ElementValue) -> String {
+    let tag = value.get_tag() as char;
+
+    match value.get_value() {
+      ElementValueEnum::ConstValue { const_value_index } => {
+        format!("{}#{}", tag, const_value_index)
+      }
+      ElementValueEnum::EnumConstValue {
+        type_name_index,
+        const_name_index,
+      } => format!("{}#{}.#{}", tag, type_name_index, const_name_index),
+      ElementValueEnum::ClassInfo { class_info_index } => format!("{}#{}", tag, class_info_index),
+      ElementValueEnum::AnnotationValue(annotation) => {
+        format!("{}{}", tag, Self::annotation_indices(annotation))
+      }
+      ElementValueEnum::ArrayValue(values) => {
+        let values: Vec<String> = values.iter().map(Self::element_value_indices).collect();
+
+        format!("{}[{}]", tag, values.join(","))
+      }
+      ElementValueEnum::NotRecognized => format!("{}?", tag),
+    }
+  }
+
+  // annotation with its resolved values (e.g. java.lang.Deprecated(since="9"))
+  fn annotation(&self, annotation: &Annotation) -> Result<String> {
+    let pairs = annotation
+      .get_element_value_pairs()
+      .iter()
+      .map(|pair| {
+        Ok(format!(
+          "{}={}",
+          self.cp.resolve_name(pair.get_element_name_index())?,
+          self.element_value(pair.get_value())?
+        ))
+      })
+      .collect::<Result<Vec<String>>>()?;
+
+    let annotation_type = self.cp.resolve_name(annotation.get_type_index())?;
+
+    Ok(format!(
+      "{}({})",
+      descriptor_to_java_type(&annotation_type),
+      pairs.join(",")
+    ))
+  }
+
+  fn element_value(&self, value: &ElementValue) -> Result<String> {
+    let value = match value.get_value() {
+      ElementValueEnum::ConstValue { const_value_index } => {
+        let info = self.cp.resolve_index(*const_value_index)?.get_info();
+
+        match (value.get_tag(), info) {
+          (b'Z', CpInfoInfoEnum::Integer(info)) => (info.int() != 0).to_string(),
+          (b'C', CpInfoInfoEnum::Integer(info)) => {
+            format!("'{}'", char::from_u32(info.int() as u32).unwrap_or('?'))
+          }
+          (b's', _) => format!("\"{}\"", self.describe_constant(*const_value_index)?),
+          _ => self.describe_constant(*const_value_index)?,
+        }
+      }
+      ElementValueEnum::EnumConstValue {
+        type_name_index,
+        const_name_index,
+      } => format!(
+        "{}.{}",
+        descriptor_to_java_type(&self.cp.resolve_name(*type_name_index)?),
+        self.cp.resolve_name(*const_name_index)?
+      ),
+      ElementValueEnum::ClassInfo { class_info_index } => format!(
+        "{}.class",
+        descriptor_to_java_type(&self.cp.resolve_name(*class_info_index)?)
+      ),
+      ElementValueEnum::AnnotationValue(annotation) => format!("@{}", self.annotation(annotation)?),
+      ElementValueEnum::ArrayValue(values) => {
+        let values = values
+          .iter()
+          .map(|value| self.element_value(value))
+          .collect::<Result<Vec<String>>>()?;
+
+        format!("[{}]", values.join(","))
+      }
+      ElementValueEnum::NotRecognized => "?".to_string(),
+    };
+
+    Ok(value)
+  }
+
+  fn bootstrap_methods(&mut self, indent: &str, methods: &BootstrapMethods) -> Result<()> {
+    writeln!(self.out, "{}BootstrapMethods:", indent)?;
+
+    for (i, method) in methods.get_methods().iter().enumerate() {
+      writeln!(
+        self.out,
+        "{}  {}: #{} {}",
+        indent,
+        i,
+        method.get_method_ref(),
+        self.describe_constant(method.get_method_ref())?
+      )?;
+      writeln!(self.out, "{}    Method arguments:", indent)?;
+
+      for argument in method.get_arguments() {
+        writeln!(
+          self.out,
+          "{}      #{} {}",
+          indent,
+          argument,
+          self.describe_constant(*argument)?
+        )?;
+      }
+    }
+
+    Ok(())
+  }
+
+  fn code(&mut self, code: &Code, args_size: usize) -> Result<()> {
+    writeln!(self.out, "    Code:")?;
+    writeln!(
+      self.out,
+      "      stack={}, locals={}, args_size={}",
+      code.get_max_stack(),
+      code.get_max_locals(),
+      args_size
+    )?;
+
+    let bytes = code.get_code_vec();
+
+    let mut pc = 0;
+
+    while pc < bytes.len() {
+      let (line, length) = self.instruction(bytes, pc)?;
+
+      writeln!(self.out, "{}", line)?;
+
+      pc += length;
+    }
+
+    let exception_table = code.get_exception_table();
+
+    if !exception_table.is_empty() {
+      writeln!(self.out, "      Exception table:")?;
+      writeln!(self.out, "         from    to  target type")?;
+
+      for entry in exception_table {
+        let catch_type = match entry.get_catch_type_index() {
+          0 => "any".to_string(),
+          index => format!("Class {}", self.cp.resolve_class_name(index)?),
+        };
+
+        writeln!(
+          self.out,
+          "        {:>5} {:>5} {:>5}   {}",
+          entry.get_start_pc(),
+          entry.get_end_pc(),
+          entry.get_handler_pc(),
+          catch_type
+        )?;
+      }
+    }
+
+    for attr in code.get_attributes().iter() {
+      match attr.get_info() {
+        AttributeInfoInfoEnum::LineNumberTable(table) => {
+          writeln!(self.out, "      LineNumberTable:")?;
+
+          for entry in table.get_entries() {
+            writeln!(
+              self.out,
+              "        line {}: {}",
+              entry.get_line_number(),
+              entry.get_start_pc()
+            )?;
+          }
+        }
+        // also used for LocalVariableTypeTable, with signatures instead of descriptors
+        AttributeInfoInfoEnum::LocalVariableTable(table) => {
+          writeln!(self.out, "      {}:", attr.get_name())?;
+          writeln!(self.out, "        Start  Length  Slot  Name   Signature")?;
+
+          for entry in table.get_entries() {
+            writeln!(
+              self.out,
+              "        {:>5}  {:>6}  {:>4}  {:>5}   {}",
+              entry.get_start_pc(),
+              entry.get_length(),
+              entry.get_index(),
+              self.cp.resolve_name(entry.get_name_index())?,
+              self.cp.resolve_name(entry.get_descriptor_index())?
+            )?;
+          }
+        }
+        AttributeInfoInfoEnum::StackMapTable(table) => self.stack_map_table(table)?,
+        _ => self.attribute(attr, "      ")?,
+      }
+    }
+
+    Ok(())
+  }
+
+  // an instruction and its length
+  fn instruction(&self, bytes: &[u8], pc: usize) -> Result<(String, usize)> {
+    let opcode = OpCode::try_from_byte(bytes[pc])
+      .ok_or_else(|| eyre!("[!] Invalid opcode {} at {}", bytes[pc], pc))?;
+
+    let u1 = |at: usize| ju1_at(bytes, pc + at);
+    let u2 = |at: usize| ju2_at(bytes, pc + at);
+    let i4 = |at: usize| ju4_at(bytes, pc + at).map(|value| value as i32);
+    let target = |offset: i64| pc as i64 + offset;
+
+    let mut comment = String::new();
+
+    let (operands, length) = match opcode {
+      OpCode::BIPUSH => ((u1(1)? as i8).to_string(), 2),
+      OpCode::SIPUSH => ((u2(1)? as i16).to_string(), 3),
+      OpCode::LDC => {
+        let index = u1(1)? as ju2;
+
+        comment = self.describe_operand(index)?;
+
+        (format!("#{}", index), 2)
+      }
+      OpCode::ILOAD
+      | OpCode::LLOAD
+      | OpCode::FLOAD
+      | OpCode::DLOAD
+      | OpCode::ALOAD
+      | OpCode::ISTORE
+      | OpCode::LSTORE
+      | OpCode::FSTORE
+      | OpCode::DSTORE
+      | OpCode::ASTORE
+      | OpCode::RET => (u1(1)?.to_string(), 2),
+      OpCode::IINC => (format!("{}, {}", u1(1)?, u1(2)? as i8), 3),
+      OpCode::IFEQ
+      | OpCode::IFNE
+      | OpCode::IFLT
+      | OpCode::IFGE
+      | OpCode::IFGT
+      | OpCode::IFLE
+      | OpCode::IFICMPEQ
+      | OpCode::IFICMPNE
+      | OpCode::IFICMPLT
+      | OpCode::IFICMPGE
+      | OpCode::IFICMPGT
+      | OpCode::IFICMPLE
+      | OpCode::IFACMPEQ
+      | OpCode::IFACMPNE
+      | OpCode::GOTO
+      | OpCode::JSR
+      | OpCode::IFNULL
+      | OpCode::IFNONNULL => (target(u2(1)? as i16 as i64).to_string(), 3),
+      OpCode::GOTOW | OpCode::JSRW => (target(i4(1)? as i64).to_string(), 5),
+      OpCode::LDCW
+      | OpCode::LDC2W
+      | OpCode::GETSTATIC
+      | OpCode::PUTSTATIC
+      | OpCode::GETFIELD
+      | OpCode::PUTFIELD
+      | OpCode::INVOKEVIRTUAL
+      | OpCode::INVOKESPECIAL
+      | OpCode::INVOKESTATIC
+      | OpCode::NEW
+      | OpCode::ANEWARRAY
+      | OpCode::CHECKCAST
+      | OpCode::INSTANCEOF => {
+        let index = u2(1)?;
+
+        comment = self.describe_operand(index)?;
+
+        (format!("#{}", index), 3)
+      }
+      OpCode::INVOKEINTERFACE => {
+        let index = u2(1)?;
+
+        comment = self.describe_operand(index)?;
+
+        (format!("#{},  {}", index, u1(3)?), 5)
+      }
+      OpCode::INVOKEDYNAMIC => {
+        let index = u2(1)?;
+
+        comment = self.describe_operand(index)?;
+
+        (format!("#{},  0", index), 5)
+      }
+      OpCode::MULTIANEWARRAY => {
+        let index = u2(1)?;
+
+        comment = self.describe_operand(index)?;
+
+        (format!("#{},  {}", index, u1(3)?), 4)
+      }
+      OpCode::NEWARRAY => {
+        let atype = match u1(1)? {
+          4 => "boolean",
+          5 => "char",
+          6 => "float",
+          7 => "double",
+          8 => "byte",
+          9 => "short",
+          10 => "int",
+          11 => "long",
+          _ => "?",
+        };
+
+        (atype.to_string(), 2)
+      }
+      OpCode::WIDE => {
+        let modified = OpCode::try_from_byte(u1(1)?)
+          .ok_or_else(|| eyre!("[!] Invalid opcode after wide at {}", pc))?;
+
+        match modified {
+          OpCode::IINC => (format!("iinc {}, {}", u2(2)?, u2(4)? as i16), 6),
+          _ => (format!("{} {}", modified.mnemonic(), u2(2)?), 4),
+        }
+      }
+      OpCode::TABLESWITCH => {
+        // the operands are aligned on 4 bytes from the start of the code
+        let base = (pc + 4) & !3;
+
+        let default = i4(base - pc)?;
+        let low = i4(base - pc + 4)?;
+        let high = i4(base - pc + 8)?;
+
+        if low > high {
+          return Err(eyre!("[!] Invalid tableswitch at {}", pc));
+        }
+
+        let mut operands = format!("{{ // {} to {}\n", low, high);
+
+        for (i, value) in (low..=high).enumerate() {
+          let offset = i4(base - pc + 12 + i * 4)?;
+
+          operands += &format!("{:>24}: {}\n", value, target(offset as i64));
+        }
+
+        operands += &format!("{:>24}: {}\n{:>13}", "default", target(default as i64), "}");
+
+        let count = (high as i64 - low as i64 + 1) as usize;
+
+        (operands, base - pc + 12 + count * 4)
+      }
+      OpCode::LOOKUPSWITCH => {
+        let base = (pc + 4) & !3;
+
+        let default = i4(base - pc)?;
+        let npairs = i4(base - pc + 4)?;
+
+        if npairs < 0 {
+          return Err(eyre!("[!] Invalid lookupswitch at {}", pc));
+        }
+
+        let mut operands = format!("{{ // {}\n", npairs);
+
+        for i in 0..npairs as usize {
+          let value = i4(base - pc + 8 + i * 8)?;
+          let offset = i4(base - pc + 12 + i * 8)?;
+
+          operands += &format!("{:>24}: {}\n", value, target(offset as i64));
+        }
+
+        operands += &format!("{:>24}: {}\n{:>13}", "default", target(default as i64), "}");
+
+        (operands, base - pc + 8 + npairs as usize * 8)
+      }
+      _ => (String::new(), 1),
+    };
+
+    let line = format!("{:>10}: {:<13} {:<18}", pc, opcode.mnemonic(), operands);
+
+    Ok((with_comment(&line, &comment), length))
+  }
+
+  fn stack_map_table(&mut self, table: &StackMapTable) -> Result<()> {
+    let entries = table.get_entries();
+
+    writeln!(
+      self.out,
+      "      StackMapTable: number_of_entries = {}",
+      entries.len()
+    )?;
+
+    for frame in entries {
+      let frame_type = frame.get_frame_type();
+
+      let kind = match frame_type {
+        0..=63 => "same",
+        64..=127 => "same_locals_1_stack_item",
+        247 => "same_locals_1_stack_item_frame_extended",
+        248..=250 => "chop",
+        251 => "same_frame_extended",
+        252..=254 => "append",
+        255 => "full_frame",
+        _ => "reserved",
+      };
+
+      writeln!(
+        self.out,
+        "        frame_type = {} /* {} */",
+        frame_type, kind
+      )?;
+
+      match frame.get_frame_enum() {
+        StackMapFrameEnum::SameFrame => {}
+        StackMapFrameEnum::SameLocals1StackItemFrame { stack } => {
+          writeln!(
+            self.out,
+            "          stack = {}",
+            self.verification_types(stack)?
+          )?;
+        }
+        StackMapFrameEnum::SameLocals1StackItemFrameExtended {
+          offset_delta,
+          stack,
+        } => {
+          writeln!(self.out, "          offset_delta = {}", offset_delta)?;
+          writeln!(
+            self.out,
+            "          stack = {}",
+            self.verification_types(stack)?
+          )?;
+        }
+        StackMapFrameEnum::ChopFrame { offset_delta }
+        | StackMapFrameEnum::SameFrameExtended { offset_delta } => {
+          writeln!(self.out, "          offset_delta = {}", offset_delta)?;
+        }
+        StackMapFrameEnum::AppendFrame {
+          offset_delta,
+          locals,
+        } => {
+          writeln!(self.out, "          offset_delta = {}", offset_delta)?;
+          writeln!(
+            self.out,
+            "          locals = {}",
+            self.verification_types(locals)?
+          )?;
+        }
+        StackMapFrameEnum::FullFrame {
+          offset_delta,
+          locals,
+          stack,
+          ..
+        } => {
+          writeln!(self.out, "          offset_delta = {}", offset_delta)?;
+          writeln!(
+            self.out,
+            "          locals = {}",
+            self.verification_types(locals)?
+          )?;
+          writeln!(
+            self.out,
+            "          stack = {}",
+            self.verification_types(stack)?
+          )?;
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  fn verification_types(&self, types: &[VerificationTypeInfo]) -> Result<String> {
+    if types.is_empty() {
+      return Ok("[]".to_string());
+    }
+
+    let types = types
+      .iter()
+      .map(|info| {
+        Ok(match info.get_info() {
+          VerificationTypeInfoEnum::Top => "top".to_string(),
+          VerificationTypeInfoEnum::Integer => "int".to_string(),
+          VerificationTypeInfoEnum::Float => "float".to_string(),
+          VerificationTypeInfoEnum::Long => "long".to_string(),
+          VerificationTypeInfoEnum::Double => "double".to_string(),
+          VerificationTypeInfoEnum::Null => "null".to_string(),
+          VerificationTypeInfoEnum::UninitializedThis => "uninitialized_this".to_string(),
+          VerificationTypeInfoEnum::Object { cpool_index } => {
+            format!("class {}", self.describe_constant(*cpool_index)?)
+          }
+          VerificationTypeInfoEnum::Uninitialized { offset } => {
+            format!("uninitialized {}", offset)
+          }
+        })
+      })
+      .collect::<Result<Vec<String>>>()?;
+
+    Ok(format!("[ {} ]", types.join(", ")))
+  }
+}
+
+impl ClassFile {
+  /// Disassemble the class like `javap -v -p`: the constant pool, the members,
+  /// the code with its resolved operands and the attributes
+  pub(crate) fn disassemble(&self) -> Result<String> {
+    let disassembler = Disassembler {
+      class_file: self,
+      cp: self.get_constant_pool(),
+      out: String::new(),
+    };
+
+    disassembler.disassemble()
+  }
+}
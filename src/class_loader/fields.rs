@@ -12,6 +12,8 @@ use crate::{
 #[allow(dead_code)]
 pub struct FieldInfo {
   access_flags: ju2,
+  name_index: ju2,
+  descriptor_index: ju2,
   name: String,
   descriptor: String,
   attributes: Attributes,
@@ -27,29 +29,31 @@ impl FieldInfo {
   fn parse(bytes: &[u8], cp: &ConstantPool) -> Result<(FieldInfo, usize)> {
     let mut index: usize = 0;
 
-    let access_flags: ju2 = ju2_from_bytes(get_slice_arr(bytes, index, 2))?;
+    let access_flags: ju2 = ju2_at(bytes, index)?;
 
     index += 2;
 
-    let name_index: ju2 = ju2_from_bytes(get_slice_arr(bytes, index, 2))?;
+    let name_index: ju2 = ju2_at(bytes, index)?;
 
     index += 2;
 
     let name = cp.resolve_name(name_index)?;
 
-    let descriptor_index: ju2 = ju2_from_bytes(get_slice_arr(bytes, index, 2))?;
+    let descriptor_index: ju2 = ju2_at(bytes, index)?;
 
     let descriptor = cp.resolve_name(descriptor_index)?;
 
     index += 2;
 
-    let (attributes, attributes_size) = Attributes::parse(&bytes[index..], cp)?;
+    let (attributes, attributes_size) = Attributes::parse(get_tail(bytes, index)?, cp)?;
 
     index += attributes_size;
 
     Ok((
       FieldInfo {
         access_flags,
+        name_index,
+        descriptor_index,
         name,
         descriptor,
         attributes,
@@ -58,9 +62,30 @@ impl FieldInfo {
     ))
   }
 
+  /// New field, with its name and descriptor added to the constant pool `cp`
+  pub(crate) fn define(
+    cp: &mut ConstantPool,
+    access_flags: ju2,
+    name: &str,
+    descriptor: &str,
+    attributes: Attributes,
+  ) -> FieldInfo {
+    FieldInfo {
+      access_flags,
+      name_index: cp.add_utf8(name),
+      descriptor_index: cp.add_utf8(descriptor),
+      name: name.to_string(),
+      descriptor: descriptor.to_string(),
+      attributes,
+    }
+  }
+
+  // a field added by the VM, not part of the class file
   pub(crate) fn new(access_flags: ju2, name: &str, descriptor: &str) -> FieldInfo {
     FieldInfo {
       access_flags,
+      name_index: 0,
+      descriptor_index: 0,
       name: name.to_string(),
       descriptor: descriptor.to_string(),
       attributes: Attributes::new(vec![]),
@@ -79,7 +104,7 @@ impl FieldInfo {
     self.access_flags
   }
 
-  pub fn _get_attributes(&self) -> &Attributes {
+  pub fn get_attributes(&self) -> &Attributes {
     &self.attributes
   }
 
@@ -92,6 +117,16 @@ impl FieldInfo {
   }
 }
 
+impl WriteInfo for FieldInfo {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend(self.access_flags.to_be_bytes());
+    bytes.extend(self.name_index.to_be_bytes());
+    bytes.extend(self.descriptor_index.to_be_bytes());
+
+    self.attributes.write(bytes);
+  }
+}
+
 #[derive(Debug)]
 pub struct Fields {
   fields_count: ju2,
@@ -106,13 +141,13 @@ impl Fields {
 
     let mut index: usize = 0;
 
-    let fields_count: ju2 = u16::from_be_bytes(bytes[0..2].try_into().unwrap());
+    let fields_count: ju2 = ju2_at(bytes, 0)?;
 
     debug!("    Found {:?} fields", fields_count);
     index += 2;
 
     for _ in 0..fields_count {
-      let slice = &bytes[index..];
+      let slice = get_tail(bytes, index)?;
 
       let (field, size) = FieldInfo::parse(slice, cp)?;
 
@@ -154,3 +189,16 @@ impl Fields {
     &self.fields[index]
   }
 }
+
+impl WriteInfo for Fields {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    // the fields added by the VM are not part of the class file
+    let fields: Vec<&FieldInfo> = self.fields.iter().filter(|f| f.name_index != 0).collect();
+
+    bytes.extend((fields.len() as ju2).to_be_bytes());
+
+    for field in fields {
+      field.write(bytes);
+    }
+  }
+}
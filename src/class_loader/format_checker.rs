@@ -0,0 +1,317 @@
+use std::collections::HashSet;
+
+use color_eyre::eyre::{eyre, Report, Result};
+
+use super::{attributes::AttributeInfoInfoEnum, class_file::ClassFile, methods::MethodInfo};
+use crate::{runtime::errors, utils::*};
+
+const MAGIC: ju4 = 0xCAFEBABE;
+
+/// Oldest supported class file version (JDK 1.1)
+const MIN_MAJOR_VERSION: ju2 = 45;
+
+/// Newest supported class file version (Java SE 23, the version of the JDK modules)
+pub(crate) const MAX_MAJOR_VERSION: ju2 = 67;
+
+/// First class file version where the minor version must be 0 (Java SE 12)
+const STRICT_MINOR_VERSION: ju2 = 56;
+
+/// Maximum number of parameter slots of a method descriptor (JVMS 4.3.3)
+const MAX_PARAMETER_SLOTS: usize = 255;
+
+pub(crate) fn format_error(msg: String) -> Report {
+  eyre!(errors::JavaException::ClassFormatError(msg))
+}
+
+/// Report any error of the parser that is not a Java exception as a `ClassFormatError`
+pub(crate) fn as_format_error(err: Report) -> Report {
+  if err.downcast_ref::<errors::JavaException>().is_some() {
+    err
+  } else {
+    format_error(err.to_string())
+  }
+}
+
+/// Check the magic number and the version of a class file (JVMS 4.1)
+pub(crate) fn check_header(magic: ju4, minor_version: ju2, major_version: ju2) -> Result<()> {
+  if magic != MAGIC {
+    return Err(format_error(format!(
+      "Incompatible magic value {} in class file",
+      magic
+    )));
+  }
+
+  let supported = match major_version {
+    MIN_MAJOR_VERSION..STRICT_MINOR_VERSION => true,
+    STRICT_MINOR_VERSION..=MAX_MAJOR_VERSION => minor_version == 0,
+    _ => false,
+  };
+
+  if !supported {
+    return Err(eyre!(errors::JavaException::UnsupportedClassVersionError(
+      format!(
+        "class file version {}.{} is not supported, this version of the Java Runtime only recognizes class file versions up to {}.0",
+        major_version, minor_version, MAX_MAJOR_VERSION
+      )
+    )));
+  }
+
+  Ok(())
+}
+
+// https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.2.2
+pub(crate) fn is_unqualified_name(name: &str) -> bool {
+  !name.is_empty() && !name.contains(['.', ';', '[', '/'])
+}
+
+pub(crate) fn is_method_name(name: &str) -> bool {
+  name == "<init>"
+    || name == "<clinit>"
+    || (is_unqualified_name(name) && !name.contains(['<', '>']))
+}
+
+// https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.2.1
+//
+// Binary name in internal form (e.g. `java/lang/Object`)
+pub(crate) fn is_binary_name(name: &str) -> bool {
+  name.split('/').all(is_unqualified_name)
+}
+
+/// Name of a `CONSTANT_Class_info`: a binary name or an array descriptor
+pub(crate) fn is_class_name(name: &str) -> bool {
+  if name.starts_with('[') {
+    is_field_descriptor(name)
+  } else {
+    is_binary_name(name)
+  }
+}
+
+// length of the field descriptor at the start of `desc`
+fn field_descriptor_len(desc: &str) -> Option<usize> {
+  let dimensions = desc.bytes().take_while(|b| *b == b'[').count();
+
+  // an array type can not have more than 255 dimensions (JVMS 4.3.2)
+  if dimensions > 255 {
+    return None;
+  }
+
+  match desc.as_bytes().get(dimensions)? {
+    b'B' | b'C' | b'D' | b'F' | b'I' | b'J' | b'S' | b'Z' => Some(dimensions + 1),
+    b'L' => {
+      let end = dimensions + desc[dimensions..].find(';')?;
+
+      is_binary_name(&desc[dimensions + 1..end]).then_some(end + 1)
+    }
+    _ => None,
+  }
+}
+
+// https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.3.2
+pub(crate) fn is_field_descriptor(desc: &str) -> bool {
+  field_descriptor_len(desc) == Some(desc.len())
+}
+
+// https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.3.3
+pub(crate) fn is_method_descriptor(desc: &str) -> bool {
+  let Some(mut rest) = desc.strip_prefix('(') else {
+    return false;
+  };
+
+  let mut slots = 0;
+
+  while !rest.starts_with(')') {
+    let Some(len) = field_descriptor_len(rest) else {
+      return false;
+    };
+
+    slots += if rest.starts_with(['J', 'D']) { 2 } else { 1 };
+
+    rest = &rest[len..];
+  }
+
+  let ret = &rest[1..];
+
+  slots <= MAX_PARAMETER_SLOTS && (ret == "V" || is_field_descriptor(ret))
+}
+
+/// A string of the constant pool in modified UTF-8 (JVMS 4.4.7)
+pub(crate) fn is_modified_utf8(bytes: &[u8]) -> bool {
+  !bytes.iter().any(|b| *b == 0 || *b >= 0xf0)
+}
+
+// at most one of ACC_PUBLIC, ACC_PRIVATE and ACC_PROTECTED
+fn has_one_visibility(access_flags: ju2) -> bool {
+  (access_flags & 0x0007).count_ones() <= 1
+}
+
+impl ClassFile {
+  /// Check the format of the class, fields and methods (JVMS 4.8)
+  ///
+  /// The constant pool is checked by `ConstantPool::check_format` while parsing.
+  pub(crate) fn check_format(&self) -> Result<()> {
+    let access_flags = self.get_access_flags();
+
+    // ACC_INTERFACE
+    if access_flags & 0x0200 != 0 {
+      // must be ACC_ABSTRACT, and neither ACC_FINAL nor ACC_ENUM
+      if access_flags & 0x0400 == 0 || access_flags & (0x0010 | 0x4000) != 0 {
+        return Err(format_error(format!(
+          "Illegal class modifiers in class {}: 0x{:X}",
+          self.get_name(),
+          access_flags
+        )));
+      }
+    } else if access_flags & 0x0010 != 0 && access_flags & 0x0400 != 0 {
+      return Err(format_error(format!(
+        "Illegal class modifiers in class {}: 0x{:X}",
+        self.get_name(),
+        access_flags
+      )));
+    }
+
+    if self.get_name().is_empty() || self.get_name().starts_with('[') {
+      return Err(format_error(
+        "Invalid this class index in class file".to_string(),
+      ));
+    }
+
+    // only java/lang/Object and module descriptors (ACC_MODULE) have no super class
+    if !self.has_parent() && self.get_name() != "java/lang/Object" && access_flags & 0x8000 == 0 {
+      return Err(format_error(format!(
+        "Invalid superclass index 0 in class file {}",
+        self.get_name()
+      )));
+    }
+
+    if self.get_parent_name().starts_with('[') {
+      return Err(format_error(format!(
+        "Invalid superclass {} in class file {}",
+        self.get_parent_name(),
+        self.get_name()
+      )));
+    }
+
+    for interface in self.get_interfaces() {
+      if interface.is_empty() || interface.starts_with('[') {
+        return Err(format_error(format!(
+          "Invalid interface \"{}\" in class file {}",
+          interface,
+          self.get_name()
+        )));
+      }
+    }
+
+    let mut fields = HashSet::new();
+
+    for field in self.get_fields() {
+      if !is_unqualified_name(field.get_name()) || !is_field_descriptor(field.get_descriptor()) {
+        return Err(format_error(format!(
+          "Illegal field name \"{}\" or signature \"{}\" in class file {}",
+          field.get_name(),
+          field.get_descriptor(),
+          self.get_name()
+        )));
+      }
+
+      if !has_one_visibility(field.get_access_flags()) {
+        return Err(format_error(format!(
+          "Illegal field modifiers in class {}: 0x{:X}",
+          self.get_name(),
+          field.get_access_flags()
+        )));
+      }
+
+      if !fields.insert((field.get_name(), field.get_descriptor())) {
+        return Err(format_error(format!(
+          "Duplicate field name \"{}\" with signature \"{}\" in class file {}",
+          field.get_name(),
+          field.get_descriptor(),
+          self.get_name()
+        )));
+      }
+    }
+
+    let mut methods = HashSet::new();
+
+    for method in self.get_methods() {
+      let name = method.get_name();
+      let descriptor = method.get_descriptor();
+
+      let valid = is_method_name(name)
+        && is_method_descriptor(descriptor)
+        && (name != "<init>" || descriptor.ends_with(")V"));
+
+      if !valid {
+        return Err(format_error(format!(
+          "Illegal method name \"{}\" or signature \"{}\" in class file {}",
+          name,
+          descriptor,
+          self.get_name()
+        )));
+      }
+
+      if !has_one_visibility(method.get_access_flags()) {
+        return Err(format_error(format!(
+          "Illegal method modifiers in class {}: 0x{:X}",
+          self.get_name(),
+          method.get_access_flags()
+        )));
+      }
+
+      if !methods.insert((name, descriptor)) {
+        return Err(format_error(format!(
+          "Duplicate method name \"{}\" with signature \"{}\" in class file {}",
+          name,
+          descriptor,
+          self.get_name()
+        )));
+      }
+
+      self.check_code_attribute(method)?;
+    }
+
+    Ok(())
+  }
+
+  // native and abstract methods have no Code attribute, every other method has exactly one
+  fn check_code_attribute(&self, method: &MethodInfo) -> Result<()> {
+    let attributes = method.get_attributes();
+
+    let codes: Vec<_> = (0..attributes.len())
+      .filter_map(|i| match attributes.get(i).get_info() {
+        AttributeInfoInfoEnum::Code(code) => Some(code),
+        _ => None,
+      })
+      .collect();
+
+    // ACC_NATIVE or ACC_ABSTRACT
+    let expected = if method.get_access_flags() & (0x0100 | 0x0400) != 0 {
+      0
+    } else {
+      1
+    };
+
+    if codes.len() != expected {
+      return Err(format_error(format!(
+        "Method {}{} in class file {} has {} Code attributes",
+        method.get_name(),
+        method.get_descriptor(),
+        self.get_name(),
+        codes.len()
+      )));
+    }
+
+    // the code can not be empty and is limited to 65535 bytes (JVMS 4.7.3)
+    if let Some(code) = codes.first() {
+      if code.get_code_vec().is_empty() || code.get_code_vec().len() > 65535 {
+        return Err(format_error(format!(
+          "Invalid method Code length {} in class file {}",
+          code.get_code_vec().len(),
+          self.get_name()
+        )));
+      }
+    }
+
+    Ok(())
+  }
+}
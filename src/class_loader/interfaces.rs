@@ -7,6 +7,8 @@ use crate::utils::*;
 #[derive(Debug)]
 pub struct Interfaces {
   interfaces: Vec<String>,
+  // indices in the constant pool, empty for the interfaces of array classes
+  indices: Vec<ju2>,
 }
 
 impl Interfaces {
@@ -15,17 +17,19 @@ impl Interfaces {
 
     let mut interfaces: Vec<String> = vec![];
 
+    let mut indices: Vec<ju2> = vec![];
+
     let mut index: usize = 0;
 
-    let interfaces_count: ju2 = u16::from_be_bytes(bytes[0..2].try_into().unwrap());
+    let interfaces_count: ju2 = ju2_at(bytes, 0)?;
 
     debug!("    Found {:?} interfaces", interfaces_count);
     index += 2;
 
     for _ in 0..interfaces_count {
-      let slice = &bytes[index..];
+      let slice = get_tail(bytes, index)?;
 
-      let interf: ju2 = u16::from_be_bytes(slice[0..2].try_into().unwrap());
+      let interf: ju2 = ju2_at(slice, 0)?;
 
       let intername = cp.resolve_name(cp.resolve_class(interf)?)?;
       debug!("    Interface : {:?}", intername);
@@ -33,13 +37,24 @@ impl Interfaces {
       index += 2;
 
       interfaces.push(intername);
+
+      indices.push(interf);
     }
 
-    Ok((Interfaces { interfaces }, index))
+    Ok((
+      Interfaces {
+        interfaces,
+        indices,
+      },
+      index,
+    ))
   }
 
   pub fn empty() -> Interfaces {
-    Interfaces { interfaces: vec![] }
+    Interfaces {
+      interfaces: vec![],
+      indices: vec![],
+    }
   }
 
   pub fn add_interface(&mut self, interface: String) {
@@ -49,4 +64,19 @@ impl Interfaces {
   pub fn get_interfaces(&self) -> &Vec<String> {
     &self.interfaces
   }
+
+  /// Rename the interfaces after their resolution by the defining loader
+  pub(crate) fn relink(&mut self, interfaces: Vec<String>) {
+    self.interfaces = interfaces;
+  }
+}
+
+impl WriteInfo for Interfaces {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend((self.indices.len() as ju2).to_be_bytes());
+
+    for index in &self.indices {
+      bytes.extend(index.to_be_bytes());
+    }
+  }
 }
@@ -4,7 +4,7 @@ use std::{
 };
 
 use color_eyre::eyre::{eyre, OptionExt, Result};
-use log::{debug, warn};
+use log::debug;
 
 use super::{
   class_file::{ClassFile, InvokeDynamicResolved, MethodHandleResolved},
@@ -12,16 +12,25 @@ use super::{
   methods::MethodInfo,
 };
 use crate::{
-  class_file,
-  class_loader::attributes,
+  class_loader::{attributes, class_file},
   runtime::{errors, modulemanager::ModuleManager, types},
-  utils::{dotclass_to_class, ju2, pause},
+  utils::{dotclass_to_class, ju2, ju4},
 };
 
 #[derive(Debug)]
 pub struct ClassLoader {
   pub(crate) modulemanager: ModuleManager,
-  classes: HashMap<String, Arc<RwLock<class_file::ClassFile>>>,
+  pub(super) classes: HashMap<String, Arc<RwLock<class_file::ClassFile>>>,
+  // hidden classes, only reached through their key (i.e. from their Class object)
+  pub(super) hidden_classes: HashMap<String, Arc<RwLock<class_file::ClassFile>>>,
+  // (initiating loader, class name) -> class key
+  pub(super) initiating_loaders: HashMap<(ju4, String), String>,
+  // class name -> sets of loaders that must load the same class
+  pub(super) loader_constraints: HashMap<String, Vec<Vec<ju4>>>,
+  // used to give a unique name to hidden classes
+  pub(super) next_hidden_id: u64,
+  // class key -> offset following the fields of the class and of its super classes
+  pub(super) fields_ends: HashMap<String, i64>,
 }
 
 impl ClassLoader {
@@ -29,6 +38,11 @@ impl ClassLoader {
     ClassLoader {
       modulemanager: ModuleManager::new(),
       classes: HashMap::new(),
+      hidden_classes: HashMap::new(),
+      initiating_loaders: HashMap::new(),
+      loader_constraints: HashMap::new(),
+      next_hidden_id: 0,
+      fields_ends: HashMap::new(),
     }
   }
 
@@ -167,10 +181,16 @@ impl ClassLoader {
 
     let has_parent = class.has_parent();
 
-    let interfaces = class.get_interfaces().clone();
+    let mut interfaces = class.get_interfaces().clone();
     drop(class);
 
-    for interface_name in interfaces {
+    // the direct interfaces, then their super interfaces (e.g. `Iterable.forEach` of a `Set`)
+    let mut next = 0;
+
+    while next < interfaces.len() {
+      let interface_name = interfaces[next].clone();
+      next += 1;
+
       let interface = self.get(&interface_name)?;
       let interface_methods = interface.get_methods();
       for method in interface_methods {
@@ -182,6 +202,12 @@ impl ClassLoader {
         }
         vtable_index += 1;
       }
+
+      for super_interface in interface.get_interfaces() {
+        if !interfaces.contains(super_interface) {
+          interfaces.push(super_interface.clone());
+        }
+      }
     }
 
     if has_parent {
@@ -361,7 +387,7 @@ impl ClassLoader {
     }
   }
 
-  pub fn resolve_method_handle(
+  pub(crate) fn resolve_method_handle(
     &mut self,
     classname: &str,
     index: ju2,
@@ -413,7 +439,7 @@ impl ClassLoader {
     }
   }
 
-  pub fn resolve_invokedynamic(
+  pub(crate) fn resolve_invokedynamic(
     &mut self,
     classname: &str,
     index: ju2,
@@ -467,94 +493,121 @@ impl ClassLoader {
 
   /// get general lock
   pub fn get_lock(&mut self, name: &str) -> Result<Arc<RwLock<class_file::ClassFile>>> {
-    let name = &dotclass_to_class(name);
-    if !self.classes.contains_key(name) {
-      self.load_class(name)?;
-    }
-
-    Ok(self.classes.get(name).unwrap().clone())
+    Ok(self.get_class_file(name)?.clone())
   }
 
   /// get lock read
-  pub fn get(&mut self, name: &str) -> Result<RwLockReadGuard<class_file::ClassFile>> {
+  pub fn get(&mut self, name: &str) -> Result<RwLockReadGuard<'_, class_file::ClassFile>> {
+    Ok(self.get_class_file(name)?.read().unwrap())
+  }
+
+  /// get lock write
+  pub fn get_mut(&mut self, name: &str) -> Result<RwLockWriteGuard<'_, class_file::ClassFile>> {
+    Ok(self.get_class_file(name)?.write().unwrap())
+  }
+
+  // the class loaded or hidden with the key `name`, loaded if needed
+  fn get_class_file(&mut self, name: &str) -> Result<&Arc<RwLock<class_file::ClassFile>>> {
     let name = &dotclass_to_class(name);
+
+    if self.hidden_classes.contains_key(name) {
+      return Ok(self.hidden_classes.get(name).unwrap());
+    }
+
     if !self.classes.contains_key(name) {
       self.load_class(name)?;
     }
 
-    Ok(
-      self
-        .classes
-        .get(name)
-        .ok_or_eyre(format!("class {:?} not found using get", name))?
-        .read()
-        .unwrap(),
-    )
+    self
+      .classes
+      .get(name)
+      .ok_or_eyre(format!("class {:?} not found using get", name))
   }
 
-  /// get lock write
-  pub fn get_mut(&mut self, name: &str) -> Result<RwLockWriteGuard<class_file::ClassFile>> {
-    let name = &dotclass_to_class(name);
-    if !self.classes.contains_key(name) {
-      self.load_class(name)?;
+  /// Values of the static fields of the loaded classes, and the loaders of the classes
+  pub(crate) fn get_static_values(&self) -> Vec<types::Type> {
+    let mut values = vec![];
+
+    for class in self.classes.values().chain(self.hidden_classes.values()) {
+      values.extend(class.read().unwrap().get_static_fields().values().copied());
     }
 
-    Ok(
-      self
-        .classes
-        .get_mut(name)
-        .ok_or_eyre(format!("class {:?} not found using get_mut", name))?
-        .write()
-        .unwrap(),
-    )
+    for (loader_ref, _) in self.initiating_loaders.keys() {
+      values.push(types::Type::ObjectRef(*loader_ref));
+    }
+
+    values
   }
 
+  // the fields of a class are placed after the ones of its super classes, so an offset is
+  // unique in the objects of its subclasses
   pub fn get_field_offset(&mut self, classname: &str, field_name: &str) -> Result<i64> {
     let class = self.get(&dotclass_to_class(classname))?;
 
-    warn!("Super classes not checked properly in get_field_offset");
-
-    let fields = class.get_fields();
-
-    for f in fields {
-      debug!("{:?}", f.get_name())
-    }
+    let offset = class.get_field_offset(field_name).ok();
+    let parent_name = class.get_parent_name().to_string();
+    let has_parent = class.has_parent();
 
-    match class.get_field_offset(field_name) {
-      r @ Ok(_) => r,
-      _ => {
-        if class.has_parent() {
-          let parent_name = class.get_parent_name().to_string();
+    drop(class);
 
-          drop(class);
+    let base = match has_parent {
+      true => self.get_fields_end(&parent_name)?,
+      false => 0,
+    };
 
-          self.get_field_offset(&parent_name, field_name)
-        } else {
-          panic!()
-        }
-      }
+    match offset {
+      Some(offset) => Ok(base + offset),
+      None if has_parent => self.get_field_offset(&parent_name, field_name),
+      None => Err(eyre!("field not found: {}.{}", classname, field_name)),
     }
   }
 
   pub fn get_field_by_offset(&mut self, classname: &str, offset: i64) -> Result<FieldInfo> {
     let class = self.get(&dotclass_to_class(classname))?;
 
-    warn!("Super classes not checked properly in get_field_by_offset");
+    let parent_name = class.get_parent_name().to_string();
+    let has_parent = class.has_parent();
 
-    match class.get_field_by_offset(offset) {
-      r @ Ok(_) => r,
-      _ => {
-        if class.has_parent() {
-          let parent_name = class.get_parent_name().to_string();
+    drop(class);
 
-          drop(class);
+    let base = match has_parent {
+      true => self.get_fields_end(&parent_name)?,
+      false => 0,
+    };
 
-          self.get_field_by_offset(&parent_name, offset)
-        } else {
-          panic!("get field by offset")
-        }
-      }
+    if offset >= base {
+      return self
+        .get(&dotclass_to_class(classname))?
+        .get_field_by_offset(offset - base);
+    }
+
+    match has_parent {
+      true => self.get_field_by_offset(&parent_name, offset),
+      false => Err(eyre!("field by offset not found")),
+    }
+  }
+
+  // the offset following the fields of the class and of its super classes
+  fn get_fields_end(&mut self, classname: &str) -> Result<i64> {
+    if let Some(end) = self.fields_ends.get(classname) {
+      return Ok(*end);
     }
+
+    let class = self.get(classname)?;
+
+    let count = class.get_fields().len() as i64;
+    let parent_name = class.get_parent_name().to_string();
+    let has_parent = class.has_parent();
+
+    drop(class);
+
+    let end = match has_parent {
+      true => self.get_fields_end(&parent_name)? + count,
+      false => count,
+    };
+    self.fields_ends.insert(classname.to_string(), end);
+
+    Ok(end)
   }
 
   // does right implements left ?
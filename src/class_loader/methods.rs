@@ -26,20 +26,65 @@ impl MethodInfo {
   fn parse(bytes: &[u8], cp: &ConstantPool) -> Result<(MethodInfo, usize)> {
     let mut index: usize = 0;
 
-    let access_flags: ju2 = ju2_from_bytes(get_slice_arr(bytes, index, 2))?;
+    let access_flags: ju2 = ju2_at(bytes, index)?;
 
     index += 2;
 
-    let name_index: ju2 = ju2_from_bytes(get_slice_arr(bytes, index, 2))?;
+    let name_index: ju2 = ju2_at(bytes, index)?;
 
     index += 2;
 
-    let descriptor_index: ju2 = ju2_from_bytes(get_slice_arr(bytes, index, 2))?;
+    let descriptor_index: ju2 = ju2_at(bytes, index)?;
 
     index += 2;
 
-    let (attributes, attributes_size) = Attributes::parse(&bytes[index..], cp)?;
+    let (attributes, attributes_size) = Attributes::parse(get_tail(bytes, index)?, cp)?;
 
+    index += attributes_size;
+
+    let name = cp.resolve_name(name_index)?;
+
+    let descriptor = cp.resolve_name(descriptor_index)?;
+
+    Ok((
+      MethodInfo::new(
+        access_flags,
+        name_index,
+        descriptor_index,
+        name,
+        descriptor,
+        attributes,
+      ),
+      index,
+    ))
+  }
+
+  /// New method, with its name and descriptor added to the constant pool `cp`
+  pub(crate) fn define(
+    cp: &mut ConstantPool,
+    access_flags: ju2,
+    name: &str,
+    descriptor: &str,
+    attributes: Attributes,
+  ) -> MethodInfo {
+    MethodInfo::new(
+      access_flags,
+      cp.add_utf8(name),
+      cp.add_utf8(descriptor),
+      name.to_string(),
+      descriptor.to_string(),
+      attributes,
+    )
+  }
+
+  fn new(
+    access_flags: ju2,
+    name_index: ju2,
+    descriptor_index: ju2,
+    name: String,
+    descriptor: String,
+    attributes: Attributes,
+  ) -> MethodInfo {
     // native methods dont have code attribute but they still have a method body
     let (has_code, code_index) = 'found: {
       if (access_flags & 0x0100) != 0 {
@@ -65,28 +110,19 @@ impl MethodInfo {
       false
     };
 
-    index += attributes_size;
-
-    let name = cp.resolve_name(name_index)?;
-
-    let descriptor = cp.resolve_name(descriptor_index)?;
-
-    Ok((
-      MethodInfo {
-        name,
-        descriptor,
-        access_flags,
-        name_index,
-        descriptor_index,
-        attributes,
+    MethodInfo {
+      name,
+      descriptor,
+      access_flags,
+      name_index,
+      descriptor_index,
+      attributes,
 
-        has_code,
-        code_index,
+      has_code,
+      code_index,
 
-        has_polymorphic_signature,
-      },
-      index,
-    ))
+      has_polymorphic_signature,
+    }
   }
 
   pub fn get_name(&self) -> &str {
@@ -105,7 +141,7 @@ impl MethodInfo {
     self.descriptor_index
   }
 
-  pub fn _get_attributes(&self) -> &Attributes {
+  pub fn get_attributes(&self) -> &Attributes {
     &self.attributes
   }
 
@@ -114,7 +150,8 @@ impl MethodInfo {
   }
 
   pub fn get_code(&self) -> Option<&Code> {
-    if self.has_code {
+    // native methods may have no attributes at all
+    if self.has_code && self.code_index < self.attributes.len() {
       if let AttributeInfoInfoEnum::Code(c) = self.attributes.get(self.code_index).get_info() {
         return Some(c);
       }
@@ -134,6 +171,10 @@ impl MethodInfo {
     (self.access_flags & 0x0100) != 0
   }
 
+  pub fn is_private(&self) -> bool {
+    (self.access_flags & 0x0002) != 0
+  }
+
   pub fn is_static(&self) -> bool {
     (self.access_flags & 0x0008) != 0
   }
@@ -143,6 +184,16 @@ impl MethodInfo {
   }
 }
 
+impl WriteInfo for MethodInfo {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend(self.access_flags.to_be_bytes());
+    bytes.extend(self.name_index.to_be_bytes());
+    bytes.extend(self.descriptor_index.to_be_bytes());
+
+    self.attributes.write(bytes);
+  }
+}
+
 #[derive(Debug)]
 pub struct Methods {
   methods: Vec<MethodInfo>,
@@ -155,13 +206,13 @@ impl Methods {
 
     let mut index: usize = 0;
 
-    let methods_count: ju2 = u16::from_be_bytes(bytes[0..2].try_into().unwrap());
+    let methods_count: ju2 = ju2_at(bytes, 0)?;
 
     debug!("    Found {:?} methods", methods_count);
     index += 2;
 
     for _ in 0..methods_count {
-      let slice = &bytes[index..];
+      let slice = get_tail(bytes, index)?;
 
       let (method, size) = MethodInfo::parse(slice, cp)?;
       //debug!(
@@ -214,3 +265,13 @@ impl Methods {
     None
   }
 }
+
+impl WriteInfo for Methods {
+  fn write(&self, bytes: &mut Vec<u8>) {
+    bytes.extend((self.methods.len() as ju2).to_be_bytes());
+
+    for method in &self.methods {
+      method.write(bytes);
+    }
+  }
+}
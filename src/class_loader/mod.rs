@@ -1,7 +1,15 @@
+pub mod access;
+pub mod assembler;
 pub mod attributes;
+pub mod builder;
 pub mod class_file;
+pub mod class_path;
 pub mod constant_pool;
+pub mod disassembler;
 pub mod fields;
+pub mod format_checker;
 pub mod interfaces;
 pub mod loader;
 pub mod methods;
+pub mod namespace;
+pub mod verifier;
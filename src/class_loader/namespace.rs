@@ -0,0 +1,311 @@
+use std::sync::{Arc, RwLock};
+
+use color_eyre::eyre::{eyre, Result};
+use log::debug;
+
+use super::{class_file::ClassFile, loader::ClassLoader};
+use crate::{runtime::errors, utils::*};
+
+/// Identifier of the bootstrap class loader (the object reference 0 is null)
+pub(crate) const BOOTSTRAP_LOADER: ju4 = 0;
+
+/// Separates the binary name of a class from the loader that defined it
+const LOADER_SEPARATOR: char = '@';
+
+/// Separates the name of a hidden class from its unique suffix
+const HIDDEN_SEPARATOR: &str = "/0x";
+
+// https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-5.html#jvms-5.3
+//
+// A class is identified by its binary name and its defining loader. The
+// classes defined by the bootstrap loader are stored under their binary name,
+// the ones defined by a user-defined loader under `name@loader_ref` so that
+// two loaders can define distinct classes with the same name.
+impl ClassLoader {
+  /// Key used to store the class `name` defined by `loader`
+  pub(crate) fn get_class_key(loader: ju4, name: &str) -> String {
+    if loader == BOOTSTRAP_LOADER {
+      name.to_string()
+    } else {
+      format!("{}{}{}", name, LOADER_SEPARATOR, loader)
+    }
+  }
+
+  /// Binary name of a class key (e.g. `Foo` for `Foo@12` and `[LFoo;` for `[LFoo@12;`)
+  pub(crate) fn get_binary_name(key: &str) -> String {
+    match key.find(LOADER_SEPARATOR) {
+      Some(start) => {
+        let end = key[start..]
+          .find(';')
+          .map(|i| start + i)
+          .unwrap_or(key.len());
+
+        format!("{}{}", &key[..start], &key[end..])
+      }
+      None => key.to_string(),
+    }
+  }
+
+  /// Does the class key look like the one of a hidden class ?
+  pub(crate) fn has_hidden_suffix(key: &str) -> bool {
+    key.contains(HIDDEN_SEPARATOR)
+  }
+
+  /// Name of a class without the suffix of hidden classes (e.g. `Foo` for `Foo/0x0000000000000001`)
+  pub(crate) fn strip_hidden_suffix(name: &str) -> &str {
+    match name.rfind(HIDDEN_SEPARATOR) {
+      Some(start)
+        if name[start + HIDDEN_SEPARATOR.len()..]
+          .chars()
+          .all(|c| c.is_ascii_hexdigit()) =>
+      {
+        &name[..start]
+      }
+      _ => name,
+    }
+  }
+
+  /// Name of a class as returned by `Class.getName` (e.g. `java.lang.String`
+  /// or `Foo/0x0000000000000001` for a hidden class)
+  pub(crate) fn get_java_name(key: &str) -> String {
+    let binary_name = ClassLoader::get_binary_name(key);
+    let name = ClassLoader::strip_hidden_suffix(&binary_name);
+
+    format!("{}{}", class_to_dotclass(name), &binary_name[name.len()..])
+  }
+
+  /// Defining loader of a class key (`BOOTSTRAP_LOADER` for the bootstrap loader)
+  pub(crate) fn get_defining_loader(key: &str) -> ju4 {
+    match key.find(LOADER_SEPARATOR) {
+      Some(start) => key[start + 1..]
+        .trim_end_matches(';')
+        .parse()
+        .unwrap_or(BOOTSTRAP_LOADER),
+      None => BOOTSTRAP_LOADER,
+    }
+  }
+
+  /// Class `name` already loaded by `loader` as initiating loader, if any
+  pub(crate) fn find_loaded_class(&self, loader: ju4, name: &str) -> Option<String> {
+    let name = dotclass_to_class(name);
+
+    if loader == BOOTSTRAP_LOADER {
+      return self.classes.contains_key(&name).then_some(name);
+    }
+
+    self.initiating_loaders.get(&(loader, name)).cloned()
+  }
+
+  /// Key of the class `name` referenced by the class `context`, looked up in
+  /// the classes already loaded by the defining loader of `context`
+  pub(crate) fn get_loaded_class_key(&self, context: &str, name: &str) -> String {
+    self
+      .find_loaded_class(ClassLoader::get_defining_loader(context), name)
+      .unwrap_or_else(|| name.to_string())
+  }
+
+  /// Record `loader` as an initiating loader of the class `key` named `name`
+  ///
+  /// # Returns
+  ///
+  /// `LinkageError` if it violates a loader constraint
+  pub(crate) fn record_initiating_loader(
+    &mut self,
+    loader: ju4,
+    name: &str,
+    key: &str,
+  ) -> Result<()> {
+    if loader == BOOTSTRAP_LOADER {
+      return Ok(());
+    }
+
+    let mut loaders = vec![loader];
+
+    if let Some(constraints) = self.loader_constraints.get(name) {
+      for constraint in constraints.iter().filter(|c| c.contains(&loader)) {
+        loaders.extend(constraint);
+      }
+    }
+
+    for other in loaders {
+      if let Some(other_key) = self.find_loaded_class(other, name) {
+        if other_key != key {
+          return Err(eyre!(errors::JavaException::LinkageError(format!(
+            "loader constraint violation: loaders {} and {} have different classes for {}",
+            loader,
+            other,
+            class_to_dotclass(name)
+          ))));
+        }
+      }
+    }
+
+    debug!("Loader {} initiated {} as {}", loader, name, key);
+
+    self
+      .initiating_loaders
+      .insert((loader, name.to_string()), key.to_string());
+
+    Ok(())
+  }
+
+  /// Add the loader constraint `name`^`left` = `name`^`right` (JVMS 5.3.4)
+  ///
+  /// # Returns
+  ///
+  /// `LinkageError` if `left` and `right` already loaded different classes
+  pub(crate) fn add_loader_constraint(&mut self, name: &str, left: ju4, right: ju4) -> Result<()> {
+    if left == right {
+      return Ok(());
+    }
+
+    let constraints = self
+      .loader_constraints
+      .get(name)
+      .cloned()
+      .unwrap_or_default();
+
+    // merge every constraint involving one of the loaders
+    let (merged, mut others): (Vec<_>, Vec<_>) = constraints
+      .into_iter()
+      .partition(|c| c.contains(&left) || c.contains(&right));
+
+    let mut loaders: Vec<ju4> = merged.into_iter().flatten().chain([left, right]).collect();
+    loaders.sort_unstable();
+    loaders.dedup();
+
+    let mut loaded: Vec<String> = loaders
+      .iter()
+      .filter_map(|l| self.find_loaded_class(*l, name))
+      .collect();
+    loaded.sort_unstable();
+    loaded.dedup();
+
+    if loaded.len() > 1 {
+      return Err(eyre!(errors::JavaException::LinkageError(format!(
+        "loader constraint violation: loaders {} and {} have different classes for {}",
+        left,
+        right,
+        class_to_dotclass(name)
+      ))));
+    }
+
+    others.push(loaders);
+
+    self.loader_constraints.insert(name.to_string(), others);
+
+    Ok(())
+  }
+
+  /// Key of the class parsed in `class_file` once defined by `loader`
+  ///
+  /// The class is renamed to its key but not recorded yet, see `define_class`.
+  ///
+  /// # Returns
+  ///
+  /// `LinkageError` if `loader` already defined a class with the same name
+  pub(crate) fn prepare_class(
+    &mut self,
+    loader: ju4,
+    class_file: &Arc<RwLock<ClassFile>>,
+  ) -> Result<String> {
+    let name = class_file.read().unwrap().get_name().to_string();
+
+    let key = ClassLoader::get_class_key(loader, &name);
+
+    if loader != BOOTSTRAP_LOADER {
+      if self.classes.contains_key(&key) {
+        return Err(eyre!(errors::JavaException::LinkageError(format!(
+          "loader {} attempted duplicate class definition for {}",
+          loader,
+          class_to_dotclass(&name)
+        ))));
+      }
+
+      let mut class = class_file.write().unwrap();
+      let interfaces = class.get_interfaces().clone();
+
+      class.relink(&key, None, interfaces);
+    }
+
+    Ok(key)
+  }
+
+  /// Define the class prepared as `key` with `loader` as defining loader
+  ///
+  /// The super class and interfaces are resolved before, see `JVM::define_class`
+  pub(crate) fn define_class(
+    &mut self,
+    loader: ju4,
+    key: &str,
+    class_file: Arc<RwLock<ClassFile>>,
+  ) -> Result<()> {
+    let name = ClassLoader::get_binary_name(key);
+
+    self.record_initiating_loader(loader, &name, key)?;
+
+    self.add_class_file("unnamed", key, class_file)
+  }
+
+  /// Keys of the hidden classes defined so far
+  #[allow(dead_code)]
+  pub(crate) fn get_hidden_classes(&self) -> Vec<String> {
+    self.hidden_classes.keys().cloned().collect()
+  }
+
+  /// Define the hidden class parsed in `class_file` with `loader` as defining loader
+  ///
+  /// A hidden class gets a unique name and is not recorded in the namespace of
+  /// any loader, so that it can not be found by name.
+  ///
+  /// # Arguments
+  ///
+  /// * `loader` - Defining loader
+  /// * `lookup` - Lookup class defining the hidden class (gives the module)
+  /// * `class_file` - Parsed class file
+  /// * `nest_host` - Nest host to join, if any
+  ///
+  /// # Returns
+  ///
+  /// The key of the new class
+  pub(crate) fn define_hidden_class(
+    &mut self,
+    loader: ju4,
+    lookup: &str,
+    class_file: Arc<RwLock<ClassFile>>,
+    nest_host: Option<String>,
+  ) -> Result<String> {
+    self.next_hidden_id += 1;
+
+    let key = {
+      let mut class = class_file.write().unwrap();
+
+      let hidden_name = format!(
+        "{}{}{:016x}",
+        class.get_name(),
+        HIDDEN_SEPARATOR,
+        self.next_hidden_id
+      );
+      let key = ClassLoader::get_class_key(loader, &hidden_name);
+      let interfaces = class.get_interfaces().clone();
+
+      class.relink(&key, None, interfaces);
+      class.set_hidden(nest_host);
+
+      key
+    };
+
+    let module = match self.modulemanager.get_module_by_class(lookup) {
+      Ok(module) => module.get_name().to_string(),
+      Err(_) => "unnamed".to_string(),
+    };
+
+    debug!("Hidden class {} defined in {}", key, module);
+
+    // kept out of the classes that can be found by name
+    self.modulemanager.add_to_module(&module, &key)?;
+    self.hidden_classes.insert(key.clone(), class_file);
+
+    Ok(key)
+  }
+}
@@ -0,0 +1,1644 @@
+use std::{collections::BTreeMap, fmt};
+
+use color_eyre::eyre::{eyre, Report, Result};
+use log::debug;
+
+use super::{
+  attributes::{
+    Code, StackMapFrameEnum, StackMapTable, VerificationTypeInfo, VerificationTypeInfoEnum,
+  },
+  class_file::ClassFile,
+  constant_pool::CpInfoInfoEnum,
+  loader::ClassLoader,
+  methods::MethodInfo,
+};
+use crate::{
+  runtime::{errors, opcode::OpCode},
+  utils::*,
+};
+
+/// First class file version verified by type checking (older ones need type inference)
+const TYPE_CHECKING_VERSION: ju2 = 50;
+
+// https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.10.1.2
+//
+// Types of the locals and of the operand stack. `boolean`, `byte`, `char` and
+// `short` are all `Integer`, a `long` or a `double` local takes two slots
+// (the second one is `Top`) and a single entry on the operand stack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum VerificationType {
+  Top,
+  Integer,
+  Float,
+  Long,
+  Double,
+  Null,
+  UninitializedThis,
+  // object created by the `new` at this offset
+  Uninitialized(usize),
+  // class name, or descriptor for arrays (e.g. `[I`)
+  Object(String),
+}
+
+impl VerificationType {
+  fn from_descriptor(desc: &str) -> VerificationType {
+    match desc.chars().next() {
+      Some('Z' | 'B' | 'C' | 'S' | 'I') => VerificationType::Integer,
+      Some('F') => VerificationType::Float,
+      Some('J') => VerificationType::Long,
+      Some('D') => VerificationType::Double,
+      _ => VerificationType::Object(descriptor_to_classname(desc)),
+    }
+  }
+
+  fn object(name: &str) -> VerificationType {
+    VerificationType::Object(name.to_string())
+  }
+
+  fn size(&self) -> usize {
+    match self {
+      VerificationType::Long | VerificationType::Double => 2,
+      _ => 1,
+    }
+  }
+
+  fn is_reference(&self) -> bool {
+    matches!(
+      self,
+      VerificationType::Null
+        | VerificationType::UninitializedThis
+        | VerificationType::Uninitialized(_)
+        | VerificationType::Object(_)
+    )
+  }
+}
+
+impl fmt::Display for VerificationType {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      VerificationType::Top => write!(f, "top"),
+      VerificationType::Integer => write!(f, "integer"),
+      VerificationType::Float => write!(f, "float"),
+      VerificationType::Long => write!(f, "long"),
+      VerificationType::Double => write!(f, "double"),
+      VerificationType::Null => write!(f, "null"),
+      VerificationType::UninitializedThis => write!(f, "uninitializedThis"),
+      VerificationType::Uninitialized(offset) => write!(f, "uninitialized({})", offset),
+      VerificationType::Object(name) => write!(f, "'{}'", name),
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+struct TypeState {
+  locals: Vec<VerificationType>,
+  stack: Vec<VerificationType>,
+}
+
+impl TypeState {
+  fn stack_size(&self) -> usize {
+    self.stack.iter().map(|t| t.size()).sum()
+  }
+
+  // `this` is not initialized yet in a constructor (flagThisUninit)
+  fn has_uninitialized_this(&self) -> bool {
+    self.locals.contains(&VerificationType::UninitializedThis)
+  }
+
+  // an object is initialized by its constructor
+  fn initialize(&mut self, uninitialized: &VerificationType, initialized: &VerificationType) {
+    for t in self.locals.iter_mut().chain(self.stack.iter_mut()) {
+      if t == uninitialized {
+        *t = initialized.clone();
+      }
+    }
+  }
+}
+
+impl ClassLoader {
+  /// Verify the code of the methods of the class `class_name` (JVMS 4.10.1)
+  ///
+  /// # Returns
+  ///
+  /// `VerifyError` if a method is not type safe
+  pub(crate) fn verify_class(&mut self, class_name: &str) -> Result<()> {
+    let class_lock = self.get_lock(class_name)?;
+    let class = class_lock.read().unwrap();
+
+    if class.get_major_version() < TYPE_CHECKING_VERSION {
+      debug!("Skip verification of {} (no StackMapTable)", class_name);
+
+      return Ok(());
+    }
+
+    debug!("Verifying {}", class_name);
+
+    for method in class.get_methods() {
+      if let Some(code) = method.get_code() {
+        MethodVerifier::new(self, &class, class_name, method, code).verify()?;
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Are the classes of `class_name` trusted and not verified ?
+  ///
+  /// As in HotSpot, the classes of the JDK modules are not verified.
+  pub(crate) fn is_trusted(&self, class_name: &str) -> bool {
+    match self.modulemanager.get_module_by_class(class_name) {
+      Ok(module) => module.get_name().starts_with("java.") || module.get_name().starts_with("jdk."),
+      Err(_) => false,
+    }
+  }
+}
+
+struct MethodVerifier<'a> {
+  loader: &'a mut ClassLoader,
+  class: &'a ClassFile,
+  // key of the verified class
+  class_name: &'a str,
+  // name of the verified class in its constant pool
+  this_name: String,
+  method: &'a MethodInfo,
+  code: &'a [ju1],
+  stack_map_table: Option<&'a StackMapTable>,
+  max_stack: usize,
+  max_locals: usize,
+  exception_table: Vec<(usize, usize, usize, String)>,
+  stack_map: BTreeMap<usize, TypeState>,
+  // pc of the current instruction, for error messages
+  pc: usize,
+}
+
+impl<'a> MethodVerifier<'a> {
+  fn new(
+    loader: &'a mut ClassLoader,
+    class: &'a ClassFile,
+    class_name: &'a str,
+    method: &'a MethodInfo,
+    code: &'a Code,
+  ) -> MethodVerifier<'a> {
+    let binary_name = ClassLoader::get_binary_name(class_name);
+
+    MethodVerifier {
+      loader,
+      class,
+      class_name,
+      this_name: ClassLoader::strip_hidden_suffix(&binary_name).to_string(),
+      method,
+      code: code.get_code_vec(),
+      stack_map_table: code.get_stack_map_table(),
+      max_stack: code.get_max_stack() as usize,
+      max_locals: code.get_max_locals() as usize,
+      exception_table: code
+        .get_exception_table()
+        .iter()
+        .map(|e| {
+          (
+            e.get_start_pc() as usize,
+            e.get_end_pc() as usize,
+            e.get_handler_pc() as usize,
+            match e.get_catch_type() {
+              "finally" => "java/lang/Throwable".to_string(),
+              catch_type => catch_type.to_string(),
+            },
+          )
+        })
+        .collect(),
+      stack_map: BTreeMap::new(),
+      pc: 0,
+    }
+  }
+
+  fn error(&self, msg: &str) -> Report {
+    eyre!(errors::JavaException::VerifyError(format!(
+      "{} in {}.{}{} @{}",
+      msg,
+      class_to_dotclass(&self.this_name),
+      self.method.get_name(),
+      self.method.get_descriptor(),
+      self.pc
+    )))
+  }
+
+  fn verify(mut self) -> Result<()> {
+    if self.code.is_empty() {
+      return Err(self.error("Code is empty"));
+    }
+
+    let starts = self.get_instruction_starts()?;
+
+    let initial = self.get_initial_state()?;
+
+    self.parse_stack_map(&initial, &starts)?;
+
+    for (start, end, handler, _) in &self.exception_table {
+      if start >= end
+        || *end > self.code.len()
+        || *handler >= self.code.len()
+        || !starts[*start]
+        || !starts[*handler]
+      {
+        return Err(self.error("Illegal exception table range"));
+      }
+
+      if *end < self.code.len() && !starts[*end] {
+        return Err(self.error("Illegal exception table range"));
+      }
+    }
+
+    let mut current = Some(initial);
+    let mut pc = 0;
+
+    while pc < self.code.len() {
+      self.pc = pc;
+
+      if let Some(frame) = self.stack_map.get(&pc).cloned() {
+        if let Some(state) = &current {
+          self.check_state(state, &frame, "Instruction type does not match stack map")?;
+        }
+
+        current = Some(frame);
+      }
+
+      let mut state = match current.take() {
+        Some(state) => state,
+        None => return Err(self.error("Expecting a stackmap frame at branch target")),
+      };
+
+      self.check_handlers(&state)?;
+
+      let (falls_through, targets) = self.execute(&mut state)?;
+
+      if state.stack_size() > self.max_stack {
+        return Err(self.error("Exceeded max stack size"));
+      }
+
+      for target in targets {
+        if target >= self.code.len() || !starts[target] {
+          return Err(self.error("Illegal target of jump or branch"));
+        }
+
+        let frame = match self.stack_map.get(&target) {
+          Some(frame) => frame.clone(),
+          None => return Err(self.error("Expecting a stackmap frame at branch target")),
+        };
+
+        self.check_state(
+          &state,
+          &frame,
+          "Inconsistent stackmap frames at branch target",
+        )?;
+      }
+
+      if falls_through {
+        current = Some(state);
+      }
+
+      pc += self.get_instruction_length(pc)?;
+    }
+
+    if current.is_some() {
+      return Err(self.error("Falling off the end of the code"));
+    }
+
+    Ok(())
+  }
+
+  // offsets where an instruction starts
+  fn get_instruction_starts(&mut self) -> Result<Vec<bool>> {
+    let mut starts = vec![false; self.code.len()];
+    let mut pc = 0;
+
+    while pc < self.code.len() {
+      self.pc = pc;
+      starts[pc] = true;
+      pc += self.get_instruction_length(pc)?;
+    }
+
+    if pc != self.code.len() {
+      return Err(self.error("Code truncated in the middle of an instruction"));
+    }
+
+    Ok(starts)
+  }
+
+  fn get_opcode(&self, pc: usize) -> Result<OpCode> {
+    OpCode::try_from_byte(self.code[pc])
+      .ok_or_else(|| self.error(&format!("Bad instruction: {:#04x}", self.code[pc])))
+  }
+
+  fn get_instruction_length(&self, pc: usize) -> Result<usize> {
+    let length = match self.get_opcode(pc)? {
+      OpCode::BIPUSH
+      | OpCode::LDC
+      | OpCode::ILOAD
+      | OpCode::LLOAD
+      | OpCode::FLOAD
+      | OpCode::DLOAD
+      | OpCode::ALOAD
+      | OpCode::ISTORE
+      | OpCode::LSTORE
+      | OpCode::FSTORE
+      | OpCode::DSTORE
+      | OpCode::ASTORE
+      | OpCode::NEWARRAY => 2,
+      OpCode::SIPUSH
+      | OpCode::LDCW
+      | OpCode::LDC2W
+      | OpCode::IINC
+      | OpCode::IFEQ
+      | OpCode::IFNE
+      | OpCode::IFLT
+      | OpCode::IFGE
+      | OpCode::IFGT
+      | OpCode::IFLE
+      | OpCode::IFICMPEQ
+      | OpCode::IFICMPNE
+      | OpCode::IFICMPLT
+      | OpCode::IFICMPGE
+      | OpCode::IFICMPGT
+      | OpCode::IFICMPLE
+      | OpCode::IFACMPEQ
+      | OpCode::IFACMPNE
+      | OpCode::GOTO
+      | OpCode::GETSTATIC
+      | OpCode::PUTSTATIC
+      | OpCode::GETFIELD
+      | OpCode::PUTFIELD
+      | OpCode::INVOKEVIRTUAL
+      | OpCode::INVOKESPECIAL
+      | OpCode::INVOKESTATIC
+      | OpCode::NEW
+      | OpCode::ANEWARRAY
+      | OpCode::CHECKCAST
+      | OpCode::INSTANCEOF
+      | OpCode::IFNULL
+      | OpCode::IFNONNULL => 3,
+      OpCode::MULTIANEWARRAY => 4,
+      OpCode::INVOKEINTERFACE | OpCode::INVOKEDYNAMIC | OpCode::GOTOW => 5,
+      OpCode::WIDE => match self.get_opcode(pc + 1)? {
+        OpCode::IINC => 6,
+        _ => 4,
+      },
+      OpCode::TABLESWITCH => {
+        let base = Self::get_switch_base(pc);
+        let low = self.read_i32(base + 4)?;
+        let high = self.read_i32(base + 8)?;
+
+        if low > high {
+          return Err(self.error("Bad tableswitch bounds"));
+        }
+
+        base + 12 + (high as i64 - low as i64 + 1) as usize * 4 - pc
+      }
+      OpCode::LOOKUPSWITCH => {
+        let base = Self::get_switch_base(pc);
+        let npairs = self.read_i32(base + 4)?;
+
+        if npairs < 0 {
+          return Err(self.error("Bad lookupswitch pair count"));
+        }
+
+        base + 8 + npairs as usize * 8 - pc
+      }
+      _ => 1,
+    };
+
+    if pc + length > self.code.len() {
+      return Err(self.error("Code truncated in the middle of an instruction"));
+    }
+
+    Ok(length)
+  }
+
+  // the operands of a switch are aligned on 4 bytes
+  fn get_switch_base(pc: usize) -> usize {
+    pc + 1 + (4 - (pc + 1) % 4) % 4
+  }
+
+  fn read_u8(&self, at: usize) -> Result<usize> {
+    match self.code.get(at) {
+      Some(b) => Ok(*b as usize),
+      None => Err(self.error("Code truncated in the middle of an instruction")),
+    }
+  }
+
+  fn read_u16(&self, at: usize) -> Result<ju2> {
+    Ok(((self.read_u8(at)? << 8) | self.read_u8(at + 1)?) as ju2)
+  }
+
+  fn read_i32(&self, at: usize) -> Result<i32> {
+    let mut value = 0u32;
+
+    for i in 0..4 {
+      value = (value << 8) | self.read_u8(at + i)? as u32;
+    }
+
+    Ok(value as i32)
+  }
+
+  fn get_branch_target(&self, offset: i64) -> Result<usize> {
+    match usize::try_from(self.pc as i64 + offset) {
+      Ok(target) => Ok(target),
+      Err(_) => Err(self.error("Illegal target of jump or branch")),
+    }
+  }
+
+  // https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.10.1.6
+  fn get_initial_state(&self) -> Result<TypeState> {
+    let mut locals = vec![];
+
+    if !self.method.is_static() {
+      if self.method.get_name() == "<init>" && self.this_name != "java/lang/Object" {
+        locals.push(VerificationType::UninitializedThis);
+      } else {
+        locals.push(VerificationType::object(&self.this_name));
+      }
+    }
+
+    let descriptor = self.method.get_descriptor();
+
+    for arg in parse_parameter_types(get_parameters_type_descriptor(descriptor)) {
+      locals.push(VerificationType::from_descriptor(arg));
+    }
+
+    self.expand_locals(&locals)
+  }
+
+  // locals of a stack map frame take one or two slots, padded with `Top`
+  fn expand_locals(&self, locals: &[VerificationType]) -> Result<TypeState> {
+    let mut expanded = vec![];
+
+    for local in locals {
+      expanded.push(local.clone());
+
+      if local.size() == 2 {
+        expanded.push(VerificationType::Top);
+      }
+    }
+
+    if expanded.len() > self.max_locals {
+      return Err(self.error("Local variable table overflow"));
+    }
+
+    expanded.resize(self.max_locals, VerificationType::Top);
+
+    Ok(TypeState {
+      locals: expanded,
+      stack: vec![],
+    })
+  }
+
+  fn get_verification_type(&self, info: &VerificationTypeInfo) -> Result<VerificationType> {
+    Ok(match info.get_info() {
+      VerificationTypeInfoEnum::Top => VerificationType::Top,
+      VerificationTypeInfoEnum::Integer => VerificationType::Integer,
+      VerificationTypeInfoEnum::Float => VerificationType::Float,
+      VerificationTypeInfoEnum::Long => VerificationType::Long,
+      VerificationTypeInfoEnum::Double => VerificationType::Double,
+      VerificationTypeInfoEnum::Null => VerificationType::Null,
+      VerificationTypeInfoEnum::UninitializedThis => VerificationType::UninitializedThis,
+      VerificationTypeInfoEnum::Object { cpool_index } => {
+        VerificationType::Object(self.class.resolve_class_name(*cpool_index)?)
+      }
+      VerificationTypeInfoEnum::Uninitialized { offset } => {
+        VerificationType::Uninitialized(*offset as usize)
+      }
+    })
+  }
+
+  fn get_verification_types(
+    &self,
+    infos: &[VerificationTypeInfo],
+  ) -> Result<Vec<VerificationType>> {
+    infos
+      .iter()
+      .map(|info| self.get_verification_type(info))
+      .collect()
+  }
+
+  // https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.7.4
+  fn parse_stack_map(&mut self, initial: &TypeState, starts: &[bool]) -> Result<()> {
+    let entries = match self.stack_map_table {
+      Some(table) => table.get_entries(),
+      None => return Ok(()),
+    };
+
+    // locals as listed in the frames (a long or a double is one entry)
+    let mut locals: Vec<VerificationType> = vec![];
+    let mut i = 0;
+
+    while i < initial.locals.len() {
+      if initial.locals[i] == VerificationType::Top {
+        break;
+      }
+
+      locals.push(initial.locals[i].clone());
+      i += initial.locals[i].size();
+    }
+
+    let mut offset: Option<usize> = None;
+
+    for entry in entries {
+      let frame_type = entry.get_frame_type();
+
+      let (offset_delta, stack) = match entry.get_frame_enum() {
+        StackMapFrameEnum::SameFrame => (frame_type as usize, vec![]),
+        StackMapFrameEnum::SameLocals1StackItemFrame { stack } => (
+          frame_type as usize - 64,
+          self.get_verification_types(stack)?,
+        ),
+        StackMapFrameEnum::SameLocals1StackItemFrameExtended {
+          offset_delta,
+          stack,
+        } => (*offset_delta as usize, self.get_verification_types(stack)?),
+        StackMapFrameEnum::ChopFrame { offset_delta } => {
+          let chopped = 251 - frame_type as usize;
+
+          if chopped > locals.len() {
+            return Err(self.error("StackMapTable error: chop frame removes too many locals"));
+          }
+
+          locals.truncate(locals.len() - chopped);
+
+          (*offset_delta as usize, vec![])
+        }
+        StackMapFrameEnum::SameFrameExtended { offset_delta } => (*offset_delta as usize, vec![]),
+        StackMapFrameEnum::AppendFrame {
+          offset_delta,
+          locals: appended,
+        } => {
+          locals.extend(self.get_verification_types(appended)?);
+
+          (*offset_delta as usize, vec![])
+        }
+        StackMapFrameEnum::FullFrame {
+          offset_delta,
+          locals: full_locals,
+          stack,
+          ..
+        } => {
+          locals = self.get_verification_types(full_locals)?;
+
+          (*offset_delta as usize, self.get_verification_types(stack)?)
+        }
+      };
+
+      let pc = match offset {
+        Some(previous) => previous + offset_delta + 1,
+        None => offset_delta,
+      };
+
+      self.pc = pc;
+
+      if pc >= starts.len() || !starts[pc] {
+        return Err(self.error("StackMapTable error: bad offset"));
+      }
+
+      let mut state = self.expand_locals(&locals)?;
+      state.stack = stack;
+
+      if state.stack_size() > self.max_stack {
+        return Err(self.error("StackMapTable error: stack size too large"));
+      }
+
+      // an uninitialized type refers to a `new` instruction
+      for t in state.locals.iter().chain(state.stack.iter()) {
+        if let VerificationType::Uninitialized(new_pc) = t {
+          if *new_pc >= starts.len() || !starts[*new_pc] {
+            return Err(self.error("StackMapTable error: bad uninitialized offset"));
+          }
+        }
+      }
+
+      self.stack_map.insert(pc, state);
+
+      offset = Some(pc);
+    }
+
+    self.pc = 0;
+
+    Ok(())
+  }
+
+  // https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.10.1.2
+  fn is_assignable(&mut self, from: &VerificationType, to: &VerificationType) -> bool {
+    if from == to {
+      return true;
+    }
+
+    match (from, to) {
+      (_, VerificationType::Top) => true,
+      (VerificationType::Null, VerificationType::Object(_)) => true,
+      (VerificationType::Object(from), VerificationType::Object(to)) => {
+        self.is_class_assignable(from, to)
+      }
+      _ => false,
+    }
+  }
+
+  fn is_class_assignable(&mut self, from: &str, to: &str) -> bool {
+    if from == to || to == "java/lang/Object" {
+      return true;
+    }
+
+    match (from.strip_prefix('['), to.strip_prefix('[')) {
+      (Some(_), None) => to == "java/lang/Cloneable" || to == "java/io/Serializable",
+      (None, Some(_)) => false,
+      (Some(from_component), Some(to_component)) => {
+        let is_reference = |c: &str| c.starts_with('L') || c.starts_with('[');
+
+        if is_reference(from_component) && is_reference(to_component) {
+          self.is_class_assignable(
+            &descriptor_to_classname(from_component),
+            &descriptor_to_classname(to_component),
+          )
+        } else {
+          from_component == to_component
+        }
+      }
+      (None, None) => self.is_subclass(from, to),
+    }
+  }
+
+  // interfaces are treated like `java/lang/Object`, a class that can not be loaded
+  // can not be checked and is not assignable
+  fn is_subclass(&mut self, from: &str, to: &str) -> bool {
+    match self.get_class_info(to) {
+      Some((true, _)) => return true,
+      Some((false, _)) => {}
+      None => return false,
+    }
+
+    let mut current = from.to_string();
+
+    loop {
+      let binary_name = ClassLoader::get_binary_name(&current);
+
+      if ClassLoader::strip_hidden_suffix(&binary_name) == to {
+        return true;
+      }
+
+      current = match self.get_class_info(&current) {
+        Some((_, Some(parent))) => parent,
+        Some((_, None)) | None => return false,
+      };
+    }
+  }
+
+  // https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.10.1.8
+  //
+  // a protected member of a super class in another run-time package is only reached
+  // through an object of the verified class or of one of its subclasses
+  fn check_protected(
+    &mut self,
+    op: OpCode,
+    class: &str,
+    name: &str,
+    descriptor: &str,
+    receiver: &VerificationType,
+  ) -> Result<()> {
+    let this_name = self.this_name.clone();
+
+    if class == this_name || !self.is_subclass(&this_name, class) {
+      return Ok(());
+    }
+
+    let key = self.loader.get_loaded_class_key(self.class_name, class);
+
+    let declaration = match descriptor.starts_with('(') {
+      true => self.loader.find_method_declaration(&key, name, descriptor),
+      false => self.loader.find_field_declaration(&key, name, descriptor),
+    };
+
+    // a member that is not found fails at resolution
+    let Ok(Some((declaring, access_flags))) = declaration else {
+      return Ok(());
+    };
+
+    // ACC_PROTECTED
+    if access_flags & 0x0004 == 0 || ClassLoader::is_same_package(self.class_name, &declaring) {
+      return Ok(());
+    }
+
+    // `clone` of an array is public
+    if matches!(receiver, VerificationType::Object(name) if name.starts_with('[')) {
+      return Ok(());
+    }
+
+    if !self.is_assignable(receiver, &VerificationType::Object(this_name)) {
+      return Err(self.error(&format!(
+        "Bad access to protected data in {}",
+        op.mnemonic()
+      )));
+    }
+
+    Ok(())
+  }
+
+  // (is interface, super class) of a class referenced by the verified class
+  fn get_class_info(&mut self, name: &str) -> Option<(bool, Option<String>)> {
+    let get_info = |class: &ClassFile| {
+      (
+        class.is_interface(),
+        class
+          .has_parent()
+          .then(|| class.get_parent_name().to_owned()),
+      )
+    };
+
+    if name == self.this_name || name == self.class_name {
+      return Some(get_info(self.class));
+    }
+
+    let key = self.loader.get_loaded_class_key(self.class_name, name);
+
+    self.loader.get(&key).ok().map(|class| get_info(&class))
+  }
+
+  fn check_state(&mut self, from: &TypeState, to: &TypeState, msg: &str) -> Result<()> {
+    if from.stack.len() != to.stack.len() {
+      return Err(self.error(&format!("{} (stack size mismatch)", msg)));
+    }
+
+    for (from, to) in from
+      .locals
+      .iter()
+      .zip(to.locals.iter())
+      .chain(from.stack.iter().zip(to.stack.iter()))
+    {
+      if !self.is_assignable(from, to) {
+        return Err(self.error(&format!("{} ({} is not assignable to {})", msg, from, to)));
+      }
+    }
+
+    Ok(())
+  }
+
+  // the locals must match the frame of the handlers covering the instruction
+  fn check_handlers(&mut self, state: &TypeState) -> Result<()> {
+    let handlers: Vec<(usize, String)> = self
+      .exception_table
+      .iter()
+      .filter(|(start, end, _, _)| *start <= self.pc && self.pc < *end)
+      .map(|(_, _, handler, catch_type)| (*handler, catch_type.clone()))
+      .collect();
+
+    for (handler, catch_type) in handlers {
+      let frame = match self.stack_map.get(&handler) {
+        Some(frame) => frame.clone(),
+        None => return Err(self.error("Expecting a stackmap frame at exception handler")),
+      };
+
+      if !self.is_class_assignable(&catch_type, "java/lang/Throwable") {
+        return Err(self.error("Catch type is not a subclass of Throwable"));
+      }
+
+      let handler_state = TypeState {
+        locals: state.locals.clone(),
+        stack: vec![VerificationType::Object(catch_type)],
+      };
+
+      self.check_state(
+        &handler_state,
+        &frame,
+        "Stack map does not match the one at exception handler",
+      )?;
+    }
+
+    Ok(())
+  }
+
+  fn push(&self, state: &mut TypeState, value: VerificationType) {
+    state.stack.push(value);
+  }
+
+  fn pop_any(&self, state: &mut TypeState) -> Result<VerificationType> {
+    state
+      .stack
+      .pop()
+      .ok_or_else(|| self.error("Operand stack underflow"))
+  }
+
+  fn pop(
+    &mut self,
+    state: &mut TypeState,
+    expected: &VerificationType,
+  ) -> Result<VerificationType> {
+    let value = self.pop_any(state)?;
+
+    if !self.is_assignable(&value, expected) {
+      return Err(self.error(&format!(
+        "Bad type on operand stack ({} is not assignable to {})",
+        value, expected
+      )));
+    }
+
+    Ok(value)
+  }
+
+  // a reference that may not be initialized yet
+  fn pop_any_reference(&self, state: &mut TypeState) -> Result<VerificationType> {
+    let value = self.pop_any(state)?;
+
+    if !value.is_reference() {
+      return Err(self.error(&format!(
+        "Bad type on operand stack ({} is not a reference)",
+        value
+      )));
+    }
+
+    Ok(value)
+  }
+
+  fn pop_reference(&mut self, state: &mut TypeState) -> Result<VerificationType> {
+    self.pop(state, &VerificationType::object("java/lang/Object"))
+  }
+
+  // array with elements of one of the descriptors `components`, or of references if empty
+  fn pop_array(&mut self, state: &mut TypeState, components: &[&str]) -> Result<VerificationType> {
+    let array = self.pop_any(state)?;
+
+    let valid = match &array {
+      VerificationType::Null => true,
+      VerificationType::Object(name) => match name.strip_prefix('[') {
+        Some(component) if components.is_empty() => {
+          component.starts_with('L') || component.starts_with('[')
+        }
+        Some(component) => components.contains(&component),
+        None => false,
+      },
+      _ => false,
+    };
+
+    if !valid {
+      return Err(self.error(&format!(
+        "Bad type on operand stack ({} is not an array of the expected type)",
+        array
+      )));
+    }
+
+    Ok(array)
+  }
+
+  // pop values taking `words` slots on the operand stack
+  fn pop_words(&self, state: &mut TypeState, words: usize) -> Result<Vec<VerificationType>> {
+    let mut values = vec![];
+    let mut size = 0;
+
+    while size < words {
+      let value = self.pop_any(state)?;
+      size += value.size();
+      values.insert(0, value);
+    }
+
+    if size != words {
+      return Err(self.error("Bad type on operand stack (splitting a long or a double)"));
+    }
+
+    Ok(values)
+  }
+
+  fn load(&self, state: &mut TypeState, index: usize, expected: VerificationType) -> Result<()> {
+    if index + expected.size() > self.max_locals {
+      return Err(self.error("Illegal local variable number"));
+    }
+
+    let value = state.locals[index].clone();
+
+    let valid = match expected {
+      VerificationType::Object(_) => value.is_reference(),
+      _ => value == expected,
+    };
+
+    if !valid {
+      return Err(self.error(&format!(
+        "Bad local variable type ({} is not {})",
+        value, expected
+      )));
+    }
+
+    self.push(state, value);
+
+    Ok(())
+  }
+
+  fn store(&self, state: &mut TypeState, index: usize, value: VerificationType) -> Result<()> {
+    let size = value.size();
+
+    if index + size > self.max_locals {
+      return Err(self.error("Illegal local variable number"));
+    }
+
+    // the first half of a long or a double is no longer valid
+    if index > 0 && state.locals[index - 1].size() == 2 {
+      state.locals[index - 1] = VerificationType::Top;
+    }
+
+    state.locals[index] = value;
+
+    if size == 2 {
+      state.locals[index + 1] = VerificationType::Top;
+    }
+
+    Ok(())
+  }
+
+  fn store_popped(
+    &mut self,
+    state: &mut TypeState,
+    index: usize,
+    expected: VerificationType,
+  ) -> Result<()> {
+    let value = match expected {
+      VerificationType::Object(_) => self.pop_any_reference(state)?,
+      _ => self.pop(state, &expected)?,
+    };
+
+    self.store(state, index, value)?;
+
+    // a handler covering the store can be reached with the new local as well
+    self.check_handlers(state)
+  }
+
+  fn get_ldc_type(&self, index: ju2, wide: bool) -> Result<VerificationType> {
+    let t = match self.class.resolve_index(index)?.get_info() {
+      CpInfoInfoEnum::Integer(_) => VerificationType::Integer,
+      CpInfoInfoEnum::Float(_) => VerificationType::Float,
+      CpInfoInfoEnum::Long(_) => VerificationType::Long,
+      CpInfoInfoEnum::Double(_) => VerificationType::Double,
+      CpInfoInfoEnum::String(_) => VerificationType::object("java/lang/String"),
+      CpInfoInfoEnum::Class(_) => VerificationType::object("java/lang/Class"),
+      CpInfoInfoEnum::MethodType(_) => VerificationType::object("java/lang/invoke/MethodType"),
+      CpInfoInfoEnum::MethodHandle(_) => VerificationType::object("java/lang/invoke/MethodHandle"),
+      CpInfoInfoEnum::Dynamic(_) => {
+        let (_, descriptor) = self.class.resolve_name_and_type(index)?;
+
+        VerificationType::from_descriptor(&descriptor)
+      }
+      _ => return Err(self.error("Invalid index in ldc")),
+    };
+
+    if wide != (t.size() == 2) {
+      return Err(self.error("Invalid index in ldc"));
+    }
+
+    Ok(t)
+  }
+
+  // class of the object created by the `new` at `pc`
+  fn get_new_class(&self, pc: usize) -> Result<String> {
+    if self.get_opcode(pc)? != OpCode::NEW {
+      return Err(self.error("Expecting new at the offset of an uninitialized type"));
+    }
+
+    self.class.resolve_class_name(self.read_u16(pc + 1)?)
+  }
+
+  fn invoke(&mut self, state: &mut TypeState, op: OpCode) -> Result<()> {
+    let index = self.read_u16(self.pc + 1)?;
+
+    let (class, name, descriptor) = if op == OpCode::INVOKEDYNAMIC {
+      let (name, descriptor) = self.class.resolve_name_and_type(index)?;
+
+      (String::new(), name, descriptor)
+    } else {
+      self.class.resolve_method_ref(index)?
+    };
+
+    let args = parse_parameter_types(get_parameters_type_descriptor(&descriptor));
+
+    for arg in args.iter().rev() {
+      self.pop(state, &VerificationType::from_descriptor(arg))?;
+    }
+
+    match op {
+      OpCode::INVOKESPECIAL if name == "<init>" => {
+        let receiver = self.pop_any_reference(state)?;
+
+        let initialized = match &receiver {
+          VerificationType::UninitializedThis => {
+            let super_name = ClassLoader::get_binary_name(self.class.get_parent_name());
+
+            if class != self.this_name && class != super_name {
+              return Err(self.error("Bad <init> method call on uninitializedThis"));
+            }
+
+            VerificationType::object(&self.this_name)
+          }
+          VerificationType::Uninitialized(new_pc) => {
+            let new_class = self.get_new_class(*new_pc)?;
+
+            if new_class != class {
+              return Err(self.error("Bad <init> method call on an uninitialized object"));
+            }
+
+            VerificationType::Object(new_class)
+          }
+          _ => {
+            return Err(self.error(&format!(
+              "Bad type on operand stack ({} is not uninitialized)",
+              receiver
+            )))
+          }
+        };
+
+        state.initialize(&receiver, &initialized);
+      }
+      OpCode::INVOKESPECIAL | OpCode::INVOKEINTERFACE => {
+        self.pop(state, &VerificationType::Object(class))?;
+      }
+      OpCode::INVOKEVIRTUAL => {
+        let receiver = self.pop(state, &VerificationType::Object(class.clone()))?;
+
+        self.check_protected(op, &class, &name, &descriptor, &receiver)?;
+      }
+      _ => {}
+    }
+
+    let return_type = get_return_type_descriptor(&descriptor);
+
+    if return_type != "V" {
+      self.push(state, VerificationType::from_descriptor(&return_type));
+    }
+
+    Ok(())
+  }
+
+  // instruction on the local `index`, the index is on two bytes after `wide`
+  fn execute_local(&mut self, state: &mut TypeState, op: OpCode, index: usize) -> Result<()> {
+    let reference = VerificationType::object("java/lang/Object");
+
+    match op {
+      OpCode::ILOAD => self.load(state, index, VerificationType::Integer),
+      OpCode::LLOAD => self.load(state, index, VerificationType::Long),
+      OpCode::FLOAD => self.load(state, index, VerificationType::Float),
+      OpCode::DLOAD => self.load(state, index, VerificationType::Double),
+      OpCode::ALOAD => self.load(state, index, reference),
+      OpCode::ISTORE => self.store_popped(state, index, VerificationType::Integer),
+      OpCode::LSTORE => self.store_popped(state, index, VerificationType::Long),
+      OpCode::FSTORE => self.store_popped(state, index, VerificationType::Float),
+      OpCode::DSTORE => self.store_popped(state, index, VerificationType::Double),
+      OpCode::ASTORE => self.store_popped(state, index, reference),
+      OpCode::IINC => {
+        self.load(state, index, VerificationType::Integer)?;
+        self.pop_any(state)?;
+
+        Ok(())
+      }
+      _ => Err(self.error(&format!("Bad instruction: wide {}", op))),
+    }
+  }
+
+  fn check_return(&mut self, state: &mut TypeState, op: OpCode) -> Result<()> {
+    let return_type = get_return_type_descriptor(self.method.get_descriptor());
+
+    let expected = match return_type.as_str() {
+      "V" => None,
+      _ => Some(VerificationType::from_descriptor(&return_type)),
+    };
+
+    let valid = match (op, &expected) {
+      (OpCode::RETURN, None) => true,
+      (OpCode::IRETURN, Some(VerificationType::Integer))
+      | (OpCode::LRETURN, Some(VerificationType::Long))
+      | (OpCode::FRETURN, Some(VerificationType::Float))
+      | (OpCode::DRETURN, Some(VerificationType::Double))
+      | (OpCode::ARETURN, Some(VerificationType::Object(_))) => {
+        self.pop(state, expected.as_ref().unwrap())?;
+
+        true
+      }
+      _ => false,
+    };
+
+    if !valid {
+      return Err(self.error("Method expects a return value of another type"));
+    }
+
+    if self.method.get_name() == "<init>" && state.has_uninitialized_this() {
+      return Err(self.error("Constructor must call super() or this() before return"));
+    }
+
+    Ok(())
+  }
+
+  // https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.10.1.9
+  //
+  // Applies the instruction at `self.pc` on `state`, returns whether the next
+  // instruction can be reached and the branch targets
+  fn execute(&mut self, state: &mut TypeState) -> Result<(bool, Vec<usize>)> {
+    use VerificationType::{Double, Float, Integer, Long};
+
+    let pc = self.pc;
+    let op = self.get_opcode(pc)?;
+
+    match op {
+      OpCode::NOP => {}
+      OpCode::ACONSTNULL => self.push(state, VerificationType::Null),
+      OpCode::ICONSTM1
+      | OpCode::ICONST0
+      | OpCode::ICONST1
+      | OpCode::ICONST2
+      | OpCode::ICONST3
+      | OpCode::ICONST4
+      | OpCode::ICONST5
+      | OpCode::BIPUSH
+      | OpCode::SIPUSH => self.push(state, Integer),
+      OpCode::LCONST0 | OpCode::LCONST1 => self.push(state, Long),
+      OpCode::FCONST0 | OpCode::FCONST1 | OpCode::FCONST2 => self.push(state, Float),
+      OpCode::DCONST0 | OpCode::DCONST1 => self.push(state, Double),
+      OpCode::LDC => {
+        let t = self.get_ldc_type(self.read_u8(pc + 1)? as ju2, false)?;
+        self.push(state, t);
+      }
+      OpCode::LDCW => {
+        let t = self.get_ldc_type(self.read_u16(pc + 1)?, false)?;
+        self.push(state, t);
+      }
+      OpCode::LDC2W => {
+        let t = self.get_ldc_type(self.read_u16(pc + 1)?, true)?;
+        self.push(state, t);
+      }
+
+      // Locals
+      OpCode::ILOAD
+      | OpCode::LLOAD
+      | OpCode::FLOAD
+      | OpCode::DLOAD
+      | OpCode::ALOAD
+      | OpCode::ISTORE
+      | OpCode::LSTORE
+      | OpCode::FSTORE
+      | OpCode::DSTORE
+      | OpCode::ASTORE
+      | OpCode::IINC => self.execute_local(state, op, self.read_u8(pc + 1)?)?,
+      OpCode::WIDE => {
+        let op = self.get_opcode(pc + 1)?;
+        self.execute_local(state, op, self.read_u16(pc + 2)? as usize)?
+      }
+      OpCode::ILOAD0 | OpCode::ILOAD1 | OpCode::ILOAD2 | OpCode::ILOAD3 => {
+        self.load(state, (op as usize) - (OpCode::ILOAD0 as usize), Integer)?
+      }
+      OpCode::LLOAD0 | OpCode::LLOAD1 | OpCode::LLOAD2 | OpCode::LLOAD3 => {
+        self.load(state, (op as usize) - (OpCode::LLOAD0 as usize), Long)?
+      }
+      OpCode::FLOAD0 | OpCode::FLOAD1 | OpCode::FLOAD2 | OpCode::FLOAD3 => {
+        self.load(state, (op as usize) - (OpCode::FLOAD0 as usize), Float)?
+      }
+      OpCode::DLOAD0 | OpCode::DLOAD1 | OpCode::DLOAD2 | OpCode::DLOAD3 => {
+        self.load(state, (op as usize) - (OpCode::DLOAD0 as usize), Double)?
+      }
+      OpCode::ALOAD0 | OpCode::ALOAD1 | OpCode::ALOAD2 | OpCode::ALOAD3 => self.load(
+        state,
+        (op as usize) - (OpCode::ALOAD0 as usize),
+        VerificationType::object("java/lang/Object"),
+      )?,
+      OpCode::ISTORE0 | OpCode::ISTORE1 | OpCode::ISTORE2 | OpCode::ISTORE3 => {
+        self.store_popped(state, (op as usize) - (OpCode::ISTORE0 as usize), Integer)?
+      }
+      OpCode::LSTORE0 | OpCode::LSTORE1 | OpCode::LSTORE2 | OpCode::LSTORE3 => {
+        self.store_popped(state, (op as usize) - (OpCode::LSTORE0 as usize), Long)?
+      }
+      OpCode::FSTORE0 | OpCode::FSTORE1 | OpCode::FSTORE2 | OpCode::FSTORE3 => {
+        self.store_popped(state, (op as usize) - (OpCode::FSTORE0 as usize), Float)?
+      }
+      OpCode::DSTORE0 | OpCode::DSTORE1 | OpCode::DSTORE2 | OpCode::DSTORE3 => {
+        self.store_popped(state, (op as usize) - (OpCode::DSTORE0 as usize), Double)?
+      }
+      OpCode::ASTORE0 | OpCode::ASTORE1 | OpCode::ASTORE2 | OpCode::ASTORE3 => self.store_popped(
+        state,
+        (op as usize) - (OpCode::ASTORE0 as usize),
+        VerificationType::object("java/lang/Object"),
+      )?,
+
+      // Arrays
+      OpCode::IALOAD
+      | OpCode::LALOAD
+      | OpCode::FALOAD
+      | OpCode::DALOAD
+      | OpCode::BALOAD
+      | OpCode::CALOAD
+      | OpCode::SALOAD => {
+        let (components, value): (&[&str], _) = match op {
+          OpCode::IALOAD => (&["I"], Integer),
+          OpCode::LALOAD => (&["J"], Long),
+          OpCode::FALOAD => (&["F"], Float),
+          OpCode::DALOAD => (&["D"], Double),
+          OpCode::BALOAD => (&["B", "Z"], Integer),
+          OpCode::CALOAD => (&["C"], Integer),
+          _ => (&["S"], Integer),
+        };
+
+        self.pop(state, &Integer)?;
+        self.pop_array(state, components)?;
+        self.push(state, value);
+      }
+      OpCode::AALOAD => {
+        self.pop(state, &Integer)?;
+
+        let value = match self.pop_array(state, &[])? {
+          VerificationType::Object(name) => VerificationType::from_descriptor(&name[1..]),
+          _ => VerificationType::Null,
+        };
+
+        self.push(state, value);
+      }
+      OpCode::IASTORE
+      | OpCode::LASTORE
+      | OpCode::FASTORE
+      | OpCode::DASTORE
+      | OpCode::BASTORE
+      | OpCode::CASTORE
+      | OpCode::SASTORE => {
+        let (components, value): (&[&str], _) = match op {
+          OpCode::IASTORE => (&["I"], Integer),
+          OpCode::LASTORE => (&["J"], Long),
+          OpCode::FASTORE => (&["F"], Float),
+          OpCode::DASTORE => (&["D"], Double),
+          OpCode::BASTORE => (&["B", "Z"], Integer),
+          OpCode::CASTORE => (&["C"], Integer),
+          _ => (&["S"], Integer),
+        };
+
+        self.pop(state, &value)?;
+        self.pop(state, &Integer)?;
+        self.pop_array(state, components)?;
+      }
+      OpCode::AASTORE => {
+        // the type of the element is checked at run time
+        self.pop_reference(state)?;
+        self.pop(state, &Integer)?;
+        self.pop_array(state, &[])?;
+      }
+      OpCode::NEWARRAY => {
+        let array = match self.read_u8(pc + 1)? {
+          4 => "[Z",
+          5 => "[C",
+          6 => "[F",
+          7 => "[D",
+          8 => "[B",
+          9 => "[S",
+          10 => "[I",
+          11 => "[J",
+          _ => return Err(self.error("Bad array type in newarray")),
+        };
+
+        self.pop(state, &Integer)?;
+        self.push(state, VerificationType::object(array));
+      }
+      OpCode::ANEWARRAY => {
+        let component = self.class.resolve_class_name(self.read_u16(pc + 1)?)?;
+
+        // the component is an array descriptor or a class name, even without package
+        let component = if component.starts_with('[') {
+          component
+        } else {
+          format!("L{};", component)
+        };
+
+        self.pop(state, &Integer)?;
+        self.push(state, VerificationType::Object(format!("[{}", component)));
+      }
+      OpCode::MULTIANEWARRAY => {
+        let class = self.class.resolve_class_name(self.read_u16(pc + 1)?)?;
+        let dimensions = self.read_u8(pc + 3)?;
+
+        if dimensions == 0 || class.chars().take_while(|c| *c == '[').count() < dimensions {
+          return Err(self.error("Bad dimensions in multianewarray"));
+        }
+
+        for _ in 0..dimensions {
+          self.pop(state, &Integer)?;
+        }
+
+        self.push(state, VerificationType::Object(class));
+      }
+      OpCode::ARRAYLENGTH => {
+        let array = self.pop_any(state)?;
+
+        match &array {
+          VerificationType::Null => {}
+          VerificationType::Object(name) if name.starts_with('[') => {}
+          _ => {
+            return Err(self.error(&format!(
+              "Bad type on operand stack ({} is not an array)",
+              array
+            )))
+          }
+        }
+
+        self.push(state, Integer);
+      }
+
+      // Operand stack
+      OpCode::POP => {
+        self.pop_words(state, 1)?;
+      }
+      OpCode::POP2 => {
+        self.pop_words(state, 2)?;
+      }
+      OpCode::DUP
+      | OpCode::DUPX1
+      | OpCode::DUPX2
+      | OpCode::DUP2
+      | OpCode::DUP2X1
+      | OpCode::DUP2X2 => {
+        let (words, skipped) = match op {
+          OpCode::DUP => (1, 0),
+          OpCode::DUPX1 => (1, 1),
+          OpCode::DUPX2 => (1, 2),
+          OpCode::DUP2 => (2, 0),
+          OpCode::DUP2X1 => (2, 1),
+          _ => (2, 2),
+        };
+
+        let top = self.pop_words(state, words)?;
+        let under = self.pop_words(state, skipped)?;
+
+        state.stack.extend(top.iter().cloned());
+        state.stack.extend(under);
+        state.stack.extend(top);
+      }
+      OpCode::SWAP => {
+        let first = self.pop_words(state, 1)?;
+        let second = self.pop_words(state, 1)?;
+
+        state.stack.extend(first);
+        state.stack.extend(second);
+      }
+
+      // Arithmetic
+      OpCode::IADD
+      | OpCode::ISUB
+      | OpCode::IMUL
+      | OpCode::IDIV
+      | OpCode::IREM
+      | OpCode::ISHL
+      | OpCode::ISHR
+      | OpCode::IUSHR
+      | OpCode::IAND
+      | OpCode::IOR
+      | OpCode::IXOR => {
+        self.pop(state, &Integer)?;
+        self.pop(state, &Integer)?;
+        self.push(state, Integer);
+      }
+      OpCode::LADD
+      | OpCode::LSUB
+      | OpCode::LMUL
+      | OpCode::LDIV
+      | OpCode::LREM
+      | OpCode::LAND
+      | OpCode::LOR
+      | OpCode::LXOR => {
+        self.pop(state, &Long)?;
+        self.pop(state, &Long)?;
+        self.push(state, Long);
+      }
+      OpCode::LSHL | OpCode::LSHR | OpCode::LUSHR => {
+        self.pop(state, &Integer)?;
+        self.pop(state, &Long)?;
+        self.push(state, Long);
+      }
+      OpCode::FADD | OpCode::FSUB | OpCode::FMUL | OpCode::FDIV | OpCode::FREM => {
+        self.pop(state, &Float)?;
+        self.pop(state, &Float)?;
+        self.push(state, Float);
+      }
+      OpCode::DADD | OpCode::DSUB | OpCode::DMUL | OpCode::DDIV | OpCode::DREM => {
+        self.pop(state, &Double)?;
+        self.pop(state, &Double)?;
+        self.push(state, Double);
+      }
+      OpCode::INEG
+      | OpCode::LNEG
+      | OpCode::FNEG
+      | OpCode::DNEG
+      | OpCode::I2L
+      | OpCode::I2F
+      | OpCode::I2D
+      | OpCode::L2I
+      | OpCode::L2F
+      | OpCode::L2D
+      | OpCode::F2I
+      | OpCode::F2L
+      | OpCode::F2D
+      | OpCode::D2I
+      | OpCode::D2L
+      | OpCode::D2F
+      | OpCode::I2B
+      | OpCode::I2C
+      | OpCode::I2S => {
+        let (from, to) = match op {
+          OpCode::INEG | OpCode::I2B | OpCode::I2C | OpCode::I2S => (Integer, Integer),
+          OpCode::LNEG => (Long, Long),
+          OpCode::FNEG => (Float, Float),
+          OpCode::DNEG => (Double, Double),
+          OpCode::I2L => (Integer, Long),
+          OpCode::I2F => (Integer, Float),
+          OpCode::I2D => (Integer, Double),
+          OpCode::L2I => (Long, Integer),
+          OpCode::L2F => (Long, Float),
+          OpCode::L2D => (Long, Double),
+          OpCode::F2I => (Float, Integer),
+          OpCode::F2L => (Float, Long),
+          OpCode::F2D => (Float, Double),
+          OpCode::D2I => (Double, Integer),
+          OpCode::D2L => (Double, Long),
+          _ => (Double, Float),
+        };
+
+        self.pop(state, &from)?;
+        self.push(state, to);
+      }
+      OpCode::LCMP | OpCode::FCMPL | OpCode::FCMPG | OpCode::DCMPL | OpCode::DCMPG => {
+        let operand = match op {
+          OpCode::LCMP => Long,
+          OpCode::FCMPL | OpCode::FCMPG => Float,
+          _ => Double,
+        };
+
+        self.pop(state, &operand)?;
+        self.pop(state, &operand)?;
+        self.push(state, Integer);
+      }
+
+      // Control flow
+      OpCode::IFEQ
+      | OpCode::IFNE
+      | OpCode::IFLT
+      | OpCode::IFGE
+      | OpCode::IFGT
+      | OpCode::IFLE
+      | OpCode::IFICMPEQ
+      | OpCode::IFICMPNE
+      | OpCode::IFICMPLT
+      | OpCode::IFICMPGE
+      | OpCode::IFICMPGT
+      | OpCode::IFICMPLE
+      | OpCode::IFACMPEQ
+      | OpCode::IFACMPNE
+      | OpCode::IFNULL
+      | OpCode::IFNONNULL => {
+        match op {
+          OpCode::IFEQ
+          | OpCode::IFNE
+          | OpCode::IFLT
+          | OpCode::IFGE
+          | OpCode::IFGT
+          | OpCode::IFLE => {
+            self.pop(state, &Integer)?;
+          }
+          OpCode::IFACMPEQ | OpCode::IFACMPNE => {
+            self.pop_reference(state)?;
+            self.pop_reference(state)?;
+          }
+          OpCode::IFNULL | OpCode::IFNONNULL => {
+            self.pop_reference(state)?;
+          }
+          _ => {
+            self.pop(state, &Integer)?;
+            self.pop(state, &Integer)?;
+          }
+        }
+
+        let target = self.get_branch_target(self.read_u16(pc + 1)? as i16 as i64)?;
+
+        return Ok((true, vec![target]));
+      }
+      OpCode::GOTO => {
+        let target = self.get_branch_target(self.read_u16(pc + 1)? as i16 as i64)?;
+
+        return Ok((false, vec![target]));
+      }
+      OpCode::GOTOW => {
+        let target = self.get_branch_target(self.read_i32(pc + 1)? as i64)?;
+
+        return Ok((false, vec![target]));
+      }
+      // subroutines are not allowed with type checking (JVMS 4.9.1)
+      OpCode::JSR | OpCode::RET | OpCode::JSRW => {
+        return Err(self.error("Bad instruction: jsr/ret are not allowed"));
+      }
+      OpCode::TABLESWITCH | OpCode::LOOKUPSWITCH => {
+        self.pop(state, &Integer)?;
+
+        let base = Self::get_switch_base(pc);
+
+        let mut targets = vec![self.get_branch_target(self.read_i32(base)? as i64)?];
+
+        if op == OpCode::TABLESWITCH {
+          let low = self.read_i32(base + 4)? as i64;
+          let high = self.read_i32(base + 8)? as i64;
+
+          for i in 0..(high - low + 1) as usize {
+            targets.push(self.get_branch_target(self.read_i32(base + 12 + i * 4)? as i64)?);
+          }
+        } else {
+          let npairs = self.read_i32(base + 4)? as usize;
+
+          for i in 0..npairs {
+            targets.push(self.get_branch_target(self.read_i32(base + 12 + i * 8)? as i64)?);
+          }
+        }
+
+        return Ok((false, targets));
+      }
+      OpCode::IRETURN
+      | OpCode::LRETURN
+      | OpCode::FRETURN
+      | OpCode::DRETURN
+      | OpCode::ARETURN
+      | OpCode::RETURN => {
+        self.check_return(state, op)?;
+
+        return Ok((false, vec![]));
+      }
+      OpCode::ATHROW => {
+        self.pop(state, &VerificationType::object("java/lang/Throwable"))?;
+
+        return Ok((false, vec![]));
+      }
+
+      // Fields
+      OpCode::GETSTATIC | OpCode::PUTSTATIC | OpCode::GETFIELD | OpCode::PUTFIELD => {
+        let (class, name, descriptor) = self.class.resolve_field_ref(self.read_u16(pc + 1)?)?;
+        let field_type = VerificationType::from_descriptor(&descriptor);
+
+        match op {
+          OpCode::GETSTATIC => self.push(state, field_type),
+          OpCode::PUTSTATIC => {
+            self.pop(state, &field_type)?;
+          }
+          OpCode::GETFIELD => {
+            let receiver = self.pop(state, &VerificationType::Object(class.clone()))?;
+
+            self.check_protected(op, &class, &name, &descriptor, &receiver)?;
+            self.push(state, field_type);
+          }
+          _ => {
+            self.pop(state, &field_type)?;
+
+            // the fields of the class can be set before calling super()
+            let receiver = self.pop_any(state)?;
+
+            let is_uninitialized_this =
+              receiver == VerificationType::UninitializedThis && class == self.this_name;
+
+            if !is_uninitialized_this {
+              if !self.is_assignable(&receiver, &VerificationType::Object(class.clone())) {
+                return Err(self.error(&format!(
+                  "Bad type on operand stack ({} is not a valid receiver)",
+                  receiver
+                )));
+              }
+
+              self.check_protected(op, &class, &name, &descriptor, &receiver)?;
+            }
+          }
+        }
+      }
+
+      // Methods
+      OpCode::INVOKEVIRTUAL
+      | OpCode::INVOKESPECIAL
+      | OpCode::INVOKESTATIC
+      | OpCode::INVOKEINTERFACE
+      | OpCode::INVOKEDYNAMIC => self.invoke(state, op)?,
+
+      // Objects
+      OpCode::NEW => {
+        let class = self.class.resolve_class_name(self.read_u16(pc + 1)?)?;
+
+        if class.starts_with('[') {
+          return Err(self.error("Illegal use of new on an array class"));
+        }
+
+        let uninitialized = VerificationType::Uninitialized(pc);
+
+        // the object of a previous execution is not initialized
+        state.initialize(&uninitialized, &VerificationType::Top);
+
+        self.push(state, uninitialized);
+      }
+      OpCode::CHECKCAST => {
+        let class = self.class.resolve_class_name(self.read_u16(pc + 1)?)?;
+
+        self.pop_reference(state)?;
+        self.push(state, VerificationType::Object(class));
+      }
+      OpCode::INSTANCEOF => {
+        self.pop_reference(state)?;
+        self.push(state, Integer);
+      }
+      OpCode::MONITORENTER | OpCode::MONITOREXIT => {
+        self.pop_reference(state)?;
+      }
+    }
+
+    Ok((true, vec![]))
+  }
+}
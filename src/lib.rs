@@ -0,0 +1,53 @@
+//! The VM as a library: the `jvmcraft` binary, `jvmcraft-javap` and the fuzz target of the
+//! class file parser (`fuzz/`) link it, and Rust programs embed the VM through it
+//! (`JvmBuilder`).
+
+mod class_loader;
+mod native;
+mod runtime;
+mod utils;
+
+use color_eyre::eyre::Result;
+
+pub use class_loader::{
+  assembler::assemble, attributes::Code, builder::ClassBuilder, class_path::ClassPath,
+  constant_pool::ConstantPool,
+};
+pub use runtime::{
+  conversion::{FromJava, ToJava},
+  embedding::{Global, JvmBuilder},
+  errors::JavaError,
+  jvm::JVM,
+  types::Type,
+};
+
+/// Parse a class file and check its format (JVMS 4.8), as done when a class is defined
+///
+/// # Returns
+///
+/// `ClassFormatError` or `UnsupportedClassVersionError` if the class file is malformed
+pub fn parse_class_file(data: &[u8]) -> Result<()> {
+  class_loader::class_file::ClassFile::parse_from_bytes(data)?;
+
+  Ok(())
+}
+
+/// Parse a class file and write it back to bytes
+///
+/// The result is identical to `data` for any well-formed class file.
+pub fn rewrite_class_file(data: &[u8]) -> Result<Vec<u8>> {
+  let class_file = class_loader::class_file::ClassFile::parse_from_bytes(data)?;
+
+  let bytes = class_file.read().unwrap().to_bytes();
+
+  Ok(bytes)
+}
+
+/// Disassemble a class file, in the format of `javap -v -p`
+pub fn disassemble_class_file(data: &[u8]) -> Result<String> {
+  let class_file = class_loader::class_file::ClassFile::parse_from_bytes(data)?;
+
+  let text = class_file.read().unwrap().disassemble()?;
+
+  Ok(text)
+}
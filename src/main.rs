@@ -1,12 +1,6 @@
-mod class_loader;
-mod native;
-mod runtime;
-mod utils;
-
 use clap::Parser;
-use class_loader::class_file;
 use color_eyre::eyre::{eyre, Result};
-use utils::get_env;
+use jvmcraft::JvmBuilder;
 
 use std::sync::{Arc, Mutex};
 use tracing_subscriber::reload;
@@ -36,10 +30,35 @@ struct Args {
   verbose: bool,
 }
 
+/// Options of the virtual machine (`-X...`), not handled by clap
+struct VmOptions {
+  verify: bool,
+}
+
+impl VmOptions {
+  fn parse(options: &[String]) -> Result<VmOptions> {
+    let mut vm_options = VmOptions { verify: true };
+
+    for option in options {
+      match option.as_str() {
+        "-Xverify:none" => vm_options.verify = false,
+        "-Xverify:all" | "-Xverify:remote" => vm_options.verify = true,
+        _ => return Err(eyre!("[!] Unrecognized option: {}", option)),
+      }
+    }
+
+    Ok(vm_options)
+  }
+}
+
 fn main() -> Result<()> {
-  let args = Args::parse();
+  let (options, args): (Vec<String>, Vec<String>) =
+    std::env::args().partition(|arg| arg.starts_with("-X"));
+
+  let vm_options = VmOptions::parse(&options)?;
+  let args = Args::parse_from(args);
 
-  if get_env("JMODS", "").is_empty() {
+  if std::env::var("JMODS").unwrap_or_default().is_empty() {
     return Err(eyre!("[!] JMODS is not set ... aborting"));
   }
 
@@ -65,7 +84,11 @@ fn main() -> Result<()> {
 
   let handle = Arc::new(Mutex::new(reload_handle));
 
-  let mut jvm = runtime::jvm::JVM::build(&args.user_module, vec![])?;
+  // the classes loaded by the bootstrap are verified too
+  let mut jvm = JvmBuilder::new()
+    .class_path(&args.user_module)
+    .verify(vm_options.verify)
+    .build()?;
   jvm.set_logging_handle(handle);
 
   jvm.push_frame_from_class(
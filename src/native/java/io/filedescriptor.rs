@@ -1,26 +1,28 @@
-use crate::runtime::{errors, jvm::*, types};
-use color_eyre::eyre::{eyre, Result};
+use crate::{
+  native::registry::NativeRegistry,
+  runtime::{jvm::*, types},
+};
+use color_eyre::eyre::Result;
 use log::warn;
 
-impl JVM {
-  pub(crate) fn native_dispatcher_java_io_filedescriptor(
-    &mut self,
-    name: &str,
-    type_str: &str,
-  ) -> Result<Option<types::Type>> {
-    match (name, type_str) {
-      ("initIDs", "()V") => self.exec_native_filedescriptor_init_ids(),
-      ("getHandle", "(I)J") => self.exec_native_get_handle(),
-      ("getAppend", "(I)Z") => self.exec_native_get_append(),
-      ("close0", "()V") => self.exec_native_java_io_filedescriptor_close0(),
-      _ => Err(eyre!(errors::InternalError::NativeNotImplemented(
-        "java/io/FileDescriptor".to_string(),
-        name.to_owned(),
-        type_str.to_owned()
-      ))),
-    }
-  }
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  let class_name = "java/io/FileDescriptor";
 
+  registry.add(class_name, "initIDs", "()V", |jvm, _, _| {
+    jvm.exec_native_filedescriptor_init_ids()
+  });
+  registry.add(class_name, "getHandle", "(I)J", |jvm, _, _| {
+    jvm.exec_native_get_handle()
+  });
+  registry.add(class_name, "getAppend", "(I)Z", |jvm, _, _| {
+    jvm.exec_native_get_append()
+  });
+  registry.add(class_name, "close0", "()V", |jvm, _, _| {
+    jvm.exec_native_java_io_filedescriptor_close0()
+  });
+}
+
+impl JVM {
   // private static native void initIDs();
   fn exec_native_filedescriptor_init_ids(&mut self) -> Result<Option<types::Type>> {
     // No-op stub (for now)
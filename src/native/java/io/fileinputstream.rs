@@ -1,27 +1,29 @@
-use crate::runtime::{errors, jvm::*, types};
+use crate::{
+  native::registry::NativeRegistry,
+  runtime::{errors, jvm::*, types},
+};
 use color_eyre::eyre::{eyre, Result};
 use log::warn;
 use std::ffi::CString;
 
-impl JVM {
-  pub(crate) fn native_dispatcher_java_io_fileinputstream(
-    &mut self,
-    name: &str,
-    type_str: &str,
-  ) -> Result<Option<types::Type>> {
-    match (name, type_str) {
-      ("initIDs", "()V") => self.exec_native_java_io_fileinputstream_init_ids(),
-      ("open0", "(Ljava/lang/String;)V") => self.exec_native_java_io_fileinputstream_open0(),
-      ("read0", "()I") => self.exec_native_java_io_fileinputstream_read0(),
-      ("readBytes", "([BII)I") => self.exec_native_java_io_fileinputstream_read_bytes(),
-      _ => Err(eyre!(errors::InternalError::NativeNotImplemented(
-        "java/io/FileInputStream".to_string(),
-        name.to_owned(),
-        type_str.to_owned()
-      ))),
-    }
-  }
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  let class_name = "java/io/FileInputStream";
+
+  registry.add(class_name, "initIDs", "()V", |jvm, _, _| {
+    jvm.exec_native_java_io_fileinputstream_init_ids()
+  });
+  registry.add(class_name, "open0", "(Ljava/lang/String;)V", |jvm, _, _| {
+    jvm.exec_native_java_io_fileinputstream_open0()
+  });
+  registry.add(class_name, "read0", "()I", |jvm, _, _| {
+    jvm.exec_native_java_io_fileinputstream_read0()
+  });
+  registry.add(class_name, "readBytes", "([BII)I", |jvm, _, _| {
+    jvm.exec_native_java_io_fileinputstream_read_bytes()
+  });
+}
 
+impl JVM {
   fn exec_native_java_io_fileinputstream_init_ids(&mut self) -> Result<Option<types::Type>> {
     warn!("java/io/FileInputStream.initIDs not supported");
 
@@ -64,8 +66,11 @@ impl JVM {
     let fd_obj = self.heap.get_obj_instance_mut(fd_ref)?;
     let fd = fd_obj.get_field("fd")?.as_integer()?;
 
-    let array = self.heap.get_array_instance_mut(array_ref)?;
-    let array_len = array.get_elements().len();
+    let array_len = self
+      .heap
+      .get_array_instance(array_ref)?
+      .get_elements()
+      .len();
 
     if offset + read_len > array_len {
       return Err(eyre!(errors::JavaException::ArrayIndexOutOfBounds(
@@ -74,26 +79,25 @@ impl JVM {
       )));
     }
 
-    let mut buffer = vec![0u8; array_len];
-
-    let bytes_read = unsafe { libc::read(fd, buffer.as_mut_ptr() as *mut libc::c_void, array_len) };
-
-    match bytes_read.cmp(&0) {
-      std::cmp::Ordering::Less => {
-        return Err(eyre!(errors::JavaException::IO(format!(
-          "Failed to to read from {}",
-          fd
-        ))));
-      }
-      std::cmp::Ordering::Equal => {
-        let ret_value = types::Type::Integer(-1);
-        self.push_stack(ret_value)?;
-        return Ok(Some(ret_value)); // EOF
-      }
-      _ => {}
-    };
-
-    for (i, buf) in buffer.iter().enumerate().take(bytes_read as usize) {
+    let mut buffer = vec![0u8; read_len];
+
+    let bytes_read = self.stdio.read(fd, &mut buffer).map_err(|_| {
+      eyre!(errors::JavaException::IO(format!(
+        "Failed to to read from {}",
+        fd
+      )))
+    })?;
+
+    // EOF
+    if bytes_read == 0 && read_len > 0 {
+      let ret_value = types::Type::Integer(-1);
+      self.push_stack(ret_value)?;
+      return Ok(Some(ret_value));
+    }
+
+    let array = self.heap.get_array_instance_mut(array_ref)?;
+
+    for (i, buf) in buffer.iter().enumerate().take(bytes_read) {
       array.set(offset + i, types::Type::Byte(*buf as i8))?;
     }
 
@@ -118,22 +122,19 @@ impl JVM {
 
     let mut buffer = vec![0u8; 1];
 
-    let bytes_read = unsafe { libc::read(fd, buffer.as_mut_ptr() as *mut libc::c_void, 1) };
-
-    match bytes_read.cmp(&0) {
-      std::cmp::Ordering::Less => {
-        return Err(eyre!(errors::JavaException::IO(format!(
-          "Failed to to read from {}",
-          fd
-        ))));
-      }
-      std::cmp::Ordering::Equal => {
-        let ret_value = types::Type::Integer(-1);
-        self.push_stack(ret_value)?;
-        return Ok(Some(ret_value)); // EOF
-      }
-      _ => {}
-    };
+    let bytes_read = self.stdio.read(fd, &mut buffer).map_err(|_| {
+      eyre!(errors::JavaException::IO(format!(
+        "Failed to to read from {}",
+        fd
+      )))
+    })?;
+
+    // EOF
+    if bytes_read == 0 {
+      let ret_value = types::Type::Integer(-1);
+      self.push_stack(ret_value)?;
+      return Ok(Some(ret_value));
+    }
 
     let ret_value = types::Type::Integer(buffer[0] as i32);
     self.push_stack(ret_value)?;
@@ -1,2 +1,9 @@
 pub mod filedescriptor;
 pub mod fileinputstream;
+
+use crate::native::registry::NativeRegistry;
+
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  filedescriptor::register_natives(registry);
+  fileinputstream::register_natives(registry);
+}
@@ -1,68 +1,209 @@
 use color_eyre::eyre::{eyre, Result};
 use core::panic;
-use log::warn;
 
 use crate::{
+  class_loader::format_checker::MAX_MAJOR_VERSION,
+  native::registry::NativeRegistry,
   notimpl,
   runtime::{
     errors,
     jvm::*,
     types::{self, Type},
   },
-  utils::class_to_dotclass,
+  utils::{class_to_dotclass, descriptor_to_classname},
 };
 
-impl JVM {
-  pub(crate) fn native_dispatcher_java_lang_class(
-    &mut self,
-    name: &str,
-    type_str: &str,
-  ) -> Result<Option<types::Type>> {
-    match (name, type_str) {
-      ("getPrimitiveClass", "(Ljava/lang/String;)Ljava/lang/Class;") => {
-        self.exec_native_get_primitive_class()
-      }
-      ("initClassName", "()Ljava/lang/String;") => self.exec_native_init_class_name(),
-      ("isPrimitive", "()Z") => self.exec_native_is_primitive(),
-      ("isInstance", "(Ljava/lang/Object;)Z") => self.exec_native_is_instance(),
-      ("desiredAssertionStatus0", "(Ljava/lang/Class;)Z") => {
-        let ret_value = types::Type::Boolean(true);
-        self.push_stack(ret_value)?;
-        Ok(Some(ret_value))
-      }
-      ("isArray", "()Z") => self.exec_native_is_array(),
-      (
-        "forName0",
-        "(Ljava/lang/String;ZLjava/lang/ClassLoader;Ljava/lang/Class;)Ljava/lang/Class;",
-      ) => self.exec_native_for_name0(),
-      ("getDeclaredMethods0", "(Z)[Ljava/lang/reflect/Method;") => {
-        self.exec_native_get_declared_methods0()
-      }
-      ("getConstantPool", "()Ljdk/internal/reflect/ConstantPool;") => {
-        self.exec_native_get_constant_pool()
-      }
-      ("isInterface", "()Z") => self.exec_native_class_is_interface(),
-      ("getDeclaredConstructors0", "(Z)[Ljava/lang/reflect/Constructor;") => {
-        self.exec_native_get_declared_constructors0()
-      }
-      ("getModifiers", "()I") => self.exec_native_get_modifiers(),
-      ("isAssignableFrom", "(Ljava/lang/Class;)Z") => self.exec_native_is_assignable_from(),
-      ("getSuperclass", "()Ljava/lang/Class;") => self.exec_native_get_superclass(),
-      ("getDeclaredFields0", "(Z)[Ljava/lang/reflect/Field;") => {
-        self.exec_native_get_declared_fields0()
-      }
-      ("isHidden", "()Z") => self.exec_native_is_hidden(),
-      ("getEnclosingMethod0", "()[Ljava/lang/Object;") => self.exec_native_get_enclosing_method0(),
-      ("getDeclaringClass0", "()Ljava/lang/Class;") => self.exec_native_get_declaring_class0(),
-      ("getNestHost0", "()Ljava/lang/Class;") => self.exec_native_get_nest_host0(),
-      _ => Err(eyre!(errors::InternalError::NativeNotImplemented(
-        "java/lang/Class".to_string(),
-        name.to_owned(),
-        type_str.to_owned()
-      ))),
-    }
-  }
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  let class_name = "java/lang/Class";
+
+  registry.add(
+    class_name,
+    "getPrimitiveClass",
+    "(Ljava/lang/String;)Ljava/lang/Class;",
+    |jvm, _, _| jvm.exec_native_get_primitive_class(),
+  );
+  registry.add(
+    class_name,
+    "initClassName",
+    "()Ljava/lang/String;",
+    |jvm, _, _| jvm.exec_native_init_class_name(),
+  );
+  registry.add(class_name, "isPrimitive", "()Z", |jvm, _, _| {
+    jvm.exec_native_is_primitive()
+  });
+  registry.add(
+    class_name,
+    "isInstance",
+    "(Ljava/lang/Object;)Z",
+    |jvm, _, _| jvm.exec_native_is_instance(),
+  );
+  registry.add(
+    class_name,
+    "desiredAssertionStatus0",
+    "(Ljava/lang/Class;)Z",
+    |jvm, _, _| {
+      let ret_value = types::Type::Boolean(true);
+      jvm.push_stack(ret_value)?;
+      Ok(Some(ret_value))
+    },
+  );
+  registry.add(class_name, "isArray", "()Z", |jvm, _, _| {
+    jvm.exec_native_is_array()
+  });
+  registry.add(
+    class_name,
+    "forName0",
+    "(Ljava/lang/String;ZLjava/lang/ClassLoader;Ljava/lang/Class;)Ljava/lang/Class;",
+    |jvm, _, _| jvm.exec_native_for_name0(),
+  );
+  registry.add(
+    class_name,
+    "getDeclaredMethods0",
+    "(Z)[Ljava/lang/reflect/Method;",
+    |jvm, _, _| jvm.exec_native_get_declared_methods0(),
+  );
+  registry.add(
+    class_name,
+    "getConstantPool",
+    "()Ljdk/internal/reflect/ConstantPool;",
+    |jvm, _, _| jvm.exec_native_get_constant_pool(),
+  );
+  registry.add(class_name, "isInterface", "()Z", |jvm, _, _| {
+    jvm.exec_native_class_is_interface()
+  });
+  registry.add(
+    class_name,
+    "getDeclaredConstructors0",
+    "(Z)[Ljava/lang/reflect/Constructor;",
+    |jvm, _, _| jvm.exec_native_get_declared_constructors0(),
+  );
+  registry.add(class_name, "getModifiers", "()I", |jvm, _, _| {
+    jvm.exec_native_get_modifiers()
+  });
+  registry.add(
+    class_name,
+    "isAssignableFrom",
+    "(Ljava/lang/Class;)Z",
+    |jvm, _, _| jvm.exec_native_is_assignable_from(),
+  );
+  registry.add(
+    class_name,
+    "getSuperclass",
+    "()Ljava/lang/Class;",
+    |jvm, _, _| jvm.exec_native_get_superclass(),
+  );
+  registry.add(
+    class_name,
+    "getDeclaredFields0",
+    "(Z)[Ljava/lang/reflect/Field;",
+    |jvm, _, _| jvm.exec_native_get_declared_fields0(),
+  );
+  registry.add(class_name, "isHidden", "()Z", |jvm, _, _| {
+    jvm.exec_native_is_hidden()
+  });
+  registry.add(
+    class_name,
+    "getEnclosingMethod0",
+    "()[Ljava/lang/Object;",
+    |jvm, _, _| jvm.exec_native_get_enclosing_method0(),
+  );
+  registry.add(
+    class_name,
+    "getDeclaringClass0",
+    "()Ljava/lang/Class;",
+    |jvm, _, _| jvm.exec_native_get_declaring_class0(),
+  );
+  registry.add(
+    class_name,
+    "getNestHost0",
+    "()Ljava/lang/Class;",
+    |jvm, _, _| jvm.exec_native_get_nest_host0(),
+  );
+  registry.add(
+    class_name,
+    "getNestMembers0",
+    "()[Ljava/lang/Class;",
+    |jvm, _, _| jvm.exec_native_get_nest_members0(),
+  );
+  registry.add(class_name, "getRawAnnotations", "()[B", |jvm, _, _| {
+    jvm.exec_native_get_raw_class_annotations("RuntimeVisibleAnnotations")
+  });
+  registry.add(class_name, "getRawTypeAnnotations", "()[B", |jvm, _, _| {
+    jvm.exec_native_get_raw_class_annotations("RuntimeVisibleTypeAnnotations")
+  });
+  registry.add(
+    class_name,
+    "getGenericSignature0",
+    "()Ljava/lang/String;",
+    |jvm, _, _| jvm.exec_native_get_generic_signature0(),
+  );
+  registry.add(class_name, "isRecord0", "()Z", |jvm, _, _| {
+    jvm.exec_native_is_record0()
+  });
+  registry.add(
+    class_name,
+    "getRecordComponents0",
+    "()[Ljava/lang/reflect/RecordComponent;",
+    |jvm, _, _| jvm.exec_native_get_record_components0(),
+  );
+  registry.add(
+    class_name,
+    "getPermittedSubclasses0",
+    "()[Ljava/lang/Class;",
+    |jvm, _, _| jvm.exec_native_get_permitted_subclasses0(),
+  );
+  registry.add(
+    class_name,
+    "getDeclaredClasses0",
+    "()[Ljava/lang/Class;",
+    |jvm, _, _| jvm.exec_native_get_declared_classes0(),
+  );
+  registry.add(
+    class_name,
+    "getInterfaces0",
+    "()[Ljava/lang/Class;",
+    |jvm, _, _| jvm.exec_native_get_interfaces0(),
+  );
+  registry.add(
+    class_name,
+    "getSimpleBinaryName0",
+    "()Ljava/lang/String;",
+    |jvm, _, _| jvm.exec_native_get_simple_binary_name0(),
+  );
+  registry.add(
+    class_name,
+    "getProtectionDomain0",
+    "()Ljava/security/ProtectionDomain;",
+    |jvm, _, _| {
+      // classes are not defined with a protection domain
+      jvm.pop_object_ref()?;
+
+      let ret_value = types::Type::Null;
+      jvm.push_stack(ret_value)?;
+      Ok(Some(ret_value))
+    },
+  );
+  registry.add(class_name, "getClassFileVersion0", "()I", |jvm, _, _| {
+    jvm.exec_native_get_class_file_version0()
+  });
+  registry.add(class_name, "getClassAccessFlagsRaw0", "()I", |jvm, _, _| {
+    jvm.exec_native_get_class_access_flags_raw0()
+  });
+  registry.add(
+    class_name,
+    "getSigners",
+    "()[Ljava/lang/Object;",
+    |jvm, _, _| jvm.exec_native_get_signers(),
+  );
+  registry.add(
+    class_name,
+    "setSigners",
+    "([Ljava/lang/Object;)V",
+    |jvm, _, _| jvm.exec_native_set_signers(),
+  );
+}
 
+impl JVM {
   pub(crate) fn exec_native_get_primitive_class(&mut self) -> Result<Option<types::Type>> {
     // Class.getPrimitiveClass("int") -> so push `Class` ref  then the "int"
     let str_obj_ref = self.pop_stack()?;
@@ -129,11 +270,9 @@ impl JVM {
     let class_name = obj.get_classname();
 
     if class_name == "java/lang/Class" {
-      let instanceof = obj.get_field("name")?;
+      let name = self.heap.get_classname_from_class_obj(obj_ref)?;
 
-      let name = self.heap.get_string(instanceof.as_ref()?)?;
-
-      let ret_value = types::Type::Boolean(types::Type::is_primitive(&name));
+      let ret_value = types::Type::Boolean(types::Type::is_primitive(&name) || name == "V");
       self.push_stack(ret_value)?;
       return Ok(Some(ret_value));
     }
@@ -145,10 +284,16 @@ impl JVM {
 
   /// public native boolean isHidden();
   pub(crate) fn exec_native_is_hidden(&mut self) -> Result<Option<types::Type>> {
-    warn!("Hidden class not supported");
-    let _ = self.pop_object_ref()?;
+    let class_ref = self.pop_object_ref()?;
+
+    let class_name = self.heap.get_classname_from_class_obj(class_ref)?;
+
+    // primitive types and arrays are never hidden
+    let is_hidden = !types::Type::is_primitive(&class_name)
+      && !class_name.starts_with('[')
+      && self.class_loader.get(&class_name)?.is_hidden();
 
-    let ret_value = types::Type::Boolean(false);
+    let ret_value = types::Type::Boolean(is_hidden);
     self.push_stack(ret_value)?;
     Ok(Some(ret_value))
   }
@@ -267,4 +412,409 @@ impl JVM {
     self.push_stack(ret_value)?;
     Ok(Some(ret_value))
   }
+
+  /// Class objects of the classes `names` referenced by the class `context`
+  fn resolve_class_objs(&mut self, context: &str, names: &[String]) -> Result<Vec<types::Type>> {
+    let mut classes = Vec::with_capacity(names.len());
+
+    for name in names {
+      let key = self.resolve_class(context, name)?;
+
+      let class_ref = self
+        .heap
+        .get_class_instance(&mut self.class_loader, &key)?
+        .get_ref();
+
+      classes.push(types::Type::ObjectRef(class_ref));
+    }
+
+    Ok(classes)
+  }
+
+  fn alloc_class_array(&mut self, context: &str, names: &[String]) -> Result<types::Type> {
+    let classes = self.resolve_class_objs(context, names)?;
+
+    let len = classes.len();
+
+    self.heap.alloc_array("java/lang/Class", classes, len)
+  }
+
+  /// Name of the class of a `Class` object, `None` for the primitive types which have no
+  /// class file
+  fn pop_class_key(&mut self) -> Result<Option<String>> {
+    let class_name = self.pop_class_name()?;
+
+    if types::Type::is_primitive(&class_name) || class_name == "V" {
+      return Ok(None);
+    }
+
+    Ok(Some(class_name))
+  }
+
+  /// private native Class<?>[] getNestMembers0();
+  fn exec_native_get_nest_members0(&mut self) -> Result<Option<types::Type>> {
+    let class_ref = self.pop_object_ref()?;
+
+    let class_name = self.heap.get_classname_from_class_obj(class_ref)?;
+
+    // the nest host is the first member of its nest, primitive types are alone in theirs
+    let mut members = vec![types::Type::ObjectRef(class_ref)];
+
+    if !types::Type::is_primitive(&class_name) && class_name != "V" {
+      let names = self.class_loader.get(&class_name)?.get_nest_members()?;
+
+      members.extend(self.resolve_class_objs(&class_name, &names)?);
+    }
+
+    let len = members.len();
+
+    let ret_value = self.heap.alloc_array("java/lang/Class", members, len)?;
+
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+
+  /// native byte[] getRawAnnotations();
+  /// native byte[] getRawTypeAnnotations();
+  fn exec_native_get_raw_class_annotations(
+    &mut self,
+    attribute_name: &str,
+  ) -> Result<Option<types::Type>> {
+    let ret_value = match self.pop_class_key()? {
+      Some(class_name) => {
+        let raw = self
+          .class_loader
+          .get(&class_name)?
+          .get_attributes()
+          .get_raw_annotations(attribute_name)
+          .map(|raw| raw.to_vec());
+
+        match raw {
+          Some(raw) => self.heap.alloc_byte_array(&raw)?,
+          None => types::Type::Null,
+        }
+      }
+      None => types::Type::Null,
+    };
+
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+
+  /// private native String getGenericSignature0();
+  fn exec_native_get_generic_signature0(&mut self) -> Result<Option<types::Type>> {
+    let signature = match self.pop_class_key()? {
+      Some(class_name) => self
+        .class_loader
+        .get(&class_name)?
+        .get_generic_signature()?,
+      None => None,
+    };
+
+    let ret_value = match signature {
+      Some(signature) => self.heap.alloc_string(&mut self.class_loader, &signature)?,
+      None => types::Type::Null,
+    };
+
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+
+  /// private native boolean isRecord0();
+  fn exec_native_is_record0(&mut self) -> Result<Option<types::Type>> {
+    let is_record = match self.pop_class_key()? {
+      Some(class_name) => self.class_loader.get(&class_name)?.is_record(),
+      None => false,
+    };
+
+    let ret_value = types::Type::Boolean(is_record);
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+
+  /// private native RecordComponent[] getRecordComponents0();
+  fn exec_native_get_record_components0(&mut self) -> Result<Option<types::Type>> {
+    let Some(class_name) = self.pop_class_key()? else {
+      let ret_value = types::Type::Null;
+      self.push_stack(ret_value)?;
+      return Ok(Some(ret_value));
+    };
+
+    // name, descriptor and attributes of each component
+    let components = {
+      let class = self.class_loader.get(&class_name)?;
+
+      match class.get_record().filter(|_| class.is_record()) {
+        Some(record) => {
+          let mut components = vec![];
+
+          for component in record.get_components() {
+            components.push((
+              class.resolve_name(component.get_name_index())?,
+              class.resolve_name(component.get_descriptor_index())?,
+              component.get_attributes().clone(),
+            ));
+          }
+
+          Some(components)
+        }
+        None => None,
+      }
+    };
+
+    let Some(components) = components else {
+      let ret_value = types::Type::Null;
+      self.push_stack(ret_value)?;
+      return Ok(Some(ret_value));
+    };
+
+    let class_ref = self
+      .heap
+      .get_class_instance(&mut self.class_loader, &class_name)?
+      .get_ref();
+
+    let mut component_objs = vec![];
+
+    for (name, descriptor, attributes) in components {
+      let type_name = descriptor_to_classname(&descriptor);
+
+      let type_key = if types::Type::is_primitive(&type_name) {
+        type_name
+      } else {
+        self.resolve_class(&class_name, &type_name)?
+      };
+
+      let type_ref = self
+        .heap
+        .get_class_instance(&mut self.class_loader, &type_key)?
+        .get_ref();
+
+      // the accessor has the name of the component
+      let accessor = {
+        let class = self.class_loader.get(&class_name)?;
+
+        class
+          .get_methods()
+          .iter()
+          .find(|m| m.get_name() == name && m.get_descriptor() == format!("(){}", descriptor))
+          .cloned()
+      };
+
+      let accessor = match accessor {
+        Some(method) => {
+          self
+            .heap
+            .alloc_reflect_method(&mut self.class_loader, &class_name, &method)?
+        }
+        None => types::Type::Null,
+      };
+
+      let signature = match attributes.get_signature() {
+        Some(signature) => {
+          let signature = self
+            .class_loader
+            .get(&class_name)?
+            .resolve_name(signature.get_signature_index())?;
+
+          self.heap.alloc_string(&mut self.class_loader, &signature)?
+        }
+        None => types::Type::Null,
+      };
+
+      let name = self.heap.alloc_string(&mut self.class_loader, &name)?;
+
+      let component_ref = self
+        .heap
+        .alloc_obj(&mut self.class_loader, "java/lang/reflect/RecordComponent")?
+        .as_ref()?;
+
+      let component = self.heap.get_obj_instance_mut(component_ref)?;
+
+      component.put_field("clazz", types::Type::ObjectRef(class_ref))?;
+      component.put_field("name", name)?;
+      component.put_field("type", types::Type::ObjectRef(type_ref))?;
+      component.put_field("accessor", accessor)?;
+      component.put_field("signature", signature)?;
+
+      self.heap.put_raw_annotations(
+        component_ref,
+        &attributes,
+        &[
+          ("annotations", "RuntimeVisibleAnnotations"),
+          ("typeAnnotations", "RuntimeVisibleTypeAnnotations"),
+        ],
+      )?;
+
+      component_objs.push(types::Type::ObjectRef(component_ref));
+    }
+
+    let len = component_objs.len();
+
+    let ret_value =
+      self
+        .heap
+        .alloc_array("java/lang/reflect/RecordComponent", component_objs, len)?;
+
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+
+  /// private native Class<?>[] getPermittedSubclasses0();
+  fn exec_native_get_permitted_subclasses0(&mut self) -> Result<Option<types::Type>> {
+    let permitted = match self.pop_class_key()? {
+      Some(class_name) => {
+        let permitted = self
+          .class_loader
+          .get(&class_name)?
+          .get_permitted_subclasses()?;
+
+        permitted.map(|permitted| (class_name, permitted))
+      }
+      None => None,
+    };
+
+    let ret_value = match permitted {
+      Some((class_name, permitted)) => {
+        // subclasses that can not be loaded are left out
+        let mut classes = vec![];
+
+        for subclass in permitted {
+          if let Ok(key) = self.resolve_class(&class_name, &subclass) {
+            if self.class_loader.get(&key).is_ok() {
+              classes.push(key);
+            }
+          }
+        }
+
+        self.alloc_class_array(&class_name, &classes)?
+      }
+      None => types::Type::Null,
+    };
+
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+
+  /// private native Class<?>[] getDeclaredClasses0();
+  fn exec_native_get_declared_classes0(&mut self) -> Result<Option<types::Type>> {
+    let ret_value = match self.pop_class_key()? {
+      Some(class_name) => {
+        let declared = self.class_loader.get(&class_name)?.get_declared_classes()?;
+
+        self.alloc_class_array(&class_name, &declared)?
+      }
+      None => self.heap.alloc_array("java/lang/Class", vec![], 0)?,
+    };
+
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+
+  /// private native Class<?>[] getInterfaces0();
+  fn exec_native_get_interfaces0(&mut self) -> Result<Option<types::Type>> {
+    let ret_value = match self.pop_class_key()? {
+      Some(class_name) => {
+        let interfaces = self.class_loader.get(&class_name)?.get_interfaces().clone();
+
+        self.alloc_class_array(&class_name, &interfaces)?
+      }
+      None => self.heap.alloc_array("java/lang/Class", vec![], 0)?,
+    };
+
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+
+  /// private native String getSimpleBinaryName0();
+  fn exec_native_get_simple_binary_name0(&mut self) -> Result<Option<types::Type>> {
+    let simple_name = match self.pop_class_key()? {
+      Some(class_name) => self
+        .class_loader
+        .get(&class_name)?
+        .get_simple_binary_name()?,
+      None => None,
+    };
+
+    let ret_value = match simple_name {
+      Some(simple_name) => self
+        .heap
+        .alloc_string(&mut self.class_loader, &simple_name)?,
+      None => types::Type::Null,
+    };
+
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+
+  /// private native int getClassFileVersion0();
+  fn exec_native_get_class_file_version0(&mut self) -> Result<Option<types::Type>> {
+    let class_name = self.pop_class_name()?;
+
+    // arrays have the version of their element type
+    let element_name = class_name.trim_start_matches('[');
+
+    let element_name = match element_name.strip_prefix('L') {
+      Some(name) => name.trim_end_matches(';'),
+      None => element_name,
+    };
+
+    // primitive types have the latest version
+    let version = if types::Type::is_primitive(element_name) || element_name == "V" {
+      MAX_MAJOR_VERSION as i32
+    } else {
+      let class = self.class_loader.get(element_name)?;
+
+      ((class.get_minor_version() as i32) << 16) | class.get_major_version() as i32
+    };
+
+    let ret_value = types::Type::Integer(version);
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+
+  /// private native int getClassAccessFlagsRaw0();
+  fn exec_native_get_class_access_flags_raw0(&mut self) -> Result<Option<types::Type>> {
+    let access_flags = match self.pop_class_key()? {
+      Some(class_name) => self.class_loader.get(&class_name)?.get_access_flags() as i32,
+      None => 0,
+    };
+
+    let ret_value = types::Type::Integer(access_flags);
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+
+  /// native Object[] getSigners();
+  fn exec_native_get_signers(&mut self) -> Result<Option<types::Type>> {
+    let class_ref = self.pop_object_ref()?;
+
+    // the signers are kept in a synthetic field of the Class object
+    let signers = self
+      .heap
+      .get_obj_instance(class_ref)?
+      .get_field("signers")
+      .unwrap_or(types::Type::Null);
+
+    let ret_value = match signers {
+      types::Type::ArrayRef(array_ref) => self.heap.clone_instance(array_ref)?,
+      _ => types::Type::Null,
+    };
+
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+
+  /// native void setSigners(Object[] signers);
+  fn exec_native_set_signers(&mut self) -> Result<Option<types::Type>> {
+    let signers = self.pop_stack()?;
+
+    let class_ref = self.pop_object_ref()?;
+
+    self
+      .heap
+      .get_obj_instance_mut(class_ref)?
+      .new_field("signers", signers)?;
+
+    Ok(None)
+  }
 }
@@ -1,42 +1,75 @@
 use crate::{
-  class_loader::class_file::ClassFile,
+  class_loader::namespace::BOOTSTRAP_LOADER,
+  native::registry::NativeRegistry,
   runtime::{
+    constants::{HIDDEN_CLASS, NESTMATE_CLASS},
     errors,
     jvm::*,
     types::{self},
   },
+  utils::*,
 };
 use color_eyre::eyre::{eyre, Result};
-use log::warn;
+use log::debug;
+
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  let class_name = "java/lang/ClassLoader";
+
+  registry.add(
+    class_name,
+    "findBootstrapClass",
+    "(Ljava/lang/String;)Ljava/lang/Class;",
+    |jvm, _, _| jvm.exec_native_find_bootstrap_class(),
+  );
+  registry.add(
+    class_name,
+    "findLoadedClass0",
+    "(Ljava/lang/String;)Ljava/lang/Class;",
+    |jvm, _, _| jvm.exec_native_find_loaded_class0(),
+  );
+  registry.add(class_name, "defineClass0", "(Ljava/lang/ClassLoader;Ljava/lang/Class;Ljava/lang/String;[BIILjava/security/ProtectionDomain;ZILjava/lang/Object;)Ljava/lang/Class;", |jvm, _, _| {
+        jvm.exec_native_define_class0()
+      });
+  registry.add(class_name, "defineClass1", "(Ljava/lang/ClassLoader;Ljava/lang/String;[BIILjava/security/ProtectionDomain;Ljava/lang/String;)Ljava/lang/Class;", |jvm, _, _| {
+        jvm.exec_native_define_class1()
+      });
+}
 
 impl JVM {
-  pub(crate) fn native_dispatcher_java_lang_classloader(
-    &mut self,
-    name: &str,
-    type_str: &str,
-  ) -> Result<Option<types::Type>> {
-    match (name, type_str) {
-      ("findBootstrapClass", "(Ljava/lang/String;)Ljava/lang/Class;") => {
-        self.exec_native_find_bootstrap_class()
-      }
-      ("defineClass0", "(Ljava/lang/ClassLoader;Ljava/lang/Class;Ljava/lang/String;[BIILjava/security/ProtectionDomain;ZILjava/lang/Object;)Ljava/lang/Class;") => {
-        self.exec_native_define_class0()
+  // private static native Class<?> findBootstrapClass(String name);
+  fn exec_native_find_bootstrap_class(&mut self) -> Result<Option<types::Type>> {
+    let str_ref = self.pop_object_ref()?; // the string instance
+    let classname = dotclass_to_class(&self.heap.get_string(str_ref)?);
+
+    // a class the bootstrap loader can not find is reported as null
+    let ret_value = match self.class_loader.get_lock(&classname) {
+      Ok(_) => self
+        .heap
+        .alloc_class_obj(&mut self.class_loader, &classname)?,
+      Err(err) => {
+        debug!("Bootstrap class {} not found: {}", classname, err);
+
+        types::Type::Null
       }
-      _ => Err(eyre!(errors::InternalError::NativeNotImplemented(
-        "java/lang/ClassLoader".to_string(),
-        name.to_owned(),
-        type_str.to_owned()
-      ))),
-    }
+    };
+
+    self.push_stack(ret_value)?;
+
+    Ok(Some(ret_value))
   }
 
-  fn exec_native_find_bootstrap_class(&mut self) -> Result<Option<types::Type>> {
-    warn!("java/lang/ClassLoader.findBootstrapClass returns Null (system-level class loader)");
-    let str_ref = self.pop_object_ref()?; // the string instance
-    let classname = self.heap.get_string(str_ref)?;
-    self.init_class(&classname)?;
+  // private final native Class<?> findLoadedClass0(String name);
+  fn exec_native_find_loaded_class0(&mut self) -> Result<Option<types::Type>> {
+    let name_ref = self.pop_object_ref()?;
+    let loader = self.pop_object_ref()?; // this
+
+    let name = self.heap.get_string(name_ref)?;
+
+    let ret_value = match self.class_loader.find_loaded_class(loader, &name) {
+      Some(key) => self.heap.alloc_class_obj(&mut self.class_loader, &key)?,
+      None => types::Type::Null,
+    };
 
-    let ret_value = types::Type::Null;
     self.push_stack(ret_value)?;
 
     Ok(Some(ret_value))
@@ -52,40 +85,39 @@ impl JVM {
   //                                Object classData);
   //
   fn exec_native_define_class0(&mut self) -> Result<Option<types::Type>> {
-    warn!("java/lang/ClassLoader.defineClass0 not fully implemented");
-
-    let _class_data = self.pop_ref()?; // may be null
-    let _flags = self.pop_ioperand()?;
+    let class_data = self.pop_stack()?; // may be null
+    let flags = self.pop_ioperand()?;
     let initialize = self.pop_stack()?.as_bool()?;
     let _protection_domain = self.pop_ref()?; // may be null
-    let len = self.pop_ioperand()? as usize;
-    let off = self.pop_ioperand()? as usize;
-    let _byte_array_ref = self.pop_array_ref()?;
+    let len = self.pop_ioperand()?;
+    let off = self.pop_ioperand()?;
+    let byte_array_ref = self.pop_array_ref()?;
     let name_ref = self.pop_ref()?;
-    let _lookup = self.pop_ref()?; // context class, may be null
+    let lookup = self.pop_stack()?; // context class, may be null
     let class_loader = self.pop_stack()?; // may be null
 
     let name = self.heap.get_string(name_ref)?;
+    let data = self.get_class_bytes(byte_array_ref, off, len)?;
 
-    let mut data = vec![];
-    let byte_array = self.heap.get_array_instance(_byte_array_ref)?;
-    for el in byte_array.get_elements() {
-      let b = el.as_byte()?;
-      data.push(b as u8);
-    }
+    let loader = self.get_loader_ref(class_loader)?;
 
-    let new_class_file = ClassFile::parse_from_bytes(&data[off..(off + len)])?;
+    let key = if flags & HIDDEN_CLASS != 0 {
+      let lookup = match lookup {
+        types::Type::ObjectRef(lookup_ref) => self.heap.get_classname_from_class_obj(lookup_ref)?,
+        _ => return Err(eyre!(errors::JavaException::NullPointer)),
+      };
 
-    self
-      .class_loader
-      .add_class_file("unnamed", &name, new_class_file)?;
+      self.define_hidden_class(loader, &lookup, &data, flags & NESTMATE_CLASS != 0)?
+    } else {
+      self.define_class(loader, Some(&name), &data)?
+    };
 
-    let new_class_obj = self.get_class_instance_mut(&name)?;
-    new_class_obj.new_field("classLoader", class_loader)?;
+    let new_class_obj = self.get_class_instance_mut(&key)?;
+    new_class_obj.put_field("classData", class_data)?;
     let new_class_ref = new_class_obj.get_ref();
 
     if initialize {
-      self.init_class(&name)?;
+      self.init_class(&key)?;
     }
 
     let ret_value = types::Type::ObjectRef(new_class_ref);
@@ -93,4 +125,69 @@ impl JVM {
 
     Ok(Some(ret_value))
   }
+
+  //static native Class<?> defineClass1(ClassLoader loader, String name, byte[] b, int off, int len,
+  //                                    ProtectionDomain pd, String source);
+  fn exec_native_define_class1(&mut self) -> Result<Option<types::Type>> {
+    let _source = self.pop_ref()?; // may be null
+    let _protection_domain = self.pop_ref()?; // may be null
+    let len = self.pop_ioperand()?;
+    let off = self.pop_ioperand()?;
+    let byte_array_ref = self.pop_array_ref()?;
+    let name = self.pop_stack()?; // may be null
+    let class_loader = self.pop_stack()?; // may be null
+
+    let name = match name {
+      types::Type::ObjectRef(name_ref) => Some(self.heap.get_string(name_ref)?),
+      _ => None,
+    };
+    let data = self.get_class_bytes(byte_array_ref, off, len)?;
+
+    let loader = self.get_loader_ref(class_loader)?;
+
+    let key = self.define_class(loader, name.as_deref(), &data)?;
+
+    let ret_value = types::Type::ObjectRef(self.get_class_instance_mut(&key)?.get_ref());
+    self.push_stack(ret_value)?;
+
+    Ok(Some(ret_value))
+  }
+
+  // the bootstrap class loader is null
+  fn get_loader_ref(&self, class_loader: types::Type) -> Result<ju4> {
+    match class_loader {
+      types::Type::ObjectRef(loader_ref) => Ok(loader_ref),
+      types::Type::Null => Ok(BOOTSTRAP_LOADER),
+      _ => Err(eyre!(errors::InternalError::WrongType(
+        "ObjectRef",
+        class_loader
+      ))),
+    }
+  }
+
+  // the `len` bytes of the array from `off`
+  fn get_class_bytes(&self, array_ref: ju4, off: i32, len: i32) -> Result<Vec<u8>> {
+    let byte_array = self.heap.get_array_instance(array_ref)?;
+    let elements = byte_array.get_elements();
+
+    let range = usize::try_from(off)
+      .ok()
+      .zip(usize::try_from(len).ok())
+      .and_then(|(start, len)| Some(start..start.checked_add(len)?))
+      .filter(|range| range.end <= elements.len())
+      .ok_or_else(|| {
+        eyre!(errors::JavaException::IndexOutOfBounds(format!(
+          "Range [{}, {} + {}) out of bounds for length {}",
+          off,
+          off,
+          len,
+          elements.len()
+        )))
+      })?;
+
+    elements[range]
+      .iter()
+      .map(|el| Ok(el.as_byte()? as u8))
+      .collect()
+  }
 }
@@ -1,6 +1,6 @@
 use crate::{
   class_loader::class_file::MethodHandleResolved,
-  notimpl,
+  native::registry::NativeRegistry,
   runtime::{
     constants::*,
     errors,
@@ -13,7 +13,36 @@ use crate::{
   },
 };
 use color_eyre::eyre::{eyre, Result};
-use log::{debug, error, warn};
+use log::{debug, warn};
+
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  let class_name = "java/lang/invoke/MethodHandle";
+
+  // signature polymorphic, the descriptor is the type of the call site
+
+  registry.add_polymorphic(class_name, "invokeBasic", |jvm, _, type_str| {
+    jvm.exec_native_invoke_basic(type_str)
+  });
+  registry.add_polymorphic(class_name, "invokeExact", |jvm, _, type_str| {
+    jvm.exec_native_invoke_exact(type_str)
+  });
+  registry.add_polymorphic(class_name, "invoke", |jvm, _, type_str| {
+    jvm.exec_native_invoke(type_str)
+  });
+  for name in [
+    "linkToStatic",
+    "linkToSpecial",
+    "linkToVirtual",
+    "linkToInterface",
+  ] {
+    registry.add_polymorphic(class_name, name, |jvm, _, type_str| {
+      jvm.exec_native_link_to(type_str)
+    });
+  }
+  registry.add_polymorphic(class_name, "linkToNative", |jvm, _, type_str| {
+    jvm.exec_native_link_to_native(type_str)
+  });
+}
 
 impl JVM {
   pub(crate) fn resolve_method_type_descriptor(
@@ -28,18 +57,6 @@ impl JVM {
     )
   }
 
-  pub(crate) fn resolve_member_name_type_descriptor(
-    &mut self,
-    member_name_ref: ju4,
-  ) -> Result<types::Type> {
-    self.call_and_resolve_method(
-      "java/lang/invoke/MemberName",
-      "getMethodDescriptor",
-      "()Ljava/lang/String;",
-      vec![types::Type::ObjectRef(member_name_ref)],
-    )
-  }
-
   pub(crate) fn get_member_name_index_and_flags(
     &mut self,
     classname: &str,
@@ -81,440 +98,6 @@ impl JVM {
     }
   }
 
-  pub(crate) fn native_dispatcher_java_lang_invoke_methodhandle(
-    &mut self,
-    name: &str,
-    type_str: &str,
-  ) -> Result<Option<types::Type>> {
-    match (name, type_str) {
-      (
-        "invokeBasic",
-        _, // PolymorphicSignature
-      ) => self.exec_native_invoke_basic(type_str),
-      (
-        "invokeExact",
-        _, // PolymorphicSignature
-      ) => self.exec_native_invoke_exact(type_str),
-      (
-        "invokeVirtual",
-        _, // PolymorphicSignature
-      ) => self.exec_native_invoke_virtual(type_str),
-
-      _ => Err(eyre!(errors::InternalError::NativeNotImplemented(
-        "java/lang/invoke/MethodHandle".to_string(),
-        name.to_owned(),
-        type_str.to_owned()
-      ))),
-    }
-  }
-
-  // For simple cases (e.g. findStatic) the method can be direcly called
-  // For more complex ones (e.g. adapters) a LambdaForm is generated
-  fn resolve_direct_method_handle(
-    &mut self,
-    method_handle: &types::ObjectInstance,
-  ) -> Result<(String, String, String, i32)> {
-    let member_name_ref = method_handle.get_field("member")?.as_ref()?;
-    let member_name = self.heap.get_obj_instance(member_name_ref)?;
-
-    let method_name_ref = member_name.get_field("name")?.as_ref()?;
-    let method_name = self.heap.get_string(method_name_ref)?;
-
-    let method_clazz_ref = member_name.get_field("clazz")?.as_ref()?;
-    let method_clazz_name = self.heap.get_classname_from_class_obj(method_clazz_ref)?;
-
-    debug!(
-      "DIRECT METHOD HANDLE {} {} {}",
-      member_name, method_clazz_name, method_name
-    );
-
-    let method_flags = member_name.get_field("flags")?.as_integer()?;
-
-    let method_type_str = if Constants::is_method(method_flags) {
-      let method_type_ref = method_handle.get_field("type")?.as_ref()?;
-      let method_type_str_ref = self
-        .resolve_method_type_descriptor(method_type_ref)?
-        .as_ref()?;
-
-      self.heap.get_string(method_type_str_ref)?
-    } else {
-      panic!();
-    };
-
-    // it could refer to a method or a field (getter)
-
-    // TODO: better way to get it ? maybe avoiding mutability on self
-
-    Ok((
-      method_clazz_name,
-      method_name,
-      method_type_str,
-      method_flags,
-    ))
-  }
-
-  fn call_direct_method_handle(
-    &mut self,
-    method_handle: &types::ObjectInstance,
-    args: &mut Vec<types::Type>,
-  ) -> Result<types::Type> {
-    let member_name_ref = method_handle.get_field("member")?.as_ref()?;
-
-    let method_type_str_ref = self
-      .resolve_member_name_type_descriptor(member_name_ref)?
-      .as_ref()?;
-    let method_type_str = self.heap.get_string(method_type_str_ref)?.clone();
-
-    let member_name = self.heap.get_obj_instance(member_name_ref)?;
-
-    let method_name_ref = member_name.get_field("name")?.as_ref()?;
-    let method_name = self.heap.get_string(method_name_ref)?;
-
-    let method_clazz_ref = member_name.get_field("clazz")?.as_ref()?;
-    let method_clazz_name = self.heap.get_classname_from_class_obj(method_clazz_ref)?;
-
-    let method_flags = member_name.get_field("flags")?.as_integer()?;
-
-    debug!(
-      "CALL DIRECT METHOD HANDLE {} {} {} {}",
-      member_name, method_clazz_name, method_name, method_flags
-    );
-
-    // it could refer to a method or a field (getter)
-
-    match method_flags {
-      // treat as a method
-      _ if Constants::is_method(method_flags) => {
-        if JVM::is_method_handle_new_invoke_special(method_flags) {
-          // we need to push this
-          let this_ref = self
-            .heap
-            .alloc_obj(&mut self.class_loader, &method_clazz_name)?;
-          // `args` is already ordered
-          // replace the first element of `args` with a new object
-          args[0] = this_ref;
-        } else {
-          // we have the method handle reference has first
-          // element of `args`, but in this case
-          // it is not expected
-          args.remove(0);
-        }
-
-        // call the method
-        let return_value = self.call_and_resolve_method(
-          &method_clazz_name,
-          &method_name,
-          &method_type_str,
-          args.to_vec(),
-        )?;
-
-        // if new invoke special => return it self
-        let ret_value = if JVM::is_method_handle_new_invoke_special(method_flags) {
-          *args.first().unwrap()
-        } else {
-          return_value
-        };
-        Ok(ret_value)
-      }
-      _ if Constants::is_field(method_flags) => {
-        error!("Field in DirectMethodHandle not supported yet");
-        Err(eyre!(errors::InternalError::NotImplemented))
-      }
-      _ if Constants::is_constructor(method_flags) => {
-        let this_ref = self
-          .heap
-          .alloc_obj(&mut self.class_loader, &method_clazz_name)?;
-        // `args` is already ordered
-        // replace the first element of `args` with a new object
-        args[0] = this_ref;
-
-        // the method type is not the one in the member name
-        // since that one contains the return type as Ljava/lang/Object
-        // while <init> has no return (V)
-
-        // contains only the arguments of <init>
-        let parameters_type_str = get_parameters_type_descriptor(&method_type_str);
-        let init_method_type_str = format!("({})V", parameters_type_str);
-
-        // call the method
-        self.call_and_resolve_method(
-          &method_clazz_name,
-          &method_name,
-          &init_method_type_str,
-          args.to_vec(),
-        )?;
-
-        Ok(*args.first().unwrap())
-      }
-      _ => notimpl!(),
-    }
-  }
-
-  fn call_bound_method_handle(
-    &mut self,
-    method_handle: &types::ObjectInstance,
-    args: &mut [types::Type],
-  ) -> Result<types::Type> {
-    self.interpret_lambda_form(method_handle, args)
-  }
-
-  fn interpret_lambda_form(
-    &mut self,
-    method_handle: &types::ObjectInstance,
-    args: &[types::Type],
-  ) -> Result<types::Type> {
-    // mini-interpreter for lambdaform
-
-    let lambdaform_ref = method_handle.get_field("form")?.as_ref()?;
-    let lambdaform = self.heap.get_obj_instance(lambdaform_ref)?;
-
-    // 1. get the names
-    let names_ref = lambdaform.get_field("names")?.as_ref()?;
-    let names = self.heap.get_array_instance(names_ref)?.clone();
-
-    // 2. prepare the frame
-    let arity = lambdaform.get_field("arity")?.as_integer()? as usize;
-    let mut guard_arity = arity;
-    let mut values = vec![types::Type::None; names.len()];
-    let mut category2_arguments = 0;
-    let mut i = 0;
-    let mut values_i = 0;
-    while i < guard_arity {
-      // name.index refers to the index in the arguments array
-      // it does not know that in stack long/double occupy two slots
-      values[values_i] = args[i];
-      // get long/double
-      if args[i].get_category() == 2 {
-        category2_arguments += 1;
-        // increment arity by one so that this cycle gather all args correctly
-        guard_arity += 1;
-        i += 1;
-      }
-      i += 1;
-      values_i += 1;
-    }
-    // we double count them in the previous loop
-    category2_arguments /= 2;
-    debug!("ARITY {} - ARGS {}", arity, args.len());
-    assert!(args.len() == guard_arity - category2_arguments);
-
-    debug!("NAMES {}", names.len());
-    debug!("OBJ {}", method_handle);
-    debug!("VALUES {:?}", values);
-
-    // 3. interpret
-    for i in (arity..names.len()).step_by(1) {
-      let name_ref = names.get(i)?.as_ref()?; // argL*
-      let name = self.heap.get_obj_instance(name_ref)?;
-
-      let arguments_ref = name.get_field("arguments")?.as_ref()?;
-      let arguments = self.heap.get_array_instance(arguments_ref)?;
-
-      let function_ref = name.get_field("function")?.as_ref()?;
-      let function = self.heap.get_obj_instance(function_ref)?;
-
-      let member_ref = function.get_field("member")?.as_ref()?;
-      let member = self.heap.get_obj_instance(member_ref)?;
-
-      let member_name_ref = member.get_field("name")?.as_ref()?;
-      let member_name_str = self.heap.get_string(member_name_ref)?;
-
-      let member_type_ref = member.get_field("type")?.as_ref()?;
-      let member_type = self.heap.get_obj_instance(member_type_ref)?;
-
-      let member_class_ref = member.get_field("clazz")?.as_ref()?;
-      let member_class_name_str = self.heap.get_classname_from_class_obj(member_class_ref)?;
-
-      let mut invoke_arguments_array = vec![];
-
-      for arg in arguments.get_elements() {
-        let new_arg = match *arg {
-          types::Type::Integer(_) => panic!(),
-          types::Type::ObjectRef(obj_ref) => {
-            let obj = self.heap.get_obj_instance(obj_ref)?;
-            // then it must be of class java/lang/invoke/LambdaForm$Name
-            // and I use the `index` field to get the correct argument out of `values`
-            let index = obj.get_field("index")?.as_integer()? as usize;
-            debug!("index {} {:?}", index, values.get(index));
-            *values.get(index).unwrap() // TODO: avoid unwrap
-          }
-          _ => panic!(),
-        };
-        invoke_arguments_array.push(new_arg);
-        // dont care if long/double since `args` is already correctly formatted
-        // e.g. long/double already occupy two slots
-        // // in case the arg is long/double it counts as two arguments
-        if new_arg.get_category() == 2 {
-          invoke_arguments_array.push(new_arg);
-        }
-      }
-      debug!(
-        "NAME {} {}.{} {} -> {:?}",
-        function.get_classname(),
-        member_class_name_str,
-        member_name_str,
-        member_type,
-        invoke_arguments_array
-      );
-
-      // TODO: there are synthetic methods to access fields
-      // TODO: such as argL0, argL1, argL2, argL3, ...
-      // TODO: I did not find a good solution for them
-      // TODO: so for now I will treat them manually
-      let member_name_str_ptr: &str = &member_name_str;
-      let res = match member_name_str_ptr {
-        _ if member_name_str_ptr.starts_with("arg") => {
-          // im handling argL* manually, so the method handle is the first
-          // element of the arguments
-          let current_method_handle_ref = invoke_arguments_array[0].as_ref()?;
-          let current_method_handle = self.heap.get_obj_instance(current_method_handle_ref)?;
-          current_method_handle.get_field(&member_name_str)?
-        }
-        "invokeBasic" => {
-          // this is most likely just a real method
-          // for instance we could have a invokeBasic
-          let mut args_type: String = "".to_owned();
-          // skip the first argument since it is the `this`
-          let mut i = 1;
-          while i < invoke_arguments_array.len() {
-            let arg = &invoke_arguments_array[i];
-            debug!("Field of {} : {}", i, arg);
-
-            let arg_type = arg.get_type(&self.heap);
-            args_type = format!("{}{}", args_type, arg_type);
-
-            // skip it if long/doable
-            if arg.get_category() == 2 {
-              i += 1;
-            }
-
-            i += 1; // Manual control over increment
-          }
-          let descriptor = format!("({})Ljava/lang/Object", args_type);
-          self.call_and_resolve_method(
-            &member_class_name_str,
-            &member_name_str,
-            &descriptor,
-            invoke_arguments_array,
-          )?
-        }
-        _ => panic!(),
-      };
-      values[i] = res;
-      debug!("RES {} : {}", i, res);
-    }
-
-    let lambdaform = self.heap.get_obj_instance(lambdaform_ref)?;
-    let result_index = lambdaform.get_field("result")?.as_integer()? as usize;
-    debug!("LAMBDAFORM RESULT {}", values[result_index]);
-    Ok(values[result_index])
-  }
-
-  fn resolve_bound_method_handle(
-    &mut self,
-    method_handle: &types::ObjectInstance,
-  ) -> Result<(String, String, String, i32)> {
-    // BoundMethodHandle has the arguments
-    // LambdaForm tells how to use the arguments
-    // get the lambda form and invokeBasic using argL0, argL1, ...
-
-    let arg_l0_ref = method_handle.get_field("argL0")?.as_ref()?;
-    let arg_l0 = self.heap.get_obj_instance(arg_l0_ref)?.clone();
-    assert!(arg_l0.get_classname() == "java/lang/invoke/DirectMethodHandle");
-
-    let args = vec![types::Type::None, types::Type::None, types::Type::None];
-    self.interpret_lambda_form(method_handle, &args)?;
-
-    panic!();
-  }
-
-  /// Resolve info of MethodHandle object
-  ///
-  /// # Arguments
-  ///
-  /// * `method_handle_ref` - MethodHandle heap ref
-  ///
-  /// # Returns
-  ///
-  /// (String, String, String)
-  /// as (classname, method_name, method_type)
-  /// It is performed in this way to be safe (but more expensive)
-  pub(crate) fn resolve_method_handle(
-    &mut self,
-    method_handle_ref: ju4,
-  ) -> Result<(String, String, String, i32)> {
-    let method_handle = self.heap.get_obj_instance(method_handle_ref)?.clone();
-
-    debug!("RESOLVING METHOD HANDLE {}", method_handle);
-
-    if types::Type::check_type(
-      &mut self.class_loader,
-      "java/lang/invoke/DirectMethodHandle",
-      method_handle.get_classname(),
-    )? {
-      return self.resolve_direct_method_handle(&method_handle);
-    }
-
-    if types::Type::check_type(
-      &mut self.class_loader,
-      "java/lang/invoke/BoundMethodHandle",
-      method_handle.get_classname(),
-    )? {
-      return self.resolve_bound_method_handle(&method_handle);
-    }
-
-    error!(
-      "method handle not handled {}",
-      method_handle.get_classname()
-    );
-
-    panic!()
-  }
-
-  /// Resolve info of MethodHandle object
-  ///
-  /// # Arguments
-  ///
-  /// * `method_handle_ref` - MethodHandle heap ref
-  /// * `args` - MethodHandle arguments (already correctly ordered)
-  ///   the first element of `args` is the the method handle reference
-  ///
-  /// # Returns
-  ///
-  /// types::Type
-  /// It is performed in this way to be safe (but more expensive)
-  pub(crate) fn call_method_handle(
-    &mut self,
-    method_handle_ref: ju4,
-    args: &mut Vec<types::Type>,
-  ) -> Result<types::Type> {
-    let method_handle = self.heap.get_obj_instance(method_handle_ref)?.clone();
-    debug!("CALLING METHOD HANDLE {}", method_handle);
-
-    if types::Type::check_type(
-      &mut self.class_loader,
-      "java/lang/invoke/DirectMethodHandle",
-      method_handle.get_classname(),
-    )? {
-      return self.call_direct_method_handle(&method_handle, args);
-    }
-
-    if types::Type::check_type(
-      &mut self.class_loader,
-      "java/lang/invoke/BoundMethodHandle",
-      method_handle.get_classname(),
-    )? {
-      return self.call_bound_method_handle(&method_handle, args);
-    }
-
-    error!(
-      "method handle not handled {}",
-      method_handle.get_classname()
-    );
-
-    panic!()
-  }
-
   fn create_member_name(
     &mut self,
     classname: &str,
@@ -655,74 +238,88 @@ impl JVM {
     Ok(method_type_ref)
   }
 
-  // TODO: consider different invoke kind
-  // ASSUMPTION: the method in MethodHandle is already materialized
-  pub fn exec_native_invoke_basic(&mut self, arg_type: &str) -> Result<Option<types::Type>> {
-    warn!("java/lang/invoke/MethodHandle.invokeBasic() not fully implemented");
+  // arguments of a call site of type `type_str`, the receiver (if any) is left on the stack
+  pub(crate) fn pop_polymorphic_arguments(&mut self, type_str: &str) -> Result<Vec<types::Type>> {
+    let param_count = parse_parameter_types(get_parameters_type_descriptor(type_str)).len();
+
+    let mut args = vec![];
+    for _ in 0..param_count {
+      args.push(self.pop_stack()?);
+    }
+
+    args.reverse();
 
-    // TODO: proper implement this
-    self.exec_native_invoke_exact(arg_type)
+    Ok(args)
   }
 
-  fn is_method_handle_new_invoke_special(flags: i32) -> bool {
-    (flags >> MN_REFERENCE_KIND_SHIFT) & REF_NEW_INVOKE_SPECIAL != 0
+  // push the value returned to a call site of type `type_str`, unless it returns void
+  pub(crate) fn push_polymorphic_result(
+    &mut self,
+    type_str: &str,
+    value: types::Type,
+  ) -> Result<Option<types::Type>> {
+    if type_str.ends_with(")V") {
+      return Ok(None);
+    }
+
+    let value = match value {
+      types::Type::None => types::Type::Null,
+      value => value,
+    };
+
+    self.push_stack(value)?;
+
+    Ok(Some(value))
   }
 
-  // TODO: consider different invoke kind
-  // TODO: add type checking (no type coercion or conversion)
-  // ASSUMPTION: the method in MethodHandle is already materialized
-  pub fn exec_native_invoke_exact(&mut self, arg_type: &str) -> Result<Option<types::Type>> {
-    warn!("java/lang/invoke/MethodHandle.invokeExact() not fully implemented");
+  // the arguments are passed as they are, the LambdaForms only use basic types
+  fn exec_native_invoke_basic(&mut self, type_str: &str) -> Result<Option<types::Type>> {
+    let args = self.pop_polymorphic_arguments(type_str)?;
+    let method_handle_ref = self.pop_ref()?;
 
-    let params_types = parse_parameter_types(get_parameters_type_descriptor(arg_type));
-    debug!("from {:?} to {:?}", arg_type, params_types);
+    let result = self.invoke_method_handle(method_handle_ref, args)?;
 
-    let mut args = vec![];
-    for _ in 0..params_types.len() {
-      let arg = self.pop_stack()?;
-      args.push(arg);
-      if arg.get_category() == 2 {
-        args.push(arg);
-      }
-    }
+    self.push_polymorphic_result(type_str, result)
+  }
 
-    let method_handle_ref = self.pop_object_ref()?; // This is the MethodHandle instance
-    args.push(types::Type::ObjectRef(method_handle_ref));
+  fn exec_native_invoke_exact(&mut self, type_str: &str) -> Result<Option<types::Type>> {
+    let args = self.pop_polymorphic_arguments(type_str)?;
+    let method_handle_ref = self.pop_ref()?;
 
-    debug!(
-      "{} / {}",
-      self.get_current_frame()?.get_pc(),
-      self.get_current_frame()?.get_code_length()
-    );
-    debug!("{}", self.get_current_frame()?);
+    let result = self.invoke_method_handle_with_type(method_handle_ref, type_str, args, true)?;
 
-    args.reverse();
+    self.push_polymorphic_result(type_str, result)
+  }
 
-    debug!("{:#?}", args);
-    debug!("{:#?}", arg_type);
-    debug!("{:#?}", params_types);
+  fn exec_native_invoke(&mut self, type_str: &str) -> Result<Option<types::Type>> {
+    let args = self.pop_polymorphic_arguments(type_str)?;
+    let method_handle_ref = self.pop_ref()?;
 
-    let ret_value = self.call_method_handle(method_handle_ref, &mut args)?;
+    let result = self.invoke_method_handle_with_type(method_handle_ref, type_str, args, false)?;
+
+    self.push_polymorphic_result(type_str, result)
+  }
 
-    // if the no ret value => send null
-    let new_ret_value = if ret_value == types::Type::None {
-      types::Type::Null
-    } else {
-      ret_value
+  // MethodHandle.linkTo* get the resolved MemberName as their last argument
+  fn exec_native_link_to(&mut self, type_str: &str) -> Result<Option<types::Type>> {
+    let mut args = self.pop_polymorphic_arguments(type_str)?;
+
+    let member_ref = match args.pop() {
+      Some(types::Type::ObjectRef(member_ref)) => member_ref,
+      _ => return Err(eyre!(errors::JavaException::NullPointer)),
     };
 
-    debug!("invoke exact return value : {}", new_ret_value);
-    self.push_stack(new_ret_value)?;
-    Ok(Some(new_ret_value))
+    let result = self.invoke_member_name(member_ref, args)?;
+
+    self.push_polymorphic_result(type_str, result)
   }
 
-  // TODO: consider different invoke kind
-  // TODO: add type checking (no type coercion or conversion)
-  // ASSUMPTION: the method in MethodHandle is already materialized
-  pub fn exec_native_invoke_virtual(&mut self, arg_type: &str) -> Result<Option<types::Type>> {
-    warn!("java/lang/invoke/MethodHandle.invokeVirtual() not fully implemented");
+  // MethodHandle.linkToNative gets the NativeEntryPoint of a downcall as its last argument
+  fn exec_native_link_to_native(&mut self, type_str: &str) -> Result<Option<types::Type>> {
+    let args = self.pop_polymorphic_arguments(type_str)?;
+
+    let result = self.link_to_native(args)?;
 
-    // TODO: proper implement this
-    self.exec_native_invoke_exact(arg_type)
+    self.push_polymorphic_result(type_str, result)
   }
 }
@@ -2,6 +2,7 @@ use color_eyre::eyre::{eyre, Result};
 use log::{debug, warn};
 
 use crate::{
+  native::registry::NativeRegistry,
   runtime::{
     constants::*,
     errors,
@@ -11,42 +12,54 @@ use crate::{
   utils::{classname_to_descriptor, ju1, ju4},
 };
 
-impl JVM {
-  pub(crate) fn native_dispatcher_java_lang_invoke_methodhandlenatives(
-    &mut self,
-    name: &str,
-    type_str: &str,
-  ) -> Result<Option<types::Type>> {
-    match (name, type_str) {
-      ("init", "(Ljava/lang/invoke/MemberName;Ljava/lang/Object;)V") => {
-        self.exec_native_method_handle_natives_init()
-      }
-      ("getNamedCon", "(I[Ljava/lang/Object;)I") => self.exec_native_get_named_con(),
-      (
-        "resolve",
-        "(Ljava/lang/invoke/MemberName;Ljava/lang/Class;IZ)Ljava/lang/invoke/MemberName;",
-      ) => self.exec_native_method_handle_natives_resolve(),
-      ("getMemberVMInfo", "(Ljava/lang/invoke/MemberName;)Ljava/lang/Object;") => {
-        self.exec_native_get_member_vm_info()
-      }
-      ("objectFieldOffset", "(Ljava/lang/invoke/MemberName;)J") => {
-        self.exec_native_object_field_offset()
-      }
-      ("staticFieldBase", "(Ljava/lang/invoke/MemberName;)Ljava/lang/Object;") => {
-        self.exec_native_static_field_base()
-      }
-      ("staticFieldOffset", "(Ljava/lang/invoke/MemberName;)J") => {
-        self.exec_native_static_field_offset()
-      }
-
-      _ => Err(eyre!(errors::InternalError::NativeNotImplemented(
-        "java/lang/invoke/MethodHandleNatives".to_string(),
-        name.to_owned(),
-        type_str.to_owned()
-      ))),
-    }
-  }
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  let class_name = "java/lang/invoke/MethodHandleNatives";
+
+  registry.add(
+    class_name,
+    "init",
+    "(Ljava/lang/invoke/MemberName;Ljava/lang/Object;)V",
+    |jvm, _, _| jvm.exec_native_method_handle_natives_init(),
+  );
+  registry.add(
+    class_name,
+    "getNamedCon",
+    "(I[Ljava/lang/Object;)I",
+    |jvm, _, _| jvm.exec_native_get_named_con(),
+  );
+  registry.add(
+    class_name,
+    "resolve",
+    "(Ljava/lang/invoke/MemberName;Ljava/lang/Class;IZ)Ljava/lang/invoke/MemberName;",
+    |jvm, _, _| jvm.exec_native_method_handle_natives_resolve(),
+  );
+  registry.add(
+    class_name,
+    "getMemberVMInfo",
+    "(Ljava/lang/invoke/MemberName;)Ljava/lang/Object;",
+    |jvm, _, _| jvm.exec_native_get_member_vm_info(),
+  );
+  registry.add(
+    class_name,
+    "objectFieldOffset",
+    "(Ljava/lang/invoke/MemberName;)J",
+    |jvm, _, _| jvm.exec_native_object_field_offset(),
+  );
+  registry.add(
+    class_name,
+    "staticFieldBase",
+    "(Ljava/lang/invoke/MemberName;)Ljava/lang/Object;",
+    |jvm, _, _| jvm.exec_native_static_field_base(),
+  );
+  registry.add(
+    class_name,
+    "staticFieldOffset",
+    "(Ljava/lang/invoke/MemberName;)J",
+    |jvm, _, _| jvm.exec_native_static_field_offset(),
+  );
+}
 
+impl JVM {
   fn exec_native_method_handle_natives_init(&mut self) -> Result<Option<types::Type>> {
     warn!("java/lang/invoke/MethodHandleNatives.init() not implemented");
     // TODO: `fill in vmtarget, vmindex while we have ctor in hand:`
@@ -103,9 +116,35 @@ impl JVM {
     let (member_index, member_modifiers) =
       match self.get_member_name_index_and_flags(&clazz_name, &name, type_ref, ref_kind as ju1) {
         Ok(res) => res,
-        Err(_) => return Err(eyre!(errors::JavaException::LinkageError)),
+        Err(_) => {
+          return Err(eyre!(errors::JavaException::LinkageError(format!(
+            "cannot resolve member {} in {}",
+            name, clazz_name
+          ))))
+        }
       };
 
+    // the kind of a field reference follows the field, like `init_field_MemberName` of
+    // HotSpot does: the JDK resolves static fields such as `MethodHandleStatics.UNSAFE`
+    // with REF_getField
+    let flags = match ref_kind {
+      REF_GET_FIELD | REF_PUT_FIELD | REF_GET_STATIC | REF_PUT_STATIC => {
+        let is_setter = ref_kind == REF_PUT_FIELD || ref_kind == REF_PUT_STATIC;
+        let is_static = member_modifiers & 0x0008 != 0;
+
+        let ref_kind = match (is_static, is_setter) {
+          (false, false) => REF_GET_FIELD,
+          (true, false) => REF_GET_STATIC,
+          (false, true) => REF_PUT_FIELD,
+          (true, true) => REF_PUT_STATIC,
+        };
+
+        (flags & !(MN_REFERENCE_KIND_MASK << MN_REFERENCE_KIND_SHIFT))
+          | (ref_kind << MN_REFERENCE_KIND_SHIFT)
+      }
+      _ => flags,
+    };
+
     let member_name_obj_mut = self.heap.get_obj_instance_mut(member_name_ref)?;
     member_name_obj_mut.put_field("clazz", types::Type::ObjectRef(clazz_ref))?;
     member_name_obj_mut.put_field("type", types::Type::ObjectRef(type_ref))?;
@@ -127,6 +166,7 @@ impl JVM {
 
   // this and the return should be similar, but they might differ while dealing with unresolved
   // methods => so I resolve it again to get all important informations
+  #[allow(clippy::too_many_arguments)]
   fn exec_native_get_member_vm_info_method(
     &mut self,
     member_name_ref: ju4,
@@ -196,6 +236,7 @@ impl JVM {
     Ok(vec![slot, types::Type::ObjectRef(target_class.get_ref())])
   }
 
+  #[allow(clippy::too_many_arguments)]
   fn exec_native_get_member_vm_info_constructor(
     &mut self,
     member_name_ref: ju4,
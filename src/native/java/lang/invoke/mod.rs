@@ -1,3 +1,11 @@
 pub mod methodhandle;
 pub mod methodhandlenatives;
 pub mod varhandle;
+
+use crate::native::registry::NativeRegistry;
+
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  methodhandle::register_natives(registry);
+  methodhandlenatives::register_natives(registry);
+  varhandle::register_natives(registry);
+}
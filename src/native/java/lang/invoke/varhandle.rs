@@ -1,149 +1,722 @@
 use crate::{
+  native::registry::NativeRegistry,
   runtime::{errors, jvm::*, types},
-  utils::get_argument_classnames,
+  utils::{get_parameters_type_descriptor, get_return_type_descriptor, ju4, parse_parameter_types},
 };
 use color_eyre::eyre::{eyre, Result};
-use log::warn;
+use log::{debug, warn};
+
+const VARHANDLE: &str = "java/lang/invoke/VarHandle";
+
+// type of the variables of each family of VarHandle classes (VarHandleInts, ...)
+const VARIABLE_TYPES: &[(&str, &str)] = &[
+  ("Booleans", "Z"),
+  ("Bytes", "B"),
+  ("Shorts", "S"),
+  ("Chars", "C"),
+  ("Ints", "I"),
+  ("Longs", "J"),
+  ("Floats", "F"),
+  ("Doubles", "D"),
+  ("References", "Ljava/lang/Object;"),
+  ("ByteArrayAsShorts", "S"),
+  ("ByteArrayAsChars", "C"),
+  ("ByteArrayAsInts", "I"),
+  ("ByteArrayAsLongs", "J"),
+  ("ByteArrayAsFloats", "F"),
+  ("ByteArrayAsDoubles", "D"),
+];
+
+/// Access mode of a VarHandle without its memory order, which does not matter with a single
+/// thread
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AccessMode {
+  Get,
+  Set,
+  CompareAndSet,
+  CompareAndExchange,
+  GetAndSet,
+  GetAndAdd,
+  GetAndBitwiseOr,
+  GetAndBitwiseAnd,
+  GetAndBitwiseXor,
+}
+
+// access mode of each signature polymorphic method of VarHandle
+const ACCESS_MODES: &[(&str, AccessMode)] = &[
+  ("get", AccessMode::Get),
+  ("getVolatile", AccessMode::Get),
+  ("getAcquire", AccessMode::Get),
+  ("getOpaque", AccessMode::Get),
+  ("set", AccessMode::Set),
+  ("setVolatile", AccessMode::Set),
+  ("setRelease", AccessMode::Set),
+  ("setOpaque", AccessMode::Set),
+  ("compareAndSet", AccessMode::CompareAndSet),
+  ("weakCompareAndSet", AccessMode::CompareAndSet),
+  ("weakCompareAndSetPlain", AccessMode::CompareAndSet),
+  ("weakCompareAndSetAcquire", AccessMode::CompareAndSet),
+  ("weakCompareAndSetRelease", AccessMode::CompareAndSet),
+  ("compareAndExchange", AccessMode::CompareAndExchange),
+  ("compareAndExchangeAcquire", AccessMode::CompareAndExchange),
+  ("compareAndExchangeRelease", AccessMode::CompareAndExchange),
+  ("getAndSet", AccessMode::GetAndSet),
+  ("getAndSetAcquire", AccessMode::GetAndSet),
+  ("getAndSetRelease", AccessMode::GetAndSet),
+  ("getAndAdd", AccessMode::GetAndAdd),
+  ("getAndAddAcquire", AccessMode::GetAndAdd),
+  ("getAndAddRelease", AccessMode::GetAndAdd),
+  ("getAndBitwiseOr", AccessMode::GetAndBitwiseOr),
+  ("getAndBitwiseOrAcquire", AccessMode::GetAndBitwiseOr),
+  ("getAndBitwiseOrRelease", AccessMode::GetAndBitwiseOr),
+  ("getAndBitwiseAnd", AccessMode::GetAndBitwiseAnd),
+  ("getAndBitwiseAndAcquire", AccessMode::GetAndBitwiseAnd),
+  ("getAndBitwiseAndRelease", AccessMode::GetAndBitwiseAnd),
+  ("getAndBitwiseXor", AccessMode::GetAndBitwiseXor),
+  ("getAndBitwiseXorAcquire", AccessMode::GetAndBitwiseXor),
+  ("getAndBitwiseXorRelease", AccessMode::GetAndBitwiseXor),
+];
+
+impl AccessMode {
+  // number of values after the coordinates
+  fn get_value_count(self) -> usize {
+    match self {
+      AccessMode::Get => 0,
+      AccessMode::CompareAndSet | AccessMode::CompareAndExchange => 2,
+      _ => 1,
+    }
+  }
+}
+
+/// Coordinates of the variables of a VarHandle
+#[derive(Debug)]
+enum Coordinates {
+  // (receiver), the offset is the one of Unsafe.objectFieldOffset
+  InstanceField { receiver_type: String, offset: i64 },
+  // (), the class of the base of Unsafe.staticFieldBase
+  StaticField { classname: String, offset: i64 },
+  // (array, index)
+  ArrayElement,
+  // (byte[], index)
+  ByteArrayView { big_endian: bool },
+  // (ByteBuffer, index)
+  ByteBufferView { big_endian: bool },
+}
+
+impl Coordinates {
+  fn count(&self) -> usize {
+    match self {
+      Coordinates::InstanceField { .. } => 1,
+      Coordinates::StaticField { .. } => 0,
+      _ => 2,
+    }
+  }
+}
+
+/// VarHandle described by its class and its fields
+#[derive(Debug)]
+struct VarHandleInfo {
+  classname: String,
+  coordinates: Coordinates,
+  // descriptor of the variables
+  var_type: String,
+  // class of the values of reference variables
+  value_class: Option<String>,
+  read_only: bool,
+}
+
+/// Variable accessed by a VarHandle
+enum Variable {
+  Field(ju4, String),
+  StaticField(String, String),
+  ArrayElement(ju4, usize),
+  // bytes of a primitive value at an index of a byte array, in big or little endian
+  Bytes(ju4, usize, bool),
+}
+
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  // signature polymorphic, the descriptor is the type of the call site
+  for &(name, mode) in ACCESS_MODES {
+    registry.add_polymorphic(VARHANDLE, name, move |jvm, name, type_str| {
+      jvm.exec_native_java_lang_invoke_varhandle_access(name, mode, type_str)
+    });
+  }
+}
 
 impl JVM {
-  pub(crate) fn native_dispatcher_java_lang_invoke_varhandle(
+  // access the variable with the coordinates and the values of the call site, and push the
+  // result converted to the return type of the call site
+  fn exec_native_java_lang_invoke_varhandle_access(
     &mut self,
     name: &str,
+    mode: AccessMode,
     type_str: &str,
   ) -> Result<Option<types::Type>> {
-    match (name, type_str) {
-      // polymorphic signature
-      ("getAndBitwiseOr", _) => self.exec_native_java_lang_invoke_varhandle_getandbitwiseor(),
-      ("compareAndSet", _) => self.exec_native_java_lang_invoke_varhandle_compareandset(),
-      ("set", _) => self.exec_native_java_lang_invoke_varhandle_set(type_str),
-      _ => Err(eyre!(errors::InternalError::NativeNotImplemented(
-        "java/lang/invoke/VarHandle".to_string(),
-        name.to_owned(),
-        type_str.to_owned()
-      ))),
+    warn!(
+      "java/lang/invoke/VarHandle.{} only works for single-threading",
+      name
+    );
+
+    let param_types: Vec<String> = parse_parameter_types(get_parameters_type_descriptor(type_str))
+      .into_iter()
+      .map(str::to_string)
+      .collect();
+
+    let mut args = vec![];
+    for _ in 0..param_types.len() {
+      args.push(self.pop_stack()?);
     }
-  }
 
-  fn exec_native_java_lang_invoke_varhandle_getandbitwiseor(
-    &mut self,
-  ) -> Result<Option<types::Type>> {
-    // TODO: make atomic
-    warn!("java/lang/invoke/VarHandle.getAndBitwiseOr is not atomic!");
-    let value = self.pop_stack()?.as_integer()?;
-    let obj_ref = self.pop_stack()?.as_ref()?;
-    let this_ref = self.pop_stack()?.as_ref()?; // java/lang/invoke/VarHandle
+    args.reverse();
+
+    let this_ref = match self.pop_stack()? {
+      types::Type::ObjectRef(this_ref) => this_ref, // java/lang/invoke/VarHandle
+      _ => return Err(eyre!(errors::JavaException::NullPointer)),
+    };
+
+    let handle = self.get_var_handle_info(this_ref)?;
+
+    debug!("VARHANDLE {} {} {:?}", name, type_str, handle);
+
+    let count = handle.coordinates.count();
+
+    if args.len() != count + mode.get_value_count() {
+      return Err(eyre!(errors::JavaException::WrongMethodTypeException(
+        format!("{}{} on {}", name, type_str, handle.classname)
+      )));
+    }
+
+    check_access_mode(&handle, mode)?;
+
+    // plain reads and writes of byte views can be misaligned
+    let aligned = !matches!(name, "get" | "set");
 
+    let variable = self.get_variable(&handle, &args[..count], mode, aligned)?;
+
+    let mut values = vec![];
+    for (value, param_type) in args[count..].iter().zip(&param_types[count..]) {
+      values.push(self.convert_variable_value(&handle, param_type, *value)?);
+    }
+
+    let var_type = handle.var_type.as_str();
+
+    let result = match mode {
+      AccessMode::Set => {
+        self.write_variable(&variable, var_type, values[0])?;
+
+        None
+      }
+      AccessMode::Get => Some(self.read_variable(&variable, var_type)?),
+      AccessMode::CompareAndSet | AccessMode::CompareAndExchange => {
+        let current = self.read_variable(&variable, var_type)?;
+
+        let same = is_same_value(var_type, current, values[0])?;
+
+        if same {
+          self.write_variable(&variable, var_type, values[1])?;
+        }
+
+        match mode {
+          AccessMode::CompareAndSet => {
+            return self.push_variable_value("Z", type_str, types::Type::Boolean(same))
+          }
+          _ => Some(current),
+        }
+      }
+      _ => {
+        let current = self.read_variable(&variable, var_type)?;
+
+        let value = match mode {
+          AccessMode::GetAndAdd => add(var_type, current, values[0])?,
+          AccessMode::GetAndBitwiseOr
+          | AccessMode::GetAndBitwiseAnd
+          | AccessMode::GetAndBitwiseXor => bitwise(mode, var_type, current, values[0])?,
+          _ => values[0],
+        };
+
+        self.write_variable(&variable, var_type, value)?;
+
+        Some(current)
+      }
+    };
+
+    match result {
+      Some(value) => self.push_variable_value(var_type, type_str, value),
+      None => Ok(None),
+    }
+  }
+
+  fn get_var_handle_info(&mut self, this_ref: ju4) -> Result<VarHandleInfo> {
     let this = self.heap.get_obj_instance(this_ref)?;
 
-    // TODO: this works only if the field is not static
+    let classname = this.get_classname().to_string();
 
-    let field_offset = this.get_field("fieldOffset")?.as_integer()?;
+    let not_supported = || {
+      eyre!(errors::InternalError::General(format!(
+        "VarHandle not supported: {}",
+        classname
+      )))
+    };
+
+    let (family, kind) = classname
+      .strip_prefix(VARHANDLE)
+      .and_then(|name| name.split_once('$'))
+      .ok_or_else(not_supported)?;
+
+    let (_, var_type) = VARIABLE_TYPES
+      .iter()
+      .find(|(name, _)| *name == family)
+      .ok_or_else(not_supported)?;
+
+    let class_field = |field: &str| -> Result<String> {
+      let class_ref = this.get_field(field)?.as_ref()?;
+
+      self.heap.get_classname_from_class_obj(class_ref)
+    };
 
-    let receiver_type_ref = this.get_field("receiverType")?.as_ref()?;
-    let receiver_type = self.heap.get_classname_from_class_obj(receiver_type_ref)?;
+    // Class mirror or instance holding the static fields from Unsafe.staticFieldBase
+    let static_base = || -> Result<String> {
+      let base_ref = this.get_field("base")?.as_ref()?;
+      let base_classname = self.heap.get_obj_instance(base_ref)?.get_classname();
 
-    let obj = self.heap.get_obj_instance_mut(obj_ref)?;
-    let class = self.class_loader.get(&receiver_type)?;
-    let field = class.get_field_by_offset(field_offset as i64)?;
-    let field_name = field.get_name();
+      if base_classname == "java/lang/Class" {
+        self.heap.get_classname_from_class_obj(base_ref)
+      } else {
+        Ok(base_classname.to_string())
+      }
+    };
 
-    let original_field_value = obj.get_field(field_name)?.as_integer()?;
+    let coordinates = match kind {
+      "FieldInstanceReadOnly" | "FieldInstanceReadWrite" => Coordinates::InstanceField {
+        receiver_type: class_field("receiverType")?,
+        offset: this.get_field("fieldOffset")?.as_long()?,
+      },
+      "FieldStaticReadOnly" | "FieldStaticReadWrite" => Coordinates::StaticField {
+        classname: static_base()?,
+        offset: this.get_field("fieldOffset")?.as_long()?,
+      },
+      "Array" => Coordinates::ArrayElement,
+      "ArrayHandle" => Coordinates::ByteArrayView {
+        big_endian: this.get_field("be")?.as_bool()?,
+      },
+      "ByteBufferHandle" => Coordinates::ByteBufferView {
+        big_endian: this.get_field("be")?.as_bool()?,
+      },
+      _ => return Err(not_supported()),
+    };
 
-    obj.put_field(
-      field_name,
-      types::Type::Integer(original_field_value | value),
-    )?;
-    drop(class);
+    // `Object` values are not checked
+    let value_class = match (var_type.starts_with('L'), &coordinates) {
+      (false, _) => None,
+      (true, Coordinates::ArrayElement) => Some(class_field("componentType")?),
+      (true, _) => Some(class_field("fieldType")?),
+    }
+    .filter(|value_class| value_class != "java/lang/Object");
 
-    let ret_value = types::Type::Integer(original_field_value);
-    self.push_stack(ret_value)?;
-    Ok(Some(ret_value))
+    Ok(VarHandleInfo {
+      read_only: kind.ends_with("ReadOnly"),
+      classname: classname.clone(),
+      coordinates,
+      var_type: var_type.to_string(),
+      value_class,
+    })
   }
 
-  fn exec_native_java_lang_invoke_varhandle_compareandset(
+  fn get_variable(
     &mut self,
-  ) -> Result<Option<types::Type>> {
-    // TODO: make atomic
-    warn!("java/lang/invoke/VarHandle.compareAndSet is not atomic!");
+    handle: &VarHandleInfo,
+    coordinates: &[types::Type],
+    mode: AccessMode,
+    aligned: bool,
+  ) -> Result<Variable> {
+    let variable = match &handle.coordinates {
+      Coordinates::InstanceField {
+        receiver_type,
+        offset,
+      } => {
+        let obj_ref = match coordinates[0] {
+          types::Type::ObjectRef(obj_ref) => obj_ref,
+          _ => return Err(eyre!(errors::JavaException::NullPointer)),
+        };
 
-    let new_value = self.pop_stack()?;
-    let expected = self.pop_stack()?;
-    let obj_ref = self.pop_stack()?.as_ref()?;
-    let this_ref = self.pop_stack()?.as_ref()?; // java/lang/invoke/VarHandle
+        let classname = self.heap.get_instance(obj_ref)?.get_classname().to_string();
 
-    let this = self.heap.get_obj_instance(this_ref)?;
+        if !types::Type::check_type(&mut self.class_loader, receiver_type, &classname)? {
+          return Err(eyre!(errors::JavaException::ClassCastException(format!(
+            "Cannot cast {} to {}",
+            classname, receiver_type
+          ))));
+        }
+
+        let field = self
+          .class_loader
+          .get_field_by_offset(receiver_type, *offset)?;
+
+        Variable::Field(obj_ref, field.get_name().to_string())
+      }
+      Coordinates::StaticField { classname, offset } => {
+        let field = self.class_loader.get_field_by_offset(classname, *offset)?;
+
+        Variable::StaticField(classname.clone(), field.get_name().to_string())
+      }
+      Coordinates::ArrayElement => {
+        let (array_ref, index) = self.get_array_coordinates(coordinates, 1)?;
+
+        Variable::ArrayElement(array_ref, index)
+      }
+      Coordinates::ByteArrayView { big_endian } => {
+        let size = get_byte_size(&handle.var_type);
 
-    // TODO: this works only if the field is not static and with fields only
-    // TODO: generalize to any kind
+        let (array_ref, index) = self.get_array_coordinates(coordinates, size)?;
 
-    let field_offset = this.get_field("fieldOffset")?.as_integer()?;
+        check_alignment(index, size, aligned)?;
 
-    let receiver_type_ref = this.get_field("receiverType")?.as_ref()?;
-    let receiver_type = self.heap.get_classname_from_class_obj(receiver_type_ref)?;
+        Variable::Bytes(array_ref, index, *big_endian)
+      }
+      Coordinates::ByteBufferView { big_endian } => {
+        let size = get_byte_size(&handle.var_type);
+
+        let buffer_ref = match coordinates[0] {
+          types::Type::ObjectRef(buffer_ref) => buffer_ref,
+          _ => return Err(eyre!(errors::JavaException::NullPointer)),
+        };
+
+        let index = coordinates[1].as_integer()?;
+
+        let buffer = self.heap.get_obj_instance(buffer_ref)?;
+
+        let limit = buffer.get_field("limit")?.as_integer()?;
 
-    let obj = self.heap.get_obj_instance_mut(obj_ref)?;
-    let class = self.class_loader.get(&receiver_type)?;
-    let field = class.get_field_by_offset(field_offset as i64)?;
-    let field_name = field.get_name();
+        if index < 0 || index as i64 + size as i64 > limit as i64 {
+          return Err(eyre!(errors::JavaException::IndexOutOfBounds(format!(
+            "Index {} out of bounds for length {}",
+            index,
+            limit - size as i32 + 1
+          ))));
+        }
 
-    let original_field_value = obj.get_field(field_name)?;
+        if mode != AccessMode::Get && buffer.get_field("isReadOnly")?.as_bool()? {
+          return Err(eyre!(errors::JavaException::UnsupportedOperationException(
+            "Read-only ByteBuffer".to_string()
+          )));
+        }
 
-    let successfull = if original_field_value == expected {
-      obj.put_field(field_name, new_value)?;
-      true
-    } else {
-      false
+        let array_ref = match buffer.get_field("hb")? {
+          types::Type::ArrayRef(array_ref) => array_ref,
+          _ => {
+            return Err(eyre!(errors::JavaException::UnsupportedOperationException(
+              "VarHandle view of a direct ByteBuffer".to_string()
+            )))
+          }
+        };
+
+        let index = (buffer.get_field("offset")?.as_integer()? + index) as usize;
+
+        check_alignment(index, size, aligned)?;
+
+        Variable::Bytes(array_ref, index, *big_endian)
+      }
     };
-    drop(class);
 
-    let ret_value = types::Type::Boolean(successfull);
-    self.push_stack(ret_value)?;
-    Ok(Some(ret_value))
+    Ok(variable)
   }
 
-  // java/lang/invoke/VarHandleByteArrayAsShorts$ArrayHandle set ([BIS)V
-  fn exec_native_java_lang_invoke_varhandle_set_bytearrayasshorts(
+  // array and index of the first of `size` elements
+  fn get_array_coordinates(
+    &self,
+    coordinates: &[types::Type],
+    size: usize,
+  ) -> Result<(ju4, usize)> {
+    let array_ref = match coordinates[0] {
+      types::Type::ArrayRef(array_ref) => array_ref,
+      _ => return Err(eyre!(errors::JavaException::NullPointer)),
+    };
+
+    let index = coordinates[1].as_integer()?;
+
+    let len = self.heap.get_array_instance(array_ref)?.len();
+
+    if index < 0 || index as usize + size > len {
+      return Err(eyre!(errors::JavaException::ArrayIndexOutOfBounds(
+        index as usize,
+        len
+      )));
+    }
+
+    Ok((array_ref, index as usize))
+  }
+
+  // value of the call site converted to the type of the variable
+  fn convert_variable_value(
     &mut self,
-    args: &[types::Type],
-  ) -> Result<Option<types::Type>> {
-    assert!(args.len() == 3);
-    let value = args[0].as_short()?;
-    let index = args[1].as_integer()? as usize;
-    let array_ref = args[2].as_ref()?; // [B
-    let array = self.heap.get_array_instance_mut(array_ref)?;
+    handle: &VarHandleInfo,
+    param_type: &str,
+    value: types::Type,
+  ) -> Result<types::Type> {
+    let var_type = handle.var_type.as_str();
+
+    if !types::Type::is_primitive(var_type) {
+      let value = match value.get_primitive_descriptor() {
+        Some(_) => self
+          .heap
+          .alloc_boxed(&mut self.class_loader, param_type, value)?,
+        None => value,
+      };
 
-    // TODO: check which is the correct byte order
-    let bytes = value.to_be_bytes();
+      if let (
+        Some(value_class),
+        types::Type::ObjectRef(value_ref) | types::Type::ArrayRef(value_ref),
+      ) = (&handle.value_class, value)
+      {
+        let classname = self
+          .heap
+          .get_instance(value_ref)?
+          .get_classname()
+          .to_string();
 
-    array.set(index, types::Type::Byte(bytes[0] as i8))?;
-    array.set(index + 1, types::Type::Byte(bytes[1] as i8))?;
+        if !types::Type::check_type(&mut self.class_loader, value_class, &classname)? {
+          let message = format!("Cannot cast {} to {}", classname, value_class);
 
-    Ok(None)
+          return Err(match handle.coordinates {
+            Coordinates::ArrayElement => eyre!(errors::JavaException::ArrayStoreException(message)),
+            _ => eyre!(errors::JavaException::ClassCastException(message)),
+          });
+        }
+      }
+
+      return Ok(value);
+    }
+
+    // boxes are unboxed
+    let (from, value) = match value {
+      types::Type::ObjectRef(value_ref) => match self.heap.get_boxed_value(value_ref)? {
+        Some((from, value)) => (from.to_string(), value),
+        None => {
+          let classname = self
+            .heap
+            .get_instance(value_ref)?
+            .get_classname()
+            .to_string();
+
+          return Err(eyre!(errors::JavaException::ClassCastException(format!(
+            "Cannot cast {} to {}",
+            classname, var_type
+          ))));
+        }
+      },
+      types::Type::Null => return Err(eyre!(errors::JavaException::NullPointer)),
+      value => (param_type.to_string(), value),
+    };
+
+    let value = types::Type::widen_primitive(&from, var_type, value)?.ok_or_else(|| {
+      eyre!(errors::JavaException::WrongMethodTypeException(format!(
+        "Cannot convert {} to {}",
+        from, var_type
+      )))
+    })?;
+
+    types::Type::to_stored_value(var_type, value)
   }
 
-  fn exec_native_java_lang_invoke_varhandle_set(
+  // push the value of the variable converted to the return type of the call site
+  fn push_variable_value(
     &mut self,
+    var_type: &str,
     type_str: &str,
+    value: types::Type,
   ) -> Result<Option<types::Type>> {
-    let args_str = get_argument_classnames(type_str);
+    let return_type = get_return_type_descriptor(type_str);
 
-    let mut args = vec![];
-    for _ in 0..args_str.len() {
-      args.push(self.pop_stack()?);
-    }
+    let value = match return_type.as_str() {
+      "V" => return Ok(None),
+      return_type if !types::Type::is_primitive(return_type) => {
+        if types::Type::is_primitive(var_type) {
+          self
+            .heap
+            .alloc_boxed(&mut self.class_loader, var_type, value)?
+        } else {
+          value
+        }
+      }
+      return_type => {
+        types::Type::widen_primitive(var_type, return_type, value)?.ok_or_else(|| {
+          eyre!(errors::JavaException::WrongMethodTypeException(format!(
+            "Cannot convert {} to {}",
+            var_type, return_type
+          )))
+        })?
+      }
+    };
 
-    let this_ref = self.pop_stack()?.as_ref()?; // java/lang/invoke/VarHandle
+    self.push_stack(value)?;
 
-    let this = self.heap.get_obj_instance(this_ref)?;
+    Ok(Some(value))
+  }
+
+  fn read_variable(&mut self, variable: &Variable, var_type: &str) -> Result<types::Type> {
+    let value = match variable {
+      Variable::Field(obj_ref, name) => self.heap.get_obj_instance(*obj_ref)?.get_field(name)?,
+      Variable::StaticField(classname, name) => {
+        *self.class_loader.get(classname)?.get_static_field(name)?
+      }
+      Variable::ArrayElement(array_ref, index) => {
+        *self.heap.get_array_instance(*array_ref)?.get(*index)?
+      }
+      Variable::Bytes(array_ref, index, big_endian) => {
+        let array = self.heap.get_array_instance(*array_ref)?;
 
-    match this.get_classname() {
-      "java/lang/invoke/VarHandleByteArrayAsShorts$ArrayHandle" => {
-        self.exec_native_java_lang_invoke_varhandle_set_bytearrayasshorts(&args)?
+        let size = get_byte_size(var_type);
+
+        let mut bits: u64 = 0;
+
+        for i in 0..size {
+          let at = if *big_endian { i } else { size - 1 - i };
+
+          bits = (bits << 8) | array.get(index + at)?.as_byte()? as u8 as u64;
+        }
+
+        match var_type {
+          "S" | "C" => types::Type::Short(bits as u16 as i16),
+          "I" => types::Type::Integer(bits as u32 as i32),
+          "J" => types::Type::Long(bits as i64),
+          "F" => types::Type::Float(f32::from_bits(bits as u32)),
+          _ => types::Type::Double(f64::from_bits(bits)),
+        }
       }
-      // TODO: add the other cases
-      _ => panic!("not handled {}", this.get_classname()),
     };
 
-    Ok(None)
+    types::Type::to_stored_value(var_type, value)
+  }
+
+  fn write_variable(
+    &mut self,
+    variable: &Variable,
+    var_type: &str,
+    value: types::Type,
+  ) -> Result<()> {
+    match variable {
+      Variable::Field(obj_ref, name) => self
+        .heap
+        .get_obj_instance_mut(*obj_ref)?
+        .put_field(name, value)?,
+      Variable::StaticField(classname, name) => self
+        .class_loader
+        .get_mut(classname)?
+        .put_static_field(name, value)?,
+      Variable::ArrayElement(array_ref, index) => self
+        .heap
+        .get_array_instance_mut(*array_ref)?
+        .set(*index, value)?,
+      Variable::Bytes(array_ref, index, big_endian) => {
+        let size = get_byte_size(var_type);
+
+        let bits = match var_type {
+          "S" | "C" => value.as_integer()? as u16 as u64,
+          "I" => value.as_integer()? as u32 as u64,
+          "J" => value.as_long()? as u64,
+          "F" => value.as_float()?.to_bits() as u64,
+          _ => value.as_double()?.to_bits(),
+        };
+
+        let array = self.heap.get_array_instance_mut(*array_ref)?;
+
+        for i in 0..size {
+          let at = if *big_endian { size - 1 - i } else { i };
+
+          array.set(index + at, types::Type::Byte((bits >> (8 * i)) as u8 as i8))?;
+        }
+      }
+    }
+
+    Ok(())
+  }
+}
+
+// the numeric and bitwise access modes are not supported by every type of variable, nor the
+// writes of the handles of final fields
+fn check_access_mode(handle: &VarHandleInfo, mode: AccessMode) -> Result<()> {
+  let var_type = handle.var_type.as_str();
+
+  let view = matches!(
+    handle.coordinates,
+    Coordinates::ByteArrayView { .. } | Coordinates::ByteBufferView { .. }
+  );
+
+  let supported = match mode {
+    AccessMode::Get => true,
+    _ if handle.read_only => false,
+    AccessMode::Set => true,
+    // byte views of shorts and chars are only read and written
+    _ if view && matches!(var_type, "S" | "C") => false,
+    AccessMode::CompareAndSet | AccessMode::CompareAndExchange | AccessMode::GetAndSet => true,
+    AccessMode::GetAndAdd => types::Type::is_primitive(var_type) && var_type != "Z",
+    _ => types::Type::is_primitive(var_type) && !matches!(var_type, "F" | "D"),
+  };
+
+  if !supported {
+    return Err(eyre!(errors::JavaException::UnsupportedOperationException(
+      format!("{:?} on {}", mode, handle.classname)
+    )));
+  }
+
+  Ok(())
+}
+
+// byte views of the access modes other than plain get and set must be aligned on the size of
+// the values
+fn check_alignment(index: usize, size: usize, aligned: bool) -> Result<()> {
+  if aligned && !index.is_multiple_of(size) {
+    return Err(eyre!(errors::JavaException::IllegalStateException(
+      format!("Misaligned access at index: {}", index)
+    )));
   }
+
+  Ok(())
+}
+
+fn get_byte_size(var_type: &str) -> usize {
+  match var_type {
+    "S" | "C" => 2,
+    "I" | "F" => 4,
+    _ => 8,
+  }
+}
+
+// floating-point values are compared by their bits, references by identity
+fn is_same_value(var_type: &str, left: types::Type, right: types::Type) -> Result<bool> {
+  let same = match var_type {
+    "F" => left.as_float()?.to_bits() == right.as_float()?.to_bits(),
+    "D" => left.as_double()?.to_bits() == right.as_double()?.to_bits(),
+    _ if !types::Type::is_primitive(var_type) => left.as_ref()? == right.as_ref()?,
+    _ => left.as_long()? == right.as_long()?,
+  };
+
+  Ok(same)
+}
+
+fn add(var_type: &str, current: types::Type, delta: types::Type) -> Result<types::Type> {
+  let value = match var_type {
+    "F" => types::Type::Float(current.as_float()? + delta.as_float()?),
+    "D" => types::Type::Double(current.as_double()? + delta.as_double()?),
+    _ => types::Type::Long(current.as_long()?.wrapping_add(delta.as_long()?)),
+  };
+
+  types::Type::to_stored_value(var_type, value)
+}
+
+fn bitwise(
+  mode: AccessMode,
+  var_type: &str,
+  current: types::Type,
+  value: types::Type,
+) -> Result<types::Type> {
+  let (current, value) = (current.as_long()?, value.as_long()?);
+
+  let result = match mode {
+    AccessMode::GetAndBitwiseOr => current | value,
+    AccessMode::GetAndBitwiseAnd => current & value,
+    _ => current ^ value,
+  };
+
+  types::Type::to_stored_value(var_type, types::Type::Long(result))
 }
@@ -2,3 +2,12 @@ pub mod class;
 pub mod classloader;
 pub mod invoke;
 pub mod r#ref;
+
+use crate::native::registry::NativeRegistry;
+
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  class::register_natives(registry);
+  classloader::register_natives(registry);
+  invoke::register_natives(registry);
+  r#ref::register_natives(registry);
+}
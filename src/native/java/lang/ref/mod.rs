@@ -1 +1,7 @@
 pub mod reference;
+
+use crate::native::registry::NativeRegistry;
+
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  reference::register_natives(registry);
+}
@@ -1,25 +1,27 @@
-use crate::runtime::{errors, jvm::*, types};
-use color_eyre::eyre::{eyre, Result};
+use crate::{
+  native::registry::NativeRegistry,
+  runtime::{jvm::*, types},
+};
+use color_eyre::eyre::Result;
+
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  let class_name = "java/lang/ref/Reference";
+
+  registry.add(
+    class_name,
+    "refersTo0",
+    "(Ljava/lang/Object;)Z",
+    |jvm, _, _| jvm.exec_native_java_lang_ref_reference_refers_to0(),
+  );
+  registry.add(class_name, "clear0", "()V", |jvm, _, _| {
+    jvm.exec_native_java_lang_ref_reference_clear0()
+  });
+  registry.add(class_name, "hasReferencePendingList", "()Z", |jvm, _, _| {
+    jvm.exec_native_java_lang_ref_reference_has_reference_pending_list()
+  });
+}
 
 impl JVM {
-  pub(crate) fn native_dispatcher_java_lang_ref_reference(
-    &mut self,
-    name: &str,
-    type_str: &str,
-  ) -> Result<Option<types::Type>> {
-    match (name, type_str) {
-      ("refersTo0", "(Ljava/lang/Object;)Z") => {
-        self.exec_native_java_lang_ref_reference_refers_to0()
-      }
-      ("clear0", "()V") => self.exec_native_java_lang_ref_reference_clear0(),
-      _ => Err(eyre!(errors::InternalError::NativeNotImplemented(
-        "java/lang/ref/Reference".to_string(),
-        name.to_owned(),
-        type_str.to_owned()
-      ))),
-    }
-  }
-
   fn exec_native_java_lang_ref_reference_refers_to0(&mut self) -> Result<Option<types::Type>> {
     let target_ref = self.pop_object_ref()?;
 
@@ -39,6 +41,15 @@ impl JVM {
     Ok(Some(ret_value))
   }
 
+  // the cleared references are processed by the collection itself, none are pending
+  fn exec_native_java_lang_ref_reference_has_reference_pending_list(
+    &mut self,
+  ) -> Result<Option<types::Type>> {
+    let ret_value = types::Type::Boolean(false);
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+
   fn exec_native_java_lang_ref_reference_clear0(&mut self) -> Result<Option<types::Type>> {
     let reference_ref = self.pop_object_ref()?;
 
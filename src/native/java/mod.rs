@@ -1,2 +1,11 @@
 pub mod io;
 pub mod lang;
+pub mod nio;
+
+use crate::native::registry::NativeRegistry;
+
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  io::register_natives(registry);
+  lang::register_natives(registry);
+  nio::register_natives(registry);
+}
@@ -0,0 +1,95 @@
+use crate::{
+  native::registry::NativeRegistry,
+  runtime::{errors, jvm::*, types},
+};
+use color_eyre::eyre::{eyre, Result};
+
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  let class_name = "java/nio/MappedMemoryUtils";
+
+  registry.add(class_name, "isLoaded0", "(JJJ)Z", |jvm, _, _| {
+    jvm.exec_native_java_nio_mappedmemoryutils_is_loaded0()
+  });
+  registry.add(class_name, "load0", "(JJ)V", |jvm, _, _| {
+    jvm.exec_native_java_nio_mappedmemoryutils_madvise(libc::MADV_WILLNEED)
+  });
+  registry.add(class_name, "unload0", "(JJ)V", |jvm, _, _| {
+    jvm.exec_native_java_nio_mappedmemoryutils_madvise(libc::MADV_DONTNEED)
+  });
+  registry.add(
+    class_name,
+    "force0",
+    "(Ljava/io/FileDescriptor;JJ)V",
+    |jvm, _, _| jvm.exec_native_java_nio_mappedmemoryutils_force0(),
+  );
+}
+
+impl JVM {
+  // the address is aligned on a page, a mapping is loaded when all of its pages are resident
+  fn exec_native_java_nio_mappedmemoryutils_is_loaded0(&mut self) -> Result<Option<types::Type>> {
+    let page_count = self.pop_stack()?.as_long()? as usize;
+    let length = self.pop_stack()?.as_long()?;
+    let address = self.pop_stack()?.as_long()? as u64;
+
+    self.nativememory.check(address, length as u64)?;
+
+    let mut pages = vec![0u8; page_count];
+
+    let ret = unsafe {
+      libc::mincore(
+        address as *mut libc::c_void,
+        length as usize,
+        pages.as_mut_ptr(),
+      )
+    };
+
+    if ret != 0 {
+      return Err(eyre!(errors::JavaException::IO(format!(
+        "mincore failed: {}",
+        std::io::Error::last_os_error()
+      ))));
+    }
+
+    let loaded = types::Type::Boolean(pages.iter().all(|page| page & 1 != 0));
+
+    self.push_stack(loaded)?;
+
+    Ok(Some(loaded))
+  }
+
+  // loading and unloading are advices, the pages are read again on access
+  fn exec_native_java_nio_mappedmemoryutils_madvise(
+    &mut self,
+    advice: i32,
+  ) -> Result<Option<types::Type>> {
+    let length = self.pop_stack()?.as_long()?;
+    let address = self.pop_stack()?.as_long()? as u64;
+
+    self.nativememory.check(address, length as u64)?;
+
+    unsafe {
+      libc::madvise(address as *mut libc::c_void, length as usize, advice);
+    }
+
+    Ok(None)
+  }
+
+  fn exec_native_java_nio_mappedmemoryutils_force0(&mut self) -> Result<Option<types::Type>> {
+    let length = self.pop_stack()?.as_long()?;
+    let address = self.pop_stack()?.as_long()? as u64;
+    let _fd = self.pop_stack()?;
+
+    self.nativememory.check(address, length as u64)?;
+
+    let ret = unsafe { libc::msync(address as *mut libc::c_void, length as usize, libc::MS_SYNC) };
+
+    if ret != 0 {
+      return Err(eyre!(errors::JavaException::IO(format!(
+        "msync failed: {}",
+        std::io::Error::last_os_error()
+      ))));
+    }
+
+    Ok(None)
+  }
+}
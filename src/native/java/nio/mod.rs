@@ -0,0 +1,7 @@
+pub mod mappedmemoryutils;
+
+use crate::native::registry::NativeRegistry;
+
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  mappedmemoryutils::register_natives(registry);
+}
@@ -0,0 +1,11 @@
+pub mod nativeentrypoint;
+pub mod upcalllinker;
+pub mod upcallstubs;
+
+use crate::native::registry::NativeRegistry;
+
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  nativeentrypoint::register_natives(registry);
+  upcalllinker::register_natives(registry);
+  upcallstubs::register_natives(registry);
+}
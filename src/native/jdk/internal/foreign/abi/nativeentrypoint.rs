@@ -0,0 +1,57 @@
+use color_eyre::eyre::Result;
+use log::debug;
+
+use crate::{
+  native::registry::NativeRegistry,
+  runtime::{foreign::DowncallStub, jvm::*, types},
+};
+
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  let class_name = "jdk/internal/foreign/abi/NativeEntryPoint";
+
+  registry.add(class_name, "makeDowncallStub", "(Ljava/lang/invoke/MethodType;Ljdk/internal/foreign/abi/ABIDescriptor;[Ljdk/internal/foreign/abi/VMStorage;[Ljdk/internal/foreign/abi/VMStorage;ZIZ)J", |jvm, _, _| jvm.exec_native_nativeentrypoint_make_downcall_stub());
+  registry.add(class_name, "freeDowncallStub0", "(J)Z", |jvm, _, _| {
+    jvm.exec_native_nativeentrypoint_free_downcall_stub0()
+  });
+}
+
+impl JVM {
+  // the ABI is the SysV one of the stubs, the thread is not in a native state to leave
+  fn exec_native_nativeentrypoint_make_downcall_stub(&mut self) -> Result<Option<types::Type>> {
+    let _needs_transition = self.pop_ioperand()?;
+    let captured_state_mask = self.pop_ioperand()?;
+    let needs_return_buffer = self.pop_ioperand()? != 0;
+    let ret_moves_ref = self.pop_ref()?;
+    let arg_moves_ref = self.pop_ref()?;
+    let _abi = self.pop_ref()?;
+    let method_type_ref = self.pop_ref()?;
+
+    let descriptor = self.get_method_type_descriptor(method_type_ref)?;
+
+    let stub = DowncallStub::new(
+      &descriptor,
+      self.get_storages(arg_moves_ref)?,
+      self.get_storages(ret_moves_ref)?,
+      needs_return_buffer,
+      captured_state_mask,
+    );
+
+    debug!("downcall stub {:?}", stub);
+
+    let address = types::Type::Long(self.foreignmanager.add_downcall(stub) as i64);
+
+    self.push_stack(address)?;
+
+    Ok(Some(address))
+  }
+
+  fn exec_native_nativeentrypoint_free_downcall_stub0(&mut self) -> Result<Option<types::Type>> {
+    let address = self.pop_stack()?.as_long()?;
+
+    let freed = types::Type::Boolean(self.foreignmanager.remove_downcall(address as u64));
+
+    self.push_stack(freed)?;
+
+    Ok(Some(freed))
+  }
+}
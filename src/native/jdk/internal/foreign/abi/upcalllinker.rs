@@ -0,0 +1,44 @@
+use color_eyre::eyre::{eyre, Result};
+
+use crate::{
+  native::registry::NativeRegistry,
+  runtime::{errors, foreign::UpcallStub, jvm::*, types},
+};
+
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  let class_name = "jdk/internal/foreign/abi/UpcallLinker";
+
+  registry.add(class_name, "makeUpcallStub", "(Ljava/lang/invoke/MethodHandle;Ljdk/internal/foreign/abi/ABIDescriptor;Ljdk/internal/foreign/abi/UpcallLinker$CallRegs;ZJ)J", |jvm, _, _| jvm.exec_native_upcalllinker_make_upcall_stub());
+}
+
+impl JVM {
+  // the stub runs `target` with the registers of `conv` (a `CallRegs` record)
+  fn exec_native_upcalllinker_make_upcall_stub(&mut self) -> Result<Option<types::Type>> {
+    let return_buffer_size = self.pop_stack()?.as_long()?;
+    let needs_return_buffer = self.pop_ioperand()? != 0;
+    let conv_ref = self.pop_ref()?;
+    let _abi = self.pop_ref()?;
+    let target_ref = self.pop_ref()?;
+
+    if target_ref == 0 || conv_ref == 0 {
+      return Err(eyre!(errors::JavaException::NullPointer));
+    }
+
+    let conv = self.heap.get_obj_instance(conv_ref)?;
+    let arg_regs_ref = conv.get_field("argRegs")?.as_ref()?;
+    let ret_regs_ref = conv.get_field("retRegs")?.as_ref()?;
+
+    let stub = UpcallStub::new(
+      target_ref,
+      self.get_storages(arg_regs_ref)?,
+      self.get_storages(ret_regs_ref)?,
+      needs_return_buffer.then_some(return_buffer_size as u64),
+    );
+
+    let address = types::Type::Long(self.foreignmanager.add_upcall(stub)? as i64);
+
+    self.push_stack(address)?;
+
+    Ok(Some(address))
+  }
+}
@@ -0,0 +1,26 @@
+use color_eyre::eyre::Result;
+
+use crate::{
+  native::registry::NativeRegistry,
+  runtime::{jvm::*, types},
+};
+
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  let class_name = "jdk/internal/foreign/abi/UpcallStubs";
+
+  registry.add(class_name, "freeUpcallStub0", "(J)Z", |jvm, _, _| {
+    jvm.exec_native_upcallstubs_free_upcall_stub0()
+  });
+}
+
+impl JVM {
+  fn exec_native_upcallstubs_free_upcall_stub0(&mut self) -> Result<Option<types::Type>> {
+    let address = self.pop_stack()?.as_long()?;
+
+    let freed = types::Type::Boolean(self.foreignmanager.remove_upcall(address as u64));
+
+    self.push_stack(freed)?;
+
+    Ok(Some(freed))
+  }
+}
@@ -0,0 +1,7 @@
+pub mod abi;
+
+use crate::native::registry::NativeRegistry;
+
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  abi::register_natives(registry);
+}
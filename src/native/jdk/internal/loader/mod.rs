@@ -0,0 +1,7 @@
+pub mod rawnativelibraries;
+
+use crate::native::registry::NativeRegistry;
+
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  rawnativelibraries::register_natives(registry);
+}
@@ -0,0 +1,55 @@
+use color_eyre::eyre::Result;
+
+use crate::{
+  native::registry::NativeRegistry,
+  runtime::{foreign, jvm::*, types},
+};
+
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  let class_name = "jdk/internal/loader/RawNativeLibraries";
+
+  registry.add(
+    class_name,
+    "load0",
+    "(Ljdk/internal/loader/RawNativeLibraries$RawNativeLibraryImpl;Ljava/lang/String;)Z",
+    |jvm, _, _| jvm.exec_native_rawnativelibraries_load0(),
+  );
+  registry.add(
+    class_name,
+    "unload0",
+    "(Ljava/lang/String;J)V",
+    |jvm, _, _| jvm.exec_native_rawnativelibraries_unload0(),
+  );
+}
+
+impl JVM {
+  // the libraries of SymbolLookup.libraryLookup, loaded without calling JNI_OnLoad
+  fn exec_native_rawnativelibraries_load0(&mut self) -> Result<Option<types::Type>> {
+    let name = self.pop_string()?;
+    let impl_ref = self.pop_ref()?;
+
+    let handle = foreign::load_library(&name)?;
+
+    if handle != 0 {
+      self
+        .heap
+        .get_obj_instance_mut(impl_ref)?
+        .put_field("handle", types::Type::Long(handle as i64))?;
+    }
+
+    let loaded = types::Type::Boolean(handle != 0);
+
+    self.push_stack(loaded)?;
+
+    Ok(Some(loaded))
+  }
+
+  fn exec_native_rawnativelibraries_unload0(&mut self) -> Result<Option<types::Type>> {
+    let handle = self.pop_stack()?.as_long()?;
+    let _name = self.pop_ref()?;
+
+    foreign::unload_library(handle as u64);
+
+    Ok(None)
+  }
+}
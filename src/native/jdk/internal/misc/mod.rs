@@ -1 +1,7 @@
 pub mod r#unsafe;
+
+use crate::native::registry::NativeRegistry;
+
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  r#unsafe::register_natives(registry);
+}
@@ -2,125 +2,323 @@ use color_eyre::eyre::{eyre, Result};
 use log::{debug, warn};
 
 use crate::{
-  notimpl,
+  native::registry::NativeRegistry,
   runtime::{errors, jvm::*, types},
-  utils::get_index_scale,
+  utils::{get_index_scale, ju4},
 };
 
-impl JVM {
-  pub(crate) fn native_dispatcher_jdk_internal_misc_unsafe(
-    &mut self,
-    name: &str,
-    type_str: &str,
-  ) -> Result<Option<types::Type>> {
-    match (name, type_str) {
-      ("arrayBaseOffset0", "(Ljava/lang/Class;)I") => self.exec_native_array_base_offset0(),
-      ("arrayIndexScale0", "(Ljava/lang/Class;)I") => self.exec_native_array_index_scale0(),
-      ("fullFence", "()V") => self.exec_native_full_fence(),
-      ("objectFieldOffset1", "(Ljava/lang/Class;Ljava/lang/String;)J") => {
-        self.exec_native_object_field_offset1()
-      }
-      ("compareAndSetReference", "(Ljava/lang/Object;JLjava/lang/Object;Ljava/lang/Object;)Z") => {
-        self.exec_native_compare_and_set_reference()
-      }
-      ("compareAndSetLong", "(Ljava/lang/Object;JJJ)Z") => self.exec_native_compare_and_set_long(),
-      ("getReferenceVolatile", "(Ljava/lang/Object;J)Ljava/lang/Object;") => {
-        self.exec_native_get_reference_volatile()
-      }
-      ("putReferenceVolatile", "(Ljava/lang/Object;JLjava/lang/Object;)V") => {
-        self.exec_native_put_reference_volatile()
-      }
-      ("getLongVolatile", "(Ljava/lang/Object;J)J") => self.exec_unsafe_get_long_volatile(),
-      ("getIntVolatile", "(Ljava/lang/Object;J)I") => self.exec_unsafe_get_int_volatile(),
-      ("ensureClassInitialized0", "(Ljava/lang/Class;)V") => {
-        self.exec_native_ensure_class_initialized0()
-      }
-      ("getReference", "(Ljava/lang/Object;J)Ljava/lang/Object;") => {
-        self.exec_native_unsafe_get_reference()
-      }
-      ("putReference", "(Ljava/lang/Object;JLjava/lang/Object;)V") => {
-        self.exec_native_unsafe_put_reference()
-      }
-      ("copyMemory0", "(Ljava/lang/Object;JLjava/lang/Object;JJ)V") => {
-        self.exec_native_copy_memory0()
-      }
-      ("setMemory0", "(Ljava/lang/Object;JJB)V") => self.exec_native_set_memory0(),
-      ("getInt", "(Ljava/lang/Object;J)I") => self.exec_native_unsafe_get_int(),
-      ("getLong", "(Ljava/lang/Object;J)J") => self.exec_native_unsafe_get_long(),
-      ("getByte", "(Ljava/lang/Object;J)B") => self.exec_native_unsafe_get_byte(),
-      ("shouldBeInitialized0", "(Ljava/lang/Class;)Z") => self.exec_native_should_be_initialized0(),
-      ("compareAndSetInt", "(Ljava/lang/Object;JII)Z") => self.exec_native_compare_and_set_int(),
-      ("allocateMemory0", "(J)J") => self.exec_native_allocate_memory0(),
-      ("putByte", "(Ljava/lang/Object;JB)V") => self.exec_native_put_byte(),
-      ("freeMemory0", "(J)V") => self.exec_native_jdk_internal_misc_unsafe_free_memory0(),
-      _ => Err(eyre!(errors::InternalError::NativeNotImplemented(
-        "jdk/internal/misc/Unsafe".to_string(),
-        name.to_owned(),
-        type_str.to_owned()
-      ))),
-    }
+// the elements of the arrays have no header before them
+const ARRAY_BASE_OFFSET: i64 = 0;
+
+/// Variable accessed by Unsafe, given by a base object and an offset
+enum Location {
+  // (object, field name, field descriptor), the offset is the one of objectFieldOffset
+  Field(ju4, String, String),
+  // (class, field name, field descriptor), the base is the one of staticFieldBase
+  StaticField(String, String, String),
+  // (array, index) when the offset is the one of an element of the accessed type
+  ArrayElement(ju4, usize),
+  // (array, byte offset) for the bytes of a value of another type in a primitive array
+  ArrayBytes(ju4, i64),
+  // off-heap memory, the base is null
+  Address(u64),
+}
+
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  let class_name = "jdk/internal/misc/Unsafe";
+
+  registry.add(
+    class_name,
+    "arrayBaseOffset0",
+    "(Ljava/lang/Class;)I",
+    |jvm, _, _| jvm.exec_native_array_base_offset0(),
+  );
+  registry.add(
+    class_name,
+    "arrayIndexScale0",
+    "(Ljava/lang/Class;)I",
+    |jvm, _, _| jvm.exec_native_array_index_scale0(),
+  );
+  for name in ["fullFence", "loadFence", "storeFence"] {
+    registry.add(class_name, name, "()V", |jvm, _, _| jvm.exec_native_fence());
+  }
+  registry.add(
+    class_name,
+    "objectFieldOffset0",
+    "(Ljava/lang/reflect/Field;)J",
+    |jvm, _, _| jvm.exec_native_field_offset0(),
+  );
+  registry.add(
+    class_name,
+    "staticFieldOffset0",
+    "(Ljava/lang/reflect/Field;)J",
+    |jvm, _, _| jvm.exec_native_field_offset0(),
+  );
+  registry.add(
+    class_name,
+    "objectFieldOffset1",
+    "(Ljava/lang/Class;Ljava/lang/String;)J",
+    |jvm, _, _| jvm.exec_native_object_field_offset1(),
+  );
+  registry.add(
+    class_name,
+    "staticFieldBase0",
+    "(Ljava/lang/reflect/Field;)Ljava/lang/Object;",
+    |jvm, _, _| jvm.exec_native_static_field_base0(),
+  );
+  registry.add(
+    class_name,
+    "ensureClassInitialized0",
+    "(Ljava/lang/Class;)V",
+    |jvm, _, _| jvm.exec_native_ensure_class_initialized0(),
+  );
+  registry.add(
+    class_name,
+    "shouldBeInitialized0",
+    "(Ljava/lang/Class;)Z",
+    |jvm, _, _| jvm.exec_native_should_be_initialized0(),
+  );
+  registry.add(
+    class_name,
+    "allocateInstance",
+    "(Ljava/lang/Class;)Ljava/lang/Object;",
+    |jvm, _, _| jvm.exec_native_allocate_instance(),
+  );
+  registry.add(
+    class_name,
+    "throwException",
+    "(Ljava/lang/Throwable;)V",
+    |jvm, _, _| jvm.exec_native_throw_exception(),
+  );
+  for name in ["getBoolean", "getBooleanVolatile"] {
+    registry.add(class_name, name, "(Ljava/lang/Object;J)Z", |jvm, _, _| {
+      jvm.exec_native_unsafe_get("Z")
+    });
+  }
+  for name in ["getByte", "getByteVolatile"] {
+    registry.add(class_name, name, "(Ljava/lang/Object;J)B", |jvm, _, _| {
+      jvm.exec_native_unsafe_get("B")
+    });
+  }
+  for name in ["getShort", "getShortVolatile"] {
+    registry.add(class_name, name, "(Ljava/lang/Object;J)S", |jvm, _, _| {
+      jvm.exec_native_unsafe_get("S")
+    });
+  }
+  for name in ["getChar", "getCharVolatile"] {
+    registry.add(class_name, name, "(Ljava/lang/Object;J)C", |jvm, _, _| {
+      jvm.exec_native_unsafe_get("C")
+    });
+  }
+  for name in ["getInt", "getIntVolatile"] {
+    registry.add(class_name, name, "(Ljava/lang/Object;J)I", |jvm, _, _| {
+      jvm.exec_native_unsafe_get("I")
+    });
+  }
+  for name in ["getLong", "getLongVolatile"] {
+    registry.add(class_name, name, "(Ljava/lang/Object;J)J", |jvm, _, _| {
+      jvm.exec_native_unsafe_get("J")
+    });
+  }
+  for name in ["getFloat", "getFloatVolatile"] {
+    registry.add(class_name, name, "(Ljava/lang/Object;J)F", |jvm, _, _| {
+      jvm.exec_native_unsafe_get("F")
+    });
+  }
+  for name in ["getDouble", "getDoubleVolatile"] {
+    registry.add(class_name, name, "(Ljava/lang/Object;J)D", |jvm, _, _| {
+      jvm.exec_native_unsafe_get("D")
+    });
   }
+  for name in ["getReference", "getReferenceVolatile"] {
+    registry.add(
+      class_name,
+      name,
+      "(Ljava/lang/Object;J)Ljava/lang/Object;",
+      |jvm, _, _| jvm.exec_native_unsafe_get("Ljava/lang/Object;"),
+    );
+  }
+  for name in ["putBoolean", "putBooleanVolatile"] {
+    registry.add(class_name, name, "(Ljava/lang/Object;JZ)V", |jvm, _, _| {
+      jvm.exec_native_unsafe_put("Z")
+    });
+  }
+  for name in ["putByte", "putByteVolatile"] {
+    registry.add(class_name, name, "(Ljava/lang/Object;JB)V", |jvm, _, _| {
+      jvm.exec_native_unsafe_put("B")
+    });
+  }
+  for name in ["putShort", "putShortVolatile"] {
+    registry.add(class_name, name, "(Ljava/lang/Object;JS)V", |jvm, _, _| {
+      jvm.exec_native_unsafe_put("S")
+    });
+  }
+  for name in ["putChar", "putCharVolatile"] {
+    registry.add(class_name, name, "(Ljava/lang/Object;JC)V", |jvm, _, _| {
+      jvm.exec_native_unsafe_put("C")
+    });
+  }
+  for name in ["putInt", "putIntVolatile"] {
+    registry.add(class_name, name, "(Ljava/lang/Object;JI)V", |jvm, _, _| {
+      jvm.exec_native_unsafe_put("I")
+    });
+  }
+  for name in ["putLong", "putLongVolatile"] {
+    registry.add(class_name, name, "(Ljava/lang/Object;JJ)V", |jvm, _, _| {
+      jvm.exec_native_unsafe_put("J")
+    });
+  }
+  for name in ["putFloat", "putFloatVolatile"] {
+    registry.add(class_name, name, "(Ljava/lang/Object;JF)V", |jvm, _, _| {
+      jvm.exec_native_unsafe_put("F")
+    });
+  }
+  for name in ["putDouble", "putDoubleVolatile"] {
+    registry.add(class_name, name, "(Ljava/lang/Object;JD)V", |jvm, _, _| {
+      jvm.exec_native_unsafe_put("D")
+    });
+  }
+  for name in ["putReference", "putReferenceVolatile"] {
+    registry.add(
+      class_name,
+      name,
+      "(Ljava/lang/Object;JLjava/lang/Object;)V",
+      |jvm, _, _| jvm.exec_native_unsafe_put("Ljava/lang/Object;"),
+    );
+  }
+  registry.add(
+    class_name,
+    "compareAndSetInt",
+    "(Ljava/lang/Object;JII)Z",
+    |jvm, _, _| jvm.exec_native_compare_and_set("I", false),
+  );
+  registry.add(
+    class_name,
+    "compareAndSetLong",
+    "(Ljava/lang/Object;JJJ)Z",
+    |jvm, _, _| jvm.exec_native_compare_and_set("J", false),
+  );
+  registry.add(
+    class_name,
+    "compareAndSetReference",
+    "(Ljava/lang/Object;JLjava/lang/Object;Ljava/lang/Object;)Z",
+    |jvm, _, _| jvm.exec_native_compare_and_set("Ljava/lang/Object;", false),
+  );
+  registry.add(
+    class_name,
+    "compareAndExchangeInt",
+    "(Ljava/lang/Object;JII)I",
+    |jvm, _, _| jvm.exec_native_compare_and_set("I", true),
+  );
+  registry.add(
+    class_name,
+    "compareAndExchangeLong",
+    "(Ljava/lang/Object;JJJ)J",
+    |jvm, _, _| jvm.exec_native_compare_and_set("J", true),
+  );
+  registry.add(
+    class_name,
+    "compareAndExchangeReference",
+    "(Ljava/lang/Object;JLjava/lang/Object;Ljava/lang/Object;)Ljava/lang/Object;",
+    |jvm, _, _| jvm.exec_native_compare_and_set("Ljava/lang/Object;", true),
+  );
+  registry.add(class_name, "allocateMemory0", "(J)J", |jvm, _, _| {
+    jvm.exec_native_allocate_memory0()
+  });
+  registry.add(class_name, "reallocateMemory0", "(JJ)J", |jvm, _, _| {
+    jvm.exec_native_reallocate_memory0()
+  });
+  registry.add(class_name, "freeMemory0", "(J)V", |jvm, _, _| {
+    jvm.exec_native_jdk_internal_misc_unsafe_free_memory0()
+  });
+  registry.add(
+    class_name,
+    "setMemory0",
+    "(Ljava/lang/Object;JJB)V",
+    |jvm, _, _| jvm.exec_native_set_memory0(),
+  );
+  registry.add(
+    class_name,
+    "copyMemory0",
+    "(Ljava/lang/Object;JLjava/lang/Object;JJ)V",
+    |jvm, _, _| jvm.exec_native_copy_memory0(),
+  );
+  registry.add(
+    class_name,
+    "copySwapMemory0",
+    "(Ljava/lang/Object;JLjava/lang/Object;JJJ)V",
+    |jvm, _, _| jvm.exec_native_copy_swap_memory0(),
+  );
+  registry.add(class_name, "park", "(ZJ)V", |jvm, _, _| {
+    jvm.exec_native_park()
+  });
+  registry.add(
+    class_name,
+    "unpark",
+    "(Ljava/lang/Object;)V",
+    |jvm, _, _| jvm.exec_native_unpark(),
+  );
+  registry.add(class_name, "getLoadAverage0", "([DI)I", |jvm, _, _| {
+    jvm.exec_native_get_load_average0()
+  });
+}
 
+impl JVM {
   // private native int arrayBaseOffset0(Class<?> arrayClass);
   fn exec_native_array_base_offset0(&mut self) -> Result<Option<types::Type>> {
-    let array_class = self.pop_stack()?;
-
-    match array_class {
-      types::Type::ObjectRef(obj_ref) => {
-        let array_obj = self.heap.get_obj_instance(obj_ref)?;
+    let _array_class = self.pop_object_ref()?;
 
-        let raw_value_ref = array_obj.get_field("name")?;
+    let _this = self.pop_stack()?;
 
-        match raw_value_ref {
-          types::Type::ObjectRef(value_ref) => {
-            let value_obj = self.heap.get_obj_instance(value_ref)?;
+    let ret_value = types::Type::Integer(ARRAY_BASE_OFFSET as i32);
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
 
-            let value_field = value_obj.get_field("value")?;
+  // private native int arrayIndexScale0(Class<?> arrayClass);
+  fn exec_native_array_index_scale0(&mut self) -> Result<Option<types::Type>> {
+    let class_ref = self.pop_object_ref()?;
 
-            if let types::Type::ArrayRef(array_ref) = value_field {
-              let array = self.heap.get_array_instance(array_ref)?;
+    let _this = self.pop_stack()?;
 
-              array.print();
+    let classname = self.heap.get_classname_from_class_obj(class_ref)?; // [Z, [B, etc
 
-              // TODO: Fixed now
-              let ret_value = types::Type::Integer(0);
-              self.push_stack(ret_value)?;
-              return Ok(Some(ret_value));
-            }
-          }
-          _ => notimpl!(),
-        }
-      }
-      _ => notimpl!(),
+    if !classname.starts_with('[') {
+      return Err(eyre!(errors::JavaException::IllegalArgumentException(
+        format!("not an array class: {}", classname)
+      )));
     }
 
-    notimpl!();
+    let ret_value = types::Type::Integer(get_index_scale(&classname) as i32);
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
   }
 
-  // private static native int arrayIndexScale0(Class clazz);
-  fn exec_native_array_index_scale0(&mut self) -> Result<Option<types::Type>> {
-    let array_class = self.pop_stack()?;
+  // public native void fullFence(); and loadFence, storeFence
+  fn exec_native_fence(&mut self) -> Result<Option<types::Type>> {
+    let _this = self.pop_stack()?;
 
-    match array_class {
-      types::Type::ObjectRef(obj_ref) => {
-        let array_obj = self.heap.get_obj_instance(obj_ref)?; // java.lang.Class
-        let raw_value_ref = array_obj.get_field("name")?.as_ref()?;
+    warn!("Fences only work for single-threading");
 
-        let inner_class_name = self.heap.get_string(raw_value_ref)?; // [Z, [B, etc
-        let ret_value = types::Type::Integer(get_index_scale(&inner_class_name) as i32);
-        self.push_stack(ret_value)?;
-        Ok(Some(ret_value))
-      }
-      _ => notimpl!(),
-    }
+    // No-op if single-threaded
+    Ok(None)
   }
 
-  // public native void fullFence();
-  fn exec_native_full_fence(&mut self) -> Result<Option<types::Type>> {
-    warn!("Full Fence only works for single-threading");
+  // private native long objectFieldOffset0(Field f); and staticFieldOffset0
+  fn exec_native_field_offset0(&mut self) -> Result<Option<types::Type>> {
+    let field_ref = self.pop_object_ref()?;
 
-    // No-op if single-threaded
-    Ok(None)
+    let _this = self.pop_stack()?;
+
+    let (class_name, field_name) = self.get_reflected_field(field_ref)?;
+
+    let offset = self
+      .class_loader
+      .get_field_offset(&class_name, &field_name)?;
+
+    let ret_value = types::Type::Long(offset);
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
   }
 
   // public native long objectFieldOffset1(Class class, String field);
@@ -129,6 +327,8 @@ impl JVM {
 
     let class_name = self.pop_class_name()?;
 
+    let _this = self.pop_stack()?;
+
     let offset = self
       .class_loader
       .get_field_offset(&class_name, &field_name)?;
@@ -140,638 +340,584 @@ impl JVM {
     Ok(Some(ret_value))
   }
 
-  // public native boolean compareAndSetReference(Object obj, long offset, Object expected, Object
-  // newVal)
-  fn exec_native_compare_and_set_reference(&mut self) -> Result<Option<types::Type>> {
-    warn!("Compare and set reference only works for single-threading");
-
-    let new_value = self.pop_stack()?;
-
-    let expected_value = self.pop_ref()?;
-
-    let offset = self.pop_loperand()?;
-
-    let obj_ref = self.pop_ref()?;
-
-    let obj = self.heap.get_instance_mut(obj_ref)?;
-
-    let status = match obj {
-      types::Instance::ObjectInstance(obj) => {
-        let class_name = obj.get_classname();
-
-        let field = self.class_loader.get_field_by_offset(class_name, offset)?;
-        let field_name = field.get_name();
-
-        let field = obj.get_field(field_name)?;
-
-        let field_value = match field {
-          types::Type::ObjectRef(val) => val,
-          types::Type::ArrayRef(val) => val,
-          types::Type::Null => 0,
-          other => return Err(eyre!("Expected ref at offset, got {:?}", other)),
-        };
-
-        if field_value == expected_value {
-          obj.put_field(field_name, new_value)?;
-
-          true
-        } else {
-          false
-        }
-      }
-      types::Instance::ArrayInstance(obj) => {
-        let field = obj.get_with_index_scale(offset as usize)?;
+  // private native Object staticFieldBase0(Field f);
+  fn exec_native_static_field_base0(&mut self) -> Result<Option<types::Type>> {
+    let field_ref = self.pop_object_ref()?;
 
-        let field_value = match field {
-          types::Type::ObjectRef(val) => *val,
-          types::Type::ArrayRef(val) => *val,
-          types::Type::Null => 0,
-          other => return Err(eyre!("Expected ref at offset, got {:?}", other)),
-        };
+    let _this = self.pop_stack()?;
 
-        if field_value == expected_value {
-          obj.set_with_index_scale(offset as usize, new_value)?;
+    let (class_name, _) = self.get_reflected_field(field_ref)?;
 
-          true
-        } else {
-          false
-        }
-      }
-    };
+    // the base is the instance holding the static fields, like MethodHandleNatives.staticFieldBase
+    self
+      .heap
+      .get_class_instance(&mut self.class_loader, &class_name)?;
+    let base_ref = self.heap.get_static_class_instance(&class_name)?.get_ref();
 
-    let ret_value = types::Type::Boolean(status);
+    let ret_value = types::Type::ObjectRef(base_ref);
     self.push_stack(ret_value)?;
     Ok(Some(ret_value))
   }
 
-  // public native boolean compareAndSetLong(Object obj, long offset, long expected, long newVal)
-  fn exec_native_compare_and_set_long(&mut self) -> Result<Option<types::Type>> {
-    warn!("Compare and set long only works for single-threading");
-
-    let new_value = self.pop_loperand()?;
-
-    let expected_value = self.pop_loperand()?;
-
-    let offset = self.pop_loperand()?;
-
-    let obj_ref = self.pop_ref()?;
-
-    let status = if obj_ref == 0 {
-      debug!("static field {}", offset);
-
-      let field = self
-        .class_loader
-        .get_field_by_offset("java/lang/Thread", offset)?;
-      let field_name = field.get_name();
-
-      let mut class = self.class_loader.get_mut("java/lang/Thread")?;
+  // private native void ensureClassInitialized0(Class<?> c);
+  fn exec_native_ensure_class_initialized0(&mut self) -> Result<Option<types::Type>> {
+    let class_name = self.pop_class_name()?;
 
-      let field = class.get_static_field(field_name)?;
+    let _this = self.pop_stack()?;
 
-      let field_value = field.as_long()?;
+    self.init_class(&class_name)?;
 
-      if field_value == expected_value {
-        class.put_static_field(field_name, types::Type::Long(new_value))?;
+    Ok(None)
+  }
 
-        true
-      } else {
-        false
-      }
-    } else {
-      let obj = self.heap.get_instance_mut(obj_ref)?;
+  // private native boolean shouldBeInitialized0(Class<?> c);
+  pub fn exec_native_should_be_initialized0(&mut self) -> Result<Option<types::Type>> {
+    let class_ref = self.pop_object_ref()?;
 
-      match obj {
-        types::Instance::ObjectInstance(obj) => {
-          let class_name = obj.get_classname();
+    let _this = self.pop_stack()?;
 
-          let field = self.class_loader.get_field_by_offset(class_name, offset)?;
-          let field_name = field.get_name();
+    let class = self
+      .heap
+      .get_class_from_class_obj(&mut self.class_loader, class_ref)?;
 
-          let field = obj.get_field(field_name)?;
+    let should_init = !class.get_init();
+    drop(class);
 
-          let field_value = field.as_long()?;
+    let ret_value = types::Type::Boolean(should_init);
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
 
-          if field_value == expected_value {
-            obj.put_field(field_name, types::Type::Long(new_value))?;
+  // public native Object allocateInstance(Class<?> cls) throws InstantiationException;
+  fn exec_native_allocate_instance(&mut self) -> Result<Option<types::Type>> {
+    let class_name = self.pop_class_name()?;
 
-            true
-          } else {
-            false
-          }
-        }
-        types::Instance::ArrayInstance(obj) => {
-          let field = obj.get_with_index_scale(offset as usize)?;
+    let _this = self.pop_stack()?;
 
-          let field_value = field.as_long()?;
+    let instantiable = !types::Type::is_primitive(&class_name) && !class_name.starts_with('[') && {
+      let class = self.class_loader.get(&class_name)?;
+      !class.is_interface() && !class.is_abstract()
+    };
 
-          if field_value == expected_value {
-            obj.set_with_index_scale(offset as usize, types::Type::Long(new_value))?;
+    if !instantiable {
+      return Err(eyre!(errors::JavaException::InstantiationException(
+        class_name
+      )));
+    }
 
-            true
-          } else {
-            false
-          }
-        }
-      }
-    };
+    // the constructor is not called
+    self.init_class(&class_name)?;
+    let ret_value = self.heap.alloc_obj(&mut self.class_loader, &class_name)?;
 
-    let ret_value = types::Type::Boolean(status);
     self.push_stack(ret_value)?;
     Ok(Some(ret_value))
   }
 
-  // public native Object getReferenceVolatile(Object obj, long offset)
-  fn exec_native_get_reference_volatile(&mut self) -> Result<Option<types::Type>> {
-    warn!("get reference only works for single-threading");
+  // public native void throwException(Throwable ee);
+  fn exec_native_throw_exception(&mut self) -> Result<Option<types::Type>> {
+    let exception_ref = self.pop_ref()?;
 
-    let offset = self.pop_loperand()?;
+    let _this = self.pop_stack()?;
 
-    let obj_ref = self.pop_ref()?;
+    if exception_ref == 0 {
+      return Err(eyre!(errors::JavaException::NullPointer));
+    }
 
-    let obj = self.heap.get_instance(obj_ref)?;
+    Err(eyre!(self.java_exception_from_obj(exception_ref)?))
+  }
 
-    let ret_value = match obj {
-      types::Instance::ObjectInstance(obj) => {
-        let obj_classname = obj.get_classname();
-        let field = self
-          .class_loader
-          .get_field_by_offset(obj_classname, offset)?;
-        let field_name = field.get_name();
+  // public native T getT(Object o, long offset); and getTVolatile
+  fn exec_native_unsafe_get(&mut self, var_type: &str) -> Result<Option<types::Type>> {
+    let offset = self.pop_loperand()?;
 
-        let value = if field.is_static() {
-          let class = self.class_loader.get(obj_classname)?;
-          let field_type = class.get_static_field(field_name)?;
-          *field_type
-        } else {
-          obj.get_field(field_name)?
-        };
+    let base = self.pop_stack()?;
 
-        match value {
-          types::Type::ObjectRef(_) => value,
-          _ => return Err(eyre!("Expected ObjectRef at offset, got {:?}", value)),
-        }
-      }
-      types::Instance::ArrayInstance(obj) => {
-        let value = obj.get_with_index_scale(offset as usize)?;
-        *value
-      }
-    };
+    let _this = self.pop_stack()?;
+
+    let location = self.get_location(base, offset, var_type)?;
+    let ret_value = self.read_location(&location, var_type)?;
 
     self.push_stack(ret_value)?;
     Ok(Some(ret_value))
   }
 
-  // public native void putReferenceVolatile(Object obj, long offset, Object newValue)
-  fn exec_native_put_reference_volatile(&mut self) -> Result<Option<types::Type>> {
-    warn!("put reference only works for single-threading");
-
-    let new_value = self.pop_stack()?;
+  // public native void putT(Object o, long offset, T x); and putTVolatile
+  fn exec_native_unsafe_put(&mut self, var_type: &str) -> Result<Option<types::Type>> {
+    let value = self.pop_stack()?;
 
     let offset = self.pop_loperand()?;
 
-    let obj_ref = self.pop_ref()?;
-
-    let obj = self.heap.get_instance_mut(obj_ref)?;
+    let base = self.pop_stack()?;
 
-    match obj {
-      types::Instance::ObjectInstance(obj) => {
-        let obj_classname = obj.get_classname();
-        let field = self
-          .class_loader
-          .get_field_by_offset(obj_classname, offset)?;
-        let field_name = field.get_name();
+    let _this = self.pop_stack()?;
 
-        if field.is_static() {
-          let mut class = self.class_loader.get_mut(obj_classname)?;
-          class.put_static_field(field_name, new_value)?;
-        } else {
-          obj.put_field(field_name, new_value)?;
-        }
-      }
-      types::Instance::ArrayInstance(obj) => {
-        obj.set_with_index_scale(offset as usize, new_value)?;
-      }
-    };
+    let location = self.get_location(base, offset, var_type)?;
+    self.write_location(&location, var_type, value)?;
 
     Ok(None)
   }
 
-  fn exec_unsafe_get_long_volatile(&mut self) -> Result<Option<types::Type>> {
-    // TODO:
-    warn!("GetLongVolatile not fully implemented!");
-
-    let offset = self.pop_loperand()?;
+  // public final native boolean compareAndSetT(Object o, long offset, T expected, T x); and
+  // compareAndExchangeT, returning the witness value
+  fn exec_native_compare_and_set(
+    &mut self,
+    var_type: &str,
+    exchange: bool,
+  ) -> Result<Option<types::Type>> {
+    warn!("Compare and set only works for single-threading");
 
-    let obj_ref = self.pop_ref()?;
+    let new_value = self.pop_stack()?;
 
-    let field = self.get_volatile_field(offset, obj_ref)?;
+    let expected_value = self.pop_stack()?;
 
-    let field_value = field.as_long()?;
+    let offset = self.pop_loperand()?;
 
-    let ret_value = types::Type::Long(field_value);
-    self.push_stack(ret_value)?;
-    Ok(None)
-  }
+    let base = self.pop_stack()?;
 
-  fn exec_unsafe_get_int_volatile(&mut self) -> Result<Option<types::Type>> {
-    // TODO:
-    warn!("GetIntVolatile not fully implemented!");
+    let _this = self.pop_stack()?;
 
-    let offset = self.pop_loperand()?;
+    let location = self.get_location(base, offset, var_type)?;
+    let current_value = self.read_location(&location, var_type)?;
 
-    let obj_ref = self.pop_ref()?;
+    // references are compared by identity
+    let same = if types::Type::is_primitive(var_type) {
+      current_value.as_long()? == expected_value.as_long()?
+    } else {
+      current_value.as_ref()? == expected_value.as_ref()?
+    };
 
-    let field = self.get_volatile_field(offset, obj_ref)?;
+    if same {
+      self.write_location(&location, var_type, new_value)?;
+    }
 
-    let field_value = field.as_integer()?;
+    let ret_value = if exchange {
+      current_value
+    } else {
+      types::Type::Boolean(same)
+    };
 
-    let ret_value = types::Type::Integer(field_value);
     self.push_stack(ret_value)?;
     Ok(Some(ret_value))
   }
 
-  fn exec_native_ensure_class_initialized0(&mut self) -> Result<Option<types::Type>> {
-    let class_ref = self.pop_object_ref()?;
-
-    let class_obj = self.heap.get_obj_instance(class_ref)?;
-
-    let class_name_field = class_obj.get_field("name")?.as_ref()?;
+  // private native long allocateMemory0(long bytes);
+  fn exec_native_allocate_memory0(&mut self) -> Result<Option<types::Type>> {
+    let size = self.pop_loperand()?;
 
-    let class_name_str = self.heap.get_string(class_name_field)?;
+    let _this = self.pop_stack()?;
 
-    self.init_class(&class_name_str)?;
+    let address = self.nativememory.alloc(size as u64)?;
 
-    Ok(None)
+    let ret_value = types::Type::Long(address as i64);
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
   }
 
-  fn exec_native_unsafe_get_reference(&mut self) -> Result<Option<types::Type>> {
-    warn!("jdk/internal/misc/Unsafe.getReference not properly implemented");
-    let offset = self.pop_loperand()?;
+  // private native long reallocateMemory0(long address, long bytes);
+  fn exec_native_reallocate_memory0(&mut self) -> Result<Option<types::Type>> {
+    let size = self.pop_loperand()?;
 
-    let obj = self.pop_stack()?;
+    let address = self.pop_loperand()?;
 
     let _this = self.pop_stack()?;
 
-    let ret_value = if obj == types::Type::Null {
-      types::Type::Null
+    let address = if address == 0 {
+      self.nativememory.alloc(size as u64)?
     } else {
-      let obj_ref = obj.as_ref()?;
-
-      let instance = self.heap.get_obj_instance(obj_ref)?;
-      let instance_classname = instance.get_classname();
-
-      let field = self
-        .class_loader
-        .get_field_by_offset(instance.get_classname(), offset)?;
-      let field_name = field.get_name();
-
-      if field.is_static() {
-        let class = self.class_loader.get(instance_classname)?;
-        let field_type = class.get_static_field(field_name)?;
-        *field_type
-      } else {
-        instance.get_field(field_name)?
-      }
+      self.nativememory.realloc(address as u64, size as u64)?
     };
 
+    let ret_value = types::Type::Long(address as i64);
     self.push_stack(ret_value)?;
     Ok(Some(ret_value))
   }
 
-  fn exec_native_unsafe_put_reference(&mut self) -> Result<Option<types::Type>> {
-    warn!("jdk/internal/misc/Unsafe.putReference not properly implemented");
+  // private native void freeMemory0(long address);
+  fn exec_native_jdk_internal_misc_unsafe_free_memory0(&mut self) -> Result<Option<types::Type>> {
+    let address = self.pop_loperand()?;
 
-    let value = self.pop_stack()?;
+    let _this = self.pop_stack()?;
 
-    let offset = self.pop_loperand()?;
+    self.nativememory.free(address as u64)?;
 
-    let base = self.pop_stack()?;
+    Ok(None)
+  }
 
-    let _this = self.pop_stack()?;
+  // private native void setMemory0(Object o, long offset, long bytes, byte value);
+  fn exec_native_set_memory0(&mut self) -> Result<Option<types::Type>> {
+    let value = self.pop_stack()?.as_byte()? as u8;
 
-    if base == types::Type::Null {
-      warn!("jdk/internal/misc/Unsafe.putReference not implemented for static");
-      notimpl!()
-    }
+    let size = self.pop_loperand()?;
 
-    let base_ref = base.as_ref()?;
+    let offset = self.pop_loperand()?;
 
-    let instance = self.heap.get_obj_instance_mut(base_ref)?;
-    let instance_classname = instance.get_classname();
+    let base = self.pop_stack()?; // can be null
 
-    let field = self
-      .class_loader
-      .get_field_by_offset(instance_classname, offset)?;
-    let field_name = field.get_name();
+    let _this = self.pop_stack()?;
 
-    // if the field is static
-    if field.is_static() {
-      let mut class = self.class_loader.get_mut(instance_classname)?;
-      class.put_static_field(field_name, value)?;
-    } else {
-      instance.put_field(field_name, value)?;
-    }
+    self.write_memory(base, offset, &vec![value; size as usize])?;
 
     Ok(None)
   }
 
+  // private native void copyMemory0(Object srcBase, long srcOffset, Object destBase, long
+  // destOffset, long bytes);
   fn exec_native_copy_memory0(&mut self) -> Result<Option<types::Type>> {
     let size = self.pop_loperand()?;
 
     let dest_offset = self.pop_loperand()?;
 
-    let dest_obj = self.pop_stack()?;
-
-    let src_offset = self.pop_loperand()? as usize;
-
-    let src_obj = self.pop_stack()?;
-
-    // Get source pointer
-    if let types::Type::ArrayRef(src_ref) = src_obj {
-      let src = self.heap.get_array_instance(src_ref)?;
-
-      if let types::Type::ArrayRef(dst_ref) = dest_obj {
-        let _dest = self.heap.get_array_instance(dst_ref)?;
-
-        panic!()
-      } else if dest_obj == types::Type::Null {
-        self.nativememory.is_valid(dest_offset as u64);
+    let dest_base = self.pop_stack()?;
 
-        let dest: *mut u8 = dest_offset as *mut u8;
+    let src_offset = self.pop_loperand()?;
 
-        for i in 0..(size as usize) {
-          let val = src.get(src_offset + i)?.as_byte()?;
+    let src_base = self.pop_stack()?;
 
-          let addr = dest.wrapping_add(i);
+    let _this = self.pop_stack()?;
 
-          unsafe {
-            *addr = val as u8;
-          }
-        }
-      } else {
-        return Err(eyre!("Invalid srcBase type"));
-      };
-    } else if src_obj == types::Type::Null {
-      panic!()
-    } else {
-      return Err(eyre!("Invalid srcBase type"));
-    };
+    let bytes = self.read_memory(src_base, src_offset, size as usize)?;
+    self.write_memory(dest_base, dest_offset, &bytes)?;
 
     Ok(None)
   }
 
-  fn exec_native_set_memory0(&mut self) -> Result<Option<types::Type>> {
-    let value = self.pop_stack()?.as_byte()? as u8;
+  // private native void copySwapMemory0(Object srcBase, long srcOffset, Object destBase, long
+  // destOffset, long bytes, long elemSize);
+  fn exec_native_copy_swap_memory0(&mut self) -> Result<Option<types::Type>> {
+    let element_size = self.pop_loperand()?;
 
-    let size = self.pop_stack()?.as_long()? as usize;
+    let size = self.pop_loperand()?;
 
-    let offset = self.pop_stack()?.as_long()? as usize;
+    let dest_offset = self.pop_loperand()?;
 
-    let base = self.pop_stack()?; // can be null
-    match base {
-      types::Type::Null => {
-        // Off-heap memory
-        self.nativememory.is_valid(offset as u64);
+    let dest_base = self.pop_stack()?;
 
-        let dest: *mut u8 = offset as *mut u8;
+    let src_offset = self.pop_loperand()?;
 
-        for i in 0..size {
-          let addr = dest.wrapping_add(i);
+    let src_base = self.pop_stack()?;
 
-          unsafe {
-            *addr = value;
-          }
-        }
-      }
-      types::Type::ArrayRef(array_ref) => {
-        let array = self.heap.get_array_instance_mut(array_ref)?;
+    let _this = self.pop_stack()?;
 
-        let array_len = array.len();
+    let mut bytes = self.read_memory(src_base, src_offset, size as usize)?;
 
-        for i in 0..array_len {
-          array.set(i, types::Type::Byte(value as i8))?;
-        }
-      }
-      _ => panic!(),
+    for element in bytes.chunks_mut(element_size as usize) {
+      element.reverse();
     }
 
+    self.write_memory(dest_base, dest_offset, &bytes)?;
+
     Ok(None)
   }
 
-  fn exec_native_unsafe_get_int(&mut self) -> Result<Option<types::Type>> {
-    // this offset is computed based on index scale (works for real array but not for my array)
-    let offset = self.pop_stack()?.as_long()? as usize;
+  // public native void park(boolean isAbsolute, long time);
+  fn exec_native_park(&mut self) -> Result<Option<types::Type>> {
+    let _time = self.pop_loperand()?;
 
-    let obj = self.pop_stack()?;
+    let _absolute = self.pop_stack()?;
 
-    let ret = match obj {
-      types::Type::ArrayRef(obj_ref) => {
-        let src = self.heap.get_array_instance(obj_ref)?;
+    let _this = self.pop_stack()?;
 
-        types::Type::Integer(src.get_with_index_scale(offset)?.as_integer()?)
-      }
-      types::Type::Null => {
-        dbg!();
+    // no other thread can unpark the current one
+    warn!("Park only works for single-threading");
 
-        let src: *const i32 = offset as *const i32;
+    Ok(None)
+  }
 
-        debug!("{:?} {:?} {:?}", src, src.is_null(), src.is_aligned());
+  // public native void unpark(Object thread);
+  fn exec_native_unpark(&mut self) -> Result<Option<types::Type>> {
+    let _thread = self.pop_stack()?;
 
-        self.nativememory.is_valid(offset as u64);
+    let _this = self.pop_stack()?;
 
-        types::Type::Integer(unsafe { *src })
-      }
-      _ => return Err(eyre!("Invalid srcBase type")),
-    };
+    warn!("Unpark only works for single-threading");
 
-    self.push_stack(ret)?;
-    Ok(Some(ret))
+    Ok(None)
   }
 
-  fn exec_native_unsafe_get_long(&mut self) -> Result<Option<types::Type>> {
-    // this offset is computed based on index scale (works for real array but not for my array)
-    let offset = self.pop_stack()?.as_long()? as usize;
+  // private native int getLoadAverage0(double[] loadavg, int nelems);
+  fn exec_native_get_load_average0(&mut self) -> Result<Option<types::Type>> {
+    let count = self.pop_ioperand()?;
 
-    let obj = self.pop_stack()?;
+    let array_ref = self.pop_array_ref()?;
 
-    let ret = match obj {
-      types::Type::ArrayRef(obj_ref) => {
-        let src = self.heap.get_array_instance(obj_ref)?;
+    let _this = self.pop_stack()?;
 
-        types::Type::Long(src.get_with_index_scale(offset)?.as_long()?)
-      }
-      types::Type::Null => {
-        self.nativememory.is_valid(offset as u64);
+    let mut averages = [0f64; 3];
+    let count = unsafe { libc::getloadavg(averages.as_mut_ptr(), count.clamp(0, 3)) };
 
-        let src: *mut u64 = offset as *mut u64;
+    let array = self.heap.get_array_instance_mut(array_ref)?;
 
-        types::Type::Long(unsafe { *src as i64 })
-      }
-      _ => return Err(eyre!("Invalid srcBase type")),
-    };
+    for (i, average) in averages.iter().take(count.max(0) as usize).enumerate() {
+      array.set(i, types::Type::Double(*average))?;
+    }
 
-    self.push_stack(ret)?;
-    Ok(Some(ret))
+    let ret_value = types::Type::Integer(count);
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
   }
 
-  fn exec_native_unsafe_get_byte(&mut self) -> Result<Option<types::Type>> {
-    // this offset is computed based on index scale (works for real array but not for my array)
-    let offset = self.pop_stack()?.as_long()? as usize;
+  // class and name of a java.lang.reflect.Field
+  fn get_reflected_field(&mut self, field_ref: ju4) -> Result<(String, String)> {
+    let field_obj = self.heap.get_obj_instance(field_ref)?;
 
-    let obj = self.pop_stack()?;
+    let class_ref = field_obj.get_field("clazz")?.as_ref()?;
+    let name_ref = field_obj.get_field("name")?.as_ref()?;
 
-    let ret = match obj {
-      types::Type::ArrayRef(obj_ref) => {
-        let src = self.heap.get_array_instance(obj_ref)?;
+    let class_name = self.heap.get_classname_from_class_obj(class_ref)?;
+    let field_name = self.heap.get_string(name_ref)?;
 
-        types::Type::Byte(src.get_with_index_scale(offset)?.as_byte()?)
-      }
+    Ok((class_name, field_name))
+  }
+
+  // variable of type `var_type` at `offset` of `base`
+  fn get_location(&mut self, base: types::Type, offset: i64, var_type: &str) -> Result<Location> {
+    let location = match base {
       types::Type::Null => {
-        self.nativememory.is_valid(offset as u64);
+        let address = offset as u64;
 
-        let src: *mut u8 = offset as *mut u8;
+        self
+          .nativememory
+          .check(address, get_value_size(var_type) as u64)?;
 
-        types::Type::Byte(unsafe { *src as i8 })
+        Location::Address(address)
       }
-      _ => return Err(eyre!("Invalid srcBase type")),
-    };
-
-    self.push_stack(ret)?;
-    Ok(Some(ret))
-  }
+      types::Type::ArrayRef(array_ref) => {
+        let array = self.heap.get_array_instance(array_ref)?;
+        let element_type = &array.get_classname()[1..];
 
-  pub fn exec_native_should_be_initialized0(&mut self) -> Result<Option<types::Type>> {
-    let class_ref = self.pop_object_ref()?;
+        let scale = get_index_scale(array.get_classname()) as i64;
+        let offset = offset - ARRAY_BASE_OFFSET;
 
-    let class = self
-      .heap
-      .get_class_from_class_obj(&mut self.class_loader, class_ref)?;
+        let same_type = element_type == var_type
+          || (!types::Type::is_primitive(element_type) && !types::Type::is_primitive(var_type));
 
-    let should_init = !class.get_init();
-    drop(class);
+        if same_type && offset % scale == 0 {
+          let index = offset / scale;
 
-    let ret_value = types::Type::Boolean(should_init);
-    self.push_stack(ret_value)?;
-    Ok(Some(ret_value))
-  }
+          if index < 0 || index >= array.len() as i64 {
+            return Err(eyre!(errors::JavaException::ArrayIndexOutOfBounds(
+              index.max(0) as usize,
+              array.len()
+            )));
+          }
 
-  // public native boolean compareAndSetInt(Object obj, long offset, int expected, int newVal)
-  fn exec_native_compare_and_set_int(&mut self) -> Result<Option<types::Type>> {
-    warn!("Compare and set int only works for single-threading");
+          Location::ArrayElement(array_ref, index as usize)
+        } else if types::Type::is_primitive(element_type) && types::Type::is_primitive(var_type) {
+          Location::ArrayBytes(array_ref, offset)
+        } else {
+          return Err(eyre!(errors::InternalError::General(format!(
+            "{} accessed at offset {} of {}",
+            var_type,
+            offset,
+            array.get_classname()
+          ))));
+        }
+      }
+      types::Type::ObjectRef(obj_ref) => {
+        let classname = self
+          .heap
+          .get_obj_instance(obj_ref)?
+          .get_classname()
+          .to_string();
 
-    let new_value = self.pop_ioperand()?;
+        let field = self.class_loader.get_field_by_offset(&classname, offset)?;
+        let field_name = field.get_name().to_string();
+        let descriptor = field.get_descriptor().to_string();
 
-    let expected_value = self.pop_ioperand()?;
+        // the base of a static field is the instance given by staticFieldBase
+        if field.is_static() {
+          Location::StaticField(classname, field_name, descriptor)
+        } else {
+          Location::Field(obj_ref, field_name, descriptor)
+        }
+      }
+      base => return Err(eyre!(errors::InternalError::WrongType("Reference", base))),
+    };
 
-    let offset = self.pop_loperand()?;
+    Ok(location)
+  }
 
-    let obj_ref = self.pop_ref()?;
+  fn read_location(&mut self, location: &Location, var_type: &str) -> Result<types::Type> {
+    let value = match location {
+      Location::Field(obj_ref, name, _) => self.heap.get_obj_instance(*obj_ref)?.get_field(name)?,
+      Location::StaticField(classname, name, _) => {
+        *self.class_loader.get(classname)?.get_static_field(name)?
+      }
+      Location::ArrayElement(array_ref, index) => {
+        *self.heap.get_array_instance(*array_ref)?.get(*index)?
+      }
+      Location::ArrayBytes(array_ref, offset) => {
+        let bytes = self.read_array_bytes(*array_ref, *offset, get_value_size(var_type))?;
+        from_bytes(var_type, &bytes)?
+      }
+      Location::Address(address) => {
+        let bytes = self
+          .nativememory
+          .read(*address, get_value_size(var_type) as u64)?;
+        from_bytes(var_type, &bytes)?
+      }
+    };
 
-    let status = if obj_ref == 0 {
-      debug!("static field {}", offset);
-      let field = self
-        .class_loader
-        .get_field_by_offset("java/lang/Thread", offset)?;
-      let field_name = field.get_name();
+    types::Type::to_stored_value(var_type, value)
+  }
 
-      let mut class = self.class_loader.get_mut("java/lang/Thread")?;
+  fn write_location(
+    &mut self,
+    location: &Location,
+    var_type: &str,
+    value: types::Type,
+  ) -> Result<()> {
+    match location {
+      Location::Field(obj_ref, name, descriptor) => {
+        let value = types::Type::to_stored_value(descriptor, value)?;
+
+        self
+          .heap
+          .get_obj_instance_mut(*obj_ref)?
+          .put_field(name, value)
+      }
+      Location::StaticField(classname, name, descriptor) => {
+        let value = types::Type::to_stored_value(descriptor, value)?;
 
-      let field = class.get_static_field(field_name)?;
+        self
+          .class_loader
+          .get_mut(classname)?
+          .put_static_field(name, value)
+      }
+      Location::ArrayElement(array_ref, index) => {
+        let array = self.heap.get_array_instance_mut(*array_ref)?;
+        let value = types::Type::to_stored_value(&array.get_classname()[1..], value)?;
 
-      let field_value = field.as_integer()?;
+        array.set(*index, value)
+      }
+      Location::ArrayBytes(array_ref, offset) => {
+        self.write_array_bytes(*array_ref, *offset, &to_bytes(var_type, value)?)
+      }
+      Location::Address(address) => self
+        .nativememory
+        .write(*address, &to_bytes(var_type, value)?),
+    }
+  }
 
-      if field_value == expected_value {
-        class.put_static_field(field_name, types::Type::Integer(new_value))?;
+  // bytes at `offset` of off-heap memory (null base) or of a primitive array
+  fn read_memory(&self, base: types::Type, offset: i64, size: usize) -> Result<Vec<u8>> {
+    match base {
+      types::Type::Null => self.nativememory.read(offset as u64, size as u64),
+      types::Type::ArrayRef(array_ref) => {
+        self.read_array_bytes(array_ref, offset - ARRAY_BASE_OFFSET, size)
+      }
+      base => Err(eyre!(errors::InternalError::WrongType("Array", base))),
+    }
+  }
 
-        true
-      } else {
-        false
+  fn write_memory(&mut self, base: types::Type, offset: i64, bytes: &[u8]) -> Result<()> {
+    match base {
+      types::Type::Null => self.nativememory.write(offset as u64, bytes),
+      types::Type::ArrayRef(array_ref) => {
+        self.write_array_bytes(array_ref, offset - ARRAY_BASE_OFFSET, bytes)
       }
-    } else {
-      let obj = self.heap.get_instance_mut(obj_ref)?;
+      base => Err(eyre!(errors::InternalError::WrongType("Array", base))),
+    }
+  }
 
-      match obj {
-        types::Instance::ObjectInstance(obj) => {
-          let class_name = obj.get_classname();
+  // bytes of the elements of a primitive array, in the native (little endian) order
+  fn read_array_bytes(&self, array_ref: ju4, offset: i64, size: usize) -> Result<Vec<u8>> {
+    let array = self.heap.get_array_instance(array_ref)?;
+    let element_type = &array.get_classname()[1..];
 
-          let field = self.class_loader.get_field_by_offset(class_name, offset)?;
-          let field_name = field.get_name();
+    let scale = check_array_range(array.get_classname(), array.len(), offset, size)?;
 
-          let field = obj.get_field(field_name)?;
+    let mut bytes = Vec::with_capacity(size);
 
-          let field_value = field.as_integer()?;
+    for position in offset as usize..offset as usize + size {
+      let element = to_bytes(element_type, *array.get(position / scale)?)?;
 
-          if field_value == expected_value {
-            obj.put_field(field_name, types::Type::Integer(new_value))?;
+      bytes.push(element[position % scale]);
+    }
 
-            true
-          } else {
-            false
-          }
-        }
-        types::Instance::ArrayInstance(obj) => {
-          let field = obj.get_with_index_scale(offset as usize)?;
+    Ok(bytes)
+  }
 
-          let field_value = field.as_integer()?;
+  fn write_array_bytes(&mut self, array_ref: ju4, offset: i64, bytes: &[u8]) -> Result<()> {
+    let array = self.heap.get_array_instance_mut(array_ref)?;
+    let element_type = array.get_classname()[1..].to_string();
 
-          if field_value == expected_value {
-            obj.set_with_index_scale(offset as usize, types::Type::Integer(new_value))?;
+    let scale = check_array_range(array.get_classname(), array.len(), offset, bytes.len())?;
 
-            true
-          } else {
-            false
-          }
-        }
-      }
-    };
+    for (i, byte) in bytes.iter().enumerate() {
+      let position = offset as usize + i;
+      let index = position / scale;
 
-    let ret_value = types::Type::Boolean(status);
-    self.push_stack(ret_value)?;
-    Ok(Some(ret_value))
-  }
+      let mut element = to_bytes(&element_type, *array.get(index)?)?;
+      element[position % scale] = *byte;
 
-  fn exec_native_allocate_memory0(&mut self) -> Result<Option<types::Type>> {
-    let size = self.pop_loperand()?;
-    let address = self.nativememory.alloc(size as u64)?;
+      array.set(index, from_bytes(&element_type, &element)?)?;
+    }
 
-    let ret_value = types::Type::Long(address as i64);
-    self.push_stack(ret_value)?;
-    Ok(Some(ret_value))
+    Ok(())
   }
+}
 
-  fn exec_native_put_byte(&mut self) -> Result<Option<types::Type>> {
-    let value = self.pop_stack()?.as_byte()?;
-
-    let offset = self.pop_stack()?.as_long()? as usize;
+// size in bytes of the values of `var_type`, references are compressed
+fn get_value_size(var_type: &str) -> usize {
+  match var_type {
+    "Z" | "B" => 1,
+    "S" | "C" => 2,
+    "J" | "D" => 8,
+    _ => 4,
+  }
+}
 
-    let obj = self.pop_stack()?;
+// check that the `size` bytes at `offset` are in the array, returns the index scale
+fn check_array_range(classname: &str, len: usize, offset: i64, size: usize) -> Result<usize> {
+  let scale = get_index_scale(classname) as usize;
 
-    if let types::Type::ArrayRef(obj_ref) = obj {
-      let src = self.heap.get_array_instance_mut(obj_ref)?;
+  if offset < 0 || offset as usize + size > len * scale {
+    return Err(eyre!(errors::JavaException::ArrayIndexOutOfBounds(
+      offset.max(0) as usize / scale,
+      len
+    )));
+  }
 
-      src.set_with_index_scale(offset, types::Type::Byte(value))?;
-    } else if obj == types::Type::Null {
-      self.nativememory.is_valid(offset as u64);
+  Ok(scale)
+}
 
-      let src: *mut u8 = offset as *mut u8;
+// bytes of a primitive value in the native (little endian) order
+fn to_bytes(var_type: &str, value: types::Type) -> Result<Vec<u8>> {
+  let bits = match var_type {
+    "Z" => value.as_bool()? as u64,
+    "B" => value.as_byte()? as u8 as u64,
+    "S" | "C" => value.as_integer()? as u16 as u64,
+    "I" => value.as_integer()? as u32 as u64,
+    "J" => value.as_long()? as u64,
+    "F" => value.as_float()?.to_bits() as u64,
+    "D" => value.as_double()?.to_bits(),
+    _ => {
+      return Err(eyre!(errors::InternalError::General(format!(
+        "{} has no bytes",
+        var_type
+      ))))
+    }
+  };
 
-      unsafe {
-        *src = value as u8;
-      }
-    } else {
-      return Err(eyre!("Invalid srcBase type"));
-    };
+  Ok(bits.to_le_bytes()[..get_value_size(var_type)].to_vec())
+}
 
-    Ok(None)
-  }
+fn from_bytes(var_type: &str, bytes: &[u8]) -> Result<types::Type> {
+  let mut buffer = [0u8; 8];
+  buffer[..bytes.len()].copy_from_slice(bytes);
 
-  fn exec_native_jdk_internal_misc_unsafe_free_memory0(&mut self) -> Result<Option<types::Type>> {
-    let address = self.pop_stack()?.as_long()?;
+  let bits = u64::from_le_bytes(buffer);
 
-    unsafe {
-      libc::free(address as *mut libc::c_void);
-    }
+  let value = match var_type {
+    "F" => types::Type::Float(f32::from_bits(bits as u32)),
+    "D" => types::Type::Double(f64::from_bits(bits)),
+    "J" => types::Type::Long(bits as i64),
+    "B" | "Z" => types::Type::Integer(bits as u8 as i8 as i32),
+    "S" => types::Type::Integer(bits as u16 as i16 as i32),
+    "C" => types::Type::Integer(bits as u16 as i32),
+    _ => types::Type::Integer(bits as u32 as i32),
+  };
 
-    Ok(None)
-  }
+  types::Type::to_stored_value(var_type, value)
 }
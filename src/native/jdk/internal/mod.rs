@@ -1,2 +1,13 @@
+pub mod foreign;
+pub mod loader;
 pub mod misc;
 pub mod reflect;
+
+use crate::native::registry::NativeRegistry;
+
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  foreign::register_natives(registry);
+  loader::register_natives(registry);
+  misc::register_natives(registry);
+  reflect::register_natives(registry);
+}
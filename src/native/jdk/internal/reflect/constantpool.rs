@@ -0,0 +1,468 @@
+use color_eyre::eyre::{eyre, Result};
+
+use crate::{
+  class_loader::{constant_pool::CpInfoInfoEnum, loader::ClassLoader},
+  native::registry::NativeRegistry,
+  runtime::{errors, jvm::*, types},
+  utils::ju2,
+};
+
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  let class_name = "jdk/internal/reflect/ConstantPool";
+
+  registry.add(
+    class_name,
+    "getSize0",
+    "(Ljava/lang/Object;)I",
+    |jvm, _, _| jvm.exec_native_constant_pool_get_size0(),
+  );
+  registry.add(
+    class_name,
+    "getClassAt0",
+    "(Ljava/lang/Object;I)Ljava/lang/Class;",
+    |jvm, _, _| jvm.exec_native_constant_pool_get_class_at0(true),
+  );
+  registry.add(
+    class_name,
+    "getClassAtIfLoaded0",
+    "(Ljava/lang/Object;I)Ljava/lang/Class;",
+    |jvm, _, _| jvm.exec_native_constant_pool_get_class_at0(false),
+  );
+  registry.add(
+    class_name,
+    "getClassRefIndexAt0",
+    "(Ljava/lang/Object;I)I",
+    |jvm, _, _| jvm.exec_native_constant_pool_get_class_ref_index_at0(),
+  );
+  registry.add(
+    class_name,
+    "getMethodAt0",
+    "(Ljava/lang/Object;I)Ljava/lang/reflect/Member;",
+    |jvm, _, _| jvm.exec_native_constant_pool_get_method_at0(true),
+  );
+  registry.add(
+    class_name,
+    "getMethodAtIfLoaded0",
+    "(Ljava/lang/Object;I)Ljava/lang/reflect/Member;",
+    |jvm, _, _| jvm.exec_native_constant_pool_get_method_at0(false),
+  );
+  registry.add(
+    class_name,
+    "getFieldAt0",
+    "(Ljava/lang/Object;I)Ljava/lang/reflect/Field;",
+    |jvm, _, _| jvm.exec_native_constant_pool_get_field_at0(true),
+  );
+  registry.add(
+    class_name,
+    "getFieldAtIfLoaded0",
+    "(Ljava/lang/Object;I)Ljava/lang/reflect/Field;",
+    |jvm, _, _| jvm.exec_native_constant_pool_get_field_at0(false),
+  );
+  registry.add(
+    class_name,
+    "getMemberRefInfoAt0",
+    "(Ljava/lang/Object;I)[Ljava/lang/String;",
+    |jvm, _, _| jvm.exec_native_constant_pool_get_member_ref_info_at0(),
+  );
+  registry.add(
+    class_name,
+    "getNameAndTypeRefIndexAt0",
+    "(Ljava/lang/Object;I)I",
+    |jvm, _, _| jvm.exec_native_constant_pool_get_name_and_type_ref_index_at0(),
+  );
+  registry.add(
+    class_name,
+    "getNameAndTypeRefInfoAt0",
+    "(Ljava/lang/Object;I)[Ljava/lang/String;",
+    |jvm, _, _| jvm.exec_native_constant_pool_get_name_and_type_ref_info_at0(),
+  );
+  registry.add(
+    class_name,
+    "getIntAt0",
+    "(Ljava/lang/Object;I)I",
+    |jvm, _, _| jvm.exec_native_constant_pool_get_int_at0(),
+  );
+  registry.add(
+    class_name,
+    "getLongAt0",
+    "(Ljava/lang/Object;I)J",
+    |jvm, _, _| jvm.exec_native_constant_pool_get_long_at0(),
+  );
+  registry.add(
+    class_name,
+    "getFloatAt0",
+    "(Ljava/lang/Object;I)F",
+    |jvm, _, _| jvm.exec_native_constant_pool_get_float_at0(),
+  );
+  registry.add(
+    class_name,
+    "getDoubleAt0",
+    "(Ljava/lang/Object;I)D",
+    |jvm, _, _| jvm.exec_native_constant_pool_get_double_at0(),
+  );
+  registry.add(
+    class_name,
+    "getStringAt0",
+    "(Ljava/lang/Object;I)Ljava/lang/String;",
+    |jvm, _, _| jvm.exec_native_constant_pool_get_string_at0(),
+  );
+  registry.add(
+    class_name,
+    "getUTF8At0",
+    "(Ljava/lang/Object;I)Ljava/lang/String;",
+    |jvm, _, _| jvm.exec_native_constant_pool_get_utf8_at0(),
+  );
+  registry.add(
+    class_name,
+    "getTagAt0",
+    "(Ljava/lang/Object;I)B",
+    |jvm, _, _| jvm.exec_native_constant_pool_get_tag_at0(),
+  );
+}
+
+impl JVM {
+  /// Class of the constant pool (`constantPoolOop` is the `Class` object set by
+  /// `Class.getConstantPool`)
+  fn pop_constant_pool_class(&mut self) -> Result<String> {
+    let class_ref = self.pop_object_ref()?;
+
+    // this
+    self.pop_object_ref()?;
+
+    self.heap.get_classname_from_class_obj(class_ref)
+  }
+
+  /// Class and entry of a `ConstantPool` native call (this, constantPoolOop, index)
+  fn pop_constant_pool_entry(&mut self) -> Result<(String, ju2, CpInfoInfoEnum)> {
+    let index = self.pop_ioperand()?;
+
+    let class_name = self.pop_constant_pool_class()?;
+
+    let class = self.class_loader.get(&class_name)?;
+
+    let cp = class.get_constant_pool();
+
+    if index <= 0 || index as usize > cp.len() {
+      return Err(eyre!(errors::JavaException::IllegalArgumentException(
+        "Constant pool index out of bounds".to_string()
+      )));
+    }
+
+    let info = cp.resolve_index(index as ju2)?.get_info().clone();
+
+    drop(class);
+
+    Ok((class_name, index as ju2, info))
+  }
+
+  fn wrong_constant_pool_type() -> Result<Option<types::Type>> {
+    Err(eyre!(errors::JavaException::IllegalArgumentException(
+      "Wrong type at constant pool index".to_string()
+    )))
+  }
+
+  fn alloc_string_array(&mut self, strings: &[String]) -> Result<types::Type> {
+    let mut elements = vec![];
+
+    for string in strings {
+      elements.push(self.heap.alloc_string(&mut self.class_loader, string)?);
+    }
+
+    let len = elements.len();
+
+    self.heap.alloc_array("java/lang/String", elements, len)
+  }
+
+  /// Key of the class `name` referenced by `context`, `None` if it must be loaded
+  fn constant_pool_class_key(
+    &mut self,
+    context: &str,
+    name: &str,
+    load: bool,
+  ) -> Result<Option<String>> {
+    if load {
+      return Ok(Some(self.resolve_class(context, name)?));
+    }
+
+    Ok(
+      self
+        .class_loader
+        .find_loaded_class(ClassLoader::get_defining_loader(context), name),
+    )
+  }
+
+  fn exec_native_constant_pool_get_size0(&mut self) -> Result<Option<types::Type>> {
+    let class_name = self.pop_constant_pool_class()?;
+
+    // the size includes the unused entry 0
+    let size = self
+      .class_loader
+      .get(&class_name)?
+      .get_constant_pool()
+      .len()
+      + 1;
+
+    let ret_value = types::Type::Integer(size as i32);
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+
+  fn exec_native_constant_pool_get_class_at0(&mut self, load: bool) -> Result<Option<types::Type>> {
+    let (class_name, index, info) = self.pop_constant_pool_entry()?;
+
+    if !matches!(info, CpInfoInfoEnum::Class(_)) {
+      return Self::wrong_constant_pool_type();
+    }
+
+    let name = self
+      .class_loader
+      .get(&class_name)?
+      .resolve_class_name(index)?;
+
+    let ret_value = match self.constant_pool_class_key(&class_name, &name, load)? {
+      Some(key) => {
+        let class_ref = self
+          .heap
+          .get_class_instance(&mut self.class_loader, &key)?
+          .get_ref();
+
+        types::Type::ObjectRef(class_ref)
+      }
+      None => types::Type::Null,
+    };
+
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+
+  fn exec_native_constant_pool_get_class_ref_index_at0(&mut self) -> Result<Option<types::Type>> {
+    let class_index = match self.pop_constant_pool_entry()?.2 {
+      CpInfoInfoEnum::Fieldref(info)
+      | CpInfoInfoEnum::Methodref(info)
+      | CpInfoInfoEnum::Interfaceref(info) => info.get_class_index(),
+      _ => return Self::wrong_constant_pool_type(),
+    };
+
+    let ret_value = types::Type::Integer(class_index as i32);
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+
+  fn exec_native_constant_pool_get_method_at0(
+    &mut self,
+    load: bool,
+  ) -> Result<Option<types::Type>> {
+    let (class_name, index, info) = self.pop_constant_pool_entry()?;
+
+    if !matches!(
+      info,
+      CpInfoInfoEnum::Methodref(_) | CpInfoInfoEnum::Interfaceref(_)
+    ) {
+      return Self::wrong_constant_pool_type();
+    }
+
+    let (owner, name, descriptor) = self
+      .class_loader
+      .get(&class_name)?
+      .resolve_method_ref(index)?;
+
+    // class initializers are not members
+    if name == "<clinit>" {
+      return Self::wrong_constant_pool_type();
+    }
+
+    let ret_value = match self.constant_pool_class_key(&class_name, &owner, load)? {
+      Some(owner) => {
+        let (method_class, method) =
+          self
+            .class_loader
+            .get_method_by_name(&owner, &name, &descriptor)?;
+
+        if name == "<init>" {
+          self
+            .heap
+            .alloc_reflect_constructor(&mut self.class_loader, &method_class, &method)?
+        } else {
+          self
+            .heap
+            .alloc_reflect_method(&mut self.class_loader, &method_class, &method)?
+        }
+      }
+      None => types::Type::Null,
+    };
+
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+
+  fn exec_native_constant_pool_get_field_at0(&mut self, load: bool) -> Result<Option<types::Type>> {
+    let (class_name, index, info) = self.pop_constant_pool_entry()?;
+
+    if !matches!(info, CpInfoInfoEnum::Fieldref(_)) {
+      return Self::wrong_constant_pool_type();
+    }
+
+    let (owner, name, descriptor) = self
+      .class_loader
+      .get(&class_name)?
+      .resolve_field_ref(index)?;
+
+    let ret_value = match self.constant_pool_class_key(&class_name, &owner, load)? {
+      Some(owner) => {
+        let (field_class, field, _) =
+          self
+            .class_loader
+            .get_field_by_name_with_index(&owner, &name, &descriptor, 0)?;
+
+        self
+          .heap
+          .alloc_reflect_field(&mut self.class_loader, &field_class, &field)?
+      }
+      None => types::Type::Null,
+    };
+
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+
+  fn exec_native_constant_pool_get_member_ref_info_at0(&mut self) -> Result<Option<types::Type>> {
+    let (class_name, index, info) = self.pop_constant_pool_entry()?;
+
+    let (owner, name, descriptor) = {
+      let class = self.class_loader.get(&class_name)?;
+
+      match info {
+        CpInfoInfoEnum::Fieldref(_) => class.resolve_field_ref(index)?,
+        CpInfoInfoEnum::Methodref(_) | CpInfoInfoEnum::Interfaceref(_) => {
+          class.resolve_method_ref(index)?
+        }
+        _ => return Self::wrong_constant_pool_type(),
+      }
+    };
+
+    let ret_value = self.alloc_string_array(&[owner, name, descriptor])?;
+
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+
+  fn exec_native_constant_pool_get_name_and_type_ref_index_at0(
+    &mut self,
+  ) -> Result<Option<types::Type>> {
+    let name_and_type_index = match self.pop_constant_pool_entry()?.2 {
+      CpInfoInfoEnum::Fieldref(info)
+      | CpInfoInfoEnum::Methodref(info)
+      | CpInfoInfoEnum::Interfaceref(info) => info.get_name_and_type_index(),
+      CpInfoInfoEnum::Dynamic(info) => info.get_name_and_type_index(),
+      CpInfoInfoEnum::InvokeDynamic(info) => info.get_name_and_type_index(),
+      _ => return Self::wrong_constant_pool_type(),
+    };
+
+    let ret_value = types::Type::Integer(name_and_type_index as i32);
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+
+  fn exec_native_constant_pool_get_name_and_type_ref_info_at0(
+    &mut self,
+  ) -> Result<Option<types::Type>> {
+    let (class_name, _, info) = self.pop_constant_pool_entry()?;
+
+    let CpInfoInfoEnum::NameAndType(info) = info else {
+      return Self::wrong_constant_pool_type();
+    };
+
+    let (name, descriptor) = {
+      let class = self.class_loader.get(&class_name)?;
+
+      (
+        class.resolve_name(info.get_name_index())?,
+        class.resolve_name(info.get_descriptor_index())?,
+      )
+    };
+
+    let ret_value = self.alloc_string_array(&[name, descriptor])?;
+
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+
+  fn exec_native_constant_pool_get_int_at0(&mut self) -> Result<Option<types::Type>> {
+    let CpInfoInfoEnum::Integer(info) = self.pop_constant_pool_entry()?.2 else {
+      return Self::wrong_constant_pool_type();
+    };
+
+    let ret_value = types::Type::Integer(info.int());
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+
+  fn exec_native_constant_pool_get_long_at0(&mut self) -> Result<Option<types::Type>> {
+    let CpInfoInfoEnum::Long(info) = self.pop_constant_pool_entry()?.2 else {
+      return Self::wrong_constant_pool_type();
+    };
+
+    let ret_value = types::Type::Long(info.value() as i64);
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+
+  fn exec_native_constant_pool_get_float_at0(&mut self) -> Result<Option<types::Type>> {
+    let CpInfoInfoEnum::Float(info) = self.pop_constant_pool_entry()?.2 else {
+      return Self::wrong_constant_pool_type();
+    };
+
+    let ret_value = types::Type::Float(info.float());
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+
+  fn exec_native_constant_pool_get_double_at0(&mut self) -> Result<Option<types::Type>> {
+    let CpInfoInfoEnum::Double(info) = self.pop_constant_pool_entry()?.2 else {
+      return Self::wrong_constant_pool_type();
+    };
+
+    let ret_value = types::Type::Double(f64::from_bits(info.value()));
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+
+  fn exec_native_constant_pool_get_string_at0(&mut self) -> Result<Option<types::Type>> {
+    let (class_name, _, info) = self.pop_constant_pool_entry()?;
+
+    let CpInfoInfoEnum::String(info) = info else {
+      return Self::wrong_constant_pool_type();
+    };
+
+    let string = self
+      .class_loader
+      .get(&class_name)?
+      .resolve_name(info.get_string_index())?;
+
+    let ret_value = self.heap.alloc_string(&mut self.class_loader, &string)?;
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+
+  fn exec_native_constant_pool_get_utf8_at0(&mut self) -> Result<Option<types::Type>> {
+    let (class_name, index, info) = self.pop_constant_pool_entry()?;
+
+    if !matches!(info, CpInfoInfoEnum::Utf8(_)) {
+      return Self::wrong_constant_pool_type();
+    }
+
+    let string = self.class_loader.get(&class_name)?.resolve_name(index)?;
+
+    let ret_value = self.heap.alloc_string(&mut self.class_loader, &string)?;
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+
+  fn exec_native_constant_pool_get_tag_at0(&mut self) -> Result<Option<types::Type>> {
+    let tag = self.pop_constant_pool_entry()?.2.get_tag();
+
+    let ret_value = types::Type::Byte(tag as i8);
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+}
@@ -1 +1,9 @@
+pub mod constantpool;
 pub mod reflection;
+
+use crate::native::registry::NativeRegistry;
+
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  constantpool::register_natives(registry);
+  reflection::register_natives(registry);
+}
@@ -1,26 +1,33 @@
-use crate::runtime::{errors, jvm::*, types};
-use color_eyre::eyre::{eyre, Result};
+use crate::{
+  native::registry::NativeRegistry,
+  runtime::{jvm::*, types},
+};
+use color_eyre::eyre::Result;
+
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  let class_name = "jdk/internal/reflect/Reflection";
+
+  registry.add(
+    class_name,
+    "getCallerClass",
+    "()Ljava/lang/Class;",
+    |jvm, _, _| jvm.exec_native_reflection_get_caller_class(),
+  );
+  registry.add(
+    class_name,
+    "getClassAccessFlags",
+    "(Ljava/lang/Class;)I",
+    |jvm, _, _| jvm.exec_native_get_class_access_flags(),
+  );
+  registry.add(
+    class_name,
+    "areNestMates",
+    "(Ljava/lang/Class;Ljava/lang/Class;)Z",
+    |jvm, _, _| jvm.exec_native_are_nest_mates(),
+  );
+}
 
 impl JVM {
-  pub(crate) fn native_dispatcher_jdk_internal_reflect_reflection(
-    &mut self,
-    name: &str,
-    type_str: &str,
-  ) -> Result<Option<types::Type>> {
-    match (name, type_str) {
-      ("getCallerClass", "()Ljava/lang/Class;") => self.exec_native_reflection_get_caller_class(),
-      ("getClassAccessFlags", "(Ljava/lang/Class;)I") => self.exec_native_get_class_access_flags(),
-      ("areNestMates", "(Ljava/lang/Class;Ljava/lang/Class;)Z") => {
-        self.exec_native_are_nest_mates()
-      }
-      _ => Err(eyre!(errors::InternalError::NativeNotImplemented(
-        "jdk/internal/reflect/Reflection".to_string(),
-        name.to_owned(),
-        type_str.to_owned()
-      ))),
-    }
-  }
-
   fn exec_native_reflection_get_caller_class(&mut self) -> Result<Option<types::Type>> {
     // Skip 2 frames: [0] getCallerClass, [1] Unsafe.getUnsafe
     let ret_value = if let Some(caller_frame) = self.frames.get(self.frames.len().wrapping_sub(2)) {
@@ -65,25 +72,11 @@ impl JVM {
     let class_a_name = self.heap.get_classname_from_class_obj(class_a_ref)?;
     let class_b_name = self.heap.get_classname_from_class_obj(class_b_ref)?;
 
-    let nest_host_a = {
-      let class_a = self.class_loader.get(&class_a_name)?;
-      if let Some(nesthost) = class_a.get_nest_host() {
-        nesthost.get_host_class_index() as i32
-      } else {
-        -1
-      }
-    };
-
-    let nest_host_b = {
-      let class_b = self.class_loader.get(&class_b_name)?;
-      if let Some(nesthost) = class_b.get_nest_host() {
-        nesthost.get_host_class_index() as i32
-      } else {
-        -1
-      }
-    };
+    let are_nest_mates = self
+      .class_loader
+      .are_nest_mates(&class_a_name, &class_b_name)?;
 
-    let ret_value = types::Type::Boolean(nest_host_a == nest_host_b);
+    let ret_value = types::Type::Boolean(are_nest_mates);
     self.push_stack(ret_value)?;
     Ok(Some(ret_value))
   }
@@ -1,2 +1,9 @@
 pub mod internal;
 pub mod net;
+
+use crate::native::registry::NativeRegistry;
+
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  internal::register_natives(registry);
+  net::register_natives(registry);
+}
@@ -1 +1,7 @@
 pub mod linuxsocketoptions;
+
+use crate::native::registry::NativeRegistry;
+
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  linuxsocketoptions::register_natives(registry);
+}
@@ -2,4 +2,5 @@ pub mod java;
 pub mod jdk;
 pub mod native_dispatcher;
 pub mod native_impl;
+pub mod registry;
 pub mod sun;
@@ -1,319 +1,570 @@
 use color_eyre::eyre::{eyre, Result};
-use log::warn;
 
-use crate::runtime::{errors, jvm::*, types};
+use crate::{
+  class_loader::disassembler::descriptor_to_java_type,
+  native::registry::NativeRegistry,
+  runtime::{errors, jvm::*, types},
+  utils::{class_to_dotclass, get_parameters_type_descriptor, parse_parameter_types},
+};
+
+// classes of the JDK binding their natives with `registerNatives`, the one of any other class
+// comes from its JNI library
+const REGISTER_NATIVES: &[&str] = &[
+  "java/lang/Class",
+  "java/lang/ClassLoader",
+  "java/lang/System",
+  "java/lang/Thread",
+  "java/lang/invoke/MethodHandleNatives",
+  "jdk/internal/foreign/abi/NativeEntryPoint",
+  "jdk/internal/foreign/abi/ProgrammableInvoker",
+  "jdk/internal/foreign/abi/ProgrammableUpcallHandler",
+  "jdk/internal/foreign/abi/UpcallStubs",
+  "jdk/internal/invoke/NativeEntryPoint",
+  "jdk/internal/misc/ScopedMemoryAccess",
+  "jdk/internal/misc/Unsafe",
+  "jdk/internal/perf/Perf",
+  "jdk/internal/vm/Continuation",
+  "jdk/jfr/internal/JVM",
+  "jdk/vm/ci/hotspot/CompilerToVM",
+];
+
+/// Register the natives of `native_impl`
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  for class_name in REGISTER_NATIVES {
+    registry.add(class_name, "registerNatives", "()V", |_, _, _| Ok(None));
+  }
+
+  registry.add(
+    "jdk/internal/util/SystemProps$Raw",
+    "vmProperties",
+    "()[Ljava/lang/String;",
+    |jvm, _, _| jvm.exec_native_vm_properties(),
+  );
+  registry.add("java/lang/Object", "hashCode", "()I", |jvm, _, _| {
+    jvm.exec_native_hash_code()
+  });
+  registry.add(
+    "java/lang/System",
+    "arraycopy",
+    "(Ljava/lang/Object;ILjava/lang/Object;II)V",
+    |jvm, _, _| jvm.exec_native_arraycopy(),
+  );
+  registry.add("java/lang/Runtime", "maxMemory", "()J", |jvm, _, _| {
+    jvm.exec_native_max_memory()
+  });
+  registry.add("java/lang/Runtime", "gc", "()V", |jvm, _, _| {
+    jvm.exec_native_gc()
+  });
+  registry.add(
+    "jdk/internal/misc/CDS",
+    "getCDSConfigStatus",
+    "()I",
+    |jvm, _, _| jvm.exec_native_get_cds_config_status(),
+  );
+  for name in [
+    "isDumpingClassList0",
+    "isDumpingArchive0",
+    "isSharingEnabled0",
+  ] {
+    registry.add("jdk/internal/misc/CDS", name, "()Z", |jvm, _, _| {
+      jvm.exec_native_cds_flag()
+    });
+  }
+  registry.add(
+    "java/util/concurrent/atomic/AtomicLong",
+    "VMSupportsCS8",
+    "()Z",
+    |jvm, _, _| jvm.exec_native_vm_supports_cs8(),
+  );
+  registry.add(
+    "java/lang/StringUTF16",
+    "isBigEndian",
+    "()Z",
+    |jvm, _, _| jvm.exec_native_is_big_endian(),
+  );
+  registry.add(
+    "jdk/internal/misc/CDS",
+    "initializeFromArchive",
+    "(Ljava/lang/Class;)V",
+    |jvm, _, _| jvm.exec_native_initialize_from_archive(),
+  );
+  registry.add(
+    "jdk/internal/misc/CDS",
+    "getRandomSeedForDumping",
+    "()J",
+    |jvm, _, _| jvm.exec_native_get_random_seed_for_dumping(),
+  );
+  registry.add(
+    "java/lang/Runtime",
+    "availableProcessors",
+    "()I",
+    |jvm, _, _| jvm.exec_native_available_processors(),
+  );
+  registry.add(
+    "java/lang/Thread",
+    "getNextThreadIdOffset",
+    "()J",
+    |jvm, _, _| jvm.exec_native_get_next_thread_id_offset(),
+  );
+  registry.add(
+    "java/lang/System",
+    "setIn0",
+    "(Ljava/io/InputStream;)V",
+    |jvm, _, _| jvm.exec_native_set_in0(),
+  );
+  registry.add(
+    "java/lang/System",
+    "setOut0",
+    "(Ljava/io/PrintStream;)V",
+    |jvm, _, _| jvm.exec_native_set_out0(),
+  );
+  registry.add(
+    "java/lang/System",
+    "setErr0",
+    "(Ljava/io/PrintStream;)V",
+    |jvm, _, _| jvm.exec_native_set_err0(),
+  );
+  registry.add(
+    "java/lang/Object",
+    "clone",
+    "()Ljava/lang/Object;",
+    |jvm, _, _| jvm.exec_native_clone(),
+  );
+  registry.add(
+    "jdk/internal/misc/Signal",
+    "findSignal0",
+    "(Ljava/lang/String;)I",
+    |jvm, _, _| jvm.exec_native_find_signal0(),
+  );
+  registry.add(
+    "jdk/internal/misc/Signal",
+    "handle0",
+    "(IJ)J",
+    |jvm, _, _| jvm.exec_native_handle0(),
+  );
+  registry.add(
+    "java/lang/Thread",
+    "currentThread",
+    "()Ljava/lang/Thread;",
+    |jvm, _, _| jvm.exec_native_current_thread(),
+  );
+  registry.add(
+    "java/security/AccessController",
+    "getStackAccessControlContext",
+    "()Ljava/security/AccessControlContext;",
+    |jvm, _, _| jvm.exec_native_get_stack_access_control_context(),
+  );
+  registry.add(
+    "java/lang/Thread",
+    "currentCarrierThread",
+    "()Ljava/lang/Thread;",
+    |jvm, _, _| jvm.exec_native_get_current_carrier_thread(),
+  );
+  registry.add("java/lang/Thread", "setPriority0", "(I)V", |jvm, _, _| {
+    jvm.exec_native_set_priority0()
+  });
+  registry.add("java/lang/Thread", "start0", "()V", |jvm, _, _| {
+    jvm.exec_native_start0()
+  });
+  registry.add(
+    "java/lang/ref/Finalizer",
+    "isFinalizationEnabled",
+    "()Z",
+    |jvm, _, _| jvm.exec_native_is_finalization_enabled(),
+  );
+  registry.add("java/lang/Object", "notifyAll", "()V", |jvm, _, _| {
+    jvm.exec_native_object_notify_all()
+  });
+  registry.add(
+    "jdk/internal/loader/BootLoader",
+    "setBootLoaderUnnamedModule0",
+    "(Ljava/lang/Module;)V",
+    |jvm, _, _| jvm.exec_set_boot_loader_unnamed_module0(),
+  );
+  registry.add(
+    "java/lang/System",
+    "mapLibraryName",
+    "(Ljava/lang/String;)Ljava/lang/String;",
+    |jvm, _, _| jvm.exec_native_map_library_name(),
+  );
+  registry.add(
+    "jdk/internal/loader/NativeLibraries",
+    "findBuiltinLib",
+    "(Ljava/lang/String;)Ljava/lang/String;",
+    |jvm, _, _| jvm.exec_native_find_builtin_lib(),
+  );
+  registry.add(
+    "java/io/UnixFileSystem",
+    "getBooleanAttributes0",
+    "(Ljava/io/File;)I",
+    |jvm, _, _| jvm.exec_native_get_boolean_attributes0(),
+  );
+  registry.add(
+    "java/security/AccessController",
+    "ensureMaterializedForStackWalk",
+    "(Ljava/lang/Object;)V",
+    |jvm, _, _| jvm.exec_native_ensure_materialized_for_stack_walk(),
+  );
+  registry.add(
+    "sun/nio/fs/UnixNativeDispatcher",
+    "init",
+    "()I",
+    |jvm, _, _| jvm.exec_unix_native_dispatcher_init(),
+  );
+  registry.add(
+    "sun/nio/fs/UnixNativeDispatcher",
+    "getcwd",
+    "()[B",
+    |jvm, _, _| jvm.exec_native_getcwd(),
+  );
+  registry.add(
+    "java/lang/System",
+    "identityHashCode",
+    "(Ljava/lang/Object;)I",
+    |jvm, _, _| jvm.exec_native_identity_hash_code(),
+  );
+  registry.add(
+    "sun/nio/fs/UnixNativeDispatcher",
+    "stat0",
+    "(JLsun/nio/fs/UnixFileAttributes;)I",
+    |jvm, _, _| jvm.exec_native_stat0(),
+  );
+  for class_name in [
+    "jdk/internal/loader/NativeLibraries",
+    "jdk/internal/loader/NativeLibrary",
+  ] {
+    registry.add(
+      class_name,
+      "findEntry0",
+      "(JLjava/lang/String;)J",
+      |jvm, _, _| jvm.exec_native_find_entry0(),
+    );
+  }
+  registry.add(
+    "jdk/internal/vm/ForeignLinkerSupport",
+    "isSupported0",
+    "()Z",
+    |jvm, _, _| jvm.exec_native_foreign_linker_supported(),
+  );
+  for descriptor in ["(Ljdk/internal/foreign/MemorySessionImpl;)Z", "(Ljdk/internal/foreign/MemorySessionImpl;Ljdk/internal/misc/ScopedMemoryAccess$ScopedAccessError;)V"] {
+registry.add("jdk/internal/misc/ScopedMemoryAccess", "closeScope0", descriptor, |jvm, _, type_str| jvm.exec_native_close_scope0(type_str));
+}
+  for descriptor in [
+    "(Ljdk/internal/loader/NativeLibraries$NativeLibraryImpl;Ljava/lang/String;ZZ)Z",
+    "(Ljdk/internal/loader/NativeLibraries$NativeLibraryImpl;Ljava/lang/String;ZZZ)Z",
+  ] {
+    registry.add(
+      "jdk/internal/loader/NativeLibraries",
+      "load",
+      descriptor,
+      |jvm, _, type_str| jvm.exec_native_library_load(type_str),
+    );
+  }
+  for descriptor in ["(Ljava/lang/String;ZJ)V", "(Ljava/lang/String;ZZJ)V"] {
+    registry.add(
+      "jdk/internal/loader/NativeLibraries",
+      "unload",
+      descriptor,
+      |jvm, _, type_str| jvm.exec_native_library_unload(type_str),
+    );
+  }
+  registry.add(
+    "jdk/internal/jimage/NativeImageBuffer",
+    "getNativeMap",
+    "(Ljava/lang/String;)Ljava/nio/ByteBuffer;",
+    |jvm, _, _| jvm.exec_native_get_native_map(),
+  );
+  registry.add(
+    "sun/nio/fs/UnixNativeDispatcher",
+    "open0",
+    "(JII)I",
+    |jvm, _, _| jvm.exec_native_open0(),
+  );
+  registry.add(
+    "jdk/internal/reflect/DirectMethodHandleAccessor$NativeAccessor",
+    "invoke0",
+    "(Ljava/lang/reflect/Method;Ljava/lang/Object;[Ljava/lang/Object;)Ljava/lang/Object;",
+    |jvm, _, _| jvm.exec_native_invoke0(),
+  );
+  registry.add("sun/nio/ch/NativeThread", "init", "()V", |jvm, _, _| {
+    jvm.exec_native_thread_init()
+  });
+  registry.add("sun/nio/ch/NativeThread", "current0", "()J", |jvm, _, _| {
+    jvm.exec_native_thread_current0()
+  });
+  registry.add(
+    "sun/nio/ch/UnixFileDispatcherImpl",
+    "pread0",
+    "(Ljava/io/FileDescriptor;JIJ)I",
+    |jvm, _, _| jvm.exec_native_pread0(),
+  );
+  registry.add(
+    "sun/nio/ch/UnixFileDispatcherImpl",
+    "size0",
+    "(Ljava/io/FileDescriptor;)J",
+    |jvm, _, _| jvm.exec_native_size0(),
+  );
+  registry.add(
+    "sun/nio/ch/UnixFileDispatcherImpl",
+    "allocationGranularity0",
+    "()J",
+    |jvm, _, _| jvm.exec_native_allocation_granularity0(),
+  );
+  registry.add(
+    "sun/nio/ch/UnixFileDispatcherImpl",
+    "map0",
+    "(Ljava/io/FileDescriptor;IJJZ)J",
+    |jvm, _, _| jvm.exec_native_map0(),
+  );
+  for class_name in [
+    "sun/nio/ch/UnixFileDispatcherImpl",
+    "sun/nio/ch/FileChannelImpl",
+  ] {
+    registry.add(class_name, "unmap0", "(JJ)I", |jvm, _, _| {
+      jvm.exec_native_unmap0()
+    });
+  }
+  registry.add(
+    "sun/nio/ch/FileChannelImpl",
+    "initIDs",
+    "()J",
+    |jvm, _, _| jvm.exec_native_allocation_granularity0(),
+  );
+  registry.add(
+    "sun/nio/ch/FileChannelImpl",
+    "map0",
+    "(IJJZ)J",
+    |jvm, _, _| jvm.exec_native_file_channel_map0(),
+  );
+  registry.add(
+    "jdk/internal/reflect/DirectConstructorHandleAccessor$NativeAccessor",
+    "newInstance0",
+    "(Ljava/lang/reflect/Constructor;[Ljava/lang/Object;)Ljava/lang/Object;",
+    |jvm, _, _| jvm.exec_native_new_instance0(),
+  );
+  // JDK 17 only, the VM needs no field ids
+  registry.add("java/io/UnixFileSystem", "initIDs", "()V", |_, _, _| {
+    Ok(None)
+  });
+  registry.add(
+    "java/io/UnixFileSystem",
+    "canonicalize0",
+    "(Ljava/lang/String;)Ljava/lang/String;",
+    |jvm, _, _| jvm.exec_native_canonicalize0(),
+  );
+  registry.add(
+    "java/lang/Module",
+    "defineModule0",
+    "(Ljava/lang/Module;ZLjava/lang/String;Ljava/lang/String;[Ljava/lang/Object;)V",
+    |jvm, _, _| jvm.exec_native_define_module0(),
+  );
+  registry.add(
+    "java/lang/Double",
+    "longBitsToDouble",
+    "(J)D",
+    |jvm, _, _| jvm.exec_native_long_bits_to_double(),
+  );
+  registry.add(
+    "java/lang/Module",
+    "addReads0",
+    "(Ljava/lang/Module;Ljava/lang/Module;)V",
+    |jvm, _, _| jvm.exec_native_add_reads0(),
+  );
+  registry.add(
+    "java/lang/Module",
+    "addExportsToAll0",
+    "(Ljava/lang/Module;Ljava/lang/String;)V",
+    |jvm, _, _| jvm.exec_native_add_exports_to_all0(),
+  );
+  registry.add(
+    "java/lang/Module",
+    "addExports0",
+    "(Ljava/lang/Module;Ljava/lang/String;Ljava/lang/Module;)V",
+    |jvm, _, _| jvm.exec_native_add_exports0(),
+  );
+  registry.add("java/io/FileOutputStream", "initIDs", "()V", |jvm, _, _| {
+    jvm.exec_native_fileoutputstream_initids()
+  });
+  registry.add(
+    "java/io/FileOutputStream",
+    "writeBytes",
+    "([BIIZ)V",
+    |jvm, _, _| jvm.exec_native_fileoutputstream_write_bytes(),
+  );
+  registry.add("java/net/InetAddress", "init", "()V", |jvm, _, _| {
+    jvm.exec_native_inetaddress_init()
+  });
+  registry.add(
+    "java/net/InetAddress",
+    "isIPv6Supported",
+    "()Z",
+    |jvm, _, _| jvm.exec_native_is_ipv6_supported(),
+  );
+  registry.add(
+    "java/net/InetAddress",
+    "isIPv4Available",
+    "()Z",
+    |jvm, _, _| jvm.exec_native_is_ipv4_available(),
+  );
+  registry.add("java/net/Inet4Address", "init", "()V", |jvm, _, _| {
+    jvm.exec_native_inet4address_init()
+  });
+  registry.add(
+    "java/lang/reflect/Array",
+    "newArray",
+    "(Ljava/lang/Class;I)Ljava/lang/Object;",
+    |jvm, _, _| jvm.exec_native_array_newarray(),
+  );
+  registry.add(
+    "java/lang/String",
+    "intern",
+    "()Ljava/lang/String;",
+    |jvm, _, _| jvm.exec_native_string_intern(),
+  );
+
+  // bound to the class declaring them, a class of the user may have natives with these names
+  registry.add(
+    "jdk/internal/util/SystemProps$Raw",
+    "platformProperties",
+    "()[Ljava/lang/String;",
+    |jvm, _, _| jvm.exec_native_platform_properties(),
+  );
+  registry.add(
+    "java/lang/Object",
+    "getClass",
+    "()Ljava/lang/Class;",
+    |jvm, _, _| jvm.exec_native_get_class(),
+  );
+  registry.add(
+    "java/lang/Float",
+    "floatToRawIntBits",
+    "(F)I",
+    |jvm, _, _| jvm.exec_native_float_to_raw_int_bits(),
+  );
+  registry.add(
+    "java/lang/Double",
+    "doubleToRawLongBits",
+    "(D)J",
+    |jvm, _, _| jvm.exec_native_double_to_raw_long_bits(),
+  );
+  registry.add(
+    "java/lang/Throwable",
+    "fillInStackTrace",
+    "(I)Ljava/lang/Throwable;",
+    |jvm, _, _| jvm.exec_native_fill_in_stack_trace(),
+  );
+  registry.add("jdk/internal/misc/VM", "initialize", "()V", |jvm, _, _| {
+    jvm.exec_native_initialize()
+  });
+}
 
 impl JVM {
   /// Call a native function
   ///
+  /// The natives of the VM and of the embedder are looked up first, then the ones of the
+  /// loaded JNI libraries.
+  ///
   /// # Arguments
   ///
   /// * `class_name` - Class of the function to call
   /// * `name` - Name of the function to call
   /// * `type_str` - Type of the function to call
+  ///
+  /// # Returns
+  ///
+  /// `UnsatisfiedLinkError` if the native is not implemented
   pub(crate) fn call_native(
     &mut self,
     class_name: &str,
     name: &str,
     type_str: &str,
   ) -> Result<Option<types::Type>> {
-    // TODO: create hierarchical dispatch
-    match (class_name, name, type_str) {
-      (_, "registerNatives", "()V") => {
-        warn!("TODO: {} {} {}", class_name, name, type_str);
-      }
-      _ if class_name == "java/lang/Class" => {
-        return self.native_dispatcher_java_lang_class(name, type_str);
-      }
-      _ if class_name == "java/lang/ClassLoader" => {
-        return self.native_dispatcher_java_lang_classloader(name, type_str);
-      }
-      _ if class_name == "java/lang/invoke/MethodHandle" => {
-        return self.native_dispatcher_java_lang_invoke_methodhandle(name, type_str);
-      }
-      _ if class_name == "java/lang/invoke/MethodHandleNatives" => {
-        return self.native_dispatcher_java_lang_invoke_methodhandlenatives(name, type_str);
-      }
-      _ if class_name == "java/lang/invoke/VarHandle" => {
-        return self.native_dispatcher_java_lang_invoke_varhandle(name, type_str);
-      }
-      _ if class_name == "jdk/internal/misc/Unsafe" => {
-        return self.native_dispatcher_jdk_internal_misc_unsafe(name, type_str);
-      }
-      _ if class_name == "jdk/internal/reflect/Reflection" => {
-        return self.native_dispatcher_jdk_internal_reflect_reflection(name, type_str);
-      }
-      _ if class_name == "jdk/net/LinuxSocketOptions" => {
-        return self.native_dispatcher_jdk_net_linux(name, type_str);
-      }
-      _ if class_name == "sun/nio/ch/Net" => {
-        return self.native_dispatcher_sun_nio_ch_net(name, type_str);
-      }
-      _ if class_name == "sun/nio/ch/SocketDispatcher" => {
-        return self.native_dispatcher_sun_nio_ch_socketdispatcher(name, type_str);
-      }
-      _ if class_name == "sun/nio/ch/IOUtil" => {
-        return self.native_dispatcher_sun_nio_ch_ioutil(name, type_str);
-      }
-      _ if class_name == "java/io/FileInputStream" => {
-        return self.native_dispatcher_java_io_fileinputstream(name, type_str);
-      }
-      _ if class_name == "java/io/FileDescriptor" => {
-        return self.native_dispatcher_java_io_filedescriptor(name, type_str);
-      }
-      _ if class_name == "java/lang/ref/Reference" => {
-        return self.native_dispatcher_java_lang_ref_reference(name, type_str);
-      }
-      (_, "platformProperties", _) => {
-        self.exec_native_platform_properties()?;
-      }
-      ("jdk/internal/util/SystemProps$Raw", "vmProperties", "()[Ljava/lang/String;") => {
-        self.exec_native_vm_properties()?;
-      }
-      ("java/lang/Object", "hashCode", "()I") => {
-        self.exec_native_hash_code()?;
-      }
-      (_, "getClass", _) => {
-        self.exec_native_get_class()?;
-      }
-      ("java/lang/System", "arraycopy", "(Ljava/lang/Object;ILjava/lang/Object;II)V") => {
-        self.exec_native_arraycopy()?;
-      }
-      (_, "floatToRawIntBits", _) => {
-        self.exec_native_float_to_raw_int_bits()?;
-      }
-      (_, "floatToIntBits", _) => {
-        self.exec_native_float_to_int_bits()?;
-      }
-      (_, "doubleToRawLongBits", _) => {
-        self.exec_native_double_to_raw_long_bits()?;
-      }
-      (_, "doubleToLongBits", _) => {
-        self.exec_native_double_to_long_bits()?;
-      }
-      (_, "fillInStackTrace", _) => {
-        self.exec_native_fill_in_stack_trace()?;
-      }
-      (_, "initialize", _) => {
-        self.exec_native_initialize()?;
-      }
-      ("java/lang/Runtime", "maxMemory", "()J") => {
-        self.exec_native_max_memory()?;
-      }
-      ("jdk/internal/misc/CDS", "getCDSConfigStatus", "()I") => {
-        self.exec_native_get_cds_config_status()?;
-      }
-      ("jdk/internal/misc/CDS", "initializeFromArchive", "(Ljava/lang/Class;)V") => {
-        self.exec_native_initialize_from_archive()?;
-      }
-      ("jdk/internal/misc/CDS", "getRandomSeedForDumping", "()J") => {
-        self.exec_native_get_random_seed_for_dumping()?;
-      }
-      ("java/lang/Runtime", "availableProcessors", "()I") => {
-        self.exec_native_available_processors()?;
-      }
-      ("java/lang/Thread", "getNextThreadIdOffset", "()J") => {
-        self.exec_native_get_next_thread_id_offset()?;
-      }
-      ("java/lang/System", "setIn0", "(Ljava/io/InputStream;)V") => {
-        self.exec_native_set_in0()?;
-      }
-      ("java/lang/System", "setOut0", "(Ljava/io/PrintStream;)V") => {
-        self.exec_native_set_out0()?;
-      }
-      ("java/lang/System", "setErr0", "(Ljava/io/PrintStream;)V") => {
-        self.exec_native_set_err0()?;
-      }
-      ("java/lang/Object", "clone", "()Ljava/lang/Object;") => {
-        self.exec_native_clone()?;
-      }
-      ("jdk/internal/misc/Signal", "findSignal0", "(Ljava/lang/String;)I") => {
-        self.exec_native_find_signal0()?;
-      }
-      ("jdk/internal/misc/Signal", "handle0", "(IJ)J") => {
-        self.exec_native_handle0()?;
-      }
-      ("java/lang/Thread", "currentThread", "()Ljava/lang/Thread;") => {
-        self.exec_native_current_thread()?;
-      }
-      (
-        "java/security/AccessController",
-        "getStackAccessControlContext",
-        "()Ljava/security/AccessControlContext;",
-      ) => {
-        self.exec_native_get_stack_access_control_context()?;
-      }
-      ("java/lang/Thread", "currentCarrierThread", "()Ljava/lang/Thread;") => {
-        self.exec_native_get_current_carrier_thread()?;
-      }
-      ("java/lang/Thread", "setPriority0", "(I)V") => {
-        self.exec_native_set_priority0()?;
-      }
-      ("java/lang/Thread", "start0", "()V") => {
-        self.exec_native_start0()?;
-      }
-      ("java/lang/ref/Finalizer", "isFinalizationEnabled", "()Z") => {
-        self.exec_native_is_finalization_enabled()?;
-      }
-      ("java/lang/Object", "notifyAll", "()V") => {
-        self.exec_native_object_notify_all()?;
-      }
-      (
-        "jdk/internal/loader/BootLoader",
-        "setBootLoaderUnnamedModule0",
-        "(Ljava/lang/Module;)V",
-      ) => {
-        self.exec_set_boot_loader_unnamed_module0()?;
-      }
-      ("java/lang/System", "mapLibraryName", "(Ljava/lang/String;)Ljava/lang/String;") => {
-        self.exec_native_map_library_name()?;
-      }
-      (
-        "jdk/internal/loader/NativeLibraries",
-        "findBuiltinLib",
-        "(Ljava/lang/String;)Ljava/lang/String;",
-      ) => {
-        self.exec_native_find_builtin_lib()?;
-      }
-      ("java/io/UnixFileSystem", "getBooleanAttributes0", "(Ljava/io/File;)I") => {
-        self.exec_native_get_boolean_attributes0()?;
-      }
-      (
-        "java/security/AccessController",
-        "ensureMaterializedForStackWalk",
-        "(Ljava/lang/Object;)V",
-      ) => {
-        self.exec_native_ensure_materialized_for_stack_walk()?;
-      }
-      ("sun/nio/fs/UnixNativeDispatcher", "init", "()I") => {
-        self.exec_unix_native_dispatcher_init()?;
-      }
-      ("sun/nio/fs/UnixNativeDispatcher", "getcwd", "()[B") => {
-        self.exec_native_getcwd()?;
-      }
-      ("java/lang/System", "identityHashCode", "(Ljava/lang/Object;)I") => {
-        self.exec_native_identity_hash_code()?;
-      }
-      ("sun/nio/fs/UnixNativeDispatcher", "stat0", "(JLsun/nio/fs/UnixFileAttributes;)I") => {
-        self.exec_native_stat0()?;
-      }
-      (
-        "jdk/internal/loader/NativeLibraries",
-        "load",
-        "(Ljdk/internal/loader/NativeLibraries$NativeLibraryImpl;Ljava/lang/String;ZZ)Z",
-      ) => {
-        self.exec_native_library_load()?;
-      }
-      (
-        "jdk/internal/jimage/NativeImageBuffer",
-        "getNativeMap",
-        "(Ljava/lang/String;)Ljava/nio/ByteBuffer;",
-      ) => {
-        self.exec_native_get_native_map()?;
-      }
-      ("sun/nio/fs/UnixNativeDispatcher", "open0", "(JII)I") => {
-        self.exec_native_open0()?;
-      }
-      (
-        "jdk/internal/reflect/DirectMethodHandleAccessor$NativeAccessor",
-        "invoke0",
-        "(Ljava/lang/reflect/Method;Ljava/lang/Object;[Ljava/lang/Object;)Ljava/lang/Object;",
-      ) => {
-        self.exec_native_invoke0()?;
-      }
-      ("sun/nio/ch/IOUtil", "initIDs", "()V") => {
-        self.exec_native_init_ids()?;
-      }
-      ("sun/nio/ch/IOUtil", "iovMax", "()I") => {
-        self.exec_native_iov_max()?;
-      }
-      ("sun/nio/ch/IOUtil", "writevMax", "()J") => {
-        self.exec_native_writev_max()?;
-      }
-      ("sun/nio/ch/NativeThread", "init", "()V") => {
-        self.exec_native_thread_init()?;
-      }
-      ("sun/nio/ch/NativeThread", "current0", "()J") => {
-        self.exec_native_thread_current0()?;
-      }
-      ("sun/nio/ch/UnixFileDispatcherImpl", "pread0", "(Ljava/io/FileDescriptor;JIJ)I") => {
-        self.exec_native_pread0()?;
-      }
-      ("sun/nio/ch/UnixFileDispatcherImpl", "size0", "(Ljava/io/FileDescriptor;)J") => {
-        self.exec_native_size0()?;
-      }
-      ("sun/nio/ch/UnixFileDispatcherImpl", "allocationGranularity0", "()J") => {
-        self.exec_native_allocation_granularity0()?;
-      }
-      ("sun/nio/ch/UnixFileDispatcherImpl", "map0", "(Ljava/io/FileDescriptor;IJJZ)J") => {
-        self.exec_native_map0()?;
-      }
-      (
-        "jdk/internal/reflect/DirectConstructorHandleAccessor$NativeAccessor",
-        "newInstance0",
-        "(Ljava/lang/reflect/Constructor;[Ljava/lang/Object;)Ljava/lang/Object;",
-      ) => {
-        self.exec_native_new_instance0()?;
-      }
-      ("java/io/UnixFileSystem", "canonicalize0", "(Ljava/lang/String;)Ljava/lang/String;") => {
-        self.exec_native_canonicalize0()?;
-      }
-      (
-        "java/lang/Module",
-        "defineModule0",
-        "(Ljava/lang/Module;ZLjava/lang/String;Ljava/lang/String;[Ljava/lang/Object;)V",
-      ) => {
-        self.exec_native_define_module0()?;
-      }
-      ("java/lang/Double", "longBitsToDouble", "(J)D") => {
-        self.exec_native_long_bits_to_double()?;
-      }
-      ("java/lang/Module", "addReads0", "(Ljava/lang/Module;Ljava/lang/Module;)V") => {
-        self.exec_native_add_reads0()?;
-      }
-      ("java/lang/Module", "addExportsToAll0", "(Ljava/lang/Module;Ljava/lang/String;)V") => {
-        self.exec_native_add_exports_to_all0()?;
-      }
-      (
-        "java/lang/Module",
-        "addExports0",
-        "(Ljava/lang/Module;Ljava/lang/String;Ljava/lang/Module;)V",
-      ) => {
-        self.exec_native_add_exports0()?;
-      }
-      ("java/io/FileOutputStream", "initIDs", "()V") => {
-        self.exec_native_fileoutputstream_initids()?;
-      }
-      ("java/io/FileOutputStream", "writeBytes", "([BIIZ)V") => {
-        self.exec_native_fileoutputstream_write_bytes()?;
-      }
-      ("java/net/InetAddress", "init", "()V") => {
-        self.exec_native_inetaddress_init()?;
-      }
-      ("java/net/InetAddress", "isIPv6Supported", "()Z") => {
-        self.exec_native_is_ipv6_supported()?;
-      }
-      ("java/net/InetAddress", "isIPv4Available", "()Z") => {
-        self.exec_native_is_ipv4_available()?;
-      }
-      ("java/net/Inet4Address", "init", "()V") => {
-        self.exec_native_inet4address_init()?;
-      }
-      ("java/lang/reflect/Array", "newArray", "(Ljava/lang/Class;I)Ljava/lang/Object;") => {
-        self.exec_native_array_newarray()?;
-      }
-      ("java/lang/String", "intern", "()Ljava/lang/String;") => {
-        self.exec_native_string_intern()?;
-      }
-      _ => {
-        return Err(eyre!(errors::InternalError::NativeNotImplemented(
-          format!("Unknown {}", class_name),
-          name.to_owned(),
-          type_str.to_owned()
-        )));
-      }
+    // the methods of a lambda class call its implementation
+    if let Some(lambda) = self.lambdamanager.get_lambda(class_name).cloned() {
+      return self.invoke_lambda(&lambda, name, type_str);
     }
 
-    Ok(None)
+    if let Some(function) = self.natives.get(class_name, name, type_str) {
+      return function(self, name, type_str);
+    }
+
+    // the natives of the JNI libraries
+    let function = self.find_jni_function(class_name, name, type_str)?;
+
+    if function != 0 {
+      return self.call_jni_function(function, class_name, name, type_str);
+    }
+
+    Err(eyre!(errors::JavaException::UnsatisfiedLinkError(
+      get_native_signature(class_name, name, type_str)
+    )))
+  }
+
+  /// Implement the native method `class_name.name` with a Rust function, in place of the one
+  /// of the VM or of a JNI library
+  ///
+  /// The function gets the arguments of the method, preceded by `this` unless the method is
+  /// static, and returns its result (`Type::None` for `void`).
+  ///
+  /// # Arguments
+  ///
+  /// * `class_name` - Class declaring the method (e.g. `java/lang/Object`)
+  /// * `name` - Name of the method
+  /// * `descriptor` - Descriptor of the method (e.g. `(I)I`)
+  /// * `function` - Implementation of the method
+  pub fn register_native<F>(&mut self, class_name: &str, name: &str, descriptor: &str, function: F)
+  where
+    F: Fn(&mut JVM, Vec<types::Type>) -> Result<types::Type> + 'static,
+  {
+    let class = class_name.to_string();
+
+    self.natives.add(
+      class_name,
+      name,
+      descriptor,
+      move |jvm, name, descriptor| {
+        let (_, method) = jvm
+          .class_loader
+          .get_method_by_name(&class, name, descriptor)?;
+
+        let param_count = parse_parameter_types(get_parameters_type_descriptor(descriptor)).len();
+
+        let mut args = vec![];
+
+        for _ in 0..param_count + (!method.is_static() as usize) {
+          args.push(jvm.pop_stack()?);
+        }
+
+        args.reverse();
+
+        let result = function(jvm, args)?;
+
+        if descriptor.ends_with(")V") || result == types::Type::None {
+          return Ok(None);
+        }
+
+        jvm.push_stack(result)?;
+
+        Ok(Some(result))
+      },
+    );
   }
 }
+
+// signature of a method in the message of an UnsatisfiedLinkError, e.g. `'int Foo.add(int, int)'`
+fn get_native_signature(class_name: &str, name: &str, descriptor: &str) -> String {
+  let parameters: Vec<String> = parse_parameter_types(get_parameters_type_descriptor(descriptor))
+    .into_iter()
+    .map(descriptor_to_java_type)
+    .collect();
+
+  let return_type = descriptor.rsplit(')').next().unwrap_or("V");
+
+  format!(
+    "'{} {}.{}({})'",
+    descriptor_to_java_type(return_type),
+    class_to_dotclass(class_name),
+    name,
+    parameters.join(", ")
+  )
+}
@@ -1,24 +1,22 @@
 use crate::{
-  runtime::{errors, jvm::*, types},
-  utils::{class_to_dotclass, descriptor_to_classname, get_argument_classnames, get_env, ju4},
+  runtime::{errors, foreign, jvm::*, types},
+  utils::{
+    class_to_dotclass, descriptor_to_classname, get_argument_classnames, get_env,
+    get_return_type_descriptor, ju4,
+  },
 };
 use color_eyre::eyre::{eyre, Result};
 use core::panic;
 use log::{debug, warn};
 use std::ffi::CString;
 use std::{
-  fs::File,
-  io::Write,
   net::Ipv6Addr,
-  os::{
-    fd::RawFd,
-    unix::{fs::MetadataExt, io::FromRawFd},
-  },
+  os::{fd::RawFd, unix::fs::MetadataExt},
   path::Path,
 };
 
 impl JVM {
-  pub(crate) fn exec_native_platform_properties(&mut self) -> Result<()> {
+  pub(crate) fn exec_native_platform_properties(&mut self) -> Result<Option<types::Type>> {
     // Indexes of array elements written by native platformProperties()
     // The order is arbitrary (but alphabetic for convenience)
     let _display_country = 0;
@@ -107,15 +105,21 @@ impl JVM {
 
     self.push_stack(array)?;
 
-    Ok(())
+    Ok(None)
   }
 
-  pub(crate) fn exec_native_vm_properties(&mut self) -> Result<()> {
+  pub(crate) fn exec_native_vm_properties(&mut self) -> Result<Option<types::Type>> {
     warn!("Assuming Linux when creating VM properties");
     // see  `java -XshowSettings:properties -version`
 
     // TODO: fixed some values now
-    let java_home = get_env("JHOME", "");
+    // the java.home given to the builder replaces JHOME
+    let java_home = self
+      .properties
+      .iter()
+      .find(|(k, _)| k == "java.home")
+      .map(|(_, v)| v.clone())
+      .unwrap_or_else(|| get_env("JHOME", ""));
 
     if java_home.is_empty() {
       return Err(eyre!(errors::InternalError::General(
@@ -158,8 +162,17 @@ impl JVM {
       ("sun.arch.data.model", "64"),
       ("sun.jnu.encoding", "UTF-8"),
       ("jdk.image.map.all", "true"),
+      // Method.invoke and Constructor.newInstance go through invoke0/newInstance0
+      // instead of spinning method handles
+      ("jdk.reflect.useNativeAccessorOnly", "true"),
     ];
 
+    // the properties given at creation come last and override the others
+    let properties = self.properties.clone();
+    let kv = kv
+      .into_iter()
+      .chain(properties.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+
     let mut strings = vec![];
 
     for (k, v) in kv {
@@ -180,7 +193,7 @@ impl JVM {
 
     self.push_stack(array)?;
 
-    Ok(())
+    Ok(None)
   }
 
   pub(crate) fn exec_native_hash_code(&mut self) -> Result<Option<types::Type>> {
@@ -292,26 +305,6 @@ impl JVM {
     Ok(None)
   }
 
-  // public static native int floatToIntBits(float value);
-  pub(crate) fn exec_native_float_to_int_bits(&mut self) -> Result<Option<types::Type>> {
-    let value = self.pop_stack()?;
-
-    let value = match value {
-      types::Type::Float(r) => r,
-      _ => return Err(eyre!("length is not an float {:?}", value)),
-    };
-
-    let bits = if value.is_nan() {
-      0x7fc00000 // Canonical NaN
-    } else {
-      value.to_bits()
-    };
-
-    self.push_stack(types::Type::Integer(bits as i32))?;
-
-    Ok(None)
-  }
-
   // public static native long doubleToRawLongBits(double value);
   pub(crate) fn exec_native_double_to_raw_long_bits(&mut self) -> Result<Option<types::Type>> {
     let value = self.pop_stack()?;
@@ -327,26 +320,6 @@ impl JVM {
     Ok(None)
   }
 
-  // public static native long doubleToLongBits(double value);
-  pub(crate) fn exec_native_double_to_long_bits(&mut self) -> Result<Option<types::Type>> {
-    let value = self.pop_stack()?;
-
-    let value = match value {
-      types::Type::Double(r) => r,
-      _ => return Err(eyre!("length is not an double{:?}", value)),
-    };
-
-    let bits = if value.is_nan() {
-      0x7ff8000000000000 // canonical NaN
-    } else {
-      value.to_bits()
-    };
-
-    self.push_stack(types::Type::Long(bits as i64))?;
-
-    Ok(None)
-  }
-
   // public synchronized native Throwable fillInStackTrace();
   pub(crate) fn exec_native_fill_in_stack_trace(&mut self) -> Result<Option<types::Type>> {
     let class = self.pop_stack()?;
@@ -373,6 +346,21 @@ impl JVM {
     Ok(None)
   }
 
+  // public native void gc();
+  pub(crate) fn exec_native_gc(&mut self) -> Result<Option<types::Type>> {
+    let _this = self.pop_stack()?;
+
+    let cleaned = self.collect_garbage()?;
+
+    debug!(
+      "GC ran {} cleaners, {} bytes off-heap",
+      cleaned,
+      self.nativememory.get_allocated()
+    );
+
+    Ok(None)
+  }
+
   // public static native int getCDSConfigStatus();
   // 0 - disabled , 1 - enabled and supported, 2+ - reserved
   pub(crate) fn exec_native_get_cds_config_status(&mut self) -> Result<Option<types::Type>> {
@@ -381,6 +369,28 @@ impl JVM {
     Ok(None)
   }
 
+  // private static native boolean isDumpingClassList0(); and the other flags of JDK 17
+  pub(crate) fn exec_native_cds_flag(&mut self) -> Result<Option<types::Type>> {
+    let ret_value = types::Type::Boolean(false);
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+
+  // private static native boolean VMSupportsCS8();
+  pub(crate) fn exec_native_vm_supports_cs8(&mut self) -> Result<Option<types::Type>> {
+    let ret_value = types::Type::Boolean(true);
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+
+  // private static native boolean isBigEndian();
+  pub(crate) fn exec_native_is_big_endian(&mut self) -> Result<Option<types::Type>> {
+    // x86-64
+    let ret_value = types::Type::Boolean(false);
+    self.push_stack(ret_value)?;
+    Ok(Some(ret_value))
+  }
+
   // public static native void initializeFromArchive(Class<?> klass);
   pub(crate) fn exec_native_initialize_from_archive(&mut self) -> Result<Option<types::Type>> {
     warn!("INITIALIZE FROM ARCHIVE NOT IMPLEMENTED YET");
@@ -408,50 +418,17 @@ impl JVM {
   }
 
   pub(crate) fn exec_native_get_next_thread_id_offset(&mut self) -> Result<Option<types::Type>> {
-    // Simulate the offset of Thread.nextThreadID
-    let offset = self
-      .class_loader
-      .get_field_offset("java/lang/Thread", "nextThreadID")?;
+    // the "offset" is the address of the counter, used with a null base by
+    // Unsafe.getAndAddLong
+    let address = self.nativememory.alloc(8)?;
 
-    self.push_stack(types::Type::Long(offset))?;
+    self
+      .nativememory
+      .write(address, &self.next_thread_id.to_le_bytes())?;
 
-    Ok(None)
-  }
-
-  pub(crate) fn get_volatile_field(&mut self, offset: i64, obj_ref: ju4) -> Result<types::Type> {
-    // if null the field is static => use Thread Class
-    let field = if obj_ref == 0 {
-      debug!("static field {}", offset);
-
-      let field = self
-        .class_loader
-        .get_field_by_offset("java/lang/Thread", offset)?;
-      let field_name = field.get_name();
-
-      let class = self.class_loader.get("java/lang/Thread")?;
-
-      let field_type = class.get_static_field(field_name)?;
-
-      *field_type
-    } else {
-      debug!("Get Long volatile {}", obj_ref);
+    self.push_stack(types::Type::Long(address as i64))?;
 
-      let obj = self.heap.get_instance_mut(obj_ref)?;
-
-      match obj {
-        types::Instance::ObjectInstance(obj) => {
-          let class_name = obj.get_classname();
-
-          let field = self.class_loader.get_field_by_offset(class_name, offset)?;
-          let field_name = field.get_name();
-
-          obj.get_field(field_name)?
-        }
-        types::Instance::ArrayInstance(obj) => *obj.get_with_index_scale(offset as usize)?,
-      }
-    };
-
-    Ok(field)
+    Ok(None)
   }
 
   // private static native void setIn0(InputStream in);
@@ -846,22 +823,112 @@ impl JVM {
     Ok(None)
   }
 
-  pub(crate) fn exec_native_library_load(&mut self) -> Result<Option<types::Type>> {
-    warn!("always positive to library load");
+  // JDK 17 has a boolean isJNI before throwExceptionIfFail, the libraries of the JDK are
+  // built into the VM
+  pub(crate) fn exec_native_library_load(&mut self, type_str: &str) -> Result<Option<types::Type>> {
+    let throw = self.pop_ioperand()? != 0;
 
-    let _throw = self.pop_ioperand()?;
+    if type_str.ends_with("ZZZ)Z") {
+      let _is_jni = self.pop_ioperand()?;
+    }
 
-    let _is_built_in = self.pop_ioperand()?;
+    let is_built_in = self.pop_ioperand()? != 0;
+
+    let name = self.pop_string()?;
 
-    let _string = self.pop_ref()?;
+    let obj = self.pop_ref()?;
 
-    let _obj = self.pop_ref()?;
+    if is_built_in {
+      debug!("built-in library {} loaded", name);
+
+      self.push_stack(types::Type::Boolean(true))?;
+
+      return Ok(None);
+    }
+
+    let (handle, version) = self.load_jni_library(&name)?;
+
+    if handle == 0 {
+      if throw {
+        return Err(eyre!(errors::JavaException::UnsatisfiedLinkError(format!(
+          "Can't load library: {}",
+          name
+        ))));
+      }
+
+      self.push_stack(types::Type::Boolean(false))?;
+
+      return Ok(None);
+    }
+
+    let library = self.heap.get_obj_instance_mut(obj)?;
+    library.put_field("handle", types::Type::Long(handle as i64))?;
+    library.put_field("jniVersion", types::Type::Integer(version))?;
 
     self.push_stack(types::Type::Boolean(true))?;
 
     Ok(None)
   }
 
+  // JDK 17 has a boolean isJNI before the handle
+  pub(crate) fn exec_native_library_unload(
+    &mut self,
+    type_str: &str,
+  ) -> Result<Option<types::Type>> {
+    let handle = self.pop_stack()?.as_long()?;
+
+    if type_str == "(Ljava/lang/String;ZZJ)V" {
+      let _is_jni = self.pop_ioperand()?;
+    }
+
+    let _is_built_in = self.pop_ioperand()?;
+
+    let _name = self.pop_ref()?;
+
+    self.unload_jni_library(handle as u64)?;
+
+    Ok(None)
+  }
+
+  // the symbols of the process are found with the handle 0 (RTLD_DEFAULT)
+  pub(crate) fn exec_native_find_entry0(&mut self) -> Result<Option<types::Type>> {
+    let name = self.pop_string()?;
+    let handle = self.pop_stack()?.as_long()?;
+
+    let address = types::Type::Long(foreign::find_entry(handle as u64, &name)? as i64);
+
+    self.push_stack(address)?;
+
+    Ok(Some(address))
+  }
+
+  // the downcalls and upcalls of the SysV x86-64 ABI are linked by the VM
+  pub(crate) fn exec_native_foreign_linker_supported(&mut self) -> Result<Option<types::Type>> {
+    let supported = types::Type::Boolean(cfg!(all(target_arch = "x86_64", target_os = "linux")));
+
+    self.push_stack(supported)?;
+
+    Ok(Some(supported))
+  }
+
+  // a single thread runs, none can be accessing the memory of the closed session
+  pub(crate) fn exec_native_close_scope0(&mut self, type_str: &str) -> Result<Option<types::Type>> {
+    if type_str.ends_with(")V") {
+      let _error = self.pop_ref()?;
+    }
+
+    let _session = self.pop_ref()?;
+    let _this = self.pop_ref()?;
+
+    if type_str.ends_with(")Z") {
+      self.push_stack(types::Type::Boolean(true))?;
+
+      return Ok(Some(types::Type::Boolean(true)));
+    }
+
+    Ok(None)
+  }
+
   pub(crate) fn exec_native_get_native_map(&mut self) -> Result<Option<types::Type>> {
     let path_ref = self.pop_object_ref()?;
 
@@ -913,7 +980,7 @@ impl JVM {
   pub(crate) fn exec_native_for_name0(&mut self) -> Result<Option<types::Type>> {
     let _caller_class = self.pop_stack()?;
 
-    let _loader = self.pop_stack()?;
+    let loader = self.pop_stack()?;
 
     let initialize = self.pop_ioperand()? != 0;
 
@@ -922,10 +989,18 @@ impl JVM {
     let name = self.heap.get_string(name_ref)?;
 
     // Convert from "java.lang.String" to "java/lang/String"
-    let internal_name = name.replace('.', "/");
+    let internal_name = match loader {
+      // the class is loaded through the given loader
+      types::Type::ObjectRef(loader_ref) => self.load_class_with_loader(loader_ref, &name)?,
+      _ => {
+        let internal_name = name.replace('.', "/");
+
+        // loaded unless it already is, reloading it would reset its statics and init state
+        drop(self.class_loader.get(&internal_name)?);
 
-    // force load class
-    self.class_loader.load_class(&internal_name)?;
+        internal_name
+      }
+    };
 
     // Initialize if requested
     if initialize {
@@ -966,6 +1041,11 @@ impl JVM {
     let mut method_objs = Vec::with_capacity(methods.len());
 
     for method in methods {
+      // the constructors and the initializer are not methods for reflection
+      if method.get_name().starts_with('<') {
+        continue;
+      }
+
       if !public_only || method.is_public() {
         let method_obj =
           self
@@ -988,39 +1068,26 @@ impl JVM {
   }
 
   pub(crate) fn exec_native_get_constant_pool(&mut self) -> Result<Option<types::Type>> {
-    warn!("exec_native_get_constant_pool not fully implemeted");
-
     let class_obj_ref = self.pop_object_ref()?;
 
-    let obj = self.heap.get_instance(class_obj_ref)?;
-
-    let class_name = obj.get_class_field_type().to_owned();
-
-    let class_obj_ref = self
-      .heap
-      .get_class_instance(&mut self.class_loader, &class_name)?
-      .get_ref();
-
-    // Simulate creating a new ConstantPool object
     let cp_obj = self
       .heap
       .alloc_obj(&mut self.class_loader, "jdk/internal/reflect/ConstantPool")?
       .as_ref()?;
 
-    let cp_instance = self.heap.get_obj_instance_mut(cp_obj)?;
-
-    // Link the real constant pool to this wrapper via a synthetic field
-    cp_instance.new_field("internalClass", types::Type::ObjectRef(class_obj_ref))?;
+    // the ConstantPool natives find the constant pool through the Class object
+    self
+      .heap
+      .get_obj_instance_mut(cp_obj)?
+      .put_field("constantPoolOop", types::Type::ObjectRef(class_obj_ref))?;
 
-    // Optionally store a raw constant pool or reference ID
-    // e.g., cp_instance.new_field("index", Type::Integer(123))?;
     self.push_stack(types::Type::ObjectRef(cp_obj))?;
 
     Ok(None)
   }
 
   pub(crate) fn exec_native_invoke0(&mut self) -> Result<Option<types::Type>> {
-    let args_array = self.pop_ref()?;
+    let args_array = self.pop_stack()?;
 
     let target_obj = self.pop_stack()?;
 
@@ -1038,65 +1105,186 @@ impl JVM {
 
     let class_ref = method_inst.get_field("clazz")?.as_ref()?;
 
-    let class_inst = self.heap.get_obj_instance(class_ref)?; // java/lang/Class
-    let class_inner_ref = class_inst.get_field("name")?.as_ref()?; // T in Class<T> as string
-    let class_name = self.heap.get_string(class_inner_ref)?; // string
-    let (method_class, _) =
-      self
-        .class_loader
-        .get_method_by_name(&class_name, &method_name, &method_type)?;
+    let class_name = self.heap.get_classname_from_class_obj(class_ref)?;
 
-    let array_args = self.heap.get_array_instance(args_array)?;
+    // the declared method may be abstract
+    let (mut method_class, method, _) = self.class_loader.get_any_method_by_name_with_index(
+      &class_name,
+      &method_name,
+      &method_type,
+    )?;
 
     let mut args = vec![];
 
-    if target_obj != types::Type::Null {
+    if method.is_static() {
+      self.init_class(&method_class)?;
+    } else {
+      let target_ref = match target_obj {
+        types::Type::Null => return Err(eyre!(errors::JavaException::NullPointer)),
+        types::Type::ObjectRef(target_ref) | types::Type::ArrayRef(target_ref) => target_ref,
+        _ => {
+          return Err(eyre!(errors::InternalError::WrongType(
+            "Reference",
+            target_obj
+          )))
+        }
+      };
+
+      let target_class = self
+        .heap
+        .get_instance(target_ref)?
+        .get_classname()
+        .to_string();
+
+      if !types::Type::check_type(&mut self.class_loader, &class_name, &target_class)? {
+        return Err(eyre!(errors::JavaException::IllegalArgumentException(
+          "object is not an instance of declaring class".to_string()
+        )));
+      }
+
+      // private methods are invoked like invokespecial, others dispatch on the target
+      if !method.is_private() {
+        method_class =
+          match self
+            .class_loader
+            .get_method_by_name(&target_class, &method_name, &method_type)
+          {
+            Ok((target_method_class, _)) => target_method_class,
+            Err(err) => match err.downcast_ref::<errors::InternalError>() {
+              Some(errors::InternalError::MethodNotFound(..)) => {
+                return Err(eyre!(errors::JavaException::AbstractMethodError(format!(
+                  "{}.{}{}",
+                  class_to_dotclass(&target_class),
+                  method_name,
+                  method_type
+                ))))
+              }
+              _ => return Err(err),
+            },
+          };
+      }
+
       args.push(target_obj);
     }
 
-    if let Some(_arg) = array_args.get_elements().iter().next() {
-      // TODO: handle arguments
-      panic!()
-    }
+    args.extend(self.unbox_reflect_arguments(&method_type, args_array)?);
+
+    let ret = self
+      .call_and_resolve_method(&method_class, &method_name, &method_type, args)
+      .map_err(JVM::wrap_invocation_target)?;
 
-    // Step 3: Push method frame and invoke
-    let ret = self.call_and_resolve_method(&method_class, &method_name, &method_type, args)?;
+    let ret_type = get_return_type_descriptor(&method_type);
+
+    let ret_value = if ret_type == "V" {
+      types::Type::Null
+    } else if types::Type::is_primitive(&ret_type) {
+      self
+        .heap
+        .alloc_boxed(&mut self.class_loader, &ret_type, ret)?
+    } else {
+      ret
+    };
 
-    self.push_stack(ret)?;
+    self.push_stack(ret_value)?;
 
     Ok(None)
   }
 
-  pub(crate) fn exec_native_init_ids(&mut self) -> Result<Option<types::Type>> {
-    warn!("IOUtil.initIDs: not fully implemented");
+  /// Arguments of a reflective call from the `Object[]` `args_array`, unboxed per `descriptor`
+  fn unbox_reflect_arguments(
+    &mut self,
+    descriptor: &str,
+    args_array: types::Type,
+  ) -> Result<Vec<types::Type>> {
+    let param_types = get_argument_classnames(descriptor);
+
+    // a null array stands for no arguments
+    let array_args = match args_array {
+      types::Type::Null => vec![],
+      _ => self
+        .heap
+        .get_array_instance(args_array.as_ref()?)?
+        .get_elements()
+        .clone(),
+    };
 
-    let mut class = self.class_loader.get_mut("java/io/FileDescriptor")?;
+    if array_args.len() != param_types.len() {
+      return Err(eyre!(errors::JavaException::IllegalArgumentException(
+        format!(
+          "wrong number of arguments: {} expected: {}",
+          array_args.len(),
+          param_types.len()
+        )
+      )));
+    }
 
-    class.put_static_field("fd", types::Type::Integer(1))?;
+    let mut args = vec![];
 
-    Ok(None)
+    for (param_type, arg) in param_types.iter().zip(array_args) {
+      let value = self.unbox_reflect_argument(param_type, arg)?;
+
+      value.push_to_locals(&mut args);
+    }
+
+    Ok(args)
   }
 
-  pub(crate) fn exec_native_fileoutputstream_initids(&mut self) -> Result<Option<types::Type>> {
-    warn!("FileOutputStream.initIDs: not fully implemented");
+  /// Check `arg` against the parameter type `param_type`, unboxing and widening primitives
+  pub(crate) fn unbox_reflect_argument(
+    &mut self,
+    param_type: &str,
+    arg: types::Type,
+  ) -> Result<types::Type> {
+    let mismatch = || {
+      eyre!(errors::JavaException::IllegalArgumentException(
+        "argument type mismatch".to_string()
+      ))
+    };
 
-    let mut class = self.class_loader.get_mut("java/io/FileDescriptor")?;
+    let arg_ref = match arg {
+      types::Type::Null if types::Type::is_primitive(param_type) => return Err(mismatch()),
+      types::Type::Null => return Ok(arg),
+      types::Type::ObjectRef(arg_ref) | types::Type::ArrayRef(arg_ref) => arg_ref,
+      _ => return Err(mismatch()),
+    };
 
-    class.put_static_field("fd", types::Type::Integer(1))?;
+    if !types::Type::is_primitive(param_type) {
+      let arg_class = self.heap.get_instance(arg_ref)?.get_classname().to_string();
 
-    Ok(None)
-  }
+      if !types::Type::check_type(
+        &mut self.class_loader,
+        &descriptor_to_classname(param_type),
+        &arg_class,
+      )? {
+        return Err(mismatch());
+      }
 
-  pub(crate) fn exec_native_iov_max(&mut self) -> Result<Option<types::Type>> {
-    let max_iov = 1024; // should be cross-platform
-    self.push_stack(types::Type::Integer(max_iov))?;
+      return Ok(arg);
+    }
 
-    Ok(None)
+    let (arg_type, value) = self.heap.get_boxed_value(arg_ref)?.ok_or_else(mismatch)?;
+
+    // widening primitive conversions allowed by reflection (JLS 5.1.2)
+    types::Type::widen_primitive(arg_type, param_type, value)?.ok_or_else(mismatch)
   }
 
-  pub(crate) fn exec_native_writev_max(&mut self) -> Result<Option<types::Type>> {
-    let max_bytes: i64 = 2_147_483_647; // 2GB - 1, typical Linux max
-    self.push_stack(types::Type::Long(max_bytes))?;
+  /// Exceptions thrown by a reflectively invoked method are wrapped in an
+  /// `InvocationTargetException`
+  fn wrap_invocation_target(err: color_eyre::eyre::Report) -> color_eyre::eyre::Report {
+    match err.downcast::<errors::JavaException>() {
+      Ok(exception) => eyre!(errors::JavaException::InvocationTargetException(Box::new(
+        exception
+      ))),
+      Err(err) => err,
+    }
+  }
+
+  pub(crate) fn exec_native_fileoutputstream_initids(&mut self) -> Result<Option<types::Type>> {
+    warn!("FileOutputStream.initIDs: not fully implemented");
+
+    let mut class = self.class_loader.get_mut("java/io/FileDescriptor")?;
+
+    class.put_static_field("fd", types::Type::Integer(1))?;
 
     Ok(None)
   }
@@ -1171,17 +1359,7 @@ impl JVM {
 
     let read_len = ret as usize;
 
-    let dest: *mut u8 = address as *mut u8;
-
-    self.nativememory.is_valid(address);
-
-    for (i, val) in buffer.iter().enumerate().take(read_len) {
-      let addr = dest.wrapping_add(i);
-
-      unsafe {
-        *addr = *val;
-      }
-    }
+    self.nativememory.write(address, &buffer[..read_len])?;
 
     self.push_stack(types::Type::Integer(read_len as i32))?;
 
@@ -1220,50 +1398,63 @@ impl JVM {
 
   pub(crate) fn exec_native_map0(&mut self) -> Result<Option<types::Type>> {
     let is_sync = self.pop_ioperand()? != 0;
-
-    let size = self.pop_stack()?.as_long()? as usize;
-
-    let position = self.pop_stack()?.as_long()? as libc::off_t;
-
-    let prot_flags = self.pop_stack()?.as_integer()?; // 1=read, 2=write, 3=rw
+    let size = self.pop_stack()?.as_long()?;
+    let position = self.pop_stack()?.as_long()?;
+    let prot = self.pop_ioperand()?;
     let fd_obj = self.pop_stack()?.as_ref()?;
 
-    let fd_inst = self.heap.get_obj_instance(fd_obj)?;
+    self.map_file(fd_obj, prot, position, size, is_sync)
+  }
 
-    let raw_fd = fd_inst.get_field("fd")?.as_integer()? as RawFd;
+  // JDK 17 maps through the file descriptor of the channel
+  pub(crate) fn exec_native_file_channel_map0(&mut self) -> Result<Option<types::Type>> {
+    let is_sync = self.pop_ioperand()? != 0;
+    let size = self.pop_stack()?.as_long()?;
+    let position = self.pop_stack()?.as_long()?;
+    let prot = self.pop_ioperand()?;
+    let this = self.pop_stack()?.as_ref()?;
 
-    // TODO: why sometimes we dont have it?
-    let mut prot = libc::PROT_READ;
+    let fd_obj = self
+      .heap
+      .get_obj_instance(this)?
+      .get_field("fd")?
+      .as_ref()?;
 
-    if prot_flags & 1 != 0 {
-      prot |= libc::PROT_READ;
-    }
+    self.map_file(fd_obj, prot, position, size, is_sync)
+  }
 
-    if prot_flags & 2 != 0 {
-      prot |= libc::PROT_WRITE;
-    }
+  // `prot` is one of the MAP_RO (0), MAP_RW (1) and MAP_PV (2) modes of the channel, the
+  // synchronous mappings of non volatile memory are mapped like the shared ones
+  fn map_file(
+    &mut self,
+    fd_obj: ju4,
+    prot: i32,
+    position: i64,
+    size: i64,
+    _is_sync: bool,
+  ) -> Result<Option<types::Type>> {
+    let raw_fd = self
+      .heap
+      .get_obj_instance(fd_obj)?
+      .get_field("fd")?
+      .as_integer()? as RawFd;
 
-    let flags = if is_sync {
-      libc::MAP_SHARED
-    } else {
-      libc::MAP_PRIVATE
-    };
+    let addr = self
+      .nativememory
+      .map(raw_fd, prot != 0, prot != 2, position, size as u64)?;
 
-    let addr = unsafe { libc::mmap(std::ptr::null_mut(), size, prot, flags, raw_fd, position) };
+    self.push_stack(types::Type::Long(addr as i64))?;
 
-    if addr == libc::MAP_FAILED {
-      return Err(eyre!("mmap failed"));
-    }
+    Ok(None)
+  }
 
-    debug!(
-      "MAP {} {} -> {:?} -> {:?}",
-      raw_fd, size, addr as i64, prot_flags
-    );
+  pub(crate) fn exec_native_unmap0(&mut self) -> Result<Option<types::Type>> {
+    let size = self.pop_stack()?.as_long()?;
+    let addr = self.pop_stack()?.as_long()?;
 
-    self.nativememory.register(addr as u64, size as u64);
+    self.nativememory.unmap(addr as u64, size as u64)?;
 
-    // Return pointer address as long
-    self.push_stack(types::Type::Long(addr as i64))?;
+    self.push_stack(types::Type::Integer(0))?;
 
     Ok(None)
   }
@@ -1285,76 +1476,23 @@ impl JVM {
 
     let class_ref = self.pop_stack()?.as_ref()?;
 
-    // Resolve the class object
-    let class_obj = self.heap.get_obj_instance(class_ref)?;
+    let class_name = self.heap.get_classname_from_class_obj(class_ref)?;
 
-    let class_inner_ref = class_obj.get_field("name")?.as_ref()?; // T in Class<T> as string
-    let class_name = self.heap.get_string(class_inner_ref)?; // string
+    let methods = self.class_loader.get(&class_name)?.get_methods().clone();
 
     let mut constructors = vec![];
 
-    // triple of (name, descriptor, access_flags)
-    let mut method_names = vec![];
-
-    let methods = self.class_loader.get(&class_name)?.get_methods().clone();
-
     for method in methods {
-      if method.get_name() == "<init>" {
-        if public_only && (!method.is_public()) {
-          continue;
-        }
-
-        method_names.push((
-          class_to_dotclass(method.get_name()),
-          method.get_descriptor().to_string(),
-          method.get_access_flags(),
-        ));
-      }
-    }
-
-    for method in method_names {
-      let param_types = get_argument_classnames(&method.1);
-
-      let mut param_types_args = vec![];
-
-      for p in param_types {
-        let p = &descriptor_to_classname(&p);
-
-        let class = self.heap.get_class_instance(&mut self.class_loader, p)?;
-
-        param_types_args.push(types::Type::ObjectRef(class.get_ref()));
+      if method.get_name() != "<init>" || (public_only && !method.is_public()) {
+        continue;
       }
 
-      let param_types_args_len = param_types_args.len();
-
-      let param_array =
+      let ctor =
         self
           .heap
-          .alloc_array("java/lang/Class", param_types_args, param_types_args_len)?;
-
-      // Allocate java/lang/reflect/Constructor object
-      let name_str = self.heap.alloc_string(&mut self.class_loader, &method.0)?;
+          .alloc_reflect_constructor(&mut self.class_loader, &class_name, &method)?;
 
-      let sig_str = self.heap.alloc_string(&mut self.class_loader, &method.1)?;
-
-      let ctor_ref = self
-        .heap
-        .alloc_obj(&mut self.class_loader, "java/lang/reflect/Constructor")?
-        .as_ref()?;
-
-      let ctor_inst = self.heap.get_obj_instance_mut(ctor_ref)?;
-
-      ctor_inst.new_field("clazz", types::Type::ObjectRef(class_ref))?;
-
-      ctor_inst.new_field("name", name_str)?;
-
-      ctor_inst.new_field("signature", sig_str)?;
-
-      ctor_inst.new_field("parameterTypes", param_array)?;
-
-      ctor_inst.new_field("modifiers", types::Type::Integer(method.2 as i32))?;
-
-      constructors.push(types::Type::ObjectRef(ctor_ref));
+      constructors.push(ctor);
     }
 
     let constructors_len = constructors.len();
@@ -1385,7 +1523,7 @@ impl JVM {
   }
 
   pub(crate) fn exec_native_new_instance0(&mut self) -> Result<Option<types::Type>> {
-    let args_array_ref = self.pop_array_ref()?;
+    let args_array = self.pop_stack()?;
 
     let ctor_obj_ref = self.pop_object_ref()?;
 
@@ -1393,50 +1531,37 @@ impl JVM {
 
     let clazz_ref = ctor_obj.get_field("clazz")?.as_ref()?;
 
-    let clazz_obj = self.heap.get_obj_instance(clazz_ref)?;
-
-    let class_inner_ref = clazz_obj.get_field("name")?.as_ref()?; // T in Class<T> as string
-    let clazz_name = self.heap.get_string(class_inner_ref)?;
-
-    let method_name = self
-      .heap
-      .get_string(ctor_obj.get_field("name")?.as_ref()?)?; // should be "<init>"
-    assert!(method_name == "<init>");
-
     let descriptor = self
       .heap
-      .get_string(ctor_obj.get_field("signature")?.as_ref()?)?
-      .to_string();
+      .get_string(ctor_obj.get_field("signature")?.as_ref()?)?;
 
-    let new_obj = self.heap.alloc_obj(&mut self.class_loader, &clazz_name)?;
+    let clazz_name = self.heap.get_classname_from_class_obj(clazz_ref)?;
 
-    // Prepare arguments
-    let mut args = vec![new_obj];
+    let instantiable = {
+      let class = self.class_loader.get(&clazz_name)?;
 
-    let array = self.heap.get_array_instance(args_array_ref)?;
+      !class.is_abstract() && !class.is_interface()
+    };
 
-    for arg in array.get_elements() {
-      match arg {
-        types::Type::Null => args.push(types::Type::Null),
-        _ => {
-          let obj = self.heap.get_obj_instance(arg.as_ref()?)?;
+    if !instantiable {
+      return Err(eyre!(errors::JavaException::InstantiationException(
+        class_to_dotclass(&clazz_name)
+      )));
+    }
 
-          if obj.is_primitive() {
-            let value = obj.get_field("value")?;
+    let args = self.unbox_reflect_arguments(&descriptor, args_array)?;
 
-            if value.get_category() == 2 {
-              args.push(value);
-            }
+    self.init_class(&clazz_name)?;
 
-            args.push(value);
-          } else {
-            args.push(types::Type::ObjectRef(obj.get_ref()));
-          }
-        }
-      }
-    }
+    let new_obj = self.heap.alloc_obj(&mut self.class_loader, &clazz_name)?;
 
-    self.call_and_resolve_method(&clazz_name, &method_name, &descriptor, args)?;
+    let mut ctor_args = vec![new_obj];
+
+    ctor_args.extend(args);
+
+    self
+      .call_and_resolve_method(&clazz_name, "<init>", &descriptor, ctor_args)
+      .map_err(JVM::wrap_invocation_target)?;
 
     // Result of <init> is always void, but we return the new instance
     self.push_stack(new_obj)?;
@@ -1471,15 +1596,18 @@ impl JVM {
 
     let this_class_obj = self.pop_object_ref()?;
 
-    let this_class = self.heap.get_obj_instance(this_class_obj)?;
+    // the classes represented by the Class objects, not java/lang/Class
+    let this_class = self.heap.get_classname_from_class_obj(this_class_obj)?;
 
-    let other_class = self.heap.get_obj_instance(other_class_obj)?;
+    let other_class = self.heap.get_classname_from_class_obj(other_class_obj)?;
 
-    let result = types::Type::check_type(
-      &mut self.class_loader,
-      this_class.get_classname(),
-      other_class.get_classname(),
-    )?;
+    // a primitive class is only assignable from itself
+    let result =
+      if types::Type::is_primitive(&this_class) || types::Type::is_primitive(&other_class) {
+        this_class == other_class
+      } else {
+        types::Type::check_type(&mut self.class_loader, &this_class, &other_class)?
+      };
 
     self.push_stack(types::Type::Boolean(result))?;
 
@@ -1493,7 +1621,8 @@ impl JVM {
       .heap
       .get_class_from_class_obj(&mut self.class_loader, class_ref)?;
 
-    if class_inner.get_name() == "java/lang/Object" {
+    // the class file of an interface names java/lang/Object as its super class
+    if class_inner.get_name() == "java/lang/Object" || class_inner.is_interface() {
       drop(class_inner);
 
       self.push_stack(types::Type::Null)?;
@@ -1514,10 +1643,17 @@ impl JVM {
 
   pub(crate) fn exec_native_define_module0(&mut self) -> Result<Option<types::Type>> {
     let packages_arr = self.pop_ref()?; // [Ljava/lang/Object;
+    let _location_obj = self.pop_ref()?; // location String (can be null)
     let version_obj = self.pop_ref()?; // version String (can be null)
-    let name_obj = self.pop_object_ref()?; // name String
     let is_open = self.pop_ioperand()?; // boolean 1/0
     let module_obj = self.pop_object_ref()?; // this Module object
+
+    // the name is set by the constructor before the module is defined
+    let name_obj = self
+      .heap
+      .get_obj_instance(module_obj)?
+      .get_field("name")?
+      .as_ref()?;
     let module_name = self.heap.get_string(name_obj)?;
 
     let version = if version_obj != 0 {
@@ -1637,12 +1773,7 @@ impl JVM {
 
     let slice = &all_bytes[off as usize..(off as usize + len as usize)];
 
-    let mut file = unsafe { File::from_raw_fd(fd) };
-
-    file.write_all(slice)?;
-
-    // to avoid closing the fd when `file` goes out of scope
-    std::mem::forget(file);
+    self.stdio.write(fd, slice)?;
 
     Ok(None)
   }
@@ -0,0 +1,138 @@
+use std::{
+  borrow::Borrow,
+  collections::HashMap,
+  fmt,
+  hash::{Hash, Hasher},
+  rc::Rc,
+};
+
+use color_eyre::eyre::Result;
+
+use crate::{
+  native::{java, jdk, native_dispatcher, sun},
+  runtime::{jvm::*, types},
+};
+
+/// Implementation of a native method, called with the name and the descriptor of the method
+///
+/// It pops the arguments (and `this`) from the stack of the caller, then pushes the result.
+pub(crate) type NativeFunction = Rc<dyn Fn(&mut JVM, &str, &str) -> Result<Option<types::Type>>>;
+
+/// Class, name and descriptor of a native, so that the registry is searched with borrowed
+/// strings
+trait NativeKey {
+  fn key(&self) -> (&str, &str, &str);
+}
+
+impl NativeKey for (String, String, String) {
+  fn key(&self) -> (&str, &str, &str) {
+    (&self.0, &self.1, &self.2)
+  }
+}
+
+impl NativeKey for (&str, &str, &str) {
+  fn key(&self) -> (&str, &str, &str) {
+    *self
+  }
+}
+
+impl<'a> Borrow<dyn NativeKey + 'a> for (String, String, String) {
+  fn borrow(&self) -> &(dyn NativeKey + 'a) {
+    self
+  }
+}
+
+// hashed like the owned keys of the map
+impl Hash for dyn NativeKey + '_ {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.key().hash(state);
+  }
+}
+
+impl PartialEq for dyn NativeKey + '_ {
+  fn eq(&self, other: &Self) -> bool {
+    self.key() == other.key()
+  }
+}
+
+impl Eq for dyn NativeKey + '_ {}
+
+/// Natives of the VM, keyed by class, name and descriptor
+#[derive(Default)]
+pub(crate) struct NativeRegistry {
+  methods: HashMap<(String, String, String), NativeFunction>,
+  // signature polymorphic methods by class then name, the descriptor is the type of the call
+  // site
+  polymorphic: HashMap<String, HashMap<String, NativeFunction>>,
+}
+
+impl NativeRegistry {
+  /// Registry with the natives implemented by the VM
+  pub(crate) fn new() -> Self {
+    let mut registry = NativeRegistry::default();
+
+    native_dispatcher::register_natives(&mut registry);
+    java::register_natives(&mut registry);
+    jdk::register_natives(&mut registry);
+    sun::register_natives(&mut registry);
+
+    registry
+  }
+
+  /// Register the native `class_name.name` with `descriptor`, replacing any previous one
+  pub(crate) fn add<F>(&mut self, class_name: &str, name: &str, descriptor: &str, function: F)
+  where
+    F: Fn(&mut JVM, &str, &str) -> Result<Option<types::Type>> + 'static,
+  {
+    self.methods.insert(
+      (
+        class_name.to_string(),
+        name.to_string(),
+        descriptor.to_string(),
+      ),
+      Rc::new(function),
+    );
+  }
+
+  /// Register the signature polymorphic native `class_name.name`, for any descriptor
+  pub(crate) fn add_polymorphic<F>(&mut self, class_name: &str, name: &str, function: F)
+  where
+    F: Fn(&mut JVM, &str, &str) -> Result<Option<types::Type>> + 'static,
+  {
+    self
+      .polymorphic
+      .entry(class_name.to_string())
+      .or_default()
+      .insert(name.to_string(), Rc::new(function));
+  }
+
+  pub(crate) fn get(
+    &self,
+    class_name: &str,
+    name: &str,
+    descriptor: &str,
+  ) -> Option<NativeFunction> {
+    self
+      .methods
+      .get(&(class_name, name, descriptor) as &dyn NativeKey)
+      .or_else(|| {
+        self
+          .polymorphic
+          .get(class_name)
+          .and_then(|methods| methods.get(name))
+      })
+      .cloned()
+  }
+}
+
+impl fmt::Debug for NativeRegistry {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("NativeRegistry")
+      .field("methods", &self.methods.len())
+      .field(
+        "polymorphic",
+        &self.polymorphic.values().map(HashMap::len).sum::<usize>(),
+      )
+      .finish()
+  }
+}
@@ -1 +1,7 @@
 pub mod nio;
+
+use crate::native::registry::NativeRegistry;
+
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  nio::register_natives(registry);
+}
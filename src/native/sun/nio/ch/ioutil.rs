@@ -1,27 +1,32 @@
-use crate::runtime::{errors, jvm::*, types};
-use color_eyre::eyre::{eyre, Result};
+use crate::{
+  native::registry::NativeRegistry,
+  runtime::{jvm::*, types},
+};
+use color_eyre::eyre::Result;
 use log::warn;
 use std::io::Error;
 
-impl JVM {
-  pub(crate) fn native_dispatcher_sun_nio_ch_ioutil(
-    &mut self,
-    name: &str,
-    type_str: &str,
-  ) -> Result<Option<types::Type>> {
-    match (name, type_str) {
-      ("initIDs", "()V") => self.exec_native_sun_nio_ch_ioutil_init_ids(),
-      ("setfdVal", "(Ljava/io/FileDescriptor;I)V") => self.exec_native_sun_nio_ch_ioutil_setfdval(),
-      ("iovMax", "()I") => self.exec_native_sun_nio_ch_ioutil_iovmax(),
-      ("writevMax", "()J") => self.exec_native_sun_nio_ch_ioutil_writevmax(),
-      _ => Err(eyre!(errors::InternalError::NativeNotImplemented(
-        "sun/nio/ch/IOUtil".to_string(),
-        name.to_owned(),
-        type_str.to_owned()
-      ))),
-    }
-  }
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  let class_name = "sun/nio/ch/IOUtil";
 
+  registry.add(class_name, "initIDs", "()V", |jvm, _, _| {
+    jvm.exec_native_sun_nio_ch_ioutil_init_ids()
+  });
+  registry.add(
+    class_name,
+    "setfdVal",
+    "(Ljava/io/FileDescriptor;I)V",
+    |jvm, _, _| jvm.exec_native_sun_nio_ch_ioutil_setfdval(),
+  );
+  registry.add(class_name, "iovMax", "()I", |jvm, _, _| {
+    jvm.exec_native_sun_nio_ch_ioutil_iovmax()
+  });
+  registry.add(class_name, "writevMax", "()J", |jvm, _, _| {
+    jvm.exec_native_sun_nio_ch_ioutil_writevmax()
+  });
+}
+
+impl JVM {
   fn exec_native_sun_nio_ch_ioutil_init_ids(&mut self) -> Result<Option<types::Type>> {
     warn!("sun/nio/ch/IOUtil.initIDs not supported");
     Ok(None)
@@ -1,3 +1,11 @@
 pub mod ioutil;
 pub mod net;
 pub mod socketdispatcher;
+
+use crate::native::registry::NativeRegistry;
+
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  ioutil::register_natives(registry);
+  net::register_natives(registry);
+  socketdispatcher::register_natives(registry);
+}
@@ -1,45 +1,73 @@
-use crate::runtime::{errors, jvm::*, types};
+use crate::{
+  native::registry::NativeRegistry,
+  runtime::{errors, jvm::*, types},
+};
 use color_eyre::eyre::{eyre, Result};
 use log::{debug, warn};
 use std::{io::Error, net::Ipv4Addr};
 
 const IPV4: u16 = 1;
 
-impl JVM {
-  pub(crate) fn native_dispatcher_sun_nio_ch_net(
-    &mut self,
-    name: &str,
-    type_str: &str,
-  ) -> Result<Option<types::Type>> {
-    match (name, type_str) {
-      ("initIDs", "()V") => self.exec_native_sun_nio_ch_net_init_ids(),
-      ("pollinValue", "()S") => self.exec_native_sun_nio_ch_pollin_value(),
-      ("polloutValue", "()S") => self.exec_native_sun_nio_ch_pollout_value(),
-      ("pollerrValue", "()S") => self.exec_native_sun_nio_ch_pollerr_value(),
-      ("pollhupValue", "()S") => self.exec_native_sun_nio_ch_pollhup_value(),
-      ("pollnvalValue", "()S") => self.exec_native_sun_nio_ch_pollnval_value(),
-      ("pollconnValue", "()S") => self.exec_native_sun_nio_ch_pollconn_value(),
-      ("isExclusiveBindAvailable", "()I") => {
-        self.exec_native_sun_nio_ch_is_exclusive_bind_available()
-      }
-      ("isIPv6Available0", "()Z") => self.exec_native_sun_nio_ch_is_ipv6_available(),
-      ("isReusePortAvailable0", "()Z") => self.exec_native_sun_nio_ch_is_reuse_port_available(),
-      ("socket0", "(ZZZZ)I") => self.exec_native_sun_nio_ch_socket0(),
-      ("connect0", "(ZLjava/io/FileDescriptor;Ljava/net/InetAddress;I)I") => {
-        self.exec_native_sun_nio_ch_connect0()
-      }
-      ("localInetAddress", "(Ljava/io/FileDescriptor;)Ljava/net/InetAddress;") => {
-        self.exec_native_sun_nio_ch_localinetaddress()
-      }
-      ("localPort", "(Ljava/io/FileDescriptor;)I") => self.exec_native_sun_nio_ch_localport(),
-      _ => Err(eyre!(errors::InternalError::NativeNotImplemented(
-        "sun/nio/ch/Net".to_string(),
-        name.to_owned(),
-        type_str.to_owned()
-      ))),
-    }
-  }
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  let class_name = "sun/nio/ch/Net";
+
+  registry.add(class_name, "initIDs", "()V", |jvm, _, _| {
+    jvm.exec_native_sun_nio_ch_net_init_ids()
+  });
+  registry.add(class_name, "pollinValue", "()S", |jvm, _, _| {
+    jvm.exec_native_sun_nio_ch_pollin_value()
+  });
+  registry.add(class_name, "polloutValue", "()S", |jvm, _, _| {
+    jvm.exec_native_sun_nio_ch_pollout_value()
+  });
+  registry.add(class_name, "pollerrValue", "()S", |jvm, _, _| {
+    jvm.exec_native_sun_nio_ch_pollerr_value()
+  });
+  registry.add(class_name, "pollhupValue", "()S", |jvm, _, _| {
+    jvm.exec_native_sun_nio_ch_pollhup_value()
+  });
+  registry.add(class_name, "pollnvalValue", "()S", |jvm, _, _| {
+    jvm.exec_native_sun_nio_ch_pollnval_value()
+  });
+  registry.add(class_name, "pollconnValue", "()S", |jvm, _, _| {
+    jvm.exec_native_sun_nio_ch_pollconn_value()
+  });
+  registry.add(
+    class_name,
+    "isExclusiveBindAvailable",
+    "()I",
+    |jvm, _, _| jvm.exec_native_sun_nio_ch_is_exclusive_bind_available(),
+  );
+  registry.add(class_name, "isIPv6Available0", "()Z", |jvm, _, _| {
+    jvm.exec_native_sun_nio_ch_is_ipv6_available()
+  });
+  registry.add(class_name, "isReusePortAvailable0", "()Z", |jvm, _, _| {
+    jvm.exec_native_sun_nio_ch_is_reuse_port_available()
+  });
+  registry.add(class_name, "socket0", "(ZZZZ)I", |jvm, _, _| {
+    jvm.exec_native_sun_nio_ch_socket0()
+  });
+  registry.add(
+    class_name,
+    "connect0",
+    "(ZLjava/io/FileDescriptor;Ljava/net/InetAddress;I)I",
+    |jvm, _, _| jvm.exec_native_sun_nio_ch_connect0(),
+  );
+  registry.add(
+    class_name,
+    "localInetAddress",
+    "(Ljava/io/FileDescriptor;)Ljava/net/InetAddress;",
+    |jvm, _, _| jvm.exec_native_sun_nio_ch_localinetaddress(),
+  );
+  registry.add(
+    class_name,
+    "localPort",
+    "(Ljava/io/FileDescriptor;)I",
+    |jvm, _, _| jvm.exec_native_sun_nio_ch_localport(),
+  );
+}
 
+impl JVM {
   fn exec_native_sun_nio_ch_net_init_ids(&mut self) -> Result<Option<types::Type>> {
     warn!("sun/nio/ch/Net.initIDs not supported");
 
@@ -1,25 +1,22 @@
-use crate::runtime::{errors, jvm::*, types};
+use crate::{
+  native::registry::NativeRegistry,
+  runtime::{errors, jvm::*, types},
+};
 use color_eyre::eyre::{eyre, Result};
 
-impl JVM {
-  pub(crate) fn native_dispatcher_sun_nio_ch_socketdispatcher(
-    &mut self,
-    name: &str,
-    type_str: &str,
-  ) -> Result<Option<types::Type>> {
-    match (name, type_str) {
-      // polymorphic signature
-      ("write0", "(Ljava/io/FileDescriptor;JI)I") => {
-        self.exec_native_sun_nio_ch_socketdispatcher_write0()
-      }
-      _ => Err(eyre!(errors::InternalError::NativeNotImplemented(
-        "sun/nio/ch/SocketDispatcher".to_string(),
-        name.to_owned(),
-        type_str.to_owned()
-      ))),
-    }
-  }
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  let class_name = "sun/nio/ch/SocketDispatcher";
 
+  // polymorphic signature
+  registry.add(
+    class_name,
+    "write0",
+    "(Ljava/io/FileDescriptor;JI)I",
+    |jvm, _, _| jvm.exec_native_sun_nio_ch_socketdispatcher_write0(),
+  );
+}
+
+impl JVM {
   fn exec_native_sun_nio_ch_socketdispatcher_write0(&mut self) -> Result<Option<types::Type>> {
     let length = self.pop_stack()?.as_integer()?;
     let address = self.pop_stack()?.as_long()? as u64;
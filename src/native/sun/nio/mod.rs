@@ -1 +1,7 @@
 pub mod ch;
+
+use crate::native::registry::NativeRegistry;
+
+pub(crate) fn register_natives(registry: &mut NativeRegistry) {
+  ch::register_natives(registry);
+}
@@ -0,0 +1,254 @@
+use super::jvm::*;
+use crate::{
+  class_loader::{class_file::ResolvedMember, loader::ClassLoader},
+  runtime::errors,
+  runtime::types,
+  utils::*,
+};
+use color_eyre::eyre::{eyre, Result};
+
+impl JVM {
+  /// Check that `accessor` can access the field referenced as `class_name.field_name`
+  ///
+  /// The declaration of the field is resolved once for the field ref at `index`, only the
+  /// receiver of a protected field is checked again.
+  ///
+  /// # Arguments
+  ///
+  /// * `accessor` - Class executing the instruction
+  /// * `index` - Index of the field ref in the constant pool of `accessor`
+  /// * `class_name` - Class named in the field reference
+  /// * `field_name` - Name of the field
+  /// * `field_type` - Descriptor of the field
+  /// * `receiver` - Object the field is read from/written to (None for static fields)
+  pub(crate) fn check_field_access(
+    &mut self,
+    accessor: &str,
+    index: ju2,
+    class_name: &str,
+    field_name: &str,
+    field_type: &str,
+    receiver: Option<types::Type>,
+  ) -> Result<()> {
+    let resolved = self
+      .class_loader
+      .get(accessor)?
+      .get_resolved_member(index)
+      .cloned();
+
+    let member = match resolved {
+      Some(member) => member,
+      None => {
+        let member = self.resolve_field_access(accessor, class_name, field_name, field_type)?;
+
+        self
+          .class_loader
+          .get_mut(accessor)?
+          .set_resolved_member(index, member.clone());
+
+        member
+      }
+    };
+
+    if let Some(receiver) = receiver {
+      self.check_protected_receiver(
+        accessor,
+        class_name,
+        &member.declaring,
+        member.access_flags,
+        receiver,
+      )?;
+    }
+
+    Ok(())
+  }
+
+  /// Check that `accessor` can access the method referenced as `class_name.method_name`
+  ///
+  /// The declaration of the method is resolved once for the method ref at `index`, only the
+  /// receiver of a protected method is checked again.
+  ///
+  /// # Arguments
+  ///
+  /// * `accessor` - Class executing the instruction
+  /// * `index` - Index of the method ref in the constant pool of `accessor`
+  /// * `class_name` - Class named in the method reference
+  /// * `method_name` - Name of the method
+  /// * `method_type` - Descriptor of the method
+  /// * `receiver` - Object the method is invoked on (None for static and special calls)
+  pub(crate) fn check_method_access(
+    &mut self,
+    accessor: &str,
+    index: ju2,
+    class_name: &str,
+    method_name: &str,
+    method_type: &str,
+    receiver: Option<types::Type>,
+  ) -> Result<()> {
+    let resolved = self
+      .class_loader
+      .get(accessor)?
+      .get_resolved_member(index)
+      .cloned();
+
+    let member = match resolved {
+      Some(member) => member,
+      None => {
+        let member = self.resolve_method_access(accessor, class_name, method_name, method_type)?;
+
+        self
+          .class_loader
+          .get_mut(accessor)?
+          .set_resolved_member(index, member.clone());
+
+        member
+      }
+    };
+
+    if let Some(receiver) = receiver {
+      self.check_protected_receiver(
+        accessor,
+        class_name,
+        &member.declaring,
+        member.access_flags,
+        receiver,
+      )?;
+    }
+
+    Ok(())
+  }
+
+  /// Find the declaration of the field `class_name.field_name`, check that `accessor`
+  /// can access it and add the loader constraints of its descriptor
+  ///
+  /// # Returns
+  ///
+  /// `NoSuchFieldError` if the field is not found, `IllegalAccessError` if it is not
+  /// accessible, `LinkageError` if a loader constraint is violated
+  pub(crate) fn resolve_field_access(
+    &mut self,
+    accessor: &str,
+    class_name: &str,
+    field_name: &str,
+    field_type: &str,
+  ) -> Result<ResolvedMember> {
+    let Some((declaring, access_flags)) = self
+      .class_loader
+      .find_field_declaration(class_name, field_name, field_type)?
+    else {
+      return Err(eyre!(errors::JavaException::NoSuchFieldError(format!(
+        "Class {} does not have member field '{} {}'",
+        class_to_dotclass(class_name),
+        field_type,
+        field_name
+      ))));
+    };
+
+    self.class_loader.check_member_access(
+      accessor,
+      class_name,
+      &declaring,
+      field_name,
+      access_flags,
+    )?;
+
+    self.check_loader_constraints(accessor, &declaring, field_type)?;
+
+    Ok(ResolvedMember {
+      declaring,
+      access_flags,
+    })
+  }
+
+  /// Find the declaration of the method `class_name.method_name`, check that `accessor`
+  /// can access it and add the loader constraints of its descriptor
+  ///
+  /// # Returns
+  ///
+  /// `NoSuchMethodError` if the method is not found, `IllegalAccessError` if it is not
+  /// accessible, `LinkageError` if a loader constraint is violated
+  pub(crate) fn resolve_method_access(
+    &mut self,
+    accessor: &str,
+    class_name: &str,
+    method_name: &str,
+    method_type: &str,
+  ) -> Result<ResolvedMember> {
+    let Some((declaring, access_flags)) =
+      self
+        .class_loader
+        .find_method_declaration(class_name, method_name, method_type)?
+    else {
+      return Err(eyre!(errors::JavaException::NoSuchMethodError(format!(
+        "'{}.{}{}'",
+        class_to_dotclass(class_name),
+        method_name,
+        method_type
+      ))));
+    };
+
+    self.class_loader.check_member_access(
+      accessor,
+      class_name,
+      &declaring,
+      method_name,
+      access_flags,
+    )?;
+
+    self.check_loader_constraints(accessor, &declaring, method_type)?;
+
+    Ok(ResolvedMember {
+      declaring,
+      access_flags,
+    })
+  }
+
+  // https://docs.oracle.com/javase/specs/jvms/se23/html/jvms-4.html#jvms-4.10.1.8
+  // A protected instance member declared in a superclass living in another run-time
+  // package can only be accessed through an object of the current class (or a subclass)
+  pub(crate) fn check_protected_receiver(
+    &mut self,
+    accessor: &str,
+    class_name: &str,
+    declaring: &str,
+    access_flags: ju2,
+    receiver: types::Type,
+  ) -> Result<()> {
+    // ACC_PROTECTED and ACC_STATIC
+    if access_flags & 0x0004 == 0 || access_flags & 0x0008 != 0 {
+      return Ok(());
+    }
+
+    if class_name.starts_with('[') || ClassLoader::is_same_package(accessor, declaring) {
+      return Ok(());
+    }
+
+    // only the subclass rule grants access
+    if accessor == declaring || !self.class_loader.is_same_or_subclass(declaring, accessor)? {
+      return Ok(());
+    }
+
+    let receiver_class = match receiver {
+      types::Type::ObjectRef(obj_ref) | types::Type::ArrayRef(obj_ref) => {
+        self.heap.get_instance(obj_ref)?.get_classname().to_string()
+      }
+      // null receivers raise a NullPointerException later
+      _ => return Ok(()),
+    };
+
+    if receiver_class.starts_with('[')
+      || !self
+        .class_loader
+        .is_same_or_subclass(accessor, &receiver_class)?
+    {
+      return Err(eyre!(errors::JavaException::IllegalAccessError(format!(
+        "class {} tried to access protected member of {} through {}",
+        class_to_dotclass(accessor),
+        class_to_dotclass(declaring),
+        class_to_dotclass(&receiver_class)
+      ))));
+    }
+
+    Ok(())
+  }
+}
@@ -0,0 +1,287 @@
+use std::sync::{Arc, RwLock};
+
+use super::jvm::*;
+use crate::{
+  class_loader::{class_file::ClassFile, loader::ClassLoader, namespace::BOOTSTRAP_LOADER},
+  runtime::{errors, types},
+  utils::*,
+};
+use color_eyre::eyre::{eyre, Result};
+use log::debug;
+
+impl JVM {
+  /// Load the class `name` with `loader` as initiating loader
+  ///
+  /// A user-defined loader is asked to load the class through
+  /// `ClassLoader.loadClass`, and recorded as an initiating loader of the result
+  ///
+  /// # Returns
+  ///
+  /// The key of the loaded class
+  pub(crate) fn load_class_with_loader(&mut self, loader: ju4, name: &str) -> Result<String> {
+    let name = dotclass_to_class(name);
+
+    if loader == BOOTSTRAP_LOADER {
+      self.class_loader.get_lock(&name)?;
+
+      return Ok(name);
+    }
+
+    if let Some(key) = self.class_loader.find_loaded_class(loader, &name) {
+      return Ok(key);
+    }
+
+    // an array class is loaded by the loader of its element type
+    if let Some(element) = name.strip_prefix('[') {
+      let key = match element.strip_prefix('L') {
+        Some(element) => format!(
+          "[L{};",
+          self.load_class_with_loader(loader, element.trim_end_matches(';'))?
+        ),
+        None if element.starts_with('[') => {
+          format!("[{}", self.load_class_with_loader(loader, element)?)
+        }
+        // primitive element type
+        None => name.clone(),
+      };
+
+      self.class_loader.get_lock(&key)?;
+
+      return Ok(key);
+    }
+
+    // java.* classes can only be defined by the bootstrap loader
+    let key = if name.starts_with("java/") {
+      self.class_loader.get_lock(&name)?;
+
+      name.clone()
+    } else {
+      let loader_class = self
+        .heap
+        .get_obj_instance(loader)?
+        .get_classname()
+        .to_string();
+
+      let name_string = self
+        .heap
+        .alloc_string(&mut self.class_loader, &class_to_dotclass(&name))?;
+
+      debug!("Loading {} with {}", name, loader_class);
+
+      let class_obj = self.call_and_resolve_method(
+        &loader_class,
+        "loadClass",
+        "(Ljava/lang/String;)Ljava/lang/Class;",
+        vec![types::Type::ObjectRef(loader), name_string],
+      )?;
+
+      match class_obj {
+        types::Type::ObjectRef(class_ref) => self.heap.get_classname_from_class_obj(class_ref)?,
+        _ => return Err(eyre!(errors::InternalError::ClassNotFound(name))),
+      }
+    };
+
+    self
+      .class_loader
+      .record_initiating_loader(loader, &name, &key)?;
+
+    Ok(key)
+  }
+
+  /// Resolve the class `name` referenced by the class `context`, using the
+  /// defining loader of `context` as initiating loader (JVMS 5.4.3.1)
+  ///
+  /// # Returns
+  ///
+  /// The key of the resolved class
+  pub(crate) fn resolve_class(&mut self, context: &str, name: &str) -> Result<String> {
+    // a hidden class can only be referenced by itself through its original name
+    if ClassLoader::has_hidden_suffix(context)
+      && ClassLoader::strip_hidden_suffix(&ClassLoader::get_binary_name(context)) == name
+      && self.class_loader.get(context)?.is_hidden()
+    {
+      return Ok(context.to_string());
+    }
+
+    let loader = ClassLoader::get_defining_loader(context);
+
+    // classes of the bootstrap loader are stored under their name
+    if loader == BOOTSTRAP_LOADER {
+      return Ok(name.to_string());
+    }
+
+    self.load_class_with_loader(loader, name)
+  }
+
+  /// Define a class from the bytes of its class file
+  ///
+  /// # Arguments
+  ///
+  /// * `loader` - Defining loader (`BOOTSTRAP_LOADER` for the bootstrap loader)
+  /// * `name` - Expected name of the class, if any
+  /// * `data` - Content of the class file
+  ///
+  /// # Returns
+  ///
+  /// The key of the new class
+  pub(crate) fn define_class(
+    &mut self,
+    loader: ju4,
+    name: Option<&str>,
+    data: &[u8],
+  ) -> Result<String> {
+    let class_file = ClassFile::parse_from_bytes(data)?;
+
+    if let Some(name) = name {
+      let class_name = class_file.read().unwrap().get_name().to_string();
+
+      if dotclass_to_class(name) != class_name {
+        return Err(eyre!(errors::JavaException::LinkageError(format!(
+          "{} (wrong name: {})",
+          class_name,
+          class_to_dotclass(name)
+        ))));
+      }
+    }
+
+    let key = self.class_loader.prepare_class(loader, &class_file)?;
+
+    // recorded once linked, so that a failed definition can be retried
+    self.link_super_types(loader, &key, &class_file)?;
+
+    self.class_loader.define_class(loader, &key, class_file)?;
+
+    debug!("Defined {} with loader {}", key, loader);
+
+    Ok(key)
+  }
+
+  /// Define a hidden class from the bytes of its class file (`Lookup.defineHiddenClass`)
+  ///
+  /// # Arguments
+  ///
+  /// * `loader` - Defining loader, the one of the lookup class
+  /// * `lookup` - Lookup class
+  /// * `data` - Content of the class file
+  /// * `nestmate` - Whether the class joins the nest of the lookup class
+  ///
+  /// # Returns
+  ///
+  /// The key of the new class
+  pub(crate) fn define_hidden_class(
+    &mut self,
+    loader: ju4,
+    lookup: &str,
+    data: &[u8],
+    nestmate: bool,
+  ) -> Result<String> {
+    let class_file = ClassFile::parse_from_bytes(data)?;
+
+    let nest_host = if nestmate {
+      Some(self.class_loader.get_nest_host_name(lookup)?)
+    } else {
+      None
+    };
+
+    let key =
+      self
+        .class_loader
+        .define_hidden_class(loader, lookup, class_file.clone(), nest_host)?;
+
+    self.link_super_types(loader, &key, &class_file)?;
+
+    debug!("Defined hidden class {} with loader {}", key, loader);
+
+    Ok(key)
+  }
+
+  /// Link the class `class_name` before the first execution of its code
+  ///
+  /// The code of the class is verified (JVMS 4.10) unless the verification is
+  /// disabled or the class comes from a JDK module.
+  pub(crate) fn link_class(&mut self, class_name: &str) -> Result<()> {
+    if self.class_loader.get(class_name)?.is_linked() {
+      return Ok(());
+    }
+
+    if self.verify && !self.class_loader.is_trusted(class_name) {
+      self.class_loader.verify_class(class_name)?;
+    }
+
+    self.class_loader.get_mut(class_name)?.link();
+
+    Ok(())
+  }
+
+  // the super class and interfaces are resolved by the defining loader
+  fn link_super_types(
+    &mut self,
+    loader: ju4,
+    key: &str,
+    class_file: &Arc<RwLock<ClassFile>>,
+  ) -> Result<()> {
+    if loader == BOOTSTRAP_LOADER {
+      return Ok(());
+    }
+
+    let (parent, interfaces) = {
+      let class = class_file.read().unwrap();
+
+      let parent = class
+        .has_parent()
+        .then(|| class.get_parent_name().to_owned());
+
+      (parent, class.get_interfaces().clone())
+    };
+
+    let parent = match parent {
+      Some(parent) => Some(self.load_class_with_loader(loader, &parent)?),
+      None => None,
+    };
+
+    let interfaces = interfaces
+      .iter()
+      .map(|interface| self.load_class_with_loader(loader, interface))
+      .collect::<Result<Vec<String>>>()?;
+
+    class_file.write().unwrap().relink(key, parent, interfaces);
+
+    Ok(())
+  }
+
+  /// Add the loader constraints required when the class `context` refers to a
+  /// member declared in `declaring` with the descriptor `descriptor` (JVMS 5.4.3.2-3)
+  ///
+  /// The loaders constrained are the defining loaders of `context` and `declaring`,
+  /// not the one of the class named in the reference.
+  pub(crate) fn check_loader_constraints(
+    &mut self,
+    context: &str,
+    declaring: &str,
+    descriptor: &str,
+  ) -> Result<()> {
+    let left = ClassLoader::get_defining_loader(context);
+    let right = ClassLoader::get_defining_loader(declaring);
+
+    if left == right {
+      return Ok(());
+    }
+
+    let mut rest = descriptor;
+
+    while let Some(start) = rest.find('L') {
+      let end = match rest[start..].find(';') {
+        Some(end) => start + end,
+        None => break,
+      };
+
+      self
+        .class_loader
+        .add_loader_constraint(&rest[start + 1..end], left, right)?;
+
+      rest = &rest[end + 1..];
+    }
+
+    Ok(())
+  }
+}
@@ -34,6 +34,17 @@ pub(crate) const _MN_HIDDEN_MEMBER: i32 = 0x00400000; // members defined in a hi
 pub(crate) const MN_REFERENCE_KIND_SHIFT: i32 = 24; // refKind
 pub(crate) const MN_REFERENCE_KIND_MASK: i32 = 0x0F000000 >> MN_REFERENCE_KIND_SHIFT;
 
+// flags of ClassLoader.defineClass0 (see java/lang/invoke/MethodHandleNatives$Constants)
+pub(crate) const NESTMATE_CLASS: i32 = 0x00000001;
+pub(crate) const HIDDEN_CLASS: i32 = 0x00000002;
+pub(crate) const _STRONG_LOADER_LINK: i32 = 0x00000004;
+pub(crate) const _ACCESS_VM_ANNOTATIONS: i32 = 0x00000008;
+
+// flags of LambdaMetafactory.altMetafactory
+pub(crate) const FLAG_SERIALIZABLE: i32 = 1 << 0;
+pub(crate) const FLAG_MARKERS: i32 = 1 << 1;
+pub(crate) const FLAG_BRIDGES: i32 = 1 << 2;
+
 #[derive(Debug)]
 #[allow(dead_code)]
 pub enum ConstantInfo {
@@ -0,0 +1,281 @@
+use color_eyre::eyre::{eyre, Result};
+
+use crate::{
+  runtime::{embedding::Global, errors, heap::BOX_CLASSES, jvm::*, types::Type},
+  utils::descriptor_to_classname,
+};
+
+/// Conversion of a Rust value into a Java value, allocating its object in the VM if needed
+pub trait ToJava {
+  fn to_java(&self, jvm: &mut JVM) -> Result<Type>;
+
+  /// Descriptor of the Java type of the values, used for the elements of the arrays
+  fn get_descriptor() -> String
+  where
+    Self: Sized,
+  {
+    "Ljava/lang/Object;".to_string()
+  }
+}
+
+/// Conversion of a Java value into a Rust value
+///
+/// The primitive types are also converted from their boxes (i.e. `java.lang.Integer` for `i32`).
+pub trait FromJava: Sized {
+  fn from_java(jvm: &mut JVM, value: Type) -> Result<Self>;
+}
+
+macro_rules! primitive_conversions {
+  ($($rust:ty => $descriptor:literal, $variant:ident, $as:ident);* $(;)?) => {
+    $(
+      impl ToJava for $rust {
+        fn to_java(&self, _jvm: &mut JVM) -> Result<Type> {
+          Ok(Type::$variant(*self))
+        }
+
+        fn get_descriptor() -> String {
+          $descriptor.to_string()
+        }
+      }
+
+      impl FromJava for $rust {
+        fn from_java(jvm: &mut JVM, value: Type) -> Result<Self> {
+          unbox(jvm, value, $descriptor)?.$as()
+        }
+      }
+    )*
+  };
+}
+
+primitive_conversions! {
+  bool => "Z", Boolean, as_bool;
+  i8 => "B", Byte, as_byte;
+  i16 => "S", Short, as_short;
+  i32 => "I", Integer, as_integer;
+  i64 => "J", Long, as_long;
+  f32 => "F", Float, as_float;
+  f64 => "D", Double, as_double;
+}
+
+// the primitive value of `value`, or the one of its box
+fn unbox(jvm: &JVM, value: Type, descriptor: &'static str) -> Result<Type> {
+  let obj_ref = match value {
+    Type::Null => return Err(eyre!(errors::JavaException::NullPointer)),
+    Type::ObjectRef(obj_ref) | Type::ArrayRef(obj_ref) => obj_ref,
+    Type::None | Type::_Unitialized => {
+      return Err(eyre!(errors::InternalError::WrongType(descriptor, value)))
+    }
+    value => return Ok(value),
+  };
+
+  match jvm.heap.get_boxed_value(obj_ref)? {
+    Some((_, value)) => Ok(value),
+    None => Err(eyre!(errors::InternalError::WrongType(descriptor, value))),
+  }
+}
+
+/// Chars are kept as ints, only the ones of the Basic Multilingual Plane fit in a Java `char`
+impl ToJava for char {
+  fn to_java(&self, _jvm: &mut JVM) -> Result<Type> {
+    let value = u16::try_from(*self as u32).map_err(|_| {
+      eyre!(errors::JavaException::IllegalArgumentException(format!(
+        "{:?} is not a Java char",
+        self
+      )))
+    })?;
+
+    Ok(Type::Integer(value as i32))
+  }
+
+  fn get_descriptor() -> String {
+    "C".to_string()
+  }
+}
+
+impl FromJava for char {
+  fn from_java(jvm: &mut JVM, value: Type) -> Result<Self> {
+    let value = unbox(jvm, value, "C")?.as_integer()? as u16;
+
+    // an unpaired surrogate is replaced
+    Ok(char::from_u32(value as u32).unwrap_or(char::REPLACEMENT_CHARACTER))
+  }
+}
+
+impl FromJava for () {
+  fn from_java(_jvm: &mut JVM, _value: Type) -> Result<Self> {
+    Ok(())
+  }
+}
+
+impl ToJava for &str {
+  fn to_java(&self, jvm: &mut JVM) -> Result<Type> {
+    jvm.heap.alloc_string(&mut jvm.class_loader, self)
+  }
+
+  fn get_descriptor() -> String {
+    "Ljava/lang/String;".to_string()
+  }
+}
+
+impl ToJava for String {
+  fn to_java(&self, jvm: &mut JVM) -> Result<Type> {
+    self.as_str().to_java(jvm)
+  }
+
+  fn get_descriptor() -> String {
+    <&str>::get_descriptor()
+  }
+}
+
+impl FromJava for String {
+  fn from_java(jvm: &mut JVM, value: Type) -> Result<Self> {
+    if value == Type::Null {
+      return Err(eyre!(errors::JavaException::NullPointer));
+    }
+
+    let obj_ref = value.as_ref()?;
+    let classname = jvm.heap.get_instance(obj_ref)?.get_classname();
+
+    if classname != "java/lang/String" {
+      return Err(eyre!(errors::JavaException::ClassCastException(format!(
+        "class {} cannot be cast to class java.lang.String",
+        classname.replace('/', ".")
+      ))));
+    }
+
+    jvm.heap.get_string(obj_ref)
+  }
+}
+
+/// Arrays, with the elements stored like the ones of the Java arrays
+impl<T: ToJava> ToJava for &[T] {
+  fn to_java(&self, jvm: &mut JVM) -> Result<Type> {
+    let element_type = descriptor_to_classname(&T::get_descriptor());
+
+    let mut elements = vec![];
+
+    for element in self.iter() {
+      let value = element.to_java(jvm)?;
+
+      elements.push(Type::to_stored_value(&element_type, value)?);
+    }
+
+    let len = elements.len();
+
+    jvm.heap.alloc_array(&element_type, elements, len)
+  }
+
+  fn get_descriptor() -> String {
+    format!("[{}", T::get_descriptor())
+  }
+}
+
+impl<T: ToJava> ToJava for Vec<T> {
+  fn to_java(&self, jvm: &mut JVM) -> Result<Type> {
+    self.as_slice().to_java(jvm)
+  }
+
+  fn get_descriptor() -> String {
+    <&[T]>::get_descriptor()
+  }
+}
+
+impl<T: FromJava> FromJava for Vec<T> {
+  fn from_java(jvm: &mut JVM, value: Type) -> Result<Self> {
+    if value == Type::Null {
+      return Err(eyre!(errors::JavaException::NullPointer));
+    }
+
+    let elements = jvm
+      .heap
+      .get_array_instance(value.as_ref()?)?
+      .get_elements()
+      .clone();
+
+    elements
+      .into_iter()
+      .map(|element| T::from_java(jvm, element))
+      .collect()
+  }
+}
+
+/// `None` is `null`, the primitive types are boxed
+impl<T: ToJava> ToJava for Option<T> {
+  fn to_java(&self, jvm: &mut JVM) -> Result<Type> {
+    let Some(value) = self else {
+      return Ok(Type::Null);
+    };
+
+    let value = value.to_java(jvm)?;
+    let descriptor = T::get_descriptor();
+
+    match BOX_CLASSES
+      .iter()
+      .find(|(primitive, _)| *primitive == descriptor)
+    {
+      Some((_, box_classname)) => box_value(jvm, box_classname, &descriptor, value),
+      None => Ok(value),
+    }
+  }
+
+  fn get_descriptor() -> String {
+    let descriptor = T::get_descriptor();
+
+    match BOX_CLASSES
+      .iter()
+      .find(|(primitive, _)| *primitive == descriptor)
+    {
+      Some((_, box_classname)) => format!("L{};", box_classname),
+      None => descriptor,
+    }
+  }
+}
+
+// boxed by `valueOf` like the code of javac, the cached boxes are the ones of Java
+fn box_value(jvm: &mut JVM, box_classname: &str, descriptor: &str, value: Type) -> Result<Type> {
+  jvm.init_class(box_classname)?;
+
+  let mut args = vec![];
+
+  value.push_to_locals(&mut args);
+
+  jvm.call_and_resolve_method(
+    box_classname,
+    "valueOf",
+    &format!("({})L{};", descriptor, box_classname),
+    args,
+  )
+}
+
+impl<T: FromJava> FromJava for Option<T> {
+  fn from_java(jvm: &mut JVM, value: Type) -> Result<Self> {
+    match value {
+      Type::Null => Ok(None),
+      value => Ok(Some(T::from_java(jvm, value)?)),
+    }
+  }
+}
+
+impl ToJava for Type {
+  fn to_java(&self, _jvm: &mut JVM) -> Result<Type> {
+    Ok(*self)
+  }
+}
+
+impl FromJava for Type {
+  fn from_java(_jvm: &mut JVM, value: Type) -> Result<Self> {
+    Ok(value)
+  }
+}
+
+impl ToJava for Global {
+  fn to_java(&self, _jvm: &mut JVM) -> Result<Type> {
+    Ok(self.get())
+  }
+}
+
+impl FromJava for Global {
+  fn from_java(jvm: &mut JVM, value: Type) -> Result<Self> {
+    Ok(jvm.new_global(value))
+  }
+}
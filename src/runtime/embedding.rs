@@ -0,0 +1,487 @@
+use std::{
+  collections::HashMap,
+  fmt,
+  io::{Read, Write},
+  mem,
+  sync::{Arc, Mutex},
+};
+
+use color_eyre::eyre::{eyre, Report, Result};
+
+use crate::{
+  runtime::{
+    conversion::{FromJava, ToJava},
+    errors::{self, JavaError},
+    heap::BOX_CLASSES,
+    jvm::*,
+    stdio::Stdio,
+    types::{self, Type},
+  },
+  utils::*,
+};
+
+/// Configuration of a VM embedded in a Rust program
+///
+/// ```no_run
+/// use jvmcraft::JvmBuilder;
+///
+/// let mut jvm = JvmBuilder::new()
+///   .class_path("samples")
+///   .module_path("/path/to/java_modules")
+///   .property("app.name", "embedded")
+///   .stdout(Vec::new())
+///   .build()
+///   .unwrap();
+///
+/// let sum: i32 = jvm.invoke_static("math/Math", "add", "(II)I", &[&1, &2]).unwrap();
+/// ```
+pub struct JvmBuilder {
+  class_path: String,
+  module_path: Option<String>,
+  properties: Vec<(String, String)>,
+  stdio: Stdio,
+  verify: bool,
+}
+
+impl JvmBuilder {
+  pub fn new() -> JvmBuilder {
+    JvmBuilder {
+      class_path: ".".to_string(),
+      module_path: None,
+      properties: vec![],
+      stdio: Stdio::default(),
+      verify: true,
+    }
+  }
+
+  /// Folder with the classes of the user (the current one by default)
+  pub fn class_path(&mut self, class_path: &str) -> &mut JvmBuilder {
+    self.class_path = class_path.to_string();
+
+    self
+  }
+
+  /// Folder with the java modules (`JMODS` by default)
+  pub fn module_path(&mut self, module_path: &str) -> &mut JvmBuilder {
+    self.module_path = Some(module_path.to_string());
+
+    self
+  }
+
+  /// System property, like `-Dkey=value`
+  pub fn property(&mut self, key: &str, value: &str) -> &mut JvmBuilder {
+    self.properties.push((key.to_string(), value.to_string()));
+
+    self
+  }
+
+  /// Stream read by `System.in` (the one of the process by default)
+  pub fn stdin<R: Read + 'static>(&mut self, stdin: R) -> &mut JvmBuilder {
+    self.stdio.stdin = Some(Box::new(stdin));
+
+    self
+  }
+
+  /// Stream written by `System.out` (the one of the process by default)
+  pub fn stdout<W: Write + 'static>(&mut self, stdout: W) -> &mut JvmBuilder {
+    self.stdio.stdout = Some(Box::new(stdout));
+
+    self
+  }
+
+  /// Stream written by `System.err` (the one of the process by default)
+  pub fn stderr<W: Write + 'static>(&mut self, stderr: W) -> &mut JvmBuilder {
+    self.stdio.stderr = Some(Box::new(stderr));
+
+    self
+  }
+
+  /// Verify the code of the classes before running it, like `-Xverify` (on by default)
+  pub fn verify(&mut self, verify: bool) -> &mut JvmBuilder {
+    self.verify = verify;
+
+    self
+  }
+
+  /// Create and bootstrap the VM, the streams are moved to it
+  pub fn build(&mut self) -> Result<JVM> {
+    let mut jvm = self.create()?;
+
+    jvm.bootstrap(&self.class_path)?;
+
+    Ok(jvm)
+  }
+
+  /// The VM with only the modules loaded (i.e. `System` is not initialized)
+  #[allow(dead_code)]
+  pub(crate) fn mock(&mut self) -> Result<JVM> {
+    let mut jvm = self.create()?;
+
+    jvm.bootstrap_mock(&self.class_path)?;
+
+    Ok(jvm)
+  }
+
+  fn create(&mut self) -> Result<JVM> {
+    let mut jvm = JVM::new(vec![])?;
+
+    if let Some(module_path) = &self.module_path {
+      jvm.module_path = module_path.clone();
+    }
+
+    jvm.properties = self.properties.clone();
+    jvm.stdio = mem::take(&mut self.stdio);
+    jvm.verify = self.verify;
+
+    Ok(jvm)
+  }
+}
+
+impl Default for JvmBuilder {
+  fn default() -> Self {
+    JvmBuilder::new()
+  }
+}
+
+/// Handle keeping a Java object alive across calls, until it is dropped
+///
+/// The clones of a handle keep the same object alive. A handle is `Send` and `Sync`, so that
+/// a `JavaError` keeps the object of its exception, but it is only used by its VM.
+pub struct Global {
+  value: Type,
+  roots: Arc<Mutex<HashMap<ju4, usize>>>,
+}
+
+impl Global {
+  pub fn get(&self) -> Type {
+    self.value
+  }
+
+  pub fn is_null(&self) -> bool {
+    self.value == Type::Null
+  }
+
+  fn new(value: Type, roots: Arc<Mutex<HashMap<ju4, usize>>>) -> Global {
+    if let Some(obj_ref) = Self::get_obj_ref(value) {
+      *roots.lock().unwrap().entry(obj_ref).or_insert(0) += 1;
+    }
+
+    Global { value, roots }
+  }
+
+  fn get_obj_ref(value: Type) -> Option<ju4> {
+    match value {
+      Type::ObjectRef(obj_ref) | Type::ArrayRef(obj_ref) if obj_ref != 0 => Some(obj_ref),
+      _ => None,
+    }
+  }
+}
+
+impl Clone for Global {
+  fn clone(&self) -> Self {
+    Global::new(self.value, self.roots.clone())
+  }
+}
+
+impl Drop for Global {
+  fn drop(&mut self) {
+    let Some(obj_ref) = Self::get_obj_ref(self.value) else {
+      return;
+    };
+
+    let mut roots = self.roots.lock().unwrap();
+
+    if let Some(count) = roots.get_mut(&obj_ref) {
+      *count -= 1;
+
+      if *count == 0 {
+        roots.remove(&obj_ref);
+      }
+    }
+  }
+}
+
+// handles of the same object
+impl PartialEq for Global {
+  fn eq(&self, other: &Self) -> bool {
+    self.value == other.value
+  }
+}
+
+impl fmt::Debug for Global {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_tuple("Global").field(&self.value).finish()
+  }
+}
+
+impl JVM {
+  /// Load and initialize the class `name` (i.e. `java/lang/String`)
+  ///
+  /// # Returns
+  ///
+  /// The `Class` object of the class, or a `JavaError` for a `NoClassDefFoundError` if the
+  /// class is not found
+  pub fn find_class(&mut self, name: &str) -> Result<Global> {
+    let classname = dotclass_to_class(name);
+
+    self.load_class(&classname)?;
+
+    let class = self
+      .heap
+      .alloc_class_obj(&mut self.class_loader, &classname)?;
+
+    Ok(self.new_global(class))
+  }
+
+  /// Create an object of the class `class_name` with its constructor of type `descriptor`
+  pub fn new_object(
+    &mut self,
+    class_name: &str,
+    descriptor: &str,
+    args: &[&dyn ToJava],
+  ) -> Result<Global> {
+    let classname = dotclass_to_class(class_name);
+
+    self.load_class(&classname)?;
+
+    let obj = self.heap.alloc_obj(&mut self.class_loader, &classname)?;
+
+    self.call_from_rust(&classname, "<init>", descriptor, Some(obj), args)?;
+
+    Ok(self.new_global(obj))
+  }
+
+  /// Call the static method `name` of type `descriptor` of the class `class_name`
+  ///
+  /// The arguments are converted to the types of the parameters like the ones of
+  /// `Method.invoke`: the primitive values are widened, boxed into the parameters of a reference
+  /// type and unboxed from their boxes.
+  pub fn invoke_static<R: FromJava>(
+    &mut self,
+    class_name: &str,
+    name: &str,
+    descriptor: &str,
+    args: &[&dyn ToJava],
+  ) -> Result<R> {
+    let classname = dotclass_to_class(class_name);
+
+    self.load_class(&classname)?;
+
+    let returned = self.call_from_rust(&classname, name, descriptor, None, args)?;
+
+    self.convert_from_java(returned)
+  }
+
+  /// Call the method `name` of type `descriptor` of the object `obj`, selected by its class
+  pub fn invoke<R: FromJava>(
+    &mut self,
+    obj: &dyn ToJava,
+    name: &str,
+    descriptor: &str,
+    args: &[&dyn ToJava],
+  ) -> Result<R> {
+    let obj = obj.to_java(self)?;
+
+    let classname = match obj {
+      Type::Null => {
+        return Err(eyre!(
+          self.java_error_of(errors::JavaException::NullPointer)?
+        ))
+      }
+      Type::ObjectRef(obj_ref) | Type::ArrayRef(obj_ref) => {
+        match self.heap.get_instance(obj_ref)? {
+          types::Instance::ObjectInstance(instance) => instance.get_classname().to_string(),
+          types::Instance::ArrayInstance(_) => "java/lang/Object".to_string(),
+        }
+      }
+      _ => return Err(eyre!(errors::InternalError::WrongType("ObjectRef", obj))),
+    };
+
+    let returned = self.call_from_rust(&classname, name, descriptor, Some(obj), args)?;
+
+    self.convert_from_java(returned)
+  }
+
+  /// Handle keeping `value` alive until it is dropped
+  pub fn new_global(&mut self, value: Type) -> Global {
+    Global::new(value, self.globals.clone())
+  }
+
+  // load and initialize a class, its absence is a Java error and the errors of its loading
+  // (e.g. ClassFormatError) are kept
+  fn load_class(&mut self, classname: &str) -> Result<()> {
+    if let Err(err) = self.class_loader.get(classname).map(|_| ()) {
+      if err.downcast_ref::<errors::JavaException>().is_some() {
+        return Err(self.catch_java_exception(err));
+      }
+
+      return Err(eyre!(self.new_java_error(
+        "java/lang/NoClassDefFoundError",
+        Some(classname.to_string())
+      )?));
+    }
+
+    self
+      .init_class(classname)
+      .map_err(|err| self.catch_java_exception(err))
+  }
+
+  fn call_from_rust(
+    &mut self,
+    classname: &str,
+    name: &str,
+    descriptor: &str,
+    this: Option<Type>,
+    args: &[&dyn ToJava],
+  ) -> Result<Type> {
+    let param_types = get_argument_classnames(descriptor);
+
+    if args.len() != param_types.len() {
+      let message = format!(
+        "wrong number of arguments: {} expected: {}",
+        args.len(),
+        param_types.len()
+      );
+
+      return Err(eyre!(self.new_java_error(
+        "java/lang/IllegalArgumentException",
+        Some(message)
+      )?));
+    }
+
+    let mut values = vec![];
+
+    values.extend(this);
+
+    for (param_type, arg) in param_types.iter().zip(args) {
+      let value = arg.to_java(self)?;
+
+      let value = self
+        .convert_argument(param_type, value)
+        .map_err(|err| self.catch_java_exception(err))?;
+
+      value.push_to_locals(&mut values);
+    }
+
+    let depth = self.frames.len();
+
+    self.uncaught_exception = None;
+
+    let returned = self.call_and_resolve_method(classname, name, descriptor, values);
+
+    // the frames of an uncaught exception are left
+    self.frames.truncate(depth);
+
+    returned.map_err(|err| self.catch_java_exception(err))
+  }
+
+  fn convert_argument(&mut self, param_type: &str, value: Type) -> Result<Type> {
+    let Some(arg_type) = value.get_primitive_descriptor() else {
+      return self.unbox_reflect_argument(param_type, value);
+    };
+
+    // chars are passed as ints
+    let arg_type = match (arg_type, param_type) {
+      ("I", "C" | "java/lang/Character") => "C",
+      _ => arg_type,
+    };
+
+    let mismatch = || {
+      eyre!(errors::JavaException::IllegalArgumentException(
+        "argument type mismatch".to_string()
+      ))
+    };
+
+    if Type::is_primitive(param_type) {
+      return Type::widen_primitive(arg_type, param_type, value)?.ok_or_else(mismatch);
+    }
+
+    // boxed into the class of its type only
+    let is_other_box = BOX_CLASSES
+      .iter()
+      .any(|(primitive, box_classname)| *box_classname == param_type && *primitive != arg_type);
+
+    if is_other_box {
+      return Err(mismatch());
+    }
+
+    let boxed = self
+      .heap
+      .alloc_boxed(&mut self.class_loader, arg_type, value)?;
+
+    self.unbox_reflect_argument(param_type, boxed)
+  }
+
+  fn convert_from_java<R: FromJava>(&mut self, value: Type) -> Result<R> {
+    R::from_java(self, value).map_err(|err| self.catch_java_exception(err))
+  }
+
+  // the Java exceptions become `JavaError`
+  fn catch_java_exception(&mut self, err: Report) -> Report {
+    match err.downcast::<errors::JavaException>() {
+      Ok(exception) => match self.java_error_of(exception) {
+        Ok(java_error) => eyre!(java_error),
+        Err(err) => err,
+      },
+      Err(err) => err,
+    }
+  }
+
+  fn java_error_of(&mut self, exception: errors::JavaException) -> Result<JavaError> {
+    let class_name =
+      errors::JavaException::convert_java_exception_to_classname(&exception).to_string();
+
+    let exec_ref = match (exception, self.uncaught_exception.take()) {
+      (errors::JavaException::Throwable(_, exec_ref), _) => exec_ref,
+      // the object thrown by the Java code, with its cause and its stack trace
+      (_, Some(exec_ref))
+        if self.heap.get_obj_instance(exec_ref)?.get_classname() == class_name =>
+      {
+        exec_ref
+      }
+      // raised by the VM itself
+      (exception, _) => {
+        let message = exception.get_message().map(str::to_string);
+
+        return self.new_java_error(&class_name, message);
+      }
+    };
+
+    let message = self
+      .heap
+      .get_obj_instance(exec_ref)?
+      .get_field("detailMessage")
+      .ok()
+      .filter(|message| *message != Type::Null)
+      .and_then(|message| self.heap.get_string(message.as_ref().ok()?).ok());
+
+    Ok(JavaError {
+      class_name,
+      message,
+      throwable: self.new_global(Type::ObjectRef(exec_ref)),
+    })
+  }
+
+  // error of a new exception of the class `class_name`, not thrown by Java code
+  fn new_java_error(&mut self, class_name: &str, message: Option<String>) -> Result<JavaError> {
+    let exec_ref = self
+      .heap
+      .alloc_obj(&mut self.class_loader, class_name)?
+      .as_ref()?;
+
+    if let Some(message) = &message {
+      let message = self.heap.alloc_string(&mut self.class_loader, message)?;
+
+      self
+        .heap
+        .get_obj_instance_mut(exec_ref)?
+        .put_field("detailMessage", message)?;
+    }
+
+    Ok(JavaError {
+      class_name: class_name.to_string(),
+      message,
+      throwable: self.new_global(Type::ObjectRef(exec_ref)),
+    })
+  }
+}
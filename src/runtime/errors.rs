@@ -1,6 +1,9 @@
 use thiserror::Error;
 
-use crate::runtime::types;
+use crate::{
+  runtime::{embedding::Global, types},
+  utils::ju4,
+};
 
 #[derive(Error, Debug)]
 pub enum InternalError<'a> {
@@ -17,14 +20,12 @@ pub enum InternalError<'a> {
   General(String),
 
   #[error["InternalException {0}"]]
+  #[allow(dead_code)]
   Exception(String),
 
   #[error["NotImplement"]]
   NotImplemented,
 
-  #[error["NativeNotImplement {0} {1} {2}"]]
-  NativeNotImplemented(String, String, String),
-
   #[error["WrongClass (expected {0}, got {1})"]]
   WrongClass(String, String),
 
@@ -52,6 +53,9 @@ pub enum JavaException {
   #[error["ArrayIndexOutOfBoundsException {0} out of {1}"]]
   ArrayIndexOutOfBounds(usize, usize),
 
+  #[error["IndexOutOfBoundsException ({0})"]]
+  IndexOutOfBounds(String),
+
   #[error["ArithmeticException"]]
   Arithmetic,
 
@@ -61,14 +65,84 @@ pub enum JavaException {
   #[error["FileNotFoundException ({0})"]]
   FileNotFound(String),
 
-  #[error["LinkageError"]]
-  LinkageError,
+  #[error["LinkageError ({0})"]]
+  LinkageError(String),
 
   #[error["AssertionError"]]
   AssertionError,
 
   #[error["IllegalArgumentException ({0})"]]
   IllegalArgumentException(String),
+
+  #[error["IllegalAccessError ({0})"]]
+  IllegalAccessError(String),
+
+  #[error["NoSuchFieldError ({0})"]]
+  NoSuchFieldError(String),
+
+  #[error["NoSuchMethodError ({0})"]]
+  NoSuchMethodError(String),
+
+  #[error["VerifyError ({0})"]]
+  VerifyError(String),
+
+  #[error["ClassFormatError ({0})"]]
+  ClassFormatError(String),
+
+  #[error["UnsupportedClassVersionError ({0})"]]
+  UnsupportedClassVersionError(String),
+
+  #[error["InstantiationException ({0})"]]
+  InstantiationException(String),
+
+  #[error["AbstractMethodError ({0})"]]
+  AbstractMethodError(String),
+
+  #[error["ClassCastException ({0})"]]
+  ClassCastException(String),
+
+  #[error["BootstrapMethodError ({0})"]]
+  BootstrapMethodError(String),
+
+  #[error["StackOverflowError ({0})"]]
+  StackOverflowError(String),
+
+  #[error["UnsupportedOperationException ({0})"]]
+  UnsupportedOperationException(String),
+
+  #[error["IllegalStateException ({0})"]]
+  IllegalStateException(String),
+
+  #[error["ArrayStoreException ({0})"]]
+  ArrayStoreException(String),
+
+  #[error["WrongMethodTypeException ({0})"]]
+  WrongMethodTypeException(String),
+
+  #[error["InternalError ({0})"]]
+  InternalError(String),
+
+  #[error["UnsatisfiedLinkError ({0})"]]
+  UnsatisfiedLinkError(String),
+
+  #[error["InvocationTargetException ({0})"]]
+  InvocationTargetException(Box<JavaException>),
+
+  /// Exception of a class without a dedicated variant, kept as the thrown object
+  #[error["{0}"]]
+  Throwable(String, ju4),
+}
+
+/// Java exception not caught by the Java code called from Rust
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error["{class_name}: {}", message.as_deref().unwrap_or("No Message")]]
+pub struct JavaError {
+  /// Binary name of the class of the exception (i.e. `java/lang/IllegalStateException`)
+  pub class_name: String,
+  /// Detail message of the exception
+  pub message: Option<String>,
+  /// The exception object, to read its cause or its stack trace
+  pub throwable: Global,
 }
 
 impl JavaException {
@@ -77,29 +151,111 @@ impl JavaException {
       JavaException::NullPointer => "java/lang/NullPointerException",
       JavaException::CloneNotSupported(_) => "java/lang/CloneNotSupportedException",
       JavaException::ArrayIndexOutOfBounds(_, _) => "java/lang/ArrayIndexOutOfBoundsException",
+      JavaException::IndexOutOfBounds(_) => "java/lang/IndexOutOfBoundsException",
       JavaException::Arithmetic => "java/lang/ArithmeticException",
-      JavaException::IO(_) => "java/lang/IOException",
-      JavaException::FileNotFound(_) => "java/lang/FileNotFoundException",
-      JavaException::LinkageError => "java/lang/LinkageError",
+      JavaException::IO(_) => "java/io/IOException",
+      JavaException::FileNotFound(_) => "java/io/FileNotFoundException",
+      JavaException::LinkageError(_) => "java/lang/LinkageError",
       JavaException::AssertionError => "java/lang/AssertionError",
       JavaException::IllegalArgumentException(_) => "java/lang/IllegalArgumentException",
+      JavaException::IllegalAccessError(_) => "java/lang/IllegalAccessError",
+      JavaException::NoSuchFieldError(_) => "java/lang/NoSuchFieldError",
+      JavaException::NoSuchMethodError(_) => "java/lang/NoSuchMethodError",
+      JavaException::VerifyError(_) => "java/lang/VerifyError",
+      JavaException::ClassFormatError(_) => "java/lang/ClassFormatError",
+      JavaException::UnsupportedClassVersionError(_) => "java/lang/UnsupportedClassVersionError",
+      JavaException::InstantiationException(_) => "java/lang/InstantiationException",
+      JavaException::AbstractMethodError(_) => "java/lang/AbstractMethodError",
+      JavaException::ClassCastException(_) => "java/lang/ClassCastException",
+      JavaException::BootstrapMethodError(_) => "java/lang/BootstrapMethodError",
+      JavaException::StackOverflowError(_) => "java/lang/StackOverflowError",
+      JavaException::UnsupportedOperationException(_) => "java/lang/UnsupportedOperationException",
+      JavaException::IllegalStateException(_) => "java/lang/IllegalStateException",
+      JavaException::ArrayStoreException(_) => "java/lang/ArrayStoreException",
+      JavaException::WrongMethodTypeException(_) => "java/lang/invoke/WrongMethodTypeException",
+      JavaException::InternalError(_) => "java/lang/InternalError",
+      JavaException::UnsatisfiedLinkError(_) => "java/lang/UnsatisfiedLinkError",
+      JavaException::InvocationTargetException(_) => "java/lang/reflect/InvocationTargetException",
+      JavaException::Throwable(classname, _) => classname,
     }
   }
 
-  pub(crate) fn convert_classname_to_java_exception(classname: &str, msg: String) -> JavaException {
-    match classname {
+  /// Detail message of the exception, `None` when the variant does not keep it
+  pub(crate) fn get_message(&self) -> Option<&str> {
+    let message = match self {
+      JavaException::CloneNotSupported(msg)
+      | JavaException::IndexOutOfBounds(msg)
+      | JavaException::IO(msg)
+      | JavaException::FileNotFound(msg)
+      | JavaException::LinkageError(msg)
+      | JavaException::IllegalArgumentException(msg)
+      | JavaException::IllegalAccessError(msg)
+      | JavaException::NoSuchFieldError(msg)
+      | JavaException::NoSuchMethodError(msg)
+      | JavaException::VerifyError(msg)
+      | JavaException::ClassFormatError(msg)
+      | JavaException::UnsupportedClassVersionError(msg)
+      | JavaException::InstantiationException(msg)
+      | JavaException::AbstractMethodError(msg)
+      | JavaException::ClassCastException(msg)
+      | JavaException::BootstrapMethodError(msg)
+      | JavaException::StackOverflowError(msg)
+      | JavaException::UnsupportedOperationException(msg)
+      | JavaException::IllegalStateException(msg)
+      | JavaException::ArrayStoreException(msg)
+      | JavaException::WrongMethodTypeException(msg)
+      | JavaException::InternalError(msg)
+      | JavaException::UnsatisfiedLinkError(msg) => msg,
+      _ => return None,
+    };
+
+    // the message of the exceptions rebuilt from a Java object without one
+    if message == "No Message" {
+      return None;
+    }
+
+    Some(message)
+  }
+
+  pub(crate) fn convert_classname_to_java_exception(
+    classname: &str,
+    msg: String,
+  ) -> Option<JavaException> {
+    let exception = match classname {
       "java/lang/NullPointerException" => JavaException::NullPointer,
       "java/lang/CloneNotSupportedException" => JavaException::CloneNotSupported(msg),
       "java/lang/ArrayIndexOutOfBoundsException" => {
         JavaException::ArrayIndexOutOfBounds(usize::MAX, usize::MAX)
       }
+      "java/lang/IndexOutOfBoundsException" => JavaException::IndexOutOfBounds(msg),
       "java/lang/ArithmeticException" => JavaException::Arithmetic,
-      "java/lang/IOException" => JavaException::IO(msg),
-      "java/lang/FileNotFoundException" => JavaException::FileNotFound(msg),
-      "java/lang/LinkageError" => JavaException::LinkageError,
+      "java/io/IOException" => JavaException::IO(msg),
+      "java/io/FileNotFoundException" => JavaException::FileNotFound(msg),
+      "java/lang/LinkageError" => JavaException::LinkageError(msg),
       "java/lang/AssertionError" => JavaException::AssertionError,
       "java/lang/IllegalArgumentException" => JavaException::IllegalArgumentException(msg),
-      _ => panic!("exception not handled {} -> '{}'", classname, msg),
-    }
+      "java/lang/IllegalAccessError" => JavaException::IllegalAccessError(msg),
+      "java/lang/NoSuchFieldError" => JavaException::NoSuchFieldError(msg),
+      "java/lang/NoSuchMethodError" => JavaException::NoSuchMethodError(msg),
+      "java/lang/VerifyError" => JavaException::VerifyError(msg),
+      "java/lang/ClassFormatError" => JavaException::ClassFormatError(msg),
+      "java/lang/UnsupportedClassVersionError" => JavaException::UnsupportedClassVersionError(msg),
+      "java/lang/InstantiationException" => JavaException::InstantiationException(msg),
+      "java/lang/AbstractMethodError" => JavaException::AbstractMethodError(msg),
+      "java/lang/ClassCastException" => JavaException::ClassCastException(msg),
+      "java/lang/BootstrapMethodError" => JavaException::BootstrapMethodError(msg),
+      "java/lang/StackOverflowError" => JavaException::StackOverflowError(msg),
+      "java/lang/UnsupportedOperationException" => {
+        JavaException::UnsupportedOperationException(msg)
+      }
+      "java/lang/IllegalStateException" => JavaException::IllegalStateException(msg),
+      "java/lang/ArrayStoreException" => JavaException::ArrayStoreException(msg),
+      "java/lang/invoke/WrongMethodTypeException" => JavaException::WrongMethodTypeException(msg),
+      "java/lang/InternalError" => JavaException::InternalError(msg),
+      "java/lang/UnsatisfiedLinkError" => JavaException::UnsatisfiedLinkError(msg),
+      _ => return None,
+    };
+
+    Some(exception)
   }
 }
@@ -0,0 +1,747 @@
+#[cfg(target_arch = "x86_64")]
+use std::arch::global_asm;
+use std::{cell::Cell, collections::HashMap, ffi::CString};
+
+use color_eyre::eyre::{eyre, Result};
+use log::{debug, error};
+
+use crate::{
+  runtime::{errors, jvm::JVM, types},
+  utils::{get_parameters_type_descriptor, ju4, parse_parameter_types},
+};
+
+// https://github.com/openjdk/jdk/blob/master/src/java.base/share/classes/jdk/internal/foreign/abi/x64/sysv/CallArranger.java
+//
+// The Java side of the Foreign Function & Memory API computes where each argument of a
+// native function goes (a register or a stack slot of the SysV x86-64 ABI) and calls the
+// downcall stub of the VM with primitive values only. The stubs of the VM move these values
+// in place and call the function from the trampolines below. An upcall stub is an entry
+// point of a fixed pool that saves the argument registers and runs a MethodHandle.
+
+// jdk.internal.foreign.abi.x64.X86_64Architecture.StorageType
+pub(crate) const STORAGE_INTEGER: i8 = 0;
+pub(crate) const STORAGE_VECTOR: i8 = 1;
+pub(crate) const STORAGE_STACK: i8 = 3;
+pub(crate) const STORAGE_PLACEHOLDER: i8 = 4;
+
+// jdk.internal.foreign.abi.StubLocations
+pub(crate) const TARGET_ADDRESS: i32 = 0;
+pub(crate) const RETURN_BUFFER: i32 = 1;
+pub(crate) const CAPTURED_STATE_BUFFER: i32 = 2;
+
+// jdk.internal.foreign.abi.CapturableState.ERRNO, the only state captured on Linux
+pub(crate) const CAPTURE_ERRNO: i32 = 1 << 2;
+
+const UPCALL_STUBS: usize = 64;
+
+// size of an upcall stub in the pool
+const UPCALL_STUB_SIZE: u64 = 16;
+
+/// A `jdk.internal.foreign.abi.VMStorage`: a register or a stack slot
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Storage {
+  kind: i8,
+  // register number, or offset of a stack slot
+  index: i32,
+}
+
+impl Storage {
+  pub(crate) fn new(kind: i8, index: i32) -> Self {
+    Storage { kind, index }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct DowncallStub {
+  // descriptor of the primitive values passed to the stub, one per argument storage
+  descriptor: String,
+  args: Vec<Storage>,
+  rets: Vec<Storage>,
+  needs_return_buffer: bool,
+  captured_state_mask: i32,
+}
+
+impl DowncallStub {
+  pub(crate) fn new(
+    descriptor: &str,
+    args: Vec<Storage>,
+    rets: Vec<Storage>,
+    needs_return_buffer: bool,
+    captured_state_mask: i32,
+  ) -> Self {
+    DowncallStub {
+      descriptor: descriptor.to_string(),
+      args,
+      rets,
+      needs_return_buffer,
+      captured_state_mask,
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct UpcallStub {
+  method_handle: ju4,
+  args: Vec<Storage>,
+  rets: Vec<Storage>,
+  // the returned registers are read from a buffer given as the first argument
+  return_buffer_size: Option<u64>,
+}
+
+impl UpcallStub {
+  pub(crate) fn new(
+    method_handle: ju4,
+    args: Vec<Storage>,
+    rets: Vec<Storage>,
+    return_buffer_size: Option<u64>,
+  ) -> Self {
+    UpcallStub {
+      method_handle,
+      args,
+      rets,
+      return_buffer_size,
+    }
+  }
+}
+
+#[derive(Debug)]
+pub(crate) struct ForeignManager {
+  // key: address of the stub given to NativeEntryPoint
+  downcalls: HashMap<u64, DowncallStub>,
+  next_downcall: u64,
+  // an upcall stub per entry point of the pool
+  upcalls: Vec<Option<UpcallStub>>,
+}
+
+impl ForeignManager {
+  pub(crate) fn new() -> Self {
+    ForeignManager {
+      downcalls: HashMap::new(),
+      next_downcall: 1,
+      upcalls: vec![None; UPCALL_STUBS],
+    }
+  }
+
+  /// Register a downcall stub
+  ///
+  /// # Returns
+  ///
+  /// The address of the stub, never 0
+  pub(crate) fn add_downcall(&mut self, stub: DowncallStub) -> u64 {
+    let address = self.next_downcall;
+
+    self.next_downcall += 1;
+    self.downcalls.insert(address, stub);
+
+    address
+  }
+
+  pub(crate) fn remove_downcall(&mut self, address: u64) -> bool {
+    self.downcalls.remove(&address).is_some()
+  }
+
+  pub(crate) fn get_downcall(&self, address: u64) -> Result<&DowncallStub> {
+    self
+      .downcalls
+      .get(&address)
+      .ok_or_else(|| eyre!(errors::InternalError::SegmentationFault(address)))
+  }
+
+  /// Register an upcall stub in a free entry point of the pool
+  ///
+  /// # Returns
+  ///
+  /// The address of the entry point, 0 when the pool is exhausted
+  pub(crate) fn add_upcall(&mut self, stub: UpcallStub) -> Result<u64> {
+    let stubs_address = upcall_stubs_address()?;
+
+    match self.upcalls.iter().position(Option::is_none) {
+      Some(index) => {
+        self.upcalls[index] = Some(stub);
+
+        Ok(stubs_address + index as u64 * UPCALL_STUB_SIZE)
+      }
+      None => Ok(0),
+    }
+  }
+
+  pub(crate) fn remove_upcall(&mut self, address: u64) -> bool {
+    match get_upcall_index(address) {
+      Some(index) => self.upcalls[index].take().is_some(),
+      None => false,
+    }
+  }
+
+  /// MethodHandles run by the upcall stubs
+  pub(crate) fn get_references(&self) -> Vec<types::Type> {
+    self
+      .upcalls
+      .iter()
+      .flatten()
+      .map(|stub| types::Type::ObjectRef(stub.method_handle))
+      .collect()
+  }
+}
+
+/// Address of the symbol `name` of the library `handle` (`dlopen`), of the process when the
+/// handle is 0
+pub(crate) fn find_entry(handle: u64, name: &str) -> Result<u64> {
+  let name = CString::new(name)?;
+
+  let address = unsafe { libc::dlsym(handle as *mut libc::c_void, name.as_ptr()) };
+
+  Ok(address as u64)
+}
+
+/// Load the library `name` (`dlopen`)
+///
+/// # Returns
+///
+/// The handle of the library, 0 when it can't be loaded
+pub(crate) fn load_library(name: &str) -> Result<u64> {
+  let name = CString::new(name)?;
+
+  let handle = unsafe { libc::dlopen(name.as_ptr(), libc::RTLD_LAZY) };
+
+  if handle.is_null() {
+    debug!("dlopen failed: {:?}", unsafe {
+      std::ffi::CStr::from_ptr(libc::dlerror())
+    });
+  }
+
+  Ok(handle as u64)
+}
+
+pub(crate) fn unload_library(handle: u64) {
+  unsafe {
+    libc::dlclose(handle as *mut libc::c_void);
+  }
+}
+
+// registers of a call: the integer ones by encoding, and xmm0 to xmm7
+#[repr(C)]
+#[derive(Debug, Default)]
+struct Registers {
+  integer: [u64; 16],
+  vector: [[u64; 2]; 8],
+}
+
+impl Registers {
+  fn get(&self, storage: Storage) -> Result<u64> {
+    match storage.kind {
+      STORAGE_INTEGER => Ok(self.integer[storage.index as usize]),
+      STORAGE_VECTOR => Ok(self.vector[storage.index as usize][0]),
+      _ => Err(unsupported_storage(storage)),
+    }
+  }
+
+  fn set(&mut self, storage: Storage, value: u64) -> Result<()> {
+    match storage.kind {
+      STORAGE_INTEGER => self.integer[storage.index as usize] = value,
+      STORAGE_VECTOR => self.vector[storage.index as usize] = [value, 0],
+      _ => return Err(unsupported_storage(storage)),
+    }
+
+    Ok(())
+  }
+}
+
+fn unsupported_storage(storage: Storage) -> color_eyre::eyre::Report {
+  eyre!(errors::InternalError::General(format!(
+    "unsupported storage {:?}",
+    storage
+  )))
+}
+
+#[cfg(target_arch = "x86_64")]
+extern "C" {
+  // call `target` with the registers and the stack arguments, the returned registers are
+  // saved in `registers`
+  fn jvmcraft_downcall(registers: *mut Registers, target: u64, stack: *const u64, words: usize);
+
+  // first entry point of the upcall stubs
+  static jvmcraft_upcall_stubs: u8;
+}
+
+#[cfg(target_arch = "x86_64")]
+global_asm!(
+  ".text",
+  ".globl jvmcraft_downcall",
+  ".p2align 4",
+  "jvmcraft_downcall:",
+  "push rbp",
+  "mov rbp, rsp",
+  "push rbx",
+  "push r12",
+  "mov rbx, rdi",
+  "mov r12, rsi",
+  // the stack arguments, aligned on 16 bytes
+  "lea rax, [rcx * 8 + 15]",
+  "and rax, -16",
+  "sub rsp, rax",
+  "xor r10, r10",
+  "2:",
+  "cmp r10, rcx",
+  "jae 3f",
+  "mov r11, [rdx + r10 * 8]",
+  "mov [rsp + r10 * 8], r11",
+  "inc r10",
+  "jmp 2b",
+  "3:",
+  "mov rdi, [rbx + 7 * 8]",
+  "mov rsi, [rbx + 6 * 8]",
+  "mov rdx, [rbx + 2 * 8]",
+  "mov rcx, [rbx + 1 * 8]",
+  "mov r8, [rbx + 8 * 8]",
+  "mov r9, [rbx + 9 * 8]",
+  "movdqu xmm0, [rbx + 128]",
+  "movdqu xmm1, [rbx + 144]",
+  "movdqu xmm2, [rbx + 160]",
+  "movdqu xmm3, [rbx + 176]",
+  "movdqu xmm4, [rbx + 192]",
+  "movdqu xmm5, [rbx + 208]",
+  "movdqu xmm6, [rbx + 224]",
+  "movdqu xmm7, [rbx + 240]",
+  // number of vector registers of the variadic functions
+  "mov rax, [rbx]",
+  "call r12",
+  "mov [rbx], rax",
+  "mov [rbx + 2 * 8], rdx",
+  "movdqu [rbx + 128], xmm0",
+  "movdqu [rbx + 144], xmm1",
+  "lea rsp, [rbp - 16]",
+  "pop r12",
+  "pop rbx",
+  "pop rbp",
+  "ret",
+  // the upcall stubs, each one gives its address to the common entry
+  ".globl jvmcraft_upcall_stubs",
+  ".p2align 4",
+  "jvmcraft_upcall_stubs:",
+  ".rept {stubs}",
+  "lea r11, [rip]",
+  "jmp 4f",
+  ".p2align 4",
+  ".endr",
+  "4:",
+  "push rbp",
+  "mov rbp, rsp",
+  "sub rsp, 256",
+  "mov [rsp], rax",
+  "mov [rsp + 1 * 8], rcx",
+  "mov [rsp + 2 * 8], rdx",
+  "mov [rsp + 6 * 8], rsi",
+  "mov [rsp + 7 * 8], rdi",
+  "mov [rsp + 8 * 8], r8",
+  "mov [rsp + 9 * 8], r9",
+  "movdqu [rsp + 128], xmm0",
+  "movdqu [rsp + 144], xmm1",
+  "movdqu [rsp + 160], xmm2",
+  "movdqu [rsp + 176], xmm3",
+  "movdqu [rsp + 192], xmm4",
+  "movdqu [rsp + 208], xmm5",
+  "movdqu [rsp + 224], xmm6",
+  "movdqu [rsp + 240], xmm7",
+  "mov rdi, r11",
+  "mov rsi, rsp",
+  // the stack arguments follow the return address
+  "lea rdx, [rbp + 16]",
+  "call {upcall}",
+  "mov rax, [rsp]",
+  "mov rdx, [rsp + 2 * 8]",
+  "movdqu xmm0, [rsp + 128]",
+  "movdqu xmm1, [rsp + 144]",
+  "mov rsp, rbp",
+  "pop rbp",
+  "ret",
+  stubs = const UPCALL_STUBS,
+  upcall = sym upcall,
+);
+
+// the trampolines follow the SysV ABI of x86-64, the stubs of the other architectures are
+// not supported
+#[cfg(target_arch = "x86_64")]
+fn downcall(registers: &mut Registers, target: u64, stack: &[u64]) -> Result<()> {
+  unsafe {
+    jvmcraft_downcall(registers, target, stack.as_ptr(), stack.len());
+  }
+
+  Ok(())
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn downcall(_registers: &mut Registers, _target: u64, _stack: &[u64]) -> Result<()> {
+  Err(unsupported_architecture())
+}
+
+#[cfg(target_arch = "x86_64")]
+fn upcall_stubs_address() -> Result<u64> {
+  Ok(std::ptr::addr_of!(jvmcraft_upcall_stubs) as u64)
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn upcall_stubs_address() -> Result<u64> {
+  Err(unsupported_architecture())
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn unsupported_architecture() -> color_eyre::eyre::Report {
+  eyre!(errors::JavaException::UnsupportedOperationException(
+    format!("native calls on {}", std::env::consts::ARCH)
+  ))
+}
+
+// `address` is in the entry point of a stub
+fn get_upcall_index(address: u64) -> Option<usize> {
+  let offset = address.checked_sub(upcall_stubs_address().ok()?)?;
+  let index = (offset / UPCALL_STUB_SIZE) as usize;
+
+  (index < UPCALL_STUBS).then_some(index)
+}
+
+thread_local! {
+  // the VM running a downcall, an upcall from the native code runs in it
+  static CURRENT_JVM: Cell<*mut JVM> = const { Cell::new(std::ptr::null_mut()) };
+}
+
+// common entry of the upcall stubs, like the JDK the process is aborted when the upcall
+// can't return to the native code
+#[cfg_attr(not(target_arch = "x86_64"), allow(dead_code))]
+extern "C" fn upcall(entry: u64, registers: *mut Registers, stack: *const u8) {
+  let jvm = CURRENT_JVM.with(Cell::get);
+
+  if jvm.is_null() {
+    error!("upcall outside of a downcall");
+    std::process::abort();
+  }
+
+  let (jvm, registers) = unsafe { (&mut *jvm, &mut *registers) };
+
+  let result = get_upcall_index(entry)
+    .ok_or_else(|| eyre!(errors::InternalError::SegmentationFault(entry)))
+    .and_then(|index| jvm.run_upcall(index, registers, stack));
+
+  if let Err(err) = result {
+    error!(
+      "Unrecoverable uncaught exception encountered in an upcall: {}",
+      err
+    );
+    std::process::abort();
+  }
+}
+
+// raw value of a register, the integers are sign extended
+fn to_raw(value: types::Type) -> Result<u64> {
+  let raw = match value {
+    types::Type::Float(value) => value.to_bits() as u64,
+    types::Type::Double(value) => value.to_bits(),
+    value => value.as_long()? as u64,
+  };
+
+  Ok(raw)
+}
+
+pub(crate) fn from_raw(descriptor: &str, raw: u64) -> Result<types::Type> {
+  let value = match descriptor {
+    "Z" => types::Type::Boolean(raw as u8 != 0),
+    "B" => types::Type::Byte(raw as i8),
+    "C" => types::Type::Integer((raw & 0xffff) as i32),
+    "S" => types::Type::Short(raw as i16),
+    "I" => types::Type::Integer(raw as i32),
+    "J" => types::Type::Long(raw as i64),
+    "F" => types::Type::Float(f32::from_bits(raw as u32)),
+    "D" => types::Type::Double(f64::from_bits(raw)),
+    "V" => types::Type::None,
+    descriptor => {
+      return Err(eyre!(errors::InternalError::General(format!(
+        "{} is not a primitive of a native call",
+        descriptor
+      ))))
+    }
+  };
+
+  Ok(value)
+}
+
+fn get_return_descriptor(descriptor: &str) -> &str {
+  descriptor.rsplit(')').next().unwrap_or("V")
+}
+
+/// Call the C function `target` with the SysV x86-64 classification of its arguments: the
+/// integers in rdi, rsi, rdx, rcx, r8 and r9, the floating point values (descriptors `F` and
+/// `D`) in xmm0 to xmm7, the others on the stack
+///
+/// # Arguments
+///
+/// * `args` - The descriptor and the raw value of each argument
+/// * `return_descriptor` - The returned value is read from xmm0 for `F` and `D`, from rax
+///   otherwise
+///
+/// # Returns
+///
+/// The raw returned value
+pub(crate) fn call_function(
+  target: u64,
+  args: &[(&str, u64)],
+  return_descriptor: &str,
+) -> Result<u64> {
+  const INTEGERS: [usize; 6] = [7, 6, 2, 1, 8, 9];
+
+  let mut registers = Registers::default();
+  let mut stack = vec![];
+  let (mut integers, mut vectors) = (0, 0);
+
+  for (descriptor, raw) in args {
+    match *descriptor {
+      "F" | "D" if vectors < registers.vector.len() => {
+        registers.vector[vectors] = [*raw, 0];
+        vectors += 1;
+      }
+      "F" | "D" => stack.push(*raw),
+      _ if integers < INTEGERS.len() => {
+        registers.integer[INTEGERS[integers]] = *raw;
+        integers += 1;
+      }
+      _ => stack.push(*raw),
+    }
+  }
+
+  // an upper bound of the vector registers used by a variadic function
+  registers.integer[0] = vectors as u64;
+
+  downcall(&mut registers, target, &stack)?;
+
+  match return_descriptor {
+    "F" | "D" => Ok(registers.vector[0][0]),
+    _ => Ok(registers.integer[0]),
+  }
+}
+
+impl JVM {
+  /// Storages of an array of `jdk.internal.foreign.abi.VMStorage`
+  pub(crate) fn get_storages(&self, array_ref: ju4) -> Result<Vec<Storage>> {
+    let mut storages = vec![];
+
+    for storage_ref in self.heap.get_array_instance(array_ref)?.get_elements() {
+      let storage = self.heap.get_obj_instance(storage_ref.as_ref()?)?;
+
+      storages.push(Storage::new(
+        storage.get_field("type")?.as_byte()?,
+        storage.get_field("indexOrOffset")?.as_integer()?,
+      ));
+    }
+
+    Ok(storages)
+  }
+
+  /// `MethodHandle.linkToNative`: the last argument is the `NativeEntryPoint` of the stub to
+  /// run with the previous ones
+  pub(crate) fn link_to_native(&mut self, mut args: Vec<types::Type>) -> Result<types::Type> {
+    let entry_point_ref = match args.pop() {
+      Some(entry_point) => entry_point.as_ref()?,
+      None => return Err(eyre!(errors::JavaException::NullPointer)),
+    };
+
+    if entry_point_ref == 0 {
+      return Err(eyre!(errors::JavaException::NullPointer));
+    }
+
+    let address = self
+      .heap
+      .get_obj_instance(entry_point_ref)?
+      .get_field("downcallStubAddress")?
+      .as_long()?;
+
+    self.invoke_downcall(address as u64, args)
+  }
+
+  /// Run the downcall stub `address` (see `MethodHandle.linkToNative`)
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - A primitive value per argument storage of the stub, the address of the
+  ///   function to call among them
+  ///
+  /// # Returns
+  ///
+  /// The returned value, `Type::None` for `void`
+  pub(crate) fn invoke_downcall(
+    &mut self,
+    address: u64,
+    args: Vec<types::Type>,
+  ) -> Result<types::Type> {
+    let stub = self.foreignmanager.get_downcall(address)?.clone();
+
+    if args.len() != stub.args.len() {
+      return Err(eyre!(errors::InternalError::General(format!(
+        "downcall of {} with {} arguments",
+        stub.descriptor,
+        args.len()
+      ))));
+    }
+
+    let mut registers = Registers::default();
+    let mut stack: Vec<u64> = vec![];
+    let mut target = 0;
+    let mut return_buffer = 0;
+    let mut captured_state = 0;
+
+    for (storage, value) in stub.args.iter().zip(args) {
+      let raw = to_raw(value)?;
+
+      match (storage.kind, storage.index) {
+        (STORAGE_PLACEHOLDER, TARGET_ADDRESS) => target = raw,
+        (STORAGE_PLACEHOLDER, RETURN_BUFFER) => return_buffer = raw,
+        (STORAGE_PLACEHOLDER, CAPTURED_STATE_BUFFER) => captured_state = raw,
+        (STORAGE_STACK, offset) => {
+          let slot = offset as usize / 8;
+
+          if stack.len() <= slot {
+            stack.resize(slot + 1, 0);
+          }
+
+          stack[slot] = raw;
+        }
+        _ => registers.set(*storage, raw)?,
+      }
+    }
+
+    if target == 0 {
+      return Err(eyre!(errors::JavaException::NullPointer));
+    }
+
+    debug!("downcall {} to {:#x}", stub.descriptor, target);
+
+    // an upcall from the function runs in this VM
+    let previous = CURRENT_JVM.with(|current| current.replace(self as *mut JVM));
+
+    let result = downcall(&mut registers, target, &stack);
+
+    let errno = std::io::Error::last_os_error().raw_os_error().unwrap_or(0);
+
+    CURRENT_JVM.with(|current| current.set(previous));
+
+    result?;
+
+    if stub.captured_state_mask & CAPTURE_ERRNO != 0 {
+      self
+        .nativememory
+        .write(captured_state, &errno.to_ne_bytes())?;
+    }
+
+    // the returned registers one after the other, 16 bytes for the vector ones
+    if stub.needs_return_buffer {
+      let mut offset = 0;
+
+      for storage in &stub.rets {
+        let raw = registers.get(*storage)?;
+        self
+          .nativememory
+          .write(return_buffer + offset, &raw.to_ne_bytes())?;
+
+        offset += if storage.kind == STORAGE_VECTOR {
+          16
+        } else {
+          8
+        };
+      }
+
+      return Ok(types::Type::None);
+    }
+
+    match stub.rets.first() {
+      Some(storage) => from_raw(
+        get_return_descriptor(&stub.descriptor),
+        registers.get(*storage)?,
+      ),
+      None => Ok(types::Type::None),
+    }
+  }
+
+  // the MethodHandle of the stub gets the return buffer, then a value per argument storage
+  fn run_upcall(
+    &mut self,
+    index: usize,
+    registers: &mut Registers,
+    stack: *const u8,
+  ) -> Result<()> {
+    let stub = self.foreignmanager.upcalls[index]
+      .clone()
+      .ok_or_else(|| eyre!(errors::InternalError::SegmentationFault(index as u64)))?;
+
+    let method_type_ref = self
+      .heap
+      .get_obj_instance(stub.method_handle)?
+      .get_field("type")?
+      .as_ref()?;
+    let descriptor = self.get_method_type_descriptor(method_type_ref)?;
+
+    let return_buffer = match stub.return_buffer_size {
+      Some(size) => Some(self.nativememory.alloc(size.max(1))?),
+      None => None,
+    };
+
+    let mut raws = vec![];
+
+    if let Some(return_buffer) = return_buffer {
+      raws.push(return_buffer);
+    }
+
+    for storage in &stub.args {
+      let raw = match storage.kind {
+        STORAGE_STACK => unsafe {
+          std::ptr::read_unaligned(stack.add(storage.index as usize) as *const u64)
+        },
+        _ => registers.get(*storage)?,
+      };
+
+      raws.push(raw);
+    }
+
+    let param_types = parse_parameter_types(get_parameters_type_descriptor(&descriptor));
+
+    if param_types.len() != raws.len() {
+      return Err(eyre!(errors::InternalError::General(format!(
+        "upcall of {} with {} arguments",
+        descriptor,
+        raws.len()
+      ))));
+    }
+
+    let args = param_types
+      .iter()
+      .zip(raws)
+      .map(|(param_type, raw)| from_raw(param_type, raw))
+      .collect::<Result<Vec<_>>>()?;
+
+    debug!("upcall {}", descriptor);
+
+    let result = self.invoke_method_handle(stub.method_handle, args)?;
+
+    match return_buffer {
+      Some(return_buffer) => {
+        let mut offset = 0;
+
+        for storage in &stub.rets {
+          let bytes = self.nativememory.read(return_buffer + offset, 8)?;
+          registers.set(*storage, u64::from_ne_bytes(bytes.try_into().unwrap()))?;
+
+          offset += if storage.kind == STORAGE_VECTOR {
+            16
+          } else {
+            8
+          };
+        }
+
+        self.nativememory.free(return_buffer)?;
+      }
+      None => {
+        if let Some(storage) = stub.rets.first() {
+          registers.set(*storage, to_raw(result)?)?;
+        }
+      }
+    }
+
+    Ok(())
+  }
+}
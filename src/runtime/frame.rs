@@ -86,7 +86,7 @@ impl Frame {
       return Err(eyre!("no more code to execute"));
     }
 
-    let index = ju2_from_bytes(get_slice_arr(&self.code, self.pc, 2))?;
+    let index = ju2_from_bytes(get_slice(&self.code, self.pc, 2)?)?;
 
     self.pc += 2;
 
@@ -98,7 +98,7 @@ impl Frame {
       return Err(eyre!("no more code to execute"));
     }
 
-    let index = ju4_from_bytes(get_slice_arr(&self.code, self.pc, 4))?;
+    let index = ju4_from_bytes(get_slice(&self.code, self.pc, 4)?)?;
 
     self.pc += 4;
 
@@ -113,6 +113,10 @@ impl Frame {
     &self.local
   }
 
+  pub fn get_stack(&self) -> &Vec<types::Type> {
+    &self.stack
+  }
+
   pub fn get_local(&self, index: usize) -> Result<&types::Type> {
     if index >= self.local.len() {
       return Err(eyre!(
@@ -141,6 +145,11 @@ impl Frame {
     self.pc
   }
 
+  /// pc of the instruction being executed, the one an exception is thrown at
+  pub fn get_last_opcode_pc(&self) -> usize {
+    self.last_opcode_pc
+  }
+
   // mainly used for testing
   pub fn _set_last_opcode_pc(&mut self, pc: usize) {
     self.last_opcode_pc = pc;
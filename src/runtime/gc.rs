@@ -0,0 +1,128 @@
+use std::collections::HashSet;
+
+use color_eyre::eyre::Result;
+use log::debug;
+
+use crate::{
+  runtime::{jvm::*, types},
+  utils::ju4,
+};
+
+// cleaners of the direct and mapped buffers, in a list starting at its static field `first`
+const CLEANER: &str = "jdk/internal/ref/Cleaner";
+
+const REFERENCE: &str = "java/lang/ref/Reference";
+
+impl JVM {
+  /// Collect the unreachable objects: the heap is not freed, only the `jdk.internal.ref.Cleaner`
+  /// of the unreachable objects are cleared and run, freeing their off-heap memory
+  ///
+  /// # Returns
+  ///
+  /// The number of cleaners run
+  pub(crate) fn collect_garbage(&mut self) -> Result<usize> {
+    let reachable = self.mark_reachable()?;
+
+    let mut cleaners = vec![];
+
+    if self.class_loader.get(CLEANER)?.get_init() {
+      let mut cleaner = self
+        .class_loader
+        .get_static_field(CLEANER, "first")?
+        .as_ref()?;
+
+      while cleaner != 0 {
+        let cleaner_obj = self.heap.get_obj_instance(cleaner)?;
+
+        let referent = cleaner_obj.get_field("referent")?.as_ref()?;
+
+        if referent != 0 && !reachable.contains(&referent) {
+          cleaners.push(cleaner);
+        }
+
+        cleaner = cleaner_obj.get_field("next")?.as_ref()?;
+      }
+    }
+
+    debug!("{} cleaners of unreachable objects", cleaners.len());
+
+    for cleaner in &cleaners {
+      // cleared like a phantom reference, then removed from the list and run
+      self
+        .heap
+        .get_obj_instance_mut(*cleaner)?
+        .put_field("referent", types::Type::Null)?;
+
+      self.call_and_resolve_method(
+        CLEANER,
+        "clean",
+        "()V",
+        vec![types::Type::ObjectRef(*cleaner)],
+      )?;
+    }
+
+    Ok(cleaners.len())
+  }
+
+  // objects strongly reachable from the frames, the static fields and the objects kept by
+  // the VM
+  fn mark_reachable(&self) -> Result<HashSet<ju4>> {
+    let mut pending: Vec<types::Type> = vec![];
+
+    for frame in &self.frames {
+      pending.extend(frame.get_locals());
+      pending.extend(frame.get_stack());
+    }
+
+    pending.extend(self.class_loader.get_static_values());
+    pending.extend(self.lambdamanager.get_references());
+    pending.extend(self.foreignmanager.get_references());
+    pending.extend(self.jnimanager.get_references());
+    pending.extend(
+      self
+        .globals
+        .lock()
+        .unwrap()
+        .keys()
+        .chain(&self.uncaught_exception)
+        .map(|obj_ref| types::Type::ObjectRef(*obj_ref)),
+    );
+    pending.push(self.get_current_thread_obj());
+
+    let vm_refs = self
+      .heap
+      .get_table_refs()
+      .into_iter()
+      .chain(self.class_loader.modulemanager.get_module_refs());
+    pending.extend(vm_refs.map(types::Type::ObjectRef));
+
+    let mut reachable = HashSet::new();
+
+    while let Some(value) = pending.pop() {
+      let obj_ref = match value {
+        types::Type::ObjectRef(obj_ref) | types::Type::ArrayRef(obj_ref) => obj_ref,
+        _ => continue,
+      };
+
+      if obj_ref == 0 || !reachable.insert(obj_ref) {
+        continue;
+      }
+
+      match self.heap.get_instance(obj_ref)? {
+        types::Instance::ObjectInstance(obj) => {
+          for (classname, name, value) in obj.get_field_values() {
+            // the referent of a reference is not strongly reachable through it
+            if classname == REFERENCE && name == "referent" {
+              continue;
+            }
+
+            pending.push(value);
+          }
+        }
+        types::Instance::ArrayInstance(array) => pending.extend(array.get_elements()),
+      }
+    }
+
+    Ok(reachable)
+  }
+}
@@ -10,16 +10,35 @@ use log::{debug, warn};
 
 use super::types::{ArrayInstance, ObjectInstance, Type};
 use crate::{
-  class_loader::{class_file, fields, loader::ClassLoader, methods},
+  class_loader::{
+    attributes::{AttributeInfoInfoEnum, Attributes},
+    class_file, fields,
+    loader::ClassLoader,
+    methods,
+    namespace::BOOTSTRAP_LOADER,
+  },
   runtime::{errors, types},
   utils::*,
 };
 
+/// Primitive types and the classes boxing them
+pub(crate) const BOX_CLASSES: [(&str, &str); 8] = [
+  ("Z", "java/lang/Boolean"),
+  ("B", "java/lang/Byte"),
+  ("C", "java/lang/Character"),
+  ("S", "java/lang/Short"),
+  ("I", "java/lang/Integer"),
+  ("J", "java/lang/Long"),
+  ("F", "java/lang/Float"),
+  ("D", "java/lang/Double"),
+];
+
 #[derive(Debug)]
 pub struct Heap {
   next_obj_ref: ju4, // 0 is used for Null
   heap: HashMap<ju4, types::Instance>,
   classes: HashMap<String, ju4>,        // Class<T>
+  class_keys: HashMap<ju4, String>,     // Class<T> -> T
   static_classes: HashMap<String, ju4>, // instance of classes to access static info
   strings: HashMap<String, ju4>,
 }
@@ -73,6 +92,7 @@ impl Heap {
       next_obj_ref: 1,
       heap: HashMap::new(),
       classes: HashMap::new(),
+      class_keys: HashMap::new(),
       static_classes: HashMap::new(),
       strings: HashMap::new(),
     }
@@ -188,6 +208,65 @@ impl Heap {
     Ok(types::Type::ObjectRef(curr_ref))
   }
 
+  /// Allocate the object boxing a primitive value
+  ///
+  /// # Arguments
+  ///
+  /// * `loader` - ClassLoader used to resolve the box class
+  /// * `descriptor` - Primitive type of the value
+  /// * `value` - Value to box
+  ///
+  /// # Returns
+  ///
+  /// A `ObjectRef` of the box
+  pub(crate) fn alloc_boxed(
+    &mut self,
+    loader: &mut ClassLoader,
+    descriptor: &str,
+    value: types::Type,
+  ) -> Result<types::Type> {
+    let (_, box_classname) = BOX_CLASSES
+      .iter()
+      .find(|(primitive, _)| *primitive == descriptor)
+      .ok_or_eyre(eyre!("not a primitive type: {}", descriptor))?;
+
+    let value = match descriptor {
+      "Z" => types::Type::Boolean(value.as_bool()?),
+      "B" => types::Type::Byte(value.as_byte()?),
+      // `Character` only holds 8 bits, chars are kept as ints like the interpreter does
+      "C" => types::Type::Integer(value.as_integer()? & 0xffff),
+      "S" => types::Type::Short(value.as_short()?),
+      "I" => types::Type::Integer(value.as_integer()?),
+      "J" => types::Type::Long(value.as_long()?),
+      "F" => types::Type::Float(value.as_float()?),
+      _ => types::Type::Double(value.as_double()?),
+    };
+
+    let box_ref = self.alloc_obj(loader, box_classname)?;
+
+    self
+      .get_obj_instance_mut(box_ref.as_ref()?)?
+      .put_field("value", value)?;
+
+    Ok(box_ref)
+  }
+
+  /// Get the primitive type and the value of a box, `None` if `obj_ref` is not a box
+  pub(crate) fn get_boxed_value(&self, obj_ref: ju4) -> Result<Option<(&str, types::Type)>> {
+    let obj = match self.get_instance(obj_ref)? {
+      types::Instance::ObjectInstance(obj) => obj,
+      types::Instance::ArrayInstance(_) => return Ok(None),
+    };
+
+    match BOX_CLASSES
+      .iter()
+      .find(|(_, box_classname)| *box_classname == obj.get_classname())
+    {
+      Some((primitive, _)) => Ok(Some((primitive, obj.get_field("value")?))),
+      None => Ok(None),
+    }
+  }
+
   /// Get the component type of an array
   ///
   /// # Arguments
@@ -246,6 +325,10 @@ impl Heap {
       ));
     }
 
+    if types::Type::is_primitive(classname) || classname == "V" {
+      return self.alloc_primitive_class_obj(loader, classname);
+    }
+
     let mut fields = vec![];
 
     // adding component type information if array
@@ -255,7 +338,7 @@ impl Heap {
       fields.push(("componentType", comp_type));
     }
 
-    let string = self.alloc_string(loader, &class_to_dotclass(classname))?;
+    let string = self.alloc_string(loader, &ClassLoader::get_java_name(classname))?;
 
     fields.push(("name", string));
 
@@ -267,9 +350,13 @@ impl Heap {
       obj_mod.new_field(f.0, f.1)?;
     }
 
-    // TODO: Check which ClassLoader is loading it.
-    // TODO: if part of the bootstrap classLoader is NULL
-    obj_mod.put_field("classLoader", types::Type::Null)?;
+    // the bootstrap class loader is null
+    let class_loader = match ClassLoader::get_defining_loader(classname) {
+      BOOTSTRAP_LOADER => types::Type::Null,
+      loader_ref => types::Type::ObjectRef(loader_ref),
+    };
+
+    obj_mod.put_field("classLoader", class_loader)?;
 
     if !Type::is_primitive(classname) && !classname.starts_with("[") {
       let module = loader.modulemanager.get_module_by_class(classname)?;
@@ -278,6 +365,7 @@ impl Heap {
     }
 
     self.classes.insert(classname.to_string(), obj_ref);
+    self.class_keys.insert(obj_ref, classname.to_string());
 
     if !Type::is_primitive(classname) && !classname.starts_with("[") {
       let static_class_obj_ref = self.alloc_obj(loader, classname)?.as_ref()?;
@@ -313,7 +401,20 @@ impl Heap {
       ));
     }
 
-    let name_ref = self.alloc_string(loader, &class_to_dotclass(classname))?;
+    // Class.getName() of a primitive type is its keyword (e.g. `int`)
+    let name = match classname {
+      "Z" => "boolean",
+      "B" => "byte",
+      "C" => "char",
+      "S" => "short",
+      "I" => "int",
+      "J" => "long",
+      "F" => "float",
+      "D" => "double",
+      "V" => "void",
+      _ => classname,
+    };
+    let name_ref = self.alloc_string(loader, name)?;
 
     let obj_ref = self.alloc_obj(loader, "java/lang/Class")?.as_ref()?;
 
@@ -333,6 +434,7 @@ impl Heap {
     obj_mod.put_field("module", types::Type::ObjectRef(module.get_obj_ref()))?;
 
     self.classes.insert(classname.to_string(), obj_ref);
+    self.class_keys.insert(obj_ref, classname.to_string());
 
     Ok(types::Type::ObjectRef(obj_ref))
   }
@@ -420,6 +522,41 @@ impl Heap {
     Ok(types::Type::ArrayRef(curr_array_ref))
   }
 
+  /// Allocate a `byte[]` with the content of `bytes`
+  pub(crate) fn alloc_byte_array(&mut self, bytes: &[u8]) -> Result<types::Type> {
+    let elements = bytes.iter().map(|b| types::Type::Byte(*b as i8)).collect();
+
+    self.alloc_array_primitive("B", elements, bytes.len())
+  }
+
+  /// Set the `byte[]` fields of a reflection object (e.g. `Method.annotations`) to
+  /// the content of the annotations attributes, as parsed by `AnnotationParser`
+  ///
+  /// # Arguments
+  ///
+  /// * `obj_ref` - `Field`, `Method` or `Constructor` object
+  /// * `attributes` - Attributes of the member
+  /// * `fields` - Pairs of the field and of the name of its attribute
+  pub(crate) fn put_raw_annotations(
+    &mut self,
+    obj_ref: ju4,
+    attributes: &Attributes,
+    fields: &[(&str, &str)],
+  ) -> Result<()> {
+    for (field, attribute_name) in fields {
+      let value = match attributes.get_raw_annotations(attribute_name) {
+        Some(raw) => self.alloc_byte_array(raw)?,
+        None => types::Type::Null,
+      };
+
+      self
+        .get_obj_instance_mut(obj_ref)?
+        .new_field(field, value)?;
+    }
+
+    Ok(())
+  }
+
   /// Allocate an multi dimenstional array
   ///
   /// # Arguments
@@ -509,6 +646,12 @@ impl Heap {
     )?;
     obj.put_field("signature", sig_ref)?;
 
+    self.put_raw_annotations(
+      obj_ref,
+      field.get_attributes(),
+      &[("annotations", "RuntimeVisibleAnnotations")],
+    )?;
+
     Ok(types::Type::ObjectRef(obj_ref))
   }
 
@@ -631,10 +774,7 @@ impl Heap {
     loader: &'a mut ClassLoader,
     class_ref: ju4,
   ) -> Result<RwLockReadGuard<'a, class_file::ClassFile>> {
-    let class_obj = self.get_obj_instance(class_ref)?;
-
-    let class_inner_name_ref = class_obj.get_field("name")?.as_ref()?; // T in Class<T> as string
-    let class_inner_name = &self.get_string(class_inner_name_ref)?;
+    let class_inner_name = &self.get_classname_from_class_obj(class_ref)?;
 
     loader.get(class_inner_name)
   }
@@ -654,6 +794,10 @@ impl Heap {
   ///
   /// works for primitive types
   pub fn get_classname_from_class_obj(&self, class_ref: ju4) -> Result<String> {
+    if let Some(classname) = self.class_keys.get(&class_ref) {
+      return Ok(classname.clone());
+    }
+
     let class_obj = self.get_obj_instance(class_ref)?;
 
     let class_inner_name_ref = class_obj.get_field("name")?.as_ref()?; // T in Class<T> as string
@@ -694,6 +838,17 @@ impl Heap {
     self.get_obj_instance(*class_ref)
   }
 
+  /// Class objects, instances of the static fields and interned strings, kept by the heap
+  pub(crate) fn get_table_refs(&self) -> Vec<ju4> {
+    self
+      .classes
+      .values()
+      .chain(self.static_classes.values())
+      .chain(self.strings.values())
+      .copied()
+      .collect()
+  }
+
   pub fn has_class_instance(&self, classname: &str) -> bool {
     self.classes.contains_key(classname)
   }
@@ -802,6 +957,8 @@ impl Heap {
     let param_array =
       self.alloc_array("java/lang/Class", param_types_args, param_types_args_len)?;
 
+    let exception_array = self.alloc_exception_types(loader, declaring_classname, method)?;
+
     // Allocate a new java/lang/reflect/Method instance
     let method_ref = self.alloc_obj(loader, "java/lang/reflect/Method")?;
 
@@ -827,14 +984,133 @@ impl Heap {
 
     method_instance.new_field("parameterTypes", param_array)?;
 
+    method_instance.new_field("exceptionTypes", exception_array)?;
+
     method_instance.new_field("signature", signature)?;
 
     // returnType: java/lang/Class
     method_instance.new_field("returnType", types::Type::ObjectRef(ret_class_obj_ref))?;
 
+    self.put_raw_annotations(
+      method_ref.as_ref()?,
+      method.get_attributes(),
+      &[
+        ("annotations", "RuntimeVisibleAnnotations"),
+        ("parameterAnnotations", "RuntimeVisibleParameterAnnotations"),
+        ("annotationDefault", "AnnotationDefault"),
+      ],
+    )?;
+
     Ok(method_ref)
   }
 
+  // the classes of the `Exceptions` attribute of a method, never null like the ones of HotSpot
+  fn alloc_exception_types(
+    &mut self,
+    loader: &mut ClassLoader,
+    declaring_classname: &str,
+    method: &methods::MethodInfo,
+  ) -> Result<types::Type> {
+    let mut exception_classnames = vec![];
+
+    for attribute in method.get_attributes().iter() {
+      if let AttributeInfoInfoEnum::Exceptions(exceptions) = attribute.get_info() {
+        let class = loader.get(declaring_classname)?;
+
+        for index in exceptions.get_exception_index_table() {
+          exception_classnames.push(class.resolve_class_name(*index)?);
+        }
+      }
+    }
+
+    let mut exception_types = vec![];
+
+    for classname in exception_classnames {
+      let class = self.get_class_instance(loader, &classname)?;
+
+      exception_types.push(types::Type::ObjectRef(class.get_ref()));
+    }
+
+    let len = exception_types.len();
+
+    self.alloc_array("java/lang/Class", exception_types, len)
+  }
+
+  /// Allocate a java/lang/reflect/Constructor
+  ///
+  /// # Arguments
+  ///
+  /// * `loader` - ClassLoader used to resolve the classes of the parameters
+  /// * `declaring_classname` - Class declaring the constructor
+  /// * `method` - `<init>` method of the constructor
+  ///
+  /// # Returns
+  ///
+  /// A `ObjectRef` of the newly instantiated constructor
+  pub(crate) fn alloc_reflect_constructor(
+    &mut self,
+    loader: &mut ClassLoader,
+    declaring_classname: &str,
+    method: &methods::MethodInfo,
+  ) -> Result<types::Type> {
+    let class_ref = self
+      .get_class_instance(loader, declaring_classname)?
+      .get_ref();
+
+    let mut param_types_args = vec![];
+
+    for p in get_argument_classnames(method.get_descriptor()) {
+      let p = &descriptor_to_classname(&p);
+
+      let class = self.get_class_instance(loader, p)?;
+
+      param_types_args.push(types::Type::ObjectRef(class.get_ref()));
+    }
+
+    let param_types_args_len = param_types_args.len();
+
+    let param_array =
+      self.alloc_array("java/lang/Class", param_types_args, param_types_args_len)?;
+
+    let exception_array = self.alloc_exception_types(loader, declaring_classname, method)?;
+
+    let name_str = self.alloc_string(loader, &class_to_dotclass(method.get_name()))?;
+
+    let sig_str = self.alloc_string(loader, method.get_descriptor())?;
+
+    let ctor_ref = self
+      .alloc_obj(loader, "java/lang/reflect/Constructor")?
+      .as_ref()?;
+
+    let ctor_inst = self.get_obj_instance_mut(ctor_ref)?;
+
+    ctor_inst.new_field("clazz", types::Type::ObjectRef(class_ref))?;
+
+    ctor_inst.new_field("name", name_str)?;
+
+    ctor_inst.new_field("signature", sig_str)?;
+
+    ctor_inst.new_field("parameterTypes", param_array)?;
+
+    ctor_inst.new_field("exceptionTypes", exception_array)?;
+
+    ctor_inst.new_field(
+      "modifiers",
+      types::Type::Integer(method.get_access_flags() as i32),
+    )?;
+
+    self.put_raw_annotations(
+      ctor_ref,
+      method.get_attributes(),
+      &[
+        ("annotations", "RuntimeVisibleAnnotations"),
+        ("parameterAnnotations", "RuntimeVisibleParameterAnnotations"),
+      ],
+    )?;
+
+    Ok(types::Type::ObjectRef(ctor_ref))
+  }
+
   /// Allocate a java/lang/Integer
   ///
   /// # Arguments
@@ -0,0 +1,301 @@
+use std::{
+  ffi::{c_char, c_void},
+  panic,
+  ptr::null_mut,
+  sync::{
+    atomic::{AtomicPtr, Ordering},
+    Mutex,
+  },
+  thread::{self, ThreadId},
+};
+
+use color_eyre::eyre::{eyre, Result};
+use log::{error, warn};
+
+use crate::runtime::{
+  errors,
+  jni::{self, Interface, JNI_EEXIST, JNI_EINVAL, JNI_ERR, JNI_EVERSION, JNI_OK},
+  jvm::JVM,
+};
+
+// https://docs.oracle.com/en/java/javase/23/docs/specs/jni/invocation.html
+//
+// The Invocation API of the `cdylib`: a C program creates the VM with `JNI_CreateJavaVM`,
+// then calls Java through the `JNIEnv` it gets back. A process runs a single VM, on the
+// thread that created it (the interpreter is not shared between threads).
+
+// nominal size of a frame on the stack, to turn -Xss into a depth
+const FRAME_SIZE: usize = 1024;
+
+// the VM created by JNI_CreateJavaVM, and its thread
+static CREATED: AtomicPtr<JVM> = AtomicPtr::new(null_mut());
+static CREATOR: Mutex<Option<ThreadId>> = Mutex::new(None);
+
+/// `JavaVMOption`
+#[repr(C)]
+struct VmOption {
+  option_string: *const c_char,
+  _extra_info: *mut c_void,
+}
+
+/// `JavaVMInitArgs`
+#[repr(C)]
+struct InitArgs {
+  version: i32,
+  n_options: i32,
+  options: *const VmOption,
+  ignore_unrecognized: u8,
+}
+
+/// Options of `JNI_CreateJavaVM`
+#[derive(Debug, PartialEq)]
+pub(crate) struct InvocationOptions {
+  // the directory of the classes of the user (-Djava.class.path)
+  pub(crate) class_path: String,
+  // -Dkey=value
+  pub(crate) properties: Vec<(String, String)>,
+  // -Xss, in bytes
+  pub(crate) stack_size: Option<usize>,
+  // -Xverify
+  pub(crate) verify: bool,
+}
+
+impl InvocationOptions {
+  /// Parse the option strings of a `JavaVMInitArgs`, the unrecognized `-X` and `_` options
+  /// are ignored with `ignore_unrecognized`
+  pub(crate) fn parse(options: &[String], ignore_unrecognized: bool) -> Result<Self> {
+    let mut invocation_options = InvocationOptions {
+      class_path: ".".to_string(),
+      properties: vec![],
+      stack_size: None,
+      verify: true,
+    };
+
+    for option in options {
+      if let Some(property) = option.strip_prefix("-D") {
+        let (key, value) = property.split_once('=').unwrap_or((property, ""));
+
+        if key == "java.class.path" {
+          if value.contains(':') {
+            return Err(eyre!(errors::InternalError::General(format!(
+              "only a single directory is supported in the class path: {}",
+              value
+            ))));
+          }
+
+          invocation_options.class_path = value.to_string();
+        }
+
+        invocation_options
+          .properties
+          .push((key.to_string(), value.to_string()));
+
+        continue;
+      }
+
+      match option.as_str() {
+        // the hooks of the VM are not called
+        "vfprintf" | "exit" | "abort" => {}
+        "-Xverify:none" => invocation_options.verify = false,
+        "-Xverify:all" | "-Xverify:remote" => invocation_options.verify = true,
+        _ if option.starts_with("-Xss") => {
+          let size = parse_size(&option[4..]).ok_or_else(|| {
+            eyre!(errors::InternalError::General(format!(
+              "Invalid thread stack size: {}",
+              option
+            )))
+          })?;
+
+          invocation_options.stack_size = Some(size);
+        }
+        _ if ignore_unrecognized && (option.starts_with("-X") || option.starts_with('_')) => {
+          warn!("Ignoring the option {}", option);
+        }
+        _ => {
+          return Err(eyre!(errors::InternalError::General(format!(
+            "Unrecognized option: {}",
+            option
+          ))))
+        }
+      }
+    }
+
+    Ok(invocation_options)
+  }
+
+  /// Create and bootstrap the VM
+  pub(crate) fn create(&self) -> Result<JVM> {
+    let mut jvm = JVM::new(vec![])?;
+
+    jvm.properties = self.properties.clone();
+    jvm.max_frames = self.stack_size.map(|size| (size / FRAME_SIZE).max(1));
+    jvm.verify = self.verify;
+
+    jvm.bootstrap(&self.class_path)?;
+
+    Ok(jvm)
+  }
+}
+
+// a size of -Xss: bytes, or a number of k, m or g
+fn parse_size(size: &str) -> Option<usize> {
+  let (number, unit) = match size.char_indices().last()? {
+    (i, 'k' | 'K') => (&size[..i], 1 << 10),
+    (i, 'm' | 'M') => (&size[..i], 1 << 20),
+    (i, 'g' | 'G') => (&size[..i], 1 << 30),
+    _ => (size, 1),
+  };
+
+  number.parse::<usize>().ok()?.checked_mul(unit)
+}
+
+fn read_options(args: &InitArgs) -> Result<Vec<String>> {
+  let mut options = vec![];
+
+  for i in 0..args.n_options.max(0) as usize {
+    let option = unsafe { &*args.options.add(i) };
+
+    options.push(unsafe { jni::read_c_string(option.option_string)? });
+  }
+
+  Ok(options)
+}
+
+/// The VM created by `JNI_CreateJavaVM` runs on the thread that created it
+pub(crate) fn is_creator_thread(jvm: *mut JVM) -> bool {
+  if CREATED.load(Ordering::SeqCst) != jvm {
+    return true;
+  }
+
+  *CREATOR.lock().unwrap() == Some(thread::current().id())
+}
+
+/// `DestroyJavaVM`: the VM created by `JNI_CreateJavaVM` is shut down and freed
+pub(crate) extern "C" fn destroy_java_vm(vm: *mut Interface) -> i32 {
+  let jvm = unsafe { jni::get_jvm(vm) } as *mut JVM;
+
+  if !is_creator_thread(jvm)
+    || CREATED
+      .compare_exchange(jvm, null_mut(), Ordering::SeqCst, Ordering::SeqCst)
+      .is_err()
+  {
+    return JNI_ERR;
+  }
+
+  let mut jvm = unsafe { Box::from_raw(jvm) };
+
+  if let Err(err) = jvm.call_and_resolve_method("java/lang/Shutdown", "shutdown", "()V", vec![]) {
+    error!("Shutdown of the VM failed: {}", err);
+  }
+
+  *CREATOR.lock().unwrap() = None;
+
+  JNI_OK
+}
+
+/// `JNI_GetDefaultJavaVMInitArgs`: the VM has no default options, only the version is checked
+///
+/// # Safety
+///
+/// `args` is a `JavaVMInitArgs`
+#[no_mangle]
+pub unsafe extern "C" fn JNI_GetDefaultJavaVMInitArgs(args: *mut c_void) -> i32 {
+  let args = &*(args as *const InitArgs);
+
+  match jni::is_supported_version(args.version) {
+    true => JNI_OK,
+    false => JNI_EVERSION,
+  }
+}
+
+/// `JNI_CreateJavaVM`: create the VM of the process, `pvm` and `penv` receive its `JavaVM`
+/// and the `JNIEnv` of the calling thread
+///
+/// # Safety
+///
+/// `pvm` and `penv` are writable, `args` is a `JavaVMInitArgs`
+#[no_mangle]
+pub unsafe extern "C" fn JNI_CreateJavaVM(
+  pvm: *mut *mut c_void,
+  penv: *mut *mut c_void,
+  args: *mut c_void,
+) -> i32 {
+  let args = &*(args as *const InitArgs);
+
+  if !jni::is_supported_version(args.version) {
+    return JNI_EVERSION;
+  }
+
+  if !CREATED.load(Ordering::SeqCst).is_null() {
+    return JNI_EEXIST;
+  }
+
+  let options = read_options(args)
+    .and_then(|options| InvocationOptions::parse(&options, args.ignore_unrecognized != 0));
+
+  let options = match options {
+    Ok(options) => options,
+    Err(err) => {
+      error!("Invalid options of the VM: {}", err);
+
+      return JNI_EINVAL;
+    }
+  };
+
+  // a panic must not unwind into the C program
+  let jvm = match panic::catch_unwind(|| options.create()) {
+    Ok(Ok(jvm)) => Box::into_raw(Box::new(jvm)),
+    Ok(Err(err)) => {
+      error!("Creation of the VM failed: {}", err);
+
+      return JNI_ERR;
+    }
+    Err(_) => {
+      error!("Creation of the VM failed: the bootstrap panicked");
+
+      return JNI_ERR;
+    }
+  };
+
+  if CREATED
+    .compare_exchange(null_mut(), jvm, Ordering::SeqCst, Ordering::SeqCst)
+    .is_err()
+  {
+    drop(Box::from_raw(jvm));
+
+    return JNI_EEXIST;
+  }
+
+  *CREATOR.lock().unwrap() = Some(thread::current().id());
+
+  *pvm = (*jvm).get_java_vm() as *mut c_void;
+  *penv = (*jvm).get_jni_env() as *mut c_void;
+
+  JNI_OK
+}
+
+/// `JNI_GetCreatedJavaVMs`: the `JavaVM` created by `JNI_CreateJavaVM`, if any
+///
+/// # Safety
+///
+/// `vms` has room for `length` pointers, `count` is writable
+#[no_mangle]
+pub unsafe extern "C" fn JNI_GetCreatedJavaVMs(
+  vms: *mut *mut c_void,
+  length: i32,
+  count: *mut i32,
+) -> i32 {
+  let jvm = CREATED.load(Ordering::SeqCst);
+
+  if jvm.is_null() {
+    *count = 0;
+  } else {
+    if length > 0 {
+      *vms = (*jvm).get_java_vm() as *mut c_void;
+    }
+
+    *count = 1;
+  }
+
+  JNI_OK
+}
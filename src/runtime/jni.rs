@@ -0,0 +1,697 @@
+use std::{
+  collections::HashMap,
+  ffi::{c_void, CStr},
+};
+
+use color_eyre::eyre::{eyre, Report, Result};
+use log::{debug, error};
+
+use crate::{
+  runtime::{
+    errors, foreign, invocation,
+    jnienv::{self, JniMember},
+    jvm::JVM,
+    types,
+  },
+  utils::{get_parameters_type_descriptor, ju4, parse_parameter_types},
+};
+
+// https://docs.oracle.com/en/java/javase/23/docs/specs/jni/index.html
+//
+// The native methods not implemented by the VM are looked up among the functions bound by
+// `RegisterNatives`, then by their JNI names in the libraries loaded by `System.loadLibrary`
+// and in the process. They are called with a `JNIEnv` whose functions are implemented in
+// `jnienv.rs`. A reference given to the native code (`jobject`) is the heap reference of its
+// object: the heap never moves the objects, the local and global references only keep them
+// reachable for the garbage collector.
+
+pub(crate) const JNI_OK: i32 = 0;
+pub(crate) const JNI_ERR: i32 = -1;
+pub(crate) const JNI_EVERSION: i32 = -3;
+pub(crate) const JNI_EEXIST: i32 = -5;
+pub(crate) const JNI_EINVAL: i32 = -6;
+
+pub(crate) const JNI_VERSION_1_1: i32 = 0x0001_0001;
+pub(crate) const JNI_VERSION_21: i32 = 0x0015_0000;
+
+// versions a library or a caller of `GetEnv` can ask for
+const JNI_VERSIONS: [i32; 9] = [
+  0x0001_0002,
+  0x0001_0004,
+  0x0001_0006,
+  0x0001_0008,
+  0x0009_0000,
+  0x000a_0000,
+  0x0013_0000,
+  0x0014_0000,
+  JNI_VERSION_21,
+];
+
+// jobjectRefType
+pub(crate) const JNI_INVALID_REF_TYPE: i32 = 0;
+pub(crate) const JNI_LOCAL_REF_TYPE: i32 = 1;
+pub(crate) const JNI_GLOBAL_REF_TYPE: i32 = 2;
+pub(crate) const JNI_WEAK_GLOBAL_REF_TYPE: i32 = 3;
+
+/// A `JNIEnv` or a `JavaVM`: a function table, then the VM the functions run in
+#[repr(C)]
+#[derive(Debug)]
+pub(crate) struct Interface {
+  functions: *const *const c_void,
+  jvm: *mut JVM,
+}
+
+/// The VM of the `JNIEnv` or the `JavaVM` given to the native code
+///
+/// # Safety
+///
+/// `interface` is one of the interfaces of a `JniManager`
+pub(crate) unsafe fn get_jvm<'a>(interface: *mut Interface) -> &'a mut JVM {
+  &mut *(*interface).jvm
+}
+
+#[derive(Debug)]
+pub(crate) struct JniManager {
+  env: Box<Interface>,
+  vm: Box<Interface>,
+  // the function tables of `env` and `vm`, kept alive
+  _env_functions: Vec<*const c_void>,
+  _vm_functions: Vec<*const c_void>,
+  // handles of the libraries loaded by System.loadLibrary
+  libraries: Vec<u64>,
+  // key: (class, name, descriptor) of a native method bound by RegisterNatives
+  registered: HashMap<(String, String, String), u64>,
+  // jmethodID and jfieldID, never freed: the boxes keep their addresses
+  #[allow(clippy::vec_box)]
+  members: Vec<Box<JniMember>>,
+  member_ids: HashMap<JniMember, *const JniMember>,
+  // local references of the native methods running, a frame per call or PushLocalFrame
+  locals: Vec<Vec<ju4>>,
+  // number of global references of each object
+  globals: HashMap<ju4, usize>,
+  weak_globals: HashMap<ju4, usize>,
+  // exception thrown by the native code, raised when it returns
+  pending: Option<ju4>,
+}
+
+impl JniManager {
+  pub(crate) fn new() -> Self {
+    let env_functions = jnienv::get_functions();
+    let vm_functions = get_invoke_functions();
+
+    JniManager {
+      env: Box::new(Interface {
+        functions: env_functions.as_ptr(),
+        jvm: std::ptr::null_mut(),
+      }),
+      vm: Box::new(Interface {
+        functions: vm_functions.as_ptr(),
+        jvm: std::ptr::null_mut(),
+      }),
+      _env_functions: env_functions,
+      _vm_functions: vm_functions,
+      libraries: vec![],
+      registered: HashMap::new(),
+      members: vec![],
+      member_ids: HashMap::new(),
+      // the references created outside of a native method (e.g. by JNI_OnLoad)
+      locals: vec![vec![]],
+      globals: HashMap::new(),
+      weak_globals: HashMap::new(),
+      pending: None,
+    }
+  }
+
+  // the VM moves with its owner, the interfaces are updated before they are given away
+  fn set_jvm(&mut self, jvm: *mut JVM) {
+    self.env.jvm = jvm;
+    self.vm.jvm = jvm;
+  }
+
+  pub(crate) fn add_library(&mut self, handle: u64) {
+    self.libraries.push(handle);
+  }
+
+  pub(crate) fn remove_library(&mut self, handle: u64) -> bool {
+    match self.libraries.iter().position(|library| *library == handle) {
+      Some(index) => {
+        self.libraries.remove(index);
+
+        true
+      }
+      None => false,
+    }
+  }
+
+  pub(crate) fn register_native(
+    &mut self,
+    classname: &str,
+    name: &str,
+    descriptor: &str,
+    address: u64,
+  ) {
+    let key = (
+      classname.to_string(),
+      name.to_string(),
+      descriptor.to_string(),
+    );
+
+    self.registered.insert(key, address);
+  }
+
+  pub(crate) fn unregister_natives(&mut self, classname: &str) {
+    self
+      .registered
+      .retain(|(class, _, _), _| class != classname);
+  }
+
+  pub(crate) fn get_registered(
+    &self,
+    classname: &str,
+    name: &str,
+    descriptor: &str,
+  ) -> Option<u64> {
+    let key = (
+      classname.to_string(),
+      name.to_string(),
+      descriptor.to_string(),
+    );
+
+    self.registered.get(&key).copied()
+  }
+
+  /// The ID of `member`, the same one for equal members
+  pub(crate) fn add_member(&mut self, member: JniMember) -> *const JniMember {
+    if let Some(id) = self.member_ids.get(&member) {
+      return *id;
+    }
+
+    let boxed = Box::new(member.clone());
+    let id = &*boxed as *const JniMember;
+
+    self.members.push(boxed);
+    self.member_ids.insert(member, id);
+
+    id
+  }
+
+  pub(crate) fn push_frame(&mut self) {
+    self.locals.push(vec![]);
+  }
+
+  // the first frame is never popped
+  pub(crate) fn pop_frame(&mut self) {
+    if self.locals.len() > 1 {
+      self.locals.pop();
+    } else {
+      self.locals[0].clear();
+    }
+  }
+
+  /// A local reference of `obj_ref` in the current frame
+  pub(crate) fn new_local(&mut self, obj_ref: ju4) -> u64 {
+    if obj_ref != 0 {
+      self.locals.last_mut().unwrap().push(obj_ref);
+    }
+
+    obj_ref as u64
+  }
+
+  pub(crate) fn delete_local(&mut self, obj_ref: ju4) {
+    let frame = self.locals.last_mut().unwrap();
+
+    if let Some(index) = frame.iter().rposition(|local| *local == obj_ref) {
+      frame.swap_remove(index);
+    }
+  }
+
+  pub(crate) fn new_global(&mut self, obj_ref: ju4) -> u64 {
+    if obj_ref != 0 {
+      *self.globals.entry(obj_ref).or_insert(0) += 1;
+    }
+
+    obj_ref as u64
+  }
+
+  pub(crate) fn delete_global(&mut self, obj_ref: ju4) {
+    Self::release(&mut self.globals, obj_ref);
+  }
+
+  pub(crate) fn new_weak_global(&mut self, obj_ref: ju4) -> u64 {
+    if obj_ref != 0 {
+      *self.weak_globals.entry(obj_ref).or_insert(0) += 1;
+    }
+
+    obj_ref as u64
+  }
+
+  pub(crate) fn delete_weak_global(&mut self, obj_ref: ju4) {
+    Self::release(&mut self.weak_globals, obj_ref);
+  }
+
+  fn release(references: &mut HashMap<ju4, usize>, obj_ref: ju4) {
+    if let Some(count) = references.get_mut(&obj_ref) {
+      *count -= 1;
+
+      if *count == 0 {
+        references.remove(&obj_ref);
+      }
+    }
+  }
+
+  /// Kind of the reference `obj_ref`: all the references of an object are equal, the ones
+  /// outliving the native method come first
+  pub(crate) fn get_ref_type(&self, obj_ref: ju4) -> i32 {
+    if self.globals.contains_key(&obj_ref) {
+      JNI_GLOBAL_REF_TYPE
+    } else if self.weak_globals.contains_key(&obj_ref) {
+      JNI_WEAK_GLOBAL_REF_TYPE
+    } else if self.locals.last().unwrap().contains(&obj_ref) {
+      JNI_LOCAL_REF_TYPE
+    } else {
+      JNI_INVALID_REF_TYPE
+    }
+  }
+
+  pub(crate) fn set_pending(&mut self, exec_ref: ju4) {
+    self.pending = Some(exec_ref);
+  }
+
+  pub(crate) fn get_pending(&self) -> Option<ju4> {
+    self.pending
+  }
+
+  pub(crate) fn take_pending(&mut self) -> Option<ju4> {
+    self.pending.take()
+  }
+
+  /// Objects referenced by the native code
+  pub(crate) fn get_references(&self) -> Vec<types::Type> {
+    self
+      .locals
+      .iter()
+      .flatten()
+      .chain(self.globals.keys())
+      .chain(self.pending.iter())
+      .map(|obj_ref| types::Type::ObjectRef(*obj_ref))
+      .collect()
+  }
+}
+
+pub(crate) fn is_supported_version(version: i32) -> bool {
+  JNI_VERSIONS.contains(&version)
+}
+
+// https://docs.oracle.com/en/java/javase/23/docs/specs/jni/invocation.html#invocation-api-functions
+fn get_invoke_functions() -> Vec<*const c_void> {
+  vec![
+    std::ptr::null(),
+    std::ptr::null(),
+    std::ptr::null(),
+    invocation::destroy_java_vm as *const c_void,
+    attach_current_thread as *const c_void,
+    detach_current_thread as *const c_void,
+    get_env as *const c_void,
+    attach_current_thread as *const c_void,
+  ]
+}
+
+// the VM runs on a single thread, already attached
+extern "C" fn attach_current_thread(
+  vm: *mut Interface,
+  penv: *mut *mut Interface,
+  _args: *mut c_void,
+) -> i32 {
+  let jvm = unsafe { get_jvm(vm) };
+
+  if !invocation::is_creator_thread(jvm) {
+    error!("The VM runs on the thread that created it");
+
+    return JNI_ERR;
+  }
+
+  unsafe {
+    *penv = jvm.get_jni_env();
+  }
+
+  JNI_OK
+}
+
+extern "C" fn detach_current_thread(_vm: *mut Interface) -> i32 {
+  JNI_OK
+}
+
+extern "C" fn get_env(vm: *mut Interface, penv: *mut *mut Interface, version: i32) -> i32 {
+  if !is_supported_version(version) {
+    unsafe {
+      *penv = std::ptr::null_mut();
+    }
+
+    return JNI_EVERSION;
+  }
+
+  attach_current_thread(vm, penv, std::ptr::null_mut())
+}
+
+// https://docs.oracle.com/en/java/javase/23/docs/specs/jni/design.html#resolving-native-method-names
+fn mangle(name: &str) -> String {
+  let mut mangled = String::new();
+
+  for unit in name.encode_utf16() {
+    match char::from_u32(unit as u32) {
+      Some('/') => mangled.push('_'),
+      Some('_') => mangled.push_str("_1"),
+      Some(';') => mangled.push_str("_2"),
+      Some('[') => mangled.push_str("_3"),
+      Some(c) if c.is_ascii_alphanumeric() => mangled.push(c),
+      _ => mangled.push_str(&format!("_0{:04x}", unit)),
+    }
+  }
+
+  mangled
+}
+
+/// The short and the long JNI names of a native method
+pub(crate) fn get_jni_names(classname: &str, name: &str, descriptor: &str) -> (String, String) {
+  let short_name = format!("Java_{}_{}", mangle(classname), mangle(name));
+  let long_name = format!(
+    "{}__{}",
+    short_name,
+    mangle(get_parameters_type_descriptor(descriptor))
+  );
+
+  (short_name, long_name)
+}
+
+impl JVM {
+  /// The `JNIEnv` of the VM
+  pub(crate) fn get_jni_env(&mut self) -> *mut Interface {
+    let jvm = self as *mut JVM;
+
+    self.jnimanager.set_jvm(jvm);
+
+    &mut *self.jnimanager.env
+  }
+
+  /// The `JavaVM` of the VM
+  pub(crate) fn get_java_vm(&mut self) -> *mut Interface {
+    let jvm = self as *mut JVM;
+
+    self.jnimanager.set_jvm(jvm);
+
+    &mut *self.jnimanager.vm
+  }
+
+  /// Load the JNI library `name` (`dlopen`) and run its `JNI_OnLoad`
+  ///
+  /// # Returns
+  ///
+  /// The handle of the library and the JNI version it needs, the handle is 0 when the
+  /// library can't be loaded
+  pub(crate) fn load_jni_library(&mut self, name: &str) -> Result<(u64, i32)> {
+    // the variadic functions of the `JNIEnv` and the native calls follow the SysV ABI of
+    // x86-64
+    if cfg!(not(target_arch = "x86_64")) {
+      return Err(eyre!(errors::JavaException::UnsatisfiedLinkError(format!(
+        "JNI libraries are not supported on {}: {}",
+        std::env::consts::ARCH,
+        name
+      ))));
+    }
+
+    let handle = foreign::load_library(name)?;
+
+    if handle == 0 {
+      return Ok((0, 0));
+    }
+
+    let version = match foreign::find_entry(handle, "JNI_OnLoad")? {
+      0 => JNI_VERSION_1_1,
+      on_load => {
+        let vm = self.get_java_vm() as u64;
+
+        self.jnimanager.push_frame();
+        let result = foreign::call_function(on_load, &[("J", vm), ("J", 0)], "I");
+        self.jnimanager.pop_frame();
+
+        match result.and_then(|version| {
+          self.raise_jni_exception()?;
+
+          Ok(version as i32)
+        }) {
+          Ok(version) => version,
+          Err(err) => {
+            foreign::unload_library(handle);
+
+            return Err(err);
+          }
+        }
+      }
+    };
+
+    if version != JNI_VERSION_1_1 && !is_supported_version(version) {
+      foreign::unload_library(handle);
+
+      return Err(eyre!(errors::JavaException::UnsatisfiedLinkError(format!(
+        "unsupported JNI version 0x{:x} required by {}",
+        version, name
+      ))));
+    }
+
+    debug!("JNI library {} loaded, version 0x{:x}", name, version);
+
+    self.jnimanager.add_library(handle);
+
+    Ok((handle, version))
+  }
+
+  /// Run the `JNI_OnUnload` of the library `handle` and unload it
+  pub(crate) fn unload_jni_library(&mut self, handle: u64) -> Result<()> {
+    if !self.jnimanager.remove_library(handle) {
+      return Ok(());
+    }
+
+    let on_unload = foreign::find_entry(handle, "JNI_OnUnload")?;
+
+    let result = match on_unload {
+      0 => Ok(()),
+      on_unload => {
+        let vm = self.get_java_vm() as u64;
+
+        foreign::call_function(on_unload, &[("J", vm), ("J", 0)], "V").map(|_| ())
+      }
+    };
+
+    foreign::unload_library(handle);
+
+    result
+  }
+
+  /// Address of the function implementing the native method, 0 when there is none
+  pub(crate) fn find_jni_function(
+    &mut self,
+    classname: &str,
+    name: &str,
+    descriptor: &str,
+  ) -> Result<u64> {
+    if let Some(address) = self.jnimanager.get_registered(classname, name, descriptor) {
+      return Ok(address);
+    }
+
+    let (short_name, long_name) = get_jni_names(classname, name, descriptor);
+
+    // the libraries, then the process
+    for handle in self.jnimanager.libraries.iter().rev().chain([&0]) {
+      for symbol in [&short_name, &long_name] {
+        let address = foreign::find_entry(*handle, symbol)?;
+
+        if address != 0 {
+          debug!("JNI function {} found", symbol);
+
+          return Ok(address);
+        }
+      }
+    }
+
+    Ok(0)
+  }
+
+  /// Run the native method implemented by the JNI function `address` with the arguments of
+  /// the operand stack, the result is pushed on it
+  pub(crate) fn call_jni_function(
+    &mut self,
+    address: u64,
+    classname: &str,
+    name: &str,
+    descriptor: &str,
+  ) -> Result<Option<types::Type>> {
+    let (_, method) = self
+      .class_loader
+      .get_method_by_name(classname, name, descriptor)?;
+
+    let param_types = parse_parameter_types(get_parameters_type_descriptor(descriptor));
+
+    let mut args = vec![];
+
+    for _ in &param_types {
+      args.push(self.pop_stack()?);
+    }
+
+    args.reverse();
+
+    // the class of a static method, the object of the others
+    let receiver = if method.is_static() {
+      self
+        .heap
+        .alloc_class_obj(&mut self.class_loader, classname)?
+    } else {
+      self.pop_stack()?
+    };
+
+    let return_type = descriptor.rsplit(')').next().unwrap_or("V");
+
+    let env = self.get_jni_env() as u64;
+
+    self.jnimanager.push_frame();
+
+    let mut raws = vec![("J", env), ("L", self.get_jni_raw(receiver)?)];
+
+    for (param_type, arg) in param_types.into_iter().zip(args) {
+      raws.push((param_type, self.get_jni_raw(arg)?));
+    }
+
+    debug!("JNI call of {}.{}{}", classname, name, descriptor);
+
+    let returned = foreign::call_function(address, &raws, return_type).and_then(|raw| {
+      self.raise_jni_exception()?;
+
+      self.get_jni_value(return_type, raw)
+    });
+
+    self.jnimanager.pop_frame();
+
+    let returned = returned?;
+
+    if returned == types::Type::None {
+      return Ok(None);
+    }
+
+    self.push_stack(returned)?;
+
+    Ok(Some(returned))
+  }
+
+  // the pending exception of the native code is raised
+  fn raise_jni_exception(&mut self) -> Result<()> {
+    match self.jnimanager.take_pending() {
+      // the object thrown by the native code, with its message
+      Some(exec_ref) => {
+        let classname = self.heap.get_obj_instance(exec_ref)?.get_classname();
+
+        Err(eyre!(errors::JavaException::Throwable(
+          classname.to_string(),
+          exec_ref
+        )))
+      }
+      None => Ok(()),
+    }
+  }
+
+  /// The error of a JNI function becomes the pending exception of the native code
+  pub(crate) fn throw_jni(&mut self, err: Report) {
+    let exception = match err.downcast::<errors::JavaException>() {
+      Ok(exception) => exception,
+      Err(err) => {
+        error!("JNI function failed: {}", err);
+
+        errors::JavaException::InternalError(err.to_string())
+      }
+    };
+
+    match self.alloc_java_exception(&exception) {
+      Ok(exec_ref) => self.jnimanager.set_pending(exec_ref),
+      Err(err) => error!("JNI exception {} not thrown: {}", exception, err),
+    }
+  }
+
+  /// A new exception of the class `classname` with the detail message `message`
+  pub(crate) fn new_throwable(
+    &mut self,
+    classname: &str,
+    message: Option<&str>,
+  ) -> Result<errors::JavaException> {
+    self.init_class(classname)?;
+
+    let exec = self.heap.alloc_obj(&mut self.class_loader, classname)?;
+
+    let message = match message {
+      Some(message) => self.heap.alloc_string(&mut self.class_loader, message)?,
+      None => types::Type::Null,
+    };
+
+    self.call_and_resolve_method(
+      classname,
+      "<init>",
+      "(Ljava/lang/String;)V",
+      vec![exec, message],
+    )?;
+
+    Ok(errors::JavaException::Throwable(
+      classname.to_string(),
+      exec.as_ref()?,
+    ))
+  }
+
+  /// The raw value given to the native code for `value`, a local reference for an object
+  pub(crate) fn get_jni_raw(&mut self, value: types::Type) -> Result<u64> {
+    let raw = match value {
+      types::Type::ObjectRef(obj_ref) | types::Type::ArrayRef(obj_ref) => {
+        self.jnimanager.new_local(obj_ref)
+      }
+      types::Type::Null | types::Type::None => 0,
+      types::Type::Boolean(value) => value as u64,
+      types::Type::Character(value) => value as u8 as u64,
+      types::Type::Float(value) => value.to_bits() as u64,
+      types::Type::Double(value) => value.to_bits(),
+      value => value.as_long()? as u64,
+    };
+
+    Ok(raw)
+  }
+
+  /// The value of type `descriptor` of the raw value `raw` given by the native code
+  pub(crate) fn get_jni_value(&self, descriptor: &str, raw: u64) -> Result<types::Type> {
+    match descriptor {
+      "Z" | "B" | "C" | "S" | "I" | "J" | "F" | "D" | "V" => foreign::from_raw(descriptor, raw),
+      _ => self.get_jni_object(raw),
+    }
+  }
+
+  /// The object of the reference `handle`
+  pub(crate) fn get_jni_object(&self, handle: u64) -> Result<types::Type> {
+    if handle == 0 {
+      return Ok(types::Type::Null);
+    }
+
+    let obj_ref =
+      ju4::try_from(handle).map_err(|_| eyre!(errors::InternalError::SegmentationFault(handle)))?;
+
+    match self.heap.get_instance(obj_ref)? {
+      types::Instance::ArrayInstance(_) => Ok(types::Type::ArrayRef(obj_ref)),
+      types::Instance::ObjectInstance(_) => Ok(types::Type::ObjectRef(obj_ref)),
+    }
+  }
+}
+
+/// A C string of the native code
+///
+/// # Safety
+///
+/// `value` is null or a string ending with a NUL
+pub(crate) unsafe fn read_c_string(value: *const std::ffi::c_char) -> Result<String> {
+  if value.is_null() {
+    return Err(eyre!(errors::JavaException::NullPointer));
+  }
+
+  Ok(jnienv::from_modified_utf8(CStr::from_ptr(value).to_bytes()))
+}
@@ -0,0 +1,1909 @@
+#[cfg(target_arch = "x86_64")]
+use std::arch::global_asm;
+use std::ffi::{c_char, c_void};
+
+use color_eyre::eyre::{eyre, Result};
+use log::{debug, error};
+
+use crate::{
+  class_loader::namespace::BOOTSTRAP_LOADER,
+  runtime::{
+    errors,
+    jni::{self, Interface, JNI_ERR, JNI_OK},
+    jvm::JVM,
+    lambdaform::is_assignable,
+    types,
+  },
+  utils::{class_to_dotclass, get_parameters_type_descriptor, ju4, parse_parameter_types},
+};
+
+// https://docs.oracle.com/en/java/javase/23/docs/specs/jni/functions.html
+//
+// The functions of the `JNIEnv` table. An error of a function becomes the pending exception
+// of the native code and the function returns 0 (e.g. NULL for a reference). The strings and
+// the elements of the arrays are copies allocated with `malloc`, written back and freed by
+// the `Release` functions. The functions with a variable number of arguments are entry points
+// of the trampoline at the bottom, which calls their `va_list` variant.
+
+// mode of the `Release<Type>ArrayElements` functions
+const JNI_COMMIT: i32 = 1;
+const JNI_ABORT: i32 = 2;
+
+const ACC_STATIC: i32 = 0x0008;
+
+// a `JNIEnv *`
+type Env = *mut Interface;
+
+// a reference (`jobject`): the heap reference of the object
+type Handle = u64;
+
+// a `jmethodID` or a `jfieldID`
+type Id = *const JniMember;
+
+/// A method or a field, in the class declaring it
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct JniMember {
+  classname: String,
+  name: String,
+  descriptor: String,
+  is_static: bool,
+}
+
+/// A `va_list` of the SysV x86-64 ABI: the argument registers saved in `reg_save_area`, then
+/// the stack arguments in `overflow_arg_area`
+#[repr(C)]
+struct VaList {
+  gp_offset: u32,
+  fp_offset: u32,
+  overflow_arg_area: *const u64,
+  reg_save_area: *const u8,
+}
+
+impl VaList {
+  // after the 6 integer registers
+  const GP_END: u32 = 48;
+  // after the 8 vector registers
+  const FP_END: u32 = 176;
+
+  unsafe fn next_integer(&mut self) -> u64 {
+    if self.gp_offset < Self::GP_END {
+      let value = *(self.reg_save_area.add(self.gp_offset as usize) as *const u64);
+      self.gp_offset += 8;
+
+      value
+    } else {
+      self.next_overflow()
+    }
+  }
+
+  unsafe fn next_vector(&mut self) -> u64 {
+    if self.fp_offset < Self::FP_END {
+      let value = *(self.reg_save_area.add(self.fp_offset as usize) as *const u64);
+      self.fp_offset += 16;
+
+      value
+    } else {
+      self.next_overflow()
+    }
+  }
+
+  unsafe fn next_overflow(&mut self) -> u64 {
+    let value = *self.overflow_arg_area;
+    self.overflow_arg_area = self.overflow_arg_area.add(1);
+
+    value
+  }
+
+  // the `float` are promoted to `double`, the small integers to `int`
+  unsafe fn next(&mut self, descriptor: &str) -> u64 {
+    match descriptor {
+      "F" => (f64::from_bits(self.next_vector()) as f32).to_bits() as u64,
+      "D" => self.next_vector(),
+      _ => self.next_integer(),
+    }
+  }
+}
+
+/// The arguments of a method called by the native code
+#[derive(Clone, Copy)]
+enum Arguments {
+  VaList(*mut VaList),
+  // an array of `jvalue`
+  Values(*const u64),
+}
+
+#[derive(Clone, Copy)]
+enum Receiver {
+  // the method of the class of the object
+  Virtual(Handle),
+  // the method of the class given
+  Nonvirtual(Handle, Handle),
+  Static,
+}
+
+/// A `JNINativeMethod` of `RegisterNatives`
+#[repr(C)]
+struct NativeMethod {
+  name: *const c_char,
+  signature: *const c_char,
+  function: *const c_void,
+}
+
+/// A value returned or passed in a register by the native code: the integers and the
+/// references in a general purpose register, `jfloat` and `jdouble` in a vector one
+trait Raw: Copy {
+  fn from_raw(raw: u64) -> Self;
+
+  fn to_raw(self) -> u64;
+}
+
+impl Raw for u64 {
+  fn from_raw(raw: u64) -> Self {
+    raw
+  }
+
+  fn to_raw(self) -> u64 {
+    self
+  }
+}
+
+impl Raw for f32 {
+  fn from_raw(raw: u64) -> Self {
+    f32::from_bits(raw as u32)
+  }
+
+  fn to_raw(self) -> u64 {
+    self.to_bits() as u64
+  }
+}
+
+impl Raw for f64 {
+  fn from_raw(raw: u64) -> Self {
+    f64::from_bits(raw)
+  }
+
+  fn to_raw(self) -> u64 {
+    self.to_bits()
+  }
+}
+
+/// Decode the modified UTF-8 of the JNI strings, invalid sequences are replaced
+pub(crate) fn from_modified_utf8(bytes: &[u8]) -> String {
+  let mut units = vec![];
+  let mut i = 0;
+
+  while i < bytes.len() {
+    let byte = bytes[i] as u16;
+    let next = |offset: usize| bytes.get(i + offset).map_or(0, |b| (*b & 0x3f) as u16);
+
+    let (unit, length) = match byte {
+      0x00..=0x7f => (byte, 1),
+      0xc0..=0xdf => (((byte & 0x1f) << 6) | next(1), 2),
+      0xe0..=0xef => (((byte & 0x0f) << 12) | (next(1) << 6) | next(2), 3),
+      _ => (0xfffd, 1),
+    };
+
+    units.push(unit);
+    i += length;
+  }
+
+  String::from_utf16_lossy(&units)
+}
+
+/// Encode UTF-16 units in the modified UTF-8 of the JNI strings
+pub(crate) fn to_modified_utf8(units: &[u16]) -> Vec<u8> {
+  let mut bytes = vec![];
+
+  for unit in units {
+    match *unit {
+      0x01..=0x7f => bytes.push(*unit as u8),
+      0x00 | 0x80..=0x7ff => {
+        bytes.push(0xc0 | (unit >> 6) as u8);
+        bytes.push(0x80 | (unit & 0x3f) as u8);
+      }
+      _ => {
+        bytes.push(0xe0 | (unit >> 12) as u8);
+        bytes.push(0x80 | ((unit >> 6) & 0x3f) as u8);
+        bytes.push(0x80 | (unit & 0x3f) as u8);
+      }
+    }
+  }
+
+  bytes
+}
+
+// size of the elements of a primitive array
+fn get_element_size(descriptor: &str) -> Result<usize> {
+  let size = match descriptor {
+    "Z" | "B" => 1,
+    "C" | "S" => 2,
+    "I" | "F" => 4,
+    "J" | "D" => 8,
+    _ => {
+      return Err(eyre!(errors::InternalError::General(format!(
+        "{} is not the element of a primitive array",
+        descriptor
+      ))))
+    }
+  };
+
+  Ok(size)
+}
+
+// descriptor of the class `classname`
+fn get_class_descriptor(classname: &str) -> String {
+  if types::Type::is_primitive(classname) || classname == "V" || classname.starts_with('[') {
+    classname.to_string()
+  } else {
+    format!("L{};", classname)
+  }
+}
+
+// a copy of `bytes` allocated by `malloc`
+fn copy_to_c(bytes: &[u8]) -> *mut c_void {
+  unsafe {
+    let copy = libc::malloc(bytes.len().max(1));
+
+    if !copy.is_null() {
+      std::ptr::copy_nonoverlapping(bytes.as_ptr(), copy as *mut u8, bytes.len());
+    }
+
+    copy
+  }
+}
+
+fn set_is_copy(is_copy: *mut u8) {
+  if !is_copy.is_null() {
+    unsafe {
+      *is_copy = 1;
+    }
+  }
+}
+
+fn get_member(id: Id) -> Result<JniMember> {
+  if id.is_null() {
+    return Err(eyre!(errors::JavaException::NullPointer));
+  }
+
+  Ok(unsafe { (*id).clone() })
+}
+
+// runs `f` in the VM of `env`, an error becomes the pending exception
+fn with_jvm<T>(env: Env, default: T, f: impl FnOnce(&mut JVM) -> Result<T>) -> T {
+  let jvm = unsafe { jni::get_jvm(env) };
+
+  match f(jvm) {
+    Ok(value) => value,
+    Err(err) => {
+      jvm.throw_jni(err);
+
+      default
+    }
+  }
+}
+
+impl JVM {
+  // the object of `handle`, not null
+  fn get_jni_ref(&self, handle: Handle) -> Result<ju4> {
+    match self.get_jni_object(handle)? {
+      types::Type::Null => Err(eyre!(errors::JavaException::NullPointer)),
+      value => value.as_ref(),
+    }
+  }
+
+  // the name of the class of the object `class`
+  fn get_jni_classname(&self, class: Handle) -> Result<String> {
+    let class_ref = self.get_jni_ref(class)?;
+
+    self.heap.get_classname_from_class_obj(class_ref)
+  }
+
+  fn get_jni_string(&self, string: Handle) -> Result<Vec<u16>> {
+    let string_ref = self.get_jni_ref(string)?;
+
+    Ok(self.heap.get_string(string_ref)?.encode_utf16().collect())
+  }
+
+  fn new_jni_local(&mut self, value: types::Type) -> Result<Handle> {
+    let obj_ref = value.as_ref()?;
+
+    Ok(self.jnimanager.new_local(obj_ref))
+  }
+
+  // the class object of `name`, loaded
+  fn find_jni_class(&mut self, name: &str) -> Result<types::Type> {
+    let classname = name
+      .strip_prefix('L')
+      .and_then(|name| name.strip_suffix(';'));
+    let classname = classname.unwrap_or(name);
+
+    let component = classname.trim_start_matches('[');
+
+    if !types::Type::is_primitive(component) {
+      let component = component
+        .strip_prefix('L')
+        .and_then(|component| component.strip_suffix(';'))
+        .unwrap_or(component);
+
+      if self.class_loader.get(component).is_err() {
+        return Err(eyre!(
+          self.new_throwable("java/lang/NoClassDefFoundError", Some(component))?
+        ));
+      }
+    }
+
+    self.heap.alloc_class_obj(&mut self.class_loader, classname)
+  }
+
+  fn get_jni_method(
+    &mut self,
+    class: Handle,
+    name: *const c_char,
+    descriptor: *const c_char,
+    is_static: bool,
+  ) -> Result<Id> {
+    let classname = self.get_jni_classname(class)?;
+    let (name, descriptor) =
+      unsafe { (jni::read_c_string(name)?, jni::read_c_string(descriptor)?) };
+
+    self.init_class(&classname)?;
+
+    let method =
+      self
+        .class_loader
+        .get_any_method_by_name_with_index(&classname, &name, &descriptor);
+
+    let method_class = match method {
+      Ok((method_class, method, _)) if method.is_static() == is_static => method_class,
+      _ => {
+        return Err(eyre!(
+          self.new_throwable("java/lang/NoSuchMethodError", Some(&name))?
+        ))
+      }
+    };
+
+    let member = JniMember {
+      classname: method_class,
+      name,
+      descriptor,
+      is_static,
+    };
+
+    Ok(self.jnimanager.add_member(member))
+  }
+
+  // the class declaring the field, its interfaces declare static fields
+  fn find_jni_field(
+    &mut self,
+    classname: &str,
+    name: &str,
+    descriptor: &str,
+    is_static: bool,
+  ) -> Result<Option<String>> {
+    let class = self.class_loader.get(classname)?;
+
+    let declared = class.get_fields().iter().any(|field| {
+      field.get_name() == name
+        && field.get_descriptor() == descriptor
+        && field.is_static() == is_static
+    });
+
+    if declared {
+      return Ok(Some(classname.to_string()));
+    }
+
+    let interfaces = match is_static {
+      true => class.get_interfaces().clone(),
+      false => vec![],
+    };
+    let parent = class
+      .has_parent()
+      .then(|| class.get_parent_name().to_string());
+
+    drop(class);
+
+    for interface in interfaces.iter().chain(parent.iter()) {
+      if let Some(declaring) = self.find_jni_field(interface, name, descriptor, is_static)? {
+        return Ok(Some(declaring));
+      }
+    }
+
+    Ok(None)
+  }
+
+  fn get_jni_field(
+    &mut self,
+    class: Handle,
+    name: *const c_char,
+    descriptor: *const c_char,
+    is_static: bool,
+  ) -> Result<Id> {
+    let classname = self.get_jni_classname(class)?;
+    let (name, descriptor) =
+      unsafe { (jni::read_c_string(name)?, jni::read_c_string(descriptor)?) };
+
+    self.init_class(&classname)?;
+
+    let Some(declaring) = self.find_jni_field(&classname, &name, &descriptor, is_static)? else {
+      return Err(eyre!(
+        self.new_throwable("java/lang/NoSuchFieldError", Some(&name))?
+      ));
+    };
+
+    let member = JniMember {
+      classname: declaring,
+      name,
+      descriptor,
+      is_static,
+    };
+
+    Ok(self.jnimanager.add_member(member))
+  }
+
+  // the arguments of the method of descriptor `descriptor`, the longs and the doubles take
+  // two local variables
+  fn get_jni_arguments(&self, descriptor: &str, args: Arguments) -> Result<Vec<types::Type>> {
+    let mut values = vec![];
+
+    let param_types = parse_parameter_types(get_parameters_type_descriptor(descriptor));
+
+    for (i, param_type) in param_types.into_iter().enumerate() {
+      let raw = unsafe {
+        match args {
+          Arguments::VaList(list) => (*list).next(param_type),
+          Arguments::Values(array) => *array.add(i),
+        }
+      };
+
+      let value = self.get_jni_value(param_type, raw)?;
+
+      value.push_to_locals(&mut values);
+    }
+
+    Ok(values)
+  }
+
+  fn call_jni_method(&mut self, receiver: Receiver, id: Id, args: Arguments) -> Result<u64> {
+    let member = get_member(id)?;
+
+    let (classname, mut values) = match receiver {
+      Receiver::Virtual(obj) => {
+        let obj = self.get_jni_object(obj)?;
+
+        let classname = match self.heap.get_instance(obj.as_ref()?)? {
+          types::Instance::ObjectInstance(instance) => instance.get_classname().to_string(),
+          types::Instance::ArrayInstance(_) => "java/lang/Object".to_string(),
+        };
+
+        let (method_class, _) =
+          self
+            .class_loader
+            .get_method_by_name(&classname, &member.name, &member.descriptor)?;
+
+        (method_class, vec![obj])
+      }
+      Receiver::Nonvirtual(obj, class) => {
+        let obj = types::Type::ObjectRef(self.get_jni_ref(obj)?);
+        let classname = self.get_jni_classname(class)?;
+
+        let (method_class, _) =
+          self
+            .class_loader
+            .get_method_by_name(&classname, &member.name, &member.descriptor)?;
+
+        (method_class, vec![obj])
+      }
+      Receiver::Static => (member.classname.clone(), vec![]),
+    };
+
+    if values.first() == Some(&types::Type::Null) {
+      return Err(eyre!(errors::JavaException::NullPointer));
+    }
+
+    values.extend(self.get_jni_arguments(&member.descriptor, args)?);
+
+    let returned =
+      self.call_and_resolve_method(&classname, &member.name, &member.descriptor, values)?;
+
+    self.get_jni_raw(returned)
+  }
+
+  fn new_jni_object(&mut self, class: Handle, id: Id, args: Arguments) -> Result<Handle> {
+    let member = get_member(id)?;
+    let classname = self.get_jni_classname(class)?;
+
+    self.init_class(&classname)?;
+
+    let obj = self.heap.alloc_obj(&mut self.class_loader, &classname)?;
+
+    let mut values = vec![obj];
+    values.extend(self.get_jni_arguments(&member.descriptor, args)?);
+
+    self.call_and_resolve_method(&classname, "<init>", &member.descriptor, values)?;
+
+    self.new_jni_local(obj)
+  }
+
+  // the array `array` and the range [start, start + length) of its elements
+  fn get_jni_region(
+    &self,
+    array: Handle,
+    start: i32,
+    length: Option<i32>,
+  ) -> Result<(ju4, usize, usize)> {
+    let array_ref = self.get_jni_ref(array)?;
+    let array_length = self.heap.get_array_instance(array_ref)?.len();
+    let length = length.unwrap_or(array_length as i32);
+
+    match (usize::try_from(start), usize::try_from(length)) {
+      (Ok(start), Ok(length)) if start + length <= array_length => Ok((array_ref, start, length)),
+      _ => Err(eyre!(errors::JavaException::ArrayIndexOutOfBounds(
+        (start as i64 + length as i64).max(0) as usize,
+        array_length
+      ))),
+    }
+  }
+
+  // the bytes of the elements of a primitive array
+  fn get_jni_elements(
+    &mut self,
+    array: Handle,
+    start: i32,
+    length: Option<i32>,
+  ) -> Result<Vec<u8>> {
+    let (array_ref, start, length) = self.get_jni_region(array, start, length)?;
+
+    let array = self.heap.get_array_instance(array_ref)?;
+    let descriptor = array.get_element_classname().to_string();
+    let size = get_element_size(&descriptor)?;
+
+    let elements = array.get_elements()[start..start + length].to_vec();
+
+    let mut bytes = vec![];
+
+    for element in elements {
+      let raw = self.get_jni_raw(element)?;
+
+      bytes.extend_from_slice(&raw.to_le_bytes()[..size]);
+    }
+
+    Ok(bytes)
+  }
+
+  // the elements of a primitive array are set to the values of `bytes`
+  fn set_jni_elements(
+    &mut self,
+    array: Handle,
+    start: i32,
+    length: Option<i32>,
+    bytes: *const u8,
+  ) -> Result<()> {
+    let (array_ref, start, length) = self.get_jni_region(array, start, length)?;
+
+    let descriptor = self
+      .heap
+      .get_array_instance(array_ref)?
+      .get_element_classname()
+      .to_string();
+    let size = get_element_size(&descriptor)?;
+
+    for i in 0..length {
+      let mut raw = [0u8; 8];
+
+      unsafe {
+        std::ptr::copy_nonoverlapping(bytes.add(i * size), raw.as_mut_ptr(), size);
+      }
+
+      let value = self.get_jni_value(&descriptor, u64::from_le_bytes(raw))?;
+      let value = types::Type::to_stored_value(&descriptor, value)?;
+
+      self
+        .heap
+        .get_array_instance_mut(array_ref)?
+        .set(start + i, value)?;
+    }
+
+    Ok(())
+  }
+
+  // the UTF-16 units [start, start + length) of the string
+  fn get_jni_string_region(&mut self, string: Handle, start: i32, length: i32) -> Result<Vec<u16>> {
+    let units = self.get_jni_string(string)?;
+
+    match (usize::try_from(start), usize::try_from(length)) {
+      (Ok(start), Ok(length)) if start + length <= units.len() => {
+        Ok(units[start..start + length].to_vec())
+      }
+      _ => {
+        let message = format!(
+          "begin {}, length {}, string length {}",
+          start,
+          length,
+          units.len()
+        );
+
+        Err(eyre!(self.new_throwable(
+          "java/lang/StringIndexOutOfBoundsException",
+          Some(&message)
+        )?))
+      }
+    }
+  }
+}
+
+extern "C" fn get_version(_env: Env) -> i32 {
+  jni::JNI_VERSION_21
+}
+
+extern "C" fn define_class(
+  env: Env,
+  name: *const c_char,
+  loader: Handle,
+  buffer: *const u8,
+  length: i32,
+) -> Handle {
+  with_jvm(env, 0, |jvm| {
+    let name = match name.is_null() {
+      true => None,
+      false => Some(unsafe { jni::read_c_string(name)? }),
+    };
+
+    let length = usize::try_from(length).map_err(|_| {
+      eyre!(errors::JavaException::ClassFormatError(format!(
+        "{}",
+        length
+      )))
+    })?;
+    let data = unsafe { std::slice::from_raw_parts(buffer, length) };
+
+    let loader = match jvm.get_jni_object(loader)? {
+      types::Type::Null => BOOTSTRAP_LOADER,
+      loader => loader.as_ref()?,
+    };
+
+    let key = jvm.define_class(loader, name.as_deref(), data)?;
+    let class_ref = jvm.get_class_instance_mut(&key)?.get_ref();
+
+    Ok(jvm.jnimanager.new_local(class_ref))
+  })
+}
+
+extern "C" fn find_class(env: Env, name: *const c_char) -> Handle {
+  with_jvm(env, 0, |jvm| {
+    let name = unsafe { jni::read_c_string(name)? };
+
+    let class = jvm.find_jni_class(&name)?;
+
+    jvm.new_jni_local(class)
+  })
+}
+
+extern "C" fn from_reflected_method(env: Env, method: Handle) -> Id {
+  with_jvm(env, std::ptr::null(), |jvm| {
+    let method = jvm.heap.get_obj_instance(jvm.get_jni_ref(method)?)?.clone();
+
+    let is_constructor = method.get_classname() == "java/lang/reflect/Constructor";
+
+    let classname = jvm
+      .heap
+      .get_classname_from_class_obj(method.get_field("clazz")?.as_ref()?)?;
+
+    let name = match is_constructor {
+      true => "<init>".to_string(),
+      false => jvm.heap.get_string(method.get_field("name")?.as_ref()?)?,
+    };
+
+    let mut descriptor = "(".to_string();
+
+    let param_types = method.get_field("parameterTypes")?.as_ref()?;
+    for param_type in jvm.heap.get_array_instance(param_types)?.get_elements() {
+      let param_type = jvm
+        .heap
+        .get_classname_from_class_obj(param_type.as_ref()?)?;
+
+      descriptor += &get_class_descriptor(&param_type);
+    }
+
+    descriptor += ")";
+    descriptor += &match is_constructor {
+      true => "V".to_string(),
+      false => {
+        let return_type = method.get_field("returnType")?.as_ref()?;
+
+        get_class_descriptor(&jvm.heap.get_classname_from_class_obj(return_type)?)
+      }
+    };
+
+    let member = JniMember {
+      classname,
+      name,
+      descriptor,
+      is_static: method.get_field("modifiers")?.as_integer()? & ACC_STATIC != 0,
+    };
+
+    Ok(jvm.jnimanager.add_member(member))
+  })
+}
+
+extern "C" fn from_reflected_field(env: Env, field: Handle) -> Id {
+  with_jvm(env, std::ptr::null(), |jvm| {
+    let field = jvm.heap.get_obj_instance(jvm.get_jni_ref(field)?)?.clone();
+
+    let classname = jvm
+      .heap
+      .get_classname_from_class_obj(field.get_field("clazz")?.as_ref()?)?;
+    let field_type = jvm
+      .heap
+      .get_classname_from_class_obj(field.get_field("type")?.as_ref()?)?;
+
+    let member = JniMember {
+      classname,
+      name: jvm.heap.get_string(field.get_field("name")?.as_ref()?)?,
+      descriptor: get_class_descriptor(&field_type),
+      is_static: field.get_field("modifiers")?.as_integer()? & ACC_STATIC != 0,
+    };
+
+    Ok(jvm.jnimanager.add_member(member))
+  })
+}
+
+extern "C" fn to_reflected_method(env: Env, _class: Handle, id: Id, _is_static: u8) -> Handle {
+  with_jvm(env, 0, |jvm| {
+    let member = get_member(id)?;
+
+    let method = jvm
+      .class_loader
+      .get(&member.classname)?
+      .get_methods()
+      .iter()
+      .find(|method| {
+        method.get_name() == member.name && method.get_descriptor() == member.descriptor
+      })
+      .cloned()
+      .ok_or_else(|| eyre!(errors::JavaException::NullPointer))?;
+
+    let reflected = match member.name.as_str() {
+      "<init>" => {
+        jvm
+          .heap
+          .alloc_reflect_constructor(&mut jvm.class_loader, &member.classname, &method)?
+      }
+      _ => jvm
+        .heap
+        .alloc_reflect_method(&mut jvm.class_loader, &member.classname, &method)?,
+    };
+
+    jvm.new_jni_local(reflected)
+  })
+}
+
+extern "C" fn get_superclass(env: Env, class: Handle) -> Handle {
+  with_jvm(env, 0, |jvm| {
+    let classname = jvm.get_jni_classname(class)?;
+
+    if types::Type::is_primitive(&classname) || classname == "V" {
+      return Ok(0);
+    }
+
+    let superclass = match classname.starts_with('[') {
+      true => Some("java/lang/Object".to_string()),
+      false => {
+        let class = jvm.class_loader.get(&classname)?;
+
+        (class.has_parent() && !class.is_interface()).then(|| class.get_parent_name().to_string())
+      }
+    };
+
+    match superclass {
+      Some(superclass) => {
+        let class = jvm
+          .heap
+          .alloc_class_obj(&mut jvm.class_loader, &superclass)?;
+
+        jvm.new_jni_local(class)
+      }
+      None => Ok(0),
+    }
+  })
+}
+
+extern "C" fn is_assignable_from(env: Env, from: Handle, to: Handle) -> u8 {
+  with_jvm(env, 0, |jvm| {
+    let from = jvm.get_jni_classname(from)?;
+    let to = jvm.get_jni_classname(to)?;
+
+    if types::Type::is_primitive(&from) || types::Type::is_primitive(&to) {
+      return Ok((from == to) as u8);
+    }
+
+    Ok(is_assignable(&mut jvm.class_loader, &to, &from)? as u8)
+  })
+}
+
+extern "C" fn to_reflected_field(env: Env, _class: Handle, id: Id, _is_static: u8) -> Handle {
+  with_jvm(env, 0, |jvm| {
+    let member = get_member(id)?;
+
+    let field = jvm
+      .class_loader
+      .get(&member.classname)?
+      .get_fields()
+      .iter()
+      .find(|field| field.get_name() == member.name)
+      .cloned()
+      .ok_or_else(|| eyre!(errors::JavaException::NullPointer))?;
+
+    let reflected =
+      jvm
+        .heap
+        .alloc_reflect_field(&mut jvm.class_loader, &member.classname, &field)?;
+
+    jvm.new_jni_local(reflected)
+  })
+}
+
+extern "C" fn throw(env: Env, obj: Handle) -> i32 {
+  with_jvm(env, JNI_ERR, |jvm| {
+    let exec_ref = jvm.get_jni_ref(obj)?;
+
+    jvm.jnimanager.set_pending(exec_ref);
+
+    Ok(JNI_OK)
+  })
+}
+
+extern "C" fn throw_new(env: Env, class: Handle, message: *const c_char) -> i32 {
+  with_jvm(env, JNI_ERR, |jvm| {
+    let classname = jvm.get_jni_classname(class)?;
+
+    let message = match message.is_null() {
+      true => None,
+      false => Some(unsafe { jni::read_c_string(message)? }),
+    };
+
+    Err(eyre!(jvm.new_throwable(&classname, message.as_deref())?))
+  })
+}
+
+extern "C" fn exception_occurred(env: Env) -> Handle {
+  with_jvm(env, 0, |jvm| {
+    let pending = jvm.jnimanager.get_pending().unwrap_or(0);
+
+    Ok(jvm.jnimanager.new_local(pending))
+  })
+}
+
+extern "C" fn exception_describe(env: Env) {
+  with_jvm(env, (), |jvm| {
+    let Some(exec_ref) = jvm.jnimanager.take_pending() else {
+      return Ok(());
+    };
+
+    let exec = jvm.heap.get_obj_instance(exec_ref)?;
+    let classname = class_to_dotclass(exec.get_classname());
+
+    match exec.get_field("detailMessage")?.as_ref()? {
+      0 => eprintln!("Exception in thread \"main\" {}", classname),
+      message => eprintln!(
+        "Exception in thread \"main\" {}: {}",
+        classname,
+        jvm.heap.get_string(message)?
+      ),
+    }
+
+    Ok(())
+  })
+}
+
+extern "C" fn exception_clear(env: Env) {
+  with_jvm(env, (), |jvm| {
+    jvm.jnimanager.take_pending();
+
+    Ok(())
+  })
+}
+
+extern "C" fn fatal_error(_env: Env, message: *const c_char) {
+  let message = unsafe { jni::read_c_string(message) }.unwrap_or_default();
+
+  error!("FATAL ERROR in native method: {}", message);
+
+  std::process::abort();
+}
+
+extern "C" fn push_local_frame(env: Env, _capacity: i32) -> i32 {
+  with_jvm(env, JNI_ERR, |jvm| {
+    jvm.jnimanager.push_frame();
+
+    Ok(JNI_OK)
+  })
+}
+
+extern "C" fn pop_local_frame(env: Env, result: Handle) -> Handle {
+  with_jvm(env, 0, |jvm| {
+    let result = jvm.get_jni_object(result)?.as_ref()?;
+
+    jvm.jnimanager.pop_frame();
+
+    Ok(jvm.jnimanager.new_local(result))
+  })
+}
+
+extern "C" fn new_global_ref(env: Env, obj: Handle) -> Handle {
+  with_jvm(env, 0, |jvm| {
+    let obj_ref = jvm.get_jni_object(obj)?.as_ref()?;
+
+    Ok(jvm.jnimanager.new_global(obj_ref))
+  })
+}
+
+extern "C" fn delete_global_ref(env: Env, obj: Handle) {
+  with_jvm(env, (), |jvm| {
+    jvm.jnimanager.delete_global(obj as ju4);
+
+    Ok(())
+  })
+}
+
+extern "C" fn delete_local_ref(env: Env, obj: Handle) {
+  with_jvm(env, (), |jvm| {
+    jvm.jnimanager.delete_local(obj as ju4);
+
+    Ok(())
+  })
+}
+
+extern "C" fn is_same_object(_env: Env, first: Handle, second: Handle) -> u8 {
+  (first == second) as u8
+}
+
+extern "C" fn new_local_ref(env: Env, obj: Handle) -> Handle {
+  with_jvm(env, 0, |jvm| {
+    let obj_ref = jvm.get_jni_object(obj)?.as_ref()?;
+
+    Ok(jvm.jnimanager.new_local(obj_ref))
+  })
+}
+
+extern "C" fn ensure_local_capacity(_env: Env, _capacity: i32) -> i32 {
+  JNI_OK
+}
+
+extern "C" fn alloc_object(env: Env, class: Handle) -> Handle {
+  with_jvm(env, 0, |jvm| {
+    let classname = jvm.get_jni_classname(class)?;
+
+    let class = jvm.class_loader.get(&classname)?;
+    let is_abstract = class.is_interface() || class.get_access_flags() & 0x0400 != 0;
+    drop(class);
+
+    if is_abstract {
+      return Err(eyre!(errors::JavaException::InstantiationException(
+        class_to_dotclass(&classname)
+      )));
+    }
+
+    jvm.init_class(&classname)?;
+
+    let obj = jvm.heap.alloc_obj(&mut jvm.class_loader, &classname)?;
+
+    jvm.new_jni_local(obj)
+  })
+}
+
+extern "C" fn new_object_v(env: Env, class: Handle, id: Id, args: *mut VaList) -> Handle {
+  with_jvm(env, 0, |jvm| {
+    jvm.new_jni_object(class, id, Arguments::VaList(args))
+  })
+}
+
+extern "C" fn new_object_a(env: Env, class: Handle, id: Id, args: *const u64) -> Handle {
+  with_jvm(env, 0, |jvm| {
+    jvm.new_jni_object(class, id, Arguments::Values(args))
+  })
+}
+
+extern "C" fn get_object_class(env: Env, obj: Handle) -> Handle {
+  with_jvm(env, 0, |jvm| {
+    let obj_ref = jvm.get_jni_ref(obj)?;
+    let classname = jvm.heap.get_instance(obj_ref)?.get_classname().to_string();
+
+    let class = jvm
+      .heap
+      .alloc_class_obj(&mut jvm.class_loader, &classname)?;
+
+    jvm.new_jni_local(class)
+  })
+}
+
+extern "C" fn is_instance_of(env: Env, obj: Handle, class: Handle) -> u8 {
+  with_jvm(env, 0, |jvm| {
+    if obj == 0 {
+      return Ok(1);
+    }
+
+    let obj_ref = jvm.get_jni_ref(obj)?;
+    let obj_classname = jvm.heap.get_instance(obj_ref)?.get_classname().to_string();
+    let classname = jvm.get_jni_classname(class)?;
+
+    Ok(is_assignable(&mut jvm.class_loader, &classname, &obj_classname)? as u8)
+  })
+}
+
+extern "C" fn get_method_id(
+  env: Env,
+  class: Handle,
+  name: *const c_char,
+  descriptor: *const c_char,
+) -> Id {
+  with_jvm(env, std::ptr::null(), |jvm| {
+    jvm.get_jni_method(class, name, descriptor, false)
+  })
+}
+
+fn call_method<R: Raw>(env: Env, receiver: Receiver, id: Id, args: Arguments) -> R {
+  R::from_raw(with_jvm(env, 0, |jvm| {
+    jvm.call_jni_method(receiver, id, args)
+  }))
+}
+
+extern "C" fn call_method_v<R: Raw>(env: Env, obj: Handle, id: Id, args: *mut VaList) -> R {
+  call_method(env, Receiver::Virtual(obj), id, Arguments::VaList(args))
+}
+
+extern "C" fn call_method_a<R: Raw>(env: Env, obj: Handle, id: Id, args: *const u64) -> R {
+  call_method(env, Receiver::Virtual(obj), id, Arguments::Values(args))
+}
+
+extern "C" fn call_nonvirtual_method_v<R: Raw>(
+  env: Env,
+  obj: Handle,
+  class: Handle,
+  id: Id,
+  args: *mut VaList,
+) -> R {
+  call_method(
+    env,
+    Receiver::Nonvirtual(obj, class),
+    id,
+    Arguments::VaList(args),
+  )
+}
+
+extern "C" fn call_nonvirtual_method_a<R: Raw>(
+  env: Env,
+  obj: Handle,
+  class: Handle,
+  id: Id,
+  args: *const u64,
+) -> R {
+  call_method(
+    env,
+    Receiver::Nonvirtual(obj, class),
+    id,
+    Arguments::Values(args),
+  )
+}
+
+extern "C" fn call_static_method_v<R: Raw>(
+  env: Env,
+  _class: Handle,
+  id: Id,
+  args: *mut VaList,
+) -> R {
+  call_method(env, Receiver::Static, id, Arguments::VaList(args))
+}
+
+extern "C" fn call_static_method_a<R: Raw>(
+  env: Env,
+  _class: Handle,
+  id: Id,
+  args: *const u64,
+) -> R {
+  call_method(env, Receiver::Static, id, Arguments::Values(args))
+}
+
+extern "C" fn get_field_id(
+  env: Env,
+  class: Handle,
+  name: *const c_char,
+  descriptor: *const c_char,
+) -> Id {
+  with_jvm(env, std::ptr::null(), |jvm| {
+    jvm.get_jni_field(class, name, descriptor, false)
+  })
+}
+
+extern "C" fn get_field<R: Raw>(env: Env, obj: Handle, id: Id) -> R {
+  R::from_raw(with_jvm(env, 0, |jvm| {
+    let member = get_member(id)?;
+    let obj_ref = jvm.get_jni_ref(obj)?;
+
+    let value = jvm
+      .heap
+      .get_obj_instance(obj_ref)?
+      .get_field(&member.name)?;
+
+    jvm.get_jni_raw(value)
+  }))
+}
+
+extern "C" fn set_field<V: Raw>(env: Env, obj: Handle, id: Id, value: V) {
+  with_jvm(env, (), |jvm| {
+    let member = get_member(id)?;
+    let obj_ref = jvm.get_jni_ref(obj)?;
+
+    let value = jvm.get_jni_value(&member.descriptor, value.to_raw())?;
+    let value = types::Type::to_stored_value(&member.descriptor, value)?;
+
+    jvm
+      .heap
+      .get_obj_instance_mut(obj_ref)?
+      .put_field(&member.name, value)
+  })
+}
+
+extern "C" fn get_static_method_id(
+  env: Env,
+  class: Handle,
+  name: *const c_char,
+  descriptor: *const c_char,
+) -> Id {
+  with_jvm(env, std::ptr::null(), |jvm| {
+    jvm.get_jni_method(class, name, descriptor, true)
+  })
+}
+
+extern "C" fn get_static_field_id(
+  env: Env,
+  class: Handle,
+  name: *const c_char,
+  descriptor: *const c_char,
+) -> Id {
+  with_jvm(env, std::ptr::null(), |jvm| {
+    jvm.get_jni_field(class, name, descriptor, true)
+  })
+}
+
+extern "C" fn get_static_field<R: Raw>(env: Env, _class: Handle, id: Id) -> R {
+  R::from_raw(with_jvm(env, 0, |jvm| {
+    let member = get_member(id)?;
+
+    jvm.init_class(&member.classname)?;
+
+    let value = jvm
+      .class_loader
+      .get_static_field(&member.classname, &member.name)?;
+
+    jvm.get_jni_raw(value)
+  }))
+}
+
+extern "C" fn set_static_field<V: Raw>(env: Env, _class: Handle, id: Id, value: V) {
+  with_jvm(env, (), |jvm| {
+    let member = get_member(id)?;
+
+    jvm.init_class(&member.classname)?;
+
+    let value = jvm.get_jni_value(&member.descriptor, value.to_raw())?;
+    let value = types::Type::to_stored_value(&member.descriptor, value)?;
+
+    jvm
+      .class_loader
+      .get_mut(&member.classname)?
+      .put_static_field(&member.name, value)
+  })
+}
+
+extern "C" fn new_string(env: Env, chars: *const u16, length: i32) -> Handle {
+  with_jvm(env, 0, |jvm| {
+    let units = match length {
+      0 => &[][..],
+      _ => unsafe { std::slice::from_raw_parts(chars, length.max(0) as usize) },
+    };
+
+    let string = jvm
+      .heap
+      .alloc_string(&mut jvm.class_loader, &String::from_utf16_lossy(units))?;
+
+    jvm.new_jni_local(string)
+  })
+}
+
+extern "C" fn get_string_length(env: Env, string: Handle) -> i32 {
+  with_jvm(env, 0, |jvm| Ok(jvm.get_jni_string(string)?.len() as i32))
+}
+
+extern "C" fn get_string_chars(env: Env, string: Handle, is_copy: *mut u8) -> *const u16 {
+  with_jvm(env, std::ptr::null(), |jvm| {
+    let units = jvm.get_jni_string(string)?;
+
+    let bytes: Vec<u8> = units.iter().flat_map(|unit| unit.to_ne_bytes()).collect();
+
+    set_is_copy(is_copy);
+
+    Ok(copy_to_c(&bytes) as *const u16)
+  })
+}
+
+extern "C" fn release_string_chars(_env: Env, _string: Handle, chars: *const u16) {
+  unsafe {
+    libc::free(chars as *mut c_void);
+  }
+}
+
+extern "C" fn new_string_utf(env: Env, bytes: *const c_char) -> Handle {
+  with_jvm(env, 0, |jvm| {
+    if bytes.is_null() {
+      return Ok(0);
+    }
+
+    let value = unsafe { jni::read_c_string(bytes)? };
+
+    let string = jvm.heap.alloc_string(&mut jvm.class_loader, &value)?;
+
+    jvm.new_jni_local(string)
+  })
+}
+
+extern "C" fn get_string_utf_length(env: Env, string: Handle) -> i32 {
+  with_jvm(env, 0, |jvm| {
+    let units = jvm.get_jni_string(string)?;
+
+    Ok(to_modified_utf8(&units).len() as i32)
+  })
+}
+
+extern "C" fn get_string_utf_chars(env: Env, string: Handle, is_copy: *mut u8) -> *const c_char {
+  with_jvm(env, std::ptr::null(), |jvm| {
+    let mut bytes = to_modified_utf8(&jvm.get_jni_string(string)?);
+    bytes.push(0);
+
+    set_is_copy(is_copy);
+
+    Ok(copy_to_c(&bytes) as *const c_char)
+  })
+}
+
+extern "C" fn release_string_utf_chars(_env: Env, _string: Handle, chars: *const c_char) {
+  unsafe {
+    libc::free(chars as *mut c_void);
+  }
+}
+
+extern "C" fn get_array_length(env: Env, array: Handle) -> i32 {
+  with_jvm(env, 0, |jvm| {
+    let array_ref = jvm.get_jni_ref(array)?;
+
+    Ok(jvm.heap.get_array_instance(array_ref)?.len() as i32)
+  })
+}
+
+extern "C" fn new_object_array(env: Env, length: i32, class: Handle, initial: Handle) -> Handle {
+  with_jvm(env, 0, |jvm| {
+    let classname = jvm.get_jni_classname(class)?;
+    let initial = jvm.get_jni_object(initial)?;
+
+    let Ok(length) = usize::try_from(length) else {
+      return Err(eyre!(jvm.new_throwable(
+        "java/lang/NegativeArraySizeException",
+        Some(&length.to_string())
+      )?));
+    };
+
+    let array = jvm
+      .heap
+      .alloc_array(&classname, vec![initial; length], length)?;
+
+    jvm.new_jni_local(array)
+  })
+}
+
+extern "C" fn get_object_array_element(env: Env, array: Handle, index: i32) -> Handle {
+  with_jvm(env, 0, |jvm| {
+    let (array_ref, index, _) = jvm.get_jni_region(array, index, Some(1))?;
+
+    let element = *jvm.heap.get_array_instance(array_ref)?.get(index)?;
+
+    jvm.new_jni_local(element)
+  })
+}
+
+extern "C" fn set_object_array_element(env: Env, array: Handle, index: i32, value: Handle) {
+  with_jvm(env, (), |jvm| {
+    let (array_ref, index, _) = jvm.get_jni_region(array, index, Some(1))?;
+    let value = jvm.get_jni_object(value)?;
+
+    if value != types::Type::Null {
+      let classname = jvm
+        .heap
+        .get_instance(value.as_ref()?)?
+        .get_classname()
+        .to_string();
+      let element_classname = jvm
+        .heap
+        .get_array_instance(array_ref)?
+        .get_element_classname()
+        .to_string();
+
+      if !is_assignable(&mut jvm.class_loader, &element_classname, &classname)? {
+        return Err(eyre!(errors::JavaException::ArrayStoreException(
+          class_to_dotclass(&classname)
+        )));
+      }
+    }
+
+    jvm
+      .heap
+      .get_array_instance_mut(array_ref)?
+      .set(index, value)
+  })
+}
+
+extern "C" fn new_array<const DESCRIPTOR: char>(env: Env, length: i32) -> Handle {
+  with_jvm(env, 0, |jvm| {
+    let Ok(length) = usize::try_from(length) else {
+      return Err(eyre!(jvm.new_throwable(
+        "java/lang/NegativeArraySizeException",
+        Some(&length.to_string())
+      )?));
+    };
+
+    let array = jvm
+      .heap
+      .alloc_array_primitive(&DESCRIPTOR.to_string(), vec![], length)?;
+
+    jvm.new_jni_local(array)
+  })
+}
+
+extern "C" fn get_array_elements(env: Env, array: Handle, is_copy: *mut u8) -> *mut c_void {
+  with_jvm(env, std::ptr::null_mut(), |jvm| {
+    let bytes = jvm.get_jni_elements(array, 0, None)?;
+
+    set_is_copy(is_copy);
+
+    Ok(copy_to_c(&bytes))
+  })
+}
+
+extern "C" fn release_array_elements(env: Env, array: Handle, elements: *mut c_void, mode: i32) {
+  with_jvm(env, (), |jvm| {
+    if mode != JNI_ABORT {
+      jvm.set_jni_elements(array, 0, None, elements as *const u8)?;
+    }
+
+    if mode != JNI_COMMIT {
+      unsafe {
+        libc::free(elements);
+      }
+    }
+
+    Ok(())
+  })
+}
+
+extern "C" fn get_array_region(env: Env, array: Handle, start: i32, length: i32, buffer: *mut u8) {
+  with_jvm(env, (), |jvm| {
+    let bytes = jvm.get_jni_elements(array, start, Some(length))?;
+
+    unsafe {
+      std::ptr::copy_nonoverlapping(bytes.as_ptr(), buffer, bytes.len());
+    }
+
+    Ok(())
+  })
+}
+
+extern "C" fn set_array_region(
+  env: Env,
+  array: Handle,
+  start: i32,
+  length: i32,
+  buffer: *const u8,
+) {
+  with_jvm(env, (), |jvm| {
+    jvm.set_jni_elements(array, start, Some(length), buffer)
+  })
+}
+
+extern "C" fn register_natives(
+  env: Env,
+  class: Handle,
+  methods: *const NativeMethod,
+  count: i32,
+) -> i32 {
+  with_jvm(env, JNI_ERR, |jvm| {
+    let classname = jvm.get_jni_classname(class)?;
+
+    for i in 0..count.max(0) as usize {
+      let method = unsafe { &*methods.add(i) };
+      let (name, descriptor) = unsafe {
+        (
+          jni::read_c_string(method.name)?,
+          jni::read_c_string(method.signature)?,
+        )
+      };
+
+      let is_native = jvm
+        .class_loader
+        .get_method_by_name(&classname, &name, &descriptor)
+        .is_ok_and(|(_, method)| method.is_native());
+
+      if !is_native || method.function.is_null() {
+        let message = format!(
+          "Method '{}{}' name or signature does not match",
+          name, descriptor
+        );
+
+        return Err(eyre!(
+          jvm.new_throwable("java/lang/NoSuchMethodError", Some(&message))?
+        ));
+      }
+
+      debug!("RegisterNatives {}.{}{}", classname, name, descriptor);
+
+      jvm
+        .jnimanager
+        .register_native(&classname, &name, &descriptor, method.function as u64);
+    }
+
+    Ok(JNI_OK)
+  })
+}
+
+extern "C" fn unregister_natives(env: Env, class: Handle) -> i32 {
+  with_jvm(env, JNI_ERR, |jvm| {
+    let classname = jvm.get_jni_classname(class)?;
+
+    jvm.jnimanager.unregister_natives(&classname);
+
+    Ok(JNI_OK)
+  })
+}
+
+// a single thread runs, it owns all the monitors
+extern "C" fn monitor_enter(env: Env, obj: Handle) -> i32 {
+  with_jvm(env, JNI_ERR, |jvm| {
+    jvm.get_jni_ref(obj)?;
+
+    Ok(JNI_OK)
+  })
+}
+
+extern "C" fn monitor_exit(env: Env, obj: Handle) -> i32 {
+  with_jvm(env, JNI_ERR, |jvm| {
+    jvm.get_jni_ref(obj)?;
+
+    Ok(JNI_OK)
+  })
+}
+
+extern "C" fn get_java_vm(env: Env, vm: *mut *mut Interface) -> i32 {
+  with_jvm(env, JNI_ERR, |jvm| {
+    unsafe {
+      *vm = jvm.get_java_vm();
+    }
+
+    Ok(JNI_OK)
+  })
+}
+
+extern "C" fn get_string_region(
+  env: Env,
+  string: Handle,
+  start: i32,
+  length: i32,
+  buffer: *mut u16,
+) {
+  with_jvm(env, (), |jvm| {
+    let units = jvm.get_jni_string_region(string, start, length)?;
+
+    unsafe {
+      std::ptr::copy_nonoverlapping(units.as_ptr(), buffer, units.len());
+    }
+
+    Ok(())
+  })
+}
+
+extern "C" fn get_string_utf_region(
+  env: Env,
+  string: Handle,
+  start: i32,
+  length: i32,
+  buffer: *mut c_char,
+) {
+  with_jvm(env, (), |jvm| {
+    let mut bytes = to_modified_utf8(&jvm.get_jni_string_region(string, start, length)?);
+    bytes.push(0);
+
+    unsafe {
+      std::ptr::copy_nonoverlapping(bytes.as_ptr(), buffer.cast::<u8>(), bytes.len());
+    }
+
+    Ok(())
+  })
+}
+
+extern "C" fn new_weak_global_ref(env: Env, obj: Handle) -> Handle {
+  with_jvm(env, 0, |jvm| {
+    let obj_ref = jvm.get_jni_object(obj)?.as_ref()?;
+
+    Ok(jvm.jnimanager.new_weak_global(obj_ref))
+  })
+}
+
+extern "C" fn delete_weak_global_ref(env: Env, obj: Handle) {
+  with_jvm(env, (), |jvm| {
+    jvm.jnimanager.delete_weak_global(obj as ju4);
+
+    Ok(())
+  })
+}
+
+extern "C" fn exception_check(env: Env) -> u8 {
+  with_jvm(env, 0, |jvm| {
+    Ok(jvm.jnimanager.get_pending().is_some() as u8)
+  })
+}
+
+// JDK 17 gives an `int` capacity to the constructor of the buffers of the JNI
+extern "C" fn new_direct_byte_buffer(env: Env, address: *mut c_void, capacity: i64) -> Handle {
+  with_jvm(env, 0, |jvm| {
+    const BUFFER: &str = "java/nio/DirectByteBuffer";
+
+    jvm.init_class(BUFFER)?;
+
+    let buffer = jvm.heap.alloc_obj(&mut jvm.class_loader, BUFFER)?;
+    let address = types::Type::Long(address as i64);
+
+    let (descriptor, capacity) = match jvm
+      .class_loader
+      .get_method_by_name(BUFFER, "<init>", "(JJ)V")
+    {
+      Ok(_) => ("(JJ)V", vec![types::Type::Long(capacity); 2]),
+      Err(_) => ("(JI)V", vec![types::Type::Integer(capacity as i32)]),
+    };
+
+    let mut args = vec![buffer, address, address];
+    args.extend(capacity);
+
+    jvm.call_and_resolve_method(BUFFER, "<init>", descriptor, args)?;
+
+    jvm.new_jni_local(buffer)
+  })
+}
+
+// the address of a direct buffer, 0 for the others
+fn get_direct_buffer(jvm: &mut JVM, buffer: Handle) -> Result<Option<(i64, i64)>> {
+  let buffer_ref = jvm.get_jni_ref(buffer)?;
+  let classname = jvm
+    .heap
+    .get_instance(buffer_ref)?
+    .get_classname()
+    .to_string();
+
+  if !is_assignable(&mut jvm.class_loader, "java/nio/Buffer", &classname)? {
+    return Ok(None);
+  }
+
+  let buffer = jvm.heap.get_obj_instance(buffer_ref)?;
+  let address = buffer.get_field("address")?.as_long()?;
+
+  if address == 0 {
+    return Ok(None);
+  }
+
+  Ok(Some((
+    address,
+    buffer.get_field("capacity")?.as_integer()? as i64,
+  )))
+}
+
+extern "C" fn get_direct_buffer_address(env: Env, buffer: Handle) -> *mut c_void {
+  with_jvm(env, std::ptr::null_mut(), |jvm| {
+    let direct = get_direct_buffer(jvm, buffer)?;
+
+    Ok(direct.map_or(0, |(address, _)| address) as *mut c_void)
+  })
+}
+
+extern "C" fn get_direct_buffer_capacity(env: Env, buffer: Handle) -> i64 {
+  with_jvm(env, -1, |jvm| {
+    let direct = get_direct_buffer(jvm, buffer)?;
+
+    Ok(direct.map_or(-1, |(_, capacity)| capacity))
+  })
+}
+
+extern "C" fn get_object_ref_type(env: Env, obj: Handle) -> i32 {
+  with_jvm(env, jni::JNI_INVALID_REF_TYPE, |jvm| {
+    Ok(jvm.jnimanager.get_ref_type(obj as ju4))
+  })
+}
+
+extern "C" fn get_module(env: Env, class: Handle) -> Handle {
+  with_jvm(env, 0, |jvm| {
+    let class_ref = jvm.get_jni_ref(class)?;
+
+    let module = jvm.heap.get_obj_instance(class_ref)?.get_field("module")?;
+
+    jvm.new_jni_local(module)
+  })
+}
+
+// the VM does not run virtual threads
+extern "C" fn is_virtual_thread(_env: Env, _obj: Handle) -> u8 {
+  0
+}
+
+// the functions with a variable number of arguments: an entry point per function, giving the
+// function of the `va_list` to call and the size of the named arguments before the `...`
+macro_rules! variadic_functions {
+  ($($entry:ident($named:literal) => $function:path;)*) => {
+    #[cfg(target_arch = "x86_64")]
+    extern "C" {
+      $(fn $entry();)*
+    }
+
+    // the JNI libraries are only loaded on x86-64, nothing calls these entry points
+    $(
+      #[cfg(not(target_arch = "x86_64"))]
+      extern "C" fn $entry() {
+        error!("{} called on {}", stringify!($entry), std::env::consts::ARCH);
+        std::process::abort();
+      }
+    )*
+
+    #[cfg(target_arch = "x86_64")]
+    global_asm!(
+      ".text",
+      $(
+        ".p2align 4",
+        concat!(".globl ", stringify!($entry)),
+        concat!(stringify!($entry), ":"),
+        concat!("lea r11, [rip + {", stringify!($entry), "}]"),
+        concat!("mov r10d, ", stringify!($named), " * 8"),
+        "jmp jvmcraft_jni_varargs",
+      )*
+      // the registers are saved in a `va_list` given after the named arguments (in rcx or
+      // in r8), the stack arguments follow the return address
+      ".p2align 4",
+      "jvmcraft_jni_varargs:",
+      "push rbp",
+      "mov rbp, rsp",
+      "sub rsp, 208",
+      "mov [rsp], rdi",
+      "mov [rsp + 8], rsi",
+      "mov [rsp + 16], rdx",
+      "mov [rsp + 24], rcx",
+      "mov [rsp + 32], r8",
+      "mov [rsp + 40], r9",
+      "movaps [rsp + 48], xmm0",
+      "movaps [rsp + 64], xmm1",
+      "movaps [rsp + 80], xmm2",
+      "movaps [rsp + 96], xmm3",
+      "movaps [rsp + 112], xmm4",
+      "movaps [rsp + 128], xmm5",
+      "movaps [rsp + 144], xmm6",
+      "movaps [rsp + 160], xmm7",
+      "mov dword ptr [rsp + 176], r10d",
+      "mov dword ptr [rsp + 180], 48",
+      "lea rax, [rbp + 16]",
+      "mov [rsp + 184], rax",
+      "mov [rsp + 192], rsp",
+      "lea r8, [rsp + 176]",
+      "cmp r10d, 24",
+      "jne 2f",
+      "mov rcx, r8",
+      "2:",
+      "call r11",
+      "leave",
+      "ret",
+      $($entry = sym $function,)*
+    );
+  };
+}
+
+variadic_functions! {
+  jvmcraft_jni_new_object(3) => new_object_v;
+  jvmcraft_jni_call_method(3) => call_method_v::<u64>;
+  jvmcraft_jni_call_float_method(3) => call_method_v::<f32>;
+  jvmcraft_jni_call_double_method(3) => call_method_v::<f64>;
+  jvmcraft_jni_call_nonvirtual_method(4) => call_nonvirtual_method_v::<u64>;
+  jvmcraft_jni_call_nonvirtual_float_method(4) => call_nonvirtual_method_v::<f32>;
+  jvmcraft_jni_call_nonvirtual_double_method(4) => call_nonvirtual_method_v::<f64>;
+  jvmcraft_jni_call_static_method(3) => call_static_method_v::<u64>;
+  jvmcraft_jni_call_static_float_method(3) => call_static_method_v::<f32>;
+  jvmcraft_jni_call_static_double_method(3) => call_static_method_v::<f64>;
+}
+
+// the functions of the 10 result types of a kind of call (`Object` to `Void`): the `...`,
+// `V` and `A` variants of each one
+macro_rules! call_functions {
+  ($variadic:ident, $variadic_float:ident, $variadic_double:ident, $v:ident, $a:ident) => {{
+    let mut functions = vec![];
+
+    for (variadic, v, a) in [
+      (
+        $variadic as *const c_void,
+        $v::<u64> as *const c_void,
+        $a::<u64> as *const c_void,
+      ),
+      (
+        $variadic_float as *const c_void,
+        $v::<f32> as *const c_void,
+        $a::<f32> as *const c_void,
+      ),
+      (
+        $variadic_double as *const c_void,
+        $v::<f64> as *const c_void,
+        $a::<f64> as *const c_void,
+      ),
+    ]
+    .iter()
+    .enumerate()
+    .flat_map(|(kind, functions)| {
+      // Object to Long, Float, Double, Void
+      let count = match kind {
+        0 => 7,
+        _ => 1,
+      };
+
+      std::iter::repeat(*functions).take(count)
+    }) {
+      functions.extend([variadic, v, a]);
+    }
+
+    // Void returns like the integers
+    functions.extend(functions[..3].to_vec());
+
+    functions
+  }};
+}
+
+// the field accessors of the 9 types (`Object` to `Double`)
+macro_rules! field_functions {
+  ($function:ident) => {
+    [
+      $function::<u64> as *const c_void,
+      $function::<u64> as *const c_void,
+      $function::<u64> as *const c_void,
+      $function::<u64> as *const c_void,
+      $function::<u64> as *const c_void,
+      $function::<u64> as *const c_void,
+      $function::<u64> as *const c_void,
+      $function::<f32> as *const c_void,
+      $function::<f64> as *const c_void,
+    ]
+  };
+}
+
+/// The function table of a `JNIEnv`, in the order of `struct JNINativeInterface_`
+pub(crate) fn get_functions() -> Vec<*const c_void> {
+  let mut functions: Vec<*const c_void> = vec![std::ptr::null(); 4];
+
+  functions.extend([
+    get_version as *const c_void,
+    define_class as *const c_void,
+    find_class as *const c_void,
+    from_reflected_method as *const c_void,
+    from_reflected_field as *const c_void,
+    to_reflected_method as *const c_void,
+    get_superclass as *const c_void,
+    is_assignable_from as *const c_void,
+    to_reflected_field as *const c_void,
+    throw as *const c_void,
+    throw_new as *const c_void,
+    exception_occurred as *const c_void,
+    exception_describe as *const c_void,
+    exception_clear as *const c_void,
+    fatal_error as *const c_void,
+    push_local_frame as *const c_void,
+    pop_local_frame as *const c_void,
+    new_global_ref as *const c_void,
+    delete_global_ref as *const c_void,
+    delete_local_ref as *const c_void,
+    is_same_object as *const c_void,
+    new_local_ref as *const c_void,
+    ensure_local_capacity as *const c_void,
+    alloc_object as *const c_void,
+    jvmcraft_jni_new_object as *const c_void,
+    new_object_v as *const c_void,
+    new_object_a as *const c_void,
+    get_object_class as *const c_void,
+    is_instance_of as *const c_void,
+    get_method_id as *const c_void,
+  ]);
+
+  functions.extend(call_functions!(
+    jvmcraft_jni_call_method,
+    jvmcraft_jni_call_float_method,
+    jvmcraft_jni_call_double_method,
+    call_method_v,
+    call_method_a
+  ));
+  functions.extend(call_functions!(
+    jvmcraft_jni_call_nonvirtual_method,
+    jvmcraft_jni_call_nonvirtual_float_method,
+    jvmcraft_jni_call_nonvirtual_double_method,
+    call_nonvirtual_method_v,
+    call_nonvirtual_method_a
+  ));
+
+  functions.push(get_field_id as *const c_void);
+  functions.extend(field_functions!(get_field));
+  functions.extend(field_functions!(set_field));
+
+  functions.push(get_static_method_id as *const c_void);
+  functions.extend(call_functions!(
+    jvmcraft_jni_call_static_method,
+    jvmcraft_jni_call_static_float_method,
+    jvmcraft_jni_call_static_double_method,
+    call_static_method_v,
+    call_static_method_a
+  ));
+
+  functions.push(get_static_field_id as *const c_void);
+  functions.extend(field_functions!(get_static_field));
+  functions.extend(field_functions!(set_static_field));
+
+  functions.extend([
+    new_string as *const c_void,
+    get_string_length as *const c_void,
+    get_string_chars as *const c_void,
+    release_string_chars as *const c_void,
+    new_string_utf as *const c_void,
+    get_string_utf_length as *const c_void,
+    get_string_utf_chars as *const c_void,
+    release_string_utf_chars as *const c_void,
+    get_array_length as *const c_void,
+    new_object_array as *const c_void,
+    get_object_array_element as *const c_void,
+    set_object_array_element as *const c_void,
+    new_array::<'Z'> as *const c_void,
+    new_array::<'B'> as *const c_void,
+    new_array::<'C'> as *const c_void,
+    new_array::<'S'> as *const c_void,
+    new_array::<'I'> as *const c_void,
+    new_array::<'J'> as *const c_void,
+    new_array::<'F'> as *const c_void,
+    new_array::<'D'> as *const c_void,
+  ]);
+
+  // the elements and the regions of the 8 primitive types
+  for function in [
+    get_array_elements as *const c_void,
+    release_array_elements as *const c_void,
+    get_array_region as *const c_void,
+    set_array_region as *const c_void,
+  ] {
+    functions.extend([function; 8]);
+  }
+
+  functions.extend([
+    register_natives as *const c_void,
+    unregister_natives as *const c_void,
+    monitor_enter as *const c_void,
+    monitor_exit as *const c_void,
+    get_java_vm as *const c_void,
+    get_string_region as *const c_void,
+    get_string_utf_region as *const c_void,
+    get_array_elements as *const c_void,
+    release_array_elements as *const c_void,
+    get_string_chars as *const c_void,
+    release_string_chars as *const c_void,
+    new_weak_global_ref as *const c_void,
+    delete_weak_global_ref as *const c_void,
+    exception_check as *const c_void,
+    new_direct_byte_buffer as *const c_void,
+    get_direct_buffer_address as *const c_void,
+    get_direct_buffer_capacity as *const c_void,
+    get_object_ref_type as *const c_void,
+    get_module as *const c_void,
+    is_virtual_thread as *const c_void,
+  ]);
+
+  functions
+}
@@ -4,12 +4,17 @@ use crate::{
     constant_pool::{self, CpInfoInfoEnum},
     loader::ClassLoader,
   },
+  native::registry::NativeRegistry,
   notimpl,
   runtime::{
-    errors, frame, heap,
+    errors,
+    foreign::ForeignManager,
+    frame, heap,
+    jni::JniManager,
     lambdamanager::LambdaManager,
     nativememory::NativeMemory,
     opcode,
+    stdio::Stdio,
     types::{self},
   },
   utils::*,
@@ -19,6 +24,7 @@ use core::panic;
 use log::{debug, error, info, warn};
 use std::{
   borrow::BorrowMut,
+  collections::HashMap,
   sync::{Arc, Mutex, RwLockReadGuard},
 };
 use tracing_subscriber::{filter, reload, Registry};
@@ -31,12 +37,35 @@ pub struct JVM {
   pub(crate) class_loader: ClassLoader,
   pub(crate) nativememory: NativeMemory,
   pub(crate) lambdamanager: LambdaManager,
+  pub(crate) foreignmanager: ForeignManager,
+  pub(crate) jnimanager: JniManager,
+  pub(crate) natives: NativeRegistry,
 
   pub(crate) counter: u64, // number of instructions executed
 
+  // verify the code of the classes before running it (-Xverify)
+  pub(crate) verify: bool,
+
+  // system properties given at creation (-D), added to the ones of the VM
+  pub(crate) properties: Vec<(String, String)>,
+
+  // depth of the Java stack (-Xss), unbounded if None
+  pub(crate) max_frames: Option<usize>,
+
+  // folder of the java modules (JMODS by default)
+  pub(crate) module_path: String,
+
+  // standard streams of the Java code
+  pub(crate) stdio: Stdio,
+
+  // objects kept alive by the `Global` handles of the embedder, with their count
+  pub(crate) globals: Arc<Mutex<HashMap<ju4, usize>>>,
+  // object of the last exception not caught by the Java code, for the `JavaError`
+  pub(crate) uncaught_exception: Option<ju4>,
+
   // Threads
   current_thread: types::Type,
-  next_thread_id: i64,
+  pub(crate) next_thread_id: i64,
 
   // Boot Loader
   boot_loader_unnamed_module: Option<ju4>,
@@ -45,27 +74,42 @@ pub struct JVM {
 }
 
 impl JVM {
-  #[allow(dead_code)]
-  pub(crate) fn mock(user_dir: &str, class_names: Vec<String>, bootstrap: bool) -> Result<JVM> {
+  // a VM not bootstrapped yet
+  pub(crate) fn new(class_names: Vec<String>) -> Result<JVM> {
     let mut classes = ClassLoader::new();
 
     for class_name in class_names {
       classes.add(class_name)?;
     }
 
-    let mut jvm = JVM {
+    Ok(JVM {
       heap: heap::Heap::new(),
       frames: vec![],
       class_loader: classes,
       nativememory: NativeMemory::new(),
       lambdamanager: LambdaManager::new(),
+      foreignmanager: ForeignManager::new(),
+      jnimanager: JniManager::new(),
+      natives: NativeRegistry::new(),
 
       counter: 0,
+      verify: true,
+      properties: vec![],
+      max_frames: None,
+      module_path: get_env("JMODS", ""),
+      stdio: Stdio::default(),
+      globals: Arc::default(),
+      uncaught_exception: None,
       current_thread: types::Type::None,
       next_thread_id: 0,
       boot_loader_unnamed_module: None,
       logging_handle: None,
-    };
+    })
+  }
+
+  #[allow(dead_code)]
+  pub(crate) fn mock(user_dir: &str, class_names: Vec<String>, bootstrap: bool) -> Result<JVM> {
+    let mut jvm = JVM::new(class_names)?;
 
     if bootstrap {
       jvm.bootstrap(user_dir)?;
@@ -77,32 +121,18 @@ impl JVM {
   }
 
   pub fn build(user_dir: &str, class_names: Vec<String>) -> Result<JVM> {
-    let mut classes = ClassLoader::new();
-
-    for class_name in class_names {
-      classes.add(class_name)?;
-    }
-
-    let mut jvm = JVM {
-      heap: heap::Heap::new(),
-      frames: vec![],
-      class_loader: classes,
-      nativememory: NativeMemory::new(),
-      lambdamanager: LambdaManager::new(),
-
-      counter: 0,
-      current_thread: types::Type::None,
-      next_thread_id: 0,
-      boot_loader_unnamed_module: None,
-      logging_handle: None,
-    };
+    let mut jvm = JVM::new(class_names)?;
 
     jvm.bootstrap(user_dir)?;
 
     Ok(jvm)
   }
 
-  pub(crate) fn set_logging_handle(
+  pub fn set_verify(&mut self, verify: bool) {
+    self.verify = verify;
+  }
+
+  pub fn set_logging_handle(
     &mut self,
     handle: Arc<Mutex<reload::Handle<filter::LevelFilter, Registry>>>,
   ) {
@@ -134,6 +164,7 @@ impl JVM {
     Ok(())
   }
 
+  #[allow(dead_code)]
   fn init_phase_3(&mut self) -> Result<()> {
     debug!("[-] Init Phase 3");
 
@@ -167,7 +198,7 @@ impl JVM {
 
     self
       .heap
-      .alloc_primitive_class_obj(&mut self.class_loader, "L")?;
+      .alloc_primitive_class_obj(&mut self.class_loader, "J")?;
 
     self
       .heap
@@ -219,7 +250,7 @@ impl JVM {
     // predicting the object reference
     let module_ref = self.heap.get_curr_obj_ref();
 
-    let path = format!("{}/{}", get_env("JMODS", ""), module_name);
+    let path = format!("{}/{}", self.module_path, module_name);
 
     // MUST load the modules before anything else (used during class resolution !)
     let packages = if module_name == "unnamed" {
@@ -268,7 +299,7 @@ impl JVM {
     self.load_module("jdk.net")
   }
 
-  fn bootstrap(&mut self, user_dir: &str) -> Result<()> {
+  pub(crate) fn bootstrap(&mut self, user_dir: &str) -> Result<()> {
     debug!("[-] Bootstrap");
 
     self.bootstrap_java_base()?;
@@ -307,11 +338,14 @@ impl JVM {
     Ok(())
   }
 
-  fn bootstrap_mock(&mut self, user_dir: &str) -> Result<()> {
+  pub(crate) fn bootstrap_mock(&mut self, user_dir: &str) -> Result<()> {
     debug!("[-] Bootstrap Mock");
 
     self.bootstrap_java_base()?;
 
+    // create unnamed module, must be after java base init
+    self.load_module("unnamed")?;
+
     self.bootstrap_user_module("usermodule", user_dir)?;
 
     Ok(())
@@ -335,7 +369,29 @@ impl JVM {
       .ok_or_eyre(errors::InternalError::FrameNotFound)
   }
 
-  fn create_main_thread(&mut self, name: &str) -> Result<types::Type> {
+  pub(crate) fn create_main_thread(&mut self, name: &str) -> Result<types::Type> {
+    let thread_obj = self.alloc_main_thread()?;
+
+    self.init_main_thread(thread_obj, name)?;
+
+    Ok(thread_obj)
+  }
+
+  // the main thread is the current thread before its constructor runs, like an attached
+  // thread
+  pub(crate) fn alloc_main_thread(&mut self) -> Result<types::Type> {
+    let thread_obj = self
+      .heap
+      .alloc_obj(&mut self.class_loader, "java/lang/Thread")?;
+
+    self.current_thread = thread_obj;
+
+    self.next_thread_id += 1;
+
+    Ok(thread_obj)
+  }
+
+  pub(crate) fn init_main_thread(&mut self, thread_obj: types::Type, name: &str) -> Result<()> {
     let thread_group_obj = self
       .heap
       .alloc_obj(&mut self.class_loader, "java/lang/ThreadGroup")?;
@@ -349,24 +405,6 @@ impl JVM {
 
     let name_string = self.heap.alloc_string(&mut self.class_loader, name)?;
 
-    let thread_obj = self
-      .heap
-      .alloc_obj(&mut self.class_loader, "java/lang/Thread")?;
-
-    self.current_thread = thread_obj;
-
-    self
-      .class_loader
-      .get_mut("java/lang/Thread")?
-      .put_static_field("nextThreadID", types::Type::Long(self.next_thread_id))?;
-
-    self
-      .class_loader
-      .get_mut("java/lang/Thread")?
-      .new_field("nextThreadID", "J")?;
-
-    self.next_thread_id += 1;
-
     self.call_and_resolve_method(
       "java/lang/Thread",
       "<init>",
@@ -374,7 +412,7 @@ impl JVM {
       vec![thread_obj, thread_group_obj, name_string],
     )?;
 
-    Ok(thread_obj)
+    Ok(())
   }
 
   fn alloc_set(&mut self, elements: Vec<types::Type>) -> Result<types::Type> {
@@ -505,6 +543,15 @@ impl JVM {
             // done here
             Ok(None)
           }
+          // faulting off-heap access, raised like HotSpot does for Unsafe
+          errors::InternalError::SegmentationFault(address) => {
+            let exception = errors::JavaException::InternalError(format!(
+              "a fault occurred in an unsafe memory access operation at {}",
+              address
+            ));
+
+            self.handle_step_error(eyre!(exception), stop_at)
+          }
           e => {
             self.show_frames();
             panic!("{}", e)
@@ -513,18 +560,22 @@ impl JVM {
       }
       _ if err.downcast_ref::<errors::JavaException>().is_some() => {
         let exception = err.downcast_ref::<errors::JavaException>().unwrap();
-        let exec_classname = errors::JavaException::convert_java_exception_to_classname(exception);
         // create new exception
-        let exec_ref = self
-          .heap
-          .alloc_obj(&mut self.class_loader, exec_classname)?
-          .as_ref()?;
+        let exec_ref = self.alloc_java_exception(exception)?;
         // and handle it
         let handled = self.handle_java_exception(exec_ref, stop_at)?;
-        if handled.is_none() {
-          Err(err)
-        } else {
-          Ok(handled)
+        if handled.is_some() {
+          return Ok(handled);
+        }
+
+        self.uncaught_exception = Some(exec_ref);
+
+        // an uncaught object thrown by Java code is returned as the exception of its class
+        match exception {
+          errors::JavaException::Throwable(_, exec_ref) => {
+            Err(eyre!(self.java_exception_from_obj(*exec_ref)?))
+          }
+          _ => Err(err),
         }
       }
       _ => {
@@ -535,6 +586,62 @@ impl JVM {
     }
   }
 
+  /// Allocate the Java object of `exception`, with the target of an InvocationTargetException
+  pub(crate) fn alloc_java_exception(&mut self, exception: &errors::JavaException) -> Result<ju4> {
+    let exec_classname = errors::JavaException::convert_java_exception_to_classname(exception);
+
+    // the object is still alive when the exception has no dedicated variant
+    if let errors::JavaException::Throwable(_, exec_ref) = exception {
+      return Ok(*exec_ref);
+    }
+
+    let exec_ref = self
+      .heap
+      .alloc_obj(&mut self.class_loader, exec_classname)?
+      .as_ref()?;
+
+    if let errors::JavaException::InvocationTargetException(target) = exception {
+      let target_ref = self.alloc_java_exception(target)?;
+
+      self
+        .heap
+        .get_obj_instance_mut(exec_ref)?
+        .put_field("target", types::Type::ObjectRef(target_ref))?;
+    }
+
+    Ok(exec_ref)
+  }
+
+  /// Rebuild the `JavaException` raised by the Java exception object `exec_ref`
+  pub(crate) fn java_exception_from_obj(&self, exec_ref: ju4) -> Result<errors::JavaException> {
+    let exec_obj = self.heap.get_obj_instance(exec_ref)?;
+    let exec_classname = exec_obj.get_classname();
+
+    if exec_classname == "java/lang/reflect/InvocationTargetException" {
+      let target_ref = exec_obj.get_field("target")?.as_ref()?;
+
+      return Ok(errors::JavaException::InvocationTargetException(Box::new(
+        self.java_exception_from_obj(target_ref)?,
+      )));
+    }
+
+    let detail_message_ref = exec_obj.get_field("detailMessage")?.as_ref()?;
+    let detail_message = if detail_message_ref == 0 {
+      "No Message".to_string()
+    } else {
+      self.heap.get_string(detail_message_ref)?
+    };
+    debug!("THROWING {} -> '{}'", exec_classname, detail_message);
+
+    Ok(
+      errors::JavaException::convert_classname_to_java_exception(exec_classname, detail_message)
+        .unwrap_or(errors::JavaException::Throwable(
+          exec_classname.to_string(),
+          exec_ref,
+        )),
+    )
+  }
+
   pub fn run(&mut self) -> Result<()> {
     loop {
       match self.step() {
@@ -915,88 +1022,91 @@ impl JVM {
     Ok(None)
   }
 
-  // https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-6.html#jvms-6.5.ldc
-  fn exec_ldc(&mut self) -> Result<Option<types::Type>> {
-    let value: types::Type = {
-      let info = {
-        let index = self.get_current_frame_mut()?.read_ju1()? as ju2;
-
-        let current_class = self.get_current_class()?;
+  /// Load the loadable constant at `index` in the constant pool of `classname`
+  ///
+  /// https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-5.html#jvms-5.1
+  pub(crate) fn load_constant(&mut self, classname: &str, index: ju2) -> Result<types::Type> {
+    let info = self
+      .class_loader
+      .get(classname)?
+      .resolve_index(index)?
+      .get_info()
+      .clone();
+
+    let value = match info {
+      constant_pool::CpInfoInfoEnum::Integer(int) => types::Type::Integer(int.int()),
+      constant_pool::CpInfoInfoEnum::Float(fl) => types::Type::Float(fl.float()),
+      constant_pool::CpInfoInfoEnum::Long(int) => types::Type::Long(int.value() as i64),
+      constant_pool::CpInfoInfoEnum::Double(fl) => types::Type::Double(f64::from_bits(fl.value())),
+      constant_pool::CpInfoInfoEnum::String(st) => {
+        let string = self
+          .class_loader
+          .resolve_string(classname, st.get_string_index())?;
 
-        let item = current_class.resolve_index(index)?;
+        self.heap.alloc_string(&mut self.class_loader, &string)?
+      }
+      constant_pool::CpInfoInfoEnum::Class(cl) => {
+        let class_name = self
+          .class_loader
+          .get(classname)?
+          .resolve_name(cl.get_name_index())?;
 
-        item.get_info().clone()
-      };
+        let class_name = self.resolve_class(classname, &class_name)?;
 
-      match info {
-        constant_pool::CpInfoInfoEnum::Integer(int) => types::Type::Integer(int.int()),
-        constant_pool::CpInfoInfoEnum::Float(fl) => types::Type::Float(fl.float()),
-        constant_pool::CpInfoInfoEnum::String(st) => {
-          let string = self
-            .get_current_class()?
-            .resolve_name(st.get_string_index())?;
+        self
+          .heap
+          .alloc_class_obj(&mut self.class_loader, &class_name)?
+      }
+      constant_pool::CpInfoInfoEnum::MethodHandle(_) => {
+        let method_handle_resolved = self.class_loader.resolve_method_handle(classname, index)?;
 
-          self.heap.alloc_string(&mut self.class_loader, &string)?
-        }
-        constant_pool::CpInfoInfoEnum::Long(_) | constant_pool::CpInfoInfoEnum::Double(_) => {
-          return Err(eyre!("long/double not allowed in LDC"))
-        }
-        constant_pool::CpInfoInfoEnum::Class(cl) => {
-          let class_name = self
-            .get_current_class()?
-            .resolve_name(cl.get_name_index())?;
+        self.create_method_handle(classname, method_handle_resolved)?
+      }
+      constant_pool::CpInfoInfoEnum::MethodType(_) => {
+        let method_type_resolved = self.class_loader.resolve_method_type(classname, index)?;
 
-          self
-            .heap
-            .alloc_class_obj(&mut self.class_loader, &class_name)?
-        }
-        _ => notimpl!(format!("ldc not implemented: {}", info)),
+        self.create_method_type(&method_type_resolved)?
+      }
+      constant_pool::CpInfoInfoEnum::Dynamic(_) => {
+        self.resolve_dynamic_constant(classname, index)?
+      }
+      _ => {
+        return Err(eyre!(errors::InternalError::General(format!(
+          "Not a loadable constant: {}",
+          info
+        ))))
       }
     };
 
-    self.push_stack(value)?;
-
-    Ok(None)
+    Ok(value)
   }
 
-  fn exec_ldcw(&mut self) -> Result<Option<types::Type>> {
-    let value: types::Type = {
-      let info = {
-        // wide index
-        let index = self.get_current_frame_mut()?.read_ju2()?;
+  // https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-6.html#jvms-6.5.ldc
+  fn exec_ldc(&mut self) -> Result<Option<types::Type>> {
+    let index = self.get_current_frame_mut()?.read_ju1()? as ju2;
 
-        let current_class = self.get_current_class()?;
+    self.exec_ldc_index(index)
+  }
 
-        let item = current_class.resolve_index(index)?;
+  fn exec_ldcw(&mut self) -> Result<Option<types::Type>> {
+    // wide index
+    let index = self.get_current_frame_mut()?.read_ju2()?;
 
-        item.get_info().clone()
-      };
+    self.exec_ldc_index(index)
+  }
 
-      match info {
-        constant_pool::CpInfoInfoEnum::Integer(int) => types::Type::Integer(int.int()),
-        constant_pool::CpInfoInfoEnum::Float(fl) => types::Type::Float(fl.float()),
-        constant_pool::CpInfoInfoEnum::String(st) => {
-          let string = self
-            .get_current_class()?
-            .resolve_name(st.get_string_index())?;
+  fn exec_ldc_index(&mut self, index: ju2) -> Result<Option<types::Type>> {
+    let curr_class_name = self.get_current_class()?.get_name().to_owned();
 
-          self.heap.alloc_string(&mut self.class_loader, &string)?
-        }
-        constant_pool::CpInfoInfoEnum::Long(_) | constant_pool::CpInfoInfoEnum::Double(_) => {
-          return Err(eyre!("long/double not implemented in LDCW"))
-        }
-        constant_pool::CpInfoInfoEnum::Class(cl) => {
-          let class_name = self
-            .get_current_class()?
-            .resolve_name(cl.get_name_index())?;
+    let value = self.load_constant(&curr_class_name, index)?;
 
-          self
-            .heap
-            .alloc_class_obj(&mut self.class_loader, &class_name)?
-        }
-        _ => notimpl!(format!("ldc not implemented: {}", info)),
-      }
-    };
+    // long and double constants are loaded by LDC2_W
+    if value.get_category() == 2 {
+      return Err(eyre!(errors::JavaException::VerifyError(format!(
+        "Category 2 constant #{} not allowed in LDC",
+        index
+      ))));
+    }
 
     self.push_stack(value)?;
 
@@ -1008,10 +1118,21 @@ impl JVM {
 
     let curr_class_name = self.get_current_class()?.get_name().to_owned();
 
-    let (class_name, field_name, _field_type) = self
+    let (class_name, field_name, field_type) = self
       .class_loader
       .resolve_field_ref(&curr_class_name, index)?;
 
+    let class_name = self.resolve_class(&curr_class_name, &class_name)?;
+
+    self.check_field_access(
+      &curr_class_name,
+      index,
+      &class_name,
+      &field_name,
+      &field_type,
+      None,
+    )?;
+
     self.init_class(&class_name)?;
 
     // TODO: check it is static and field type
@@ -1039,6 +1160,17 @@ impl JVM {
       .class_loader
       .resolve_field_ref(&curr_class_name, index)?;
 
+    let class_name = self.resolve_class(&curr_class_name, &class_name)?;
+
+    self.check_field_access(
+      &curr_class_name,
+      index,
+      &class_name,
+      &field_name,
+      &field_type,
+      None,
+    )?;
+
     self.init_class(&class_name)?;
 
     // TODO: check it is static and field type
@@ -1084,6 +1216,17 @@ impl JVM {
       .class_loader
       .resolve_method_ref(&curr_class_name, index)?;
 
+    let class_name = self.resolve_class(&curr_class_name, &class_name)?;
+
+    self.check_method_access(
+      &curr_class_name,
+      index,
+      &class_name,
+      &method_name,
+      &method_type,
+      None,
+    )?;
+
     if !self.heap.has_class_instance(&class_name) {
       self
         .heap
@@ -1159,7 +1302,20 @@ impl JVM {
   fn exec_new(&mut self) -> Result<Option<types::Type>> {
     let index = self.get_current_frame_mut()?.read_ju2()?;
 
-    let class_name = self.get_current_class()?.resolve_class_name(index)?;
+    let (curr_class_name, class_name) = {
+      let current_class = self.get_current_class()?;
+
+      (
+        current_class.get_name().to_owned(),
+        current_class.resolve_class_name(index)?,
+      )
+    };
+
+    let class_name = self.resolve_class(&curr_class_name, &class_name)?;
+
+    self
+      .class_loader
+      .check_class_access(&curr_class_name, &class_name)?;
 
     let obj = self.heap.alloc_obj(&mut self.class_loader, &class_name)?;
 
@@ -1448,6 +1604,8 @@ impl JVM {
       .class_loader
       .resolve_method_ref(&curr_class_name, index)?;
 
+    let class_name = self.resolve_class(&curr_class_name, &class_name)?;
+
     // TODO: skip for now -> handle later
     debug!(
       "        [~] InvokeVirtual {} {} {} from {}",
@@ -1458,18 +1616,25 @@ impl JVM {
 
     let mut args = self.pop_arguments(arg_count, &class_name, true)?;
 
-    let caller = args
+    let caller = *args
       .first()
       .ok_or_eyre(eyre!(errors::InternalError::General(
         "Caller not found in InvokeVirtual".to_string()
       )))?;
 
+    self.check_method_access(
+      &curr_class_name,
+      index,
+      &class_name,
+      &method_name,
+      &method_type,
+      Some(caller),
+    )?;
+
     let caller_class = match caller {
-      types::Type::ObjectRef(obj_ref) | types::Type::ArrayRef(obj_ref) => self
-        .heap
-        .get_instance(*obj_ref)?
-        .get_classname()
-        .to_string(),
+      types::Type::ObjectRef(obj_ref) | types::Type::ArrayRef(obj_ref) => {
+        self.heap.get_instance(obj_ref)?.get_classname().to_string()
+      }
       _ => panic!("not ref {}", caller),
     };
 
@@ -1526,6 +1691,17 @@ impl JVM {
       .class_loader
       .resolve_method_ref(&curr_class_name, index)?;
 
+    let class_name = self.resolve_class(&curr_class_name, &class_name)?;
+
+    self.check_method_access(
+      &curr_class_name,
+      index,
+      &class_name,
+      &method_name,
+      &method_type,
+      None,
+    )?;
+
     if !self.heap.has_class_instance(&class_name) {
       self
         .heap
@@ -1586,6 +1762,8 @@ impl JVM {
       .class_loader
       .resolve_method_ref(&curr_class_name, index)?;
 
+    let class_name = self.resolve_class(&curr_class_name, &class_name)?;
+
     debug!(
       "INVOKEINTERFACE {} {} {}",
       class_name, method_name, method_type
@@ -1610,16 +1788,25 @@ impl JVM {
     // with the descriptor of the resolved interface method.
     let mut args = self.pop_arguments(arg_count, &class_name, true)?;
 
-    let caller = args
+    let caller = *args
       .first()
       .ok_or_eyre(eyre!(errors::InternalError::General(
         "Caller not found in InvokeInterface".to_string()
       )))?;
 
+    self.check_method_access(
+      &curr_class_name,
+      index,
+      &class_name,
+      &method_name,
+      &method_type,
+      Some(caller),
+    )?;
+
     let caller_class = if let types::Type::ObjectRef(caller_ref) = caller {
       self
         .heap
-        .get_obj_instance(*caller_ref)?
+        .get_obj_instance(caller_ref)?
         .get_classname()
         .to_owned()
     } else {
@@ -1679,11 +1866,22 @@ impl JVM {
       .class_loader
       .resolve_field_ref(&curr_class_name, index)?;
 
+    let class_name = self.resolve_class(&curr_class_name, &class_name)?;
+
     // TODO: check the type
     //let field_name = self.classes.get(&class_name)?.resolve_name(field_index)?;
     //let field_name = self.get_current_class()?.resolve_name(field_index)?;
     let obj = self.pop_stack()?;
 
+    self.check_field_access(
+      &curr_class_name,
+      index,
+      &class_name,
+      &field_name,
+      &field_type,
+      Some(obj),
+    )?;
+
     debug!(
       "        [~] Get field {} {} {} {}",
       class_name, field_name, field_type, obj
@@ -1693,7 +1891,7 @@ impl JVM {
       types::Type::ObjectRef(obj_ref) => self
         .heap
         .get_obj_instance(obj_ref)?
-        .get_field(&field_name)?,
+        .get_field_from(&class_name, &field_name)?,
       types::Type::Null => return Err(eyre!(errors::JavaException::NullPointer)),
       _ => return Err(eyre!("not object ref: {}", obj)),
     };
@@ -1717,10 +1915,21 @@ impl JVM {
       .class_loader
       .resolve_field_ref(&curr_class_name, index)?;
 
+    let class_name = self.resolve_class(&curr_class_name, &class_name)?;
+
     let value = self.pop_stack()?;
 
     let objref = self.pop_stack()?;
 
+    self.check_field_access(
+      &curr_class_name,
+      index,
+      &class_name,
+      &field_name,
+      &field_type,
+      Some(objref),
+    )?;
+
     debug!(
       "        [~] Put field {} {} in {}",
       field_name, field_type, class_name
@@ -1734,7 +1943,7 @@ impl JVM {
         let objinstance = self.heap.get_obj_instance_mut(reference)?;
 
         if types::Type::check_type(&mut self.class_loader, &field_type, &value_type)? {
-          objinstance.put_field(&field_name, value)?;
+          objinstance.put_field_from(&class_name, &field_name, value)?;
         } else {
           return Err(eyre!(
             "Putfield wrong type in {} : {} <!: {}",
@@ -1782,9 +1991,16 @@ impl JVM {
 
     match info {
       CpInfoInfoEnum::Class(class_info) => {
-        let class_name = self
-          .get_current_class()?
-          .resolve_name(class_info.get_name_index())?;
+        let (curr_class_name, class_name) = {
+          let current_class = self.get_current_class()?;
+
+          (
+            current_class.get_name().to_owned(),
+            current_class.resolve_name(class_info.get_name_index())?,
+          )
+        };
+
+        let class_name = self.resolve_class(&curr_class_name, &class_name)?;
 
         //  If objectref is a value of the type given by the resolved class, array, or
         // interface type, the operand stack is unchanged.
@@ -1833,9 +2049,12 @@ impl JVM {
       match &item.get_info() {
         CpInfoInfoEnum::Class(cl_index) => {
           let class_name = class.resolve_name(cl_index.get_name_index())?;
+          let curr_class_name = class.get_name().to_owned();
 
           drop(class);
 
+          let class_name = self.resolve_class(&curr_class_name, &class_name)?;
+
           debug!("        INSTANCEOF {} {}", class_name, obj_class_name);
 
           if types::Type::check_type(&mut self.class_loader, &class_name, &obj_class_name)? {
@@ -1909,7 +2128,7 @@ impl JVM {
           .get_method_by_name(&method_class, &method_name, &method_type)?;
 
       if let Some(method_code) = method.get_code() {
-        let pc = self.get_current_frame()?.get_pc() as ju2;
+        let pc = self.get_current_frame()?.get_last_opcode_pc() as ju2;
         let check = method_code.check_exception(&mut self.class_loader, pc, &class_name)?;
         if let Some(handler_pc) = check {
           self.push_stack(types::Type::ObjectRef(exec_ref))?;
@@ -1940,24 +2159,24 @@ impl JVM {
     warn!("ATHROW NOT IMPLEMENTED YET");
 
     let exec_ref = self.pop_stack()?.as_ref()?;
-    let exec_obj = self.heap.get_obj_instance(exec_ref)?;
-    let exec_classname = exec_obj.get_classname();
 
-    let detail_message_ref = exec_obj.get_field("detailMessage")?.as_ref()?;
-    let detail_message = if detail_message_ref == 0 {
-      "No Message".to_string()
-    } else {
-      self.heap.get_string(detail_message_ref)?
-    };
-    debug!("THROWING {} -> '{}'", exec_classname, detail_message);
+    if exec_ref == 0 {
+      return Err(eyre!(errors::JavaException::NullPointer));
+    }
+
+    // the thrown object is kept, with its message, cause and stack trace
+    let exec_classname = self
+      .heap
+      .get_obj_instance(exec_ref)?
+      .get_classname()
+      .to_string();
+    let exception = errors::JavaException::Throwable(exec_classname, exec_ref);
 
     self.show_frames();
 
     // make this propagate until main loop
 
-    Err(eyre!(
-      errors::JavaException::convert_classname_to_java_exception(exec_classname, detail_message)
-    ))
+    Err(eyre!(exception))
   }
 
   // Access jump table by index and jump
@@ -2094,10 +2313,18 @@ impl JVM {
     let size = self.pop_ioperand()?;
 
     let class_name = match info {
-      CpInfoInfoEnum::Class(cl) => self
-        .get_current_class()?
-        .resolve_name(cl.get_name_index())?
-        .clone(),
+      CpInfoInfoEnum::Class(cl) => {
+        let (curr_class_name, class_name) = {
+          let current_class = self.get_current_class()?;
+
+          (
+            current_class.get_name().to_owned(),
+            current_class.resolve_name(cl.get_name_index())?,
+          )
+        };
+
+        self.resolve_class(&curr_class_name, &class_name)?
+      }
       CpInfoInfoEnum::Interfaceref(_) => {
         notimpl!()
       }
@@ -2172,10 +2399,18 @@ impl JVM {
     let num_dimensions = self.get_current_frame_mut()?.read_ju1()?;
 
     let class_name = match info {
-      CpInfoInfoEnum::Class(cl) => self
-        .get_current_class()?
-        .resolve_name(cl.get_name_index())?
-        .clone(),
+      CpInfoInfoEnum::Class(cl) => {
+        let (curr_class_name, class_name) = {
+          let current_class = self.get_current_class()?;
+
+          (
+            current_class.get_name().to_owned(),
+            current_class.resolve_name(cl.get_name_index())?,
+          )
+        };
+
+        self.resolve_class(&curr_class_name, &class_name)?
+      }
       CpInfoInfoEnum::Interfaceref(_) => {
         notimpl!()
       }
@@ -2208,7 +2443,7 @@ impl JVM {
     Ok(None)
   }
 
-  pub(crate) fn get_current_class(&mut self) -> Result<RwLockReadGuard<class_file::ClassFile>> {
+  pub(crate) fn get_current_class(&mut self) -> Result<RwLockReadGuard<'_, class_file::ClassFile>> {
     let current_class = self.get_current_frame()?.get_classname().to_string();
 
     let class = self.class_loader.get(&current_class)?;
@@ -2275,9 +2510,12 @@ impl JVM {
       // correct stack state
       self.restore_stack(args)?;
 
-      returned = self
-        .call_native(&method_class, method_name, descriptor)?
-        .unwrap_or(types::Type::None);
+      self.call_native(&method_class, method_name, descriptor)?;
+
+      // natives push their result on the stack of the caller
+      if get_return_type_descriptor(descriptor) != "V" {
+        returned = self.pop_stack()?;
+      }
     } else {
       self.push_frame_from_class(class_name, method_name, descriptor, args)?;
 
@@ -2299,20 +2537,17 @@ impl JVM {
             }
           }
           Err(err) => {
-            match self.handle_step_error(err, num_frames) {
-              // can not handle the error => pass the error to the upper handler
-              Err(err) => return Err(err),
-              // can handle the error => continue
-              _ => {}
-            }
+            // can not handle the error => pass the error to the upper handler
+            // can handle the error => continue
+            self.handle_step_error(err, num_frames)?;
           }
         }
       }
-    }
 
-    // clean stack from dirty returns
-    if returned != types::Type::None && !self.frames.is_empty() {
-      self.pop_stack()?;
+      // clean stack from dirty returns
+      if returned != types::Type::None && !self.frames.is_empty() {
+        self.pop_stack()?;
+      }
     }
 
     info!("[!] Resolved {} {} {}", class_name, method_name, returned);
@@ -2432,7 +2667,7 @@ impl JVM {
     self.frames.pop();
   }
 
-  pub(crate) fn push_frame_from_class(
+  pub fn push_frame_from_class(
     &mut self,
     class_name: &str,
     method_name: &str,
@@ -2444,6 +2679,8 @@ impl JVM {
         .class_loader
         .get_method_code_by_name(class_name, method_name, method_type)?;
 
+    self.link_class(&method_class)?;
+
     let max_locals = method.get_max_locals();
 
     let mut nargs = args.clone();
@@ -2465,6 +2702,16 @@ impl JVM {
       class_name, method_name, method_type, args
     );
 
+    if self
+      .max_frames
+      .is_some_and(|max_frames| self.frames.len() >= max_frames)
+    {
+      return Err(eyre!(errors::JavaException::StackOverflowError(format!(
+        "{} frames",
+        self.frames.len()
+      ))));
+    }
+
     let frame = frame::Frame::new(
       method_class.to_string(),
       method_name.to_string(),
@@ -0,0 +1,1047 @@
+use color_eyre::eyre::{eyre, Result};
+use log::debug;
+
+use crate::{
+  class_loader::loader::ClassLoader,
+  runtime::{constants::*, errors, jvm::JVM, types},
+  utils::{get_default_value, get_parameters_type_descriptor, ju4, parse_parameter_types},
+};
+
+const METHOD_HANDLE: &str = "java/lang/invoke/MethodHandle";
+const DIRECT_METHOD_HANDLE: &str = "java/lang/invoke/DirectMethodHandle";
+const INTERFACE_METHOD_HANDLE: &str = "java/lang/invoke/DirectMethodHandle$Interface";
+const VARARGS_COLLECTOR: &str = "java/lang/invoke/MethodHandleImpl$AsVarargsCollector";
+const METHOD_HANDLE_IMPL: &str = "java/lang/invoke/MethodHandleImpl";
+const ARRAY_ACCESSOR: &str = "java/lang/invoke/MethodHandleImpl$ArrayAccessor";
+const LAMBDA_FORM_NAME: &str = "java/lang/invoke/LambdaForm$Name";
+
+// https://github.com/openjdk/jdk/blob/master/src/java.base/share/classes/java/lang/invoke/LambdaForm.java
+//
+// A MethodHandle is run by interpreting its LambdaForm: the first `arity` names are the
+// handle and its arguments, each following name applies a function (a MemberName or a
+// MethodHandle) to previous names and constants, and `result` is the index of the returned
+// name. DirectMethodHandles are invoked from their MemberName, and the intrinsics of
+// MethodHandleImpl (which call `invokeWithArguments`) are run natively.
+impl JVM {
+  /// Invoke the MethodHandle `method_handle_ref` with a value per parameter of its type
+  ///
+  /// # Returns
+  ///
+  /// The returned value, `Type::None` for `void`
+  pub(crate) fn invoke_method_handle(
+    &mut self,
+    method_handle_ref: ju4,
+    args: Vec<types::Type>,
+  ) -> Result<types::Type> {
+    if method_handle_ref == 0 {
+      return Err(eyre!(errors::JavaException::NullPointer));
+    }
+
+    let method_handle = self.heap.get_obj_instance(method_handle_ref)?;
+    let classname = method_handle.get_classname().to_string();
+
+    if types::Type::check_type(&mut self.class_loader, DIRECT_METHOD_HANDLE, &classname)? {
+      let member_ref = method_handle.get_field("member")?.as_ref()?;
+
+      // the receiver of interface methods is checked against the referenced interface
+      if classname == INTERFACE_METHOD_HANDLE {
+        let refc_ref = method_handle.get_field("refc")?.as_ref()?;
+        let refc = self.heap.get_classname_from_class_obj(refc_ref)?;
+
+        if let Some(types::Type::ObjectRef(receiver_ref)) = args.first() {
+          if !self.is_instance_of(*receiver_ref, &refc)? {
+            return Err(eyre!(errors::JavaException::ClassCastException(format!(
+              "Cannot cast {} to {}",
+              self.heap.get_instance(*receiver_ref)?.get_classname(),
+              refc
+            ))));
+          }
+        }
+      }
+
+      return self.invoke_member_name(member_ref, args);
+    }
+
+    // the form of a variable arity handle only reinvokes its target
+    if classname == VARARGS_COLLECTOR {
+      let target_ref = method_handle.get_field("target")?.as_ref()?;
+
+      return self.invoke_method_handle(target_ref, args);
+    }
+
+    let form_ref = method_handle.get_field("form")?.as_ref()?;
+
+    self.interpret_lambda_form(method_handle_ref, form_ref, args)
+  }
+
+  /// Invoke the MethodHandle `method_handle_ref` from a call site of type `call_type`, like
+  /// `invokeExact` when `exact` is set and like `invoke` otherwise
+  ///
+  /// `invoke` converts the arguments and the returned value as `MethodHandle.asType` would,
+  /// and collects the trailing arguments of variable arity handles.
+  pub(crate) fn invoke_method_handle_with_type(
+    &mut self,
+    method_handle_ref: ju4,
+    call_type: &str,
+    args: Vec<types::Type>,
+    exact: bool,
+  ) -> Result<types::Type> {
+    if method_handle_ref == 0 {
+      return Err(eyre!(errors::JavaException::NullPointer));
+    }
+
+    let method_handle = self.heap.get_obj_instance(method_handle_ref)?;
+    let is_varargs_collector = method_handle.get_classname() == VARARGS_COLLECTOR;
+    let handle_type = self.get_method_handle_descriptor(method_handle_ref)?;
+
+    if handle_type == call_type {
+      return self.invoke_method_handle(method_handle_ref, args);
+    }
+
+    if exact {
+      return Err(eyre!(errors::JavaException::WrongMethodTypeException(
+        format!(
+          "handle's method type {} but found {}",
+          handle_type, call_type
+        )
+      )));
+    }
+
+    debug!(
+      "MethodHandle of type {} invoked as {}",
+      handle_type, call_type
+    );
+
+    let wrong_type = || {
+      eyre!(errors::JavaException::WrongMethodTypeException(format!(
+        "cannot convert MethodHandle{} to {}",
+        handle_type, call_type
+      )))
+    };
+
+    let mut from_types: Vec<String> = get_parameter_descriptors(call_type);
+    let to_types = get_parameter_descriptors(&handle_type);
+    let mut args = args;
+    let mut target_ref = method_handle_ref;
+
+    // the target of a variable arity handle gets the trailing arguments in an array
+    if is_varargs_collector {
+      let last = to_types.len().checked_sub(1).ok_or_else(wrong_type)?;
+
+      if from_types.len() < last {
+        return Err(wrong_type());
+      }
+
+      // an array passed as the last argument is not collected
+      let passes_array = from_types.len() == to_types.len()
+        && (from_types[last] == to_types[last]
+          || match args[last] {
+            types::Type::ArrayRef(array_ref) => {
+              self.is_instance_of(array_ref, &get_descriptor_classname(&to_types[last]))?
+            }
+            _ => false,
+          });
+
+      if !passes_array {
+        let component = &to_types[last][1..];
+
+        let mut elements = vec![];
+        for (from, arg) in from_types[last..].iter().zip(&args[last..]) {
+          let element = self.convert_as_type(from, component, *arg)?;
+          elements.push(types::Type::to_stored_value(component, element)?);
+        }
+
+        let array = self
+          .heap
+          .alloc_array(&get_descriptor_classname(component), elements, 0)?;
+
+        args.truncate(last);
+        args.push(array);
+        from_types.truncate(last);
+        from_types.push(to_types[last].clone());
+      }
+
+      target_ref = self
+        .heap
+        .get_obj_instance(method_handle_ref)?
+        .get_field("target")?
+        .as_ref()?;
+    }
+
+    if from_types.len() != to_types.len() {
+      return Err(wrong_type());
+    }
+
+    let mut converted = vec![];
+    for ((from, to), arg) in from_types.iter().zip(&to_types).zip(args) {
+      converted.push(self.convert_as_type(from, to, arg)?);
+    }
+
+    let result = self.invoke_method_handle(target_ref, converted)?;
+
+    let from = get_return_descriptor(&handle_type);
+    let to = get_return_descriptor(call_type);
+
+    match (from, to) {
+      (_, "V") => Ok(types::Type::None),
+      // a call site returning a value gets `null` or zero from a `void` handle
+      ("V", to) => Ok(match to {
+        "C" => types::Type::Integer(0),
+        to => get_default_value(to),
+      }),
+      (from, to) => self.convert_as_type(from, to, result),
+    }
+  }
+
+  /// Invoke the MethodHandle `method_handle_ref` with boxed arguments and box the returned
+  /// value, like `MethodHandle.invokeWithArguments`
+  pub(crate) fn invoke_with_arguments(
+    &mut self,
+    method_handle_ref: ju4,
+    args: Vec<types::Type>,
+  ) -> Result<types::Type> {
+    let call_type = format!(
+      "({})Ljava/lang/Object;",
+      "Ljava/lang/Object;".repeat(args.len())
+    );
+
+    self.invoke_method_handle_with_type(method_handle_ref, &call_type, args, false)
+  }
+
+  /// Descriptor of a `java/lang/invoke/MethodType` (e.g. `(ILjava/lang/String;)V`)
+  pub(crate) fn get_method_type_descriptor(&self, method_type_ref: ju4) -> Result<String> {
+    let method_type = self.heap.get_obj_instance(method_type_ref)?;
+
+    let rtype_ref = method_type.get_field("rtype")?.as_ref()?;
+    let ptypes_ref = method_type.get_field("ptypes")?.as_ref()?;
+
+    let mut descriptor = "(".to_string();
+    for ptype in self.heap.get_array_instance(ptypes_ref)?.get_elements() {
+      descriptor += &self.get_class_descriptor(ptype.as_ref()?)?;
+    }
+    descriptor += ")";
+    descriptor += &self.get_class_descriptor(rtype_ref)?;
+
+    Ok(descriptor)
+  }
+
+  /// Descriptor of the class of a `java/lang/Class` object (e.g. `I` or `Ljava/lang/String;`)
+  fn get_class_descriptor(&self, class_ref: ju4) -> Result<String> {
+    let classname =
+      ClassLoader::get_binary_name(&self.heap.get_classname_from_class_obj(class_ref)?);
+
+    if types::Type::is_primitive(&classname) || classname == "V" || classname.starts_with('[') {
+      Ok(classname)
+    } else {
+      Ok(format!("L{};", classname))
+    }
+  }
+
+  // names are evaluated in order, their values are the arguments of the next ones
+  fn interpret_lambda_form(
+    &mut self,
+    method_handle_ref: ju4,
+    form_ref: ju4,
+    args: Vec<types::Type>,
+  ) -> Result<types::Type> {
+    let form = self.heap.get_obj_instance(form_ref)?;
+
+    let arity = form.get_field("arity")?.as_integer()? as usize;
+    let result = form.get_field("result")?.as_integer()?;
+    let names_ref = form.get_field("names")?.as_ref()?;
+    let names = self
+      .heap
+      .get_array_instance(names_ref)?
+      .get_elements()
+      .clone();
+
+    if args.len() + 1 != arity {
+      return Err(eyre!(errors::InternalError::General(format!(
+        "LambdaForm of arity {} invoked with {} arguments",
+        arity,
+        args.len()
+      ))));
+    }
+
+    let mut values = vec![types::Type::None; names.len()];
+    values[0] = types::Type::ObjectRef(method_handle_ref);
+    values[1..arity].copy_from_slice(&args);
+
+    for (index, name) in names.iter().enumerate().skip(arity) {
+      let name = self.heap.get_obj_instance(name.as_ref()?)?;
+
+      let function_ref = name.get_field("function")?.as_ref()?;
+      let arguments = match name.get_field("arguments")? {
+        types::Type::Null => vec![],
+        arguments => self
+          .heap
+          .get_array_instance(arguments.as_ref()?)?
+          .get_elements()
+          .clone(),
+      };
+
+      let mut function_args = vec![];
+      for argument in arguments {
+        function_args.push(self.get_lambda_form_argument(&values, argument)?);
+      }
+
+      values[index] = self.invoke_named_function(function_ref, function_args)?;
+
+      debug!("LambdaForm name {} = {}", index, values[index]);
+    }
+
+    match result {
+      -1 => Ok(types::Type::None), // LambdaForm.VOID_RESULT
+      result => Ok(values[result as usize]),
+    }
+  }
+
+  // an argument of a name is a previous name or a constant
+  fn get_lambda_form_argument(
+    &self,
+    values: &[types::Type],
+    argument: types::Type,
+  ) -> Result<types::Type> {
+    if let types::Type::ObjectRef(argument_ref) = argument {
+      let argument = self.heap.get_obj_instance(argument_ref)?;
+
+      if argument.get_classname() == LAMBDA_FORM_NAME {
+        let index = argument.get_field("index")?.as_integer()? as usize;
+
+        return values.get(index).copied().ok_or_else(|| {
+          eyre!(errors::InternalError::General(format!(
+            "LambdaForm name {} used before its definition",
+            index
+          )))
+        });
+      }
+    }
+
+    Ok(argument)
+  }
+
+  fn invoke_named_function(
+    &mut self,
+    function_ref: ju4,
+    args: Vec<types::Type>,
+  ) -> Result<types::Type> {
+    let function = self.heap.get_obj_instance(function_ref)?;
+
+    match function.get_field("member")? {
+      types::Type::ObjectRef(member_ref) => self.invoke_member_name(member_ref, args),
+      _ => {
+        let resolved_handle_ref = function.get_field("resolvedHandle")?.as_ref()?;
+
+        self.invoke_method_handle(resolved_handle_ref, args)
+      }
+    }
+  }
+
+  /// Invoke the method or access the field of the resolved MemberName `member_ref`, the
+  /// receiver (if any) is the first of `args`
+  pub(crate) fn invoke_member_name(
+    &mut self,
+    member_ref: ju4,
+    args: Vec<types::Type>,
+  ) -> Result<types::Type> {
+    let member = self.heap.get_obj_instance(member_ref)?;
+
+    let class_ref = member.get_field("clazz")?.as_ref()?;
+    let name_ref = member.get_field("name")?.as_ref()?;
+    let type_ref = member.get_field("type")?.as_ref()?;
+    let flags = member.get_field("flags")?.as_integer()?;
+
+    let classname = self.heap.get_classname_from_class_obj(class_ref)?;
+    let name = self.heap.get_string(name_ref)?;
+    let descriptor = self.get_member_name_descriptor(type_ref)?;
+    let ref_kind = (flags >> MN_REFERENCE_KIND_SHIFT) & MN_REFERENCE_KIND_MASK;
+
+    debug!(
+      "MemberName {}.{} {} (kind {}) {:?}",
+      classname, name, descriptor, ref_kind, args
+    );
+
+    if Constants::is_field(flags) {
+      return self.access_member_field(&classname, &name, &descriptor, ref_kind, args);
+    }
+
+    if let Some(result) = self.invoke_intrinsic(&classname, &name, &descriptor, &args)? {
+      return Ok(result);
+    }
+
+    self.invoke_member_method(&classname, &name, &descriptor, ref_kind, args)
+  }
+
+  // the type of a MemberName is a MethodType or a descriptor for methods, a class for fields
+  fn get_member_name_descriptor(&self, type_ref: ju4) -> Result<String> {
+    match self.heap.get_obj_instance(type_ref)?.get_classname() {
+      "java/lang/String" => self.heap.get_string(type_ref),
+      "java/lang/Class" => self.get_class_descriptor(type_ref),
+      "java/lang/invoke/MethodType" => self.get_method_type_descriptor(type_ref),
+      classname => Err(eyre!(errors::InternalError::General(format!(
+        "MemberName of type {} not resolved",
+        classname
+      )))),
+    }
+  }
+
+  fn access_member_field(
+    &mut self,
+    classname: &str,
+    name: &str,
+    descriptor: &str,
+    ref_kind: i32,
+    args: Vec<types::Type>,
+  ) -> Result<types::Type> {
+    match (ref_kind, args.as_slice()) {
+      (REF_GET_FIELD, [receiver]) => {
+        let receiver_ref = get_receiver_ref(*receiver)?;
+
+        self.heap.get_obj_instance(receiver_ref)?.get_field(name)
+      }
+      (REF_PUT_FIELD, [receiver, value]) => {
+        let receiver_ref = get_receiver_ref(*receiver)?;
+        let value = self.convert_to_parameter(descriptor, *value)?;
+
+        self
+          .heap
+          .get_obj_instance_mut(receiver_ref)?
+          .put_field(name, value)?;
+
+        Ok(types::Type::None)
+      }
+      (REF_GET_STATIC, []) => {
+        self.init_class(classname)?;
+
+        self.class_loader.get_static_field(classname, name)
+      }
+      (REF_PUT_STATIC, [value]) => {
+        self.init_class(classname)?;
+
+        let value = self.convert_to_parameter(descriptor, *value)?;
+
+        self
+          .class_loader
+          .get_mut(classname)?
+          .put_static_field(name, value)?;
+
+        Ok(types::Type::None)
+      }
+      _ => Err(eyre!(errors::InternalError::General(format!(
+        "field {}.{} of kind {} accessed with {} arguments",
+        classname,
+        name,
+        ref_kind,
+        args.len()
+      )))),
+    }
+  }
+
+  fn invoke_member_method(
+    &mut self,
+    classname: &str,
+    name: &str,
+    descriptor: &str,
+    ref_kind: i32,
+    args: Vec<types::Type>,
+  ) -> Result<types::Type> {
+    // signature polymorphic methods of the invokers
+    if classname == METHOD_HANDLE {
+      if let Some(result) = self.invoke_polymorphic_member(name, descriptor, args.clone())? {
+        return Ok(result);
+      }
+    }
+
+    let param_types = get_parameter_descriptors(descriptor);
+    let has_receiver = matches!(
+      ref_kind,
+      REF_INVOKE_VIRTUAL | REF_INVOKE_SPECIAL | REF_INVOKE_INTERFACE
+    );
+
+    if args.len() != param_types.len() + has_receiver as usize {
+      return Err(eyre!(errors::InternalError::General(format!(
+        "{}.{}{} invoked with {} arguments",
+        classname,
+        name,
+        descriptor,
+        args.len()
+      ))));
+    }
+
+    let mut locals = vec![];
+
+    if has_receiver {
+      get_receiver_ref(args[0])?;
+
+      locals.push(args[0]);
+    }
+
+    for (param_type, arg) in param_types.iter().zip(&args[has_receiver as usize..]) {
+      let value = self.convert_to_parameter(param_type, *arg)?;
+
+      value.push_to_locals(&mut locals);
+    }
+
+    match ref_kind {
+      REF_INVOKE_STATIC => {
+        self.init_class(classname)?;
+
+        self.call_and_resolve_method(classname, name, descriptor, locals)
+      }
+      REF_INVOKE_VIRTUAL | REF_INVOKE_INTERFACE => {
+        let method_class = self.resolve_virtual_member(classname, name, descriptor, args[0])?;
+
+        self.call_and_resolve_method(&method_class, name, descriptor, locals)
+      }
+      REF_INVOKE_SPECIAL => self.call_and_resolve_method(classname, name, descriptor, locals),
+      REF_NEW_INVOKE_SPECIAL => {
+        self.init_class(classname)?;
+
+        let obj = self.heap.alloc_obj(&mut self.class_loader, classname)?;
+        locals.insert(0, obj);
+
+        let init_descriptor = format!("({})V", get_parameters_type_descriptor(descriptor));
+        self.call_and_resolve_method(classname, "<init>", &init_descriptor, locals)?;
+
+        Ok(obj)
+      }
+      _ => Err(eyre!(errors::InternalError::General(format!(
+        "method {}.{} of kind {}",
+        classname, name, ref_kind
+      )))),
+    }
+  }
+
+  // class of the method selected by the receiver, private methods are not overridden
+  fn resolve_virtual_member(
+    &mut self,
+    classname: &str,
+    name: &str,
+    descriptor: &str,
+    receiver: types::Type,
+  ) -> Result<String> {
+    let (method_class, method, _) = self
+      .class_loader
+      .get_any_method_by_name_with_index(classname, name, descriptor)?;
+
+    if method.is_private() {
+      return Ok(method_class);
+    }
+
+    let receiver_class = self
+      .heap
+      .get_instance(get_receiver_ref(receiver)?)?
+      .get_classname()
+      .to_string();
+
+    // arrays only have the methods of Object
+    let receiver_class = match receiver_class.starts_with('[') {
+      true => "java/lang/Object".to_string(),
+      false => receiver_class,
+    };
+
+    match self
+      .class_loader
+      .get_method_by_name(&receiver_class, name, descriptor)
+    {
+      Ok((method_class, _)) => Ok(method_class),
+      Err(err) => match err.downcast_ref::<errors::InternalError>() {
+        Some(errors::InternalError::MethodNotFound(..)) => {
+          Err(eyre!(errors::JavaException::AbstractMethodError(format!(
+            "{}.{}{}",
+            receiver_class, name, descriptor
+          ))))
+        }
+        _ => Err(err),
+      },
+    }
+  }
+
+  // the invokers of the JDK use the signature polymorphic methods as functions
+  fn invoke_polymorphic_member(
+    &mut self,
+    name: &str,
+    descriptor: &str,
+    mut args: Vec<types::Type>,
+  ) -> Result<Option<types::Type>> {
+    let result = match name {
+      "invokeBasic" | "invokeExact" | "invoke" => {
+        if args.is_empty() {
+          return Err(eyre!(errors::InternalError::General(format!(
+            "MethodHandle.{} without a MethodHandle",
+            name
+          ))));
+        }
+
+        let method_handle_ref = get_receiver_ref(args.remove(0))?;
+
+        match name {
+          "invokeBasic" => self.invoke_method_handle(method_handle_ref, args)?,
+          _ => self.invoke_method_handle_with_type(
+            method_handle_ref,
+            descriptor,
+            args,
+            name == "invokeExact",
+          )?,
+        }
+      }
+      "linkToStatic" | "linkToSpecial" | "linkToVirtual" | "linkToInterface" => {
+        let member_ref = match args.pop() {
+          Some(member) => member.as_ref()?,
+          None => {
+            return Err(eyre!(errors::InternalError::General(format!(
+              "MethodHandle.{} without a MemberName",
+              name
+            ))))
+          }
+        };
+
+        self.invoke_member_name(member_ref, args)?
+      }
+      "linkToNative" => self.link_to_native(args)?,
+      _ => return Ok(None),
+    };
+
+    Ok(Some(result))
+  }
+
+  // https://github.com/openjdk/jdk/blob/master/src/java.base/share/classes/java/lang/invoke/MethodHandleImpl.java
+  //
+  // the intrinsics recognized by the VM, the combinators among them call the handles with
+  // `invokeWithArguments` which needs the VM
+  fn invoke_intrinsic(
+    &mut self,
+    classname: &str,
+    name: &str,
+    descriptor: &str,
+    args: &[types::Type],
+  ) -> Result<Option<types::Type>> {
+    let result = match (classname, name, args) {
+      (METHOD_HANDLE_IMPL, "selectAlternative", [test, target, fallback]) => {
+        match self.convert_to_parameter("Z", *test)?.as_bool()? {
+          true => *target,
+          false => *fallback,
+        }
+      }
+      (METHOD_HANDLE_IMPL, "guardWithCatch", [target, ex_type, catcher, av]) => {
+        let av = self.get_array_arguments(*av)?;
+
+        self.guard_with_catch(target.as_ref()?, ex_type.as_ref()?, catcher.as_ref()?, av)?
+      }
+      (METHOD_HANDLE_IMPL, "tryFinally", [target, cleanup, av]) => {
+        let av = self.get_array_arguments(*av)?;
+
+        self.try_finally(target.as_ref()?, cleanup.as_ref()?, av)?
+      }
+      (METHOD_HANDLE_IMPL, "loop", [_, clause_data, av]) => {
+        let av = self.get_array_arguments(*av)?;
+
+        self.run_loop(clause_data.as_ref()?, av)?
+      }
+      (METHOD_HANDLE_IMPL, "tableSwitch", [input, default_case, holder, av]) => {
+        let input = self.convert_to_parameter("I", *input)?.as_integer()?;
+        let holder = self.heap.get_obj_instance(holder.as_ref()?)?;
+        let cases = self.get_array_arguments(holder.get_field("cases")?)?;
+        let av = self.get_array_arguments(*av)?;
+
+        let selected = match usize::try_from(input).ok().and_then(|i| cases.get(i)) {
+          Some(selected) => *selected,
+          None => *default_case,
+        };
+
+        self.invoke_with_arguments(selected.as_ref()?, av)?
+      }
+      // not an intrinsic but it is called by every spreader
+      (METHOD_HANDLE_IMPL, "checkSpreadArgument", [av, n]) => {
+        let n = self.convert_to_parameter("I", *n)?.as_integer()?;
+
+        let len = match av {
+          types::Type::Null if n == 0 => return Ok(Some(types::Type::None)),
+          types::Type::Null => return Err(eyre!(errors::JavaException::NullPointer)),
+          av => self.heap.get_array_instance(av.as_ref()?)?.len(),
+        };
+
+        if len != n as usize {
+          return Err(eyre!(errors::JavaException::IllegalArgumentException(
+            format!("array is not of length {}", n)
+          )));
+        }
+
+        types::Type::None
+      }
+      (ARRAY_ACCESSOR, name, [array, rest @ ..]) => {
+        let array_ref = get_receiver_ref(*array)?;
+
+        match (name, rest) {
+          (name, [index]) if name.starts_with("getElement") => {
+            let index = self.convert_to_parameter("I", *index)?.as_integer()?;
+            let array = self.heap.get_array_instance(array_ref)?;
+
+            *array.get(get_array_index(index, array.len())?)?
+          }
+          (name, [index, value]) if name.starts_with("setElement") => {
+            let index = self.convert_to_parameter("I", *index)?.as_integer()?;
+            let element_type = get_parameter_descriptors(descriptor)
+              .last()
+              .cloned()
+              .unwrap_or_default();
+            let value = self.convert_to_parameter(&element_type, *value)?;
+
+            let array = self.heap.get_array_instance_mut(array_ref)?;
+            let value = types::Type::to_stored_value(&array.get_classname()[1..], value)?;
+            let index = get_array_index(index, array.len())?;
+            array.set(index, value)?;
+
+            types::Type::None
+          }
+          (name, []) if name.starts_with("length") => {
+            types::Type::Integer(self.heap.get_array_instance(array_ref)?.len() as i32)
+          }
+          _ => return Ok(None),
+        }
+      }
+      _ => return Ok(None),
+    };
+
+    debug!("Intrinsic {}.{} = {}", classname, name, result);
+
+    Ok(Some(result))
+  }
+
+  fn guard_with_catch(
+    &mut self,
+    target_ref: ju4,
+    ex_type_ref: ju4,
+    catcher_ref: ju4,
+    av: Vec<types::Type>,
+  ) -> Result<types::Type> {
+    let err = match self.invoke_with_arguments(target_ref, av.clone()) {
+      Ok(result) => return Ok(result),
+      Err(err) => err,
+    };
+
+    let exception_ref = match err.downcast_ref::<errors::JavaException>() {
+      Some(exception) => self.alloc_java_exception(exception)?,
+      None => return Err(err),
+    };
+
+    let ex_type = self.heap.get_classname_from_class_obj(ex_type_ref)?;
+
+    if !self.is_instance_of(exception_ref, &ex_type)? {
+      return Err(err);
+    }
+
+    let mut catcher_args = vec![types::Type::ObjectRef(exception_ref)];
+    catcher_args.extend(av);
+
+    self.invoke_with_arguments(catcher_ref, catcher_args)
+  }
+
+  // the cleanup gets the exception (or null), the result (unless void) and the arguments,
+  // the exception is rethrown after the cleanup
+  fn try_finally(
+    &mut self,
+    target_ref: ju4,
+    cleanup_ref: ju4,
+    av: Vec<types::Type>,
+  ) -> Result<types::Type> {
+    let result = self.invoke_with_arguments(target_ref, av.clone());
+
+    let (throwable, value) = match &result {
+      Ok(value) => (types::Type::Null, *value),
+      Err(err) => match err.downcast_ref::<errors::JavaException>() {
+        Some(exception) => (
+          types::Type::ObjectRef(self.alloc_java_exception(exception)?),
+          types::Type::Null,
+        ),
+        None => return result,
+      },
+    };
+
+    let mut cleanup_args = vec![throwable];
+    if self.get_method_handle_return_descriptor(target_ref)? != "V" {
+      cleanup_args.push(value);
+    }
+    cleanup_args.extend(av);
+
+    let cleaned = self.invoke_with_arguments(cleanup_ref, cleanup_args)?;
+
+    result.map(|_| cleaned)
+  }
+
+  // the clauses are the arrays of the init, step, pred and fini handles, the loop variables
+  // (the results of the non-void inits and steps) precede the arguments
+  fn run_loop(&mut self, clause_data_ref: ju4, av: Vec<types::Type>) -> Result<types::Type> {
+    let clauses = self
+      .heap
+      .get_obj_instance(clause_data_ref)?
+      .get_field("clauses")?;
+
+    let mut handles = vec![];
+    for clause in self.get_array_arguments(clauses)? {
+      handles.push(self.get_array_arguments(clause)?);
+    }
+
+    let [init, step, pred, fini] = handles.as_slice() else {
+      return Err(eyre!(errors::InternalError::General(format!(
+        "loop of {} clause arrays",
+        handles.len()
+      ))));
+    };
+
+    let mut vars_and_args = vec![];
+    for handle in init {
+      let value = self.invoke_with_arguments(handle.as_ref()?, av.clone())?;
+
+      if self.get_method_handle_return_descriptor(handle.as_ref()?)? != "V" {
+        vars_and_args.push(value);
+      }
+    }
+
+    let arg_count = match init.first() {
+      Some(handle) => {
+        get_parameter_descriptors(&self.get_method_handle_descriptor(handle.as_ref()?)?)
+          .len()
+          .min(av.len())
+      }
+      None => 0,
+    };
+    vars_and_args.extend_from_slice(&av[..arg_count]);
+
+    loop {
+      let mut var = 0;
+
+      for ((step, pred), fini) in step.iter().zip(pred).zip(fini) {
+        let value = self.invoke_with_arguments(step.as_ref()?, vars_and_args.clone())?;
+
+        if self.get_method_handle_return_descriptor(step.as_ref()?)? != "V" {
+          vars_and_args[var] = value;
+          var += 1;
+        }
+
+        let condition = self.invoke_with_arguments(pred.as_ref()?, vars_and_args.clone())?;
+
+        if !self.convert_to_parameter("Z", condition)?.as_bool()? {
+          return self.invoke_with_arguments(fini.as_ref()?, vars_and_args);
+        }
+      }
+    }
+  }
+
+  fn get_method_handle_descriptor(&self, method_handle_ref: ju4) -> Result<String> {
+    let method_type_ref = self
+      .heap
+      .get_obj_instance(method_handle_ref)?
+      .get_field("type")?
+      .as_ref()?;
+
+    self.get_method_type_descriptor(method_type_ref)
+  }
+
+  fn get_method_handle_return_descriptor(&self, method_handle_ref: ju4) -> Result<String> {
+    let descriptor = self.get_method_handle_descriptor(method_handle_ref)?;
+
+    Ok(get_return_descriptor(&descriptor).to_string())
+  }
+
+  // elements of an array of arguments, a null array stands for no arguments
+  fn get_array_arguments(&self, array: types::Type) -> Result<Vec<types::Type>> {
+    match array {
+      types::Type::Null => Ok(vec![]),
+      array => Ok(
+        self
+          .heap
+          .get_array_instance(array.as_ref()?)?
+          .get_elements()
+          .clone(),
+      ),
+    }
+  }
+
+  /// Conversion of a value of the type `from` to the type `to` applied by
+  /// `MethodHandle.asType` (casts, boxing, unboxing and widening)
+  pub(crate) fn convert_as_type(
+    &mut self,
+    from: &str,
+    to: &str,
+    value: types::Type,
+  ) -> Result<types::Type> {
+    if from == to {
+      return Ok(value);
+    }
+
+    let wrong_type = || {
+      eyre!(errors::JavaException::WrongMethodTypeException(format!(
+        "cannot convert {} to {}",
+        from, to
+      )))
+    };
+
+    match (
+      types::Type::is_primitive(from),
+      types::Type::is_primitive(to),
+    ) {
+      (true, true) => types::Type::widen_primitive(from, to, value)?.ok_or_else(wrong_type),
+      (true, false) => {
+        let boxed = self.heap.alloc_boxed(&mut self.class_loader, from, value)?;
+
+        match self.is_instance_of(boxed.as_ref()?, &get_descriptor_classname(to))? {
+          true => Ok(boxed),
+          false => Err(wrong_type()),
+        }
+      }
+      (false, true) => {
+        let value_ref = match value {
+          types::Type::Null => return Err(eyre!(errors::JavaException::NullPointer)),
+          value => value.as_ref()?,
+        };
+
+        let unboxed = match self.heap.get_boxed_value(value_ref)? {
+          Some((primitive, value)) => types::Type::widen_primitive(primitive, to, value)?,
+          None => None,
+        };
+
+        unboxed.ok_or_else(|| {
+          let classname = self
+            .heap
+            .get_instance(value_ref)
+            .map(|instance| instance.get_classname().to_string())
+            .unwrap_or_default();
+
+          eyre!(errors::JavaException::ClassCastException(format!(
+            "Cannot cast {} to {}",
+            classname, to
+          )))
+        })
+      }
+      (false, false) => {
+        let classname = get_descriptor_classname(to);
+
+        match value {
+          types::Type::ObjectRef(value_ref) | types::Type::ArrayRef(value_ref)
+            if !self.is_instance_of(value_ref, &classname)? =>
+          {
+            Err(eyre!(errors::JavaException::ClassCastException(format!(
+              "Cannot cast {} to {}",
+              self.heap.get_instance(value_ref)?.get_classname(),
+              classname
+            ))))
+          }
+          value => Ok(value),
+        }
+      }
+    }
+  }
+
+  // value passed for a parameter of the type `param_type`: constants of the forms are boxed,
+  // the values of basic type `I` are narrowed to the subword types
+  fn convert_to_parameter(&mut self, param_type: &str, value: types::Type) -> Result<types::Type> {
+    if !types::Type::is_primitive(param_type) {
+      return match value.get_primitive_descriptor() {
+        Some(primitive) => self
+          .heap
+          .alloc_boxed(&mut self.class_loader, primitive, value),
+        None => Ok(value),
+      };
+    }
+
+    let value = match value {
+      types::Type::ObjectRef(value_ref) => match self.heap.get_boxed_value(value_ref)? {
+        Some((_, value)) => value,
+        None => {
+          return Err(eyre!(errors::JavaException::ClassCastException(format!(
+            "Cannot cast {} to {}",
+            self.heap.get_instance(value_ref)?.get_classname(),
+            param_type
+          ))))
+        }
+      },
+      types::Type::Null => return Err(eyre!(errors::JavaException::NullPointer)),
+      value => value,
+    };
+
+    match param_type {
+      // `Character` only holds 8 bits, chars are kept as ints like the interpreter does
+      "C" => Ok(types::Type::Integer(value.as_integer()? & 0xffff)),
+      param_type => types::Type::to_stored_value(param_type, value),
+    }
+  }
+
+  /// Is the object `obj_ref` an instance of the class `classname` ?
+  fn is_instance_of(&mut self, obj_ref: ju4, classname: &str) -> Result<bool> {
+    let obj_classname = self.heap.get_instance(obj_ref)?.get_classname().to_string();
+
+    is_assignable(&mut self.class_loader, classname, &obj_classname)
+  }
+}
+
+// is the class `right` assignable to the class `left` ? (arrays included)
+pub(crate) fn is_assignable(loader: &mut ClassLoader, left: &str, right: &str) -> Result<bool> {
+  if left == right || left == "java/lang/Object" {
+    return Ok(true);
+  }
+
+  match (left.strip_prefix('['), right.strip_prefix('[')) {
+    (Some(left), Some(right)) => {
+      let is_reference = |component: &str| component.starts_with('L') || component.starts_with('[');
+
+      if is_reference(left) && is_reference(right) {
+        is_assignable(
+          loader,
+          &get_descriptor_classname(left),
+          &get_descriptor_classname(right),
+        )
+      } else {
+        Ok(left == right)
+      }
+    }
+    (None, Some(_)) => Ok(left == "java/lang/Cloneable" || left == "java/io/Serializable"),
+    (Some(_), None) => Ok(false),
+    (None, None) => types::Type::check_type(loader, left, right),
+  }
+}
+
+// class of a field descriptor (e.g. `java/lang/String` for `Ljava/lang/String;`), arrays and
+// primitives are left as they are
+fn get_descriptor_classname(descriptor: &str) -> String {
+  match descriptor.strip_prefix('L') {
+    Some(classname) => classname.trim_end_matches(';').to_string(),
+    None => descriptor.to_string(),
+  }
+}
+
+fn get_parameter_descriptors(descriptor: &str) -> Vec<String> {
+  parse_parameter_types(get_parameters_type_descriptor(descriptor))
+    .into_iter()
+    .map(str::to_string)
+    .collect()
+}
+
+// return type of a method descriptor as a field descriptor (e.g. `Ljava/lang/String;`)
+fn get_return_descriptor(descriptor: &str) -> &str {
+  match descriptor.rfind(')') {
+    Some(end) => &descriptor[end + 1..],
+    None => descriptor,
+  }
+}
+
+fn get_receiver_ref(receiver: types::Type) -> Result<ju4> {
+  match receiver {
+    types::Type::ObjectRef(receiver_ref) | types::Type::ArrayRef(receiver_ref) => Ok(receiver_ref),
+    types::Type::Null => Err(eyre!(errors::JavaException::NullPointer)),
+    receiver => Err(eyre!(errors::InternalError::WrongType(
+      "Reference",
+      receiver
+    ))),
+  }
+}
+
+fn get_array_index(index: i32, len: usize) -> Result<usize> {
+  match usize::try_from(index) {
+    Ok(index) if index < len => Ok(index),
+    _ => Err(eyre!(errors::JavaException::ArrayIndexOutOfBounds(
+      index.max(0) as usize,
+      len
+    ))),
+  }
+}
@@ -1,25 +1,60 @@
-use crate::{runtime::jvm::*, utils::get_argument_classnames};
+use crate::runtime::jvm::*;
 use color_eyre::eyre::{eyre, Result};
 use log::{debug, warn};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::{
-  class_loader::{class_file::InvokeDynamicResolved, constant_pool::CpInfoInfoEnum},
-  runtime::{errors, types},
-  utils::{ju2, ju4},
+  class_loader::class_file::InvokeDynamicResolved,
+  runtime::{
+    errors,
+    lambdametafactory::{Lambda, LambdaFactory},
+    objectmethods::ObjectMethod,
+    stringconcat::StringConcat,
+    switchbootstraps::TypeSwitch,
+    types,
+  },
+  utils::{
+    descriptor_to_classname, get_parameters_type_descriptor, ju2, ju4, parse_parameter_types,
+  },
 };
 
+#[derive(Debug, Clone)]
+pub(crate) enum CallSite {
+  // call site ref in the heap returned by the bootstrap method
+  Object(ju4),
+  // bootstrap of `StringConcatFactory` linked to the native concatenation
+  StringConcat(StringConcat),
+  // bootstrap of `LambdaMetafactory` linked to a lambda class of the VM
+  Lambda(LambdaFactory),
+  // bootstrap of `SwitchBootstraps` linked to the native matching of the labels
+  Switch(TypeSwitch),
+  // bootstrap of `ObjectMethods` linked to the native methods of a record
+  ObjectMethod(ObjectMethod),
+}
+
 #[derive(Debug)]
 pub(crate) struct LambdaManager {
   // key: class | method name | bytecode offset
-  // value: call site ref in the heap
-  callsites: HashMap<String, ju4>,
+  // value: linked call site
+  callsites: HashMap<String, CallSite>,
+  // key: lambda class
+  // value: implementation of its methods
+  lambdas: HashMap<String, Lambda>,
+  // key: class | constant pool index
+  // value: resolved dynamically-computed constant, or the message of the
+  // BootstrapMethodError of its failed resolution
+  dynamic_constants: HashMap<String, std::result::Result<types::Type, String>>,
+  // dynamic constants whose bootstrap method is running
+  resolving: HashSet<String>,
 }
 
 impl LambdaManager {
   pub(crate) fn new() -> Self {
     LambdaManager {
       callsites: HashMap::new(),
+      lambdas: HashMap::new(),
+      dynamic_constants: HashMap::new(),
+      resolving: HashSet::new(),
     }
   }
 
@@ -28,14 +63,14 @@ impl LambdaManager {
     classname: &str,
     method_name: &str,
     offset: usize,
-    callsite_ref: ju4,
+    callsite: CallSite,
   ) {
     if self.get_call_site(classname, method_name, offset).is_some() {
       return;
     }
 
     let key = LambdaManager::gen_key(classname, method_name, offset);
-    self.callsites.insert(key, callsite_ref);
+    self.callsites.insert(key, callsite);
   }
 
   pub(crate) fn get_call_site(
@@ -43,9 +78,69 @@ impl LambdaManager {
     classname: &str,
     method_name: &str,
     offset: usize,
-  ) -> Option<ju4> {
+  ) -> Option<&CallSite> {
     let key = LambdaManager::gen_key(classname, method_name, offset);
-    self.callsites.get(&key).copied()
+    self.callsites.get(&key)
+  }
+
+  pub(crate) fn add_lambda(&mut self, classname: &str, lambda: Lambda) {
+    self.lambdas.insert(classname.to_string(), lambda);
+  }
+
+  pub(crate) fn get_lambda(&self, classname: &str) -> Option<&Lambda> {
+    self.lambdas.get(classname)
+  }
+
+  pub(crate) fn add_dynamic_constant(
+    &mut self,
+    classname: &str,
+    index: ju2,
+    value: std::result::Result<types::Type, String>,
+  ) {
+    self
+      .dynamic_constants
+      .insert(format!("{}_{}", classname, index), value);
+  }
+
+  pub(crate) fn get_dynamic_constant(
+    &self,
+    classname: &str,
+    index: ju2,
+  ) -> Option<std::result::Result<types::Type, String>> {
+    self
+      .dynamic_constants
+      .get(&format!("{}_{}", classname, index))
+      .cloned()
+  }
+
+  /// Objects kept by the linked call sites and the resolved dynamic constants
+  pub(crate) fn get_references(&self) -> Vec<types::Type> {
+    let mut references = vec![];
+
+    for callsite in self.callsites.values() {
+      match callsite {
+        CallSite::Object(callsite_ref) => references.push(types::Type::ObjectRef(*callsite_ref)),
+        CallSite::Lambda(factory) => {
+          if let Some(instance) = factory.get_instance() {
+            references.push(types::Type::ObjectRef(instance));
+          }
+        }
+        _ => {}
+      }
+    }
+
+    references.extend(self.dynamic_constants.values().flatten().copied());
+
+    references
+  }
+
+  /// Mark the dynamic constant as being resolved, false if it already is
+  pub(crate) fn start_resolution(&mut self, classname: &str, index: ju2) -> bool {
+    self.resolving.insert(format!("{}_{}", classname, index))
+  }
+
+  pub(crate) fn end_resolution(&mut self, classname: &str, index: ju2) {
+    self.resolving.remove(&format!("{}_{}", classname, index));
   }
 
   fn gen_key(classname: &str, method_name: &str, offset: usize) -> String {
@@ -93,32 +188,262 @@ impl JVM {
     Ok(types::Type::ObjectRef(method_handles_lookup_ref))
   }
 
-  fn resolve_bootstrap_argument(&mut self, classname: &str, index: ju2) -> Result<types::Type> {
-    let cpinfo = self
-      .class_loader
-      .get(classname)?
-      .resolve_index(index)?
-      .clone();
-    match cpinfo.get_info() {
-      CpInfoInfoEnum::MethodHandle(_) => {
-        let method_handle_resolved = self.class_loader.resolve_method_handle(classname, index)?;
-        self.create_method_handle(classname, method_handle_resolved)
+  /// Adapt the arguments to the parameters of the bootstrap method: primitives are boxed or
+  /// unboxed, and the trailing arguments of a variable arity bootstrap method are collected in
+  /// an array
+  fn adapt_bootstrap_arguments(
+    &mut self,
+    bootstrap_type: &str,
+    mut args: Vec<types::Type>,
+  ) -> Result<Vec<types::Type>> {
+    let param_types = parse_parameter_types(get_parameters_type_descriptor(bootstrap_type));
+
+    if let Some(last) = param_types.last().filter(|last| last.starts_with('[')) {
+      let fixed = param_types.len() - 1;
+
+      let collect = args.len() != param_types.len()
+        || !matches!(
+          args.last(),
+          Some(types::Type::ArrayRef(_)) | Some(types::Type::Null)
+        );
+
+      if collect && args.len() >= fixed {
+        let component = descriptor_to_classname(&last[1..]);
+
+        let mut elements = vec![];
+
+        for arg in args.split_off(fixed) {
+          elements.push(self.adapt_bootstrap_argument(&last[1..], arg)?);
+        }
+
+        args.push(self.heap.alloc_array(&component, elements, 0)?);
       }
-      CpInfoInfoEnum::MethodType(_) => {
-        let method_type_resolved = self.class_loader.resolve_method_type(classname, index)?;
-        self.create_method_type(&method_type_resolved)
+    }
+
+    if args.len() != param_types.len() {
+      return Err(eyre!(errors::JavaException::BootstrapMethodError(format!(
+        "Bootstrap method {} called with {} arguments",
+        bootstrap_type,
+        args.len()
+      ))));
+    }
+
+    let mut locals = vec![];
+
+    for (param_type, arg) in param_types.iter().zip(args) {
+      let value = self.adapt_bootstrap_argument(param_type, arg)?;
+
+      value.push_to_locals(&mut locals);
+    }
+
+    Ok(locals)
+  }
+
+  // primitive values are widened or boxed, boxes are unboxed then widened
+  fn adapt_bootstrap_argument(
+    &mut self,
+    param_type: &str,
+    arg: types::Type,
+  ) -> Result<types::Type> {
+    let (arg_type, value) = match arg {
+      types::Type::ObjectRef(arg_ref) if types::Type::is_primitive(param_type) => {
+        match self.heap.get_boxed_value(arg_ref)? {
+          Some((arg_type, value)) => (arg_type.to_string(), value),
+          None => {
+            let classname = self.heap.get_instance(arg_ref)?.get_classname().to_string();
+
+            return Err(eyre!(errors::JavaException::BootstrapMethodError(format!(
+              "Cannot convert {} to {}",
+              classname, param_type
+            ))));
+          }
+        }
+      }
+      types::Type::Null if types::Type::is_primitive(param_type) => {
+        return Err(eyre!(errors::JavaException::BootstrapMethodError(format!(
+          "Cannot convert null to {}",
+          param_type
+        ))))
+      }
+      arg => match arg.get_primitive_descriptor() {
+        Some(arg_type) if !types::Type::is_primitive(param_type) => {
+          return self.heap.alloc_boxed(&mut self.class_loader, arg_type, arg)
+        }
+        Some(arg_type) => (arg_type.to_string(), arg),
+        None => return Ok(arg),
+      },
+    };
+
+    types::Type::widen_primitive(&arg_type, param_type, value)?.ok_or_else(|| {
+      eyre!(errors::JavaException::BootstrapMethodError(format!(
+        "Cannot convert {} to {}",
+        arg_type, param_type
+      )))
+    })
+  }
+
+  /// Invoke the bootstrap method, the exceptions it throws are wrapped in a
+  /// `BootstrapMethodError` unless they are errors
+  fn call_bootstrap_method(
+    &mut self,
+    resolved: &InvokeDynamicResolved,
+    args: Vec<types::Type>,
+  ) -> Result<types::Type> {
+    let args = self.adapt_bootstrap_arguments(resolved.get_bootstrap_method_type(), args)?;
+
+    debug!("{}", resolved);
+    debug!("BOOTSTRAP ARGS {:?}", args);
+
+    // TODO: call based on ref_kind
+    let result = self.call_and_resolve_method(
+      resolved.get_bootstrap_class_name(),
+      resolved.get_bootstrap_method_name(),
+      resolved.get_bootstrap_method_type(),
+      args,
+    );
+
+    let err = match result {
+      Ok(value) => return Ok(value),
+      Err(err) => err,
+    };
+
+    let exception = match err.downcast_ref::<errors::JavaException>() {
+      Some(exception) => exception,
+      None => return Err(err),
+    };
+
+    let classname = errors::JavaException::convert_java_exception_to_classname(exception);
+
+    if types::Type::check_type(&mut self.class_loader, "java/lang/Error", classname)? {
+      return Err(err);
+    }
+
+    Err(eyre!(errors::JavaException::BootstrapMethodError(format!(
+      "bootstrap method initialization exception: {}",
+      exception
+    ))))
+  }
+
+  /// Resolve the dynamically-computed constant at `index` in the constant pool of `classname`
+  ///
+  /// The bootstrap method is invoked once with the lookup of the class, the name and the
+  /// type of the constant and its static arguments, and the result is kept for the next
+  /// resolutions, a resolution that failed with a `BootstrapMethodError` fails again with the
+  /// same error. A constant whose static arguments depend on itself cannot be resolved.
+  pub(crate) fn resolve_dynamic_constant(
+    &mut self,
+    classname: &str,
+    index: ju2,
+  ) -> Result<types::Type> {
+    match self.lambdamanager.get_dynamic_constant(classname, index) {
+      Some(Ok(constant)) => return Ok(constant),
+      Some(Err(message)) => {
+        return Err(eyre!(errors::JavaException::BootstrapMethodError(message)))
+      }
+      None => {}
+    }
+
+    if !self.lambdamanager.start_resolution(classname, index) {
+      return Err(eyre!(errors::JavaException::StackOverflowError(format!(
+        "Cycle in the resolution of the dynamic constant #{} of {}",
+        index, classname
+      ))));
+    }
+
+    let constant = self.exec_dynamic_constant_bootstrap(classname, index);
+
+    self.lambdamanager.end_resolution(classname, index);
+
+    let constant = match constant {
+      Ok(constant) => constant,
+      Err(err) => {
+        if let Some(errors::JavaException::BootstrapMethodError(message)) = err.downcast_ref() {
+          self
+            .lambdamanager
+            .add_dynamic_constant(classname, index, Err(message.clone()));
+        }
+
+        return Err(err);
       }
-      CpInfoInfoEnum::String(info) => {
-        let string = self
-          .class_loader
-          .resolve_string(classname, info.get_string_index())?;
-        self.heap.alloc_string(&mut self.class_loader, &string)
+    };
+
+    self
+      .lambdamanager
+      .add_dynamic_constant(classname, index, Ok(constant));
+
+    Ok(constant)
+  }
+
+  fn exec_dynamic_constant_bootstrap(
+    &mut self,
+    classname: &str,
+    index: ju2,
+  ) -> Result<types::Type> {
+    let resolved = self.class_loader.get(classname)?.resolve_dynamic(index)?;
+
+    let descriptor = resolved.get_method_type();
+
+    let type_name = if types::Type::is_primitive(descriptor) {
+      descriptor.to_string()
+    } else {
+      self.resolve_class(classname, &descriptor_to_classname(descriptor))?
+    };
+
+    let lookup = self.create_method_handles_lookup(classname)?;
+    let name = self
+      .heap
+      .alloc_string(&mut self.class_loader, resolved.get_method_name())?;
+    let type_class = self
+      .heap
+      .alloc_class_obj(&mut self.class_loader, &type_name)?;
+
+    let mut args = vec![lookup, name, type_class];
+
+    for arg in resolved.get_arguments() {
+      args.push(self.load_constant(classname, *arg)?);
+    }
+
+    let value = self.call_bootstrap_method(&resolved, args)?;
+
+    debug!(
+      "DYNAMIC CONSTANT {} {:?}",
+      resolved.get_method_name(),
+      value
+    );
+
+    self.convert_dynamic_constant(descriptor, &type_name, value)
+  }
+
+  // the value returned by the bootstrap method is converted to the type of the constant
+  fn convert_dynamic_constant(
+    &mut self,
+    descriptor: &str,
+    type_name: &str,
+    value: types::Type,
+  ) -> Result<types::Type> {
+    let value_ref = match value {
+      types::Type::ObjectRef(value_ref) | types::Type::ArrayRef(value_ref)
+        if !types::Type::is_primitive(descriptor) =>
+      {
+        value_ref
       }
-      _ => Err(eyre!(errors::InternalError::General(format!(
-        "Bootstrap argument not supported: {}",
-        cpinfo.get_info()
-      )))),
+      value => return self.adapt_bootstrap_argument(descriptor, value),
+    };
+
+    let value_class = self
+      .heap
+      .get_instance(value_ref)?
+      .get_classname()
+      .to_string();
+
+    if !types::Type::check_type(&mut self.class_loader, type_name, &value_class)? {
+      return Err(eyre!(errors::JavaException::BootstrapMethodError(format!(
+        "Dynamic constant of type {} bound to {}",
+        descriptor, value_class
+      ))));
     }
+
+    Ok(value)
   }
 
   pub(crate) fn exec_invokedynamic_newcallsite(
@@ -149,77 +474,83 @@ impl JVM {
 
     let mut factory_args = vec![method_lookup, method_name_ref, method_descriptor_ref];
     for arg in invoke_dynamic_resolved.get_arguments() {
-      factory_args.push(self.resolve_bootstrap_argument(curr_class_name, *arg)?);
+      factory_args.push(self.load_constant(curr_class_name, *arg)?);
     }
 
-    let bootstrap_arg_classes =
-      get_argument_classnames(invoke_dynamic_resolved.get_bootstrap_method_type());
+    // And then create the CallSite using the bootstrap method
 
-    // for variadic (...Object) if is is empty, it is not written in the arguments
-    // of the bootstrap method, but we still need it
-    for _ in factory_args.len()..bootstrap_arg_classes.len() {
-      // assuming it's the only case possible
-      let empty_array = self.heap.alloc_array("java/lang/Object", vec![], 0)?;
-      factory_args.push(empty_array);
-    }
-
-    // And then create the CallSite using the java/lang/invoke/LambdaMetafactory
-    // just obtained
-
-    debug!("{}", invoke_dynamic_resolved);
-    debug!("FACTORY ARGS {:?}", factory_args);
-
-    // TODO: call based on ref_kind
     let callsite_ref = self
-      .call_and_resolve_method(
-        invoke_dynamic_resolved.get_bootstrap_class_name(),
-        invoke_dynamic_resolved.get_bootstrap_method_name(),
-        invoke_dynamic_resolved.get_bootstrap_method_type(),
-        factory_args,
-      )?
+      .call_bootstrap_method(invoke_dynamic_resolved, factory_args)?
       .as_ref()?;
 
     Ok(callsite_ref)
   }
 
-  fn invoke_constant_callsite(
+  // the arguments of the call site are passed to the target of the CallSite, like
+  // `invokeExact` does
+  fn invoke_callsite_target(
     &mut self,
-    callsite_obj: &types::ObjectInstance,
-  ) -> Result<types::Type> {
-    let target_ref = callsite_obj.get_field("target")?.as_ref()?; // MethodHandle
-    let target = self.heap.get_obj_instance(target_ref)?;
+    callsite_ref: ju4,
+    method_type: &str,
+  ) -> Result<Option<types::Type>> {
+    let callsite = self.heap.get_obj_instance(callsite_ref)?;
+    let classname = callsite.get_classname().to_string();
 
-    let target_type_ref = target.get_field("type")?.as_ref()?; // MethodType
-    let target_type = self.heap.get_obj_instance(target_type_ref)?;
-    let ptypes_ref = target_type.get_field("ptypes")?.as_ref()?; // Array of Class<?>
-    let ptypes_len = self
-      .heap
-      .get_array_instance(ptypes_ref)?
-      .get_elements()
-      .len();
+    if !types::Type::check_type(
+      &mut self.class_loader,
+      "java/lang/invoke/CallSite",
+      &classname,
+    )? {
+      return Err(eyre!(errors::JavaException::BootstrapMethodError(format!(
+        "{} is not a CallSite",
+        classname
+      ))));
+    }
+
+    let target_ref = callsite.get_field("target")?.as_ref()?;
+
+    debug!("CALLSITE {} {}", classname, method_type);
+
+    let args = self.pop_polymorphic_arguments(method_type)?;
+    let result = self.invoke_method_handle_with_type(target_ref, method_type, args, true)?;
+
+    self.push_polymorphic_result(method_type, result)?;
+
+    Ok(None)
+  }
+
+  // the bootstraps of the JDK known by the VM are linked without being run
+  fn link_call_site(
+    &mut self,
+    curr_class_name: &str,
+    invoke_dynamic_resolved: &InvokeDynamicResolved,
+  ) -> Result<CallSite> {
+    if JVM::is_string_concat_bootstrap(invoke_dynamic_resolved) {
+      return Ok(CallSite::StringConcat(
+        self.link_string_concat(curr_class_name, invoke_dynamic_resolved)?,
+      ));
+    }
 
-    let target_classname = target.get_classname().to_owned();
-    let mut invoke_type: String = "(".to_string();
-    let mut args = vec![];
+    if JVM::is_lambda_metafactory_bootstrap(invoke_dynamic_resolved) {
+      return self.link_lambda(curr_class_name, invoke_dynamic_resolved);
+    }
 
-    for _ in 0..ptypes_len {
-      args.push(self.pop_stack()?);
-      invoke_type += "Ljava/lang/Object;";
+    if JVM::is_switch_bootstrap(invoke_dynamic_resolved) {
+      return Ok(CallSite::Switch(
+        self.link_switch(curr_class_name, invoke_dynamic_resolved)?,
+      ));
     }
-    args.push(types::Type::ObjectRef(target_ref));
-    args.reverse();
-    invoke_type += ")Ljava/lang/Object;";
 
-    debug!(
-      "CALLSITE {} {} {} {:?}",
-      target_classname,
-      self.heap.get_obj_instance(target_type_ref)?,
-      invoke_type,
-      args
-    );
+    if JVM::is_object_methods_bootstrap(invoke_dynamic_resolved) {
+      return Ok(CallSite::ObjectMethod(
+        self.link_object_method(curr_class_name, invoke_dynamic_resolved)?,
+      ));
+    }
 
-    let result_ref = self.call_method_handle(target_ref, &mut args)?;
-    Ok(result_ref)
+    Ok(CallSite::Object(self.exec_invokedynamic_newcallsite(
+      curr_class_name,
+      invoke_dynamic_resolved,
+    )?))
   }
 
   pub(crate) fn exec_invokedynamic(&mut self) -> Result<Option<types::Type>> {
@@ -231,6 +562,12 @@ impl JVM {
 
     let current_pc = self.get_current_frame_mut()?.get_pc();
 
+    // call sites at the same offset of different methods are different
+    let curr_method = {
+      let frame = self.get_current_frame_mut()?;
+      format!("{}{}", frame.get_method_name(), frame.get_method_type())
+    };
+
     let curr_class_name = self.get_current_class()?.get_name().to_owned();
 
     let invoke_dynamic_resolved = self
@@ -241,39 +578,48 @@ impl JVM {
 
     // we need a factory
     // generate if it does not exist for this invokedynamic
-    let callsite_ref = match self.lambdamanager.get_call_site(
-      &curr_class_name,
-      invoke_dynamic_resolved.get_method_name(),
-      current_pc,
-    ) {
-      Some(v) => v,
-      _ => {
-        let callsite_ref =
-          self.exec_invokedynamic_newcallsite(&curr_class_name, &invoke_dynamic_resolved)?;
-        self.lambdamanager.add_call_site(
-          &curr_class_name,
-          invoke_dynamic_resolved.get_method_name(),
-          current_pc,
-          callsite_ref,
-        );
-        callsite_ref
+    let callsite =
+      match self
+        .lambdamanager
+        .get_call_site(&curr_class_name, &curr_method, current_pc)
+      {
+        Some(v) => v.clone(),
+        _ => {
+          let callsite = self.link_call_site(&curr_class_name, &invoke_dynamic_resolved)?;
+          self.lambdamanager.add_call_site(
+            &curr_class_name,
+            &curr_method,
+            current_pc,
+            callsite.clone(),
+          );
+          callsite
+        }
+      };
+
+    let callsite_ref = match callsite {
+      CallSite::Object(callsite_ref) => callsite_ref,
+      CallSite::StringConcat(concat) => {
+        let string_ref = self.invoke_string_concat(&concat)?;
+        self.push_stack(string_ref)?;
+        return Ok(None);
       }
-    };
-
-    // use the factory to create the runnable instance
-    let callsite = self.heap.get_obj_instance(callsite_ref)?.clone();
-    let runnable_ref = match callsite.get_classname() {
-      "java/lang/invoke/ConstantCallSite" => self.invoke_constant_callsite(&callsite)?,
-      v => {
-        return Err(eyre!(errors::InternalError::General(format!(
-          "{} callsite not supported",
-          v
-        ))))
+      CallSite::Lambda(factory) => {
+        let lambda_ref = self.invoke_lambda_factory(&factory)?;
+        self.push_stack(lambda_ref)?;
+        return Ok(None);
+      }
+      CallSite::Switch(switch) => {
+        let index = self.invoke_switch(&switch)?;
+        self.push_stack(index)?;
+        return Ok(None);
+      }
+      CallSite::ObjectMethod(method) => {
+        let result = self.invoke_object_method(&method)?;
+        self.push_stack(result)?;
+        return Ok(None);
       }
     };
 
-    // return the runnable instance
-    self.push_stack(runnable_ref)?;
-    Ok(None)
+    self.invoke_callsite_target(callsite_ref, invoke_dynamic_resolved.get_method_type())
   }
 }
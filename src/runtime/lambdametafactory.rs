@@ -0,0 +1,583 @@
+use color_eyre::eyre::{eyre, Result};
+use log::debug;
+use std::collections::HashSet;
+
+use crate::{
+  class_loader::{
+    builder::ClassBuilder, class_file::InvokeDynamicResolved, class_file::MethodHandleResolved,
+    constant_pool::CpInfoInfoEnum, loader::ClassLoader,
+  },
+  runtime::{
+    constants::{
+      FLAG_BRIDGES, FLAG_MARKERS, FLAG_SERIALIZABLE, REF_INVOKE_INTERFACE, REF_INVOKE_SPECIAL,
+      REF_INVOKE_STATIC, REF_INVOKE_VIRTUAL, REF_NEW_INVOKE_SPECIAL,
+    },
+    errors,
+    jvm::JVM,
+    lambdamanager::CallSite,
+    types,
+  },
+  utils::{
+    descriptor_to_classname, get_parameters_type_descriptor, get_return_type_descriptor, ju2, ju4,
+    parse_parameter_types,
+  },
+};
+
+const LAMBDA_METAFACTORY: &str = "java/lang/invoke/LambdaMetafactory";
+
+/// Implementation of the functional interface of a lambda class
+#[derive(Debug, Clone)]
+pub(crate) struct Lambda {
+  // class of the invokedynamic
+  caller: String,
+  interface: String,
+  sam_name: String,
+  sam_type: String,
+  implementation: MethodHandleResolved,
+  instantiated_type: String,
+  // descriptors of the captured arguments, stored in the fields `arg$1`, `arg$2`...
+  captured_types: Vec<String>,
+}
+
+/// Call site of `LambdaMetafactory` creating instances of a lambda class
+#[derive(Debug, Clone)]
+pub(crate) struct LambdaFactory {
+  class_name: String,
+  captured_types: Vec<String>,
+  // a non-capturing lambda always evaluates to the same instance
+  instance: Option<ju4>,
+}
+
+impl LambdaFactory {
+  pub(crate) fn get_instance(&self) -> Option<ju4> {
+    self.instance
+  }
+}
+
+fn parameter_types(descriptor: &str) -> Vec<String> {
+  parse_parameter_types(get_parameters_type_descriptor(descriptor))
+    .into_iter()
+    .map(str::to_string)
+    .collect()
+}
+
+fn return_type(descriptor: &str) -> &str {
+  descriptor.split(')').nth(1).unwrap_or("V")
+}
+
+impl JVM {
+  pub(crate) fn is_lambda_metafactory_bootstrap(
+    invoke_dynamic_resolved: &InvokeDynamicResolved,
+  ) -> bool {
+    invoke_dynamic_resolved.get_bootstrap_class_name() == LAMBDA_METAFACTORY
+      && matches!(
+        invoke_dynamic_resolved.get_bootstrap_method_name(),
+        "metafactory" | "altMetafactory"
+      )
+  }
+
+  /// Link the call site to a lambda class synthesized by the VM instead of running the
+  /// bootstrap method
+  ///
+  /// The lambda class is a hidden nestmate of `curr_class_name` implementing the functional
+  /// interface (and the markers), with the captured arguments as fields. Its methods are
+  /// native and call the implementation method, see `invoke_lambda`.
+  pub(crate) fn link_lambda(
+    &mut self,
+    curr_class_name: &str,
+    invoke_dynamic_resolved: &InvokeDynamicResolved,
+  ) -> Result<CallSite> {
+    let linkage_error = |msg: &str| {
+      eyre!(errors::JavaException::BootstrapMethodError(format!(
+        "{} in {}",
+        msg, curr_class_name
+      )))
+    };
+
+    let args = invoke_dynamic_resolved.get_arguments();
+
+    if args.len() < 3 {
+      return Err(linkage_error("Missing arguments of the lambda metafactory"));
+    }
+
+    let sam_type = self
+      .class_loader
+      .resolve_method_type(curr_class_name, args[0])?;
+    let implementation = self
+      .class_loader
+      .resolve_method_handle(curr_class_name, args[1])?;
+    let instantiated_type = self
+      .class_loader
+      .resolve_method_type(curr_class_name, args[2])?;
+
+    let mut flags = 0;
+    let mut markers = vec![];
+    let mut bridges = vec![];
+
+    if invoke_dynamic_resolved.get_bootstrap_method_name() == "altMetafactory" {
+      let mut args = args[3..].iter();
+      let mut next_arg = || {
+        args
+          .next()
+          .copied()
+          .ok_or_else(|| linkage_error("Missing arguments of altMetafactory"))
+      };
+
+      flags = self.resolve_lambda_int(curr_class_name, next_arg()?)?;
+
+      if flags & FLAG_MARKERS != 0 {
+        for _ in 0..self.resolve_lambda_int(curr_class_name, next_arg()?)? {
+          markers.push(self.resolve_lambda_class(curr_class_name, next_arg()?)?);
+        }
+      }
+
+      if flags & FLAG_BRIDGES != 0 {
+        for _ in 0..self.resolve_lambda_int(curr_class_name, next_arg()?)? {
+          bridges.push(
+            self
+              .class_loader
+              .resolve_method_type(curr_class_name, next_arg()?)?,
+          );
+        }
+      }
+    }
+
+    let serializable = flags & FLAG_SERIALIZABLE != 0;
+
+    let factory_type = invoke_dynamic_resolved.get_method_type();
+    let interface = get_return_type_descriptor(factory_type);
+    let captured_types = parameter_types(factory_type);
+
+    // ACC_FINAL | ACC_SUPER | ACC_SYNTHETIC
+    let mut builder = ClassBuilder::new(
+      0x1030,
+      &format!(
+        "{}$$Lambda",
+        ClassLoader::strip_hidden_suffix(&ClassLoader::get_binary_name(curr_class_name))
+      ),
+      Some("java/lang/Object"),
+    );
+
+    let mut interfaces = vec![interface.clone()];
+    interfaces.extend(markers);
+    if serializable {
+      interfaces.push("java/io/Serializable".to_string());
+    }
+
+    // a marker may repeat any of the interfaces, not only the previous one
+    let mut seen = HashSet::new();
+    interfaces.retain(|interface| seen.insert(interface.clone()));
+
+    for interface in &interfaces {
+      builder.interface(interface);
+    }
+
+    for (i, captured_type) in captured_types.iter().enumerate() {
+      // ACC_PRIVATE | ACC_FINAL
+      builder.field(0x0012, &format!("arg${}", i + 1), captured_type);
+    }
+
+    let sam_name = invoke_dynamic_resolved.get_method_name();
+
+    let mut method_types = vec![sam_type.clone()];
+    for bridge in bridges {
+      if !method_types.contains(&bridge) {
+        method_types.push(bridge);
+      }
+    }
+
+    for method_type in &method_types {
+      // ACC_PUBLIC | ACC_NATIVE
+      builder.method(0x0101, sam_name, method_type, None);
+    }
+
+    if serializable {
+      // ACC_PRIVATE | ACC_FINAL | ACC_NATIVE
+      builder.method(0x0112, "writeReplace", "()Ljava/lang/Object;", None);
+    }
+
+    let loader = ClassLoader::get_defining_loader(curr_class_name);
+
+    let class_name = self.define_hidden_class(loader, curr_class_name, &builder.build()?, true)?;
+
+    debug!(
+      "LAMBDA {} {} {} -> {}",
+      class_name,
+      sam_name,
+      sam_type,
+      implementation.get_method_name()
+    );
+
+    self.lambdamanager.add_lambda(
+      &class_name,
+      Lambda {
+        caller: curr_class_name.to_string(),
+        interface,
+        sam_name: sam_name.to_string(),
+        sam_type,
+        implementation,
+        instantiated_type,
+        captured_types: captured_types.clone(),
+      },
+    );
+
+    self.init_class(&class_name)?;
+
+    let instance = if captured_types.is_empty() {
+      Some(
+        self
+          .heap
+          .alloc_obj(&mut self.class_loader, &class_name)?
+          .as_ref()?,
+      )
+    } else {
+      None
+    };
+
+    Ok(CallSite::Lambda(LambdaFactory {
+      class_name,
+      captured_types,
+      instance,
+    }))
+  }
+
+  fn resolve_lambda_int(&mut self, classname: &str, index: ju2) -> Result<i32> {
+    match self
+      .class_loader
+      .get(classname)?
+      .resolve_index(index)?
+      .get_info()
+    {
+      CpInfoInfoEnum::Integer(int) => Ok(int.int()),
+      info => Err(eyre!(errors::JavaException::BootstrapMethodError(format!(
+        "Not an int argument of altMetafactory: {}",
+        info
+      )))),
+    }
+  }
+
+  fn resolve_lambda_class(&mut self, classname: &str, index: ju2) -> Result<String> {
+    let class = self.class_loader.get(classname)?;
+
+    match class.resolve_index(index)?.get_info() {
+      CpInfoInfoEnum::Class(class_info) => class.resolve_name(class_info.get_name_index()),
+      info => Err(eyre!(errors::JavaException::BootstrapMethodError(format!(
+        "Not a class argument of altMetafactory: {}",
+        info
+      )))),
+    }
+  }
+
+  /// Pop the captured arguments and push the instance of the lambda class
+  pub(crate) fn invoke_lambda_factory(&mut self, factory: &LambdaFactory) -> Result<types::Type> {
+    if let Some(instance) = factory.instance {
+      return Ok(types::Type::ObjectRef(instance));
+    }
+
+    let mut captured = vec![];
+    for _ in 0..factory.captured_types.len() {
+      captured.push(self.pop_stack()?);
+    }
+
+    captured.reverse();
+
+    let lambda = self
+      .heap
+      .alloc_obj(&mut self.class_loader, &factory.class_name)?;
+
+    let lambda_obj = self.heap.get_obj_instance_mut(lambda.as_ref()?)?;
+
+    for (i, value) in captured.into_iter().enumerate() {
+      lambda_obj.put_field(&format!("arg${}", i + 1), value)?;
+    }
+
+    Ok(lambda)
+  }
+
+  /// Native methods of a lambda class, the functional interface method and its bridges
+  ///
+  /// The arguments are appended to the captured ones and adapted to the types of the
+  /// implementation method (boxing, unboxing, widening and casts) like the classes spun by
+  /// the JDK, and so is the result.
+  pub(crate) fn invoke_lambda(
+    &mut self,
+    lambda: &Lambda,
+    name: &str,
+    descriptor: &str,
+  ) -> Result<Option<types::Type>> {
+    let arg_types = parameter_types(descriptor);
+
+    let mut args = vec![];
+    for _ in 0..arg_types.len() {
+      args.push(self.pop_stack()?);
+    }
+
+    args.reverse();
+
+    let receiver = self.pop_stack()?.as_ref()?;
+
+    let mut values = vec![];
+    for i in 0..lambda.captured_types.len() {
+      values.push(
+        self
+          .heap
+          .get_obj_instance(receiver)?
+          .get_field(&format!("arg${}", i + 1))?,
+      );
+    }
+
+    if name == "writeReplace" {
+      let serialized = self.serialize_lambda(lambda, values)?;
+
+      self.push_stack(serialized)?;
+
+      return Ok(Some(serialized));
+    }
+
+    if name != lambda.sam_name {
+      return Err(eyre!(errors::JavaException::AbstractMethodError(format!(
+        "{} {}",
+        name, descriptor
+      ))));
+    }
+
+    values.extend(args);
+
+    let mut from_types = lambda.captured_types.clone();
+    from_types.extend(arg_types);
+
+    let implementation = &lambda.implementation;
+    let ref_kind = implementation.get_ref_kind() as i32;
+    let impl_class = self.resolve_class(&lambda.caller, implementation.get_classname())?;
+    let impl_name = implementation.get_method_name();
+    let impl_type = implementation.get_method_type();
+
+    let mut to_types = parameter_types(impl_type);
+
+    if matches!(
+      ref_kind,
+      REF_INVOKE_VIRTUAL | REF_INVOKE_SPECIAL | REF_INVOKE_INTERFACE
+    ) {
+      to_types.insert(0, format!("L{};", implementation.get_classname()));
+    }
+
+    if to_types.len() != values.len() {
+      return Err(eyre!(errors::InternalError::General(format!(
+        "lambda {} {} does not match {} {}",
+        name, descriptor, impl_name, impl_type
+      ))));
+    }
+
+    let mut impl_args = vec![];
+
+    for ((from, to), value) in from_types.iter().zip(&to_types).zip(values) {
+      let value = self.adapt_lambda_value(&lambda.caller, from, to, value)?;
+
+      value.push_to_locals(&mut impl_args);
+    }
+
+    let (returned, returned_type) = match ref_kind {
+      REF_INVOKE_STATIC => {
+        self.init_class(&impl_class)?;
+
+        (
+          self.call_and_resolve_method(&impl_class, impl_name, impl_type, impl_args)?,
+          return_type(impl_type).to_string(),
+        )
+      }
+      REF_NEW_INVOKE_SPECIAL => {
+        self.init_class(&impl_class)?;
+
+        let obj = self.heap.alloc_obj(&mut self.class_loader, &impl_class)?;
+
+        impl_args.insert(0, obj);
+
+        self.call_and_resolve_method(&impl_class, impl_name, impl_type, impl_args)?;
+
+        (obj, format!("L{};", implementation.get_classname()))
+      }
+      REF_INVOKE_SPECIAL => (
+        self.call_and_resolve_method(&impl_class, impl_name, impl_type, impl_args)?,
+        return_type(impl_type).to_string(),
+      ),
+      REF_INVOKE_VIRTUAL | REF_INVOKE_INTERFACE => {
+        // dispatch on the receiver
+        let receiver_class = match impl_args[0] {
+          types::Type::ObjectRef(obj_ref) => match self.heap.get_instance(obj_ref)? {
+            types::Instance::ObjectInstance(obj) => obj.get_classname().to_string(),
+            types::Instance::ArrayInstance(_) => "java/lang/Object".to_string(),
+          },
+          types::Type::ArrayRef(_) => "java/lang/Object".to_string(),
+          _ => return Err(eyre!(errors::JavaException::NullPointer)),
+        };
+
+        (
+          self.call_and_resolve_method(&receiver_class, impl_name, impl_type, impl_args)?,
+          return_type(impl_type).to_string(),
+        )
+      }
+      _ => {
+        return Err(eyre!(errors::InternalError::General(format!(
+          "lambda implementation kind not supported: {}",
+          ref_kind
+        ))))
+      }
+    };
+
+    let expected_type = return_type(descriptor);
+
+    if expected_type == "V" {
+      return Ok(None);
+    }
+
+    let returned =
+      self.adapt_lambda_value(&lambda.caller, &returned_type, expected_type, returned)?;
+
+    self.push_stack(returned)?;
+
+    Ok(Some(returned))
+  }
+
+  // adapt `value` of the type `from` to the type `to` (descriptors)
+  fn adapt_lambda_value(
+    &mut self,
+    caller: &str,
+    from: &str,
+    to: &str,
+    value: types::Type,
+  ) -> Result<types::Type> {
+    if from == to {
+      return Ok(value);
+    }
+
+    let class_cast = |from: &str, to: &str| {
+      eyre!(errors::JavaException::ClassCastException(format!(
+        "class {} cannot be cast to class {}",
+        ClassLoader::get_java_name(from),
+        ClassLoader::get_java_name(to)
+      )))
+    };
+
+    match (
+      types::Type::is_primitive(from),
+      types::Type::is_primitive(to),
+    ) {
+      (true, true) => {
+        types::Type::widen_primitive(from, to, value)?.ok_or_else(|| class_cast(from, to))
+      }
+      // boxed to the wrapper of the primitive, which is then a subtype of `to`
+      (true, false) => self.heap.alloc_boxed(&mut self.class_loader, from, value),
+      (false, true) => {
+        let value_ref = match value {
+          types::Type::Null => return Err(eyre!(errors::JavaException::NullPointer)),
+          value => value.as_ref()?,
+        };
+
+        match self.heap.get_boxed_value(value_ref)? {
+          Some((boxed_type, boxed)) => {
+            let boxed_type = boxed_type.to_string();
+
+            types::Type::widen_primitive(&boxed_type, to, boxed)?
+              .ok_or_else(|| class_cast(&boxed_type, to))
+          }
+          None => Err(class_cast(
+            self.heap.get_instance(value_ref)?.get_classname(),
+            to,
+          )),
+        }
+      }
+      (false, false) => {
+        let value_ref = match value {
+          types::Type::Null => return Ok(value),
+          value => value.as_ref()?,
+        };
+
+        let value_class = self
+          .heap
+          .get_instance(value_ref)?
+          .get_classname()
+          .to_string();
+        let to_class = self.resolve_class(caller, &descriptor_to_classname(to))?;
+
+        if !types::Type::check_type(&mut self.class_loader, &to_class, &value_class)? {
+          return Err(class_cast(&value_class, &to_class));
+        }
+
+        Ok(value)
+      }
+    }
+  }
+
+  // `writeReplace` of a serializable lambda
+  fn serialize_lambda(
+    &mut self,
+    lambda: &Lambda,
+    captured: Vec<types::Type>,
+  ) -> Result<types::Type> {
+    let capturing_class = types::Type::ObjectRef(
+      self
+        .heap
+        .get_class_instance(&mut self.class_loader, &lambda.caller)?
+        .get_ref(),
+    );
+
+    let mut captured_args = vec![];
+    for (captured_type, value) in lambda.captured_types.iter().zip(captured) {
+      captured_args.push(if types::Type::is_primitive(captured_type) {
+        self
+          .heap
+          .alloc_boxed(&mut self.class_loader, captured_type, value)?
+      } else {
+        value
+      });
+    }
+
+    let captured_len = captured_args.len();
+    let captured_args = self
+      .heap
+      .alloc_array("java/lang/Object", captured_args, captured_len)?;
+
+    let implementation = &lambda.implementation;
+
+    let mut args = vec![capturing_class];
+    for string in [
+      lambda.interface.as_str(),
+      lambda.sam_name.as_str(),
+      lambda.sam_type.as_str(),
+    ] {
+      args.push(self.heap.alloc_string(&mut self.class_loader, string)?);
+    }
+
+    args.push(types::Type::Integer(implementation.get_ref_kind() as i32));
+
+    for string in [
+      implementation.get_classname(),
+      implementation.get_method_name(),
+      implementation.get_method_type(),
+      lambda.instantiated_type.as_str(),
+    ] {
+      args.push(self.heap.alloc_string(&mut self.class_loader, string)?);
+    }
+
+    args.push(captured_args);
+
+    let serialized_class = "java/lang/invoke/SerializedLambda";
+
+    self.init_class(serialized_class)?;
+
+    let serialized = self
+      .heap
+      .alloc_obj(&mut self.class_loader, serialized_class)?;
+
+    args.insert(0, serialized);
+
+    self.call_and_resolve_method(
+      serialized_class,
+      "<init>",
+      "(Ljava/lang/Class;Ljava/lang/String;Ljava/lang/String;Ljava/lang/String;ILjava/lang/String;Ljava/lang/String;Ljava/lang/String;Ljava/lang/String;[Ljava/lang/Object;)V",
+      args,
+    )?;
+
+    Ok(serialized)
+  }
+}
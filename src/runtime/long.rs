@@ -1,7 +1,5 @@
 use super::jvm::*;
 use crate::{
-  class_loader::constant_pool,
-  notimpl,
   runtime::{errors, types},
   utils::*,
 };
@@ -137,19 +135,16 @@ impl JVM {
   }
 
   pub(crate) fn exec_ldc2w(&mut self, index: ju2) -> Result<Option<types::Type>> {
-    let value: types::Type = {
-      let class = self.get_current_class()?;
+    let curr_class_name = self.get_current_class()?.get_name().to_owned();
 
-      let item = class.resolve_index(index)?;
+    let value = self.load_constant(&curr_class_name, index)?;
 
-      match &item.get_info() {
-        constant_pool::CpInfoInfoEnum::Long(int) => types::Type::Long(int.value() as i64),
-        constant_pool::CpInfoInfoEnum::Double(fl) => {
-          types::Type::Double(f64::from_bits(fl.value()))
-        }
-        _ => notimpl!("ldc2w not implemented for {:?}", item),
-      }
-    };
+    if value.get_category() != 2 {
+      return Err(eyre!(errors::JavaException::VerifyError(format!(
+        "Category 1 constant #{} not allowed in LDC2_W",
+        index
+      ))));
+    }
 
     self.push_stack(value)?;
 
@@ -1,22 +1,62 @@
+pub mod access;
+pub mod classloading;
 pub mod constants;
+pub mod conversion;
 pub mod double;
+pub mod embedding;
 pub mod errors;
 pub mod float;
+pub mod foreign;
 pub mod frame;
+pub mod gc;
 pub mod heap;
 pub mod integer;
+pub mod invocation;
+pub mod jni;
+pub mod jnienv;
 pub mod jvm;
+pub mod lambdaform;
 pub mod lambdamanager;
+pub mod lambdametafactory;
 pub mod long;
 pub mod modulemanager;
 pub mod nativememory;
+pub mod objectmethods;
 pub mod opcode;
 pub mod reference;
 pub mod short;
+pub mod stdio;
+pub mod stringconcat;
+pub mod switchbootstraps;
+pub mod test_access;
+pub mod test_annotations;
+pub mod test_assembler;
+pub mod test_bytebuffer;
+pub mod test_classfile;
+pub mod test_classloader;
+pub mod test_classwriter;
+pub mod test_condy;
+pub mod test_disassembler;
+pub mod test_embedding;
 pub mod test_float;
+pub mod test_foreign;
 pub mod test_general;
 pub mod test_integer;
+pub mod test_invocation;
+pub mod test_invoke;
+pub mod test_jni;
+pub mod test_lambda;
+pub mod test_methodhandle;
 pub mod test_native;
 pub mod test_opcode;
+pub mod test_proxy;
 pub mod test_reference;
+pub mod test_reflection;
+pub mod test_stringconcat;
+#[cfg(test)]
+pub(crate) mod test_support;
+pub mod test_switch;
+pub mod test_unsafe;
+pub mod test_varhandle;
+pub mod test_verifier;
 pub mod types;
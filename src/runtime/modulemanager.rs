@@ -20,6 +20,10 @@ pub(crate) struct Module {
 }
 
 impl Module {
+  pub(crate) fn get_name(&self) -> &str {
+    &self.name
+  }
+
   pub(crate) fn add_read(&mut self, mod_ref: ju4) {
     if !self.has_read(&mod_ref) {
       self.read.push(mod_ref)
@@ -72,6 +76,10 @@ pub(crate) struct ModuleManager {
 }
 
 impl ModuleManager {
+  pub(crate) fn get_module_refs(&self) -> Vec<ju4> {
+    self.modules.keys().copied().collect()
+  }
+
   pub(crate) fn new() -> Self {
     ModuleManager {
       modules: HashMap::new(),
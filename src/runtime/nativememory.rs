@@ -3,6 +3,8 @@ use std::collections::HashMap;
 use color_eyre::eyre::{eyre, OptionExt, Result};
 use log::debug;
 
+use crate::runtime::errors;
+
 #[derive(Debug)]
 pub struct NativeMemory {
   allocations: HashMap<u64, u64>,
@@ -32,18 +34,141 @@ impl NativeMemory {
     Ok(addr)
   }
 
+  // `addr` must be the start of an allocation, its content is kept
+  pub fn realloc(&mut self, addr: u64, size: u64) -> Result<u64> {
+    if !self.allocations.contains_key(&addr) {
+      return Err(eyre!(errors::InternalError::SegmentationFault(addr)));
+    }
+
+    let ptr = unsafe { libc::realloc(addr as *mut libc::c_void, size as usize) as *mut u8 };
+
+    if ptr.is_null() {
+      return Err(eyre!("OutOfMemoryError"));
+    }
+
+    self.allocations.remove(&addr);
+    self.allocations.insert(ptr as u64, size);
+
+    Ok(ptr as u64)
+  }
+
+  pub fn free(&mut self, addr: u64) -> Result<()> {
+    if self.allocations.remove(&addr).is_none() {
+      return Err(eyre!(errors::InternalError::SegmentationFault(addr)));
+    }
+
+    unsafe {
+      libc::free(addr as *mut libc::c_void);
+    }
+
+    Ok(())
+  }
+
   pub fn register(&mut self, addr: u64, size: u64) {
     self.allocations.insert(addr, size);
   }
 
+  /// Map `size` bytes of the file `fd` from `position`, the mapping is registered like an
+  /// allocation until it is unmapped
+  pub(crate) fn map(
+    &mut self,
+    fd: i32,
+    writable: bool,
+    shared: bool,
+    position: i64,
+    size: u64,
+  ) -> Result<u64> {
+    let prot = if writable {
+      libc::PROT_READ | libc::PROT_WRITE
+    } else {
+      libc::PROT_READ
+    };
+
+    let flags = if shared {
+      libc::MAP_SHARED
+    } else {
+      libc::MAP_PRIVATE
+    };
+
+    let ptr = unsafe {
+      libc::mmap(
+        std::ptr::null_mut(),
+        size as usize,
+        prot,
+        flags,
+        fd,
+        position as libc::off_t,
+      )
+    };
+
+    if ptr == libc::MAP_FAILED {
+      return Err(eyre!(errors::JavaException::IO(format!(
+        "Map failed: {}",
+        std::io::Error::last_os_error()
+      ))));
+    }
+
+    let addr = ptr as u64;
+
+    debug!("MAP {} {} -> {}", fd, size, addr);
+
+    self.register(addr, size);
+
+    Ok(addr)
+  }
+
+  // `addr` must be the start of a mapping
+  pub(crate) fn unmap(&mut self, addr: u64, size: u64) -> Result<()> {
+    if self.allocations.remove(&addr).is_none() {
+      return Err(eyre!(errors::InternalError::SegmentationFault(addr)));
+    }
+
+    if unsafe { libc::munmap(addr as *mut libc::c_void, size as usize) } != 0 {
+      return Err(eyre!(errors::JavaException::IO(format!(
+        "Unmap failed: {}",
+        std::io::Error::last_os_error()
+      ))));
+    }
+
+    Ok(())
+  }
+
+  /// Number of bytes allocated or registered
+  pub(crate) fn get_allocated(&self) -> u64 {
+    self.allocations.values().sum()
+  }
+
   pub(crate) fn is_valid(&self, addr: u64) -> bool {
+    self.check(addr, 1).is_ok()
+  }
+
+  /// Check that the `size` bytes from `addr` are in a single allocation
+  pub(crate) fn check(&self, addr: u64, size: u64) -> Result<()> {
     for (k, v) in &self.allocations {
-      if addr >= *k && addr < *k + *v {
-        return true;
+      if addr >= *k && addr.saturating_add(size) <= *k + *v {
+        return Ok(());
       }
     }
 
-    panic!("invalid address {}", addr);
+    Err(eyre!(errors::InternalError::SegmentationFault(addr)))
+  }
+
+  pub(crate) fn read(&self, addr: u64, size: u64) -> Result<Vec<u8>> {
+    self.check(addr, size)?;
+
+    let bytes = unsafe { std::slice::from_raw_parts(addr as *const u8, size as usize) };
+
+    Ok(bytes.to_vec())
+  }
+
+  pub(crate) fn write(&mut self, addr: u64, bytes: &[u8]) -> Result<()> {
+    self.check(addr, bytes.len() as u64)?;
+
+    unsafe {
+      std::ptr::copy(bytes.as_ptr(), addr as *mut u8, bytes.len());
+    }
+
+    Ok(())
   }
 
   pub(crate) fn _get(&self, addr: u64) -> Result<&u64> {
@@ -0,0 +1,350 @@
+use color_eyre::eyre::{eyre, Result};
+use log::debug;
+
+use crate::{
+  class_loader::{class_file::InvokeDynamicResolved, constant_pool::CpInfoInfoEnum},
+  runtime::{constants::REF_GET_FIELD, errors, jvm::JVM, types},
+  utils::{ju2, ju4},
+};
+
+const OBJECT_METHODS: &str = "java/lang/runtime/ObjectMethods";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum RecordMethod {
+  ToString,
+  Equals,
+  HashCode,
+}
+
+/// Call site of `ObjectMethods.bootstrap` linked to the native implementation of a method of
+/// a record, from its components
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ObjectMethod {
+  method: RecordMethod,
+  simple_name: String,
+  // name and descriptor of the field of each component
+  components: Vec<(String, String)>,
+}
+
+impl JVM {
+  pub(crate) fn is_object_methods_bootstrap(
+    invoke_dynamic_resolved: &InvokeDynamicResolved,
+  ) -> bool {
+    invoke_dynamic_resolved.get_bootstrap_class_name() == OBJECT_METHODS
+      && invoke_dynamic_resolved.get_bootstrap_method_name() == "bootstrap"
+  }
+
+  /// Link the call site to the native implementation instead of running the bootstrap method
+  ///
+  /// The static arguments are the record class, the names of the components separated by `;`
+  /// and a getter (a `getfield` method handle) per component.
+  pub(crate) fn link_object_method(
+    &mut self,
+    curr_class_name: &str,
+    invoke_dynamic_resolved: &InvokeDynamicResolved,
+  ) -> Result<ObjectMethod> {
+    let method_type = invoke_dynamic_resolved.get_method_type();
+
+    let method = match invoke_dynamic_resolved.get_method_name() {
+      "toString" if method_type.ends_with(")Ljava/lang/String;") => RecordMethod::ToString,
+      "equals" if method_type.ends_with(";Ljava/lang/Object;)Z") => RecordMethod::Equals,
+      "hashCode" if method_type.ends_with(")I") => RecordMethod::HashCode,
+      name => {
+        return Err(eyre!(errors::JavaException::BootstrapMethodError(format!(
+          "Illegal method name or type: {}{}",
+          name, method_type
+        ))))
+      }
+    };
+
+    let (record, getters) = match invoke_dynamic_resolved.get_arguments().split_first() {
+      Some((record, [names, getters @ ..])) => {
+        let names = self.resolve_object_methods_names(curr_class_name, *names)?;
+
+        if names.len() != getters.len() {
+          return Err(eyre!(errors::JavaException::BootstrapMethodError(format!(
+            "Illegal names: {} names for {} getters",
+            names.len(),
+            getters.len()
+          ))));
+        }
+
+        (*record, getters)
+      }
+      _ => {
+        return Err(eyre!(errors::JavaException::BootstrapMethodError(
+          "ObjectMethods.bootstrap without record class or names".to_string()
+        )))
+      }
+    };
+
+    let record = {
+      let class = self.class_loader.get(curr_class_name)?;
+
+      match class.resolve_index(record)?.get_info() {
+        CpInfoInfoEnum::Class(_) => class.resolve_class_name(record)?,
+        info => {
+          return Err(eyre!(errors::JavaException::BootstrapMethodError(format!(
+            "Record class expected, got {}",
+            info
+          ))))
+        }
+      }
+    };
+
+    let record = self.resolve_class(curr_class_name, &record)?;
+
+    let mut components = vec![];
+
+    for getter in getters {
+      let handle = self
+        .class_loader
+        .resolve_method_handle(curr_class_name, *getter)?;
+
+      if handle.get_ref_kind() as i32 != REF_GET_FIELD {
+        return Err(eyre!(errors::JavaException::BootstrapMethodError(format!(
+          "Getter expected, got {}",
+          handle
+        ))));
+      }
+
+      components.push((
+        handle.get_method_name().to_string(),
+        handle.get_method_type().to_string(),
+      ));
+    }
+
+    let simple_name = {
+      let class = self.class_loader.get(&record)?;
+
+      match class.get_simple_binary_name()? {
+        Some(simple_name) => simple_name,
+        None => class
+          .get_name()
+          .rsplit('/')
+          .next()
+          .unwrap_or_default()
+          .to_string(),
+      }
+    };
+
+    debug!("OBJECT METHOD {:?} {} {:?}", method, record, components);
+
+    Ok(ObjectMethod {
+      method,
+      simple_name,
+      components,
+    })
+  }
+
+  // the names of the components are a `;` separated string, empty without components
+  fn resolve_object_methods_names(&mut self, classname: &str, index: ju2) -> Result<Vec<String>> {
+    let cpinfo = self
+      .class_loader
+      .get(classname)?
+      .resolve_index(index)?
+      .clone();
+
+    let names = match cpinfo.get_info() {
+      CpInfoInfoEnum::String(info) => self
+        .class_loader
+        .resolve_string(classname, info.get_string_index())?,
+      _ => {
+        return Err(eyre!(errors::JavaException::BootstrapMethodError(format!(
+          "Names of the components expected, got {}",
+          cpinfo.get_info()
+        ))))
+      }
+    };
+
+    if names.is_empty() {
+      return Ok(vec![]);
+    }
+
+    Ok(names.split(';').map(str::to_string).collect())
+  }
+
+  /// Pop the arguments of the call site and push the result of the method of the record
+  pub(crate) fn invoke_object_method(&mut self, method: &ObjectMethod) -> Result<types::Type> {
+    let other = match method.method {
+      RecordMethod::Equals => Some(self.pop_stack()?),
+      _ => None,
+    };
+
+    let record = match self.pop_stack()? {
+      types::Type::ObjectRef(record) => record,
+      _ => return Err(eyre!(errors::JavaException::NullPointer)),
+    };
+
+    match (method.method, other) {
+      (RecordMethod::ToString, _) => {
+        let mut components = vec![];
+
+        for (name, descriptor) in &method.components {
+          let value = self.heap.get_obj_instance(record)?.get_field(name)?;
+
+          components.push(format!(
+            "{}={}",
+            name,
+            self.java_to_string(descriptor, value)?
+          ));
+        }
+
+        let string = format!("{}[{}]", method.simple_name, components.join(", "));
+
+        self.heap.alloc_string(&mut self.class_loader, &string)
+      }
+      (RecordMethod::HashCode, _) => {
+        let mut hash: i32 = 0;
+
+        for (name, descriptor) in &method.components {
+          let value = self.heap.get_obj_instance(record)?.get_field(name)?;
+
+          hash = hash
+            .wrapping_mul(31)
+            .wrapping_add(self.component_hash_code(descriptor, value)?);
+        }
+
+        Ok(types::Type::Integer(hash))
+      }
+      (RecordMethod::Equals, Some(other)) => Ok(types::Type::Boolean(
+        self.record_equals(record, method, other)?,
+      )),
+      (RecordMethod::Equals, None) => unreachable!(),
+    }
+  }
+
+  fn record_equals(
+    &mut self,
+    record: ju4,
+    method: &ObjectMethod,
+    other: types::Type,
+  ) -> Result<bool> {
+    let other = match other {
+      types::Type::ObjectRef(other) if other == record => return Ok(true),
+      types::Type::ObjectRef(other) => other,
+      _ => return Ok(false),
+    };
+
+    let record_obj = self.heap.get_obj_instance(record)?.clone();
+
+    // an instance of the same class, not of a subclass
+    match self.heap.get_instance(other)? {
+      types::Instance::ObjectInstance(obj) if obj.get_classname() == record_obj.get_classname() => {
+      }
+      _ => return Ok(false),
+    };
+
+    for (name, descriptor) in &method.components {
+      let left = record_obj.get_field(name)?;
+      let right = self.heap.get_obj_instance(other)?.get_field(name)?;
+
+      let equal = match descriptor.as_str() {
+        // `Float.compare` and `Double.compare`, NaN is equal to itself but not 0.0 to -0.0
+        "F" => float_to_int_bits(left.as_float()?) == float_to_int_bits(right.as_float()?),
+        "D" => double_to_long_bits(left.as_double()?) == double_to_long_bits(right.as_double()?),
+        "Z" => left.as_bool()? == right.as_bool()?,
+        "J" => left.as_long()? == right.as_long()?,
+        "B" | "C" | "S" | "I" => left.as_integer()? == right.as_integer()?,
+        _ => self.objects_equals(left, right)?,
+      };
+
+      if !equal {
+        return Ok(false);
+      }
+    }
+
+    Ok(true)
+  }
+
+  // `Objects.equals`
+  fn objects_equals(&mut self, left: types::Type, right: types::Type) -> Result<bool> {
+    let left_ref = match left {
+      types::Type::Null => return Ok(right == types::Type::Null),
+      types::Type::ObjectRef(obj_ref) | types::Type::ArrayRef(obj_ref) => obj_ref,
+      value => return Err(eyre!("Not a Object Reference: {}", value)),
+    };
+
+    if left == right {
+      return Ok(true);
+    }
+
+    let classname = self.get_object_classname(left_ref)?;
+
+    let equal = self.call_and_resolve_method(
+      &classname,
+      "equals",
+      "(Ljava/lang/Object;)Z",
+      vec![left, right],
+    )?;
+
+    equal.as_bool()
+  }
+
+  // hash code of a component, like the `hashCode` of its box class
+  fn component_hash_code(&mut self, descriptor: &str, value: types::Type) -> Result<i32> {
+    let hash = match descriptor {
+      "Z" => {
+        if value.as_bool()? {
+          1231
+        } else {
+          1237
+        }
+      }
+      "B" | "C" | "S" | "I" => value.as_integer()?,
+      "J" => {
+        let value = value.as_long()?;
+
+        (value ^ ((value as u64) >> 32) as i64) as i32
+      }
+      "F" => float_to_int_bits(value.as_float()?),
+      "D" => {
+        let bits = double_to_long_bits(value.as_double()?);
+
+        (bits ^ ((bits as u64) >> 32) as i64) as i32
+      }
+      // `Objects.hashCode`
+      _ => match value {
+        types::Type::Null => 0,
+        types::Type::ObjectRef(obj_ref) | types::Type::ArrayRef(obj_ref) => {
+          let classname = self.get_object_classname(obj_ref)?;
+
+          self
+            .call_and_resolve_method(&classname, "hashCode", "()I", vec![value])?
+            .as_integer()?
+        }
+        value => return Err(eyre!("Not a Object Reference: {}", value)),
+      },
+    };
+
+    Ok(hash)
+  }
+
+  // class of the virtual calls on an object, methods of arrays are the ones of Object
+  fn get_object_classname(&self, obj_ref: ju4) -> Result<String> {
+    let classname = match self.heap.get_instance(obj_ref)? {
+      types::Instance::ObjectInstance(obj) => obj.get_classname().to_string(),
+      types::Instance::ArrayInstance(_) => "java/lang/Object".to_string(),
+    };
+
+    Ok(classname)
+  }
+}
+
+// `Float.floatToIntBits`, all the NaN are the canonical one
+fn float_to_int_bits(value: f32) -> i32 {
+  if value.is_nan() {
+    0x7fc00000
+  } else {
+    value.to_bits() as i32
+  }
+}
+
+// `Double.doubleToLongBits`
+fn double_to_long_bits(value: f64) -> i64 {
+  if value.is_nan() {
+    0x7ff8000000000000
+  } else {
+    value.to_bits() as i64
+  }
+}
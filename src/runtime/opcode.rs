@@ -55,6 +55,8 @@ pub enum OpCode {
   ALOAD3 = 45,
   IALOAD = 46,
   LALOAD = 47,
+  FALOAD = 48,
+  DALOAD = 49,
   AALOAD = 50,
   BALOAD = 51,
   CALOAD = 52,
@@ -86,6 +88,8 @@ pub enum OpCode {
   ASTORE3 = 78,
   IASTORE = 79,
   LASTORE = 80,
+  FASTORE = 81,
+  DASTORE = 82,
   AASTORE = 83,
   BASTORE = 84,
   CASTORE = 85,
@@ -171,6 +175,8 @@ pub enum OpCode {
   IFACMPEQ = 165,
   IFACMPNE = 166,
   GOTO = 167,
+  JSR = 168,
+  RET = 169,
   TABLESWITCH = 170,
   LOOKUPSWITCH = 171,
   IRETURN = 172,
@@ -197,11 +203,220 @@ pub enum OpCode {
   INSTANCEOF = 193,
   MONITORENTER = 194,
   MONITOREXIT = 195,
+  WIDE = 196,
   MULTIANEWARRAY = 197,
   IFNULL = 198,
   IFNONNULL = 199,
+  GOTOW = 200,
+  JSRW = 201,
 }
 
+// mnemonics of the opcodes, indexed by their value
+const MNEMONICS: [&str; 202] = [
+  "nop",
+  "aconst_null",
+  "iconst_m1",
+  "iconst_0",
+  "iconst_1",
+  "iconst_2",
+  "iconst_3",
+  "iconst_4",
+  "iconst_5",
+  "lconst_0",
+  "lconst_1",
+  "fconst_0",
+  "fconst_1",
+  "fconst_2",
+  "dconst_0",
+  "dconst_1",
+  "bipush",
+  "sipush",
+  "ldc",
+  "ldc_w",
+  "ldc2_w",
+  "iload",
+  "lload",
+  "fload",
+  "dload",
+  "aload",
+  "iload_0",
+  "iload_1",
+  "iload_2",
+  "iload_3",
+  "lload_0",
+  "lload_1",
+  "lload_2",
+  "lload_3",
+  "fload_0",
+  "fload_1",
+  "fload_2",
+  "fload_3",
+  "dload_0",
+  "dload_1",
+  "dload_2",
+  "dload_3",
+  "aload_0",
+  "aload_1",
+  "aload_2",
+  "aload_3",
+  "iaload",
+  "laload",
+  "faload",
+  "daload",
+  "aaload",
+  "baload",
+  "caload",
+  "saload",
+  "istore",
+  "lstore",
+  "fstore",
+  "dstore",
+  "astore",
+  "istore_0",
+  "istore_1",
+  "istore_2",
+  "istore_3",
+  "lstore_0",
+  "lstore_1",
+  "lstore_2",
+  "lstore_3",
+  "fstore_0",
+  "fstore_1",
+  "fstore_2",
+  "fstore_3",
+  "dstore_0",
+  "dstore_1",
+  "dstore_2",
+  "dstore_3",
+  "astore_0",
+  "astore_1",
+  "astore_2",
+  "astore_3",
+  "iastore",
+  "lastore",
+  "fastore",
+  "dastore",
+  "aastore",
+  "bastore",
+  "castore",
+  "sastore",
+  "pop",
+  "pop2",
+  "dup",
+  "dup_x1",
+  "dup_x2",
+  "dup2",
+  "dup2_x1",
+  "dup2_x2",
+  "swap",
+  "iadd",
+  "ladd",
+  "fadd",
+  "dadd",
+  "isub",
+  "lsub",
+  "fsub",
+  "dsub",
+  "imul",
+  "lmul",
+  "fmul",
+  "dmul",
+  "idiv",
+  "ldiv",
+  "fdiv",
+  "ddiv",
+  "irem",
+  "lrem",
+  "frem",
+  "drem",
+  "ineg",
+  "lneg",
+  "fneg",
+  "dneg",
+  "ishl",
+  "lshl",
+  "ishr",
+  "lshr",
+  "iushr",
+  "lushr",
+  "iand",
+  "land",
+  "ior",
+  "lor",
+  "ixor",
+  "lxor",
+  "iinc",
+  "i2l",
+  "i2f",
+  "i2d",
+  "l2i",
+  "l2f",
+  "l2d",
+  "f2i",
+  "f2l",
+  "f2d",
+  "d2i",
+  "d2l",
+  "d2f",
+  "i2b",
+  "i2c",
+  "i2s",
+  "lcmp",
+  "fcmpl",
+  "fcmpg",
+  "dcmpl",
+  "dcmpg",
+  "ifeq",
+  "ifne",
+  "iflt",
+  "ifge",
+  "ifgt",
+  "ifle",
+  "if_icmpeq",
+  "if_icmpne",
+  "if_icmplt",
+  "if_icmpge",
+  "if_icmpgt",
+  "if_icmple",
+  "if_acmpeq",
+  "if_acmpne",
+  "goto",
+  "jsr",
+  "ret",
+  "tableswitch",
+  "lookupswitch",
+  "ireturn",
+  "lreturn",
+  "freturn",
+  "dreturn",
+  "areturn",
+  "return",
+  "getstatic",
+  "putstatic",
+  "getfield",
+  "putfield",
+  "invokevirtual",
+  "invokespecial",
+  "invokestatic",
+  "invokeinterface",
+  "invokedynamic",
+  "new",
+  "newarray",
+  "anewarray",
+  "arraylength",
+  "athrow",
+  "checkcast",
+  "instanceof",
+  "monitorenter",
+  "monitorexit",
+  "wide",
+  "multianewarray",
+  "ifnull",
+  "ifnonnull",
+  "goto_w",
+  "jsr_w",
+];
+
 impl fmt::Display for OpCode {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
     write!(f, "{:?}", self)
@@ -209,203 +424,230 @@ impl fmt::Display for OpCode {
 }
 
 impl OpCode {
+  /// Name of the opcode in the JVM specification (e.g. `iconst_m1`)
+  pub fn mnemonic(&self) -> &'static str {
+    MNEMONICS[*self as usize]
+  }
+
+  /// Opcode of the instruction named `mnemonic` (e.g. `iconst_m1`)
+  pub fn from_mnemonic(mnemonic: &str) -> Option<OpCode> {
+    MNEMONICS
+      .iter()
+      .position(|name| *name == mnemonic)
+      .and_then(|b| OpCode::try_from_byte(b as ju1))
+  }
+
   pub fn from_byte(b: ju1) -> OpCode {
+    OpCode::try_from_byte(b).unwrap_or_else(|| notimpl!(b))
+  }
+
+  /// Opcode of the byte `b`, `None` for an instruction that is not supported
+  pub fn try_from_byte(b: ju1) -> Option<OpCode> {
     match b {
-      0 => OpCode::NOP,
-      1 => OpCode::ACONSTNULL,
-      2 => OpCode::ICONSTM1,
-      3 => OpCode::ICONST0,
-      4 => OpCode::ICONST1,
-      5 => OpCode::ICONST2,
-      6 => OpCode::ICONST3,
-      7 => OpCode::ICONST4,
-      8 => OpCode::ICONST5,
-      9 => OpCode::LCONST0,
-      10 => OpCode::LCONST1,
-      11 => OpCode::FCONST0,
-      12 => OpCode::FCONST1,
-      13 => OpCode::FCONST2,
-      14 => OpCode::DCONST0,
-      15 => OpCode::DCONST1,
-      16 => OpCode::BIPUSH,
-      17 => OpCode::SIPUSH,
-      18 => OpCode::LDC,
-      19 => OpCode::LDCW,
-      20 => OpCode::LDC2W,
-      21 => OpCode::ILOAD,
-      22 => OpCode::LLOAD,
-      23 => OpCode::FLOAD,
-      24 => OpCode::DLOAD,
-      25 => OpCode::ALOAD,
-      26 => OpCode::ILOAD0,
-      27 => OpCode::ILOAD1,
-      28 => OpCode::ILOAD2,
-      29 => OpCode::ILOAD3,
-      30 => OpCode::LLOAD0,
-      31 => OpCode::LLOAD1,
-      32 => OpCode::LLOAD2,
-      33 => OpCode::LLOAD3,
-      34 => OpCode::FLOAD0,
-      35 => OpCode::FLOAD1,
-      36 => OpCode::FLOAD2,
-      37 => OpCode::FLOAD3,
-      38 => OpCode::DLOAD0,
-      39 => OpCode::DLOAD1,
-      40 => OpCode::DLOAD2,
-      41 => OpCode::DLOAD3,
-      42 => OpCode::ALOAD0,
-      43 => OpCode::ALOAD1,
-      44 => OpCode::ALOAD2,
-      45 => OpCode::ALOAD3,
-      46 => OpCode::IALOAD,
-      47 => OpCode::LALOAD,
-      50 => OpCode::AALOAD,
-      51 => OpCode::BALOAD,
-      52 => OpCode::CALOAD,
-      53 => OpCode::SALOAD,
-      54 => OpCode::ISTORE,
-      55 => OpCode::LSTORE,
-      56 => OpCode::FSTORE,
-      57 => OpCode::DSTORE,
-      58 => OpCode::ASTORE,
-      59 => OpCode::ISTORE0,
-      60 => OpCode::ISTORE1,
-      61 => OpCode::ISTORE2,
-      62 => OpCode::ISTORE3,
-      63 => OpCode::LSTORE0,
-      64 => OpCode::LSTORE1,
-      65 => OpCode::LSTORE2,
-      66 => OpCode::LSTORE3,
-      67 => OpCode::FSTORE0,
-      68 => OpCode::FSTORE1,
-      69 => OpCode::FSTORE2,
-      70 => OpCode::FSTORE3,
-      71 => OpCode::DSTORE0,
-      72 => OpCode::DSTORE1,
-      73 => OpCode::DSTORE2,
-      74 => OpCode::DSTORE3,
-      75 => OpCode::ASTORE0,
-      76 => OpCode::ASTORE1,
-      77 => OpCode::ASTORE2,
-      78 => OpCode::ASTORE3,
-      79 => OpCode::IASTORE,
-      80 => OpCode::LASTORE,
-      83 => OpCode::AASTORE,
-      84 => OpCode::BASTORE,
-      85 => OpCode::CASTORE,
-      86 => OpCode::SASTORE,
-      87 => OpCode::POP,
-      88 => OpCode::POP2,
-      89 => OpCode::DUP,
-      90 => OpCode::DUPX1,
-      91 => OpCode::DUPX2,
-      92 => OpCode::DUP2,
-      93 => OpCode::DUP2X1,
-      94 => OpCode::DUP2X2,
-      95 => OpCode::SWAP,
-      96 => OpCode::IADD,
-      97 => OpCode::LADD,
-      98 => OpCode::FADD,
-      99 => OpCode::DADD,
-      100 => OpCode::ISUB,
-      101 => OpCode::LSUB,
-      102 => OpCode::FSUB,
-      103 => OpCode::DSUB,
-      104 => OpCode::IMUL,
-      105 => OpCode::LMUL,
-      106 => OpCode::FMUL,
-      107 => OpCode::DMUL,
-      108 => OpCode::IDIV,
-      109 => OpCode::LDIV,
-      110 => OpCode::FDIV,
-      111 => OpCode::DDIV,
-      112 => OpCode::IREM,
-      113 => OpCode::LREM,
-      114 => OpCode::FREM,
-      115 => OpCode::DREM,
-      116 => OpCode::INEG,
-      117 => OpCode::LNEG,
-      118 => OpCode::FNEG,
-      119 => OpCode::DNEG,
-      120 => OpCode::ISHL,
-      121 => OpCode::LSHL,
-      122 => OpCode::ISHR,
-      123 => OpCode::LSHR,
-      124 => OpCode::IUSHR,
-      125 => OpCode::LUSHR,
-      126 => OpCode::IAND,
-      127 => OpCode::LAND,
-      128 => OpCode::IOR,
-      129 => OpCode::LOR,
-      130 => OpCode::IXOR,
-      131 => OpCode::LXOR,
-      132 => OpCode::IINC,
-      133 => OpCode::I2L,
-      134 => OpCode::I2F,
-      135 => OpCode::I2D,
-      136 => OpCode::L2I,
-      137 => OpCode::L2F,
-      138 => OpCode::L2D,
-      139 => OpCode::F2I,
-      140 => OpCode::F2L,
-      141 => OpCode::F2D,
-      142 => OpCode::D2I,
-      143 => OpCode::D2L,
-      144 => OpCode::D2F,
-      145 => OpCode::I2B,
-      146 => OpCode::I2C,
-      147 => OpCode::I2S,
-      148 => OpCode::LCMP,
-      149 => OpCode::FCMPL,
-      150 => OpCode::FCMPG,
-      151 => OpCode::DCMPL,
-      152 => OpCode::DCMPG,
-      153 => OpCode::IFEQ,
-      154 => OpCode::IFNE,
-      155 => OpCode::IFLT,
-      156 => OpCode::IFGE,
-      157 => OpCode::IFGT,
-      158 => OpCode::IFLE,
-      159 => OpCode::IFICMPEQ,
-      160 => OpCode::IFICMPNE,
-      161 => OpCode::IFICMPLT,
-      162 => OpCode::IFICMPGE,
-      163 => OpCode::IFICMPGT,
-      164 => OpCode::IFICMPLE,
-      165 => OpCode::IFACMPEQ,
-      166 => OpCode::IFACMPNE,
-      167 => OpCode::GOTO,
-      170 => OpCode::TABLESWITCH,
-      171 => OpCode::LOOKUPSWITCH,
-      172 => OpCode::IRETURN,
-      173 => OpCode::LRETURN,
-      174 => OpCode::FRETURN,
-      175 => OpCode::DRETURN,
-      176 => OpCode::ARETURN,
-      177 => OpCode::RETURN,
-      178 => OpCode::GETSTATIC,
-      179 => OpCode::PUTSTATIC,
-      180 => OpCode::GETFIELD,
-      181 => OpCode::PUTFIELD,
-      182 => OpCode::INVOKEVIRTUAL,
-      183 => OpCode::INVOKESPECIAL,
-      184 => OpCode::INVOKESTATIC,
-      185 => OpCode::INVOKEINTERFACE,
-      186 => OpCode::INVOKEDYNAMIC,
-      187 => OpCode::NEW,
-      188 => OpCode::NEWARRAY,
-      189 => OpCode::ANEWARRAY,
-      190 => OpCode::ARRAYLENGTH,
-      191 => OpCode::ATHROW,
-      192 => OpCode::CHECKCAST,
-      193 => OpCode::INSTANCEOF,
-      194 => OpCode::MONITORENTER,
-      195 => OpCode::MONITOREXIT,
-      197 => OpCode::MULTIANEWARRAY,
-      198 => OpCode::IFNULL,
-      199 => OpCode::IFNONNULL,
+      0 => Some(OpCode::NOP),
+      1 => Some(OpCode::ACONSTNULL),
+      2 => Some(OpCode::ICONSTM1),
+      3 => Some(OpCode::ICONST0),
+      4 => Some(OpCode::ICONST1),
+      5 => Some(OpCode::ICONST2),
+      6 => Some(OpCode::ICONST3),
+      7 => Some(OpCode::ICONST4),
+      8 => Some(OpCode::ICONST5),
+      9 => Some(OpCode::LCONST0),
+      10 => Some(OpCode::LCONST1),
+      11 => Some(OpCode::FCONST0),
+      12 => Some(OpCode::FCONST1),
+      13 => Some(OpCode::FCONST2),
+      14 => Some(OpCode::DCONST0),
+      15 => Some(OpCode::DCONST1),
+      16 => Some(OpCode::BIPUSH),
+      17 => Some(OpCode::SIPUSH),
+      18 => Some(OpCode::LDC),
+      19 => Some(OpCode::LDCW),
+      20 => Some(OpCode::LDC2W),
+      21 => Some(OpCode::ILOAD),
+      22 => Some(OpCode::LLOAD),
+      23 => Some(OpCode::FLOAD),
+      24 => Some(OpCode::DLOAD),
+      25 => Some(OpCode::ALOAD),
+      26 => Some(OpCode::ILOAD0),
+      27 => Some(OpCode::ILOAD1),
+      28 => Some(OpCode::ILOAD2),
+      29 => Some(OpCode::ILOAD3),
+      30 => Some(OpCode::LLOAD0),
+      31 => Some(OpCode::LLOAD1),
+      32 => Some(OpCode::LLOAD2),
+      33 => Some(OpCode::LLOAD3),
+      34 => Some(OpCode::FLOAD0),
+      35 => Some(OpCode::FLOAD1),
+      36 => Some(OpCode::FLOAD2),
+      37 => Some(OpCode::FLOAD3),
+      38 => Some(OpCode::DLOAD0),
+      39 => Some(OpCode::DLOAD1),
+      40 => Some(OpCode::DLOAD2),
+      41 => Some(OpCode::DLOAD3),
+      42 => Some(OpCode::ALOAD0),
+      43 => Some(OpCode::ALOAD1),
+      44 => Some(OpCode::ALOAD2),
+      45 => Some(OpCode::ALOAD3),
+      46 => Some(OpCode::IALOAD),
+      47 => Some(OpCode::LALOAD),
+      48 => Some(OpCode::FALOAD),
+      49 => Some(OpCode::DALOAD),
+      50 => Some(OpCode::AALOAD),
+      51 => Some(OpCode::BALOAD),
+      52 => Some(OpCode::CALOAD),
+      53 => Some(OpCode::SALOAD),
+      54 => Some(OpCode::ISTORE),
+      55 => Some(OpCode::LSTORE),
+      56 => Some(OpCode::FSTORE),
+      57 => Some(OpCode::DSTORE),
+      58 => Some(OpCode::ASTORE),
+      59 => Some(OpCode::ISTORE0),
+      60 => Some(OpCode::ISTORE1),
+      61 => Some(OpCode::ISTORE2),
+      62 => Some(OpCode::ISTORE3),
+      63 => Some(OpCode::LSTORE0),
+      64 => Some(OpCode::LSTORE1),
+      65 => Some(OpCode::LSTORE2),
+      66 => Some(OpCode::LSTORE3),
+      67 => Some(OpCode::FSTORE0),
+      68 => Some(OpCode::FSTORE1),
+      69 => Some(OpCode::FSTORE2),
+      70 => Some(OpCode::FSTORE3),
+      71 => Some(OpCode::DSTORE0),
+      72 => Some(OpCode::DSTORE1),
+      73 => Some(OpCode::DSTORE2),
+      74 => Some(OpCode::DSTORE3),
+      75 => Some(OpCode::ASTORE0),
+      76 => Some(OpCode::ASTORE1),
+      77 => Some(OpCode::ASTORE2),
+      78 => Some(OpCode::ASTORE3),
+      79 => Some(OpCode::IASTORE),
+      80 => Some(OpCode::LASTORE),
+      81 => Some(OpCode::FASTORE),
+      82 => Some(OpCode::DASTORE),
+      83 => Some(OpCode::AASTORE),
+      84 => Some(OpCode::BASTORE),
+      85 => Some(OpCode::CASTORE),
+      86 => Some(OpCode::SASTORE),
+      87 => Some(OpCode::POP),
+      88 => Some(OpCode::POP2),
+      89 => Some(OpCode::DUP),
+      90 => Some(OpCode::DUPX1),
+      91 => Some(OpCode::DUPX2),
+      92 => Some(OpCode::DUP2),
+      93 => Some(OpCode::DUP2X1),
+      94 => Some(OpCode::DUP2X2),
+      95 => Some(OpCode::SWAP),
+      96 => Some(OpCode::IADD),
+      97 => Some(OpCode::LADD),
+      98 => Some(OpCode::FADD),
+      99 => Some(OpCode::DADD),
+      100 => Some(OpCode::ISUB),
+      101 => Some(OpCode::LSUB),
+      102 => Some(OpCode::FSUB),
+      103 => Some(OpCode::DSUB),
+      104 => Some(OpCode::IMUL),
+      105 => Some(OpCode::LMUL),
+      106 => Some(OpCode::FMUL),
+      107 => Some(OpCode::DMUL),
+      108 => Some(OpCode::IDIV),
+      109 => Some(OpCode::LDIV),
+      110 => Some(OpCode::FDIV),
+      111 => Some(OpCode::DDIV),
+      112 => Some(OpCode::IREM),
+      113 => Some(OpCode::LREM),
+      114 => Some(OpCode::FREM),
+      115 => Some(OpCode::DREM),
+      116 => Some(OpCode::INEG),
+      117 => Some(OpCode::LNEG),
+      118 => Some(OpCode::FNEG),
+      119 => Some(OpCode::DNEG),
+      120 => Some(OpCode::ISHL),
+      121 => Some(OpCode::LSHL),
+      122 => Some(OpCode::ISHR),
+      123 => Some(OpCode::LSHR),
+      124 => Some(OpCode::IUSHR),
+      125 => Some(OpCode::LUSHR),
+      126 => Some(OpCode::IAND),
+      127 => Some(OpCode::LAND),
+      128 => Some(OpCode::IOR),
+      129 => Some(OpCode::LOR),
+      130 => Some(OpCode::IXOR),
+      131 => Some(OpCode::LXOR),
+      132 => Some(OpCode::IINC),
+      133 => Some(OpCode::I2L),
+      134 => Some(OpCode::I2F),
+      135 => Some(OpCode::I2D),
+      136 => Some(OpCode::L2I),
+      137 => Some(OpCode::L2F),
+      138 => Some(OpCode::L2D),
+      139 => Some(OpCode::F2I),
+      140 => Some(OpCode::F2L),
+      141 => Some(OpCode::F2D),
+      142 => Some(OpCode::D2I),
+      143 => Some(OpCode::D2L),
+      144 => Some(OpCode::D2F),
+      145 => Some(OpCode::I2B),
+      146 => Some(OpCode::I2C),
+      147 => Some(OpCode::I2S),
+      148 => Some(OpCode::LCMP),
+      149 => Some(OpCode::FCMPL),
+      150 => Some(OpCode::FCMPG),
+      151 => Some(OpCode::DCMPL),
+      152 => Some(OpCode::DCMPG),
+      153 => Some(OpCode::IFEQ),
+      154 => Some(OpCode::IFNE),
+      155 => Some(OpCode::IFLT),
+      156 => Some(OpCode::IFGE),
+      157 => Some(OpCode::IFGT),
+      158 => Some(OpCode::IFLE),
+      159 => Some(OpCode::IFICMPEQ),
+      160 => Some(OpCode::IFICMPNE),
+      161 => Some(OpCode::IFICMPLT),
+      162 => Some(OpCode::IFICMPGE),
+      163 => Some(OpCode::IFICMPGT),
+      164 => Some(OpCode::IFICMPLE),
+      165 => Some(OpCode::IFACMPEQ),
+      166 => Some(OpCode::IFACMPNE),
+      167 => Some(OpCode::GOTO),
+      168 => Some(OpCode::JSR),
+      169 => Some(OpCode::RET),
+      170 => Some(OpCode::TABLESWITCH),
+      171 => Some(OpCode::LOOKUPSWITCH),
+      172 => Some(OpCode::IRETURN),
+      173 => Some(OpCode::LRETURN),
+      174 => Some(OpCode::FRETURN),
+      175 => Some(OpCode::DRETURN),
+      176 => Some(OpCode::ARETURN),
+      177 => Some(OpCode::RETURN),
+      178 => Some(OpCode::GETSTATIC),
+      179 => Some(OpCode::PUTSTATIC),
+      180 => Some(OpCode::GETFIELD),
+      181 => Some(OpCode::PUTFIELD),
+      182 => Some(OpCode::INVOKEVIRTUAL),
+      183 => Some(OpCode::INVOKESPECIAL),
+      184 => Some(OpCode::INVOKESTATIC),
+      185 => Some(OpCode::INVOKEINTERFACE),
+      186 => Some(OpCode::INVOKEDYNAMIC),
+      187 => Some(OpCode::NEW),
+      188 => Some(OpCode::NEWARRAY),
+      189 => Some(OpCode::ANEWARRAY),
+      190 => Some(OpCode::ARRAYLENGTH),
+      191 => Some(OpCode::ATHROW),
+      192 => Some(OpCode::CHECKCAST),
+      193 => Some(OpCode::INSTANCEOF),
+      194 => Some(OpCode::MONITORENTER),
+      195 => Some(OpCode::MONITOREXIT),
+      196 => Some(OpCode::WIDE),
+      197 => Some(OpCode::MULTIANEWARRAY),
+      198 => Some(OpCode::IFNULL),
+      199 => Some(OpCode::IFNONNULL),
+      200 => Some(OpCode::GOTOW),
+      201 => Some(OpCode::JSRW),
 
-      _ => notimpl!(b),
+      _ => None,
     }
   }
 }
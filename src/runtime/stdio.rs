@@ -0,0 +1,64 @@
+use std::{
+  fmt,
+  fs::File,
+  io::{Read, Write},
+  mem::ManuallyDrop,
+  os::{fd::RawFd, unix::io::FromRawFd},
+};
+
+use color_eyre::eyre::Result;
+
+const STDIN: RawFd = 0;
+const STDOUT: RawFd = 1;
+const STDERR: RawFd = 2;
+
+/// Standard streams of the VM: the file descriptors 0, 1 and 2 of the Java streams
+/// (`FileDescriptor.in`, `out` and `err`) are read from and written to the streams set by the
+/// embedder, the other ones and the streams not set go to the file descriptors of the process
+#[derive(Default)]
+pub(crate) struct Stdio {
+  pub(crate) stdin: Option<Box<dyn Read>>,
+  pub(crate) stdout: Option<Box<dyn Write>>,
+  pub(crate) stderr: Option<Box<dyn Write>>,
+}
+
+impl Stdio {
+  pub(crate) fn write(&mut self, fd: RawFd, bytes: &[u8]) -> Result<()> {
+    let stream = match fd {
+      STDOUT => self.stdout.as_mut(),
+      STDERR => self.stderr.as_mut(),
+      _ => None,
+    };
+
+    match stream {
+      Some(stream) => {
+        stream.write_all(bytes)?;
+        stream.flush()?;
+      }
+      // the fd is not closed when `file` goes out of scope
+      None => ManuallyDrop::new(unsafe { File::from_raw_fd(fd) }).write_all(bytes)?,
+    }
+
+    Ok(())
+  }
+
+  /// Read at most `buffer.len()` bytes, 0 at the end of the stream
+  pub(crate) fn read(&mut self, fd: RawFd, buffer: &mut [u8]) -> Result<usize> {
+    let read = match self.stdin.as_mut() {
+      Some(stream) if fd == STDIN => stream.read(buffer)?,
+      _ => ManuallyDrop::new(unsafe { File::from_raw_fd(fd) }).read(buffer)?,
+    };
+
+    Ok(read)
+  }
+}
+
+impl fmt::Debug for Stdio {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Stdio")
+      .field("stdin", &self.stdin.is_some())
+      .field("stdout", &self.stdout.is_some())
+      .field("stderr", &self.stderr.is_some())
+      .finish()
+  }
+}
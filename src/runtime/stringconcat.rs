@@ -0,0 +1,372 @@
+use color_eyre::eyre::{eyre, Result};
+use log::debug;
+
+use crate::{
+  class_loader::{class_file::InvokeDynamicResolved, constant_pool::CpInfoInfoEnum},
+  runtime::{errors, jvm::JVM, types},
+  utils::{get_argument_classnames, get_return_type_descriptor, ju2},
+};
+
+const STRING_CONCAT_FACTORY: &str = "java/lang/invoke/StringConcatFactory";
+
+// tags of the recipe of `makeConcatWithConstants`
+const TAG_ARG: char = '\u{1}';
+const TAG_CONST: char = '\u{2}';
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum RecipeElement {
+  Constant(String),
+  // index in the arguments of the call site
+  Argument(usize),
+}
+
+/// Call site of `StringConcatFactory` linked to the native concatenation
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct StringConcat {
+  elements: Vec<RecipeElement>,
+  argument_types: Vec<String>,
+}
+
+impl StringConcat {
+  /// Parse the recipe of `makeConcatWithConstants`, `\1` is replaced by the next argument and
+  /// `\2` by the next constant
+  pub(crate) fn from_recipe(
+    recipe: &str,
+    constants: &[String],
+    argument_types: Vec<String>,
+  ) -> Result<StringConcat> {
+    let mut elements = vec![];
+    let mut constants = constants.iter();
+    let mut literal = String::new();
+    let mut argument = 0;
+
+    for c in recipe.chars() {
+      if c != TAG_ARG && c != TAG_CONST {
+        literal.push(c);
+        continue;
+      }
+
+      if c == TAG_CONST {
+        let constant = constants.next().ok_or_else(|| {
+          eyre!(errors::JavaException::BootstrapMethodError(
+            "Mismatched number of concat constants: recipe wants more constants".to_string()
+          ))
+        })?;
+
+        literal.push_str(constant);
+        continue;
+      }
+
+      if !literal.is_empty() {
+        elements.push(RecipeElement::Constant(std::mem::take(&mut literal)));
+      }
+
+      elements.push(RecipeElement::Argument(argument));
+      argument += 1;
+    }
+
+    if !literal.is_empty() {
+      elements.push(RecipeElement::Constant(literal));
+    }
+
+    if constants.len() != 0 {
+      return Err(eyre!(errors::JavaException::BootstrapMethodError(format!(
+        "Mismatched number of concat constants: {} constants left unused",
+        constants.len()
+      ))));
+    }
+
+    if argument != argument_types.len() {
+      return Err(eyre!(errors::JavaException::BootstrapMethodError(
+        format!(
+          "Mismatched number of concat arguments: recipe wants {} arguments, but signature provides {}",
+          argument,
+          argument_types.len()
+        )
+      )));
+    }
+
+    Ok(StringConcat {
+      elements,
+      argument_types,
+    })
+  }
+
+  /// `makeConcat` concatenates all the arguments
+  pub(crate) fn from_arguments(argument_types: Vec<String>) -> StringConcat {
+    StringConcat {
+      elements: (0..argument_types.len())
+        .map(RecipeElement::Argument)
+        .collect(),
+      argument_types,
+    }
+  }
+}
+
+/// Same digits as `Double.toString`, the shortest decimal that rounds to the value, with at
+/// least two of them like the JDK
+pub(crate) fn java_double_to_string(value: f64) -> String {
+  if value.is_nan() || value.is_infinite() || value == 0.0 {
+    return java_special_to_string(value.is_nan(), value.is_sign_negative(), value == 0.0);
+  }
+
+  let mut repr = format!("{:e}", value.abs());
+
+  if !repr.contains('.') {
+    let closest = format!("{:.1e}", value.abs());
+
+    if closest.parse::<f64>().ok() == Some(value.abs()) {
+      repr = closest;
+    }
+  }
+
+  java_decimal_to_string(value.is_sign_negative(), &repr)
+}
+
+/// Same as `java_double_to_string` for `Float.toString`
+pub(crate) fn java_float_to_string(value: f32) -> String {
+  if value.is_nan() || value.is_infinite() || value == 0.0 {
+    return java_special_to_string(value.is_nan(), value.is_sign_negative(), value == 0.0);
+  }
+
+  let mut repr = format!("{:e}", value.abs());
+
+  if !repr.contains('.') {
+    let closest = format!("{:.1e}", value.abs());
+
+    if closest.parse::<f32>().ok() == Some(value.abs()) {
+      repr = closest;
+    }
+  }
+
+  java_decimal_to_string(value.is_sign_negative(), &repr)
+}
+
+fn java_special_to_string(nan: bool, negative: bool, zero: bool) -> String {
+  let repr = match (nan, zero) {
+    (true, _) => return "NaN".to_string(),
+    (_, true) => "0.0",
+    _ => "Infinity",
+  };
+
+  if negative {
+    format!("-{}", repr)
+  } else {
+    repr.to_string()
+  }
+}
+
+// `repr` is the scientific notation of rust (e.g. `1.25e-3`)
+fn java_decimal_to_string(negative: bool, repr: &str) -> String {
+  let (mantissa, exponent) = repr.split_once('e').unwrap_or((repr, "0"));
+  let exponent: i32 = exponent.parse().unwrap_or(0);
+  let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+  let digits = digits.trim_end_matches('0');
+  let digits = if digits.is_empty() { "0" } else { digits };
+
+  let sign = if negative { "-" } else { "" };
+
+  // plain notation in [10^-3, 10^7), computerized scientific notation otherwise
+  if !(-3..7).contains(&exponent) {
+    let fraction = if digits.len() > 1 { &digits[1..] } else { "0" };
+
+    return format!("{}{}.{}E{}", sign, &digits[..1], fraction, exponent);
+  }
+
+  if exponent < 0 {
+    return format!(
+      "{}0.{}{}",
+      sign,
+      "0".repeat((-exponent - 1) as usize),
+      digits
+    );
+  }
+
+  let integer_len = exponent as usize + 1;
+
+  if digits.len() <= integer_len {
+    format!(
+      "{}{}{}.0",
+      sign,
+      digits,
+      "0".repeat(integer_len - digits.len())
+    )
+  } else {
+    format!(
+      "{}{}.{}",
+      sign,
+      &digits[..integer_len],
+      &digits[integer_len..]
+    )
+  }
+}
+
+fn java_char_to_string(value: i32) -> String {
+  char::from_u32((value & 0xffff) as u32)
+    .unwrap_or(char::REPLACEMENT_CHARACTER)
+    .to_string()
+}
+
+/// Format a primitive like `String.valueOf`
+pub(crate) fn java_primitive_to_string(descriptor: &str, value: &types::Type) -> Result<String> {
+  let string = match descriptor {
+    "Z" => value.as_bool()?.to_string(),
+    "C" => java_char_to_string(value.as_integer()?),
+    "B" | "S" | "I" => value.as_integer()?.to_string(),
+    "J" => value.as_long()?.to_string(),
+    "F" => java_float_to_string(value.as_float()?),
+    "D" => java_double_to_string(value.as_double()?),
+    _ => {
+      return Err(eyre!(errors::InternalError::General(format!(
+        "not a primitive type: {}",
+        descriptor
+      ))))
+    }
+  };
+
+  Ok(string)
+}
+
+impl JVM {
+  pub(crate) fn is_string_concat_bootstrap(
+    invoke_dynamic_resolved: &InvokeDynamicResolved,
+  ) -> bool {
+    invoke_dynamic_resolved.get_bootstrap_class_name() == STRING_CONCAT_FACTORY
+      && matches!(
+        invoke_dynamic_resolved.get_bootstrap_method_name(),
+        "makeConcat" | "makeConcatWithConstants"
+      )
+  }
+
+  /// Link the call site to the native concatenation instead of running the bootstrap method
+  pub(crate) fn link_string_concat(
+    &mut self,
+    curr_class_name: &str,
+    invoke_dynamic_resolved: &InvokeDynamicResolved,
+  ) -> Result<StringConcat> {
+    let method_type = invoke_dynamic_resolved.get_method_type();
+
+    if get_return_type_descriptor(method_type) != "java/lang/String" {
+      return Err(eyre!(errors::JavaException::BootstrapMethodError(format!(
+        "Return type should be compatible with String, but it is {}",
+        method_type
+      ))));
+    }
+
+    let argument_types = get_argument_classnames(method_type);
+
+    if invoke_dynamic_resolved.get_bootstrap_method_name() == "makeConcat" {
+      return Ok(StringConcat::from_arguments(argument_types));
+    }
+
+    let (recipe, constants) = invoke_dynamic_resolved
+      .get_arguments()
+      .split_first()
+      .ok_or_else(|| {
+        eyre!(errors::JavaException::BootstrapMethodError(
+          "makeConcatWithConstants without recipe".to_string()
+        ))
+      })?;
+
+    let recipe = self.resolve_string_concat_constant(curr_class_name, *recipe)?;
+
+    let constants = constants
+      .iter()
+      .map(|index| self.resolve_string_concat_constant(curr_class_name, *index))
+      .collect::<Result<Vec<_>>>()?;
+
+    debug!("STRING CONCAT {:?} {:?}", recipe, constants);
+
+    StringConcat::from_recipe(&recipe, &constants, argument_types)
+  }
+
+  // constants are loadable constants formatted when linking
+  fn resolve_string_concat_constant(&mut self, classname: &str, index: ju2) -> Result<String> {
+    let cpinfo = self
+      .class_loader
+      .get(classname)?
+      .resolve_index(index)?
+      .clone();
+
+    let constant = match cpinfo.get_info() {
+      CpInfoInfoEnum::String(info) => self
+        .class_loader
+        .resolve_string(classname, info.get_string_index())?,
+      CpInfoInfoEnum::Integer(int) => int.int().to_string(),
+      CpInfoInfoEnum::Float(fl) => java_float_to_string(fl.float()),
+      CpInfoInfoEnum::Long(long) => (long.value() as i64).to_string(),
+      CpInfoInfoEnum::Double(double) => java_double_to_string(f64::from_bits(double.value())),
+      // dynamic constants, classes, method types and handles
+      _ => {
+        let value = self.load_constant(classname, index)?;
+
+        let arg_type = value
+          .get_primitive_descriptor()
+          .unwrap_or("Ljava/lang/Object;");
+
+        self.java_to_string(arg_type, value)?
+      }
+    };
+
+    Ok(constant)
+  }
+
+  /// Pop the arguments of the call site and build the concatenated string
+  pub(crate) fn invoke_string_concat(&mut self, concat: &StringConcat) -> Result<types::Type> {
+    let mut args = vec![];
+
+    for _ in 0..concat.argument_types.len() {
+      args.push(self.pop_stack()?);
+    }
+
+    args.reverse();
+
+    // arguments are converted first, `toString` may have side effects
+    let mut strings = vec![];
+
+    for (arg_type, arg) in concat.argument_types.iter().zip(args) {
+      strings.push(self.java_to_string(arg_type, arg)?);
+    }
+
+    let mut result = String::new();
+
+    for element in &concat.elements {
+      match element {
+        RecipeElement::Constant(constant) => result.push_str(constant),
+        RecipeElement::Argument(index) => result.push_str(&strings[*index]),
+      }
+    }
+
+    self.heap.alloc_string(&mut self.class_loader, &result)
+  }
+
+  // `String.valueOf` of an argument
+  pub(crate) fn java_to_string(&mut self, arg_type: &str, arg: types::Type) -> Result<String> {
+    let obj_ref = match arg {
+      types::Type::Null => return Ok("null".to_string()),
+      types::Type::ObjectRef(obj_ref) | types::Type::ArrayRef(obj_ref) => obj_ref,
+      value => return java_primitive_to_string(arg_type, &value),
+    };
+
+    if let Some((descriptor, value)) = self.heap.get_boxed_value(obj_ref)? {
+      return java_primitive_to_string(descriptor, &value);
+    }
+
+    let classname = match self.heap.get_instance(obj_ref)? {
+      types::Instance::ObjectInstance(obj) => obj.get_classname().to_string(),
+      types::Instance::ArrayInstance(_) => "java/lang/Object".to_string(),
+    };
+
+    if classname == "java/lang/String" {
+      return self.heap.get_string(obj_ref);
+    }
+
+    let string =
+      self.call_and_resolve_method(&classname, "toString", "()Ljava/lang/String;", vec![arg])?;
+
+    match string {
+      types::Type::Null => Ok("null".to_string()),
+      string => self.heap.get_string(string.as_ref()?),
+    }
+  }
+}
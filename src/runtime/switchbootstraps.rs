@@ -0,0 +1,179 @@
+use color_eyre::eyre::{eyre, Result};
+use log::debug;
+
+use crate::{
+  class_loader::{class_file::InvokeDynamicResolved, constant_pool::CpInfoInfoEnum},
+  runtime::{errors, jvm::JVM, types},
+  utils::{get_parameters_type_descriptor, ju2, parse_parameter_types},
+};
+
+const SWITCH_BOOTSTRAPS: &str = "java/lang/runtime/SwitchBootstraps";
+
+/// Case label of a pattern switch
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum SwitchLabel {
+  // type pattern, the class is resolved from the caller
+  Class(String),
+  // constant of a `String` switch or name of an enum constant of `enumSwitch`
+  String(String),
+  Integer(i32),
+}
+
+/// Call site of `SwitchBootstraps` linked to the native matching of the labels
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TypeSwitch {
+  labels: Vec<SwitchLabel>,
+  // `enumSwitch` matches the names of the enum constants
+  enum_switch: bool,
+}
+
+impl TypeSwitch {
+  pub(crate) fn new(labels: Vec<SwitchLabel>, enum_switch: bool) -> TypeSwitch {
+    TypeSwitch {
+      labels,
+      enum_switch,
+    }
+  }
+}
+
+impl JVM {
+  pub(crate) fn is_switch_bootstrap(invoke_dynamic_resolved: &InvokeDynamicResolved) -> bool {
+    invoke_dynamic_resolved.get_bootstrap_class_name() == SWITCH_BOOTSTRAPS
+      && matches!(
+        invoke_dynamic_resolved.get_bootstrap_method_name(),
+        "typeSwitch" | "enumSwitch"
+      )
+  }
+
+  /// Link the call site to the native matching of the labels instead of running the bootstrap
+  /// method
+  ///
+  /// The call site takes the selector and the index of the first label to try (after a guard
+  /// failed), and returns the index of the matching label, `labels.length` if none matches.
+  pub(crate) fn link_switch(
+    &mut self,
+    curr_class_name: &str,
+    invoke_dynamic_resolved: &InvokeDynamicResolved,
+  ) -> Result<TypeSwitch> {
+    let enum_switch = invoke_dynamic_resolved.get_bootstrap_method_name() == "enumSwitch";
+
+    let method_type = invoke_dynamic_resolved.get_method_type();
+
+    // (selector, restart index) -> label index, the selector of `enumSwitch` is an enum
+    let valid_type = method_type.ends_with(")I")
+      && match parse_parameter_types(get_parameters_type_descriptor(method_type))[..] {
+        [selector, "I"] => selector.starts_with('L') || (!enum_switch && selector.starts_with('[')),
+        _ => false,
+      };
+
+    if !valid_type {
+      return Err(eyre!(errors::JavaException::BootstrapMethodError(format!(
+        "Illegal invocation type {} of {}",
+        method_type,
+        invoke_dynamic_resolved.get_bootstrap_method_name()
+      ))));
+    }
+
+    let labels = invoke_dynamic_resolved
+      .get_arguments()
+      .iter()
+      .map(|index| self.resolve_switch_label(curr_class_name, *index, enum_switch))
+      .collect::<Result<Vec<_>>>()?;
+
+    debug!("SWITCH {:?}", labels);
+
+    Ok(TypeSwitch::new(labels, enum_switch))
+  }
+
+  fn resolve_switch_label(
+    &mut self,
+    classname: &str,
+    index: ju2,
+    enum_switch: bool,
+  ) -> Result<SwitchLabel> {
+    let cpinfo = self
+      .class_loader
+      .get(classname)?
+      .resolve_index(index)?
+      .clone();
+
+    let label = match cpinfo.get_info() {
+      CpInfoInfoEnum::Class(info) => {
+        let name = self
+          .class_loader
+          .get(classname)?
+          .resolve_name(info.get_name_index())?;
+
+        SwitchLabel::Class(self.resolve_class(classname, &name)?)
+      }
+      CpInfoInfoEnum::String(info) => SwitchLabel::String(
+        self
+          .class_loader
+          .resolve_string(classname, info.get_string_index())?,
+      ),
+      CpInfoInfoEnum::Integer(int) if !enum_switch => SwitchLabel::Integer(int.int()),
+      _ => {
+        return Err(eyre!(errors::JavaException::BootstrapMethodError(format!(
+          "Switch label not supported: {}",
+          cpinfo.get_info()
+        ))))
+      }
+    };
+
+    Ok(label)
+  }
+
+  /// Pop the selector and the restart index, and return the index of the matching label
+  pub(crate) fn invoke_switch(&mut self, switch: &TypeSwitch) -> Result<types::Type> {
+    let restart = self.pop_stack()?.as_integer()?;
+    let target = self.pop_stack()?;
+
+    if restart < 0 || restart as usize > switch.labels.len() {
+      return Err(eyre!(errors::JavaException::IndexOutOfBounds(format!(
+        "Index {} out of bounds for length {}",
+        restart,
+        switch.labels.len() + 1
+      ))));
+    }
+
+    let obj_ref = match target {
+      types::Type::Null => return Ok(types::Type::Integer(-1)),
+      types::Type::ObjectRef(obj_ref) | types::Type::ArrayRef(obj_ref) => obj_ref,
+      _ => return Err(eyre!("Not a Object Reference: {}", target)),
+    };
+
+    let classname = self.heap.get_instance(obj_ref)?.get_classname().to_string();
+
+    // enum constants are matched by name, numbers and characters by their int value
+    let constant = if switch.enum_switch {
+      let name = self.heap.get_obj_instance(obj_ref)?.get_field("name")?;
+
+      Some(SwitchLabel::String(self.heap.get_string(name.as_ref()?)?))
+    } else if classname == "java/lang/String" {
+      Some(SwitchLabel::String(self.heap.get_string(obj_ref)?))
+    } else {
+      match self.heap.get_boxed_value(obj_ref)? {
+        Some(("B" | "S" | "C" | "I", value)) => Some(SwitchLabel::Integer(value.as_integer()?)),
+        Some(("J", value)) => Some(SwitchLabel::Integer(value.as_long()? as i32)),
+        Some(("F", value)) => Some(SwitchLabel::Integer(value.as_float()? as i32)),
+        Some(("D", value)) => Some(SwitchLabel::Integer(value.as_double()? as i32)),
+        _ => None,
+      }
+    };
+
+    for (index, label) in switch.labels.iter().enumerate().skip(restart as usize) {
+      let matches = match label {
+        SwitchLabel::Class(label_class) => {
+          types::Type::check_type(&mut self.class_loader, label_class, &classname)?
+        }
+        label => constant.as_ref() == Some(label),
+      };
+
+      if matches {
+        return Ok(types::Type::Integer(index as i32));
+      }
+    }
+
+    Ok(types::Type::Integer(switch.labels.len() as i32))
+  }
+}
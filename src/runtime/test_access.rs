@@ -0,0 +1,166 @@
+#[cfg(test)]
+mod tests {
+
+  use crate::runtime::{errors::JavaException, jvm::JVM, types};
+
+  #[test]
+
+  fn test_nest_mates() {
+    let mut jvm = JVM::mock("tests/classes/", vec![], false).unwrap();
+
+    jvm.class_loader.load_class("TestAccess").unwrap();
+    jvm.class_loader.load_class("TestAccess$Inner").unwrap();
+
+    assert!(jvm
+      .class_loader
+      .are_nest_mates("TestAccess", "TestAccess$Inner")
+      .unwrap());
+
+    assert!(!jvm
+      .class_loader
+      .are_nest_mates("TestAccess", "java/lang/String")
+      .unwrap());
+
+    assert_eq!(
+      jvm
+        .class_loader
+        .get_nest_host_name("TestAccess$Inner")
+        .unwrap(),
+      "TestAccess"
+    );
+  }
+
+  #[test]
+
+  fn test_private_member_access() {
+    let mut jvm = JVM::mock("tests/classes/", vec![], false).unwrap();
+
+    jvm.class_loader.load_class("TestAccess").unwrap();
+    jvm.class_loader.load_class("TestAccess$Inner").unwrap();
+
+    // private members of a nest mate are accessible
+    assert!(jvm
+      .resolve_field_access("TestAccess$Inner", "TestAccess", "secret", "I")
+      .is_ok());
+
+    assert!(jvm
+      .resolve_method_access("TestAccess", "TestAccess$Inner", "peek", "(LTestAccess;)I",)
+      .is_ok());
+
+    // but not the private fields of another class
+    assert!(jvm
+      .resolve_field_access("TestAccess", "java/lang/String", "value", "[B")
+      .is_err());
+
+    // public members are accessible
+    assert!(jvm
+      .resolve_method_access("TestAccess", "java/lang/String", "length", "()I")
+      .is_ok());
+  }
+
+  #[test]
+
+  fn test_inherited_private_member_access() {
+    let mut jvm = JVM::mock("tests/classes/", vec![], false).unwrap();
+
+    jvm.class_loader.load_class("TestAccessChild").unwrap();
+
+    // a private member of the super class is not accessible through the current class
+    let err = jvm
+      .resolve_field_access("TestAccessChild", "TestAccessChild", "secret", "I")
+      .unwrap_err();
+
+    assert!(matches!(
+      err.downcast_ref::<JavaException>(),
+      Some(JavaException::IllegalAccessError(_))
+    ));
+
+    // the inherited package private ones are
+    assert!(jvm
+      .resolve_method_access("TestAccessChild", "TestAccessChild", "shared", "()I")
+      .is_ok());
+  }
+
+  #[test]
+
+  fn test_missing_member() {
+    let mut jvm = JVM::mock("tests/classes/", vec![], false).unwrap();
+
+    jvm.class_loader.load_class("TestAccess").unwrap();
+
+    let err = jvm
+      .resolve_field_access("TestAccess", "TestAccess", "missing", "I")
+      .unwrap_err();
+
+    assert!(matches!(
+      err.downcast_ref::<JavaException>(),
+      Some(JavaException::NoSuchFieldError(_))
+    ));
+
+    let err = jvm
+      .resolve_method_access("TestAccess", "java/lang/String", "missing", "()V")
+      .unwrap_err();
+
+    assert!(matches!(
+      err.downcast_ref::<JavaException>(),
+      Some(JavaException::NoSuchMethodError(_))
+    ));
+  }
+
+  #[test]
+
+  fn test_protected_member_access() {
+    let mut jvm = JVM::mock("tests/classes/", vec![], false).unwrap();
+
+    jvm.class_loader.load_class("TestAccess").unwrap();
+
+    let string_obj = jvm.heap.alloc_string(&mut jvm.class_loader, "wow").unwrap();
+
+    let clone = jvm
+      .resolve_method_access(
+        "TestAccess",
+        "java/lang/Object",
+        "clone",
+        "()Ljava/lang/Object;",
+      )
+      .unwrap();
+
+    // Object.clone is protected: it cannot be called on an unrelated object
+    assert!(jvm
+      .check_protected_receiver(
+        "TestAccess",
+        "java/lang/Object",
+        &clone.declaring,
+        clone.access_flags,
+        string_obj
+      )
+      .is_err());
+
+    let obj = jvm
+      .heap
+      .alloc_obj(&mut jvm.class_loader, "TestAccess")
+      .unwrap();
+
+    assert!(jvm
+      .check_protected_receiver(
+        "TestAccess",
+        "java/lang/Object",
+        &clone.declaring,
+        clone.access_flags,
+        obj
+      )
+      .is_ok());
+  }
+
+  #[test]
+
+  fn test_exec_nest_mates() {
+    let mut jvm = JVM::mock("tests/classes/", vec![], false).unwrap();
+
+    let result = jvm
+      .call_and_resolve_method("TestAccess", "_nestmates", "()I", vec![])
+      .unwrap();
+
+    assert_eq!(result, types::Type::Integer(49));
+  }
+}
@@ -0,0 +1,184 @@
+#[cfg(test)]
+mod tests {
+
+  use crate::class_loader::{
+    attributes::{AttributeInfoInfoEnum, ElementValueEnum, TargetInfo},
+    class_file::ClassFile,
+  };
+
+  fn read_class(name: &str) -> (Vec<u8>, std::sync::Arc<std::sync::RwLock<ClassFile>>) {
+    let data = std::fs::read(format!("tests/classes/{}.class", name)).unwrap();
+
+    let class_file = ClassFile::parse_from_bytes(&data).unwrap();
+
+    (data, class_file)
+  }
+
+  // the raw bytes are the attribute info, exactly as found in the class file
+  fn assert_raw(data: &[u8], raw: &[u8]) {
+    assert!(!raw.is_empty());
+    assert!(
+      data
+        .windows(raw.len() + 4)
+        .any(|window| { window[..4] == (raw.len() as u32).to_be_bytes() && &window[4..] == raw }),
+      "raw bytes not found in the class file"
+    );
+  }
+
+  #[test]
+
+  fn test_parameter_annotations() {
+    let (data, class_file) = read_class("TestAnnotations");
+
+    let class_file = class_file.read().unwrap();
+
+    let method = class_file
+      .get_methods()
+      .iter()
+      .find(|m| m.get_name() == "tagged")
+      .unwrap();
+
+    let attributes = method.get_attributes();
+
+    let attr = attributes.get_by_name("RuntimeVisibleParameterAnnotations");
+
+    let AttributeInfoInfoEnum::RuntimeVisibleParameterAnnotations(annotations) = attr.get_info()
+    else {
+      panic!("unexpected attribute {:?}", attr.get_info());
+    };
+
+    let counts: Vec<usize> = annotations
+      .get_parameter_annotations()
+      .iter()
+      .map(|a| a.len())
+      .collect();
+
+    assert_eq!(counts, vec![1, 0, 1]);
+    assert_eq!(
+      annotations.get_raw().len(),
+      attr.get_attribute_length() as usize
+    );
+
+    for name in [
+      "RuntimeVisibleAnnotations",
+      "RuntimeVisibleParameterAnnotations",
+    ] {
+      assert_raw(&data, attributes.get_raw_annotations(name).unwrap());
+    }
+
+    assert!(attributes
+      .get_raw_annotations("AnnotationDefault")
+      .is_none());
+  }
+
+  #[test]
+
+  fn test_type_annotations() {
+    let (data, class_file) = read_class("TestAnnotations");
+
+    let class_file = class_file.read().unwrap();
+
+    let field = &class_file.get_fields()[0];
+
+    let attr = field
+      .get_attributes()
+      .get_by_name("RuntimeVisibleTypeAnnotations");
+
+    let AttributeInfoInfoEnum::RuntimeVisibleTypeAnnotations(annotations) = attr.get_info() else {
+      panic!("unexpected attribute {:?}", attr.get_info());
+    };
+
+    let annotation = &annotations.get_annotations()[0];
+
+    // List<@NonNull String>: a field type, first type argument
+    assert_eq!(annotation.get_target_type(), 0x13);
+    assert!(matches!(annotation.get_target_info(), TargetInfo::Empty));
+    assert_eq!(annotation.get_type_path(), &vec![(3, 0)]);
+    assert_raw(&data, annotations.get_raw());
+
+    // @NonNull on a local variable is recorded on the Code attribute
+    let method = class_file
+      .get_methods()
+      .iter()
+      .find(|m| m.get_name() == "tagged")
+      .unwrap();
+
+    let code_attributes = method.get_code().unwrap().get_attributes();
+
+    let attr = code_attributes.get_by_name("RuntimeVisibleTypeAnnotations");
+
+    let AttributeInfoInfoEnum::RuntimeVisibleTypeAnnotations(annotations) = attr.get_info() else {
+      panic!("unexpected attribute {:?}", attr.get_info());
+    };
+
+    let annotation = &annotations.get_annotations()[0];
+
+    assert_eq!(annotation.get_target_type(), 0x40);
+
+    let TargetInfo::Localvar { table } = annotation.get_target_info() else {
+      panic!("unexpected target {:?}", annotation.get_target_info());
+    };
+
+    assert_eq!(table.len(), 1);
+    assert_eq!(table[0].2, 3);
+  }
+
+  #[test]
+
+  fn test_annotation_default() {
+    let (data, class_file) = read_class("TestAnnotations$Tag");
+
+    let class_file = class_file.read().unwrap();
+
+    for (name, tag) in [("value", b's'), ("priority", b'I')] {
+      let method = class_file
+        .get_methods()
+        .iter()
+        .find(|m| m.get_name() == name)
+        .unwrap();
+
+      let attr = method.get_attributes().get_by_name("AnnotationDefault");
+
+      let AttributeInfoInfoEnum::AnnotationDefault(default) = attr.get_info() else {
+        panic!("unexpected attribute {:?}", attr.get_info());
+      };
+
+      let value = default.get_default_value();
+
+      assert_eq!(value.get_tag(), tag);
+      assert!(matches!(
+        value.get_value(),
+        ElementValueEnum::ConstValue { .. }
+      ));
+      assert_eq!(default.get_raw().len(), 3);
+      assert_raw(&data, default.get_raw());
+    }
+  }
+
+  #[test]
+
+  fn test_disassemble_annotations() {
+    let (_, class_file) = read_class("TestAnnotations");
+
+    let text = class_file.read().unwrap().disassemble().unwrap();
+
+    for expected in [
+      "      0: #24(): FIELD, location=[TYPE_ARGUMENT(0)]\n",
+      "        0: #24(): LOCAL_VARIABLE, {start_pc=3, length=11, index=3}\n",
+      "    RuntimeVisibleParameterAnnotations:\n      parameter 0:\n",
+      "          TestAnnotations$Tag(value=\"first\")\n",
+    ] {
+      assert!(text.contains(expected), "{}", text);
+    }
+
+    let (_, class_file) = read_class("TestAnnotations$Tag");
+
+    let text = class_file.read().unwrap().disassemble().unwrap();
+
+    assert!(
+      text.contains("    AnnotationDefault:\n      default_value: I#13\n        5\n"),
+      "{}",
+      text
+    );
+  }
+}
@@ -0,0 +1,412 @@
+#[cfg(test)]
+mod tests {
+
+  use crate::{
+    class_loader::{assembler::assemble, class_file::ClassFile, namespace::BOOTSTRAP_LOADER},
+    runtime::{errors::JavaException, jvm::JVM, types::Type},
+  };
+
+  fn define(jvm: &mut JVM, source: &str) -> String {
+    let bytes = assemble(source).unwrap();
+
+    jvm.define_class(BOOTSTRAP_LOADER, None, &bytes).unwrap()
+  }
+
+  fn code_of(source: &str, method: &str) -> Vec<u8> {
+    let class_file = ClassFile::parse_from_bytes(&assemble(source).unwrap()).unwrap();
+
+    let class_file = class_file.read().unwrap();
+
+    let method = class_file
+      .get_methods()
+      .iter()
+      .find(|m| m.get_name() == method)
+      .unwrap();
+
+    method.get_code().unwrap().get_code_vec().clone()
+  }
+
+  fn assemble_error(source: &str) -> String {
+    assemble(source).unwrap_err().to_string()
+  }
+
+  const ARITHMETIC: &str = "
+    .class public Arithmetic
+    .field private static counter I
+
+    ; sum of 1..n with a loop
+    .method public static sum(I)I
+      .limit stack 2
+      .limit locals 2
+      iconst_0
+      istore_1
+    Loop:
+      iload_0
+      ifle End
+      iload_1
+      iload_0
+      iadd
+      istore_1
+      iinc 0 -1
+      goto Loop
+    End:
+      iload_1
+      ireturn
+    .end method
+
+    .method public static bump()I
+      getstatic Arithmetic/counter I
+      ldc 40000
+      iadd
+      dup
+      putstatic Arithmetic/counter I
+      ireturn
+    .end method
+
+    .method public static wide()J
+      ldc2_w 5000000000
+      lconst_1
+      ladd
+      lreturn
+    .end method
+  ";
+
+  #[test]
+
+  fn test_assemble_and_run() {
+    let mut jvm = JVM::mock("tests/classes/", vec![], false).unwrap();
+
+    let key = define(&mut jvm, ARITHMETIC);
+
+    let result = jvm
+      .call_and_resolve_method(&key, "sum", "(I)I", vec![Type::Integer(10)])
+      .unwrap();
+
+    assert_eq!(result, Type::Integer(55));
+
+    for expected in [40000, 80000] {
+      let result = jvm
+        .call_and_resolve_method(&key, "bump", "()I", vec![])
+        .unwrap();
+
+      assert_eq!(result, Type::Integer(expected));
+    }
+
+    let result = jvm
+      .call_and_resolve_method(&key, "wide", "()J", vec![])
+      .unwrap();
+
+    assert_eq!(result, Type::Long(5000000001));
+  }
+
+  #[test]
+
+  fn test_assemble_switches() {
+    let source = "
+      .class public Switches
+
+      .method public static table(I)I
+        iload_0
+        tableswitch 1 3
+          One
+          Two
+          Two
+          default : Other
+      One:
+        bipush 10
+        ireturn
+      Two:
+        bipush 20
+        ireturn
+      Other:
+        iconst_m1
+        ireturn
+      .end method
+
+      .method public static lookup(I)I
+        iload_0
+        lookupswitch
+          -5 : Minus
+          100: Hundred
+          default: Other
+      Minus:
+        bipush 5
+        ireturn
+      Hundred:
+        bipush 100
+        ireturn
+      Other:
+        iconst_0
+        ireturn
+      .end method
+    ";
+
+    let mut jvm = JVM::mock("tests/classes/", vec![], false).unwrap();
+
+    let key = define(&mut jvm, source);
+
+    for (method, argument, expected) in [
+      ("table", 1, 10),
+      ("table", 3, 20),
+      ("table", 4, -1),
+      ("table", 0, -1),
+      ("lookup", -5, 5),
+      ("lookup", 100, 100),
+      ("lookup", 7, 0),
+    ] {
+      let result = jvm
+        .call_and_resolve_method(&key, method, "(I)I", vec![Type::Integer(argument)])
+        .unwrap();
+
+      assert_eq!(result, Type::Integer(expected), "{}({})", method, argument);
+    }
+  }
+
+  #[test]
+
+  fn test_assemble_encoding() {
+    let source = "
+      .class public Encoding
+
+      .method static subroutine()V
+        .limit locals 300
+        jsr Sub
+        return
+      Sub:
+        astore 299        ; wide form, the index does not fit a byte
+        iinc 1 1000       ; wide form, the increment does not fit a byte
+        wide iload 2      ; explicit wide form
+        pop
+        ret 299
+      .end method
+    ";
+
+    #[rustfmt::skip]
+    let expected = vec![
+      0xa8, 0x00, 0x04,                         // jsr Sub
+      0xb1,                                     // return
+      0xc4, 0x3a, 0x01, 0x2b,                   // wide astore 299
+      0xc4, 0x84, 0x00, 0x01, 0x03, 0xe8,       // wide iinc 1 1000
+      0xc4, 0x15, 0x00, 0x02,                   // wide iload 2
+      0x57,                                     // pop
+      0xc4, 0xa9, 0x01, 0x2b,                   // wide ret 299
+    ];
+
+    assert_eq!(code_of(source, "subroutine"), expected);
+  }
+
+  #[test]
+
+  fn test_assemble_exception_table() {
+    let source = "
+      .class public Handlers
+
+      .method public static guarded()V
+        .catch java/lang/ArithmeticException from Start to End using Handler
+        .catch all from Start to End using Handler
+      Start:
+        nop
+      End:
+        return
+      Handler:
+        pop
+        return
+      .end method
+    ";
+
+    let class_file = ClassFile::parse_from_bytes(&assemble(source).unwrap()).unwrap();
+
+    let class_file = class_file.read().unwrap();
+
+    let code = class_file.get_methods()[0].get_code().unwrap();
+
+    let handlers: Vec<(u16, u16, u16, &str)> = code
+      .get_exception_table()
+      .iter()
+      .map(|e| {
+        (
+          e.get_start_pc(),
+          e.get_end_pc(),
+          e.get_handler_pc(),
+          e.get_catch_type(),
+        )
+      })
+      .collect();
+
+    assert_eq!(
+      handlers,
+      vec![
+        (0, 1, 2, "java/lang/ArithmeticException"),
+        (0, 1, 2, "finally")
+      ]
+    );
+  }
+
+  #[test]
+
+  fn test_assemble_stack_map_table() {
+    let branch = |frame: &str| {
+      format!(
+        "
+        .class public Frames
+        .version 61
+
+        .method public static abs(I)I
+          .limit stack 1
+          iload_0
+          ifge Positive
+          iload_0
+          ineg
+          ireturn
+        {}
+        Positive:
+          iload_0
+          ireturn
+        .end method
+        ",
+        frame
+      )
+    };
+
+    let mut jvm = JVM::mock("tests/classes/", vec![], false).unwrap();
+
+    // a branch target needs a frame in a type checked class file
+    let key = define(&mut jvm, &branch(""));
+
+    let err = jvm.link_class(&key).unwrap_err();
+
+    assert!(
+      matches!(
+        err.downcast_ref::<JavaException>(),
+        Some(JavaException::VerifyError(_))
+      ),
+      "{}",
+      err
+    );
+
+    let key = define(
+      &mut jvm,
+      &branch(".stack\n locals Integer\n .end stack").replace("Frames", "Frames2"),
+    );
+
+    jvm.link_class(&key).unwrap();
+
+    let result = jvm
+      .call_and_resolve_method(&key, "abs", "(I)I", vec![Type::Integer(-7)])
+      .unwrap();
+
+    assert_eq!(result, Type::Integer(7));
+  }
+
+  #[test]
+
+  fn test_assemble_errors() {
+    let method = |body: &str| {
+      format!(
+        ".class public Errors\n.method public static m()V\n{}\n.end method",
+        body
+      )
+    };
+
+    let err = assemble_error(&method("goto Nowhere"));
+    assert!(err.contains("Line 3: Undefined label Nowhere"), "{}", err);
+
+    let err = assemble_error(&method("iadd 1"));
+    assert!(
+      err.contains("Line 3: Expected 0 operand(s), got 1"),
+      "{}",
+      err
+    );
+
+    let err = assemble_error(&method("frobnicate"));
+    assert!(
+      err.contains("Line 3: Unknown instruction frobnicate"),
+      "{}",
+      err
+    );
+
+    let err = assemble_error(&method("bipush 300"));
+    assert!(
+      err.contains("Line 3: Invalid or out of range number 300"),
+      "{}",
+      err
+    );
+
+    let err = assemble_error(&method("ldc \"unterminated"));
+    assert!(err.contains("Line 3: Unterminated string"), "{}", err);
+
+    let err = assemble_error(&method(
+      "invokedynamic run()V Boot/boot()Ljava/lang/invoke/CallSite; handle getter Boot/x I",
+    ));
+    assert!(
+      err.contains("Line 3: Unknown reference kind getter"),
+      "{}",
+      err
+    );
+
+    let err = assemble_error(&method("ldc dynamic Missing"));
+    assert!(
+      err.contains("Line 3: Undefined dynamic constant Missing"),
+      "{}",
+      err
+    );
+
+    let err = assemble_error(".method public static m()V\nreturn\n.end method");
+    assert!(
+      err.contains("Line 1: Missing .class or .interface"),
+      "{}",
+      err
+    );
+
+    let err = assemble_error(".class public Errors\n.method public static m()V\nreturn");
+    assert!(err.contains("Missing .end method"), "{}", err);
+  }
+
+  #[test]
+
+  fn test_assemble_malformed_class() {
+    // the assembler does not check the format, the class loader does
+    let source = "
+      .class public Malformed
+      .method public static m()V
+        .limit locals 0
+      .end method
+    ";
+
+    let bytes = assemble(source).unwrap();
+
+    let err = ClassFile::parse_from_bytes(&bytes).unwrap_err();
+
+    assert!(
+      matches!(
+        err.downcast_ref::<JavaException>(),
+        Some(JavaException::ClassFormatError(_))
+      ),
+      "{}",
+      err
+    );
+  }
+
+  #[test]
+
+  fn test_assemble_disassemble() {
+    let bytes = assemble(ARITHMETIC).unwrap();
+
+    let text = ClassFile::parse_from_bytes(&bytes)
+      .unwrap()
+      .read()
+      .unwrap()
+      .disassemble()
+      .unwrap();
+
+    assert!(
+      text.contains("        10: iinc          0, -1\n"),
+      "{}",
+      text
+    );
+    assert!(text.contains("        13: goto          2\n"), "{}", text);
+    assert!(text.contains("ldc           #"), "{}", text);
+    assert!(text.contains("// int 40000"), "{}", text);
+    assert!(text.contains("// long 5000000000l"), "{}", text);
+  }
+}
@@ -0,0 +1,163 @@
+#[cfg(test)]
+mod tests {
+
+  use std::os::fd::AsRawFd;
+
+  use crate::runtime::{
+    errors::InternalError,
+    jvm::JVM,
+    test_support::{self, *},
+    types::Type,
+  };
+
+  const CLASS: &str = "TestByteBuffer";
+
+  const UTILS: &str = "java/nio/MappedMemoryUtils";
+
+  // direct buffers are created by a thread
+  fn mock() -> JVM {
+    let mut jvm = test_support::mock();
+
+    test_support::create_main_thread(&mut jvm, "main");
+
+    jvm
+  }
+
+  // off-heap bytes allocated by the method
+  fn call_allocated(jvm: &mut JVM, method: &str, descriptor: &str, args: Vec<Type>) -> i64 {
+    let before = jvm.nativememory.get_allocated() as i64;
+
+    jvm
+      .call_and_resolve_method(CLASS, method, descriptor, args)
+      .unwrap();
+
+    jvm.nativememory.get_allocated() as i64 - before
+  }
+
+  #[test]
+
+  fn test_bytebuffer_direct() {
+    let mut jvm = mock();
+
+    assert_eq!(
+      call_string(&mut jvm, CLASS, "direct", "()Ljava/lang/String;", vec![]),
+      "true 16909060 67305985 5 -2 4"
+    );
+  }
+
+  #[test]
+
+  fn test_bytebuffer_bulk() {
+    let mut jvm = mock();
+
+    assert_eq!(
+      call_string(&mut jvm, CLASS, "bulk", "()Ljava/lang/String;", vec![]),
+      "1 4 2 6 5 7 8"
+    );
+  }
+
+  #[test]
+
+  fn test_bytebuffer_free() {
+    let mut jvm = mock();
+
+    assert_eq!(call_allocated(&mut jvm, "free", "()V", vec![]), 0);
+  }
+
+  #[test]
+
+  fn test_bytebuffer_garbage() {
+    let mut jvm = mock();
+
+    let args = vec![Type::Integer(3)];
+    assert_eq!(call_allocated(&mut jvm, "garbage", "(I)V", args), 0);
+  }
+
+  #[test]
+
+  fn test_bytebuffer_exhaust() {
+    let mut jvm = mock();
+
+    // 80MB are allocated over the 64MB limit, the unreachable buffers are freed
+    let allocated = call_allocated(&mut jvm, "exhaust", "()V", vec![]);
+    assert!(allocated <= 64 * 1024 * 1024);
+  }
+
+  #[test]
+
+  fn test_bytebuffer_keep() {
+    let mut jvm = mock();
+
+    // the buffer of the static field is not freed
+    assert_eq!(call_allocated(&mut jvm, "keep", "()V", vec![]), 16);
+  }
+
+  #[test]
+
+  fn test_bytebuffer_mapped() {
+    let mut jvm = mock();
+
+    let path = std::env::temp_dir().join(format!("jvmcraft_mapped_{}", std::process::id()));
+    std::fs::write(&path, "abcdefgh").unwrap();
+    let file = std::fs::OpenOptions::new()
+      .read(true)
+      .write(true)
+      .open(&path)
+      .unwrap();
+
+    let fd = jvm
+      .heap
+      .alloc_obj(&mut jvm.class_loader, "java/io/FileDescriptor")
+      .unwrap();
+    jvm
+      .call_and_resolve_method("java/io/FileDescriptor", "<init>", "()V", vec![fd])
+      .unwrap();
+
+    let address = jvm
+      .nativememory
+      .map(file.as_raw_fd(), true, true, 0, 8)
+      .unwrap();
+    jvm.nativememory.write(address + 1, b"xy").unwrap();
+
+    // force(fd, address, isSync, index, length)
+    let args = vec![
+      fd,
+      Type::Long(address as i64),
+      Type::Long(address as i64),
+      Type::Boolean(false),
+      Type::Long(0),
+      Type::Long(0),
+      Type::Long(8),
+      Type::Long(8),
+    ];
+    jvm
+      .call_and_resolve_method(UTILS, "force", "(Ljava/io/FileDescriptor;JZJJ)V", args)
+      .unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "axydefgh");
+
+    let args = vec![
+      Type::Long(address as i64),
+      Type::Long(address as i64),
+      Type::Boolean(false),
+      Type::Long(8),
+      Type::Long(8),
+    ];
+    jvm
+      .call_and_resolve_method(UTILS, "load", "(JZJ)V", args.clone())
+      .unwrap();
+    let loaded = jvm
+      .call_and_resolve_method(UTILS, "isLoaded", "(JZJ)Z", args)
+      .unwrap();
+    assert_eq!(loaded, Type::Boolean(true));
+
+    // the unmapped memory is not accessed anymore
+    jvm.nativememory.unmap(address, 8).unwrap();
+    let err = jvm.nativememory.read(address, 1).unwrap_err();
+    assert!(matches!(
+      err.downcast::<InternalError>().unwrap(),
+      InternalError::SegmentationFault(_)
+    ));
+
+    std::fs::remove_file(&path).unwrap();
+  }
+}
@@ -0,0 +1,168 @@
+#[cfg(test)]
+mod tests {
+
+  use color_eyre::eyre::Report;
+  use rand::{rngs::StdRng, Rng, SeedableRng};
+
+  use crate::{class_loader::class_file::ClassFile, runtime::errors::JavaException};
+
+  fn class_bytes() -> Vec<u8> {
+    std::fs::read("tests/classes/TestVerifier.class").unwrap()
+  }
+
+  fn parse_error(data: &[u8]) -> Report {
+    match ClassFile::parse_from_bytes(data) {
+      Ok(_) => panic!("malformed class file accepted"),
+      Err(err) => err,
+    }
+  }
+
+  fn is_class_format_error(err: &Report) -> bool {
+    matches!(
+      err.downcast_ref::<JavaException>(),
+      Some(JavaException::ClassFormatError(_))
+    )
+  }
+
+  fn replace_bytes(data: &mut [u8], from: &[u8], to: &[u8]) {
+    let start = data.windows(from.len()).position(|w| w == from).unwrap();
+
+    data[start..start + to.len()].copy_from_slice(to);
+  }
+
+  #[test]
+
+  fn test_parse_valid_classes() {
+    for entry in std::fs::read_dir("tests/classes/").unwrap() {
+      let path = entry.unwrap().path();
+
+      if path.extension().is_some_and(|ext| ext == "class") {
+        let data = std::fs::read(&path).unwrap();
+
+        ClassFile::parse_from_bytes(&data)
+          .unwrap_or_else(|err| panic!("{:?} rejected: {}", path, err));
+      }
+    }
+  }
+
+  #[test]
+
+  fn test_truncated_class_file() {
+    let data = class_bytes();
+
+    for len in 0..data.len() {
+      let err = parse_error(&data[..len]);
+
+      assert!(is_class_format_error(&err), "{}: {}", len, err);
+    }
+  }
+
+  #[test]
+
+  fn test_extra_bytes() {
+    let mut data = class_bytes();
+    data.push(0);
+
+    let err = parse_error(&data);
+
+    assert!(is_class_format_error(&err), "{}", err);
+    assert!(err.to_string().contains("Extra bytes"), "{}", err);
+  }
+
+  #[test]
+
+  fn test_bad_magic() {
+    let mut data = class_bytes();
+    data[0] = 0xBA;
+
+    let err = parse_error(&data);
+
+    assert!(is_class_format_error(&err), "{}", err);
+    assert!(err.to_string().contains("magic"), "{}", err);
+  }
+
+  #[test]
+
+  fn test_unsupported_version() {
+    // major version 68 (Java SE 24)
+    let mut data = class_bytes();
+    data[6..8].copy_from_slice(&[0x00, 0x44]);
+
+    let err = parse_error(&data);
+
+    assert!(
+      matches!(
+        err.downcast_ref::<JavaException>(),
+        Some(JavaException::UnsupportedClassVersionError(_))
+      ),
+      "{}",
+      err
+    );
+
+    // preview features are not supported
+    let mut data = class_bytes();
+    data[4..6].copy_from_slice(&[0xff, 0xff]);
+
+    let err = parse_error(&data);
+
+    assert!(
+      matches!(
+        err.downcast_ref::<JavaException>(),
+        Some(JavaException::UnsupportedClassVersionError(_))
+      ),
+      "{}",
+      err
+    );
+  }
+
+  #[test]
+
+  fn test_bad_descriptor() {
+    let mut data = class_bytes();
+    replace_bytes(&mut data, b"()V", b"(V)");
+
+    let err = parse_error(&data);
+
+    assert!(is_class_format_error(&err), "{}", err);
+  }
+
+  #[test]
+
+  fn test_bad_constant_pool_tag() {
+    // the tag of the first constant is right after constant_pool_count
+    let mut data = class_bytes();
+    data[10] = 14;
+
+    let err = parse_error(&data);
+
+    assert!(is_class_format_error(&err), "{}", err);
+  }
+
+  #[test]
+
+  fn test_unknown_attribute_ignored() {
+    let mut data = class_bytes();
+    replace_bytes(&mut data, b"SourceFile", b"SourceFilX");
+
+    ClassFile::parse_from_bytes(&data).unwrap();
+  }
+
+  #[test]
+
+  fn test_mutated_class_files() {
+    let original = class_bytes();
+    let mut rng = StdRng::seed_from_u64(0xCAFEBABE);
+
+    // any mutation is either accepted or rejected with an error, never a panic
+    for _ in 0..20000 {
+      let mut data = original.clone();
+
+      for _ in 0..rng.random_range(1..8) {
+        let index = rng.random_range(0..data.len());
+        data[index] = rng.random();
+      }
+
+      let _ = ClassFile::parse_from_bytes(&data);
+    }
+  }
+}
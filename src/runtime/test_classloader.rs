@@ -0,0 +1,367 @@
+#[cfg(test)]
+mod tests {
+
+  use crate::{
+    class_loader::namespace::BOOTSTRAP_LOADER,
+    runtime::{errors::JavaException, jvm::JVM, test_support::*, types},
+  };
+
+  const PLUGIN: &str = "TestClassLoader$Plugin";
+
+  fn alloc_loader(jvm: &mut JVM) -> u32 {
+    jvm
+      .heap
+      .alloc_obj(&mut jvm.class_loader, "TestClassLoader$ByteLoader")
+      .unwrap()
+      .as_ref()
+      .unwrap()
+  }
+
+  fn plugin_bytes() -> Vec<u8> {
+    std::fs::read("tests/classes/TestClassLoader$Plugin.class").unwrap()
+  }
+
+  #[test]
+
+  fn test_define_class_namespaces() {
+    let mut jvm = JVM::mock("tests/classes/", vec![], false).unwrap();
+
+    let loader1 = alloc_loader(&mut jvm);
+    let loader2 = alloc_loader(&mut jvm);
+
+    let key1 = jvm
+      .define_class(loader1, Some(PLUGIN), &plugin_bytes())
+      .unwrap();
+    let key2 = jvm
+      .define_class(loader2, Some(PLUGIN), &plugin_bytes())
+      .unwrap();
+
+    assert_ne!(key1, key2);
+
+    assert_eq!(
+      jvm.class_loader.find_loaded_class(loader1, PLUGIN),
+      Some(key1.clone())
+    );
+    assert_eq!(
+      jvm.class_loader.find_loaded_class(loader2, PLUGIN),
+      Some(key2.clone())
+    );
+
+    // a loader can not define the same class twice
+    assert!(jvm
+      .define_class(loader1, Some(PLUGIN), &plugin_bytes())
+      .is_err());
+
+    // each class has its own static fields
+    for expected in 1..3 {
+      let result = jvm
+        .call_and_resolve_method(&key1, "bump", "()I", vec![])
+        .unwrap();
+
+      assert_eq!(result, types::Type::Integer(expected));
+    }
+
+    let result = jvm
+      .call_and_resolve_method(&key2, "bump", "()I", vec![])
+      .unwrap();
+
+    assert_eq!(result, types::Type::Integer(1));
+  }
+
+  #[test]
+
+  fn test_define_class_retry() {
+    let mut jvm = JVM::mock("tests/classes/", vec![], false).unwrap();
+
+    let loader = alloc_loader(&mut jvm);
+
+    let host = std::fs::read("tests/classes/TestClassLoader$Host.class").unwrap();
+
+    // the super class can not be loaded through the loader yet
+    assert!(jvm
+      .define_class(loader, Some("TestClassLoader$Host"), &host)
+      .is_err());
+    assert_eq!(
+      jvm
+        .class_loader
+        .find_loaded_class(loader, "TestClassLoader$Host"),
+      None
+    );
+
+    jvm
+      .class_loader
+      .record_initiating_loader(loader, "TestClassLoader", "TestClassLoader")
+      .unwrap();
+
+    // the failed definition left nothing behind
+    assert!(jvm
+      .define_class(loader, Some("TestClassLoader$Host"), &host)
+      .is_ok());
+  }
+
+  #[test]
+
+  fn test_define_class_wrong_name() {
+    let mut jvm = JVM::mock("tests/classes/", vec![], false).unwrap();
+
+    let loader = alloc_loader(&mut jvm);
+
+    assert!(jvm
+      .define_class(loader, Some("TestClassLoader$Other"), &plugin_bytes())
+      .is_err());
+  }
+
+  #[test]
+
+  fn test_define_class_bad_range() {
+    let mut jvm = mock_with_frame("TestClassLoader", "plug", "(LTestClassLoader$Plugin;)I");
+
+    let bytes: Vec<types::Type> = plugin_bytes()
+      .into_iter()
+      .map(|b| types::Type::Byte(b as i8))
+      .collect();
+    let size = bytes.len() as i32;
+    let bytes = array(&mut jvm, "B", bytes);
+
+    for (off, len) in [(-1, size), (1, size), (1, i32::MAX), (0, -1)] {
+      for arg in [
+        types::Type::Null,
+        types::Type::Null,
+        bytes,
+        types::Type::Integer(off),
+        types::Type::Integer(len),
+        types::Type::Null,
+        types::Type::Null,
+      ] {
+        jvm.push_stack(arg).unwrap();
+      }
+
+      let err = jvm
+        .call_native(
+          "java/lang/ClassLoader",
+          "defineClass1",
+          "(Ljava/lang/ClassLoader;Ljava/lang/String;[BIILjava/security/ProtectionDomain;Ljava/lang/String;)Ljava/lang/Class;",
+        )
+        .unwrap_err();
+
+      assert!(
+        matches!(java_exception(err), JavaException::IndexOutOfBounds(_)),
+        "[{}, {} + {})",
+        off,
+        off,
+        len
+      );
+    }
+  }
+
+  #[test]
+
+  fn test_class_object_loader() {
+    let mut jvm = JVM::mock("tests/classes/", vec![], false).unwrap();
+
+    let loader = alloc_loader(&mut jvm);
+
+    let key = jvm
+      .define_class(loader, Some(PLUGIN), &plugin_bytes())
+      .unwrap();
+
+    let class_obj = jvm.get_class_instance_mut(&key).unwrap();
+
+    assert_eq!(
+      class_obj.get_field("classLoader").unwrap(),
+      types::Type::ObjectRef(loader)
+    );
+
+    let class_ref = class_obj.get_ref();
+    let name_ref = class_obj.get_field("name").unwrap().as_ref().unwrap();
+
+    assert_eq!(
+      jvm.heap.get_string(name_ref).unwrap(),
+      "TestClassLoader$Plugin"
+    );
+    assert_eq!(
+      jvm.heap.get_classname_from_class_obj(class_ref).unwrap(),
+      key
+    );
+
+    // classes of the bootstrap loader have a null loader
+    let string_class = jvm.get_class_instance_mut("java/lang/String").unwrap();
+
+    assert_eq!(
+      string_class.get_field("classLoader").unwrap(),
+      types::Type::Null
+    );
+  }
+
+  #[test]
+
+  fn test_loader_constraints() {
+    let mut jvm = JVM::mock("tests/classes/", vec![], false).unwrap();
+
+    let loader1 = alloc_loader(&mut jvm);
+    let loader2 = alloc_loader(&mut jvm);
+    let loader3 = alloc_loader(&mut jvm);
+
+    jvm
+      .define_class(loader1, Some(PLUGIN), &plugin_bytes())
+      .unwrap();
+    let key2 = jvm
+      .define_class(loader2, Some(PLUGIN), &plugin_bytes())
+      .unwrap();
+
+    // loader1 and loader2 see different classes
+    assert!(jvm
+      .class_loader
+      .add_loader_constraint(PLUGIN, loader1, loader2)
+      .is_err());
+
+    assert!(jvm
+      .class_loader
+      .add_loader_constraint("java/lang/String", loader1, loader2)
+      .is_ok());
+
+    // loader3 must now load the same class as loader1
+    assert!(jvm
+      .class_loader
+      .add_loader_constraint(PLUGIN, loader1, loader3)
+      .is_ok());
+
+    assert!(jvm
+      .class_loader
+      .record_initiating_loader(loader3, PLUGIN, &key2)
+      .is_err());
+  }
+
+  #[test]
+
+  fn test_resolution_loader_constraints() {
+    let mut jvm = JVM::mock("tests/classes/", vec![], false).unwrap();
+
+    let loader = alloc_loader(&mut jvm);
+
+    jvm.class_loader.load_class(PLUGIN).unwrap();
+
+    // the loader delegates TestClassLoader to the bootstrap loader
+    jvm
+      .class_loader
+      .record_initiating_loader(loader, "TestClassLoader", "TestClassLoader")
+      .unwrap();
+
+    let plugin = jvm
+      .define_class(loader, Some(PLUGIN), &plugin_bytes())
+      .unwrap();
+    let host = jvm
+      .define_class(
+        loader,
+        Some("TestClassLoader$Host"),
+        &std::fs::read("tests/classes/TestClassLoader$Host.class").unwrap(),
+      )
+      .unwrap();
+
+    // `plug` is declared by the bootstrap loader, that has another Plugin class, even
+    // when referenced through a class of the same loader
+    let err = jvm
+      .resolve_method_access(&plugin, &host, "plug", "(LTestClassLoader$Plugin;)I")
+      .unwrap_err();
+
+    assert!(matches!(
+      err.downcast_ref::<JavaException>(),
+      Some(JavaException::LinkageError(_))
+    ));
+
+    // members of classes of the same loader add no constraint
+    assert!(jvm
+      .resolve_method_access(&plugin, &plugin, "bump", "()I")
+      .is_ok());
+  }
+
+  #[test]
+
+  fn test_define_hidden_class() {
+    let mut jvm = JVM::mock("tests/classes/", vec![], false).unwrap();
+
+    let nestmate = jvm
+      .define_hidden_class(BOOTSTRAP_LOADER, "TestClassLoader", &plugin_bytes(), true)
+      .unwrap();
+    let other = jvm
+      .define_hidden_class(BOOTSTRAP_LOADER, "TestClassLoader", &plugin_bytes(), false)
+      .unwrap();
+
+    assert_ne!(nestmate, other);
+    assert!(nestmate.starts_with("TestClassLoader$Plugin/0x"));
+    assert!(jvm.class_loader.get(&nestmate).unwrap().is_hidden());
+
+    // hidden classes can not be found by name, not even by their own
+    for name in [PLUGIN, &nestmate, &other] {
+      assert_eq!(
+        jvm.class_loader.find_loaded_class(BOOTSTRAP_LOADER, name),
+        None
+      );
+    }
+
+    // a hidden class refers to itself through its original name
+    for key in [&nestmate, &other] {
+      let result = jvm
+        .call_and_resolve_method(key, "bump", "()I", vec![])
+        .unwrap();
+
+      assert_eq!(result, types::Type::Integer(1));
+    }
+
+    assert!(jvm
+      .class_loader
+      .are_nest_mates(&nestmate, "TestClassLoader")
+      .unwrap());
+    assert!(!jvm
+      .class_loader
+      .are_nest_mates(&other, "TestClassLoader")
+      .unwrap());
+
+    let class_obj = jvm.get_class_instance_mut(&other).unwrap();
+    let name_ref = class_obj.get_field("name").unwrap().as_ref().unwrap();
+
+    assert_eq!(jvm.heap.get_string(name_ref).unwrap(), other);
+  }
+
+  #[test]
+
+  fn test_jdk_hidden_classes() {
+    // the bootstraps need the system properties and a current thread
+    let mut jvm = booted_mock();
+
+    // InnerClassLambdaMetafactory spins the class of the lambda with Lookup.defineHiddenClass
+    let result = jvm
+      .call_and_resolve_method("TestHidden", "metafactory", "()I", vec![])
+      .unwrap();
+
+    assert_eq!(result, types::Type::Integer(42));
+
+    let result = jvm
+      .call_and_resolve_method("TestHidden", "isLambdaHidden", "()Z", vec![])
+      .unwrap();
+
+    assert!(result.as_bool().unwrap());
+
+    let hidden = jvm.class_loader.get_hidden_classes();
+    let lambda = hidden
+      .iter()
+      .find(|key| key.starts_with("TestHidden$$Lambda"))
+      .unwrap();
+
+    assert_eq!(
+      jvm.class_loader.find_loaded_class(BOOTSTRAP_LOADER, lambda),
+      None
+    );
+
+    // StringConcatFactory defines the hidden classes of its lambda forms
+    let result = jvm
+      .call_and_resolve_method("TestHidden", "concat", "()Ljava/lang/String;", vec![])
+      .unwrap();
+
+    assert_eq!(
+      jvm.heap.get_string(result.as_ref().unwrap()).unwrap(),
+      "a-1"
+    );
+    assert!(jvm.class_loader.get_hidden_classes().len() > hidden.len());
+  }
+}
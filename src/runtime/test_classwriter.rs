@@ -0,0 +1,164 @@
+#[cfg(test)]
+mod tests {
+
+  use std::path::Path;
+
+  use crate::{
+    class_loader::{
+      attributes::Code, builder::ClassBuilder, class_file::ClassFile, constant_pool::ConstantPool,
+      namespace::BOOTSTRAP_LOADER,
+    },
+    runtime::{jvm::JVM, types},
+    utils::*,
+  };
+
+  fn assert_round_trip(path: &Path) {
+    let data = std::fs::read(path).unwrap();
+
+    let class_file = ClassFile::parse_from_bytes(&data)
+      .unwrap_or_else(|err| panic!("{:?} rejected: {}", path, err));
+
+    let bytes = class_file.read().unwrap().to_bytes();
+
+    assert!(bytes == data, "{:?} written back differently", path);
+  }
+
+  fn round_trip_dir(dir: &Path) -> usize {
+    let mut count = 0;
+
+    for entry in std::fs::read_dir(dir).unwrap() {
+      let path = entry.unwrap().path();
+
+      if path.is_dir() {
+        count += round_trip_dir(&path);
+      } else if path.extension().is_some_and(|ext| ext == "class") {
+        assert_round_trip(&path);
+
+        count += 1;
+      }
+    }
+
+    count
+  }
+
+  #[test]
+
+  fn test_round_trip_test_classes() {
+    assert!(round_trip_dir(Path::new("tests/classes/")) > 0);
+  }
+
+  #[test]
+
+  fn test_round_trip_java_base() {
+    let java_base = Path::new(&get_env("JMODS", "")).join("java.base");
+
+    assert!(round_trip_dir(&java_base) > 1000);
+  }
+
+  #[test]
+
+  fn test_constant_pool_entries() {
+    let mut cp = ConstantPool::empty();
+
+    let class = cp.add_class("java/lang/Object");
+
+    // entries are shared
+    assert_eq!(cp.add_class("java/lang/Object"), class);
+    assert_eq!(cp.resolve_class_name(class).unwrap(), "java/lang/Object");
+
+    // a long takes two entries
+    let long = cp.add_long(-2);
+    let int = cp.add_integer(7);
+
+    assert_eq!(int, long + 2);
+
+    let method = cp.add_method_ref("java/lang/Object", "hashCode", "()I");
+
+    assert_eq!(
+      cp.resolve_method_ref(method).unwrap(),
+      (
+        "java/lang/Object".to_string(),
+        "hashCode".to_string(),
+        "()I".to_string()
+      )
+    );
+
+    // the null character and supplementary characters in modified UTF-8
+    let utf8 = cp.add_utf8("a\0b\u{1F600}");
+
+    assert_eq!(cp.resolve_name(utf8).unwrap(), "a\0b\u{1F600}");
+  }
+
+  #[test]
+
+  fn test_build_class() {
+    let mut builder = ClassBuilder::new(0x0421, "Generated", Some("java/lang/Object")); // ACC_PUBLIC | ACC_SUPER | ACC_ABSTRACT
+
+    builder
+      .interface("java/lang/Runnable")
+      .field(0x000A, "counter", "I"); // ACC_PRIVATE | ACC_STATIC
+
+    let counter = builder
+      .constant_pool()
+      .add_field_ref("Generated", "counter", "I");
+    let answer = builder
+      .constant_pool()
+      .add_method_ref("Generated", "answer", "()I");
+
+    #[rustfmt::skip]
+    let bump = vec![
+      0xb2, (counter >> 8) as u8, counter as u8, // getstatic counter
+      0xb8, (answer >> 8) as u8, answer as u8,   // invokestatic answer
+      0x60,                                      // iadd
+      0x59,                                      // dup
+      0xb3, (counter >> 8) as u8, counter as u8, // putstatic counter
+      0xac,                                      // ireturn
+    ];
+
+    builder
+      .method(
+        0x0009, // ACC_PUBLIC | ACC_STATIC
+        "answer",
+        "()I",
+        Some(Code::new(1, 0, vec![0x10, 42, 0xac])), // bipush 42, ireturn
+      )
+      .method(0x0009, "bump", "()I", Some(Code::new(3, 0, bump)))
+      .method(0x0401, "run", "()V", None); // ACC_PUBLIC | ACC_ABSTRACT
+
+    let bytes = builder.build().unwrap();
+
+    // the builder output is a class file like any other
+    let class_file = ClassFile::parse_from_bytes(&bytes).unwrap();
+
+    {
+      let class_file = class_file.read().unwrap();
+
+      assert_eq!(class_file.get_name(), "Generated");
+      assert_eq!(class_file.get_interfaces(), &vec!["java/lang/Runnable"]);
+      assert_eq!(class_file.to_bytes(), bytes);
+    }
+
+    let mut jvm = JVM::mock("tests/classes/", vec![], false).unwrap();
+
+    let key = jvm.define_class(BOOTSTRAP_LOADER, None, &bytes).unwrap();
+
+    for expected in [42, 84] {
+      let result = jvm
+        .call_and_resolve_method(&key, "bump", "()I", vec![])
+        .unwrap();
+
+      assert_eq!(result, types::Type::Integer(expected));
+    }
+  }
+
+  #[test]
+
+  fn test_build_malformed_class() {
+    // a concrete method needs a Code attribute
+    let mut builder = ClassBuilder::new(0x0021, "Malformed", Some("java/lang/Object"));
+
+    builder.method(0x0009, "missing", "()V", None);
+
+    assert!(builder.build().is_err());
+  }
+}
@@ -0,0 +1,218 @@
+#[cfg(test)]
+mod tests {
+
+  use color_eyre::eyre::Result;
+
+  use crate::{
+    class_loader::{assembler::assemble, namespace::BOOTSTRAP_LOADER},
+    runtime::{errors::JavaException, jvm::JVM, test_support::*, types::Type},
+  };
+
+  // dynamic constants of the bootstrap methods of TestCondy.java
+  const CONSTANTS: &str = "
+    .class public TestCondyConstants
+    .version 55
+
+    .dynamic Answer answer I TestCondy/answer(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/Class;)I
+    .dynamic Greeting hello Ljava/lang/String; TestCondy/greeting(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/Class;Ljava/lang/String;I)Ljava/lang/String; \"world\" 2
+    .dynamic Sum sum J TestCondy/sum(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/Class;J[Ljava/lang/Object;)J 40 dynamic Answer 2
+    .dynamic Boxed boxed Ljava/lang/Object; TestCondy/identity(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/Class;Ljava/lang/Object;)Ljava/lang/Object; dynamic Answer
+    .dynamic Unboxed unboxed J TestCondy/identity(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/Class;Ljava/lang/Object;)Ljava/lang/Object; dynamic Boxed
+    .dynamic Lookup lookup Ljava/lang/Class; TestCondy/lookupClass(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/Class;)Ljava/lang/Object;
+    .dynamic Type type Ljava/lang/Object; TestCondy/type(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/Class;)Ljava/lang/Object;
+    .dynamic Null none Ljava/lang/String; java/lang/invoke/ConstantBootstraps/nullConstant(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/Class;)Ljava/lang/Object;
+    .dynamic Fail fail Ljava/lang/Object; TestCondy/fail(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/Class;)Ljava/lang/Object;
+    .dynamic Error error Ljava/lang/Object; TestCondy/error(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/Class;)Ljava/lang/Object;
+    .dynamic Mismatch mismatch Ljava/lang/Integer; TestCondy/string(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/Class;)Ljava/lang/Object;
+    .dynamic NullInt nullInt I TestCondy/identity(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/Class;Ljava/lang/Object;)Ljava/lang/Object; dynamic Null
+    .dynamic Cycle cycle Ljava/lang/Object; TestCondy/identity(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/Class;Ljava/lang/Object;)Ljava/lang/Object; dynamic Cycle
+
+    .method public static answer()I
+      ldc dynamic Answer
+      ireturn
+    .end method
+
+    .method public static greeting()Ljava/lang/String;
+      ldc dynamic Greeting
+      areturn
+    .end method
+
+    .method public static sum()J
+      ldc2_w dynamic Sum
+      lreturn
+    .end method
+
+    .method public static boxed()Ljava/lang/Object;
+      ldc dynamic Boxed
+      areturn
+    .end method
+
+    .method public static unboxed()J
+      ldc2_w dynamic Unboxed
+      lreturn
+    .end method
+
+    .method public static lookup()Ljava/lang/Class;
+      ldc dynamic Lookup
+      areturn
+    .end method
+
+    .method public static type()Ljava/lang/Object;
+      ldc dynamic Type
+      areturn
+    .end method
+
+    .method public static none()Ljava/lang/String;
+      ldc dynamic Null
+      areturn
+    .end method
+
+    .method public static fail()Ljava/lang/Object;
+      ldc dynamic Fail
+      areturn
+    .end method
+
+    .method public static error()Ljava/lang/Object;
+      ldc dynamic Error
+      areturn
+    .end method
+
+    .method public static mismatch()Ljava/lang/Integer;
+      ldc dynamic Mismatch
+      areturn
+    .end method
+
+    .method public static nullInt()I
+      ldc dynamic NullInt
+      ireturn
+    .end method
+
+    .method public static cycle()Ljava/lang/Object;
+      ldc dynamic Cycle
+      areturn
+    .end method
+
+    ; a dynamic constant as static argument of an invokedynamic
+    .method public static concat()Ljava/lang/String;
+      invokedynamic makeConcatWithConstants()Ljava/lang/String; java/lang/invoke/StringConcatFactory/makeConcatWithConstants(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/invoke/MethodType;Ljava/lang/String;[Ljava/lang/Object;)Ljava/lang/invoke/CallSite; \"\\u0002!\" dynamic Greeting
+      areturn
+    .end method
+  ";
+
+  fn mock() -> (JVM, String) {
+    let mut jvm = JVM::mock("tests/classes/", vec![], false).unwrap();
+
+    let bytes = assemble(CONSTANTS).unwrap();
+
+    let key = jvm.define_class(BOOTSTRAP_LOADER, None, &bytes).unwrap();
+
+    (jvm, key)
+  }
+
+  fn constant(jvm: &mut JVM, key: &str, method: &str, descriptor: &str) -> Result<Type> {
+    jvm.call_and_resolve_method(key, method, descriptor, vec![])
+  }
+
+  fn calls(jvm: &mut JVM) -> Type {
+    jvm
+      .call_and_resolve_method("TestCondy", "getCalls", "()I", vec![])
+      .unwrap()
+  }
+
+  fn bootstrap_method_error(err: color_eyre::Report) -> String {
+    match err.downcast_ref::<JavaException>() {
+      Some(JavaException::BootstrapMethodError(message)) => message.clone(),
+      _ => panic!("BootstrapMethodError expected, got {:?}", err),
+    }
+  }
+
+  #[test]
+
+  fn test_condy_resolved_once() {
+    let (mut jvm, key) = mock();
+
+    for _ in 0..3 {
+      let ret = constant(&mut jvm, &key, "answer", "()I").unwrap();
+      assert_eq!(ret, Type::Integer(42));
+    }
+
+    let ret = constant(&mut jvm, &key, "greeting", "()Ljava/lang/String;").unwrap();
+    assert_eq!(get_string(&jvm, ret), "hello worldworld");
+
+    let again = constant(&mut jvm, &key, "greeting", "()Ljava/lang/String;").unwrap();
+    assert_eq!(ret, again);
+
+    assert_eq!(calls(&mut jvm), Type::Integer(2));
+  }
+
+  #[test]
+
+  fn test_condy_arguments() {
+    let (mut jvm, key) = mock();
+
+    // 40 widened to long, then the constant Answer and 2 boxed in the varargs
+    let ret = constant(&mut jvm, &key, "sum", "()J").unwrap();
+    assert_eq!(ret, Type::Long(84));
+
+    let ret = constant(&mut jvm, &key, "boxed", "()Ljava/lang/Object;").unwrap();
+    let boxed = jvm.heap.get_boxed_value(ret.as_ref().unwrap()).unwrap();
+    assert_eq!(boxed, Some(("I", Type::Integer(42))));
+
+    let ret = constant(&mut jvm, &key, "unboxed", "()J").unwrap();
+    assert_eq!(ret, Type::Long(42));
+
+    // the lookup of the class of the constant and the Class of its type
+    let ret = constant(&mut jvm, &key, "lookup", "()Ljava/lang/Class;").unwrap();
+    let class = jvm
+      .heap
+      .alloc_class_obj(&mut jvm.class_loader, &key)
+      .unwrap();
+    assert_eq!(ret, class);
+
+    let ret = constant(&mut jvm, &key, "type", "()Ljava/lang/Object;").unwrap();
+    let class = jvm
+      .heap
+      .alloc_class_obj(&mut jvm.class_loader, "java/lang/Object")
+      .unwrap();
+    assert_eq!(ret, class);
+
+    let ret = constant(&mut jvm, &key, "none", "()Ljava/lang/String;").unwrap();
+    assert_eq!(ret, Type::Null);
+
+    let ret = constant(&mut jvm, &key, "concat", "()Ljava/lang/String;").unwrap();
+    assert_eq!(get_string(&jvm, ret), "hello worldworld!");
+  }
+
+  #[test]
+
+  fn test_condy_errors() {
+    let (mut jvm, key) = mock();
+
+    // the exception is wrapped, and the failed resolution is not retried
+    for _ in 0..2 {
+      let err = constant(&mut jvm, &key, "fail", "()Ljava/lang/Object;").unwrap_err();
+      assert!(bootstrap_method_error(err).contains("IllegalStateException"));
+    }
+
+    assert_eq!(calls(&mut jvm), Type::Integer(1));
+
+    // errors are not wrapped
+    let err = constant(&mut jvm, &key, "error", "()Ljava/lang/Object;").unwrap_err();
+    assert!(matches!(
+      err.downcast_ref::<JavaException>(),
+      Some(JavaException::AssertionError)
+    ));
+
+    let err = constant(&mut jvm, &key, "mismatch", "()Ljava/lang/Integer;").unwrap_err();
+    assert!(bootstrap_method_error(err).contains("java/lang/String"));
+
+    let err = constant(&mut jvm, &key, "nullInt", "()I").unwrap_err();
+    assert!(bootstrap_method_error(err).contains("null"));
+
+    let err = constant(&mut jvm, &key, "cycle", "()Ljava/lang/Object;").unwrap_err();
+    assert!(matches!(
+      err.downcast_ref::<JavaException>(),
+      Some(JavaException::StackOverflowError(_))
+    ));
+  }
+}
@@ -0,0 +1,173 @@
+#[cfg(test)]
+mod tests {
+
+  use std::{io::Write, path::Path};
+
+  use zip::{write::SimpleFileOptions, ZipWriter};
+
+  use crate::{
+    class_loader::{class_file::ClassFile, class_path::ClassPath},
+    utils::*,
+  };
+
+  fn disassemble(data: &[u8]) -> String {
+    let class_file = ClassFile::parse_from_bytes(data).unwrap();
+
+    let text = class_file.read().unwrap().disassemble().unwrap();
+
+    text
+  }
+
+  fn disassemble_dir(dir: &Path) -> usize {
+    let mut count = 0;
+
+    for entry in std::fs::read_dir(dir).unwrap() {
+      let path = entry.unwrap().path();
+
+      if path.is_dir() {
+        count += disassemble_dir(&path);
+      } else if path.extension().is_some_and(|ext| ext == "class") {
+        let data = std::fs::read(&path).unwrap();
+
+        let class_file = ClassFile::parse_from_bytes(&data).unwrap();
+
+        if let Err(err) = class_file.read().unwrap().disassemble() {
+          panic!("{:?} not disassembled: {}", path, err);
+        }
+
+        count += 1;
+      }
+    }
+
+    count
+  }
+
+  #[test]
+
+  fn test_disassemble_test_classes() {
+    assert!(disassemble_dir(Path::new("tests/classes/")) > 0);
+  }
+
+  #[test]
+
+  fn test_disassemble_java_base() {
+    let java_base = Path::new(&get_env("JMODS", "")).join("java.base");
+
+    assert!(disassemble_dir(&java_base) > 1000);
+  }
+
+  #[test]
+
+  fn test_disassemble_code() {
+    let data = std::fs::read("tests/classes/TestAccess$Inner.class").unwrap();
+
+    let text = disassemble(&data);
+
+    assert!(text.starts_with("class TestAccess$Inner extends java.lang.Object\n"));
+    assert!(text
+      .contains("   #1 = Methodref          #2.#3         // java/lang/Object.\"<init>\":()V\n"));
+    assert!(text.contains("  private int peek(TestAccess);\n"));
+    assert!(text.contains(
+      "         1: invokespecial #1                // Method java/lang/Object.\"<init>\":()V\n"
+    ));
+    assert!(text.contains("         5: bipush        7\n"));
+    assert!(text.contains("      LineNumberTable:\n        line 5: 0\n"));
+    assert!(text.contains("NestHost: class TestAccess\n"));
+  }
+
+  #[test]
+
+  fn test_disassemble_stack_map_table() {
+    let java_base = Path::new(&get_env("JMODS", "")).join("java.base");
+
+    let data = std::fs::read(java_base.join("java/util/ArrayList.class")).unwrap();
+
+    let text = disassemble(&data);
+
+    assert!(text.contains("      StackMapTable: number_of_entries = "));
+    assert!(text.contains("      Exception table:\n"));
+    assert!(text.contains("      LocalVariableTable:\n"));
+    assert!(text.contains("Signature: #"));
+  }
+
+  #[test]
+
+  fn test_disassemble_non_ascii_names() {
+    let data = std::fs::read("tests/classes/TestUnicode.class").unwrap();
+
+    let text = disassemble(&data);
+
+    assert!(text.contains("  static int take(TestUnicode$Café[], long, TestUnicode$Café);\n"));
+    assert!(text.contains(", args_size=4\n"));
+  }
+
+  #[test]
+
+  fn test_class_path_directory() {
+    let mut class_path = ClassPath::new("tests/classes/").unwrap();
+
+    let data = class_path.find_class("TestAccess").unwrap().unwrap();
+
+    assert_eq!(
+      data,
+      std::fs::read("tests/classes/TestAccess.class").unwrap()
+    );
+    assert!(class_path.find_class("Missing").unwrap().is_none());
+  }
+
+  #[test]
+
+  fn test_class_path_jar() {
+    let path = std::env::temp_dir().join("jvmcraft_test_class_path.jar");
+
+    let data = std::fs::read("tests/classes/TestAccess.class").unwrap();
+
+    let mut jar = ZipWriter::new(std::fs::File::create(&path).unwrap());
+
+    jar
+      .start_file("pkg/TestAccess.class", SimpleFileOptions::default())
+      .unwrap();
+    jar.write_all(&data).unwrap();
+    jar.finish().unwrap();
+
+    let mut class_path = ClassPath::new(&format!("tests/classes/:{}", path.display())).unwrap();
+
+    assert_eq!(
+      class_path.find_class("pkg.TestAccess").unwrap().unwrap(),
+      data
+    );
+    assert!(class_path.find_class("pkg/Missing").unwrap().is_none());
+  }
+
+  #[test]
+
+  fn test_class_path_jimage() {
+    let java_home = get_env("JAVA_HOME", "/usr/lib/jvm/java-17-openjdk-amd64");
+
+    let modules = Path::new(&java_home).join("lib/modules");
+
+    // the jimage comes with a JDK, which is not needed by the other tests
+    if !modules.is_file() {
+      return;
+    }
+
+    let mut class_path = ClassPath::new(modules.to_str().unwrap()).unwrap();
+
+    // java.base and another module
+    for class_name in ["java/lang/Object", "java.sql.Connection"] {
+      let data = class_path.find_class(class_name).unwrap().unwrap();
+
+      let class_file = ClassFile::parse_from_bytes(&data).unwrap();
+
+      assert_eq!(
+        class_file.read().unwrap().get_name(),
+        dotclass_to_class(class_name)
+      );
+    }
+
+    assert!(class_path
+      .find_class("java/lang/Missing")
+      .unwrap()
+      .is_none());
+  }
+}
@@ -0,0 +1,383 @@
+#[cfg(test)]
+mod tests {
+
+  use std::{cell::RefCell, io, rc::Rc};
+
+  use crate::{
+    class_loader::assembler::assemble,
+    runtime::{
+      conversion::ToJava,
+      embedding::Global,
+      embedding::JvmBuilder,
+      errors::JavaError,
+      jvm::JVM,
+      test_support::{booted_mock, create_main_thread},
+      types::Type,
+    },
+  };
+
+  const CLASS: &str = "TestEmbedding";
+
+  fn mock() -> JVM {
+    JvmBuilder::new()
+      .class_path("tests/classes/")
+      .mock()
+      .unwrap()
+  }
+
+  fn java_error(err: color_eyre::eyre::Report) -> JavaError {
+    err.downcast_ref::<JavaError>().unwrap().clone()
+  }
+
+  // stream shared with the test once moved to the VM
+  #[derive(Clone, Default)]
+  struct Shared(Rc<RefCell<Vec<u8>>>);
+
+  impl io::Write for Shared {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+      self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+      Ok(())
+    }
+  }
+
+  #[test]
+
+  fn test_invoke_static() {
+    let mut jvm = mock();
+
+    let sum: i32 = jvm
+      .invoke_static(CLASS, "add", "(II)I", &[&40, &2])
+      .unwrap();
+    assert_eq!(sum, 42);
+
+    // widened like Method.invoke
+    let twice: i64 = jvm.invoke_static(CLASS, "twice", "(J)J", &[&21]).unwrap();
+    assert_eq!(twice, 42);
+
+    let even: bool = jvm.invoke_static(CLASS, "isEven", "(I)Z", &[&42]).unwrap();
+    assert!(even);
+
+    let next: char = jvm.invoke_static(CLASS, "next", "(C)C", &[&'a']).unwrap();
+    assert_eq!(next, 'b');
+
+    let half: f64 = jvm.invoke_static(CLASS, "half", "(D)D", &[&5.0]).unwrap();
+    assert_eq!(half, 2.5);
+
+    let greeting: String = jvm
+      .invoke_static(
+        CLASS,
+        "greet",
+        "(Ljava/lang/String;)Ljava/lang/String;",
+        &[&"rust"],
+      )
+      .unwrap();
+    assert_eq!(greeting, "hello rust");
+  }
+
+  #[test]
+
+  fn test_arrays() {
+    let mut jvm = mock();
+
+    let sum: i32 = jvm
+      .invoke_static(CLASS, "sum", "([I)I", &[&vec![1, 2, 3]])
+      .unwrap();
+    assert_eq!(sum, 6);
+
+    let range: Vec<i32> = jvm.invoke_static(CLASS, "range", "(I)[I", &[&4]).unwrap();
+    assert_eq!(range, vec![0, 1, 2, 3]);
+
+    let length: i32 = jvm
+      .invoke_static(
+        CLASS,
+        "totalLength",
+        "([Ljava/lang/String;)I",
+        &[&vec!["ab", "cde"]],
+      )
+      .unwrap();
+    assert_eq!(length, 5);
+  }
+
+  #[test]
+
+  fn test_boxed_values() {
+    let mut jvm = mock();
+
+    let descriptor = "(Ljava/lang/Long;)Ljava/lang/Long;";
+
+    let next: Option<i64> = jvm
+      .invoke_static(CLASS, "successor", descriptor, &[&Some(41i64)])
+      .unwrap();
+    assert_eq!(next, Some(42));
+
+    // the primitive values are boxed
+    let next: i64 = jvm
+      .invoke_static(CLASS, "successor", descriptor, &[&41i64])
+      .unwrap();
+    assert_eq!(next, 42);
+
+    let next: Option<i64> = jvm
+      .invoke_static(CLASS, "successor", descriptor, &[&None::<i64>])
+      .unwrap();
+    assert_eq!(next, None);
+
+    // but not widened
+    let err = jvm
+      .invoke_static::<i64>(CLASS, "successor", descriptor, &[&41])
+      .unwrap_err();
+    assert_eq!(java_error(err).message.unwrap(), "argument type mismatch");
+
+    let descriptor = "(Ljava/lang/Object;)Ljava/lang/Object;";
+
+    let value: i64 = jvm
+      .invoke_static(CLASS, "identity", descriptor, &[&7i64])
+      .unwrap();
+    assert_eq!(value, 7);
+
+    let value: String = jvm
+      .invoke_static(CLASS, "identity", descriptor, &[&"same"])
+      .unwrap();
+    assert_eq!(value, "same");
+
+    let err = jvm
+      .invoke_static::<String>(CLASS, "identity", descriptor, &[&7])
+      .unwrap_err();
+    assert_eq!(java_error(err).class_name, "java/lang/ClassCastException");
+  }
+
+  #[test]
+
+  fn test_option_boxes() {
+    // the caches of the boxes are sized by the system properties
+    let mut jvm = booted_mock();
+
+    let classname = |jvm: &JVM, value: Type| {
+      jvm
+        .heap
+        .get_instance(value.as_ref().unwrap())
+        .unwrap()
+        .get_classname()
+        .to_string()
+    };
+
+    let five = Some(5i32).to_java(&mut jvm).unwrap();
+    assert_eq!(classname(&jvm, five), "java/lang/Integer");
+
+    // Integer.valueOf returns the cached box of the small values
+    assert_eq!(Some(5i32).to_java(&mut jvm).unwrap(), five);
+
+    let big = Some(1000i32).to_java(&mut jvm).unwrap();
+    assert_ne!(Some(1000i32).to_java(&mut jvm).unwrap(), big);
+
+    let long = Some(41i64).to_java(&mut jvm).unwrap();
+    assert_eq!(classname(&jvm, long), "java/lang/Long");
+
+    let letter = Some('a').to_java(&mut jvm).unwrap();
+    assert_eq!(classname(&jvm, letter), "java/lang/Character");
+
+    let name = Some("name").to_java(&mut jvm).unwrap();
+    assert_eq!(classname(&jvm, name), "java/lang/String");
+
+    assert_eq!(None::<i32>.to_java(&mut jvm).unwrap(), Type::Null);
+  }
+
+  #[test]
+
+  fn test_invoke() {
+    let mut jvm = mock();
+
+    let obj = jvm.new_object(CLASS, "(I)V", &[&40]).unwrap();
+
+    let count: i32 = jvm.invoke(&obj, "increment", "(I)I", &[&1]).unwrap();
+    assert_eq!(count, 41);
+    let count: i32 = jvm.invoke(&obj, "increment", "(I)I", &[&1]).unwrap();
+    assert_eq!(count, 42);
+
+    let description: String = jvm
+      .invoke(&obj, "describe", "()Ljava/lang/String;", &[])
+      .unwrap();
+    assert_eq!(description, "base");
+
+    // selected by the class of the object
+    let child: Global = jvm
+      .invoke_static(CLASS, "child", "()LTestEmbedding;", &[])
+      .unwrap();
+    let description: String = jvm
+      .invoke(&child, "describe", "()Ljava/lang/String;", &[])
+      .unwrap();
+    assert_eq!(description, "child");
+
+    let err = jvm
+      .invoke::<String>(&Type::Null, "describe", "()Ljava/lang/String;", &[])
+      .unwrap_err();
+    assert_eq!(java_error(err).class_name, "java/lang/NullPointerException");
+
+    let class = jvm.find_class(CLASS).unwrap();
+    let name: String = jvm
+      .invoke(&class, "getName", "()Ljava/lang/String;", &[])
+      .unwrap();
+    assert_eq!(name, CLASS);
+  }
+
+  #[test]
+
+  fn test_java_exceptions() {
+    let mut jvm = mock();
+
+    let err = jvm
+      .invoke_static::<()>(CLASS, "fail", "(Ljava/lang/String;)V", &[&"broken"])
+      .unwrap_err();
+    let error = java_error(err);
+    assert_eq!(error.class_name, "java/lang/IllegalStateException");
+    assert_eq!(error.message.unwrap(), "broken");
+
+    let err = jvm
+      .invoke_static::<()>(CLASS, "failCustom", "()V", &[])
+      .unwrap_err();
+    let error = java_error(err);
+    assert_eq!(error.class_name, "TestEmbedding$Failure");
+    assert_eq!(error.message.unwrap(), "custom");
+
+    // the VM is still usable
+    let sum: i32 = jvm.invoke_static(CLASS, "add", "(II)I", &[&1, &2]).unwrap();
+    assert_eq!(sum, 3);
+    assert!(jvm.frames.is_empty());
+
+    let err = jvm.find_class("Missing").unwrap_err();
+    let error = java_error(err);
+    assert_eq!(error.class_name, "java/lang/NoClassDefFoundError");
+    assert_eq!(error.message.unwrap(), "Missing");
+
+    let err = jvm
+      .invoke_static::<i32>(CLASS, "add", "(II)I", &[&1])
+      .unwrap_err();
+    assert_eq!(
+      java_error(err).message.unwrap(),
+      "wrong number of arguments: 1 expected: 2"
+    );
+
+    let err = jvm
+      .invoke_static::<i32>(CLASS, "add", "(II)I", &[&"1", &2])
+      .unwrap_err();
+    let error = java_error(err);
+    assert_eq!(error.class_name, "java/lang/IllegalArgumentException");
+    assert_eq!(error.message.unwrap(), "argument type mismatch");
+  }
+
+  #[test]
+
+  fn test_exception_object() {
+    let mut jvm = mock();
+
+    let err = jvm
+      .invoke_static::<()>(CLASS, "failWrapped", "()V", &[])
+      .unwrap_err();
+    let error = java_error(err);
+    assert_eq!(error.message.as_deref(), Some("wrapped"));
+
+    let cause: Global = jvm
+      .invoke(&error.throwable, "getCause", "()Ljava/lang/Throwable;", &[])
+      .unwrap();
+    let message: String = jvm
+      .invoke(&cause, "getMessage", "()Ljava/lang/String;", &[])
+      .unwrap();
+    assert_eq!(message, "cause");
+
+    // the exceptions raised by the VM have an object too
+    let err = jvm
+      .invoke_static::<i32>(CLASS, "add", "(II)I", &[&"1", &2])
+      .unwrap_err();
+    let error = java_error(err);
+    let message: String = jvm
+      .invoke(&error.throwable, "getMessage", "()Ljava/lang/String;", &[])
+      .unwrap();
+    assert_eq!(message, "argument type mismatch");
+  }
+
+  #[test]
+
+  fn test_class_loading_errors() {
+    let class_path = std::env::temp_dir().join(format!("jvmcraft-future-{}", std::process::id()));
+    std::fs::create_dir_all(&class_path).unwrap();
+
+    let bytes = assemble(".class public Future\n.version 99").unwrap();
+    std::fs::write(class_path.join("Future.class"), bytes).unwrap();
+
+    let mut jvm = JvmBuilder::new()
+      .class_path(class_path.to_str().unwrap())
+      .mock()
+      .unwrap();
+
+    // the error of the loading, not a NoClassDefFoundError
+    let err = jvm.find_class("Future").unwrap_err();
+    let error = java_error(err);
+    assert_eq!(error.class_name, "java/lang/UnsupportedClassVersionError");
+
+    std::fs::remove_dir_all(&class_path).unwrap();
+  }
+
+  #[test]
+
+  fn test_stdio() {
+    let stdout = Shared::default();
+    let stderr = Shared::default();
+
+    let mut jvm = JvmBuilder::new()
+      .class_path("tests/classes/")
+      .stdin(io::Cursor::new(b"z".to_vec()))
+      .stdout(stdout.clone())
+      .stderr(stderr.clone())
+      .mock()
+      .unwrap();
+
+    let bytes = |text: &str| text.bytes().map(|b| b as i8).collect::<Vec<i8>>();
+
+    jvm
+      .invoke_static::<()>(CLASS, "write", "([BZ)V", &[&bytes("out"), &false])
+      .unwrap();
+    jvm
+      .invoke_static::<()>(CLASS, "write", "([BZ)V", &[&bytes("err"), &true])
+      .unwrap();
+    assert_eq!(*stdout.0.borrow(), b"out");
+    assert_eq!(*stderr.0.borrow(), b"err");
+
+    let read: i32 = jvm.invoke_static(CLASS, "read", "()I", &[]).unwrap();
+    assert_eq!(read, b'z' as i32);
+    let read: i32 = jvm.invoke_static(CLASS, "read", "()I", &[]).unwrap();
+    assert_eq!(read, -1);
+  }
+
+  #[test]
+
+  fn test_global() {
+    let mut jvm = mock();
+
+    // the cleaners are run by a thread
+    create_main_thread(&mut jvm, "main");
+
+    let tracked: Global = jvm
+      .invoke_static(CLASS, "tracked", "()Ljava/lang/Object;", &[])
+      .unwrap();
+    let copy = tracked.clone();
+
+    // kept alive while a handle is
+    drop(tracked);
+    assert_eq!(jvm.collect_garbage().unwrap(), 0);
+    assert!(!jvm
+      .invoke_static::<bool>(CLASS, "isCleaned", "()Z", &[])
+      .unwrap());
+
+    let value = copy.to_java(&mut jvm).unwrap();
+    drop(copy);
+    assert!(jvm.globals.lock().unwrap().is_empty());
+
+    assert_eq!(jvm.collect_garbage().unwrap(), 1);
+    assert!(jvm
+      .invoke_static::<bool>(CLASS, "isCleaned", "()Z", &[])
+      .unwrap());
+    assert_ne!(value, Type::Null);
+  }
+}
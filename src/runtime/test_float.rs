@@ -1,5 +1,4 @@
 #[cfg(test)]
-
 mod tests {
 
   use std::ops::Neg;
@@ -185,11 +184,11 @@ mod tests {
   #[test]
 
   fn test_exec_fcmpg() {
-    let values1 = vec![-10.10, 23.23, 7.7, 3.3, 8.8, 42.42, f32::NAN];
+    let values1 = [-10.10, 23.23, 7.7, 3.3, 8.8, 42.42, f32::NAN];
 
-    let values2 = vec![-10.10, 3.4, 7.7, 5.5, 3.3, 23.23, f32::NAN];
+    let values2 = [-10.10, 3.4, 7.7, 5.5, 3.3, 23.23, f32::NAN];
 
-    let exp = vec![0, 1, 0, -1, 1, 1, 1];
+    let exp = [0, 1, 0, -1, 1, 1, 1];
 
     assert!(values1.len() == values2.len());
 
@@ -238,11 +237,11 @@ mod tests {
   #[test]
 
   fn test_exec_fcmpl() {
-    let values1 = vec![-10.10, 23.23, 7.7, 3.3, 8.8, 42.42, f32::NAN];
+    let values1 = [-10.10, 23.23, 7.7, 3.3, 8.8, 42.42, f32::NAN];
 
-    let values2 = vec![-10.10, 3.4, 7.7, 5.5, 3.3, 23.23, f32::NAN];
+    let values2 = [-10.10, 3.4, 7.7, 5.5, 3.3, 23.23, f32::NAN];
 
-    let exp = vec![0, 1, 0, -1, 1, 1, -1];
+    let exp = [0, 1, 0, -1, 1, 1, -1];
 
     assert!(values1.len() == values2.len());
 
@@ -291,9 +290,9 @@ mod tests {
   #[test]
 
   fn test_exec_fcmp_error() {
-    let values1 = vec![f32::NAN, 1.0];
+    let values1 = [f32::NAN, 1.0];
 
-    let values2 = vec![0.0, f32::NAN];
+    let values2 = [0.0, f32::NAN];
 
     assert!(values1.len() == values2.len());
 
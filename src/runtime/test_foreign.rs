@@ -0,0 +1,309 @@
+#[cfg(test)]
+mod tests {
+
+  use crate::runtime::{
+    constants::*,
+    foreign::{self, *},
+    jvm::JVM,
+    test_support::*,
+    types::Type,
+  };
+
+  const CLASS: &str = "TestForeign";
+
+  // encodings of the integer registers
+  const RAX: i32 = 0;
+  const RCX: i32 = 1;
+  const RDX: i32 = 2;
+  const RSI: i32 = 6;
+  const RDI: i32 = 7;
+  const R8: i32 = 8;
+  const R9: i32 = 9;
+
+  // DirectMethodHandle of TestForeign.compare(long, long)
+  fn comparator(jvm: &mut JVM) -> Type {
+    let rtype = class(jvm, "I");
+    let ptypes = vec![class(jvm, "J"), class(jvm, "J")];
+    let ptypes = jvm.heap.alloc_array("java/lang/Class", ptypes, 2).unwrap();
+    let method_type = object(
+      jvm,
+      "java/lang/invoke/MethodType",
+      vec![("rtype", rtype), ("ptypes", ptypes)],
+    );
+
+    let clazz = class(jvm, CLASS);
+    let name = jvm
+      .heap
+      .alloc_string(&mut jvm.class_loader, "compare")
+      .unwrap();
+    let member = object(
+      jvm,
+      "java/lang/invoke/MemberName",
+      vec![
+        ("clazz", clazz),
+        ("name", name),
+        ("type", method_type),
+        (
+          "flags",
+          Type::Integer(MN_IS_METHOD | REF_INVOKE_STATIC << MN_REFERENCE_KIND_SHIFT),
+        ),
+      ],
+    );
+
+    object(
+      jvm,
+      "java/lang/invoke/DirectMethodHandle",
+      vec![("member", member), ("type", method_type)],
+    )
+  }
+
+  fn integer(index: i32) -> Storage {
+    Storage::new(STORAGE_INTEGER, index)
+  }
+
+  fn vector(index: i32) -> Storage {
+    Storage::new(STORAGE_VECTOR, index)
+  }
+
+  fn c_string(jvm: &mut JVM, value: &str) -> Type {
+    let address = jvm.nativememory.alloc(value.len() as u64 + 1).unwrap();
+
+    jvm
+      .nativememory
+      .write(address, format!("{}\0", value).as_bytes())
+      .unwrap();
+
+    Type::Long(address as i64)
+  }
+
+  // a downcall stub of the function `name` for the values of `args`, like the ones of
+  // CallArranger: the address of the function first and the number of vector registers
+  // last
+  fn downcall(jvm: &mut JVM, name: &str, args: Vec<(Storage, Type)>, rets: Vec<Storage>) -> Type {
+    let vectors = args
+      .iter()
+      .filter(|(_, value)| matches!(value, Type::Float(_) | Type::Double(_)))
+      .count();
+
+    let mut descriptor = "(J".to_string();
+    let mut storages = vec![Storage::new(STORAGE_PLACEHOLDER, TARGET_ADDRESS)];
+    let mut values = vec![Type::Long(foreign::find_entry(0, name).unwrap() as i64)];
+
+    for (storage, value) in args {
+      descriptor += value.get_primitive_descriptor().unwrap();
+      storages.push(storage);
+      values.push(value);
+    }
+
+    descriptor += "J)";
+    storages.push(integer(RAX));
+    values.push(Type::Long(vectors as i64));
+
+    descriptor += match rets.first() {
+      None => "V",
+      Some(storage) if *storage == vector(0) => "D",
+      Some(_) => "J",
+    };
+
+    let stub = DowncallStub::new(&descriptor, storages, rets, false, 0);
+    let address = jvm.foreignmanager.add_downcall(stub);
+
+    jvm.invoke_downcall(address, values).unwrap()
+  }
+
+  #[test]
+
+  fn test_foreign_lookup() {
+    assert_eq!(
+      foreign::find_entry(0, "strlen").unwrap(),
+      libc::strlen as *const () as u64
+    );
+    assert_eq!(foreign::find_entry(0, "jvmcraft_missing").unwrap(), 0);
+
+    let handle = foreign::load_library("libc.so.6").unwrap();
+    assert_ne!(handle, 0);
+    assert_eq!(
+      foreign::find_entry(handle, "getpid").unwrap(),
+      libc::getpid as *const () as u64
+    );
+    foreign::unload_library(handle);
+
+    assert_eq!(foreign::load_library("libjvmcraft_missing.so").unwrap(), 0);
+  }
+
+  #[test]
+
+  fn test_foreign_downcall() {
+    let mut jvm = mock();
+
+    let hello = c_string(&mut jvm, "hello");
+    let ret = downcall(
+      &mut jvm,
+      "strlen",
+      vec![(integer(RDI), hello)],
+      vec![integer(RAX)],
+    );
+    assert_eq!(ret, Type::Long(5));
+
+    let ret = downcall(&mut jvm, "getpid", vec![], vec![integer(RAX)]);
+    assert_eq!(ret, Type::Long(std::process::id() as i64));
+
+    // returned in xmm0
+    let number = c_string(&mut jvm, "2.5");
+    let args = vec![(integer(RDI), number), (integer(RSI), Type::Long(0))];
+    let ret = downcall(&mut jvm, "strtod", args, vec![vector(0)]);
+    assert_eq!(ret, Type::Double(2.5));
+  }
+
+  #[test]
+
+  fn test_foreign_downcall_stack() {
+    let mut jvm = mock();
+
+    let buffer = jvm.nativememory.alloc(64).unwrap();
+    let format = c_string(&mut jvm, "%d %d %d %d %d");
+
+    // the arguments after the 6 integer registers are on the stack
+    let args = vec![
+      (integer(RDI), Type::Long(buffer as i64)),
+      (integer(RSI), Type::Long(64)),
+      (integer(RDX), format),
+      (integer(RCX), Type::Integer(1)),
+      (integer(R8), Type::Integer(2)),
+      (integer(R9), Type::Integer(-3)),
+      (Storage::new(STORAGE_STACK, 0), Type::Integer(4)),
+      (Storage::new(STORAGE_STACK, 8), Type::Integer(5)),
+    ];
+    let ret = downcall(&mut jvm, "snprintf", args, vec![integer(RAX)]);
+    assert_eq!(ret, Type::Long(10));
+    assert_eq!(jvm.nativememory.read_string(buffer).unwrap(), "1 2 -3 4 5");
+
+    // a variadic function gets the number of vector registers in rax
+    let format = c_string(&mut jvm, "%.2f %d");
+    let args = vec![
+      (integer(RDI), Type::Long(buffer as i64)),
+      (integer(RSI), Type::Long(64)),
+      (integer(RDX), format),
+      (vector(0), Type::Double(1.25)),
+      (integer(RCX), Type::Integer(7)),
+    ];
+    downcall(&mut jvm, "snprintf", args, vec![integer(RAX)]);
+    assert_eq!(jvm.nativememory.read_string(buffer).unwrap(), "1.25 7");
+  }
+
+  #[test]
+
+  fn test_foreign_downcall_buffers() {
+    let mut jvm = mock();
+
+    // lldiv_t is returned in rax and rdx
+    let buffer = jvm.nativememory.alloc(16).unwrap();
+    let stub = DowncallStub::new(
+      "(JJJJJ)V",
+      vec![
+        Storage::new(STORAGE_PLACEHOLDER, TARGET_ADDRESS),
+        Storage::new(STORAGE_PLACEHOLDER, RETURN_BUFFER),
+        integer(RDI),
+        integer(RSI),
+        integer(RAX),
+      ],
+      vec![integer(RAX), integer(RDX)],
+      true,
+      0,
+    );
+    let address = jvm.foreignmanager.add_downcall(stub);
+
+    let target = foreign::find_entry(0, "lldiv").unwrap();
+    let args = [target, buffer, 17, 5, 0].map(|value| Type::Long(value as i64));
+    let ret = jvm.invoke_downcall(address, args.to_vec()).unwrap();
+    assert_eq!(ret, Type::None);
+
+    let quotient = jvm.nativememory.read(buffer, 8).unwrap();
+    let remainder = jvm.nativememory.read(buffer + 8, 8).unwrap();
+    assert_eq!(quotient, 3i64.to_ne_bytes());
+    assert_eq!(remainder, 2i64.to_ne_bytes());
+
+    // errno is captured after the call
+    let state = jvm.nativememory.alloc(4).unwrap();
+    let stub = DowncallStub::new(
+      "(JJIJ)I",
+      vec![
+        Storage::new(STORAGE_PLACEHOLDER, TARGET_ADDRESS),
+        Storage::new(STORAGE_PLACEHOLDER, CAPTURED_STATE_BUFFER),
+        integer(RDI),
+        integer(RAX),
+      ],
+      vec![integer(RAX)],
+      false,
+      CAPTURE_ERRNO,
+    );
+    let address = jvm.foreignmanager.add_downcall(stub);
+
+    let target = foreign::find_entry(0, "close").unwrap();
+    let args = vec![
+      Type::Long(target as i64),
+      Type::Long(state as i64),
+      Type::Integer(-1),
+      Type::Long(0),
+    ];
+    let ret = jvm.invoke_downcall(address, args).unwrap();
+    assert_eq!(ret, Type::Integer(-1));
+    assert_eq!(
+      jvm.nativememory.read(state, 4).unwrap(),
+      libc::EBADF.to_ne_bytes()
+    );
+
+    // a freed stub is not run
+    assert!(jvm.foreignmanager.remove_downcall(address));
+    assert!(jvm.invoke_downcall(address, vec![]).is_err());
+  }
+
+  #[test]
+
+  fn test_foreign_upcall() {
+    let mut jvm = mock();
+
+    let comparator = comparator(&mut jvm);
+
+    let stub = UpcallStub::new(
+      comparator.as_ref().unwrap(),
+      vec![integer(RDI), integer(RSI)],
+      vec![integer(RAX)],
+      None,
+    );
+    let compare = jvm.foreignmanager.add_upcall(stub).unwrap();
+    assert_ne!(compare, 0);
+
+    let values: [i32; 5] = [5, -1, 42, 0, 7];
+    let array = jvm.nativememory.alloc(values.len() as u64 * 4).unwrap();
+    for (i, value) in values.iter().enumerate() {
+      jvm
+        .nativememory
+        .write(array + i as u64 * 4, &value.to_ne_bytes())
+        .unwrap();
+    }
+
+    // qsort calls back the comparator of the Java class
+    let args = vec![
+      (integer(RDI), Type::Long(array as i64)),
+      (integer(RSI), Type::Long(values.len() as i64)),
+      (integer(RDX), Type::Long(4)),
+      (integer(RCX), Type::Long(compare as i64)),
+    ];
+    downcall(&mut jvm, "qsort", args, vec![]);
+
+    let sorted: Vec<i32> = (0..values.len() as u64)
+      .map(|i| {
+        let bytes = jvm.nativememory.read(array + i * 4, 4).unwrap();
+        i32::from_ne_bytes(bytes.try_into().unwrap())
+      })
+      .collect();
+    assert_eq!(sorted, vec![-1, 0, 5, 7, 42]);
+
+    let calls = jvm.class_loader.get_static_field(CLASS, "calls").unwrap();
+    assert!(calls.as_integer().unwrap() > 0);
+
+    assert!(jvm.foreignmanager.remove_upcall(compare));
+    assert!(!jvm.foreignmanager.remove_upcall(compare));
+  }
+}
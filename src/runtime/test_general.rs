@@ -1,5 +1,4 @@
 #[cfg(test)]
-
 mod tests {
 
   use crate::runtime::{jvm::JVM, types};
@@ -78,12 +77,7 @@ mod tests {
 
       assert!(jvm.get_current_frame_mut().unwrap().read_ju1().is_ok());
 
-      let local0 = jvm
-        .get_current_frame()
-        .unwrap()
-        .get_local(0)
-        .unwrap()
-        .clone();
+      let local0 = *jvm.get_current_frame().unwrap().get_local(0).unwrap();
 
       jvm.get_current_frame_mut().unwrap().push_stack(local0);
 
@@ -108,12 +102,7 @@ mod tests {
 
       assert!(jvm.get_current_frame_mut().unwrap().read_ju1().is_ok());
 
-      let local1 = jvm
-        .get_current_frame()
-        .unwrap()
-        .get_local(1)
-        .unwrap()
-        .clone();
+      let local1 = *jvm.get_current_frame().unwrap().get_local(1).unwrap();
 
       jvm.get_current_frame_mut().unwrap().push_stack(local1);
 
@@ -138,12 +127,7 @@ mod tests {
 
       assert!(jvm.get_current_frame_mut().unwrap().read_ju1().is_ok());
 
-      let local3 = jvm
-        .get_current_frame()
-        .unwrap()
-        .get_local(3)
-        .unwrap()
-        .clone();
+      let local3 = *jvm.get_current_frame().unwrap().get_local(3).unwrap();
 
       jvm.get_current_frame_mut().unwrap().push_stack(local3);
 
@@ -168,12 +152,7 @@ mod tests {
 
       assert!(jvm.get_current_frame_mut().unwrap().read_ju1().is_ok());
 
-      let local2 = jvm
-        .get_current_frame()
-        .unwrap()
-        .get_local(2)
-        .unwrap()
-        .clone();
+      let local2 = *jvm.get_current_frame().unwrap().get_local(2).unwrap();
 
       jvm.get_current_frame_mut().unwrap().push_stack(local2);
 
@@ -220,12 +199,7 @@ mod tests {
       // skip instanceof
       assert!(jvm.get_current_frame_mut().unwrap().read_ju1().is_ok());
 
-      let local1 = jvm
-        .get_current_frame()
-        .unwrap()
-        .get_local(1)
-        .unwrap()
-        .clone();
+      let local1 = *jvm.get_current_frame().unwrap().get_local(1).unwrap();
 
       jvm.get_current_frame_mut().unwrap().push_stack(local1);
 
@@ -272,12 +246,7 @@ mod tests {
       // skip instanceof
       assert!(jvm.get_current_frame_mut().unwrap().read_ju1().is_ok());
 
-      let local2 = jvm
-        .get_current_frame()
-        .unwrap()
-        .get_local(2)
-        .unwrap()
-        .clone();
+      let local2 = *jvm.get_current_frame().unwrap().get_local(2).unwrap();
 
       jvm.get_current_frame_mut().unwrap().push_stack(local2);
 
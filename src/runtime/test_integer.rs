@@ -1,5 +1,4 @@
 #[cfg(test)]
-
 mod tests {
 
   use crate::runtime::{jvm::JVM, types};
@@ -760,9 +759,9 @@ mod tests {
   fn test_exec_if_icmp() {
     let offset = 4;
 
-    let values1 = vec![-10, 23, 7, 3, 8, 42];
+    let values1 = [-10, 23, 7, 3, 8, 42];
 
-    let values2 = vec![-10, 3, 7, 5, 3, 23];
+    let values2 = [-10, 3, 7, 5, 3, 23];
 
     assert!(values1.len() == values2.len());
 
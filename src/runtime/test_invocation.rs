@@ -0,0 +1,167 @@
+#[cfg(test)]
+mod tests {
+
+  use std::{ffi::c_void, ptr::null_mut};
+
+  use crate::runtime::{
+    errors::JavaException,
+    invocation::{self, InvocationOptions},
+    jni::{JNI_EINVAL, JNI_EVERSION, JNI_OK},
+    jvm::JVM,
+    types::Type,
+  };
+
+  // JavaVMOption
+  #[repr(C)]
+  struct VmOption {
+    option_string: *const std::ffi::c_char,
+    extra_info: *mut c_void,
+  }
+
+  // JavaVMInitArgs
+  #[repr(C)]
+  struct InitArgs {
+    version: i32,
+    n_options: i32,
+    options: *const VmOption,
+    ignore_unrecognized: u8,
+  }
+
+  fn parse(options: &[&str], ignore_unrecognized: bool) -> color_eyre::Result<InvocationOptions> {
+    let options: Vec<String> = options.iter().map(|option| option.to_string()).collect();
+
+    InvocationOptions::parse(&options, ignore_unrecognized)
+  }
+
+  #[test]
+
+  fn test_invocation_options() {
+    let options = parse(
+      &[
+        "-Djava.class.path=tests/classes",
+        "-Dinvocation.test=true",
+        "-Xss512k",
+        "-Xverify:none",
+        "vfprintf",
+      ],
+      false,
+    )
+    .unwrap();
+
+    assert_eq!(options.class_path, "tests/classes");
+    assert_eq!(
+      options.properties,
+      vec![
+        ("java.class.path".to_string(), "tests/classes".to_string()),
+        ("invocation.test".to_string(), "true".to_string()),
+      ]
+    );
+    assert_eq!(options.stack_size, Some(512 << 10));
+    assert!(!options.verify);
+
+    let options = parse(&[], false).unwrap();
+    assert_eq!(options.class_path, ".");
+    assert_eq!(options.stack_size, None);
+    assert!(options.verify);
+
+    assert_eq!(parse(&["-Xss2m"], false).unwrap().stack_size, Some(2 << 20));
+    assert_eq!(parse(&["-Xss4096"], false).unwrap().stack_size, Some(4096));
+    assert!(parse(&["-Xssmany"], false).is_err());
+  }
+
+  #[test]
+
+  fn test_invocation_unrecognized() {
+    assert!(parse(&["-Xmx1g"], false).is_err());
+    assert!(parse(&["-Xmx1g", "_hook"], true).is_ok());
+
+    // only the -X and _ options can be ignored
+    assert!(parse(&["--add-opens"], true).is_err());
+    assert!(parse(&["-Djava.class.path=a:b"], true).is_err());
+  }
+
+  #[test]
+
+  fn test_invocation_stack_size() {
+    let mut jvm = JVM::mock("tests/classes/", vec![], false).unwrap();
+
+    // testFields gets the only frame, the constructor it calls overflows the stack
+    jvm.max_frames = Some(1);
+
+    let err = jvm
+      .call_and_resolve_method("TestJni", "testFields", "()Ljava/lang/String;", vec![])
+      .unwrap_err();
+    assert!(
+      matches!(
+        err.downcast_ref::<JavaException>(),
+        Some(JavaException::StackOverflowError(_))
+      ),
+      "{}",
+      err
+    );
+
+    jvm.max_frames = None;
+    jvm.frames.clear();
+
+    // the longs and the doubles take two slots
+    let args = vec![
+      Type::Float(1.0),
+      Type::Double(2.0),
+      Type::Double(2.0),
+      Type::Long(3),
+      Type::Long(3),
+      Type::Integer(4),
+      Type::Integer(5),
+      Type::Integer(6),
+      Type::Double(7.0),
+      Type::Double(7.0),
+    ];
+    let ret = jvm
+      .call_and_resolve_method("TestJni", "mix", "(FDJIIID)D", args)
+      .unwrap();
+    assert_eq!(ret, Type::Double(28.0));
+  }
+
+  #[test]
+
+  fn test_invocation_functions() {
+    let option = VmOption {
+      option_string: c"-Xunknown".as_ptr(),
+      extra_info: null_mut(),
+    };
+    let mut args = InitArgs {
+      version: 0x0001_0008,
+      n_options: 1,
+      options: &option,
+      ignore_unrecognized: 0,
+    };
+    let args = &mut args as *mut InitArgs as *mut c_void;
+
+    unsafe {
+      assert_eq!(invocation::JNI_GetDefaultJavaVMInitArgs(args), JNI_OK);
+
+      // no VM is created with invalid options
+      let mut vm = null_mut();
+      let mut env = null_mut();
+      assert_eq!(
+        invocation::JNI_CreateJavaVM(&mut vm, &mut env, args),
+        JNI_EINVAL
+      );
+      assert!(vm.is_null());
+
+      let mut count = -1;
+      assert_eq!(
+        invocation::JNI_GetCreatedJavaVMs(&mut vm, 1, &mut count),
+        JNI_OK
+      );
+      assert_eq!(count, 0);
+
+      (*(args as *mut InitArgs)).version = 0x0001_0001;
+      assert_eq!(invocation::JNI_GetDefaultJavaVMInitArgs(args), JNI_EVERSION);
+      assert_eq!(
+        invocation::JNI_CreateJavaVM(&mut vm, &mut env, args),
+        JNI_EVERSION
+      );
+    }
+  }
+}
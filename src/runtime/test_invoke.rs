@@ -0,0 +1,304 @@
+#[cfg(test)]
+mod tests {
+
+  use color_eyre::eyre::Result;
+
+  use crate::runtime::{errors::JavaException, jvm::JVM, test_support::*, types::Type};
+
+  const METHOD_ACCESSOR: &str = "jdk/internal/reflect/DirectMethodHandleAccessor$NativeAccessor";
+  const CONSTRUCTOR_ACCESSOR: &str =
+    "jdk/internal/reflect/DirectConstructorHandleAccessor$NativeAccessor";
+
+  fn mock() -> JVM {
+    mock_with_frame("TestInvoke", "nothing", "()V")
+  }
+
+  fn method(jvm: &mut JVM, class_name: &str, name: &str, descriptor: &str) -> Type {
+    let (_, method, _) = jvm
+      .class_loader
+      .get_any_method_by_name_with_index(class_name, name, descriptor)
+      .unwrap();
+
+    jvm
+      .heap
+      .alloc_reflect_method(&mut jvm.class_loader, class_name, &method)
+      .unwrap()
+  }
+
+  fn constructor(jvm: &mut JVM, class_name: &str, descriptor: &str) -> Type {
+    let (_, method) = jvm
+      .class_loader
+      .get_method_by_name(class_name, "<init>", descriptor)
+      .unwrap();
+
+    jvm
+      .heap
+      .alloc_reflect_constructor(&mut jvm.class_loader, class_name, &method)
+      .unwrap()
+  }
+
+  fn unboxed(jvm: &JVM, value: Type) -> (String, Type) {
+    let (descriptor, value) = jvm
+      .heap
+      .get_boxed_value(value.as_ref().unwrap())
+      .unwrap()
+      .unwrap();
+
+    (descriptor.to_string(), value)
+  }
+
+  fn objects(jvm: &mut JVM, elements: Vec<Type>) -> Type {
+    array(jvm, "java/lang/Object", elements)
+  }
+
+  fn invoke(jvm: &mut JVM, method: Type, target: Type, args: Type) -> Result<Type> {
+    jvm.push_stack(method)?;
+    jvm.push_stack(target)?;
+    jvm.push_stack(args)?;
+
+    jvm.call_native(
+      METHOD_ACCESSOR,
+      "invoke0",
+      "(Ljava/lang/reflect/Method;Ljava/lang/Object;[Ljava/lang/Object;)Ljava/lang/Object;",
+    )?;
+
+    jvm.pop_stack()
+  }
+
+  fn new_instance(jvm: &mut JVM, constructor: Type, args: Type) -> Result<Type> {
+    jvm.push_stack(constructor)?;
+    jvm.push_stack(args)?;
+
+    jvm.call_native(
+      CONSTRUCTOR_ACCESSOR,
+      "newInstance0",
+      "(Ljava/lang/reflect/Constructor;[Ljava/lang/Object;)Ljava/lang/Object;",
+    )?;
+
+    jvm.pop_stack()
+  }
+
+  fn exception(result: Result<Type>) -> JavaException {
+    java_exception(result.unwrap_err())
+  }
+
+  #[test]
+
+  fn test_invoke_static() {
+    let mut jvm = mock();
+
+    let add = method(&mut jvm, "TestInvoke", "add", "(IJ)J");
+    let a = boxed(&mut jvm, "I", Type::Integer(40));
+    let b = boxed(&mut jvm, "J", Type::Long(2));
+    let args = objects(&mut jvm, vec![a, b]);
+    let ret = invoke(&mut jvm, add, Type::Null, args).unwrap();
+    assert_eq!(unboxed(&jvm, ret), ("J".to_string(), Type::Long(42)));
+
+    // byte widens to int and int widens to long
+    let a = boxed(&mut jvm, "B", Type::Byte(-1));
+    let b = boxed(&mut jvm, "I", Type::Integer(3));
+    let args = objects(&mut jvm, vec![a, b]);
+    let ret = invoke(&mut jvm, add, Type::Null, args).unwrap();
+    assert_eq!(unboxed(&jvm, ret), ("J".to_string(), Type::Long(2)));
+
+    let half = method(&mut jvm, "TestInvoke", "half", "(D)D");
+    let f = boxed(&mut jvm, "F", Type::Float(3.0));
+    let args = objects(&mut jvm, vec![f]);
+    let ret = invoke(&mut jvm, half, Type::Null, args).unwrap();
+    assert_eq!(unboxed(&jvm, ret), ("D".to_string(), Type::Double(1.5)));
+
+    let next = method(&mut jvm, "TestInvoke", "next", "(C)C");
+    let c = boxed(&mut jvm, "C", Type::Integer('a' as i32));
+    let args = objects(&mut jvm, vec![c]);
+    let ret = invoke(&mut jvm, next, Type::Null, args).unwrap();
+    assert_eq!(
+      unboxed(&jvm, ret),
+      ("C".to_string(), Type::Integer('b' as i32))
+    );
+
+    let not = method(&mut jvm, "TestInvoke", "not", "(Z)Z");
+    let z = boxed(&mut jvm, "Z", Type::Boolean(true));
+    let args = objects(&mut jvm, vec![z]);
+    let ret = invoke(&mut jvm, not, Type::Null, args).unwrap();
+    assert_eq!(unboxed(&jvm, ret), ("Z".to_string(), Type::Boolean(false)));
+
+    // void methods return null, a null array stands for no arguments
+    let nothing = method(&mut jvm, "TestInvoke", "nothing", "()V");
+    let ret = invoke(&mut jvm, nothing, Type::Null, Type::Null).unwrap();
+    assert_eq!(ret, Type::Null);
+
+    // varargs are passed as an array
+    let sum = method(&mut jvm, "TestInvoke", "sum", "([I)I");
+    let values = jvm
+      .heap
+      .alloc_array_primitive(
+        "I",
+        vec![Type::Integer(1), Type::Integer(2), Type::Integer(3)],
+        3,
+      )
+      .unwrap();
+    let args = objects(&mut jvm, vec![values]);
+    let ret = invoke(&mut jvm, sum, Type::Null, args).unwrap();
+    assert_eq!(unboxed(&jvm, ret), ("I".to_string(), Type::Integer(6)));
+  }
+
+  #[test]
+
+  fn test_invoke_arguments() {
+    let mut jvm = mock();
+
+    let add = method(&mut jvm, "TestInvoke", "add", "(IJ)J");
+
+    let a = boxed(&mut jvm, "I", Type::Integer(1));
+    let args = objects(&mut jvm, vec![a]);
+    assert!(matches!(
+      exception(invoke(&mut jvm, add, Type::Null, args)),
+      JavaException::IllegalArgumentException(msg) if msg == "wrong number of arguments: 1 expected: 2"
+    ));
+
+    // long does not narrow to int
+    let a = boxed(&mut jvm, "J", Type::Long(1));
+    let b = boxed(&mut jvm, "J", Type::Long(2));
+    let args = objects(&mut jvm, vec![a, b]);
+    assert!(matches!(
+      exception(invoke(&mut jvm, add, Type::Null, args)),
+      JavaException::IllegalArgumentException(msg) if msg == "argument type mismatch"
+    ));
+
+    let b = boxed(&mut jvm, "J", Type::Long(2));
+    let args = objects(&mut jvm, vec![Type::Null, b]);
+    assert!(matches!(
+      exception(invoke(&mut jvm, add, Type::Null, args)),
+      JavaException::IllegalArgumentException(_)
+    ));
+
+    let a = jvm.heap.alloc_string(&mut jvm.class_loader, "1").unwrap();
+    let b = boxed(&mut jvm, "J", Type::Long(2));
+    let args = objects(&mut jvm, vec![a, b]);
+    assert!(matches!(
+      exception(invoke(&mut jvm, add, Type::Null, args)),
+      JavaException::IllegalArgumentException(_)
+    ));
+
+    // a boolean never converts to a number
+    let not = method(&mut jvm, "TestInvoke", "not", "(Z)Z");
+    let i = boxed(&mut jvm, "I", Type::Integer(1));
+    let args = objects(&mut jvm, vec![i]);
+    assert!(matches!(
+      exception(invoke(&mut jvm, not, Type::Null, args)),
+      JavaException::IllegalArgumentException(_)
+    ));
+  }
+
+  #[test]
+
+  fn test_invoke_virtual() {
+    let mut jvm = mock();
+
+    let derived = new_object(&mut jvm, "TestInvoke$Derived");
+
+    // abstract and overridden methods dispatch on the target
+    for name in ["name", "describe"] {
+      let m = method(&mut jvm, "TestInvoke$Base", name, "()Ljava/lang/String;");
+      let ret = invoke(&mut jvm, m, derived, Type::Null).unwrap();
+      assert_eq!(
+        jvm.heap.get_string(ret.as_ref().unwrap()).unwrap(),
+        "derived"
+      );
+    }
+
+    // private methods are not overridden
+    let secret = method(
+      &mut jvm,
+      "TestInvoke$Base",
+      "secret",
+      "()Ljava/lang/String;",
+    );
+    let ret = invoke(&mut jvm, secret, derived, Type::Null).unwrap();
+    assert_eq!(
+      jvm.heap.get_string(ret.as_ref().unwrap()).unwrap(),
+      "base secret"
+    );
+
+    let square = new_object(&mut jvm, "TestInvoke$Square");
+    let sides = method(&mut jvm, "TestInvoke$Shape", "sides", "()I");
+    let ret = invoke(&mut jvm, sides, square, Type::Null).unwrap();
+    assert_eq!(unboxed(&jvm, ret), ("I".to_string(), Type::Integer(4)));
+
+    let describe = method(
+      &mut jvm,
+      "TestInvoke$Base",
+      "describe",
+      "()Ljava/lang/String;",
+    );
+    assert!(matches!(
+      exception(invoke(&mut jvm, describe, Type::Null, Type::Null)),
+      JavaException::NullPointer
+    ));
+    assert!(matches!(
+      exception(invoke(&mut jvm, describe, square, Type::Null)),
+      JavaException::IllegalArgumentException(msg) if msg == "object is not an instance of declaring class"
+    ));
+  }
+
+  #[test]
+
+  fn test_invocation_target_exception() {
+    let mut jvm = mock();
+
+    let divide = method(&mut jvm, "TestInvoke", "divide", "(II)I");
+    let a = boxed(&mut jvm, "I", Type::Integer(1));
+    let b = boxed(&mut jvm, "I", Type::Integer(0));
+    let args = objects(&mut jvm, vec![a, b]);
+
+    let JavaException::InvocationTargetException(target) =
+      exception(invoke(&mut jvm, divide, Type::Null, args))
+    else {
+      panic!("not an InvocationTargetException");
+    };
+    assert!(matches!(*target, JavaException::Arithmetic));
+  }
+
+  #[test]
+
+  fn test_new_instance() {
+    let mut jvm = mock();
+
+    let ctor = constructor(&mut jvm, "TestInvoke", "(I)V");
+    let value = boxed(&mut jvm, "I", Type::Integer(7));
+    let args = objects(&mut jvm, vec![value]);
+    let obj = new_instance(&mut jvm, ctor, args).unwrap();
+    assert_eq!(
+      jvm
+        .heap
+        .get_obj_instance(obj.as_ref().unwrap())
+        .unwrap()
+        .get_field("value")
+        .unwrap()
+        .as_integer()
+        .unwrap(),
+      7
+    );
+
+    let ctor = constructor(&mut jvm, "TestInvoke", "(II)V");
+    let a = boxed(&mut jvm, "I", Type::Integer(1));
+    let b = boxed(&mut jvm, "I", Type::Integer(0));
+    let args = objects(&mut jvm, vec![a, b]);
+    assert!(matches!(
+      exception(new_instance(&mut jvm, ctor, args)),
+      JavaException::InvocationTargetException(_)
+    ));
+
+    let args = objects(&mut jvm, vec![]);
+    assert!(matches!(
+      exception(new_instance(&mut jvm, ctor, args)),
+      JavaException::IllegalArgumentException(_)
+    ));
+
+    let ctor = constructor(&mut jvm, "TestInvoke$Base", "()V");
+    assert!(matches!(
+      exception(new_instance(&mut jvm, ctor, Type::Null)),
+      JavaException::InstantiationException(name) if name == "TestInvoke$Base"
+    ));
+  }
+}
@@ -0,0 +1,187 @@
+#[cfg(test)]
+mod tests {
+
+  use std::{path::PathBuf, process::Command};
+
+  use crate::runtime::{
+    jni::{self, JNI_GLOBAL_REF_TYPE},
+    jvm::JVM,
+    test_support::{self, *},
+    types::Type,
+  };
+
+  const CLASS: &str = "TestJni";
+
+  // the headers of the JDK of javac
+  fn java_home() -> PathBuf {
+    if let Ok(home) = std::env::var("JAVA_HOME") {
+      return PathBuf::from(home);
+    }
+
+    let output = Command::new("which").arg("javac").output().unwrap();
+    let javac = String::from_utf8(output.stdout).unwrap();
+    let javac = std::fs::canonicalize(javac.trim()).unwrap();
+
+    javac.parent().unwrap().parent().unwrap().to_path_buf()
+  }
+
+  // tests/native/testjni.c, built in a directory of its own for each test
+  fn build_library(name: &str) -> String {
+    let home = java_home();
+    let directory = std::env::temp_dir().join(format!("jvmcraft-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+
+    let library = directory.join("libtestjni.so");
+
+    let status = Command::new("cc")
+      .args(["-shared", "-fPIC", "-o"])
+      .arg(&library)
+      .arg(format!("-I{}", home.join("include").display()))
+      .arg(format!("-I{}", home.join("include/linux").display()))
+      .arg("tests/native/testjni.c")
+      .status()
+      .unwrap();
+    assert!(status.success());
+
+    library.to_str().unwrap().to_string()
+  }
+
+  fn mock(name: &str) -> JVM {
+    let mut jvm = test_support::mock();
+
+    let (handle, version) = jvm.load_jni_library(&build_library(name)).unwrap();
+    assert_ne!(handle, 0);
+    assert_eq!(version, 0x0001_0008);
+
+    jvm
+  }
+
+  #[test]
+
+  fn test_jni_names() {
+    assert_eq!(
+      jni::get_jni_names(CLASS, "sum", "(JD)D"),
+      (
+        "Java_TestJni_sum".to_string(),
+        "Java_TestJni_sum__JD".to_string()
+      )
+    );
+    assert_eq!(
+      jni::get_jni_names("a/b_c$D", "f\u{e9}", "([Ljava/lang/String;)V"),
+      (
+        "Java_a_b_1c_00024D_f_000e9".to_string(),
+        "Java_a_b_1c_00024D_f_000e9___3Ljava_lang_String_2".to_string()
+      )
+    );
+  }
+
+  #[test]
+
+  fn test_jni_calls() {
+    let mut jvm = mock("calls");
+
+    assert_eq!(
+      call(&mut jvm, CLASS, "testAdd", "()I", vec![]).unwrap(),
+      Type::Integer(42)
+    );
+    assert_eq!(
+      call(&mut jvm, CLASS, "testSum", "()D", vec![]).unwrap(),
+      Type::Double(13.5)
+    );
+    assert_eq!(
+      call_string(&mut jvm, CLASS, "testGreet", "()Ljava/lang/String;", vec![]),
+      "hello jni"
+    );
+    assert_eq!(
+      call_string(
+        &mut jvm,
+        CLASS,
+        "testFields",
+        "()Ljava/lang/String;",
+        vec![]
+      ),
+      "eight=8"
+    );
+    assert_eq!(
+      call_string(
+        &mut jvm,
+        CLASS,
+        "testConcat",
+        "()Ljava/lang/String;",
+        vec![]
+      ),
+      "a+\u{e9}+\u{20ac}+z"
+    );
+    assert_eq!(
+      call(&mut jvm, CLASS, "testReverse", "()I", vec![]).unwrap(),
+      Type::Integer(54321)
+    );
+    assert_eq!(
+      call(&mut jvm, CLASS, "testStatics", "()I", vec![]).unwrap(),
+      Type::Integer(2)
+    );
+  }
+
+  #[test]
+
+  fn test_jni_callbacks() {
+    let mut jvm = mock("callbacks");
+
+    // the arguments of the va_list after the registers are on the stack
+    assert_eq!(
+      call(&mut jvm, CLASS, "testCallback", "()D", vec![]).unwrap(),
+      Type::Double(1.5 + 2.25 + 3.0 + 4.0 + 5.0 + 6.0 + 7.125)
+    );
+    assert_eq!(
+      call_string(
+        &mut jvm,
+        CLASS,
+        "testCreate",
+        "()Ljava/lang/String;",
+        vec![]
+      ),
+      "three=3"
+    );
+  }
+
+  #[test]
+
+  fn test_jni_exceptions() {
+    let mut jvm = mock("exceptions");
+
+    assert_eq!(
+      call_string(&mut jvm, CLASS, "testFail", "()Ljava/lang/String;", vec![]),
+      "from native"
+    );
+    assert_eq!(
+      call(&mut jvm, CLASS, "testCatch", "()Z", vec![]).unwrap(),
+      Type::Boolean(true)
+    );
+    assert_eq!(jvm.jnimanager.get_pending(), None);
+  }
+
+  #[test]
+
+  fn test_jni_references() {
+    let mut jvm = mock("references");
+
+    let obj = jvm
+      .heap
+      .alloc_string(&mut jvm.class_loader, "kept")
+      .unwrap();
+
+    let ret = jvm
+      .call_and_resolve_method(CLASS, "testKeep", "(Ljava/lang/Object;)I", vec![obj])
+      .unwrap();
+    assert_eq!(ret, Type::Integer(JNI_GLOBAL_REF_TYPE));
+
+    // the global reference is a root of the collector
+    assert!(jvm.jnimanager.get_references().contains(&obj));
+
+    jvm.collect_garbage().unwrap();
+
+    let kept = call(&mut jvm, CLASS, "testKept", "()Ljava/lang/Object;", vec![]).unwrap();
+    assert_eq!(kept, obj);
+    assert_eq!(jvm.heap.get_string(kept.as_ref().unwrap()).unwrap(), "kept");
+  }
+}
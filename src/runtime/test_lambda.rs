@@ -0,0 +1,188 @@
+#[cfg(test)]
+mod tests {
+
+  use crate::runtime::{errors::JavaException, jvm::JVM, test_support::*, types::Type};
+
+  const CLASS: &str = "TestLambda";
+
+  fn mock() -> JVM {
+    mock_with_frame(CLASS, "nonCapturing", "()I")
+  }
+
+  #[test]
+
+  fn test_lambda_capture() {
+    let mut jvm = mock();
+
+    let ret = call(&mut jvm, CLASS, "nonCapturing", "()I", vec![]).unwrap();
+    assert_eq!(ret, Type::Integer(5));
+
+    let ret = call(&mut jvm, CLASS, "sameInstance", "()Z", vec![]).unwrap();
+    assert!(ret.as_bool().unwrap());
+
+    // category 2 values are captured with the others
+    let suffix = jvm.heap.alloc_string(&mut jvm.class_loader, "de").unwrap();
+    let ret = call(
+      &mut jvm,
+      CLASS,
+      "capturing",
+      "(JDLjava/lang/String;)J",
+      vec![
+        Type::Long(100),
+        Type::Long(100),
+        Type::Double(1.5),
+        Type::Double(1.5),
+        suffix,
+      ],
+    )
+    .unwrap();
+    assert_eq!(ret, Type::Long(107));
+
+    let obj = jvm
+      .heap
+      .alloc_obj(&mut jvm.class_loader, "TestLambda")
+      .unwrap();
+    jvm
+      .call_and_resolve_method("TestLambda", "<init>", "(I)V", vec![obj, Type::Integer(10)])
+      .unwrap();
+    let ret = call(
+      &mut jvm,
+      CLASS,
+      "capturingThis",
+      "(I)I",
+      vec![obj, Type::Integer(4)],
+    )
+    .unwrap();
+    assert_eq!(ret, Type::Integer(41));
+  }
+
+  #[test]
+
+  fn test_lambda_method_references() {
+    let mut jvm = mock();
+
+    // virtual, static, interface and constructor references
+    let ret = call(&mut jvm, CLASS, "methodReferences", "()I", vec![]).unwrap();
+    assert_eq!(ret, Type::Integer(1004));
+
+    let ret = call(&mut jvm, CLASS, "widening", "()D", vec![]).unwrap();
+    assert_eq!(ret, Type::Double(7.0));
+
+    // unboxing null for a primitive parameter
+    assert!(matches!(
+      call(
+        &mut jvm,
+        CLASS,
+        "unboxNull",
+        "()Ljava/lang/Integer;",
+        vec![]
+      )
+      .unwrap_err()
+      .downcast::<JavaException>()
+      .unwrap(),
+      JavaException::NullPointer
+    ));
+  }
+
+  #[test]
+
+  fn test_lambda_bridges_and_markers() {
+    let mut jvm = mock();
+
+    let ret = call(&mut jvm, CLASS, "bridges", "()Ljava/lang/String;", vec![]).unwrap();
+    assert_eq!(get_string(&jvm, ret), "ababab");
+
+    let ret = call(&mut jvm, CLASS, "metafactoryBridges", "()I", vec![]).unwrap();
+    assert_eq!(ret, Type::Integer(32));
+
+    let ret = call(&mut jvm, CLASS, "markers", "()Z", vec![]).unwrap();
+    assert!(ret.as_bool().unwrap());
+  }
+
+  #[test]
+
+  fn test_lambda_serializable() {
+    let mut jvm = mock();
+
+    let lambda = call(
+      &mut jvm,
+      CLASS,
+      "serializable",
+      "(I)LTestLambda$SerializableOp;",
+      vec![Type::Integer(3)],
+    )
+    .unwrap();
+
+    let lambda_class = jvm
+      .heap
+      .get_obj_instance(lambda.as_ref().unwrap())
+      .unwrap()
+      .get_classname()
+      .to_string();
+
+    assert!(lambda_class.starts_with("TestLambda$$Lambda/0x"));
+    assert!(jvm
+      .class_loader
+      .get(&lambda_class)
+      .unwrap()
+      .get_interfaces()
+      .contains(&"java/io/Serializable".to_string()));
+
+    let ret = jvm
+      .call_and_resolve_method(
+        &lambda_class,
+        "apply",
+        "(II)I",
+        vec![lambda, Type::Integer(1), Type::Integer(2)],
+      )
+      .unwrap();
+    assert_eq!(ret, Type::Integer(9));
+
+    let serialized = jvm
+      .call_and_resolve_method(
+        &lambda_class,
+        "writeReplace",
+        "()Ljava/lang/Object;",
+        vec![lambda],
+      )
+      .unwrap();
+
+    let serialized_obj = jvm
+      .heap
+      .get_obj_instance(serialized.as_ref().unwrap())
+      .unwrap()
+      .clone();
+
+    assert_eq!(
+      serialized_obj.get_classname(),
+      "java/lang/invoke/SerializedLambda"
+    );
+
+    for (field, value) in [
+      ("functionalInterfaceClass", "TestLambda$SerializableOp"),
+      ("functionalInterfaceMethodName", "apply"),
+      ("implMethodName", "lambda$serializable$74137711$1"),
+      ("implMethodSignature", "(III)I"),
+    ] {
+      assert_eq!(
+        get_string(&jvm, serialized_obj.get_field(field).unwrap()),
+        value
+      );
+    }
+
+    let captured_args = serialized_obj.get_field("capturedArgs").unwrap();
+    let captured_arg = *jvm
+      .heap
+      .get_array_instance(captured_args.as_ref().unwrap())
+      .unwrap()
+      .get(0)
+      .unwrap();
+    assert_eq!(
+      jvm
+        .heap
+        .get_boxed_value(captured_arg.as_ref().unwrap())
+        .unwrap(),
+      Some(("I", Type::Integer(3)))
+    );
+  }
+}
@@ -0,0 +1,1298 @@
+#[cfg(test)]
+mod tests {
+
+  use crate::{
+    runtime::{constants::*, errors::JavaException, jvm::JVM, test_support::*, types::Type},
+    utils::{get_parameters_type_descriptor, parse_parameter_types},
+  };
+
+  const CLASS: &str = "TestMethodHandle";
+  const METHOD_HANDLE_IMPL: &str = "java/lang/invoke/MethodHandleImpl";
+
+  fn integer(jvm: &mut JVM, value: i32) -> Type {
+    boxed(jvm, "I", Type::Integer(value))
+  }
+
+  // MethodType of the JDK with its fields set, like MethodType.methodType does
+  fn method_type(jvm: &mut JVM, descriptor: &str) -> Type {
+    let (_, rtype) = descriptor.split_once(')').unwrap();
+    let rtype = class(jvm, rtype);
+
+    let mut ptypes = vec![];
+    for ptype in parse_parameter_types(get_parameters_type_descriptor(descriptor)) {
+      ptypes.push(class(jvm, ptype));
+    }
+    let ptypes = array(jvm, "java/lang/Class", ptypes);
+
+    object(
+      jvm,
+      "java/lang/invoke/MethodType",
+      vec![("rtype", rtype), ("ptypes", ptypes)],
+    )
+  }
+
+  // resolved MemberName, like MethodHandleNatives.resolve leaves them
+  fn member(jvm: &mut JVM, classname: &str, name: &str, member_type: Type, flags: i32) -> Type {
+    let clazz = class(jvm, classname);
+    let name = string(jvm, name);
+
+    object(
+      jvm,
+      "java/lang/invoke/MemberName",
+      vec![
+        ("clazz", clazz),
+        ("name", name),
+        ("type", member_type),
+        ("flags", Type::Integer(flags)),
+      ],
+    )
+  }
+
+  fn method(jvm: &mut JVM, classname: &str, name: &str, descriptor: &str, ref_kind: i32) -> Type {
+    let member_type = method_type(jvm, descriptor);
+    let kind = match name {
+      "<init>" => MN_IS_CONSTRUCTOR,
+      _ => MN_IS_METHOD,
+    };
+
+    member(
+      jvm,
+      classname,
+      name,
+      member_type,
+      kind | ref_kind << MN_REFERENCE_KIND_SHIFT,
+    )
+  }
+
+  fn field(jvm: &mut JVM, classname: &str, name: &str, field_type: &str, ref_kind: i32) -> Type {
+    let member_type = class(jvm, field_type);
+
+    member(
+      jvm,
+      classname,
+      name,
+      member_type,
+      MN_IS_FIELD | ref_kind << MN_REFERENCE_KIND_SHIFT,
+    )
+  }
+
+  fn direct(jvm: &mut JVM, classname: &str, member: Type, descriptor: &str) -> Type {
+    let handle_type = method_type(jvm, descriptor);
+
+    object(
+      jvm,
+      &format!("java/lang/invoke/{}", classname),
+      vec![("member", member), ("type", handle_type)],
+    )
+  }
+
+  fn direct_static(jvm: &mut JVM, name: &str, descriptor: &str) -> Type {
+    let member = method(jvm, CLASS, name, descriptor, REF_INVOKE_STATIC);
+
+    direct(jvm, "DirectMethodHandle", member, descriptor)
+  }
+
+  fn bound(
+    jvm: &mut JVM,
+    species: &str,
+    descriptor: &str,
+    form: Type,
+    mut fields: Vec<(&str, Type)>,
+  ) -> Type {
+    let handle_type = method_type(jvm, descriptor);
+
+    fields.push(("type", handle_type));
+    fields.push(("form", form));
+
+    object(
+      jvm,
+      &format!("java/lang/invoke/BoundMethodHandle$Species_{}", species),
+      fields,
+    )
+  }
+
+  fn function(jvm: &mut JVM, member: Type) -> Type {
+    object(
+      jvm,
+      "java/lang/invoke/LambdaForm$NamedFunction",
+      vec![("member", member)],
+    )
+  }
+
+  // MethodHandle.invokeBasic of a basic type, as in MethodHandles.basicInvoker
+  fn invoker(jvm: &mut JVM, descriptor: &str) -> Type {
+    let member = method(
+      jvm,
+      "java/lang/invoke/MethodHandle",
+      "invokeBasic",
+      descriptor,
+      REF_INVOKE_VIRTUAL,
+    );
+
+    function(jvm, member)
+  }
+
+  // MethodHandle.invoke, converting the arguments and the result with asType
+  fn generic_invoker(jvm: &mut JVM, descriptor: &str) -> Type {
+    let member = method(
+      jvm,
+      "java/lang/invoke/MethodHandle",
+      "invoke",
+      descriptor,
+      REF_INVOKE_VIRTUAL,
+    );
+
+    function(jvm, member)
+  }
+
+  // MethodHandle.asVarargsCollector(array_type) of `target`
+  fn varargs(jvm: &mut JVM, target: Type, array_type: Type, descriptor: &str) -> Type {
+    let handle_type = method_type(jvm, descriptor);
+
+    object(
+      jvm,
+      "java/lang/invoke/MethodHandleImpl$AsVarargsCollector",
+      vec![
+        ("target", target),
+        ("arrayType", array_type),
+        ("type", handle_type),
+      ],
+    )
+  }
+
+  // getter of a bound argument, as in BoundMethodHandle.SpeciesData.getterFunction
+  fn getter(jvm: &mut JVM, species: &str, name: &str, field_type: &str) -> Type {
+    let member = field(
+      jvm,
+      &format!("java/lang/invoke/BoundMethodHandle$Species_{}", species),
+      name,
+      field_type,
+      REF_GET_FIELD,
+    );
+
+    function(jvm, member)
+  }
+
+  fn static_function(jvm: &mut JVM, classname: &str, name: &str, descriptor: &str) -> Type {
+    let member = method(jvm, classname, name, descriptor, REF_INVOKE_STATIC);
+
+    function(jvm, member)
+  }
+
+  /// Names of a LambdaForm under construction, the first ones are its parameters
+  struct Form {
+    arity: usize,
+    names: Vec<Type>,
+  }
+
+  impl Form {
+    fn new(jvm: &mut JVM, arity: usize) -> Form {
+      let mut form = Form {
+        arity,
+        names: vec![],
+      };
+
+      for _ in 0..arity {
+        form.add(jvm, Type::Null, vec![]);
+      }
+
+      form
+    }
+
+    fn param(&self, index: usize) -> Type {
+      self.names[index]
+    }
+
+    fn add(&mut self, jvm: &mut JVM, function: Type, arguments: Vec<Type>) -> Type {
+      let arguments = array(jvm, "java/lang/Object", arguments);
+
+      let name = object(
+        jvm,
+        "java/lang/invoke/LambdaForm$Name",
+        vec![
+          ("index", Type::Short(self.names.len() as i16)),
+          ("function", function),
+          ("arguments", arguments),
+        ],
+      );
+
+      self.names.push(name);
+
+      name
+    }
+
+    // the last name is the result
+    fn build(self, jvm: &mut JVM) -> Type {
+      let result = self.names.len() as i32 - 1;
+      let names = array(jvm, "java/lang/invoke/LambdaForm$Name", self.names);
+
+      object(
+        jvm,
+        "java/lang/invoke/LambdaForm",
+        vec![
+          ("arity", Type::Integer(self.arity as i32)),
+          ("result", Type::Integer(result)),
+          ("names", names),
+        ],
+      )
+    }
+  }
+
+  const EXACT_I: &str = "(Ljava/lang/invoke/MethodHandle;I)I";
+  const EXACT_II: &str = "(Ljava/lang/invoke/MethodHandle;II)I";
+  const EXACT_S: &str = "(Ljava/lang/invoke/MethodHandle;Ljava/lang/String;)I";
+  const EXACT_STRING: &str =
+    "(Ljava/lang/invoke/MethodHandle;Ljava/lang/String;)Ljava/lang/String;";
+
+  #[test]
+
+  fn test_methodhandle_direct() {
+    let mut jvm = mock();
+
+    let handle = direct_static(&mut jvm, "add", "(II)I");
+    let ret = call(
+      &mut jvm,
+      CLASS,
+      "exactII",
+      EXACT_II,
+      vec![handle, Type::Integer(40), Type::Integer(2)],
+    );
+    assert_eq!(ret.unwrap(), Type::Integer(42));
+
+    // category 2 arguments
+    let handle = direct_static(&mut jvm, "twice", "(J)J");
+    let ret = call(
+      &mut jvm,
+      CLASS,
+      "exactJ",
+      "(Ljava/lang/invoke/MethodHandle;J)J",
+      vec![handle, Type::Long(1 << 40), Type::Long(1 << 40)],
+    );
+    assert_eq!(ret.unwrap(), Type::Long(1 << 41));
+
+    // virtual methods dispatch on the receiver, special ones do not
+    let counter = object(&mut jvm, "TestMethodHandle$Counter", vec![]);
+    jvm
+      .heap
+      .get_obj_instance_mut(counter.as_ref().unwrap())
+      .unwrap()
+      .put_field("value", Type::Integer(1))
+      .unwrap();
+
+    let descriptor = "(Ljava/lang/invoke/MethodHandle;LTestMethodHandle;)I";
+
+    let member = method(&mut jvm, CLASS, "getValue", "()I", REF_INVOKE_VIRTUAL);
+    let handle = direct(
+      &mut jvm,
+      "DirectMethodHandle",
+      member,
+      "(LTestMethodHandle;)I",
+    );
+    let ret = call(
+      &mut jvm,
+      CLASS,
+      "exactReceiver",
+      descriptor,
+      vec![handle, counter],
+    );
+    assert_eq!(ret.unwrap(), Type::Integer(101));
+
+    let member = method(&mut jvm, CLASS, "getValue", "()I", REF_INVOKE_SPECIAL);
+    let handle = direct(
+      &mut jvm,
+      "DirectMethodHandle$Special",
+      member,
+      "(LTestMethodHandle;)I",
+    );
+    let ret = call(
+      &mut jvm,
+      CLASS,
+      "exactReceiver",
+      descriptor,
+      vec![handle, counter],
+    );
+    assert_eq!(ret.unwrap(), Type::Integer(1));
+
+    let err = call(
+      &mut jvm,
+      CLASS,
+      "exactReceiver",
+      descriptor,
+      vec![handle, Type::Null],
+    )
+    .unwrap_err();
+    assert!(matches!(java_exception(err), JavaException::NullPointer));
+
+    // the receiver of interface methods is checked
+    let shape = "TestMethodHandle$Shape";
+    let member = method(&mut jvm, shape, "sides", "()I", REF_INVOKE_INTERFACE);
+    let handle = direct(
+      &mut jvm,
+      "DirectMethodHandle$Interface",
+      member,
+      "(LTestMethodHandle$Shape;)I",
+    );
+    let refc = class(&mut jvm, shape);
+    jvm
+      .heap
+      .get_obj_instance_mut(handle.as_ref().unwrap())
+      .unwrap()
+      .put_field("refc", refc)
+      .unwrap();
+
+    let descriptor = "(Ljava/lang/invoke/MethodHandle;LTestMethodHandle$Shape;)I";
+    let square = object(&mut jvm, "TestMethodHandle$Square", vec![]);
+    let ret = call(
+      &mut jvm,
+      CLASS,
+      "exactShape",
+      descriptor,
+      vec![handle, square],
+    );
+    assert_eq!(ret.unwrap(), Type::Integer(4));
+
+    let err = call(
+      &mut jvm,
+      CLASS,
+      "exactShape",
+      descriptor,
+      vec![handle, counter],
+    )
+    .unwrap_err();
+    assert!(matches!(
+      java_exception(err),
+      JavaException::ClassCastException(_)
+    ));
+
+    // constructors
+    let member = method(&mut jvm, CLASS, "<init>", "(I)V", REF_NEW_INVOKE_SPECIAL);
+    let handle = direct(
+      &mut jvm,
+      "DirectMethodHandle$Constructor",
+      member,
+      "(I)LTestMethodHandle;",
+    );
+    let ret = call(
+      &mut jvm,
+      CLASS,
+      "exactNew",
+      EXACT_I,
+      vec![handle, Type::Integer(7)],
+    );
+    assert_eq!(ret.unwrap(), Type::Integer(7));
+
+    // invokeExact checks the type of the call site
+    let handle = direct_static(&mut jvm, "add", "(II)I");
+    let err = call(
+      &mut jvm,
+      CLASS,
+      "exactI",
+      EXACT_I,
+      vec![handle, Type::Integer(1)],
+    )
+    .unwrap_err();
+    assert!(matches!(
+      java_exception(err),
+      JavaException::WrongMethodTypeException(_)
+    ));
+
+    let err = call(
+      &mut jvm,
+      CLASS,
+      "exactI",
+      EXACT_I,
+      vec![Type::Null, Type::Integer(1)],
+    )
+    .unwrap_err();
+    assert!(matches!(java_exception(err), JavaException::NullPointer));
+  }
+
+  #[test]
+
+  fn test_methodhandle_fields() {
+    let mut jvm = mock();
+
+    let t = object(&mut jvm, CLASS, vec![("value", Type::Integer(5))]);
+
+    let member = field(&mut jvm, CLASS, "value", "I", REF_GET_FIELD);
+    let getter = direct(
+      &mut jvm,
+      "DirectMethodHandle$Accessor",
+      member,
+      "(LTestMethodHandle;)I",
+    );
+    let member = field(&mut jvm, CLASS, "value", "I", REF_PUT_FIELD);
+    let setter = direct(
+      &mut jvm,
+      "DirectMethodHandle$Accessor",
+      member,
+      "(LTestMethodHandle;I)V",
+    );
+
+    let ret = call(
+      &mut jvm,
+      CLASS,
+      "exactReceiver",
+      "(Ljava/lang/invoke/MethodHandle;LTestMethodHandle;)I",
+      vec![getter, t],
+    );
+    assert_eq!(ret.unwrap(), Type::Integer(5));
+
+    let ret = call(
+      &mut jvm,
+      CLASS,
+      "exactPut",
+      "(Ljava/lang/invoke/MethodHandle;LTestMethodHandle;I)I",
+      vec![setter, t, Type::Integer(9)],
+    );
+    assert_eq!(ret.unwrap(), Type::Integer(9));
+
+    // static fields initialize their class
+    let member = field(
+      &mut jvm,
+      CLASS,
+      "label",
+      "Ljava/lang/String;",
+      REF_PUT_STATIC,
+    );
+    let setter = direct(
+      &mut jvm,
+      "DirectMethodHandle$StaticAccessor",
+      member,
+      "(Ljava/lang/String;)V",
+    );
+    let value = string(&mut jvm, "set");
+    assert_eq!(
+      call_string(
+        &mut jvm,
+        CLASS,
+        "exactRecord",
+        "(Ljava/lang/invoke/MethodHandle;Ljava/lang/String;)Ljava/lang/String;",
+        vec![setter, value]
+      ),
+      "set"
+    );
+
+    let member = field(
+      &mut jvm,
+      CLASS,
+      "label",
+      "Ljava/lang/String;",
+      REF_GET_STATIC,
+    );
+    let getter = direct(
+      &mut jvm,
+      "DirectMethodHandle$StaticAccessor",
+      member,
+      "()Ljava/lang/String;",
+    );
+    assert_eq!(
+      call_string(
+        &mut jvm,
+        CLASS,
+        "exactLabel",
+        "(Ljava/lang/invoke/MethodHandle;)Ljava/lang/String;",
+        vec![getter]
+      ),
+      "set"
+    );
+  }
+
+  #[test]
+
+  fn test_methodhandle_bound_arguments() {
+    let mut jvm = mock();
+
+    // MethodHandles.insertArguments(concat, 0, "Hello, ")
+    let target = direct_static(
+      &mut jvm,
+      "concat",
+      "(Ljava/lang/String;Ljava/lang/String;)Ljava/lang/String;",
+    );
+    let prefix = string(&mut jvm, "Hello, ");
+
+    let mut form = Form::new(&mut jvm, 2);
+    let function = getter(&mut jvm, "LL", "argL0", "Ljava/lang/Object;");
+    let t = form.add(&mut jvm, function, vec![form.param(0)]);
+    let function = getter(&mut jvm, "LL", "argL1", "Ljava/lang/Object;");
+    let v = form.add(&mut jvm, function, vec![form.param(0)]);
+    let function = invoker(
+      &mut jvm,
+      "(Ljava/lang/Object;Ljava/lang/Object;)Ljava/lang/Object;",
+    );
+    form.add(&mut jvm, function, vec![t, v, form.param(1)]);
+    let form = form.build(&mut jvm);
+
+    let handle = bound(
+      &mut jvm,
+      "LL",
+      "(Ljava/lang/String;)Ljava/lang/String;",
+      form,
+      vec![("argL0", target), ("argL1", prefix)],
+    );
+    let world = string(&mut jvm, "World");
+    assert_eq!(
+      call_string(
+        &mut jvm,
+        CLASS,
+        "exactString",
+        EXACT_STRING,
+        vec![handle, world]
+      ),
+      "Hello, World"
+    );
+
+    // MethodHandles.insertArguments(add, 0, 40), the int is bound as an int
+    let target = direct_static(&mut jvm, "add", "(II)I");
+
+    let mut form = Form::new(&mut jvm, 2);
+    let function = getter(&mut jvm, "IL", "argL1", "Ljava/lang/Object;");
+    let t = form.add(&mut jvm, function, vec![form.param(0)]);
+    let function = getter(&mut jvm, "IL", "argI0", "I");
+    let v = form.add(&mut jvm, function, vec![form.param(0)]);
+    let function = invoker(&mut jvm, "(II)I");
+    form.add(&mut jvm, function, vec![t, v, form.param(1)]);
+    let form = form.build(&mut jvm);
+
+    let handle = bound(
+      &mut jvm,
+      "IL",
+      "(I)I",
+      form,
+      vec![("argI0", Type::Integer(40)), ("argL1", target)],
+    );
+    let ret = call(
+      &mut jvm,
+      CLASS,
+      "exactI",
+      EXACT_I,
+      vec![handle, Type::Integer(2)],
+    );
+    assert_eq!(ret.unwrap(), Type::Integer(42));
+
+    // MethodHandles.dropArguments(negate, 0, int.class)
+    let target = direct_static(&mut jvm, "negate", "(I)I");
+
+    let mut form = Form::new(&mut jvm, 3);
+    let function = getter(&mut jvm, "L", "argL0", "Ljava/lang/Object;");
+    let t = form.add(&mut jvm, function, vec![form.param(0)]);
+    let function = invoker(&mut jvm, "(I)I");
+    form.add(&mut jvm, function, vec![t, form.param(2)]);
+    let form = form.build(&mut jvm);
+
+    let handle = bound(&mut jvm, "L", "(II)I", form, vec![("argL0", target)]);
+    let ret = call(
+      &mut jvm,
+      CLASS,
+      "exactII",
+      EXACT_II,
+      vec![handle, Type::Integer(1), Type::Integer(5)],
+    );
+    assert_eq!(ret.unwrap(), Type::Integer(-5));
+  }
+
+  // the target and the filter (or the combiner) of filterArguments, filterReturnValue and
+  // foldArguments are bound as argL0 and argL1
+  fn filter_form(jvm: &mut JVM, arity: usize, shape: &str) -> Type {
+    let mut form = Form::new(jvm, arity);
+    let function = getter(jvm, "LL", "argL0", "Ljava/lang/Object;");
+    let t = form.add(jvm, function, vec![form.param(0)]);
+    let function = getter(jvm, "LL", "argL1", "Ljava/lang/Object;");
+    let f = form.add(jvm, function, vec![form.param(0)]);
+
+    match shape {
+      "filterArguments" => {
+        let function = invoker(jvm, "(Ljava/lang/Object;)I");
+        let r = form.add(jvm, function, vec![f, form.param(1)]);
+        let function = invoker(jvm, "(I)I");
+        form.add(jvm, function, vec![t, r]);
+      }
+      "filterReturnValue" => {
+        let function = invoker(jvm, "(Ljava/lang/Object;)I");
+        let r = form.add(jvm, function, vec![t, form.param(1)]);
+        let function = invoker(jvm, "(I)I");
+        form.add(jvm, function, vec![f, r]);
+      }
+      _ => {
+        let function = invoker(jvm, "(I)I");
+        let v = form.add(jvm, function, vec![f, form.param(1)]);
+        let function = invoker(jvm, "(II)I");
+        form.add(jvm, function, vec![t, v, form.param(1)]);
+      }
+    }
+
+    form.build(jvm)
+  }
+
+  #[test]
+
+  fn test_methodhandle_filters() {
+    let mut jvm = mock();
+
+    let negate = direct_static(&mut jvm, "negate", "(I)I");
+    let length = direct_static(&mut jvm, "length", "(Ljava/lang/String;)I");
+    let add = direct_static(&mut jvm, "add", "(II)I");
+
+    let form = filter_form(&mut jvm, 2, "filterArguments");
+    let handle = bound(
+      &mut jvm,
+      "LL",
+      "(Ljava/lang/String;)I",
+      form,
+      vec![("argL0", negate), ("argL1", length)],
+    );
+    let four = string(&mut jvm, "four");
+    let ret = call(&mut jvm, CLASS, "exactS", EXACT_S, vec![handle, four]);
+    assert_eq!(ret.unwrap(), Type::Integer(-4));
+
+    let form = filter_form(&mut jvm, 2, "filterReturnValue");
+    let handle = bound(
+      &mut jvm,
+      "LL",
+      "(Ljava/lang/String;)I",
+      form,
+      vec![("argL0", length), ("argL1", negate)],
+    );
+    let ret = call(&mut jvm, CLASS, "exactS", EXACT_S, vec![handle, four]);
+    assert_eq!(ret.unwrap(), Type::Integer(-4));
+
+    // foldArguments(add, negate): add(negate(a), a)
+    let form = filter_form(&mut jvm, 2, "foldArguments");
+    let handle = bound(
+      &mut jvm,
+      "LL",
+      "(I)I",
+      form,
+      vec![("argL0", add), ("argL1", negate)],
+    );
+    let ret = call(
+      &mut jvm,
+      CLASS,
+      "exactI",
+      EXACT_I,
+      vec![handle, Type::Integer(5)],
+    );
+    assert_eq!(ret.unwrap(), Type::Integer(0));
+  }
+
+  #[test]
+
+  fn test_methodhandle_guard_with_test() {
+    let mut jvm = mock();
+
+    // guardWithTest(isPositive, negate, square)
+    let test = direct_static(&mut jvm, "isPositive", "(I)Z");
+    let target = direct_static(&mut jvm, "negate", "(I)I");
+    let fallback = direct_static(&mut jvm, "square", "(I)I");
+
+    let mut form = Form::new(&mut jvm, 2);
+    let mut bound_names = vec![];
+    for name in ["argL0", "argL1", "argL2"] {
+      let function = getter(&mut jvm, "LLL", name, "Ljava/lang/Object;");
+      bound_names.push(form.add(&mut jvm, function, vec![form.param(0)]));
+    }
+    let function = invoker(&mut jvm, "(I)I");
+    let c = form.add(&mut jvm, function, vec![bound_names[0], form.param(1)]);
+    let function = static_function(
+      &mut jvm,
+      METHOD_HANDLE_IMPL,
+      "selectAlternative",
+      "(ZLjava/lang/invoke/MethodHandle;Ljava/lang/invoke/MethodHandle;)Ljava/lang/invoke/MethodHandle;",
+    );
+    let s = form.add(&mut jvm, function, vec![c, bound_names[1], bound_names[2]]);
+    let function = invoker(&mut jvm, "(I)I");
+    form.add(&mut jvm, function, vec![s, form.param(1)]);
+    let form = form.build(&mut jvm);
+
+    let handle = bound(
+      &mut jvm,
+      "LLL",
+      "(I)I",
+      form,
+      vec![("argL0", test), ("argL1", target), ("argL2", fallback)],
+    );
+
+    let ret = call(
+      &mut jvm,
+      CLASS,
+      "exactI",
+      EXACT_I,
+      vec![handle, Type::Integer(3)],
+    );
+    assert_eq!(ret.unwrap(), Type::Integer(-3));
+
+    let ret = call(
+      &mut jvm,
+      CLASS,
+      "exactI",
+      EXACT_I,
+      vec![handle, Type::Integer(-3)],
+    );
+    assert_eq!(ret.unwrap(), Type::Integer(9));
+  }
+
+  // the arguments of the handles called with invokeWithArguments are boxed in an Object[] by
+  // the handle argL(n), the result is unboxed by argL(n + 1)
+  fn boxing_form(
+    jvm: &mut JVM,
+    species: &str,
+    arity: usize,
+    intrinsic: &str,
+    descriptor: &str,
+    leading: &[Type],
+  ) -> Type {
+    let mut form = Form::new(jvm, arity);
+
+    let mut bound_names = vec![];
+    for i in 0..species.len() {
+      let function = getter(jvm, species, &format!("argL{}", i), "Ljava/lang/Object;");
+      bound_names.push(form.add(jvm, function, vec![form.param(0)]));
+    }
+
+    let collect = bound_names[species.len() - 2];
+    let unbox = bound_names[species.len() - 1];
+
+    let function = invoker(jvm, "(Ljava/lang/Object;)Ljava/lang/Object;");
+    let boxed = form.add(jvm, function, vec![collect, form.param(arity - 1)]);
+
+    let mut args = leading.to_vec();
+    args.extend(&bound_names[..species.len() - 2]);
+    args.push(boxed);
+
+    let function = static_function(jvm, METHOD_HANDLE_IMPL, intrinsic, descriptor);
+    let result = form.add(jvm, function, args);
+    let function = invoker(jvm, "(Ljava/lang/Object;)Ljava/lang/Object;");
+    form.add(jvm, function, vec![unbox, result]);
+
+    form.build(jvm)
+  }
+
+  fn boxing_handles(jvm: &mut JVM) -> (Type, Type) {
+    let pack = direct_static(jvm, "pack", "(Ljava/lang/String;)[Ljava/lang/Object;");
+    let unpack = direct_static(jvm, "unpack", "(Ljava/lang/Object;)Ljava/lang/String;");
+
+    (pack, unpack)
+  }
+
+  #[test]
+
+  fn test_methodhandle_catch_exception() {
+    let mut jvm = mock();
+
+    // catchException(check, IllegalStateException.class, recover)
+    let form = boxing_form(
+      &mut jvm,
+      "LLLLL",
+      2,
+      "guardWithCatch",
+      "(Ljava/lang/invoke/MethodHandle;Ljava/lang/Class;Ljava/lang/invoke/MethodHandle;[Ljava/lang/Object;)Ljava/lang/Object;",
+      &[],
+    );
+    let target = direct_static(&mut jvm, "check", "(Ljava/lang/String;)Ljava/lang/String;");
+    let ex_type = class(&mut jvm, "java/lang/IllegalStateException");
+    let catcher = direct_static(
+      &mut jvm,
+      "recover",
+      "(Ljava/lang/IllegalStateException;Ljava/lang/String;)Ljava/lang/String;",
+    );
+    let (pack, unpack) = boxing_handles(&mut jvm);
+
+    let handle = bound(
+      &mut jvm,
+      "LLLLL",
+      "(Ljava/lang/String;)Ljava/lang/String;",
+      form,
+      vec![
+        ("argL0", target),
+        ("argL1", ex_type),
+        ("argL2", catcher),
+        ("argL3", pack),
+        ("argL4", unpack),
+      ],
+    );
+
+    let ok = string(&mut jvm, "ok");
+    assert_eq!(
+      call_string(
+        &mut jvm,
+        CLASS,
+        "exactString",
+        EXACT_STRING,
+        vec![handle, ok]
+      ),
+      "ok"
+    );
+
+    let empty = string(&mut jvm, "");
+    assert_eq!(
+      call_string(
+        &mut jvm,
+        CLASS,
+        "exactString",
+        EXACT_STRING,
+        vec![handle, empty]
+      ),
+      "recovered"
+    );
+
+    // other exceptions are not caught
+    let unknown = string(&mut jvm, "?");
+    let err = call(
+      &mut jvm,
+      CLASS,
+      "exactString",
+      EXACT_STRING,
+      vec![handle, unknown],
+    )
+    .unwrap_err();
+    assert!(matches!(
+      java_exception(err),
+      JavaException::UnsupportedOperationException(_)
+    ));
+  }
+
+  #[test]
+
+  fn test_methodhandle_try_finally() {
+    let mut jvm = mock();
+
+    // tryFinally(check, cleanup)
+    let form = boxing_form(
+      &mut jvm,
+      "LLLL",
+      2,
+      "tryFinally",
+      "(Ljava/lang/invoke/MethodHandle;Ljava/lang/invoke/MethodHandle;[Ljava/lang/Object;)Ljava/lang/Object;",
+      &[],
+    );
+    let target = direct_static(&mut jvm, "check", "(Ljava/lang/String;)Ljava/lang/String;");
+    let cleanup = direct_static(
+      &mut jvm,
+      "cleanup",
+      "(Ljava/lang/Throwable;Ljava/lang/String;Ljava/lang/String;)Ljava/lang/String;",
+    );
+    let (pack, unpack) = boxing_handles(&mut jvm);
+
+    let handle = bound(
+      &mut jvm,
+      "LLLL",
+      "(Ljava/lang/String;)Ljava/lang/String;",
+      form,
+      vec![
+        ("argL0", target),
+        ("argL1", cleanup),
+        ("argL2", pack),
+        ("argL3", unpack),
+      ],
+    );
+
+    let ok = string(&mut jvm, "ok");
+    assert_eq!(
+      call_string(
+        &mut jvm,
+        CLASS,
+        "exactString",
+        EXACT_STRING,
+        vec![handle, ok]
+      ),
+      "ok!"
+    );
+    let label = jvm.class_loader.get_static_field(CLASS, "label").unwrap();
+    assert_eq!(
+      jvm.heap.get_string(label.as_ref().unwrap()).unwrap(),
+      "done"
+    );
+
+    // the exception is rethrown after the cleanup
+    let empty = string(&mut jvm, "");
+    let err = call(
+      &mut jvm,
+      CLASS,
+      "exactString",
+      EXACT_STRING,
+      vec![handle, empty],
+    )
+    .unwrap_err();
+    assert!(matches!(
+      java_exception(err),
+      JavaException::IllegalStateException(_)
+    ));
+    let label = jvm.class_loader.get_static_field(CLASS, "label").unwrap();
+    assert_eq!(
+      jvm.heap.get_string(label.as_ref().unwrap()).unwrap(),
+      "failed"
+    );
+  }
+
+  #[test]
+
+  fn test_methodhandle_table_switch() {
+    let mut jvm = mock();
+
+    // tableSwitch(second, first, second)
+    let mut form = Form::new(&mut jvm, 3);
+    let mut bound_names = vec![];
+    for name in ["argL0", "argL1", "argL2", "argL3"] {
+      let function = getter(&mut jvm, "LLLL", name, "Ljava/lang/Object;");
+      bound_names.push(form.add(&mut jvm, function, vec![form.param(0)]));
+    }
+    let function = invoker(&mut jvm, "(Ljava/lang/Object;)Ljava/lang/Object;");
+    let boxed = form.add(&mut jvm, function, vec![bound_names[2], form.param(2)]);
+    let function = static_function(
+      &mut jvm,
+      METHOD_HANDLE_IMPL,
+      "tableSwitch",
+      "(ILjava/lang/invoke/MethodHandle;Ljava/lang/invoke/MethodHandleImpl$CasesHolder;[Ljava/lang/Object;)Ljava/lang/Object;",
+    );
+    let result = form.add(
+      &mut jvm,
+      function,
+      vec![form.param(1), bound_names[0], bound_names[1], boxed],
+    );
+    let function = invoker(&mut jvm, "(Ljava/lang/Object;)Ljava/lang/Object;");
+    form.add(&mut jvm, function, vec![bound_names[3], result]);
+    let form = form.build(&mut jvm);
+
+    let descriptor = "(Ljava/lang/String;)Ljava/lang/String;";
+    let first = direct_static(&mut jvm, "first", descriptor);
+    let second = direct_static(&mut jvm, "second", descriptor);
+    let cases = array(
+      &mut jvm,
+      "java/lang/invoke/MethodHandle",
+      vec![first, second],
+    );
+    let holder = object(
+      &mut jvm,
+      "java/lang/invoke/MethodHandleImpl$CasesHolder",
+      vec![("cases", cases)],
+    );
+    let (pack, unpack) = boxing_handles(&mut jvm);
+
+    let handle = bound(
+      &mut jvm,
+      "LLLL",
+      "(ILjava/lang/String;)Ljava/lang/String;",
+      form,
+      vec![
+        ("argL0", second),
+        ("argL1", holder),
+        ("argL2", pack),
+        ("argL3", unpack),
+      ],
+    );
+
+    let descriptor = "(Ljava/lang/invoke/MethodHandle;ILjava/lang/String;)Ljava/lang/String;";
+    let mut results = vec![];
+    for input in [0, 1, 7, -1] {
+      let s = string(&mut jvm, "case");
+      results.push(call_string(
+        &mut jvm,
+        CLASS,
+        "exactSwitch",
+        descriptor,
+        vec![handle, Type::Integer(input), s],
+      ));
+    }
+    assert_eq!(
+      results,
+      ["first case", "second case", "second case", "second case"]
+    );
+  }
+
+  #[test]
+
+  fn test_methodhandle_loop() {
+    let mut jvm = mock();
+
+    // countedLoop-like sum of 1..n, the loop variables are (i, sum)
+    let mut form = Form::new(&mut jvm, 2);
+    let mut bound_names = vec![];
+    for name in ["argL0", "argL1", "argL2"] {
+      let function = getter(&mut jvm, "LLL", name, "Ljava/lang/Object;");
+      bound_names.push(form.add(&mut jvm, function, vec![form.param(0)]));
+    }
+    let function = generic_invoker(&mut jvm, "(I)[Ljava/lang/Object;");
+    let boxed = form.add(&mut jvm, function, vec![bound_names[1], form.param(1)]);
+    let function = static_function(
+      &mut jvm,
+      METHOD_HANDLE_IMPL,
+      "loop",
+      "([Ljava/lang/invoke/LambdaForm$BasicType;Ljava/lang/invoke/MethodHandleImpl$LoopClauses;[Ljava/lang/Object;)Ljava/lang/Object;",
+    );
+    let result = form.add(&mut jvm, function, vec![Type::Null, bound_names[0], boxed]);
+    let function = generic_invoker(&mut jvm, "(Ljava/lang/Object;)I");
+    form.add(&mut jvm, function, vec![bound_names[2], result]);
+    let form = form.build(&mut jvm);
+
+    let zero = direct_static(&mut jvm, "zero", "(I)I");
+    let mut clauses = vec![array(
+      &mut jvm,
+      "java/lang/invoke/MethodHandle",
+      vec![zero, zero],
+    )];
+    for names in [
+      ["next", "accumulate"],
+      ["notAfter", "always"],
+      ["result", "result"],
+    ] {
+      let mut handles = vec![];
+      for name in names {
+        let descriptor = match name {
+          "notAfter" | "always" => "(III)Z",
+          _ => "(III)I",
+        };
+        handles.push(direct_static(&mut jvm, name, descriptor));
+      }
+      clauses.push(array(&mut jvm, "java/lang/invoke/MethodHandle", handles));
+    }
+    let clauses = array(&mut jvm, "[Ljava/lang/invoke/MethodHandle;", clauses);
+    let clause_data = object(
+      &mut jvm,
+      "java/lang/invoke/MethodHandleImpl$LoopClauses",
+      vec![("clauses", clauses)],
+    );
+
+    // the argument is boxed by a varargs collector, the result is unboxed by `invoke`
+    let target = direct_static(
+      &mut jvm,
+      "arguments",
+      "([Ljava/lang/Object;)[Ljava/lang/Object;",
+    );
+    let array_type = class(&mut jvm, "[Ljava/lang/Object;");
+    let boxer = varargs(
+      &mut jvm,
+      target,
+      array_type,
+      "([Ljava/lang/Object;)[Ljava/lang/Object;",
+    );
+    let unboxer = direct_static(&mut jvm, "negate", "(I)I");
+
+    let handle = bound(
+      &mut jvm,
+      "LLL",
+      "(I)I",
+      form,
+      vec![("argL0", clause_data), ("argL1", boxer), ("argL2", unboxer)],
+    );
+
+    let ret = call(
+      &mut jvm,
+      CLASS,
+      "exactI",
+      EXACT_I,
+      vec![handle, Type::Integer(4)],
+    );
+    assert_eq!(ret.unwrap(), Type::Integer(-10));
+  }
+
+  #[test]
+
+  fn test_methodhandle_spread_and_collect() {
+    let mut jvm = mock();
+
+    // asSpreader(add, int[].class, 2)
+    let mut form = Form::new(&mut jvm, 2);
+    let function = getter(&mut jvm, "L", "argL0", "Ljava/lang/Object;");
+    let t = form.add(&mut jvm, function, vec![form.param(0)]);
+    let function = static_function(
+      &mut jvm,
+      METHOD_HANDLE_IMPL,
+      "checkSpreadArgument",
+      "(Ljava/lang/Object;I)V",
+    );
+    let two = integer(&mut jvm, 2);
+    form.add(&mut jvm, function, vec![form.param(1), two]);
+    let mut elements = vec![];
+    for i in 0..2 {
+      let function = static_function(
+        &mut jvm,
+        "java/lang/invoke/MethodHandleImpl$ArrayAccessor",
+        "getElementI",
+        "([II)I",
+      );
+      let index = integer(&mut jvm, i);
+      elements.push(form.add(&mut jvm, function, vec![form.param(1), index]));
+    }
+    let function = invoker(&mut jvm, "(II)I");
+    form.add(&mut jvm, function, vec![t, elements[0], elements[1]]);
+    let form = form.build(&mut jvm);
+
+    let add = direct_static(&mut jvm, "add", "(II)I");
+    let handle = bound(&mut jvm, "L", "([I)I", form, vec![("argL0", add)]);
+
+    let descriptor = "(Ljava/lang/invoke/MethodHandle;[I)I";
+    let values = array(&mut jvm, "I", vec![Type::Integer(40), Type::Integer(2)]);
+    let ret = call(
+      &mut jvm,
+      CLASS,
+      "exactArray",
+      descriptor,
+      vec![handle, values],
+    );
+    assert_eq!(ret.unwrap(), Type::Integer(42));
+
+    let values = array(&mut jvm, "I", vec![Type::Integer(40)]);
+    let err = call(
+      &mut jvm,
+      CLASS,
+      "exactArray",
+      descriptor,
+      vec![handle, values],
+    )
+    .unwrap_err();
+    assert!(matches!(
+      java_exception(err),
+      JavaException::IllegalArgumentException(_)
+    ));
+
+    // asCollector(sum, int[].class, 2)
+    let mut form = Form::new(&mut jvm, 3);
+    let function = getter(&mut jvm, "LL", "argL0", "Ljava/lang/Object;");
+    let t = form.add(&mut jvm, function, vec![form.param(0)]);
+    let function = getter(&mut jvm, "LL", "argL1", "Ljava/lang/Object;");
+    let c = form.add(&mut jvm, function, vec![form.param(0)]);
+    let function = invoker(&mut jvm, "(II)Ljava/lang/Object;");
+    let a = form.add(&mut jvm, function, vec![c, form.param(1), form.param(2)]);
+    let function = invoker(&mut jvm, "(Ljava/lang/Object;)I");
+    form.add(&mut jvm, function, vec![t, a]);
+    let form = form.build(&mut jvm);
+
+    let sum = direct_static(&mut jvm, "sum", "([I)I");
+    let ints = direct_static(&mut jvm, "ints", "(II)[I");
+    let handle = bound(
+      &mut jvm,
+      "LL",
+      "(II)I",
+      form,
+      vec![("argL0", sum), ("argL1", ints)],
+    );
+    let ret = call(
+      &mut jvm,
+      CLASS,
+      "exactII",
+      EXACT_II,
+      vec![handle, Type::Integer(3), Type::Integer(4)],
+    );
+    assert_eq!(ret.unwrap(), Type::Integer(7));
+  }
+
+  #[test]
+
+  fn test_methodhandle_varargs() {
+    let mut jvm = mock();
+
+    let sum = direct_static(&mut jvm, "sum", "([I)I");
+    let array_type = class(&mut jvm, "[I");
+    let handle = varargs(&mut jvm, sum, array_type, "([I)I");
+
+    let ret = call(
+      &mut jvm,
+      CLASS,
+      "invokeVarargs",
+      "(Ljava/lang/invoke/MethodHandle;III)I",
+      vec![handle, Type::Integer(1), Type::Integer(2), Type::Integer(3)],
+    );
+    assert_eq!(ret.unwrap(), Type::Integer(6));
+
+    // an array is passed as it is
+    let values = array(&mut jvm, "I", vec![Type::Integer(4), Type::Integer(5)]);
+    let ret = call(
+      &mut jvm,
+      CLASS,
+      "invokeArray",
+      "(Ljava/lang/invoke/MethodHandle;[I)I",
+      vec![handle, values],
+    );
+    assert_eq!(ret.unwrap(), Type::Integer(9));
+  }
+
+  #[test]
+
+  fn test_methodhandle_as_type() {
+    let mut jvm = mock();
+
+    let negate = direct_static(&mut jvm, "negate", "(I)I");
+    let descriptor = "(Ljava/lang/invoke/MethodHandle;Ljava/lang/Object;)Ljava/lang/Object;";
+
+    // boxing and unboxing
+    let five = integer(&mut jvm, 5);
+    let ret = call(
+      &mut jvm,
+      CLASS,
+      "invokeObject",
+      descriptor,
+      vec![negate, five],
+    )
+    .unwrap();
+    let boxed = jvm.heap.get_boxed_value(ret.as_ref().unwrap()).unwrap();
+    assert_eq!(boxed, Some(("I", Type::Integer(-5))));
+
+    let s = string(&mut jvm, "five");
+    let err = call(&mut jvm, CLASS, "invokeObject", descriptor, vec![negate, s]).unwrap_err();
+    assert!(matches!(
+      java_exception(err),
+      JavaException::ClassCastException(_)
+    ));
+
+    let err = call(
+      &mut jvm,
+      CLASS,
+      "invokeObject",
+      descriptor,
+      vec![negate, Type::Null],
+    )
+    .unwrap_err();
+    assert!(matches!(java_exception(err), JavaException::NullPointer));
+
+    // widening of the arguments and of the result
+    let ret = call(
+      &mut jvm,
+      CLASS,
+      "invokeLong",
+      "(Ljava/lang/invoke/MethodHandle;I)J",
+      vec![negate, Type::Integer(3)],
+    );
+    assert_eq!(ret.unwrap(), Type::Long(-3));
+
+    let add = direct_static(&mut jvm, "add", "(II)I");
+    let ret = call(
+      &mut jvm,
+      CLASS,
+      "invokeShorts",
+      "(Ljava/lang/invoke/MethodHandle;SS)I",
+      vec![add, Type::Integer(2), Type::Integer(3)],
+    );
+    assert_eq!(ret.unwrap(), Type::Integer(5));
+
+    // but not narrowing
+    let err = call(
+      &mut jvm,
+      CLASS,
+      "invokeNarrowing",
+      "(Ljava/lang/invoke/MethodHandle;J)I",
+      vec![negate, Type::Long(1), Type::Long(1)],
+    )
+    .unwrap_err();
+    assert!(matches!(
+      java_exception(err),
+      JavaException::WrongMethodTypeException(_)
+    ));
+
+    // a void handle returns null
+    let record = direct_static(&mut jvm, "record", "(Ljava/lang/String;)V");
+    let s = string(&mut jvm, "recorded");
+    let ret = call(
+      &mut jvm,
+      CLASS,
+      "invokeRecord",
+      "(Ljava/lang/invoke/MethodHandle;Ljava/lang/String;)Ljava/lang/Object;",
+      vec![record, s],
+    );
+    assert_eq!(ret.unwrap(), Type::Null);
+    let label = jvm.class_loader.get_static_field(CLASS, "label").unwrap();
+    assert_eq!(
+      jvm.heap.get_string(label.as_ref().unwrap()).unwrap(),
+      "recorded"
+    );
+  }
+}
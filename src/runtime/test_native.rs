@@ -1,3 +1,127 @@
 #[cfg(test)]
+mod tests {
 
-mod tests {}
+  use std::{cell::RefCell, rc::Rc};
+
+  use crate::runtime::{test_support::*, types::Type};
+
+  const CLASS: &str = "TestNative";
+
+  #[test]
+
+  fn test_register_native() {
+    let mut jvm = mock();
+
+    jvm.register_native(CLASS, "twice", "(I)I", |_, args| {
+      Ok(Type::Integer(args[0].as_integer()? * 2))
+    });
+    assert_eq!(
+      call(&mut jvm, CLASS, "testTwice", "()I", vec![]).unwrap(),
+      Type::Integer(42)
+    );
+
+    // the object comes before the arguments
+    jvm.register_native(CLASS, "scale", "(J)J", |jvm, args| {
+      let base = jvm
+        .heap
+        .get_obj_instance(args[0].as_ref()?)?
+        .get_field("base")?
+        .as_integer()?;
+
+      Ok(Type::Long(base as i64 * args[1].as_long()?))
+    });
+    assert_eq!(
+      call(&mut jvm, CLASS, "testScale", "()J", vec![]).unwrap(),
+      Type::Long(42)
+    );
+
+    jvm.register_native(
+      CLASS,
+      "greet",
+      "(Ljava/lang/String;)Ljava/lang/String;",
+      |jvm, args| {
+        let name = jvm.heap.get_string(args[0].as_ref()?)?;
+
+        jvm
+          .heap
+          .alloc_string(&mut jvm.class_loader, &format!("hello {}", name))
+      },
+    );
+    let greeting = call(&mut jvm, CLASS, "testGreet", "()Ljava/lang/String;", vec![]).unwrap();
+    assert_eq!(
+      jvm.heap.get_string(greeting.as_ref().unwrap()).unwrap(),
+      "hello native"
+    );
+
+    let recorded = Rc::new(RefCell::new(vec![]));
+    let values = recorded.clone();
+    jvm.register_native(CLASS, "record", "(D)V", move |_, args| {
+      values.borrow_mut().push(args[0].as_double()?);
+
+      Ok(Type::None)
+    });
+    assert_eq!(
+      call(&mut jvm, CLASS, "testRecord", "()V", vec![]).unwrap(),
+      Type::None
+    );
+    assert_eq!(*recorded.borrow(), vec![1.5, 2.5]);
+  }
+
+  #[test]
+
+  fn test_unsatisfied_link() {
+    let mut jvm = mock();
+
+    let err = jvm
+      .call_native(CLASS, "missing", "(ILjava/lang/String;)I")
+      .unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "UnsatisfiedLinkError ('int TestNative.missing(int, java.lang.String)')"
+    );
+
+    assert_eq!(
+      call(&mut jvm, CLASS, "testMissing", "()Z", vec![]).unwrap(),
+      Type::Integer(1)
+    );
+  }
+
+  #[test]
+
+  fn test_natives_bound_to_their_class() {
+    let mut jvm = mock();
+
+    // the natives of the JDK with the same names are not called
+    for (name, descriptor, signature) in [
+      ("initialize", "()V", "'void TestNative.initialize()'"),
+      (
+        "floatToRawIntBits",
+        "(F)I",
+        "'int TestNative.floatToRawIntBits(float)'",
+      ),
+    ] {
+      let err = jvm.call_native(CLASS, name, descriptor).unwrap_err();
+      assert!(err.to_string().contains(signature), "{}", err);
+    }
+
+    // nor the `registerNatives` of the classes of the JDK, the one of a class comes from its
+    // JNI library
+    let err = jvm
+      .call_native(CLASS, "registerNatives", "()V")
+      .unwrap_err();
+    assert!(err.to_string().contains("UnsatisfiedLinkError"), "{}", err);
+
+    assert_eq!(
+      call(&mut jvm, CLASS, "testJdkFloatBits", "()I", vec![]).unwrap(),
+      Type::Integer(0x3f80_0000)
+    );
+
+    jvm.register_native(CLASS, "floatToRawIntBits", "(F)I", |_, args| {
+      Ok(Type::Integer(-(args[0].as_float()?.to_bits() as i32)))
+    });
+    assert_eq!(
+      call(&mut jvm, CLASS, "testFloatBits", "()I", vec![]).unwrap(),
+      Type::Integer(-0x3f80_0000)
+    );
+  }
+}
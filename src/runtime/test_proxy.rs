@@ -0,0 +1,74 @@
+#[cfg(test)]
+mod tests {
+
+  use crate::runtime::{jvm::JVM, test_support::*, types::Type};
+
+  const CLASS: &str = "TestProxy";
+
+  fn mock() -> JVM {
+    let mut jvm = booted_mock();
+
+    // the module system is not booted, Proxy only checks its init level
+    jvm
+      .call_and_resolve_method(
+        "jdk/internal/misc/VM",
+        "initLevel",
+        "(I)V",
+        vec![Type::Integer(2)],
+      )
+      .unwrap();
+
+    jvm
+  }
+
+  #[test]
+
+  fn test_interface_proxy() {
+    let mut jvm = mock();
+
+    let name = string(&mut jvm, "world");
+
+    let ret = call(
+      &mut jvm,
+      CLASS,
+      "greet",
+      "(Ljava/lang/String;)Ljava/lang/String;",
+      vec![name],
+    )
+    .unwrap();
+    assert_eq!(ret, name);
+
+    assert_eq!(
+      call_string(&mut jvm, CLASS, "fail", "()Ljava/lang/String;", vec![]),
+      "checked"
+    );
+    assert_eq!(
+      call_string(&mut jvm, CLASS, "crash", "()Ljava/lang/String;", vec![]),
+      "unchecked"
+    );
+
+    let calls = call(&mut jvm, CLASS, "calls", "()I", vec![]).unwrap();
+    assert_eq!(calls.as_integer().unwrap(), 3);
+
+    let is_proxy = call(&mut jvm, CLASS, "isProxy", "()Z", vec![]).unwrap();
+    assert!(is_proxy.as_bool().unwrap());
+  }
+
+  #[test]
+
+  fn test_annotation_proxy() {
+    let mut jvm = mock();
+
+    assert_eq!(
+      call_string(&mut jvm, CLASS, "tagValue", "()Ljava/lang/String;", vec![]),
+      "hello"
+    );
+
+    // the default value of the annotation interface
+    let priority = call(&mut jvm, CLASS, "tagPriority", "()I", vec![]).unwrap();
+    assert_eq!(priority.as_integer().unwrap(), 3);
+
+    let is_tag = call(&mut jvm, CLASS, "isTagType", "()Z", vec![]).unwrap();
+    assert!(is_tag.as_bool().unwrap());
+  }
+}
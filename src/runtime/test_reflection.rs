@@ -0,0 +1,462 @@
+#[cfg(test)]
+mod tests {
+
+  use crate::{
+    class_loader::constant_pool::CpInfoInfoEnum,
+    runtime::{errors::JavaException, jvm::JVM, test_support::*, types::Type},
+  };
+
+  const CLASS: &str = "java/lang/Class";
+  const CONSTANT_POOL: &str = "jdk/internal/reflect/ConstantPool";
+
+  fn mock() -> JVM {
+    mock_with_frame("TestReflection", "_constants", "()D")
+  }
+
+  fn class_obj(jvm: &mut JVM, class_name: &str) -> Type {
+    let class_ref = jvm
+      .heap
+      .get_class_instance(&mut jvm.class_loader, class_name)
+      .unwrap()
+      .get_ref();
+
+    Type::ObjectRef(class_ref)
+  }
+
+  fn call_native(
+    jvm: &mut JVM,
+    class_name: &str,
+    name: &str,
+    type_str: &str,
+    args: Vec<Type>,
+  ) -> Type {
+    for arg in args {
+      jvm.push_stack(arg).unwrap();
+    }
+
+    jvm.call_native(class_name, name, type_str).unwrap();
+
+    jvm.pop_stack().unwrap()
+  }
+
+  fn call_class(jvm: &mut JVM, class_name: &str, name: &str, type_str: &str) -> Type {
+    let this = class_obj(jvm, class_name);
+
+    call_native(jvm, CLASS, name, type_str, vec![this])
+  }
+
+  fn elements(jvm: &JVM, array: Type) -> Vec<Type> {
+    let Type::ArrayRef(array_ref) = array else {
+      panic!("not an array {:?}", array);
+    };
+
+    jvm
+      .heap
+      .get_array_instance(array_ref)
+      .unwrap()
+      .get_elements()
+      .clone()
+  }
+
+  fn class_names(jvm: &JVM, array: Type) -> Vec<String> {
+    elements(jvm, array)
+      .into_iter()
+      .map(|class| {
+        jvm
+          .heap
+          .get_classname_from_class_obj(class.as_ref().unwrap())
+          .unwrap()
+      })
+      .collect()
+  }
+
+  #[test]
+
+  fn test_class_natives() {
+    let mut jvm = mock();
+
+    let interfaces = call_class(
+      &mut jvm,
+      "TestReflection",
+      "getInterfaces0",
+      "()[Ljava/lang/Class;",
+    );
+    assert_eq!(
+      class_names(&jvm, interfaces),
+      vec!["java/lang/Comparable", "java/io/Serializable"]
+    );
+
+    let interfaces = call_class(&mut jvm, "I", "getInterfaces0", "()[Ljava/lang/Class;");
+    assert!(class_names(&jvm, interfaces).is_empty());
+
+    let signature = call_class(
+      &mut jvm,
+      "TestReflection",
+      "getGenericSignature0",
+      "()Ljava/lang/String;",
+    );
+    assert_eq!(
+      get_string(&jvm, signature),
+      "<T:Ljava/lang/Number;>Ljava/lang/Object;Ljava/lang/Comparable<LTestReflection<TT;>;>;Ljava/io/Serializable;"
+    );
+
+    let signature = call_class(
+      &mut jvm,
+      "TestReflection$Inner",
+      "getGenericSignature0",
+      "()Ljava/lang/String;",
+    );
+    assert_eq!(signature, Type::Null);
+
+    let declared = call_class(
+      &mut jvm,
+      "TestReflection",
+      "getDeclaredClasses0",
+      "()[Ljava/lang/Class;",
+    );
+    let mut declared = class_names(&jvm, declared);
+    declared.sort();
+
+    // the anonymous class is not a member
+    assert_eq!(
+      declared,
+      vec![
+        "TestReflection$Circle",
+        "TestReflection$Inner",
+        "TestReflection$Marker",
+        "TestReflection$Shape",
+        "TestReflection$Square"
+      ]
+    );
+
+    let simple_name = "()Ljava/lang/String;";
+
+    let inner = call_class(
+      &mut jvm,
+      "TestReflection$Inner",
+      "getSimpleBinaryName0",
+      simple_name,
+    );
+    assert_eq!(get_string(&jvm, inner), "Inner");
+
+    for class_name in ["TestReflection", "TestReflection$1"] {
+      let name = call_class(&mut jvm, class_name, "getSimpleBinaryName0", simple_name);
+      assert_eq!(name, Type::Null, "{}", class_name);
+    }
+
+    let members = call_class(
+      &mut jvm,
+      "TestReflection",
+      "getNestMembers0",
+      "()[Ljava/lang/Class;",
+    );
+    let members = class_names(&jvm, members);
+    assert_eq!(members[0], "TestReflection");
+    assert!(members.contains(&"TestReflection$Inner".to_string()));
+
+    let data = std::fs::read("tests/classes/TestReflection.class").unwrap();
+
+    let version = call_class(&mut jvm, "TestReflection", "getClassFileVersion0", "()I");
+    assert_eq!(
+      version,
+      // minor << 16 | major, as in the class file
+      Type::Integer(i32::from_be_bytes(data[4..8].try_into().unwrap()))
+    );
+
+    let annotations = call_class(&mut jvm, "TestReflection", "getRawAnnotations", "()[B");
+    let annotations: Vec<u8> = elements(&jvm, annotations)
+      .into_iter()
+      .map(|b| match b {
+        Type::Byte(b) => b as u8,
+        _ => panic!("not a byte {:?}", b),
+      })
+      .collect();
+    assert!(data
+      .windows(annotations.len())
+      .any(|window| window == annotations));
+
+    let annotations = call_class(&mut jvm, "TestReflection", "getRawTypeAnnotations", "()[B");
+    assert_eq!(annotations, Type::Null);
+
+    let domain = call_class(
+      &mut jvm,
+      "TestReflection",
+      "getProtectionDomain0",
+      "()Ljava/security/ProtectionDomain;",
+    );
+    assert_eq!(domain, Type::Null);
+
+    // signers are kept on the Class object
+    let this = class_obj(&mut jvm, "TestReflection");
+    let signers = jvm
+      .heap
+      .alloc_array("java/lang/Object", vec![this], 1)
+      .unwrap();
+    jvm.push_stack(this).unwrap();
+    jvm.push_stack(signers).unwrap();
+    jvm
+      .call_native(CLASS, "setSigners", "([Ljava/lang/Object;)V")
+      .unwrap();
+
+    let copy = call_class(
+      &mut jvm,
+      "TestReflection",
+      "getSigners",
+      "()[Ljava/lang/Object;",
+    );
+    assert_ne!(copy, signers);
+    assert_eq!(elements(&jvm, copy), vec![this]);
+  }
+
+  #[test]
+
+  fn test_record_and_sealed_natives() {
+    let mut jvm = mock();
+
+    for (class_name, expected) in [
+      ("TestReflection$Circle", true),
+      ("TestReflection", false),
+      ("I", false),
+    ] {
+      let is_record = call_class(&mut jvm, class_name, "isRecord0", "()Z");
+      assert_eq!(is_record, Type::Boolean(expected), "{}", class_name);
+    }
+
+    let components_type = "()[Ljava/lang/reflect/RecordComponent;";
+
+    let components = call_class(
+      &mut jvm,
+      "TestReflection$Circle",
+      "getRecordComponents0",
+      components_type,
+    );
+    let components = elements(&jvm, components);
+    assert_eq!(components.len(), 1);
+
+    let component = jvm
+      .heap
+      .get_obj_instance(components[0].as_ref().unwrap())
+      .unwrap();
+    let name = component.get_field("name").unwrap();
+    let type_ref = component.get_field("type").unwrap().as_ref().unwrap();
+    let accessor = component.get_field("accessor").unwrap();
+    let annotations = component.get_field("annotations").unwrap();
+
+    assert_eq!(get_string(&jvm, name), "radius");
+    assert_eq!(
+      jvm.heap.get_classname_from_class_obj(type_ref).unwrap(),
+      "I"
+    );
+    assert!(matches!(accessor, Type::ObjectRef(_)));
+    assert!(matches!(annotations, Type::ArrayRef(_)));
+
+    let components = call_class(
+      &mut jvm,
+      "TestReflection$Square",
+      "getRecordComponents0",
+      components_type,
+    );
+    let component = elements(&jvm, components)[0];
+    let signature = jvm
+      .heap
+      .get_obj_instance(component.as_ref().unwrap())
+      .unwrap()
+      .get_field("signature")
+      .unwrap();
+    assert_eq!(
+      get_string(&jvm, signature),
+      "Ljava/util/List<Ljava/lang/String;>;"
+    );
+
+    let components = call_class(
+      &mut jvm,
+      "TestReflection",
+      "getRecordComponents0",
+      components_type,
+    );
+    assert_eq!(components, Type::Null);
+
+    let permitted_type = "()[Ljava/lang/Class;";
+
+    let permitted = call_class(
+      &mut jvm,
+      "TestReflection$Shape",
+      "getPermittedSubclasses0",
+      permitted_type,
+    );
+    assert_eq!(
+      class_names(&jvm, permitted),
+      vec!["TestReflection$Circle", "TestReflection$Square"]
+    );
+
+    let permitted = call_class(
+      &mut jvm,
+      "TestReflection",
+      "getPermittedSubclasses0",
+      permitted_type,
+    );
+    assert_eq!(permitted, Type::Null);
+  }
+
+  #[test]
+
+  fn test_constant_pool_natives() {
+    let mut jvm = mock();
+
+    let cp = call_class(
+      &mut jvm,
+      "TestReflection",
+      "getConstantPool",
+      "()Ljdk/internal/reflect/ConstantPool;",
+    );
+    let oop = jvm
+      .heap
+      .get_obj_instance(cp.as_ref().unwrap())
+      .unwrap()
+      .get_field("constantPoolOop")
+      .unwrap();
+
+    let cp_call = |jvm: &mut JVM, name: &str, type_str: &str, index: i32| {
+      call_native(
+        jvm,
+        CONSTANT_POOL,
+        name,
+        type_str,
+        vec![cp, oop, Type::Integer(index)],
+      )
+    };
+
+    // index of the first entry matching `predicate`
+    let find = |jvm: &mut JVM, predicate: &dyn Fn(&CpInfoInfoEnum) -> bool| {
+      let class = jvm.class_loader.get("TestReflection").unwrap();
+      let cp = class.get_constant_pool();
+
+      (1..=cp.len() as u16)
+        .find(|i| predicate(cp.resolve_index(*i).unwrap().get_info()))
+        .unwrap() as i32
+    };
+
+    let size = call_native(
+      &mut jvm,
+      CONSTANT_POOL,
+      "getSize0",
+      "(Ljava/lang/Object;)I",
+      vec![cp, oop],
+    );
+    let len = jvm
+      .class_loader
+      .get("TestReflection")
+      .unwrap()
+      .get_constant_pool()
+      .len();
+    assert_eq!(size, Type::Integer(len as i32 + 1));
+
+    let index = find(&mut jvm, &|info| matches!(info, CpInfoInfoEnum::Long(_)));
+    let long = cp_call(&mut jvm, "getLongAt0", "(Ljava/lang/Object;I)J", index);
+    assert_eq!(long, Type::Long(5000000000));
+
+    // the entry after a long is unusable
+    let tag = cp_call(&mut jvm, "getTagAt0", "(Ljava/lang/Object;I)B", index + 1);
+    assert_eq!(tag, Type::Byte(0));
+    let tag = cp_call(&mut jvm, "getTagAt0", "(Ljava/lang/Object;I)B", index);
+    assert_eq!(tag, Type::Byte(5));
+
+    let index = find(&mut jvm, &|info| matches!(info, CpInfoInfoEnum::Float(_)));
+    let float = cp_call(&mut jvm, "getFloatAt0", "(Ljava/lang/Object;I)F", index);
+    assert_eq!(float, Type::Float(1.5));
+
+    let index = find(&mut jvm, &|info| matches!(info, CpInfoInfoEnum::Double(_)));
+    let double = cp_call(&mut jvm, "getDoubleAt0", "(Ljava/lang/Object;I)D", index);
+    assert_eq!(double, Type::Double(2.25));
+
+    let index = find(&mut jvm, &|info| matches!(info, CpInfoInfoEnum::String(_)));
+    let value = cp_call(
+      &mut jvm,
+      "getStringAt0",
+      "(Ljava/lang/Object;I)Ljava/lang/String;",
+      index,
+    );
+    assert_eq!(get_string(&jvm, value), "hello");
+
+    // getUTF8At0 needs a Utf8 entry, not a String
+    for arg in [cp, oop, Type::Integer(index)] {
+      jvm.push_stack(arg).unwrap();
+    }
+    let err = jvm
+      .call_native(
+        CONSTANT_POOL,
+        "getUTF8At0",
+        "(Ljava/lang/Object;I)Ljava/lang/String;",
+      )
+      .unwrap_err();
+    assert!(
+      matches!(
+        err.downcast_ref::<JavaException>(),
+        Some(JavaException::IllegalArgumentException(_))
+      ),
+      "{}",
+      err
+    );
+
+    for arg in [cp, oop, Type::Integer(len as i32 + 1)] {
+      jvm.push_stack(arg).unwrap();
+    }
+    let err = jvm
+      .call_native(CONSTANT_POOL, "getTagAt0", "(Ljava/lang/Object;I)B")
+      .unwrap_err();
+    assert!(err.to_string().contains("out of bounds"), "{}", err);
+
+    let index = find(&mut jvm, &|info| {
+      matches!(info, CpInfoInfoEnum::Methodref(_))
+    });
+    let info = cp_call(
+      &mut jvm,
+      "getMemberRefInfoAt0",
+      "(Ljava/lang/Object;I)[Ljava/lang/String;",
+      index,
+    );
+    let info: Vec<String> = elements(&jvm, info)
+      .into_iter()
+      .map(|s| get_string(&jvm, s))
+      .collect();
+    assert_eq!(info, vec!["java/lang/Object", "<init>", "()V"]);
+
+    let constructor = cp_call(
+      &mut jvm,
+      "getMethodAt0",
+      "(Ljava/lang/Object;I)Ljava/lang/reflect/Member;",
+      index,
+    );
+    let constructor = jvm
+      .heap
+      .get_obj_instance(constructor.as_ref().unwrap())
+      .unwrap();
+    assert_eq!(constructor.get_classname(), "java/lang/reflect/Constructor");
+
+    let index = find(&mut jvm, &|info| {
+      matches!(info, CpInfoInfoEnum::Fieldref(_))
+    });
+    let field = cp_call(
+      &mut jvm,
+      "getFieldAt0",
+      "(Ljava/lang/Object;I)Ljava/lang/reflect/Field;",
+      index,
+    );
+    let name = jvm
+      .heap
+      .get_obj_instance(field.as_ref().unwrap())
+      .unwrap()
+      .get_field("name")
+      .unwrap();
+    assert_eq!(get_string(&jvm, name), "count");
+
+    let index = find(&mut jvm, &|info| matches!(info, CpInfoInfoEnum::Class(_)));
+    let class = cp_call(
+      &mut jvm,
+      "getClassAt0",
+      "(Ljava/lang/Object;I)Ljava/lang/Class;",
+      index,
+    );
+    assert_eq!(class, class_obj(&mut jvm, "java/lang/Object"));
+  }
+}
@@ -0,0 +1,192 @@
+#[cfg(test)]
+mod tests {
+
+  use crate::runtime::{
+    stringconcat::{java_double_to_string, java_float_to_string, StringConcat},
+    test_support::*,
+    types::Type,
+  };
+
+  const CLASS: &str = "TestStringConcat";
+
+  #[test]
+
+  fn test_java_double_to_string() {
+    for (value, expected) in [
+      (1.0, "1.0"),
+      (0.1, "0.1"),
+      (100.0, "100.0"),
+      (0.001, "0.001"),
+      (0.00123, "0.00123"),
+      (123456.789, "123456.789"),
+      (9999999.0, "9999999.0"),
+      (1.0E7, "1.0E7"),
+      (1.0E10, "1.0E10"),
+      (1.0E-4, "1.0E-4"),
+      (-1.5E-7, "-1.5E-7"),
+      (2E22, "2.0E22"),
+      (f64::MIN_POSITIVE * f64::EPSILON, "4.9E-324"),
+      (f64::MAX, "1.7976931348623157E308"),
+      (-0.0, "-0.0"),
+      (f64::NAN, "NaN"),
+      (f64::NEG_INFINITY, "-Infinity"),
+    ] {
+      assert_eq!(java_double_to_string(value), expected);
+    }
+
+    for (value, expected) in [
+      (1.1, "1.1"),
+      (3.0, "3.0"),
+      (1.0E-5, "1.0E-5"),
+      (12345678.0, "1.2345678E7"),
+      (f32::from_bits(1), "1.4E-45"),
+      (f32::MAX, "3.4028235E38"),
+      (f32::INFINITY, "Infinity"),
+    ] {
+      assert_eq!(java_float_to_string(value), expected);
+    }
+  }
+
+  #[test]
+
+  fn test_recipe() {
+    let types = vec!["I".to_string(), "J".to_string()];
+
+    assert!(
+      StringConcat::from_recipe("a\u{1}b\u{2}\u{1}", &["c".to_string()], types.clone()).is_ok()
+    );
+
+    // the recipe and the call site must agree
+    assert!(StringConcat::from_recipe("\u{1}", &[], types.clone()).is_err());
+    assert!(StringConcat::from_recipe("\u{1}\u{1}\u{2}", &[], types.clone()).is_err());
+    assert!(StringConcat::from_recipe("\u{1}\u{1}", &["c".to_string()], types).is_err());
+  }
+
+  #[test]
+
+  fn test_concat_primitives() {
+    let mut jvm = mock();
+
+    let ret = call_string(
+      &mut jvm,
+      CLASS,
+      "primitives",
+      "(IJCZBS)Ljava/lang/String;",
+      vec![
+        Type::Integer(-7),
+        // category 2 values take two locals
+        Type::Long(1 << 40),
+        Type::Long(1 << 40),
+        Type::Integer('é' as i32),
+        Type::Boolean(true),
+        Type::Byte(-128),
+        Type::Short(300),
+      ],
+    );
+    assert_eq!(ret, "i=-7 l=1099511627776 c=é z=true b=-128 s=300");
+
+    let ret = call_string(
+      &mut jvm,
+      CLASS,
+      "decimals",
+      "(FD)Ljava/lang/String;",
+      vec![Type::Float(0.5), Type::Double(1.0E-4), Type::Double(1.0E-4)],
+    );
+    assert_eq!(ret, "0.5|1.0E-4");
+  }
+
+  #[test]
+
+  fn test_concat_objects() {
+    let mut jvm = mock();
+
+    let hello = string(&mut jvm, "hello");
+
+    let i = boxed(&mut jvm, "I", Type::Integer(42));
+    let c = boxed(&mut jvm, "C", Type::Integer('x' as i32));
+    let d = boxed(&mut jvm, "D", Type::Double(2.5));
+
+    let ret = call_string(
+      &mut jvm,
+      CLASS,
+      "boxed",
+      "(Ljava/lang/Integer;Ljava/lang/Character;Ljava/lang/Double;)Ljava/lang/String;",
+      vec![i, c, d],
+    );
+    assert_eq!(ret, "42x2.5");
+
+    let ret = call_string(
+      &mut jvm,
+      CLASS,
+      "boxed",
+      "(Ljava/lang/Integer;Ljava/lang/Character;Ljava/lang/Double;)Ljava/lang/String;",
+      vec![Type::Null, Type::Null, Type::Null],
+    );
+    assert_eq!(ret, "nullnullnull");
+
+    let point = jvm
+      .heap
+      .alloc_obj(&mut jvm.class_loader, "TestStringConcat$Point")
+      .unwrap();
+    jvm
+      .call_and_resolve_method(
+        "TestStringConcat$Point",
+        "<init>",
+        "(II)V",
+        vec![point, Type::Integer(1), Type::Integer(-2)],
+      )
+      .unwrap();
+
+    let ret = call_string(
+      &mut jvm,
+      CLASS,
+      "objects",
+      "(Ljava/lang/String;Ljava/lang/Object;LTestStringConcat$Point;)Ljava/lang/String;",
+      vec![hello, Type::Null, point],
+    );
+    assert_eq!(ret, "hellonull(1, -2)");
+
+    // `toString` returning null is formatted as null
+    let ret = call_string(&mut jvm, CLASS, "nameless", "()Ljava/lang/String;", vec![]);
+    assert_eq!(ret, "<null>");
+  }
+
+  #[test]
+
+  fn test_concat_call_sites() {
+    let mut jvm = mock();
+
+    let ret = call_string(
+      &mut jvm,
+      CLASS,
+      "tags",
+      "(I)Ljava/lang/String;",
+      vec![Type::Integer(5)],
+    );
+    assert_eq!(ret, "\u{1}5\u{2}");
+
+    // each call site keeps its own recipe
+    for i in 0..2 {
+      let ret = call_string(
+        &mut jvm,
+        CLASS,
+        "first",
+        "(I)Ljava/lang/String;",
+        vec![Type::Integer(i)],
+      );
+      assert_eq!(ret, format!("first {}", i));
+
+      let ret = call_string(
+        &mut jvm,
+        CLASS,
+        "second",
+        "(I)Ljava/lang/String;",
+        vec![Type::Integer(i)],
+      );
+      assert_eq!(ret, format!("{} second", i));
+    }
+
+    let ret = call_string(&mut jvm, CLASS, "loop", "()Ljava/lang/String;", vec![]);
+    assert_eq!(ret, "0,1,2,");
+  }
+}
@@ -0,0 +1,177 @@
+// Helpers shared by the tests of the VM
+
+use color_eyre::eyre::{Report, Result};
+
+use crate::runtime::{embedding::JvmBuilder, errors::JavaException, jvm::JVM, types::Type};
+
+pub(crate) fn mock() -> JVM {
+  JVM::mock("tests/classes/", vec![], false).unwrap()
+}
+
+// the natives take their arguments from the stack of the current frame, the one of
+// `class_name.method`
+pub(crate) fn mock_with_frame(class_name: &str, method: &str, descriptor: &str) -> JVM {
+  let mut jvm = mock();
+
+  jvm
+    .push_frame_from_class(class_name, method, descriptor, vec![])
+    .unwrap();
+
+  jvm
+}
+
+// the code of the JDK calling System.getProperty or Thread.currentThread (Proxy,
+// AnnotationParser, the bootstraps of lambdas and of string concatenations) needs the
+// system properties and a current thread
+pub(crate) fn booted_mock() -> JVM {
+  let mut jvm = JvmBuilder::new()
+    .class_path("tests/classes/")
+    .property("java.home", "/tmp")
+    .mock()
+    .unwrap();
+  add_host_natives(&mut jvm);
+
+  jvm.init_class("java/lang/System").unwrap();
+  create_main_thread(&mut jvm, "MainThread");
+  for class_name in [
+    "java/lang/Thread",
+    "java/lang/ref/Reference",
+    "java/lang/reflect/AccessibleObject",
+  ] {
+    jvm.init_class(class_name).unwrap();
+  }
+  jvm
+    .call_and_resolve_method("java/lang/System", "initPhase1", "()V", vec![])
+    .unwrap();
+
+  jvm
+}
+
+// the tests run on the jmods of a JDK 17, whose reflection goes through the accessors
+// that JDK 23 replaced with the ones of `DirectMethodHandleAccessor`
+fn add_host_natives(jvm: &mut JVM) {
+  jvm.natives.add(
+    "jdk/internal/reflect/NativeMethodAccessorImpl",
+    "invoke0",
+    "(Ljava/lang/reflect/Method;Ljava/lang/Object;[Ljava/lang/Object;)Ljava/lang/Object;",
+    |jvm, _, _| jvm.exec_native_invoke0(),
+  );
+  jvm.natives.add(
+    "jdk/internal/reflect/NativeConstructorAccessorImpl",
+    "newInstance0",
+    "(Ljava/lang/reflect/Constructor;[Ljava/lang/Object;)Ljava/lang/Object;",
+    |jvm, _, _| jvm.exec_native_new_instance0(),
+  );
+}
+
+// the constructor of the JDK 17 Thread copies the priority of the current thread, which
+// is the main thread itself, where the one of JDK 23 gives NORM_PRIORITY to an attached
+// thread
+pub(crate) fn create_main_thread(jvm: &mut JVM, name: &str) {
+  let thread_obj = jvm.alloc_main_thread().unwrap();
+
+  jvm
+    .heap
+    .get_obj_instance_mut(thread_obj.as_ref().unwrap())
+    .unwrap()
+    .put_field("priority", Type::Integer(5))
+    .unwrap();
+
+  jvm.init_main_thread(thread_obj, name).unwrap();
+}
+
+pub(crate) fn call(
+  jvm: &mut JVM,
+  class_name: &str,
+  method: &str,
+  descriptor: &str,
+  args: Vec<Type>,
+) -> Result<Type> {
+  jvm.call_and_resolve_method(class_name, method, descriptor, args)
+}
+
+pub(crate) fn call_string(
+  jvm: &mut JVM,
+  class_name: &str,
+  method: &str,
+  descriptor: &str,
+  args: Vec<Type>,
+) -> String {
+  let ret = call(jvm, class_name, method, descriptor, args).unwrap();
+
+  get_string(jvm, ret)
+}
+
+pub(crate) fn string(jvm: &mut JVM, value: &str) -> Type {
+  jvm.heap.alloc_string(&mut jvm.class_loader, value).unwrap()
+}
+
+pub(crate) fn get_string(jvm: &JVM, value: Type) -> String {
+  jvm.heap.get_string(value.as_ref().unwrap()).unwrap()
+}
+
+pub(crate) fn boxed(jvm: &mut JVM, descriptor: &str, value: Type) -> Type {
+  jvm
+    .heap
+    .alloc_boxed(&mut jvm.class_loader, descriptor, value)
+    .unwrap()
+}
+
+// object of `classname` with its fields set, without calling a constructor
+pub(crate) fn object(jvm: &mut JVM, classname: &str, fields: Vec<(&str, Type)>) -> Type {
+  let obj = jvm
+    .heap
+    .alloc_obj(&mut jvm.class_loader, classname)
+    .unwrap();
+
+  let instance = jvm
+    .heap
+    .get_obj_instance_mut(obj.as_ref().unwrap())
+    .unwrap();
+
+  for (name, value) in fields {
+    instance.put_field(name, value).unwrap();
+  }
+
+  obj
+}
+
+// object of `classname` created by its constructor without arguments
+pub(crate) fn new_object(jvm: &mut JVM, classname: &str) -> Type {
+  let obj = jvm
+    .heap
+    .alloc_obj(&mut jvm.class_loader, classname)
+    .unwrap();
+
+  jvm
+    .call_and_resolve_method(classname, "<init>", "()V", vec![obj])
+    .unwrap();
+
+  obj
+}
+
+pub(crate) fn array(jvm: &mut JVM, classname: &str, elements: Vec<Type>) -> Type {
+  let size = elements.len();
+
+  jvm.heap.alloc_array(classname, elements, size).unwrap()
+}
+
+// Class object of a class name or of a field descriptor (`I`, `Ljava/lang/String;`)
+pub(crate) fn class(jvm: &mut JVM, descriptor: &str) -> Type {
+  let classname = match descriptor.strip_prefix('L') {
+    Some(classname) if descriptor.ends_with(';') => classname.trim_end_matches(';'),
+    _ => descriptor,
+  };
+
+  jvm
+    .heap
+    .alloc_class_obj(&mut jvm.class_loader, classname)
+    .unwrap()
+}
+
+pub(crate) fn java_exception(err: Report) -> JavaException {
+  match err.downcast::<JavaException>() {
+    Ok(exception) => exception,
+    Err(err) => panic!("JavaException expected, got {:?}", err),
+  }
+}
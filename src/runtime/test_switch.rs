@@ -0,0 +1,455 @@
+#[cfg(test)]
+mod tests {
+
+  use color_eyre::eyre::Result;
+
+  use crate::{
+    class_loader::{assembler::assemble, namespace::BOOTSTRAP_LOADER},
+    runtime::{errors::JavaException, jvm::JVM, test_support::*, types::Type},
+  };
+
+  // the methods of TestSwitch.java as javac 21 lowers them
+  const SWITCHES: &str = "
+    .class public TestSwitch
+
+    .method public static area(LTestRecord$Shape;)I
+      .limit locals 5
+      aload_0
+      dup
+      invokestatic java/util/Objects/requireNonNull(Ljava/lang/Object;)Ljava/lang/Object;
+      pop
+      astore_1
+      iconst_0
+      istore_2
+    Restart:
+      aload_1
+      iload_2
+      invokedynamic typeSwitch(LTestRecord$Shape;I)I java/lang/runtime/SwitchBootstraps/typeSwitch(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/invoke/MethodType;[Ljava/lang/Object;)Ljava/lang/invoke/CallSite; class TestRecord$Circle class TestRecord$Square class TestRecord$Square class TestRecord$Rect
+      tableswitch 0 3
+        Circle
+        BigSquare
+        Square
+        Rect
+        default : NoMatch
+    Circle:
+      aload_1
+      checkcast TestRecord$Circle
+      astore_3
+      ldc2_w 3.0
+      aload_3
+      invokevirtual TestRecord$Circle/radius()D
+      dmul
+      aload_3
+      invokevirtual TestRecord$Circle/radius()D
+      dmul
+      d2i
+      ireturn
+    BigSquare:
+      aload_1
+      checkcast TestRecord$Square
+      astore_3
+      aload_3
+      invokevirtual TestRecord$Square/side()I
+      bipush 10
+      if_icmpgt Big
+      iconst_2
+      istore_2
+      goto Restart
+    Big:
+      iconst_m1
+      ireturn
+    Square:
+      aload_1
+      checkcast TestRecord$Square
+      astore_3
+      aload_3
+      invokevirtual TestRecord$Square/side()I
+      aload_3
+      invokevirtual TestRecord$Square/side()I
+      imul
+      ireturn
+    Rect:
+      aload_1
+      checkcast TestRecord$Rect
+      astore_3
+      aload_3
+      invokevirtual TestRecord$Rect/corner()LTestRecord$Point;
+      astore 4
+      ; null does not match the nested record pattern
+      aload 4
+      ifnonnull Corner
+      iconst_4
+      istore_2
+      goto Restart
+    Corner:
+      aload 4
+      invokevirtual TestRecord$Point/x()I
+      aload 4
+      invokevirtual TestRecord$Point/y()I
+      iadd
+      aload_3
+      invokevirtual TestRecord$Rect/width()I
+      aload_3
+      invokevirtual TestRecord$Rect/height()I
+      imul
+      iadd
+      ireturn
+    NoMatch:
+      new java/lang/MatchException
+      dup
+      aconst_null
+      aconst_null
+      invokespecial java/lang/MatchException/<init>(Ljava/lang/String;Ljava/lang/Throwable;)V
+      athrow
+    .end method
+
+    .method public static number(Ljava/lang/Integer;)I
+      .limit locals 4
+      aload_0
+      astore_1
+      iconst_0
+      istore_2
+    Restart:
+      aload_1
+      iload_2
+      invokedynamic typeSwitch(Ljava/lang/Integer;I)I java/lang/runtime/SwitchBootstraps/typeSwitch(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/invoke/MethodType;[Ljava/lang/Object;)Ljava/lang/invoke/CallSite; 1 class java/lang/Integer class java/lang/Integer
+      tableswitch -1 2
+        Null
+        One
+        Guarded
+        Any
+        default : NoMatch
+    Null:
+      iconst_m1
+      ireturn
+    One:
+      bipush 100
+      ireturn
+    Guarded:
+      aload_1
+      astore_3
+      aload_3
+      invokevirtual java/lang/Integer/intValue()I
+      bipush 10
+      if_icmpgt Large
+      iconst_2
+      istore_2
+      goto Restart
+    Large:
+      bipush 10
+      ireturn
+    Any:
+      aload_1
+      invokevirtual java/lang/Integer/intValue()I
+      ireturn
+    NoMatch:
+      new java/lang/MatchException
+      dup
+      aconst_null
+      aconst_null
+      invokespecial java/lang/MatchException/<init>(Ljava/lang/String;Ljava/lang/Throwable;)V
+      athrow
+    .end method
+
+    .method public static color(LTestRecord$Color;)I
+      .limit locals 4
+      aload_0
+      dup
+      invokestatic java/util/Objects/requireNonNull(Ljava/lang/Object;)Ljava/lang/Object;
+      pop
+      astore_1
+      iconst_0
+      istore_2
+    Restart:
+      aload_1
+      iload_2
+      invokedynamic enumSwitch(LTestRecord$Color;I)I java/lang/runtime/SwitchBootstraps/enumSwitch(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/invoke/MethodType;[Ljava/lang/Object;)Ljava/lang/invoke/CallSite; \"RED\" class TestRecord$Color class TestRecord$Color
+      tableswitch 0 2
+        Red
+        Guarded
+        Any
+        default : NoMatch
+    Red:
+      iconst_1
+      ireturn
+    Guarded:
+      aload_1
+      astore_3
+      aload_3
+      invokevirtual TestRecord$Color/ordinal()I
+      iconst_1
+      if_icmpgt Last
+      iconst_2
+      istore_2
+      goto Restart
+    Last:
+      iconst_3
+      ireturn
+    Any:
+      iconst_2
+      ireturn
+    NoMatch:
+      new java/lang/MatchException
+      dup
+      aconst_null
+      aconst_null
+      invokespecial java/lang/MatchException/<init>(Ljava/lang/String;Ljava/lang/Throwable;)V
+      athrow
+    .end method
+
+    ; the call site alone, with labels javac does not mix
+    .method public static labels(Ljava/lang/Object;I)I
+      aload_0
+      iload_1
+      invokedynamic typeSwitch(Ljava/lang/Object;I)I java/lang/runtime/SwitchBootstraps/typeSwitch(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/invoke/MethodType;[Ljava/lang/Object;)Ljava/lang/invoke/CallSite; \"one\" 2 class java/lang/CharSequence class TestRecord$Shape
+      ireturn
+    .end method
+
+    .method public static badEnum(Ljava/lang/Object;)I
+      aload_0
+      iconst_0
+      invokedynamic enumSwitch(Ljava/lang/Object;)I java/lang/runtime/SwitchBootstraps/enumSwitch(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/invoke/MethodType;[Ljava/lang/Object;)Ljava/lang/invoke/CallSite; \"RED\"
+      ireturn
+    .end method
+  ";
+
+  fn mock() -> (JVM, String) {
+    let mut jvm = JVM::mock("tests/classes/", vec![], false).unwrap();
+
+    let bytes = assemble(SWITCHES).unwrap();
+
+    let key = jvm.define_class(BOOTSTRAP_LOADER, None, &bytes).unwrap();
+
+    (jvm, key)
+  }
+
+  fn record(jvm: &mut JVM, method: &str, descriptor: &str, args: Vec<Type>) -> Type {
+    jvm
+      .call_and_resolve_method("TestRecord", method, descriptor, args)
+      .unwrap()
+  }
+
+  fn labels(jvm: &mut JVM, key: &str, target: Type, restart: i32) -> Result<Type> {
+    jvm.call_and_resolve_method(
+      key,
+      "labels",
+      "(Ljava/lang/Object;I)I",
+      vec![target, Type::Integer(restart)],
+    )
+  }
+
+  #[test]
+
+  fn test_record_to_string() {
+    let (mut jvm, _) = mock();
+
+    let ret = record(&mut jvm, "points", "()Ljava/lang/String;", vec![]);
+    assert_eq!(
+      get_string(&jvm, ret),
+      "Point[x=1, y=-2] Empty[] Rect[corner=Point[x=1, y=-2], width=3, height=4]"
+    );
+
+    let ret = record(&mut jvm, "primitives", "()Ljava/lang/String;", vec![]);
+    assert_eq!(
+      get_string(&jvm, ret),
+      "Primitives[z=true, b=-1, c=x, s=7, j=1099511627776, f=0.5, d=1.0E-4]"
+    );
+
+    let ret = record(&mut jvm, "named", "()Ljava/lang/String;", vec![]);
+    assert_eq!(
+      get_string(&jvm, ret),
+      "Named[name=origin, value=null] Named[name=point, value=Point[x=0, y=0]]"
+    );
+  }
+
+  #[test]
+
+  fn test_record_equals_and_hash_code() {
+    let (mut jvm, _) = mock();
+
+    let ret = record(&mut jvm, "equality", "()Z", vec![]);
+    assert!(ret.as_bool().unwrap());
+
+    // 31 * x + y
+    let ret = record(
+      &mut jvm,
+      "pointHash",
+      "(II)I",
+      vec![Type::Integer(3), Type::Integer(-4)],
+    );
+    assert_eq!(ret, Type::Integer(89));
+
+    // hash codes of the boxes, combined like the one of the JDK
+    let expected = [
+      1237,
+      2,
+      'a' as i32,
+      -3,
+      0,
+      1.5f32.to_bits() as i32,
+      ((2.0f64.to_bits() ^ (2.0f64.to_bits() >> 32)) as i32),
+    ]
+    .iter()
+    .fold(0i32, |hash, value| {
+      hash.wrapping_mul(31).wrapping_add(*value)
+    });
+
+    let ret = record(&mut jvm, "primitivesHash", "()I", vec![]);
+    assert_eq!(ret, Type::Integer(expected));
+
+    // "a".hashCode() and 0 for null
+    let ret = record(&mut jvm, "namedHash", "()I", vec![]);
+    assert_eq!(ret, Type::Integer(97 * 31));
+  }
+
+  #[test]
+
+  fn test_type_switch() {
+    let (mut jvm, key) = mock();
+
+    let area = |jvm: &mut JVM, shape: Type| {
+      jvm
+        .call_and_resolve_method(&key, "area", "(LTestRecord$Shape;)I", vec![shape])
+        .unwrap()
+    };
+
+    let shape = record(
+      &mut jvm,
+      "circle",
+      "(D)LTestRecord$Shape;",
+      vec![Type::Double(2.0), Type::Double(2.0)],
+    );
+    assert_eq!(area(&mut jvm, shape), Type::Integer(12));
+
+    // the guard fails and the switch restarts after its label
+    let shape = record(
+      &mut jvm,
+      "square",
+      "(I)LTestRecord$Shape;",
+      vec![Type::Integer(4)],
+    );
+    assert_eq!(area(&mut jvm, shape), Type::Integer(16));
+
+    let shape = record(
+      &mut jvm,
+      "square",
+      "(I)LTestRecord$Shape;",
+      vec![Type::Integer(11)],
+    );
+    assert_eq!(area(&mut jvm, shape), Type::Integer(-1));
+
+    let shape = record(
+      &mut jvm,
+      "rect",
+      "(IIII)LTestRecord$Shape;",
+      vec![
+        Type::Integer(1),
+        Type::Integer(2),
+        Type::Integer(3),
+        Type::Integer(4),
+      ],
+    );
+    assert_eq!(area(&mut jvm, shape), Type::Integer(15));
+
+    let number = |jvm: &mut JVM, value: i32| {
+      let value = jvm
+        .heap
+        .alloc_boxed(&mut jvm.class_loader, "I", Type::Integer(value))
+        .unwrap();
+
+      jvm
+        .call_and_resolve_method(&key, "number", "(Ljava/lang/Integer;)I", vec![value])
+        .unwrap()
+    };
+
+    assert_eq!(number(&mut jvm, 1), Type::Integer(100));
+    assert_eq!(number(&mut jvm, 42), Type::Integer(10));
+    assert_eq!(number(&mut jvm, -5), Type::Integer(-5));
+
+    let ret = jvm
+      .call_and_resolve_method(&key, "number", "(Ljava/lang/Integer;)I", vec![Type::Null])
+      .unwrap();
+    assert_eq!(ret, Type::Integer(-1));
+  }
+
+  #[test]
+
+  fn test_enum_switch() {
+    let (mut jvm, key) = mock();
+
+    for (ordinal, expected) in [(0, 1), (1, 2), (2, 3)] {
+      let color = record(
+        &mut jvm,
+        "color",
+        "(I)LTestRecord$Color;",
+        vec![Type::Integer(ordinal)],
+      );
+
+      let ret = jvm
+        .call_and_resolve_method(&key, "color", "(LTestRecord$Color;)I", vec![color])
+        .unwrap();
+      assert_eq!(ret, Type::Integer(expected));
+    }
+
+    let err = jvm
+      .call_and_resolve_method(&key, "badEnum", "(Ljava/lang/Object;)I", vec![Type::Null])
+      .unwrap_err();
+    assert!(matches!(
+      err.downcast_ref::<JavaException>(),
+      Some(JavaException::BootstrapMethodError(_))
+    ));
+  }
+
+  #[test]
+
+  fn test_switch_labels() {
+    let (mut jvm, key) = mock();
+
+    let one = jvm.heap.alloc_string(&mut jvm.class_loader, "one").unwrap();
+    let two = jvm.heap.alloc_string(&mut jvm.class_loader, "two").unwrap();
+
+    // labels from the restart index, then labels.length when none matches
+    for (restart, expected) in [(0, 0), (1, 2), (3, 4), (4, 4)] {
+      assert_eq!(
+        labels(&mut jvm, &key, one, restart).unwrap(),
+        Type::Integer(expected)
+      );
+    }
+
+    assert_eq!(labels(&mut jvm, &key, two, 0).unwrap(), Type::Integer(2));
+
+    // numbers and characters match an Integer label by their int value
+    for (descriptor, value) in [
+      ("I", Type::Integer(2)),
+      ("C", Type::Integer(2)),
+      ("J", Type::Long(2)),
+      ("D", Type::Double(2.5)),
+    ] {
+      let boxed = jvm
+        .heap
+        .alloc_boxed(&mut jvm.class_loader, descriptor, value)
+        .unwrap();
+
+      assert_eq!(labels(&mut jvm, &key, boxed, 0).unwrap(), Type::Integer(1));
+    }
+
+    let square = record(
+      &mut jvm,
+      "square",
+      "(I)LTestRecord$Shape;",
+      vec![Type::Integer(1)],
+    );
+    assert_eq!(labels(&mut jvm, &key, square, 0).unwrap(), Type::Integer(3));
+
+    assert_eq!(
+      labels(&mut jvm, &key, Type::Null, 0).unwrap(),
+      Type::Integer(-1)
+    );
+
+    let err = labels(&mut jvm, &key, one, 5).unwrap_err();
+    assert!(matches!(
+      err.downcast_ref::<JavaException>(),
+      Some(JavaException::IndexOutOfBounds(_))
+    ));
+  }
+}
@@ -0,0 +1,230 @@
+#[cfg(test)]
+mod tests {
+
+  use crate::runtime::{errors::JavaException, jvm::JVM, test_support::*, types::Type};
+
+  const CLASS: &str = "TestUnsafe";
+
+  const UNSAFE: &str = "Ljdk/internal/misc/Unsafe;";
+
+  fn the_unsafe(jvm: &mut JVM) -> Type {
+    jvm.init_class("jdk/internal/misc/Unsafe").unwrap();
+
+    *jvm
+      .class_loader
+      .get("jdk/internal/misc/Unsafe")
+      .unwrap()
+      .get_static_field("theUnsafe")
+      .unwrap()
+  }
+
+  // offset of objectFieldOffset, as a long argument
+  fn offset(jvm: &mut JVM, field: &str) -> [Type; 2] {
+    let offset = Type::Long(jvm.class_loader.get_field_offset(CLASS, field).unwrap());
+
+    [offset, offset]
+  }
+
+  #[test]
+
+  fn test_unsafe_fields() {
+    let mut jvm = mock();
+    let unsafe_obj = the_unsafe(&mut jvm);
+    let t = new_object(&mut jvm, CLASS);
+
+    let mut args = vec![unsafe_obj, t];
+    for field in ["count", "total", "name"] {
+      args.extend(offset(&mut jvm, field));
+    }
+
+    let descriptor = format!("({}LTestUnsafe;JJJ)Ljava/lang/String;", UNSAFE);
+    assert_eq!(
+      call_string(&mut jvm, CLASS, "fields", &descriptor, args),
+      "41 42 true false 7 a b"
+    );
+
+    let mut args = vec![unsafe_obj, t];
+    for field in ["small", "ratio", "average", "enabled"] {
+      args.extend(offset(&mut jvm, field));
+    }
+
+    let descriptor = format!("({}LTestUnsafe;JJJJ)Ljava/lang/String;", UNSAFE);
+    assert_eq!(
+      call_string(&mut jvm, CLASS, "primitives", &descriptor, args),
+      "-2 0.5 1.25 true"
+    );
+  }
+
+  #[test]
+
+  fn test_unsafe_static_fields() {
+    let mut jvm = mock();
+    let unsafe_obj = the_unsafe(&mut jvm);
+
+    // the base of Unsafe.staticFieldBase
+    jvm
+      .heap
+      .get_class_instance(&mut jvm.class_loader, CLASS)
+      .unwrap();
+    let base = Type::ObjectRef(jvm.heap.get_static_class_instance(CLASS).unwrap().get_ref());
+
+    let mut args = vec![unsafe_obj, base];
+    args.extend(offset(&mut jvm, "created"));
+
+    let descriptor = format!("({}Ljava/lang/Object;J)Ljava/lang/String;", UNSAFE);
+    assert_eq!(
+      call_string(&mut jvm, CLASS, "statics", &descriptor, args),
+      "3 7"
+    );
+  }
+
+  #[test]
+
+  fn test_unsafe_arrays() {
+    let mut jvm = mock();
+    let unsafe_obj = the_unsafe(&mut jvm);
+
+    let ints = array(
+      &mut jvm,
+      "I",
+      vec![Type::Integer(1), Type::Integer(2), Type::Integer(3)],
+    );
+    let bytes = array(&mut jvm, "B", vec![Type::Byte(0); 8]);
+    let strings = array(&mut jvm, "java/lang/String", vec![Type::Null; 2]);
+
+    // ints and strings by index scale, the bytes viewed as wider values
+    let descriptor = format!("({}[I[B[Ljava/lang/String;)Ljava/lang/String;", UNSAFE);
+    assert_eq!(
+      call_string(
+        &mut jvm,
+        CLASS,
+        "arrays",
+        &descriptor,
+        vec![unsafe_obj, ints, bytes, strings]
+      ),
+      "100 2 12 5 true 16909060 258 1 set"
+    );
+  }
+
+  #[test]
+
+  fn test_unsafe_arrays_out_of_bounds() {
+    let mut jvm = mock();
+    let unsafe_obj = the_unsafe(&mut jvm);
+
+    let ints = array(&mut jvm, "I", vec![Type::Integer(1)]);
+    let bytes = array(&mut jvm, "B", vec![Type::Byte(0); 2]);
+    let strings = array(&mut jvm, "java/lang/String", vec![Type::Null; 2]);
+
+    let descriptor = format!("({}[I[B[Ljava/lang/String;)Ljava/lang/String;", UNSAFE);
+    let err = call(
+      &mut jvm,
+      CLASS,
+      "arrays",
+      &descriptor,
+      vec![unsafe_obj, ints, bytes, strings],
+    )
+    .unwrap_err();
+
+    assert!(matches!(
+      err.downcast::<JavaException>().unwrap(),
+      JavaException::ArrayIndexOutOfBounds(2, 1)
+    ));
+  }
+
+  #[test]
+
+  fn test_unsafe_memory() {
+    let mut jvm = mock();
+    let unsafe_obj = the_unsafe(&mut jvm);
+
+    let bytes = array(
+      &mut jvm,
+      "B",
+      vec![Type::Byte(9), Type::Byte(8), Type::Byte(7), Type::Byte(6)],
+    );
+
+    let descriptor = format!("({}[B)Ljava/lang/String;", UNSAFE);
+    assert_eq!(
+      call_string(
+        &mut jvm,
+        CLASS,
+        "memory",
+        &descriptor,
+        vec![unsafe_obj, bytes]
+      ),
+      "84281096 1 true 72340172838076673 101124105 1 7 z 2.5"
+    );
+  }
+
+  #[test]
+
+  fn test_unsafe_memory_checked() {
+    let mut jvm = mock();
+    let unsafe_obj = the_unsafe(&mut jvm);
+
+    // an address outside of the allocations is not dereferenced, it raises an InternalError
+    let descriptor = format!("({}J)I", UNSAFE);
+    let address = Type::Long(16);
+    let err = call(
+      &mut jvm,
+      CLASS,
+      "read",
+      &descriptor,
+      vec![unsafe_obj, address, address],
+    )
+    .unwrap_err();
+
+    assert!(matches!(
+      err.downcast::<JavaException>().unwrap(),
+      JavaException::InternalError(message) if message.ends_with(" 16")
+    ));
+
+    let descriptor = format!("({})I", UNSAFE);
+    let err = call(&mut jvm, CLASS, "readFreed", &descriptor, vec![unsafe_obj]).unwrap_err();
+
+    assert!(matches!(
+      err.downcast::<JavaException>().unwrap(),
+      JavaException::InternalError(_)
+    ));
+  }
+
+  #[test]
+
+  fn test_unsafe_instances() {
+    let mut jvm = mock();
+    let unsafe_obj = the_unsafe(&mut jvm);
+
+    // the constructor is not called
+    let descriptor = format!("({})I", UNSAFE);
+    let ret = call(&mut jvm, CLASS, "allocate", &descriptor, vec![unsafe_obj]).unwrap();
+    assert_eq!(ret, Type::Integer(0));
+
+    let descriptor = format!("({})V", UNSAFE);
+    let err = call(&mut jvm, CLASS, "raise", &descriptor, vec![unsafe_obj]).unwrap_err();
+
+    assert!(matches!(
+      err.downcast::<JavaException>().unwrap(),
+      JavaException::IllegalStateException(_)
+    ));
+  }
+
+  #[test]
+
+  fn test_unsafe_threads() {
+    let mut jvm = mock();
+    let unsafe_obj = the_unsafe(&mut jvm);
+    let thread = string(&mut jvm, "thread");
+
+    let descriptor = format!("({}Ljava/lang/Object;)I", UNSAFE);
+    let ret = call(
+      &mut jvm,
+      CLASS,
+      "threads",
+      &descriptor,
+      vec![unsafe_obj, thread],
+    )
+    .unwrap();
+    assert_eq!(ret, Type::Integer(3));
+  }
+}
@@ -0,0 +1,338 @@
+#[cfg(test)]
+mod tests {
+
+  use crate::runtime::{errors::JavaException, jvm::JVM, test_support::*, types::Type};
+
+  const CLASS: &str = "TestVarHandle";
+
+  // VarHandle of the JDK with its fields set, like VarHandles.makeFieldHandle and
+  // MethodHandles.arrayElementVarHandle do
+  fn var_handle(jvm: &mut JVM, classname: &str, fields: &[(&str, Type)]) -> Type {
+    object(
+      jvm,
+      &format!("java/lang/invoke/{}", classname),
+      fields.to_vec(),
+    )
+  }
+
+  fn field_handle(jvm: &mut JVM, family: &str, field: &str, field_type: Option<&str>) -> Type {
+    let offset = jvm.class_loader.get_field_offset(CLASS, field).unwrap();
+    let receiver_type = class(jvm, CLASS);
+
+    let mut fields = vec![
+      ("fieldOffset", Type::Long(offset)),
+      ("receiverType", receiver_type),
+    ];
+
+    if let Some(field_type) = field_type {
+      fields.push(("fieldType", class(jvm, field_type)));
+    }
+
+    var_handle(
+      jvm,
+      &format!("VarHandle{}$FieldInstanceReadWrite", family),
+      &fields,
+    )
+  }
+
+  fn byte_view(jvm: &mut JVM, family: &str, kind: &str, big_endian: bool) -> Type {
+    var_handle(
+      jvm,
+      &format!("VarHandleByteArrayAs{}${}", family, kind),
+      &[("be", Type::Boolean(big_endian))],
+    )
+  }
+
+  // String of a method taking the handles `args`
+  fn call_handles(jvm: &mut JVM, method: &str, args: Vec<Type>) -> String {
+    let descriptor = format!(
+      "({})Ljava/lang/String;",
+      "Ljava/lang/invoke/VarHandle;".repeat(args.len())
+    );
+
+    call_string(jvm, CLASS, method, &descriptor, args)
+  }
+
+  #[test]
+
+  fn test_varhandle_fields() {
+    let mut jvm = mock();
+
+    let handle = field_handle(&mut jvm, "Ints", "count", None);
+    assert_eq!(
+      call_handles(&mut jvm, "ints", vec![handle]),
+      "5 8 24 8 true false 10 12 12 21 21"
+    );
+
+    let handle = field_handle(&mut jvm, "Booleans", "flag", None);
+    assert_eq!(
+      call_handles(&mut jvm, "booleans", vec![handle]),
+      "false true false"
+    );
+
+    let handle = field_handle(&mut jvm, "Doubles", "ratio", None);
+    assert_eq!(
+      call_handles(&mut jvm, "doubles", vec![handle]),
+      "true false 0.0 0.5"
+    );
+
+    let handle = field_handle(&mut jvm, "References", "name", Some("java/lang/String"));
+    assert_eq!(
+      call_handles(&mut jvm, "references", vec![handle]),
+      "a true false c"
+    );
+
+    // static field, the base is the class like Unsafe.staticFieldBase
+    let offset = jvm.class_loader.get_field_offset(CLASS, "counter").unwrap();
+    let base = class(&mut jvm, CLASS);
+    let handle = var_handle(
+      &mut jvm,
+      "VarHandleLongs$FieldStaticReadWrite",
+      &[("base", base), ("fieldOffset", Type::Long(offset))],
+    );
+    assert_eq!(
+      call_handles(&mut jvm, "longs", vec![handle]),
+      "7 true -1 -1"
+    );
+  }
+
+  #[test]
+
+  fn test_varhandle_conversions() {
+    let mut jvm = mock();
+
+    // Integer unboxed and the short delta widened, then the int result boxed
+    let handle = field_handle(&mut jvm, "Ints", "count", None);
+    let value = jvm
+      .heap
+      .alloc_boxed(&mut jvm.class_loader, "I", Type::Integer(40))
+      .unwrap();
+
+    let ret = call(
+      &mut jvm,
+      CLASS,
+      "boxed",
+      "(Ljava/lang/invoke/VarHandle;Ljava/lang/Object;)Ljava/lang/Object;",
+      vec![handle, value],
+    )
+    .unwrap();
+    assert_eq!(
+      jvm.heap.get_boxed_value(ret.as_ref().unwrap()).unwrap(),
+      Some(("I", Type::Integer(40)))
+    );
+
+    let err = call(
+      &mut jvm,
+      CLASS,
+      "set",
+      "(Ljava/lang/invoke/VarHandle;Ljava/lang/Object;)V",
+      vec![handle, Type::Null],
+    )
+    .unwrap_err();
+    assert!(matches!(java_exception(err), JavaException::NullPointer));
+
+    // the values of reference variables are checked
+    let handle = field_handle(&mut jvm, "References", "name", Some("java/lang/String"));
+    let err = call(
+      &mut jvm,
+      CLASS,
+      "set",
+      "(Ljava/lang/invoke/VarHandle;Ljava/lang/Object;)V",
+      vec![handle, value],
+    )
+    .unwrap_err();
+    assert!(matches!(
+      java_exception(err),
+      JavaException::ClassCastException(_)
+    ));
+
+    // read-only handles of final fields and numeric modes of booleans
+    let offset = jvm.class_loader.get_field_offset(CLASS, "count").unwrap();
+    let receiver_type = class(&mut jvm, CLASS);
+    let handle = var_handle(
+      &mut jvm,
+      "VarHandleInts$FieldInstanceReadOnly",
+      &[
+        ("fieldOffset", Type::Long(offset)),
+        ("receiverType", receiver_type),
+      ],
+    );
+    let err = call(
+      &mut jvm,
+      CLASS,
+      "set",
+      "(Ljava/lang/invoke/VarHandle;Ljava/lang/Object;)V",
+      vec![handle, value],
+    )
+    .unwrap_err();
+    assert!(matches!(
+      java_exception(err),
+      JavaException::UnsupportedOperationException(_)
+    ));
+
+    let handle = field_handle(&mut jvm, "Booleans", "flag", None);
+    let boolean = jvm
+      .heap
+      .alloc_boxed(&mut jvm.class_loader, "Z", Type::Boolean(true))
+      .unwrap();
+    let err = call(
+      &mut jvm,
+      CLASS,
+      "boxed",
+      "(Ljava/lang/invoke/VarHandle;Ljava/lang/Object;)Ljava/lang/Object;",
+      vec![handle, boolean],
+    )
+    .unwrap_err();
+    assert!(matches!(
+      java_exception(err),
+      JavaException::UnsupportedOperationException(_)
+    ));
+  }
+
+  #[test]
+
+  fn test_varhandle_arrays() {
+    let mut jvm = mock();
+
+    let handle = var_handle(
+      &mut jvm,
+      "VarHandleInts$Array",
+      &[("abase", Type::Integer(0)), ("ashift", Type::Integer(2))],
+    );
+    let ints = array(&mut jvm, "I", vec![Type::Integer(0); 2]);
+
+    let ret = call(
+      &mut jvm,
+      CLASS,
+      "arrays",
+      "(Ljava/lang/invoke/VarHandle;[I)Ljava/lang/String;",
+      vec![handle, ints],
+    )
+    .unwrap();
+    assert_eq!(
+      jvm.heap.get_string(ret.as_ref().unwrap()).unwrap(),
+      "3 true 7 9"
+    );
+
+    let err = call(
+      &mut jvm,
+      CLASS,
+      "element",
+      "(Ljava/lang/invoke/VarHandle;[II)I",
+      vec![handle, ints, Type::Integer(2)],
+    )
+    .unwrap_err();
+    assert!(matches!(
+      java_exception(err),
+      JavaException::ArrayIndexOutOfBounds(2, 2)
+    ));
+
+    // the elements of a String[] are checked
+    let array_type = class(&mut jvm, "[Ljava/lang/String;");
+    let component_type = class(&mut jvm, "java/lang/String");
+    let handle = var_handle(
+      &mut jvm,
+      "VarHandleReferences$Array",
+      &[
+        ("abase", Type::Integer(0)),
+        ("ashift", Type::Integer(2)),
+        ("arrayType", array_type),
+        ("componentType", component_type),
+      ],
+    );
+    let strings = array(&mut jvm, "java/lang/String", vec![Type::Null]);
+    let string = jvm.heap.alloc_string(&mut jvm.class_loader, "s").unwrap();
+
+    call(
+      &mut jvm,
+      CLASS,
+      "store",
+      "(Ljava/lang/invoke/VarHandle;[Ljava/lang/Object;Ljava/lang/Object;)V",
+      vec![handle, strings, string],
+    )
+    .unwrap();
+
+    let element = *jvm
+      .heap
+      .get_array_instance(strings.as_ref().unwrap())
+      .unwrap()
+      .get(0)
+      .unwrap();
+    assert_eq!(element, string);
+
+    let err = call(
+      &mut jvm,
+      CLASS,
+      "store",
+      "(Ljava/lang/invoke/VarHandle;[Ljava/lang/Object;Ljava/lang/Object;)V",
+      vec![handle, strings, strings],
+    )
+    .unwrap_err();
+    assert!(matches!(
+      java_exception(err),
+      JavaException::ArrayStoreException(_)
+    ));
+  }
+
+  #[test]
+
+  fn test_varhandle_byte_views() {
+    let mut jvm = mock();
+
+    // plain accesses can be misaligned
+    let handle = byte_view(&mut jvm, "Ints", "ArrayHandle", true);
+    let bytes = array(&mut jvm, "B", vec![Type::Byte(0); 8]);
+
+    let ret = call(
+      &mut jvm,
+      CLASS,
+      "bytes",
+      "(Ljava/lang/invoke/VarHandle;[BI)Ljava/lang/String;",
+      vec![handle, bytes, Type::Integer(1)],
+    )
+    .unwrap();
+    assert_eq!(
+      jvm.heap.get_string(ret.as_ref().unwrap()).unwrap(),
+      "0 1 2 3 4 0 0 0 16909060"
+    );
+
+    let err = call(
+      &mut jvm,
+      CLASS,
+      "misaligned",
+      "(Ljava/lang/invoke/VarHandle;[B)Z",
+      vec![handle, bytes],
+    )
+    .unwrap_err();
+    assert!(matches!(
+      java_exception(err),
+      JavaException::IllegalStateException(_)
+    ));
+
+    let err = call(
+      &mut jvm,
+      CLASS,
+      "bytes",
+      "(Ljava/lang/invoke/VarHandle;[BI)Ljava/lang/String;",
+      vec![handle, bytes, Type::Integer(5)],
+    )
+    .unwrap_err();
+    assert!(matches!(
+      java_exception(err),
+      JavaException::ArrayIndexOutOfBounds(5, 8)
+    ));
+
+    let handle = byte_view(&mut jvm, "Longs", "ArrayHandle", false);
+    let bytes = array(&mut jvm, "B", vec![Type::Byte(0); 16]);
+
+    let ret = call(
+      &mut jvm,
+      CLASS,
+      "littleEndian",
+      "(Ljava/lang/invoke/VarHandle;[B)J",
+      vec![handle, bytes],
+    )
+    .unwrap();
+    assert_eq!(ret, Type::Long(0x0102030405060708 + 9 + 1));
+  }
+}
@@ -0,0 +1,257 @@
+#[cfg(test)]
+mod tests {
+
+  use crate::{
+    class_loader::{assembler::assemble, namespace::BOOTSTRAP_LOADER},
+    runtime::{errors::JavaException, jvm::JVM},
+  };
+
+  // `bipush 42; ireturn` in TestVerifier.answer
+  const ANSWER_CODE: [u8; 3] = [0x10, 0x2a, 0xac];
+
+  fn patched_bytes(code: [u8; 3]) -> Vec<u8> {
+    let mut data = std::fs::read("tests/classes/TestVerifier.class").unwrap();
+
+    let start = data
+      .windows(ANSWER_CODE.len())
+      .position(|w| w == ANSWER_CODE)
+      .unwrap();
+
+    data[start..start + code.len()].copy_from_slice(&code);
+
+    data
+  }
+
+  fn get_verify_error(jvm: &mut JVM, key: &str) -> String {
+    let err = jvm.link_class(key).unwrap_err();
+
+    match err.downcast_ref::<JavaException>() {
+      Some(JavaException::VerifyError(msg)) => msg.clone(),
+      _ => panic!("expected a VerifyError, got {}", err),
+    }
+  }
+
+  fn define_assembled(jvm: &mut JVM, source: &str) -> String {
+    jvm
+      .define_class(BOOTSTRAP_LOADER, None, &assemble(source).unwrap())
+      .unwrap()
+  }
+
+  #[test]
+
+  fn test_verify_valid_classes() {
+    let mut jvm = JVM::mock("tests/classes/", vec![], false).unwrap();
+
+    for entry in std::fs::read_dir("tests/classes/").unwrap() {
+      let path = entry.unwrap().path();
+
+      if path.extension().is_some_and(|ext| ext == "class") {
+        let name = path.file_stem().unwrap().to_str().unwrap().to_string();
+
+        jvm.class_loader.load_class(&name).unwrap();
+
+        jvm
+          .class_loader
+          .verify_class(&name)
+          .unwrap_or_else(|err| panic!("{} failed verification: {}", name, err));
+      }
+    }
+  }
+
+  #[test]
+
+  fn test_verify_bad_operand_type() {
+    let mut jvm = JVM::mock("tests/classes/", vec![], false).unwrap();
+
+    // aconst_null; nop; ireturn
+    let key = jvm
+      .define_class(BOOTSTRAP_LOADER, None, &patched_bytes([0x01, 0x00, 0xac]))
+      .unwrap();
+
+    let msg = get_verify_error(&mut jvm, &key);
+
+    assert!(msg.starts_with("Bad type on operand stack"), "{}", msg);
+    assert!(msg.contains("TestVerifier.answer()I"), "{}", msg);
+
+    // the class is not linked, its code can not run
+    assert!(jvm
+      .call_and_resolve_method(&key, "answer", "()I", vec![])
+      .is_err());
+  }
+
+  #[test]
+
+  fn test_verify_falling_off_code() {
+    let mut jvm = JVM::mock("tests/classes/", vec![], false).unwrap();
+
+    // bipush 42; nop
+    let key = jvm
+      .define_class(BOOTSTRAP_LOADER, None, &patched_bytes([0x10, 0x2a, 0x00]))
+      .unwrap();
+
+    let msg = get_verify_error(&mut jvm, &key);
+
+    assert!(
+      msg.starts_with("Falling off the end of the code"),
+      "{}",
+      msg
+    );
+  }
+
+  #[test]
+
+  fn test_verify_none() {
+    let mut jvm = JVM::mock("tests/classes/", vec![], false).unwrap();
+
+    jvm.set_verify(false);
+
+    let key = jvm
+      .define_class(BOOTSTRAP_LOADER, None, &patched_bytes([0x01, 0x00, 0xac]))
+      .unwrap();
+
+    jvm.link_class(&key).unwrap();
+
+    assert!(jvm.class_loader.get(&key).unwrap().is_linked());
+  }
+
+  #[test]
+
+  fn test_verify_unloadable_class() {
+    let mut jvm = JVM::mock("tests/classes/", vec![], false).unwrap();
+
+    let key = define_assembled(
+      &mut jvm,
+      "
+      .class public Unloadable
+      .version 61
+
+      .method public static pass(Ljava/lang/String;)V
+        aload_0
+        invokestatic Unloadable/take(LMissing;)V
+        return
+      .end method
+
+      .method public static take(LMissing;)V
+        return
+      .end method
+    ",
+    );
+
+    let msg = get_verify_error(&mut jvm, &key);
+
+    assert!(
+      msg.starts_with(
+        "Bad type on operand stack ('java/lang/String' is not assignable to 'Missing')"
+      ),
+      "{}",
+      msg
+    );
+  }
+
+  #[test]
+
+  fn test_verify_store_in_try_range() {
+    let mut jvm = JVM::mock("tests/classes/", vec![], false).unwrap();
+
+    // the handler reads an int from the local replaced by a string in the range
+    let key = define_assembled(
+      &mut jvm,
+      "
+      .class public StoreInTry
+      .version 61
+
+      .method public static store(I)I
+        .limit stack 1
+        .limit locals 2
+        .catch java/lang/Exception from Start to End using Handler
+        iconst_0
+        istore_1
+      Start:
+        ldc \"text\"
+        astore_1
+      End:
+        iconst_0
+        ireturn
+        .stack
+          locals Integer Integer
+          stack Object java/lang/Exception
+        .end stack
+      Handler:
+        iload_1
+        ireturn
+      .end method
+    ",
+    );
+
+    let msg = get_verify_error(&mut jvm, &key);
+
+    assert!(
+      msg.starts_with("Stack map does not match the one at exception handler"),
+      "{}",
+      msg
+    );
+    assert!(msg.ends_with("@4"), "{}", msg);
+  }
+
+  #[test]
+
+  fn test_verify_protected_access() {
+    let mut jvm = JVM::mock("tests/classes/", vec![], false).unwrap();
+
+    define_assembled(
+      &mut jvm,
+      "
+      .class public protect/Base
+      .field protected value I
+
+      .method protected get()I
+        iconst_0
+        ireturn
+      .end method
+    ",
+    );
+
+    // `peek` reads the protected member with `instruction`
+    let subclass = |name: &str, receiver: &str, instruction: &str| {
+      format!(
+        "
+        .class public {name}
+        .super protect/Base
+        .version 61
+
+        .method public static peek(L{receiver};)I
+          aload_0
+          {instruction}
+          ireturn
+        .end method
+      "
+      )
+    };
+
+    let field = "getfield protect/Base/value I";
+    let method = "invokevirtual protect/Base/get()I";
+
+    // through an object of the subclass
+    for (name, instruction) in [("Mine", field), ("MineToo", method)] {
+      let key = define_assembled(&mut jvm, &subclass(name, name, instruction));
+
+      jvm.link_class(&key).unwrap();
+    }
+
+    // through any object of the super class
+    for (name, instruction, mnemonic) in [
+      ("Theirs", field, "getfield"),
+      ("TheirsToo", method, "invokevirtual"),
+    ] {
+      let key = define_assembled(&mut jvm, &subclass(name, "protect/Base", instruction));
+
+      let msg = get_verify_error(&mut jvm, &key);
+
+      assert!(
+        msg.starts_with(&format!("Bad access to protected data in {}", mnemonic)),
+        "{}",
+        msg
+      );
+    }
+  }
+}
@@ -60,6 +60,32 @@ impl Type {
     }
   }
 
+  /// Push the value to the locals of a frame, a category 2 value takes two slots
+  pub(crate) fn push_to_locals(self, locals: &mut Vec<Type>) {
+    if self.get_category() == 2 {
+      locals.push(self);
+    }
+
+    locals.push(self);
+  }
+
+  /// Descriptor of the primitive type of the value, `None` for references
+  pub fn get_primitive_descriptor(&self) -> Option<&'static str> {
+    let descriptor = match self {
+      Type::Byte(_) => "B",
+      Type::Boolean(_) => "Z",
+      Type::Character(_) => "C",
+      Type::Short(_) => "S",
+      Type::Integer(_) => "I",
+      Type::Float(_) => "F",
+      Type::Long(_) => "J",
+      Type::Double(_) => "D",
+      _ => return None,
+    };
+
+    Some(descriptor)
+  }
+
   pub fn as_float(&self) -> Result<f32> {
     let value: f32 = match self {
       Type::Float(l) => *l,
@@ -215,6 +241,64 @@ impl Type {
       || type_str == "J"
   }
 
+  /// Widening primitive conversion (JLS 5.1.2) of `value` from the type `from` to `to`
+  ///
+  /// # Returns
+  ///
+  /// `None` if there is no such conversion
+  pub fn widen_primitive(from: &str, to: &str, value: Type) -> Result<Option<Type>> {
+    let widened = match (from, to) {
+      (a, p) if a == p => true,
+      ("B", "S" | "I" | "J" | "F" | "D") => true,
+      ("S" | "C", "I" | "J" | "F" | "D") => true,
+      ("I", "J" | "F" | "D") => true,
+      ("J", "F" | "D") => true,
+      ("F", "D") => true,
+      _ => false,
+    };
+
+    if !widened {
+      return Ok(None);
+    }
+
+    let value = match to {
+      "Z" => Type::Boolean(value.as_bool()?),
+      "B" => Type::Byte(value.as_byte()?),
+      "C" => Type::Integer(value.as_integer()?),
+      "S" => Type::Short(value.as_short()?),
+      "I" => Type::Integer(value.as_integer()?),
+      "J" => Type::Long(value.as_long()?),
+      "F" => match value {
+        Type::Float(f) => Type::Float(f),
+        _ => Type::Float(value.as_long()? as f32),
+      },
+      _ => Type::Double(value.as_double()?),
+    };
+
+    Ok(Some(value))
+  }
+
+  /// Value in the representation of the fields and the elements of the arrays of the type
+  /// `var_type`
+  pub(crate) fn to_stored_value(var_type: &str, value: Type) -> Result<Type> {
+    let value = match var_type {
+      "Z" => Type::Boolean(value.as_bool()?),
+      "B" => Type::Byte(value.as_byte()?),
+      "C" => Type::Character(value.as_integer()? as i8),
+      "S" => Type::Short(value.as_short()?),
+      "I" => Type::Integer(value.as_integer()?),
+      "J" => Type::Long(value.as_long()?),
+      "F" => match value {
+        Type::Float(value) => Type::Float(value),
+        value => Type::Float(value.as_double()? as f32),
+      },
+      "D" => Type::Double(value.as_double()?),
+      _ => value,
+    };
+
+    Ok(value)
+  }
+
   fn is_class_integer(type_str: &str) -> bool {
     type_str == "B"
       || type_str == "Z"
@@ -436,11 +520,11 @@ impl ObjectInstance {
     }
   }
 
-  pub fn get_parent(&self) -> Option<RwLockReadGuard<ObjectInstance>> {
+  pub fn get_parent(&self) -> Option<RwLockReadGuard<'_, ObjectInstance>> {
     self.parent_obj.as_ref().map(|arc| arc.read().unwrap())
   }
 
-  pub fn get_parent_mut(&self) -> Option<RwLockWriteGuard<ObjectInstance>> {
+  pub fn get_parent_mut(&self) -> Option<RwLockWriteGuard<'_, ObjectInstance>> {
     self.parent_obj.as_ref().map(|arc| arc.write().unwrap())
   }
 
@@ -477,6 +561,62 @@ impl ObjectInstance {
     }
   }
 
+  /// Value of the field `fieldname` referenced through the class `classname`, so that a field
+  /// hidden by one of a subclass with the same name is still found
+  pub fn get_field_from(&self, classname: &str, fieldname: &str) -> Result<types::Type> {
+    if self.classname != classname {
+      if let Some(parent_obj) = self.get_parent() {
+        if parent_obj.has_class(classname) {
+          return parent_obj.get_field_from(classname, fieldname);
+        }
+      }
+    }
+
+    self.get_field(fieldname)
+  }
+
+  /// Set the field `fieldname` referenced through the class `classname`
+  pub fn put_field_from(
+    &mut self,
+    classname: &str,
+    fieldname: &str,
+    fieldvalue: types::Type,
+  ) -> Result<()> {
+    if self.classname != classname {
+      if let Some(mut parent_obj) = self.get_parent_mut() {
+        if parent_obj.has_class(classname) {
+          return parent_obj.put_field_from(classname, fieldname, fieldvalue);
+        }
+      }
+    }
+
+    self.put_field(fieldname, fieldvalue)
+  }
+
+  // is `classname` the class of the instance or one of its super classes ?
+  fn has_class(&self, classname: &str) -> bool {
+    self.classname == classname
+      || self
+        .get_parent()
+        .is_some_and(|parent_obj| parent_obj.has_class(classname))
+  }
+
+  /// Values of the fields of the instance and of its parent classes, with the class declaring
+  /// them
+  pub fn get_field_values(&self) -> Vec<(String, String, types::Type)> {
+    let mut values: Vec<(String, String, types::Type)> = self
+      .fields
+      .iter()
+      .map(|(name, value)| (self.classname.clone(), name.clone(), *value))
+      .collect();
+
+    if let Some(parent_obj) = self.get_parent() {
+      values.extend(parent_obj.get_field_values());
+    }
+
+    values
+  }
+
   pub fn put_field(&mut self, fieldname: &str, fieldvalue: types::Type) -> Result<()> {
     match self.fields.get(fieldname) {
       Some(_) => self.fields.insert(fieldname.to_string(), fieldvalue),
@@ -5,7 +5,7 @@ use std::{
   sync::atomic::{AtomicU64, Ordering},
 };
 
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{eyre, Result};
 use walkdir::WalkDir;
 
 use crate::runtime::*;
@@ -58,14 +58,39 @@ pub trait ParseInfo<T> {
   fn parse(bytes: &[u8]) -> Result<T>;
 }
 
-#[allow(dead_code)]
-pub fn get_slice(v: &[u8], start: usize, size: usize) -> &[u8] {
-  &v[start..(start + size)]
+/// Inverse of the parsing, to write a class file back to bytes
+pub trait WriteInfo {
+  /// Append the class file representation of `self` to `bytes`
+  fn write(&self, bytes: &mut Vec<u8>);
 }
 
-#[allow(dead_code)]
-pub fn get_slice_arr(v: &[u8], start: usize, size: usize) -> &[u8] {
-  &v[start..(start + size)]
+/// `size` bytes of `v` from `start`, `ClassFormatError` if `v` is too short
+pub fn get_slice(v: &[u8], start: usize, size: usize) -> Result<&[u8]> {
+  start
+    .checked_add(size)
+    .and_then(|end| v.get(start..end))
+    .ok_or_else(|| {
+      eyre!(errors::JavaException::ClassFormatError(
+        "Truncated class file".to_string()
+      ))
+    })
+}
+
+/// Bytes of `v` from `start` to the end, `ClassFormatError` if `v` is too short
+pub fn get_tail(v: &[u8], start: usize) -> Result<&[u8]> {
+  get_slice(v, start, v.len().saturating_sub(start))
+}
+
+pub fn ju1_at(v: &[u8], index: usize) -> Result<ju1> {
+  Ok(get_slice(v, index, 1)?[0])
+}
+
+pub fn ju2_at(v: &[u8], index: usize) -> Result<ju2> {
+  ju2_from_bytes(get_slice(v, index, 2)?)
+}
+
+pub fn ju4_at(v: &[u8], index: usize) -> Result<ju4> {
+  ju4_from_bytes(get_slice(v, index, 4)?)
 }
 
 #[allow(dead_code)]
@@ -135,54 +160,31 @@ pub(crate) fn get_parameters_type_descriptor(desc: &str) -> &str {
 pub(crate) fn parse_parameter_types(params: &str) -> Vec<&str> {
   let mut types = vec![];
 
-  let chars: Vec<char> = params.chars().collect();
+  // the delimiters are ASCII, so an index of a byte before or after one of them is a char
+  // boundary even when the class names are not
+  let bytes = params.as_bytes();
 
   let mut i = 0;
 
-  while i < chars.len() {
+  while i < bytes.len() {
     let start = i;
 
-    let ch = chars[i];
-
-    match ch {
-      'B' | 'C' | 'D' | 'F' | 'I' | 'J' | 'S' | 'Z' => {
-        types.push(&params[i..=i]);
-
-        i += 1;
-      }
-      'L' => {
-        // Object type: Ljava/lang/String;
-        i += 1;
-
-        while i < chars.len() && chars[i] != ';' {
-          i += 1;
-        }
-
-        i += 1; // include ;
-        types.push(&params[start..i]);
-      }
-      '[' => {
-        // Array: can be multiple '[' + type
-        while i < chars.len() && chars[i] == '[' {
-          i += 1;
-        }
-
-        if i < chars.len() && chars[i] == 'L' {
-          i += 1;
-
-          while i < chars.len() && chars[i] != ';' {
-            i += 1;
-          }
-
-          i += 1;
-        } else {
-          i += 1;
-        }
-
-        types.push(&params[start..i]);
-      }
+    // Array: can be multiple '[' + type
+    while i < bytes.len() && bytes[i] == b'[' {
+      i += 1;
+    }
+
+    match bytes.get(i) {
+      Some(b'B' | b'C' | b'D' | b'F' | b'I' | b'J' | b'S' | b'Z') => i += 1,
+      // Object type: Ljava/lang/String;
+      Some(b'L') => match params[i..].find(';') {
+        Some(end) => i += end + 1,
+        None => break,
+      },
       _ => break,
     }
+
+    types.push(&params[start..i]);
   }
 
   types
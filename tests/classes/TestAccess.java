@@ -0,0 +1,25 @@
+public class TestAccess {
+
+  private int secret = 42;
+
+  static class Inner {
+    private int hidden = 7;
+
+    private int peek(TestAccess outer) {
+      return outer.secret;
+    }
+  }
+
+  public static int _nestmates() {
+    Inner inner = new Inner();
+    return inner.peek(new TestAccess()) + inner.hidden;
+  }
+
+  int shared() {
+    return secret;
+  }
+}
+
+// not a nest mate of TestAccess, its private members are not inherited
+class TestAccessChild extends TestAccess {
+}
@@ -0,0 +1,29 @@
+import java.lang.annotation.ElementType;
+import java.lang.annotation.Retention;
+import java.lang.annotation.RetentionPolicy;
+import java.lang.annotation.Target;
+import java.util.List;
+
+@TestAnnotations.Tag("class")
+public class TestAnnotations {
+
+  @Retention(RetentionPolicy.RUNTIME)
+  @interface Tag {
+    String value() default "none";
+
+    int priority() default 5;
+  }
+
+  @Retention(RetentionPolicy.RUNTIME)
+  @Target(ElementType.TYPE_USE)
+  @interface NonNull {}
+
+  @Tag(priority = 1)
+  private List<@NonNull String> names;
+
+  @Tag("method")
+  public static int tagged(@Tag("first") int a, int b, @Tag int c) {
+    @NonNull Object local = "local";
+    return a + b + c + local.hashCode();
+  }
+}
@@ -0,0 +1,78 @@
+import java.nio.ByteBuffer;
+import java.nio.ByteOrder;
+import java.nio.IntBuffer;
+
+import sun.nio.ch.DirectBuffer;
+
+// Direct buffers used by test_bytebuffer.rs
+public class TestByteBuffer {
+
+  static String direct() {
+    ByteBuffer b = ByteBuffer.allocateDirect(16);
+    b.putInt(0x01020304);
+    b.putLong(8, -2L);
+    b.put(4, (byte) 5);
+
+    int big = b.getInt(0);
+    long value = b.getLong(8);
+    b.order(ByteOrder.LITTLE_ENDIAN);
+    int little = b.getInt(0);
+
+    return b.isDirect() + " " + big + " " + little + " " + b.get(4) + " " + value + " "
+        + b.position();
+  }
+
+  static String bulk() {
+    ByteBuffer b = ByteBuffer.allocateDirect(8);
+    b.put(new byte[] {1, 2, 3, 4, 5, 6});
+    b.flip();
+
+    byte[] out = new byte[4];
+    b.get(out);
+
+    // views and copies share the memory of the buffer
+    ByteBuffer slice = b.slice();
+    ByteBuffer copy = ByteBuffer.allocate(2);
+    copy.put(slice.duplicate());
+
+    IntBuffer ints = ByteBuffer.allocateDirect(8).order(ByteOrder.nativeOrder()).asIntBuffer();
+    ints.put(new int[] {7, 8});
+    int[] values = new int[2];
+    ints.flip();
+    ints.get(values);
+
+    return out[0] + " " + out[3] + " " + slice.remaining() + " " + slice.get(1) + " "
+        + copy.get(0) + " " + values[0] + " " + values[1];
+  }
+
+  static void free() {
+    ByteBuffer b = ByteBuffer.allocateDirect(64);
+    ((DirectBuffer) b).cleaner().clean();
+  }
+
+  static void garbage(int count) {
+    for (int i = 0; i < count; i++) {
+      ByteBuffer.allocateDirect(32).putInt(0, i);
+    }
+
+    System.gc();
+  }
+
+  // more than the maximum direct memory, reserving it collects the previous buffers
+  static void exhaust() {
+    for (int i = 0; i < 80; i++) {
+      ByteBuffer.allocateDirect(1024 * 1024);
+    }
+  }
+
+  static ByteBuffer kept;
+
+  static void keep() {
+    kept = ByteBuffer.allocateDirect(16);
+    ByteBuffer.allocateDirect(16);
+
+    System.gc();
+
+    kept.putInt(0, 1);
+  }
+}
@@ -0,0 +1,25 @@
+public class TestClassLoader {
+
+  static class Plugin {
+    static int counter;
+
+    static int bump() {
+      counter += 1;
+      return counter;
+    }
+  }
+
+  // inherits `plug` when defined by another loader
+  static class Host extends TestClassLoader {
+  }
+
+  public static int plug(Plugin plugin) {
+    return Plugin.counter;
+  }
+
+  static class ByteLoader extends ClassLoader {
+    ByteLoader() {
+      super(null);
+    }
+  }
+}
@@ -0,0 +1,64 @@
+import java.lang.invoke.MethodHandles;
+
+// Bootstrap methods of the dynamically-computed constants (CONSTANT_Dynamic)
+// assembled in test_condy.rs, javac does not emit them for this code
+public class TestCondy {
+
+  private static int calls;
+
+  public static int answer(MethodHandles.Lookup lookup, String name, Class<?> type) {
+    calls++;
+
+    return 42;
+  }
+
+  public static String greeting(
+      MethodHandles.Lookup lookup, String name, Class<?> type, String who, int times) {
+    calls++;
+
+    return name + " " + who.repeat(times);
+  }
+
+  // a long computed from the other constants, boxed in the array of the varargs
+  public static long sum(
+      MethodHandles.Lookup lookup, String name, Class<?> type, long first, Object... rest) {
+    long sum = first;
+
+    for (Object value : rest) {
+      sum += ((Number) value).longValue();
+    }
+
+    return sum;
+  }
+
+  public static Object lookupClass(MethodHandles.Lookup lookup, String name, Class<?> type) {
+    return lookup.lookupClass();
+  }
+
+  public static Object type(MethodHandles.Lookup lookup, String name, Class<?> type) {
+    return type;
+  }
+
+  public static Object fail(MethodHandles.Lookup lookup, String name, Class<?> type) {
+    calls++;
+
+    throw new IllegalStateException(name);
+  }
+
+  public static Object error(MethodHandles.Lookup lookup, String name, Class<?> type) {
+    throw new AssertionError();
+  }
+
+  public static Object string(MethodHandles.Lookup lookup, String name, Class<?> type) {
+    return name;
+  }
+
+  public static Object identity(
+      MethodHandles.Lookup lookup, String name, Class<?> type, Object value) {
+    return value;
+  }
+
+  public static int getCalls() {
+    return calls;
+  }
+}
@@ -0,0 +1,142 @@
+import java.io.FileDescriptor;
+import java.io.FileInputStream;
+import java.io.FileOutputStream;
+import java.io.IOException;
+
+import jdk.internal.ref.Cleaner;
+
+// Java side of test_embedding.rs, called from Rust with JVM::invoke_static and JVM::invoke
+public class TestEmbedding {
+
+  static boolean cleaned;
+
+  int count;
+
+  TestEmbedding(int count) {
+    this.count = count;
+  }
+
+  int increment(int by) {
+    count += by;
+    return count;
+  }
+
+  String describe() {
+    return "base";
+  }
+
+  static class Child extends TestEmbedding {
+    Child() {
+      super(0);
+    }
+
+    @Override
+    String describe() {
+      return "child";
+    }
+  }
+
+  static class Clean implements Runnable {
+    public void run() {
+      cleaned = true;
+    }
+  }
+
+  static class Failure extends RuntimeException {
+    Failure(String message) {
+      super(message);
+    }
+  }
+
+  static TestEmbedding child() {
+    return new Child();
+  }
+
+  static int add(int a, int b) {
+    return a + b;
+  }
+
+  static long twice(long value) {
+    return value * 2;
+  }
+
+  static boolean isEven(int value) {
+    return value % 2 == 0;
+  }
+
+  static char next(char c) {
+    return (char) (c + 1);
+  }
+
+  static double half(double value) {
+    return value / 2;
+  }
+
+  static String greet(String name) {
+    return "hello ".concat(name);
+  }
+
+  static int sum(int[] values) {
+    int sum = 0;
+    for (int value : values) {
+      sum += value;
+    }
+    return sum;
+  }
+
+  static int[] range(int n) {
+    int[] values = new int[n];
+    for (int i = 0; i < n; i++) {
+      values[i] = i;
+    }
+    return values;
+  }
+
+  static int totalLength(String[] words) {
+    int length = 0;
+    for (String word : words) {
+      length += word.length();
+    }
+    return length;
+  }
+
+  // Long.valueOf does not need the VM to be initialized, unlike Integer.valueOf
+  static Long successor(Long value) {
+    return value == null ? null : value + 1;
+  }
+
+  static Object identity(Object value) {
+    return value;
+  }
+
+  static void fail(String message) {
+    throw new IllegalStateException(message);
+  }
+
+  static void failCustom() {
+    throw new Failure("custom");
+  }
+
+  static void failWrapped() {
+    throw new IllegalStateException("wrapped", new Failure("cause"));
+  }
+
+  static void write(byte[] bytes, boolean err) throws IOException {
+    new FileOutputStream(err ? FileDescriptor.err : FileDescriptor.out).write(bytes);
+  }
+
+  static int read() throws IOException {
+    return new FileInputStream(FileDescriptor.in).read();
+  }
+
+  // an object running a cleaner once it is unreachable
+  static Object tracked() {
+    Object obj = new Object();
+    Cleaner.create(obj, new Clean());
+    return obj;
+  }
+
+  static boolean isCleaned() {
+    return cleaned;
+  }
+}
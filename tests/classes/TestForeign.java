@@ -0,0 +1,16 @@
+import jdk.internal.misc.Unsafe;
+
+// Upcall targets of test_foreign.rs
+public class TestForeign {
+
+  static int calls;
+
+  // comparator of qsort, the elements are ints
+  static int compare(long a, long b) {
+    calls++;
+
+    Unsafe unsafe = Unsafe.getUnsafe();
+
+    return Integer.compare(unsafe.getInt(a), unsafe.getInt(b));
+  }
+}
@@ -0,0 +1,45 @@
+import java.lang.invoke.CallSite;
+import java.lang.invoke.LambdaMetafactory;
+import java.lang.invoke.MethodHandle;
+import java.lang.invoke.MethodHandles;
+import java.lang.invoke.MethodType;
+import java.lang.invoke.StringConcatFactory;
+import java.util.function.IntUnaryOperator;
+
+// bootstraps of the JDK called directly, instead of being linked by the VM
+public class TestHidden {
+
+  static IntUnaryOperator lambda;
+
+  static int twice(int value) {
+    return 2 * value;
+  }
+
+  public static int metafactory() throws Throwable {
+    MethodHandles.Lookup lookup = MethodHandles.lookup();
+
+    MethodType type = MethodType.methodType(int.class, int.class);
+    MethodHandle impl = lookup.findStatic(TestHidden.class, "twice", type);
+
+    CallSite site = LambdaMetafactory.metafactory(
+        lookup, "applyAsInt", MethodType.methodType(IntUnaryOperator.class), type, impl, type);
+
+    lambda = (IntUnaryOperator) site.getTarget().invoke();
+
+    return lambda.applyAsInt(21);
+  }
+
+  public static boolean isLambdaHidden() {
+    return lambda.getClass().isHidden();
+  }
+
+  public static String concat() throws Throwable {
+    CallSite site = StringConcatFactory.makeConcatWithConstants(
+        MethodHandles.lookup(),
+        "concat",
+        MethodType.methodType(String.class, String.class, int.class),
+        "\u0001-\u0001");
+
+    return (String) site.getTarget().invoke("a", 1);
+  }
+}
@@ -0,0 +1,85 @@
+public class TestInvoke {
+  abstract static class Base {
+    abstract String name();
+
+    String describe() {
+      return "base";
+    }
+
+    private String secret() {
+      return "base secret";
+    }
+  }
+
+  static class Derived extends Base {
+    String name() {
+      return "derived";
+    }
+
+    String describe() {
+      return "derived";
+    }
+
+    String secret() {
+      return "derived secret";
+    }
+  }
+
+  interface Shape {
+    int sides();
+  }
+
+  static class Square implements Shape {
+    public int sides() {
+      return 4;
+    }
+  }
+
+  int value;
+
+  public TestInvoke(int value) {
+    this.value = value;
+  }
+
+  public TestInvoke(int a, int b) {
+    this.value = a / b;
+  }
+
+  static long add(int a, long b) {
+    return a + b;
+  }
+
+  static double half(double d) {
+    return d / 2;
+  }
+
+  static char next(char c) {
+    return (char) (c + 1);
+  }
+
+  static boolean not(boolean b) {
+    return !b;
+  }
+
+  static int sum(int... values) {
+    int sum = 0;
+    for (int v : values) {
+      sum += v;
+    }
+    return sum;
+  }
+
+  static void nothing() {}
+
+  static int divide(int a, int b) {
+    return a / b;
+  }
+
+  int getValue() {
+    return value;
+  }
+
+  String concat(String prefix) {
+    return prefix;
+  }
+}
@@ -0,0 +1,127 @@
+// Native methods of test_jni.rs, implemented by tests/native/testjni.c
+public class TestJni {
+
+  static int counter;
+
+  int value;
+
+  String name;
+
+  TestJni(int value, String name) {
+    this.value = value;
+    this.name = name;
+  }
+
+  // short name Java_TestJni_add
+  static native int add(int a, int b);
+
+  // overloaded, long names Java_TestJni_sum__JD and Java_TestJni_sum___3I
+  static native double sum(long a, double b);
+
+  static native int sum(int[] values);
+
+  // bound by RegisterNatives in JNI_OnLoad
+  static native String greet(String name);
+
+  native int getValue();
+
+  native void setName(String name);
+
+  static native String concat(String[] parts);
+
+  // the native code calls back the Java methods below
+  static native double callback(float a, double b, long c, int d, int e, int f, double g);
+
+  static native Object create(int value, String name);
+
+  static native int increment();
+
+  static native void fail(String message);
+
+  static native boolean catchFailure();
+
+  static native byte[] reverse(byte[] bytes);
+
+  // the references given to the native code survive a collection
+  static native int keep(Object obj);
+
+  static native Object kept();
+
+  static double mix(float a, double b, long c, int d, int e, int f, double g) {
+    return a + b + c + d + e + f + g;
+  }
+
+  String describe() {
+    return name + "=" + value;
+  }
+
+  static void thrower() {
+    throw new IllegalStateException("from java");
+  }
+
+  static int testAdd() {
+    return add(40, 2);
+  }
+
+  static double testSum() {
+    return sum(3L, 0.5) + sum(new int[] {1, 2, 3, 4});
+  }
+
+  static String testGreet() {
+    return greet("jni");
+  }
+
+  static String testFields() {
+    TestJni obj = new TestJni(7, "seven");
+    obj.setName("eight");
+    obj.value = obj.getValue() + 1;
+    return obj.describe();
+  }
+
+  static String testConcat() {
+    return concat(new String[] {"a", "\u00e9", "\u20ac", "z"});
+  }
+
+  static double testCallback() {
+    return callback(1.5f, 2.25, 3L, 4, 5, 6, 7.125);
+  }
+
+  static String testCreate() {
+    return ((TestJni) create(3, "three")).describe();
+  }
+
+  static int testStatics() {
+    increment();
+    return increment();
+  }
+
+  static String testFail() {
+    try {
+      fail("from native");
+      return "not thrown";
+    } catch (IllegalArgumentException e) {
+      return e.getMessage();
+    }
+  }
+
+  static boolean testCatch() {
+    return catchFailure();
+  }
+
+  static int testReverse() {
+    byte[] reversed = reverse(new byte[] {1, 2, 3, 4, 5});
+    int digits = 0;
+    for (byte digit : reversed) {
+      digits = digits * 10 + digit;
+    }
+    return digits;
+  }
+
+  static int testKeep(Object obj) {
+    return keep(obj);
+  }
+
+  static Object testKept() {
+    return kept();
+  }
+}
@@ -0,0 +1,146 @@
+import java.io.Serializable;
+
+public class TestLambda {
+  interface IntOp {
+    int apply(int a, int b);
+  }
+
+  interface Mapper<T, R> {
+    R map(T t);
+  }
+
+  interface Getter<T> {
+    T get();
+  }
+
+  // `get` overrides a method with another erasure, the lambda needs a bridge
+  interface StringGetter extends Getter<String> {
+    String get();
+
+    default String twice() {
+      return get() + get();
+    }
+  }
+
+  interface Consumer<T> {
+    int accept(T t);
+  }
+
+  interface StringConsumer {
+    int accept(String s);
+  }
+
+  // no bridge in the interface, the metafactory adds `accept(Object)`
+  interface Both extends Consumer<String>, StringConsumer {}
+
+  interface Marker {}
+
+  interface Shape {
+    int sides();
+  }
+
+  interface SerializableOp extends IntOp, Serializable {}
+
+  interface PointMaker {
+    Point make(int x, int y);
+  }
+
+  interface Measure {
+    long measure(String s);
+  }
+
+  interface Widener {
+    double widen(long value);
+  }
+
+  static class Point {
+    int x;
+    int y;
+
+    Point(int x, int y) {
+      this.x = x;
+      this.y = y;
+    }
+  }
+
+  static class Square implements Shape {
+    public int sides() {
+      return 4;
+    }
+  }
+
+  int base;
+
+  TestLambda(int base) {
+    this.base = base;
+  }
+
+  static int twice(int value) {
+    return value * 2;
+  }
+
+  static IntOp adder() {
+    return (a, b) -> a + b;
+  }
+
+  static int nonCapturing() {
+    return adder().apply(2, 3);
+  }
+
+  static boolean sameInstance() {
+    return adder() == adder();
+  }
+
+  static long capturing(long offset, double scale, String suffix) {
+    Measure measure = s -> (long) ((s + suffix).length() * scale) + offset;
+    return measure.measure("abc");
+  }
+
+  int capturingThis(int value) {
+    IntOp op = (a, b) -> a * base + b;
+    return op.apply(value, 1);
+  }
+
+  static int methodReferences() {
+    Mapper<String, Integer> length = String::length;
+    Mapper<Integer, Integer> doubler = TestLambda::twice;
+    Mapper<Shape, Integer> sides = Shape::sides;
+    PointMaker maker = Point::new;
+
+    Point p = maker.make(doubler.map(length.map("hello")), sides.map(new Square()));
+
+    return p.x * 100 + p.y;
+  }
+
+  static double widening() {
+    Widener widener = v -> v;
+    return widener.widen(7L);
+  }
+
+  static String bridges() {
+    StringGetter getter = () -> "ab";
+    Getter<String> erased = getter;
+    return erased.get() + getter.twice();
+  }
+
+  static int metafactoryBridges() {
+    Both both = s -> s.length();
+    Consumer<String> consumer = both;
+    StringConsumer stringConsumer = both;
+    return consumer.accept("abc") * 10 + stringConsumer.accept("ab");
+  }
+
+  static boolean markers() {
+    IntOp op = (IntOp & Marker) (a, b) -> a - b;
+    return op instanceof Marker && op.apply(5, 3) == 2;
+  }
+
+  static SerializableOp serializable(int factor) {
+    return (a, b) -> (a + b) * factor;
+  }
+
+  static Integer unboxNull() {
+    Mapper<Integer, Integer> doubler = TestLambda::twice;
+    return doubler.map(null);
+  }
+}
@@ -0,0 +1,229 @@
+import java.lang.invoke.MethodHandle;
+
+// Targets of the MethodHandles built by test_methodhandle.rs, and the call sites invoking
+// them
+public class TestMethodHandle {
+
+  interface Shape {
+    int sides();
+  }
+
+  static class Square implements Shape {
+    public int sides() {
+      return 4;
+    }
+  }
+
+  static class Counter extends TestMethodHandle {
+    Counter(int value) {
+      super(value);
+    }
+
+    int getValue() {
+      return value + 100;
+    }
+  }
+
+  int value;
+  static String label = "none";
+
+  TestMethodHandle(int value) {
+    this.value = value;
+  }
+
+  int getValue() {
+    return value;
+  }
+
+  static int add(int a, int b) {
+    return a + b;
+  }
+
+  static long twice(long a) {
+    return a * 2;
+  }
+
+  static int negate(int a) {
+    return -a;
+  }
+
+  static int square(int a) {
+    return a * a;
+  }
+
+  static boolean isPositive(int a) {
+    return a > 0;
+  }
+
+  static int length(String s) {
+    return s.length();
+  }
+
+  static String concat(String a, String b) {
+    return a + b;
+  }
+
+  static String first(String s) {
+    return "first " + s;
+  }
+
+  static String second(String s) {
+    return "second " + s;
+  }
+
+  static void record(String s) {
+    label = s;
+  }
+
+  static String check(String s) {
+    if (s.isEmpty()) {
+      throw new IllegalStateException("empty");
+    }
+
+    if (s.equals("?")) {
+      throw new UnsupportedOperationException("unknown");
+    }
+
+    return s;
+  }
+
+  static String recover(IllegalStateException e, String s) {
+    return "recovered";
+  }
+
+  static String cleanup(Throwable t, String result, String s) {
+    label = t == null ? "done" : "failed";
+    return result + "!";
+  }
+
+  static int sum(int[] values) {
+    int sum = 0;
+    for (int value : values) {
+      sum += value;
+    }
+    return sum;
+  }
+
+  static int[] ints(int a, int b) {
+    return new int[] {a, b};
+  }
+
+  static Object[] pack(String s) {
+    return new Object[] {s};
+  }
+
+  static String unpack(Object o) {
+    return (String) o;
+  }
+
+  static Object[] arguments(Object... args) {
+    return args;
+  }
+
+  // clauses of a loop summing the integers up to n: (i, sum, n)
+  static int zero(int n) {
+    return 0;
+  }
+
+  static int next(int i, int sum, int n) {
+    return i + 1;
+  }
+
+  static boolean notAfter(int i, int sum, int n) {
+    return i <= n;
+  }
+
+  static int accumulate(int i, int sum, int n) {
+    return sum + i;
+  }
+
+  static boolean always(int i, int sum, int n) {
+    return true;
+  }
+
+  static int result(int i, int sum, int n) {
+    return sum;
+  }
+
+  static int exactI(MethodHandle mh, int a) throws Throwable {
+    return (int) mh.invokeExact(a);
+  }
+
+  static int exactII(MethodHandle mh, int a, int b) throws Throwable {
+    return (int) mh.invokeExact(a, b);
+  }
+
+  static long exactJ(MethodHandle mh, long a) throws Throwable {
+    return (long) mh.invokeExact(a);
+  }
+
+  static int exactS(MethodHandle mh, String s) throws Throwable {
+    return (int) mh.invokeExact(s);
+  }
+
+  static String exactString(MethodHandle mh, String s) throws Throwable {
+    return (String) mh.invokeExact(s);
+  }
+
+  static String exactSwitch(MethodHandle mh, int i, String s) throws Throwable {
+    return (String) mh.invokeExact(i, s);
+  }
+
+  static int exactArray(MethodHandle mh, int[] values) throws Throwable {
+    return (int) mh.invokeExact(values);
+  }
+
+  static int exactReceiver(MethodHandle mh, TestMethodHandle t) throws Throwable {
+    return (int) mh.invokeExact(t);
+  }
+
+  static int exactShape(MethodHandle mh, Shape s) throws Throwable {
+    return (int) mh.invokeExact(s);
+  }
+
+  static int exactNew(MethodHandle mh, int value) throws Throwable {
+    return ((TestMethodHandle) mh.invokeExact(value)).value;
+  }
+
+  static int exactPut(MethodHandle mh, TestMethodHandle t, int value) throws Throwable {
+    mh.invokeExact(t, value);
+    return t.value;
+  }
+
+  static String exactRecord(MethodHandle mh, String s) throws Throwable {
+    mh.invokeExact(s);
+    return label;
+  }
+
+  static String exactLabel(MethodHandle mh) throws Throwable {
+    return (String) mh.invokeExact();
+  }
+
+  static Object invokeObject(MethodHandle mh, Object o) throws Throwable {
+    return mh.invoke(o);
+  }
+
+  static long invokeLong(MethodHandle mh, int a) throws Throwable {
+    return (long) mh.invoke(a);
+  }
+
+  static int invokeShorts(MethodHandle mh, short a, short b) throws Throwable {
+    return (int) mh.invoke(a, b);
+  }
+
+  static int invokeNarrowing(MethodHandle mh, long a) throws Throwable {
+    return (int) mh.invoke(a);
+  }
+
+  static Object invokeRecord(MethodHandle mh, String s) throws Throwable {
+    return mh.invoke(s);
+  }
+
+  static int invokeVarargs(MethodHandle mh, int a, int b, int c) throws Throwable {
+    return (int) mh.invoke(a, b, c);
+  }
+
+  static int invokeArray(MethodHandle mh, int[] values) throws Throwable {
+    return (int) mh.invoke(values);
+  }
+}
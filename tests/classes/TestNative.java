@@ -0,0 +1,63 @@
+// Native methods of test_native.rs, implemented by the test with JVM::register_native
+public class TestNative {
+
+  int base;
+
+  TestNative(int base) {
+    this.base = base;
+  }
+
+  static native int twice(int value);
+
+  native long scale(long factor);
+
+  static native String greet(String name);
+
+  static native void record(double value);
+
+  // implemented neither by the VM nor by a JNI library
+  static native int missing(int a, String b);
+
+  // named like natives of the JDK
+  static native void initialize();
+
+  static native int floatToRawIntBits(float value);
+
+  static int testTwice() {
+    return twice(21);
+  }
+
+  static long testScale() {
+    return new TestNative(6).scale(7L);
+  }
+
+  static String testGreet() {
+    return greet("native");
+  }
+
+  static void testRecord() {
+    record(1.5);
+    record(2.5);
+  }
+
+  static boolean testMissing() {
+    try {
+      missing(1, "a");
+      return false;
+    } catch (UnsatisfiedLinkError e) {
+      return true;
+    }
+  }
+
+  static void testInitialize() {
+    initialize();
+  }
+
+  static int testFloatBits() {
+    return floatToRawIntBits(1.0f);
+  }
+
+  static int testJdkFloatBits() {
+    return Float.floatToRawIntBits(1.0f);
+  }
+}
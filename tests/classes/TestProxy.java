@@ -0,0 +1,96 @@
+import java.lang.annotation.*;
+import java.lang.reflect.*;
+
+// proxies created by the JDK, with Proxy.newProxyInstance and Class.getAnnotation
+public class TestProxy {
+  interface Greeter {
+    String greet(String name);
+
+    String fail(String name);
+
+    String crash(String name);
+  }
+
+  @Retention(RetentionPolicy.RUNTIME)
+  @interface Tag {
+    String value();
+
+    int priority() default 3;
+  }
+
+  static class Handler implements InvocationHandler {
+    int calls;
+
+    public Object invoke(Object proxy, Method method, Object[] args) throws Throwable {
+      calls++;
+
+      if (method.getName().equals("fail")) {
+        throw new Exception("checked");
+      }
+
+      if (method.getName().equals("crash")) {
+        throw new UnsupportedOperationException("unchecked");
+      }
+
+      return args[0];
+    }
+  }
+
+  @Tag("hello")
+  static class Tagged {}
+
+  static Handler handler = new Handler();
+
+  static Greeter newGreeter() {
+    return (Greeter) Proxy.newProxyInstance(
+        TestProxy.class.getClassLoader(), new Class<?>[] {Greeter.class}, handler);
+  }
+
+  static String greet(String name) {
+    return newGreeter().greet(name);
+  }
+
+  // checked exceptions not declared by the interface are wrapped
+  static String fail() {
+    try {
+      newGreeter().fail("world");
+      return null;
+    } catch (UndeclaredThrowableException e) {
+      return e.getCause().getMessage();
+    }
+  }
+
+  static String crash() {
+    try {
+      newGreeter().crash("world");
+      return null;
+    } catch (UnsupportedOperationException e) {
+      return e.getMessage();
+    }
+  }
+
+  static int calls() {
+    return handler.calls;
+  }
+
+  static boolean isProxy() {
+    Greeter greeter = newGreeter();
+
+    // the class is generated once for the same interfaces
+    return Proxy.isProxyClass(greeter.getClass()) && greeter.getClass() == newGreeter().getClass();
+  }
+
+  static String tagValue() {
+    return Tagged.class.getAnnotation(Tag.class).value();
+  }
+
+  static int tagPriority() {
+    return Tagged.class.getAnnotation(Tag.class).priority();
+  }
+
+  static boolean isTagType() {
+    Tag tag = Tagged.class.getAnnotation(Tag.class);
+
+    return tag.annotationType() == Tag.class && Proxy.isProxyClass(tag.getClass());
+  }
+}
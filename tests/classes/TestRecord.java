@@ -0,0 +1,102 @@
+// Records and sealed interfaces, their `toString`, `equals` and `hashCode` are
+// call sites of ObjectMethods.bootstrap
+//
+// The pattern switches over them (`SwitchBootstraps.typeSwitch` and
+// `enumSwitch`) are in TestSwitch.java, compiled with --release 21.
+public class TestRecord {
+
+  public sealed interface Shape permits Circle, Square, Rect {}
+
+  public record Circle(double radius) implements Shape {}
+
+  public record Square(int side) implements Shape {}
+
+  public record Rect(Point corner, int width, int height) implements Shape {}
+
+  public record Point(int x, int y) {}
+
+  public record Empty() {}
+
+  public record Primitives(boolean z, byte b, char c, short s, long j, float f, double d) {}
+
+  public record Named(String name, Object value) {}
+
+  public enum Color {
+    RED,
+    GREEN,
+    BLUE
+  }
+
+  public record Pixel(Point at, Color color) {}
+
+  public static Shape circle(double radius) {
+    return new Circle(radius);
+  }
+
+  public static Shape square(int side) {
+    return new Square(side);
+  }
+
+  public static Shape rect(int x, int y, int width, int height) {
+    return new Rect(new Point(x, y), width, height);
+  }
+
+  public static Color color(int ordinal) {
+    return Color.values()[ordinal];
+  }
+
+  public static String describe(Object record) {
+    return record.toString();
+  }
+
+  public static boolean same(Object left, Object right) {
+    return left.equals(right);
+  }
+
+  public static int hash(Object record) {
+    return record.hashCode();
+  }
+
+  public static String points() {
+    Point p = new Point(1, -2);
+
+    return p + " " + new Empty() + " " + new Rect(p, 3, 4);
+  }
+
+  public static String primitives() {
+    return new Primitives(true, (byte) -1, 'x', (short) 7, 1L << 40, 0.5f, 1.0E-4).toString();
+  }
+
+  public static String named() {
+    return new Named("origin", null) + " " + new Named("point", new Point(0, 0));
+  }
+
+  public static boolean equality() {
+    Point p = new Point(1, 2);
+
+    return p.equals(p)
+        && p.equals(new Point(1, 2))
+        && !p.equals(new Point(2, 1))
+        && !p.equals(null)
+        && !p.equals(new Square(1))
+        && new Rect(p, 1, 1).equals(new Rect(new Point(1, 2), 1, 1))
+        && new Named("a", null).equals(new Named("a", null))
+        && !new Named("a", null).equals(new Named("a", p))
+        && new Circle(Double.NaN).equals(new Circle(Double.NaN))
+        && !new Circle(0.0).equals(new Circle(-0.0))
+        && new Pixel(p, Color.RED).equals(new Pixel(p, Color.RED))
+        && !new Pixel(p, Color.RED).equals(new Pixel(p, Color.BLUE));
+  }
+
+  public static int pointHash(int x, int y) {
+    return new Point(x, y).hashCode();
+  }
+
+  public static int primitivesHash() {
+    return new Primitives(false, (byte) 2, 'a', (short) -3, -1L, 1.5f, 2.0).hashCode();
+  }
+
+  public static int namedHash() {
+    return new Named("a", null).hashCode();
+  }
+}
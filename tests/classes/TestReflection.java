@@ -0,0 +1,39 @@
+import java.io.Serializable;
+import java.lang.annotation.Retention;
+import java.lang.annotation.RetentionPolicy;
+import java.util.List;
+
+@TestReflection.Marker
+public class TestReflection<T extends Number> implements Comparable<TestReflection<T>>, Serializable {
+
+  static final long BIG = 5000000000L;
+
+  static final String GREETING = "hello";
+
+  int count;
+
+  @Retention(RetentionPolicy.RUNTIME)
+  @interface Marker {}
+
+  static class Inner {}
+
+  sealed interface Shape permits Circle, Square {}
+
+  record Circle(@Marker int radius) implements Shape {}
+
+  record Square(List<String> sides) implements Shape {}
+
+  public int compareTo(TestReflection<T> other) {
+    return count - other.count;
+  }
+
+  static double _constants() {
+    float f = 1.5f;
+    double d = 2.25;
+    return BIG + GREETING.length() + f + d + 100000;
+  }
+
+  static Object _anonymous() {
+    return new Object() {};
+  }
+}
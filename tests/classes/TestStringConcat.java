@@ -0,0 +1,63 @@
+public class TestStringConcat {
+  static class Point {
+    int x;
+    int y;
+
+    Point(int x, int y) {
+      this.x = x;
+      this.y = y;
+    }
+
+    public String toString() {
+      return "(" + x + ", " + y + ")";
+    }
+  }
+
+  static class Nameless {
+    public String toString() {
+      return null;
+    }
+  }
+
+  static String primitives(int i, long l, char c, boolean z, byte b, short s) {
+    return "i=" + i + " l=" + l + " c=" + c + " z=" + z + " b=" + b + " s=" + s;
+  }
+
+  static String decimals(float f, double d) {
+    return f + "|" + d;
+  }
+
+  static String objects(String s, Object o, Point p) {
+    return s + o + p;
+  }
+
+  static String nameless() {
+    return "<" + new Nameless() + ">";
+  }
+
+  static String boxed(Integer i, Character c, Double d) {
+    return i + "" + c + d;
+  }
+
+  // tags in a literal are passed as constants of the bootstrap method
+  static String tags(int i) {
+    return "\u0001" + i + "\u0002";
+  }
+
+  // same offset as the call site of `second`
+  static String first(int i) {
+    return "first " + i;
+  }
+
+  static String second(int i) {
+    return i + " second";
+  }
+
+  static String loop() {
+    String s = "";
+    for (int i = 0; i < 3; i++) {
+      s = s + i + ",";
+    }
+    return s;
+  }
+}
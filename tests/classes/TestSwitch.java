@@ -0,0 +1,36 @@
+// Pattern switches over the records and the sealed interface of TestRecord,
+// call sites of SwitchBootstraps.typeSwitch and enumSwitch (javac 21 or newer)
+//
+// test_switch.rs assembles the same methods as javac lowers them, so that the
+// tests do not depend on the version of javac.
+public class TestSwitch {
+
+  // sealed interface: no default, a guard and a record pattern
+  public static int area(TestRecord.Shape shape) {
+    return switch (shape) {
+      case TestRecord.Circle c -> (int) (3 * c.radius() * c.radius());
+      case TestRecord.Square s when s.side() > 10 -> -1;
+      case TestRecord.Square s -> s.side() * s.side();
+      case TestRecord.Rect(TestRecord.Point(var x, var y), var w, var h) -> x + y + w * h;
+    };
+  }
+
+  // constant labels of a boxed selector mixed with type patterns
+  public static int number(Integer n) {
+    return switch (n) {
+      case null -> -1;
+      case 1 -> 100;
+      case Integer i when i > 10 -> 10;
+      case Integer i -> i;
+    };
+  }
+
+  // constants of the enum, then a guarded pattern over the enum
+  public static int color(TestRecord.Color color) {
+    return switch (color) {
+      case RED -> 1;
+      case TestRecord.Color c when c.ordinal() > 1 -> 3;
+      case TestRecord.Color c -> 2;
+    };
+  }
+}
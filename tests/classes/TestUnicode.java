@@ -0,0 +1,9 @@
+// class names are not limited to ASCII
+public class TestUnicode {
+
+  static class Café {}
+
+  static int take(Café[] cafes, long count, Café cafe) {
+    return cafes.length + (int) count;
+  }
+}
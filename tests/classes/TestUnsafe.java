@@ -0,0 +1,132 @@
+import jdk.internal.misc.Unsafe;
+
+// Accesses through Unsafe, the instance of Unsafe and the field offsets are given by
+// test_unsafe.rs
+public class TestUnsafe {
+
+  int count;
+  long total;
+  short small;
+  char letter;
+  float ratio;
+  double average;
+  boolean enabled;
+  String name;
+
+  static int created;
+
+  TestUnsafe() {
+    count = 1;
+  }
+
+  static String fields(Unsafe u, TestUnsafe t, long count, long total, long name) {
+    u.putInt(t, count, 41);
+    int previous = u.getAndAddInt(t, count, 1);
+
+    u.putLong(t, total, 5);
+    boolean swapped = u.compareAndSetLong(t, total, 5, 7);
+    boolean failed = u.compareAndSetLong(t, total, 5, 9);
+
+    u.putReference(t, name, "a");
+    Object old = u.getAndSetReference(t, name, "b");
+
+    return previous + " " + t.count + " " + swapped + " " + failed + " " + t.total + " " + old
+        + " " + t.name;
+  }
+
+  static String primitives(Unsafe u, TestUnsafe t, long small, long ratio, long average,
+      long enabled) {
+    u.putShort(t, small, (short) -2);
+    u.putFloat(t, ratio, 0.5f);
+    u.putDouble(t, average, 1.25);
+    u.putBoolean(t, enabled, true);
+
+    return u.getShort(t, small) + " " + t.ratio + " " + u.getDoubleVolatile(t, average) + " "
+        + u.getBoolean(t, enabled);
+  }
+
+  static String arrays(Unsafe u, int[] ints, byte[] bytes, String[] strings) {
+    long base = u.arrayBaseOffset(int[].class);
+    long scale = u.arrayIndexScale(int[].class);
+
+    u.putInt(ints, base + 2 * scale, 5);
+    int added = u.getAndAddInt(ints, base + scale, 10);
+    boolean swapped = u.compareAndSetInt(ints, base, 1, 100);
+
+    // wider values in byte arrays are in the native (little endian) order
+    long byteBase = u.arrayBaseOffset(byte[].class);
+    u.putInt(bytes, byteBase, 0x01020304);
+    long value = u.getLong(bytes, byteBase);
+    short high = u.getShort(bytes, byteBase + 2);
+
+    long stringBase = u.arrayBaseOffset(String[].class);
+    long stringScale = u.arrayIndexScale(String[].class);
+    u.putReference(strings, stringBase + stringScale, "set");
+
+    return ints[0] + " " + added + " " + ints[1] + " " + ints[2] + " " + swapped + " " + value
+        + " " + high + " " + bytes[3] + " " + strings[1];
+  }
+
+  static String memory(Unsafe u, byte[] bytes) {
+    long address = u.allocateMemory(8);
+    u.putLong(address, 0x0102030405060708L);
+    int low = u.getInt(address);
+    byte last = u.getByte(address + 7);
+
+    address = u.reallocateMemory(address, 16);
+    long kept = u.getLong(address);
+    u.setMemory(address + 8, 8, (byte) 1);
+    long filled = u.getLong(address + 8);
+
+    u.copyMemory(bytes, u.arrayBaseOffset(byte[].class), null, address, 4);
+    int copied = u.getInt(address);
+    u.copyMemory(null, address + 8, bytes, u.arrayBaseOffset(byte[].class), 2);
+
+    u.putChar(address, 'z');
+    char c = u.getChar(address);
+    u.putFloat(address, 2.5f);
+    float f = u.getFloat(address);
+
+    u.freeMemory(address);
+
+    return low + " " + last + " " + (kept == 0x0102030405060708L) + " " + filled + " "
+        + copied + " " + bytes[0] + " " + bytes[2] + " " + c + " " + f;
+  }
+
+  static int read(Unsafe u, long address) {
+    return u.getInt(address);
+  }
+
+  static int readFreed(Unsafe u) {
+    long address = u.allocateMemory(4);
+    u.freeMemory(address);
+    return u.getInt(address);
+  }
+
+  static String statics(Unsafe u, Object base, long offset) {
+    u.putInt(base, offset, 3);
+    int previous = u.getAndAddInt(base, offset, 4);
+
+    return previous + " " + created;
+  }
+
+  static int allocate(Unsafe u) throws InstantiationException {
+    TestUnsafe t = (TestUnsafe) u.allocateInstance(TestUnsafe.class);
+
+    return t.count;
+  }
+
+  static void raise(Unsafe u) {
+    u.throwException(new IllegalStateException("raised"));
+  }
+
+  static int threads(Unsafe u, Object thread) {
+    u.unpark(thread);
+    u.park(false, 1L);
+    u.loadFence();
+    u.storeFence();
+    u.fullFence();
+
+    return u.getLoadAverage(new double[3], 3);
+  }
+}
@@ -0,0 +1,130 @@
+import java.lang.invoke.VarHandle;
+
+// Access modes of VarHandles on fields, arrays and byte views, the VarHandles
+// are created by test_varhandle.rs
+public class TestVarHandle {
+
+  int count;
+  boolean flag;
+  double ratio;
+  String name;
+  static long counter;
+
+  public static String ints(VarHandle vh) {
+    TestVarHandle t = new TestVarHandle();
+
+    vh.set(t, 5);
+
+    int add = (int) vh.getAndAdd(t, 3);
+    int or = (int) vh.getAndBitwiseOrAcquire(t, 16);
+    int and = (int) vh.getAndBitwiseAndRelease(t, 12);
+    int xor = (int) vh.getAndBitwiseXor(t, 1);
+    boolean set = vh.compareAndSet(t, 9, 10);
+    boolean failed = vh.weakCompareAndSetPlain(t, 9, 11);
+    int exchanged = (int) vh.compareAndExchange(t, 10, 12);
+    int unchanged = (int) vh.compareAndExchangeAcquire(t, 10, 13);
+    int old = (int) vh.getAndSet(t, 20);
+
+    vh.setRelease(t, (int) vh.getVolatile(t) + 1);
+
+    return add + " " + or + " " + and + " " + xor + " " + set + " " + failed + " "
+        + exchanged + " " + unchanged + " " + old + " " + (int) vh.getOpaque(t) + " " + t.count;
+  }
+
+  public static String longs(VarHandle vh) {
+    vh.setVolatile(7L);
+
+    long add = (long) vh.getAndAdd(1L << 40);
+    boolean set = vh.weakCompareAndSet(7L + (1L << 40), -1L);
+
+    return add + " " + set + " " + (long) vh.get() + " " + counter;
+  }
+
+  public static String booleans(VarHandle vh) {
+    TestVarHandle t = new TestVarHandle();
+
+    boolean or = (boolean) vh.getAndBitwiseOr(t, true);
+    boolean xor = (boolean) vh.getAndBitwiseXor(t, true);
+
+    return or + " " + xor + " " + t.flag;
+  }
+
+  public static String doubles(VarHandle vh) {
+    TestVarHandle t = new TestVarHandle();
+
+    vh.set(t, Double.NaN);
+
+    // compared by their bits
+    boolean nan = vh.compareAndSet(t, Double.NaN, 0.0);
+    boolean zero = vh.compareAndSet(t, -0.0, 1.0);
+    double add = (double) vh.getAndAdd(t, 0.5);
+
+    return nan + " " + zero + " " + add + " " + t.ratio;
+  }
+
+  public static String references(VarHandle vh) {
+    TestVarHandle t = new TestVarHandle();
+    String a = "a";
+    String b = "b";
+
+    vh.set(t, a);
+
+    String old = (String) vh.getAndSet(t, b);
+    boolean set = vh.compareAndSet(t, b, "c");
+    boolean failed = vh.compareAndSet(t, b, "d");
+
+    return old + " " + set + " " + failed + " " + t.name;
+  }
+
+  // inexact call sites: boxed values and an Object result
+  public static Object boxed(VarHandle vh, Object value) {
+    TestVarHandle t = new TestVarHandle();
+
+    vh.set(t, value);
+
+    return vh.getAndAdd(t, (short) 2);
+  }
+
+  public static void set(VarHandle vh, Object value) {
+    vh.set(new TestVarHandle(), value);
+  }
+
+  public static String arrays(VarHandle vh, int[] array) {
+    vh.set(array, 0, 3);
+
+    int add = (int) vh.getAndAdd(array, 0, 4);
+    boolean set = vh.compareAndSet(array, 1, 0, 9);
+
+    return add + " " + set + " " + array[0] + " " + array[1];
+  }
+
+  public static int element(VarHandle vh, int[] array, int index) {
+    return (int) vh.getVolatile(array, index);
+  }
+
+  public static void store(VarHandle vh, Object[] array, Object value) {
+    vh.setVolatile(array, 0, value);
+  }
+
+  public static String bytes(VarHandle vh, byte[] array, int index) {
+    vh.set(array, index, 0x01020304);
+
+    String bytes = "";
+
+    for (byte b : array) {
+      bytes += b + " ";
+    }
+
+    return bytes + (int) vh.get(array, index);
+  }
+
+  public static long littleEndian(VarHandle vh, byte[] array) {
+    vh.set(array, 8, 0x0102030405060708L);
+
+    return (long) vh.getAndAdd(array, 8, 1L) + array[8] + array[15];
+  }
+
+  public static boolean misaligned(VarHandle vh, byte[] array) {
+    return vh.compareAndSet(array, 1, 0, 1);
+  }
+}
@@ -0,0 +1,66 @@
+public class TestVerifier {
+  private long total;
+  private double ratio = 0.5;
+
+  class Inner {
+    int value() {
+      return (int) total;
+    }
+  }
+
+  static int answer() {
+    return 42;
+  }
+
+  static int loop(int n) {
+    int sum = 0;
+
+    for (int i = 0; i < n; i++) {
+      sum += i;
+    }
+
+    return sum;
+  }
+
+  static String pick(int n) {
+    switch (n) {
+      case 0:
+        return "zero";
+      case 1:
+        return "one";
+      case 1000:
+        return "thousand";
+      default:
+        return "many";
+    }
+  }
+
+  static int catchIt(int[] values) {
+    try {
+      return values[3];
+    } catch (ArrayIndexOutOfBoundsException e) {
+      return -1;
+    } finally {
+      values[0] = 0;
+    }
+  }
+
+  long wide(long a, double b) {
+    total += a;
+    long[] longs = new long[] {a, total};
+    long[][] matrix = new long[2][3];
+    matrix[1][2] = (long) (b * ratio);
+
+    return longs[1] + matrix[1][2];
+  }
+
+  Object choose(boolean flag) {
+    Object value = flag ? new StringBuilder("yes") : "no";
+
+    return value instanceof String ? ((String) value).length() : value;
+  }
+
+  int inner() {
+    return new Inner().value();
+  }
+}
@@ -0,0 +1,70 @@
+// Host of the VM through the Invocation API of libjvmcraft.so (make invocation):
+// it creates the VM, calls TestJni.mix and checks the result
+#include <jni.h>
+#include <stdio.h>
+
+int main(int argc, char **argv) {
+  JavaVM *vm;
+  JNIEnv *env;
+
+  JavaVMOption options[] = {
+      {"-Djava.class.path=tests/classes", NULL},
+      {"-Dinvocation.test=true", NULL},
+      {"-Xss1m", NULL},
+  };
+
+  JavaVMInitArgs args;
+  args.version = JNI_VERSION_1_8;
+  args.nOptions = sizeof(options) / sizeof(options[0]);
+  args.options = options;
+  args.ignoreUnrecognized = JNI_FALSE;
+
+  if (JNI_GetDefaultJavaVMInitArgs(&args) != JNI_OK) {
+    fprintf(stderr, "JNI_VERSION_1_8 is not supported\n");
+    return 1;
+  }
+
+  if (JNI_CreateJavaVM(&vm, (void **)&env, &args) != JNI_OK) {
+    fprintf(stderr, "JNI_CreateJavaVM failed\n");
+    return 1;
+  }
+
+  JavaVM *created;
+  jsize count;
+
+  if (JNI_GetCreatedJavaVMs(&created, 1, &count) != JNI_OK || count != 1 || created != vm) {
+    fprintf(stderr, "JNI_GetCreatedJavaVMs does not give the VM\n");
+    return 1;
+  }
+
+  JNIEnv *attached;
+
+  if ((*vm)->AttachCurrentThread(vm, (void **)&attached, NULL) != JNI_OK || attached != env) {
+    fprintf(stderr, "AttachCurrentThread failed\n");
+    return 1;
+  }
+
+  jclass cls = (*env)->FindClass(env, "TestJni");
+  jmethodID mix = cls == NULL ? NULL : (*env)->GetStaticMethodID(env, cls, "mix", "(FDJIIID)D");
+
+  if (mix == NULL) {
+    (*env)->ExceptionDescribe(env);
+    return 1;
+  }
+
+  jdouble result = (*env)->CallStaticDoubleMethod(env, cls, mix, 1.5f, 2.25, 3LL, 4, 5, 6, 7.125);
+
+  if ((*env)->ExceptionCheck(env)) {
+    (*env)->ExceptionDescribe(env);
+    return 1;
+  }
+
+  printf("TestJni.mix = %g\n", result);
+
+  if ((*vm)->DestroyJavaVM(vm) != JNI_OK) {
+    fprintf(stderr, "DestroyJavaVM failed\n");
+    return 1;
+  }
+
+  return result == 28.875 ? 0 : 1;
+}
@@ -0,0 +1,200 @@
+// JNI library of tests/classes/TestJni.java, built by test_jni.rs
+#include <jni.h>
+#include <stdlib.h>
+#include <string.h>
+
+static jobject kept;
+
+static jstring greet(JNIEnv *env, jclass cls, jstring name) {
+  const char *chars = (*env)->GetStringUTFChars(env, name, NULL);
+  char buffer[64];
+
+  strcpy(buffer, "hello ");
+  strncat(buffer, chars, sizeof(buffer) - strlen(buffer) - 1);
+  (*env)->ReleaseStringUTFChars(env, name, chars);
+
+  return (*env)->NewStringUTF(env, buffer);
+}
+
+JNIEXPORT jint JNICALL JNI_OnLoad(JavaVM *vm, void *reserved) {
+  JNIEnv *env;
+
+  if ((*vm)->GetEnv(vm, (void **)&env, JNI_VERSION_1_8) != JNI_OK) {
+    return JNI_ERR;
+  }
+
+  jclass cls = (*env)->FindClass(env, "TestJni");
+  JNINativeMethod methods[] = {
+      {"greet", "(Ljava/lang/String;)Ljava/lang/String;", (void *)greet},
+  };
+
+  if ((*env)->RegisterNatives(env, cls, methods, 1) != JNI_OK) {
+    return JNI_ERR;
+  }
+
+  return JNI_VERSION_1_8;
+}
+
+JNIEXPORT jint JNICALL Java_TestJni_add(JNIEnv *env, jclass cls, jint a, jint b) {
+  return a + b;
+}
+
+JNIEXPORT jdouble JNICALL Java_TestJni_sum__JD(JNIEnv *env, jclass cls, jlong a, jdouble b) {
+  return a + b;
+}
+
+JNIEXPORT jint JNICALL Java_TestJni_sum___3I(JNIEnv *env, jclass cls, jintArray values) {
+  jsize length = (*env)->GetArrayLength(env, values);
+  jint *elements = (*env)->GetIntArrayElements(env, values, NULL);
+  jint sum = 0;
+
+  for (jsize i = 0; i < length; i++) {
+    sum += elements[i];
+  }
+
+  (*env)->ReleaseIntArrayElements(env, values, elements, JNI_ABORT);
+
+  return sum;
+}
+
+JNIEXPORT jint JNICALL Java_TestJni_getValue(JNIEnv *env, jobject obj) {
+  jclass cls = (*env)->GetObjectClass(env, obj);
+  jfieldID value = (*env)->GetFieldID(env, cls, "value", "I");
+
+  return (*env)->GetIntField(env, obj, value);
+}
+
+JNIEXPORT void JNICALL Java_TestJni_setName(JNIEnv *env, jobject obj, jstring name) {
+  jclass cls = (*env)->GetObjectClass(env, obj);
+  jfieldID field = (*env)->GetFieldID(env, cls, "name", "Ljava/lang/String;");
+
+  (*env)->SetObjectField(env, obj, field, name);
+}
+
+// the parts are joined with '+', in modified UTF-8
+JNIEXPORT jstring JNICALL Java_TestJni_concat(JNIEnv *env, jclass cls, jobjectArray parts) {
+  char buffer[64] = "";
+
+  for (jsize i = 0; i < (*env)->GetArrayLength(env, parts); i++) {
+    jstring part = (*env)->GetObjectArrayElement(env, parts, i);
+    const char *chars = (*env)->GetStringUTFChars(env, part, NULL);
+
+    if (i > 0) {
+      strcat(buffer, "+");
+    }
+    strcat(buffer, chars);
+
+    (*env)->ReleaseStringUTFChars(env, part, chars);
+    (*env)->DeleteLocalRef(env, part);
+  }
+
+  return (*env)->NewStringUTF(env, buffer);
+}
+
+// the floats, doubles and the arguments after the registers go through the va_list
+JNIEXPORT jdouble JNICALL Java_TestJni_callback(JNIEnv *env, jclass cls, jfloat a, jdouble b,
+                                               jlong c, jint d, jint e, jint f, jdouble g) {
+  jmethodID mix = (*env)->GetStaticMethodID(env, cls, "mix", "(FDJIIID)D");
+
+  jdouble variadic = (*env)->CallStaticDoubleMethod(env, cls, mix, a, b, c, d, e, f, g);
+
+  jvalue values[7];
+  values[0].f = a;
+  values[1].d = b;
+  values[2].j = c;
+  values[3].i = d;
+  values[4].i = e;
+  values[5].i = f;
+  values[6].d = g;
+
+  jdouble array = (*env)->CallStaticDoubleMethodA(env, cls, mix, values);
+
+  return variadic == array ? variadic : -1;
+}
+
+JNIEXPORT jobject JNICALL Java_TestJni_create(JNIEnv *env, jclass cls, jint value, jstring name) {
+  jmethodID init = (*env)->GetMethodID(env, cls, "<init>", "(ILjava/lang/String;)V");
+  jobject obj = (*env)->NewObject(env, cls, init, value, name);
+
+  jmethodID describe = (*env)->GetMethodID(env, cls, "describe", "()Ljava/lang/String;");
+  jstring description = (*env)->CallObjectMethod(env, obj, describe);
+
+  if ((*env)->GetStringUTFLength(env, description) != 7) {
+    return NULL;
+  }
+
+  return obj;
+}
+
+JNIEXPORT jint JNICALL Java_TestJni_increment(JNIEnv *env, jclass cls) {
+  jfieldID counter = (*env)->GetStaticFieldID(env, cls, "counter", "I");
+  jint value = (*env)->GetStaticIntField(env, cls, counter) + 1;
+
+  (*env)->SetStaticIntField(env, cls, counter, value);
+
+  return value;
+}
+
+JNIEXPORT void JNICALL Java_TestJni_fail(JNIEnv *env, jclass cls, jstring message) {
+  const char *chars = (*env)->GetStringUTFChars(env, message, NULL);
+  jclass exception = (*env)->FindClass(env, "java/lang/IllegalArgumentException");
+
+  (*env)->ThrowNew(env, exception, chars);
+  (*env)->ReleaseStringUTFChars(env, message, chars);
+}
+
+// the exception of a Java method is pending in the native code
+JNIEXPORT jboolean JNICALL Java_TestJni_catchFailure(JNIEnv *env, jclass cls) {
+  jmethodID thrower = (*env)->GetStaticMethodID(env, cls, "thrower", "()V");
+
+  (*env)->CallStaticVoidMethod(env, cls, thrower);
+
+  if (!(*env)->ExceptionCheck(env)) {
+    return JNI_FALSE;
+  }
+
+  jthrowable exception = (*env)->ExceptionOccurred(env);
+  jclass expected = (*env)->FindClass(env, "java/lang/IllegalStateException");
+  (*env)->ExceptionClear(env);
+
+  // a missing method is a pending NoSuchMethodError
+  if ((*env)->GetStaticMethodID(env, cls, "missing", "()V") != NULL ||
+      !(*env)->ExceptionCheck(env)) {
+    return JNI_FALSE;
+  }
+  (*env)->ExceptionClear(env);
+
+  return (*env)->IsInstanceOf(env, exception, expected);
+}
+
+JNIEXPORT jbyteArray JNICALL Java_TestJni_reverse(JNIEnv *env, jclass cls, jbyteArray bytes) {
+  jsize length = (*env)->GetArrayLength(env, bytes);
+  jbyteArray reversed = (*env)->NewByteArray(env, length);
+  jbyte buffer[64];
+
+  (*env)->GetByteArrayRegion(env, bytes, 0, length, buffer);
+
+  for (jsize i = 0; i < length / 2; i++) {
+    jbyte byte = buffer[i];
+    buffer[i] = buffer[length - 1 - i];
+    buffer[length - 1 - i] = byte;
+  }
+
+  (*env)->SetByteArrayRegion(env, reversed, 0, length, buffer);
+
+  return reversed;
+}
+
+JNIEXPORT jint JNICALL Java_TestJni_keep(JNIEnv *env, jclass cls, jobject obj) {
+  if (kept != NULL) {
+    (*env)->DeleteGlobalRef(env, kept);
+  }
+
+  kept = (*env)->NewGlobalRef(env, obj);
+
+  return (*env)->GetObjectRefType(env, kept);
+}
+
+JNIEXPORT jobject JNICALL Java_TestJni_kept(JNIEnv *env, jclass cls) {
+  return kept;
+}